@@ -0,0 +1,135 @@
+//! Achievements, milestones that the player reaches once per world (like breaking a first block
+//! or going up to altitude 200). They are unlocked by the game events that meet their
+//! trigger conditions, a toast is displayed when one is unlocked, and the unlocked ones
+//! are kept in the save.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::localization::Localization;
+
+/// The altitude of `Achievement::Altitude200`.
+const HIGH_ALTITUDE: f32 = 200.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub(crate) enum Achievement {
+	FirstBlockBroken,
+	FirstBlockPlaced,
+	Altitude200,
+	SkyIslandVisited,
+}
+
+impl Achievement {
+	const ALL: [Achievement; 4] = [
+		Achievement::FirstBlockBroken,
+		Achievement::FirstBlockPlaced,
+		Achievement::Altitude200,
+		Achievement::SkyIslandVisited,
+	];
+
+	/// Its name in the localization files, as in `achievement.first_block_broken`.
+	fn localization_key(self) -> &'static str {
+		match self {
+			Achievement::FirstBlockBroken => "achievement.first_block_broken",
+			Achievement::FirstBlockPlaced => "achievement.first_block_placed",
+			Achievement::Altitude200 => "achievement.altitude_200",
+			Achievement::SkyIslandVisited => "achievement.sky_island_visited",
+		}
+	}
+
+	pub(crate) fn localized_name(self, localization: &Localization) -> String {
+		localization.get(self.localization_key()).to_string()
+	}
+
+	/// The trigger condition, does the event unlock the achievement?
+	fn is_triggered_by(self, event: &AchievementEvent) -> bool {
+		match (self, event) {
+			(Achievement::FirstBlockBroken, AchievementEvent::BlockBroken) => true,
+			(Achievement::FirstBlockPlaced, AchievementEvent::BlockPlaced) => true,
+			(Achievement::Altitude200, AchievementEvent::PlayerMoved { pos, .. }) => {
+				pos.z >= HIGH_ALTITUDE
+			},
+			(Achievement::SkyIslandVisited, AchievementEvent::PlayerMoved { on_sky_island, .. }) => {
+				*on_sky_island
+			},
+			_ => false,
+		}
+	}
+}
+
+/// The game events that achievements can be unlocked by.
+pub(crate) enum AchievementEvent {
+	BlockBroken,
+	BlockPlaced,
+	/// Happens every frame.
+	PlayerMoved {
+		pos: cgmath::Point3<f32>,
+		/// The player stands on the ground of a sky island.
+		on_sky_island: bool,
+	},
+}
+
+/// The achievements unlocked in a world.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Achievements {
+	unlocked: BTreeSet<Achievement>,
+}
+
+impl Achievements {
+	/// Unlocks the achievements that the event triggers, returns the ones that were not
+	/// unlocked before.
+	pub(crate) fn handle_event(&mut self, event: &AchievementEvent) -> Vec<Achievement> {
+		let mut newly_unlocked = vec![];
+		for achievement in Achievement::ALL {
+			if !self.unlocked.contains(&achievement) && achievement.is_triggered_by(event) {
+				self.unlocked.insert(achievement);
+				newly_unlocked.push(achievement);
+			}
+		}
+		newly_unlocked
+	}
+
+	/// The lines of text about the achievements on the stats screen.
+	pub(crate) fn lines(&self, localization: &Localization) -> Vec<String> {
+		let mut lines = vec![localization.get_with_args(
+			"stats_screen.achievements",
+			&[
+				("unlocked", &self.unlocked.len()),
+				("total", &Achievement::ALL.len()),
+			],
+		)];
+		lines.extend(
+			self
+				.unlocked
+				.iter()
+				.map(|achievement| format!("  {}", achievement.localized_name(localization))),
+		);
+		lines
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn achievements_are_unlocked_once() {
+		let mut achievements = Achievements::default();
+		let low = AchievementEvent::PlayerMoved {
+			pos: cgmath::point3(0.0, 0.0, 50.0),
+			on_sky_island: false,
+		};
+		let high = AchievementEvent::PlayerMoved {
+			pos: cgmath::point3(0.0, 0.0, 250.0),
+			on_sky_island: false,
+		};
+		assert!(achievements.handle_event(&low).is_empty());
+		assert!(achievements.handle_event(&high) == vec![Achievement::Altitude200]);
+		assert!(achievements.handle_event(&high).is_empty());
+		assert!(
+			achievements.handle_event(&AchievementEvent::BlockBroken)
+				== vec![Achievement::FirstBlockBroken]
+		);
+	}
+}
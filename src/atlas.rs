@@ -4,12 +4,24 @@ use cgmath::MetricSpace;
 use image::{GenericImage, GenericImageView};
 use rand::{Rng, SeedableRng};
 
-use crate::{saves::Save, texture_gen};
+use crate::{
+	block_definitions::{BlockDefinition, DefinedTexture},
+	block_types::{BlockTypeTable, ToolKind},
+	saves::Save,
+	texture_gen,
+};
 
 pub(crate) const ATLAS_DIMS: (usize, usize) = (512, 512);
 
+/// Where the texture previewed by the `texgen_preview` command is painted on the atlas
+/// (see `TextureRecipe`).
+pub(crate) const TEXTURE_PREVIEW_COORDS_ON_ATLAS: (u32, u32) = (496, 0);
+
 pub(crate) struct Atlas {
 	pub(crate) image: image::RgbaImage,
+	/// Same layout as `image`, tells where the textures glow (from 0 to 255), the glowing parts of
+	/// the blocks are seen whatever light reaches them.
+	pub(crate) emissive_mask: image::GrayImage,
 }
 
 impl Atlas {
@@ -38,7 +50,9 @@ impl Atlas {
 			image::load_from_memory(include_bytes!("../assets/spritesheet.png")).unwrap();
 		image.copy_from(&spritesheet_image, 256, 32).unwrap();
 
-		Atlas { image }
+		let emissive_mask = image::GrayImage::new(ATLAS_DIMS.0 as u32, ATLAS_DIMS.1 as u32);
+
+		Atlas { image, emissive_mask }
 	}
 
 	pub(crate) fn new_slow_complete(world_gen_seed: i32) -> Atlas {
@@ -187,19 +201,379 @@ impl Atlas {
 			}
 		}
 
+		// Water block
+		{
+			let mut view = atlas.image.sub_image(80, 0, 16, 16);
+			for y in 0..16 {
+				for x in 0..16 {
+					let ripple = ((x as f32 * 0.8 + y as f32 * 0.4).sin() * 12.0) as i32;
+					let r = 30 + rng.gen_range(0..10);
+					let g = (90 + ripple + rng.gen_range(0..15)) as u8;
+					let b = (190 + ripple + rng.gen_range(0..20)) as u8;
+					view.put_pixel(x, y, image::Rgba::from([r, g, b, 255]));
+				}
+			}
+		}
+
+		// Lamp block
+		{
+			let mut view = atlas.image.sub_image(96, 0, 16, 16);
+			let mut emissive_view = atlas.emissive_mask.sub_image(96, 0, 16, 16);
+			for y in 0..16 {
+				for x in 0..16 {
+					let is_frame = x == 0 || x == 15 || y == 0 || y == 15;
+					let pixel = if is_frame {
+						let shade = rng.gen_range(60..80);
+						[shade, shade / 2, 0, 255]
+					} else {
+						[255, rng.gen_range(210..240), rng.gen_range(120..160), 255]
+					};
+					view.put_pixel(x, y, image::Rgba::from(pixel));
+					emissive_view.put_pixel(x, y, image::Luma([if is_frame { 0 } else { 255 }]));
+				}
+			}
+		}
+
+		// Lava block
+		{
+			let mut view = atlas.image.sub_image(112, 0, 16, 16);
+			let mut emissive_view = atlas.emissive_mask.sub_image(112, 0, 16, 16);
+			for y in 0..16 {
+				for x in 0..16 {
+					let crust = ((x as f32 * 0.9).sin() + (y as f32 * 0.7 + x as f32 * 0.3).cos()) > 1.2;
+					let pixel = if crust {
+						let shade = rng.gen_range(50..70);
+						[shade + 20, shade / 2, 0, 255]
+					} else {
+						[255, rng.gen_range(90..150), rng.gen_range(0..40), 255]
+					};
+					view.put_pixel(x, y, image::Rgba::from(pixel));
+					emissive_view.put_pixel(x, y, image::Luma([if crust { 0 } else { 255 }]));
+				}
+			}
+		}
+
+		// Crystal block
+		{
+			let mut view = atlas.image.sub_image(128, 0, 16, 16);
+			let mut emissive_view = atlas.emissive_mask.sub_image(128, 0, 16, 16);
+			for y in 0..16 {
+				for x in 0..16 {
+					let facet = (x + y) % 8 == 0 || (x + 16 - y) % 8 == 0;
+					// The facets glow a bit, the crystal does not glow as much as a lamp.
+					emissive_view.put_pixel(x, y, image::Luma([if facet { 180 } else { 0 }]));
+					let pixel = if facet {
+						[200, 230, 255, 255]
+					} else {
+						[
+							rng.gen_range(40..80),
+							rng.gen_range(90..130),
+							rng.gen_range(200..250),
+							255,
+						]
+					};
+					view.put_pixel(x, y, image::Rgba::from(pixel));
+				}
+			}
+		}
+
+		// Decorations
+		{
+			let view = atlas.image.sub_image(144, 0, 16, 16);
+			let petal_color = image::Rgba::from([220, 40, 50, 255]);
+			texture_gen::decoration_flower(view, world_gen_seed, 1, petal_color);
+			let view = atlas.image.sub_image(160, 0, 16, 16);
+			let petal_color = image::Rgba::from([250, 220, 40, 255]);
+			texture_gen::decoration_flower(view, world_gen_seed, 2, petal_color);
+			let view = atlas.image.sub_image(176, 0, 16, 16);
+			texture_gen::decoration_mushroom(view, world_gen_seed, 3);
+		}
+
+		// Layers
+		{
+			let view = atlas.image.sub_image(192, 0, 16, 16);
+			texture_gen::layer_snow(view, world_gen_seed, 4);
+			let view = atlas.image.sub_image(208, 0, 16, 16);
+			texture_gen::layer_fallen_leaves(view, world_gen_seed, 5);
+		}
+
+		// Ice and sand
+		{
+			let view = atlas.image.sub_image(224, 0, 16, 16);
+			texture_gen::block_ice(view, world_gen_seed, 6);
+			let view = atlas.image.sub_image(240, 0, 16, 16);
+			texture_gen::block_sand(view, world_gen_seed, 7);
+		}
+
+		// Pressure plate block
+		{
+			let view = atlas.image.sub_image(256, 0, 16, 16);
+			texture_gen::block_pressure_plate(view, world_gen_seed, 8);
+		}
+
+		// Door and trapdoor
+		{
+			let view = atlas.image.sub_image(272, 0, 16, 16);
+			texture_gen::block_door(view, world_gen_seed, 9);
+			let view = atlas.image.sub_image(288, 0, 16, 16);
+			texture_gen::block_trapdoor(view, world_gen_seed, 10);
+		}
+
+		// Chest
+		{
+			let view = atlas.image.sub_image(304, 0, 16, 16);
+			texture_gen::block_chest(view, world_gen_seed, 11);
+		}
+
+		// Tools, in the same order as their block types.
+		{
+			let kinds = [ToolKind::Pickaxe, ToolKind::Shovel, ToolKind::Axe];
+			let head_colors = [[175, 125, 70], [140, 140, 145]];
+			for (tier_index, head_color) in head_colors.into_iter().enumerate() {
+				for (kind_index, kind) in kinds.into_iter().enumerate() {
+					let i = tier_index * kinds.len() + kind_index;
+					let view = atlas.image.sub_image(320 + 16 * i as u32, 0, 16, 16);
+					texture_gen::tool(view, world_gen_seed, 12 + i as i32, kind, head_color);
+				}
+			}
+		}
+
+		// Dirt
+		{
+			let view = atlas.image.sub_image(416, 0, 16, 16);
+			texture_gen::block_dirt(view, world_gen_seed, 18);
+		}
+
+		// Fire, tongues of flames that glow, yellow at the bottom and red at the tips.
+		{
+			let mut view = atlas.image.sub_image(432, 0, 16, 16);
+			let mut emissive_view = atlas.emissive_mask.sub_image(432, 0, 16, 16);
+			for y in 0..16 {
+				for x in 0..16 {
+					let tip_height =
+						9.0 + 2.5 * ((x as f32 * 1.7).sin() + 1.0) - (x as f32 - 7.5).abs() * 0.6;
+					let height = 15.0 - y as f32;
+					let (pixel, emission) = if height > tip_height {
+						([0, 0, 0, 0], 0)
+					} else {
+						let ratio = height / tip_height;
+						let pixel = if ratio < 0.4 {
+							[255, rng.gen_range(210..240), 120, 255]
+						} else if ratio < 0.75 {
+							[255, rng.gen_range(130..170), 30, 255]
+						} else {
+							[rng.gen_range(200..240), 60, 10, 255]
+						};
+						(pixel, 255)
+					};
+					view.put_pixel(x, y, image::Rgba::from(pixel));
+					emissive_view.put_pixel(x, y, image::Luma([emission]));
+				}
+			}
+		}
+
+		// Signal source, a stone block with a glowing red core.
+		{
+			let mut view = atlas.image.sub_image(448, 0, 16, 16);
+			let mut emissive_view = atlas.emissive_mask.sub_image(448, 0, 16, 16);
+			for y in 0..16 {
+				for x in 0..16 {
+					let is_core = (4..12).contains(&x) && (4..12).contains(&y);
+					let pixel = if is_core {
+						[rng.gen_range(200..240), rng.gen_range(20..40), 20, 255]
+					} else {
+						let shade = rng.gen_range(90..110);
+						[shade, shade, shade, 255]
+					};
+					view.put_pixel(x, y, image::Rgba::from(pixel));
+					emissive_view.put_pixel(x, y, image::Luma([if is_core { 255 } else { 0 }]));
+				}
+			}
+		}
+
+		// Signal wire, a red cross on dark stone (it is only seen from above).
+		{
+			let mut view = atlas.image.sub_image(464, 0, 16, 16);
+			for y in 0..16 {
+				for x in 0..16 {
+					let is_wire = (7..9).contains(&x) || (7..9).contains(&y);
+					let pixel = if is_wire {
+						[rng.gen_range(150..180), 15, 15, 255]
+					} else {
+						let shade = rng.gen_range(50..65);
+						[shade, shade, shade, 255]
+					};
+					view.put_pixel(x, y, image::Rgba::from(pixel));
+				}
+			}
+		}
+
+		// Signal lamp when unlit, the frame of the lamp block around dull glass
+		// (the lit signal lamp looks like the lamp block).
+		{
+			let mut view = atlas.image.sub_image(480, 0, 16, 16);
+			for y in 0..16 {
+				for x in 0..16 {
+					let is_frame = x == 0 || x == 15 || y == 0 || y == 15;
+					let pixel = if is_frame {
+						let shade = rng.gen_range(60..80);
+						[shade, shade / 2, 0, 255]
+					} else {
+						let shade = rng.gen_range(90..105);
+						[shade, shade * 9 / 10, shade * 7 / 10, 255]
+					};
+					view.put_pixel(x, y, image::Rgba::from(pixel));
+				}
+			}
+		}
+
+		// Piston, stone in a wooden frame.
+		{
+			let mut view = atlas.image.sub_image(320, 32, 16, 16);
+			for y in 0..16 {
+				for x in 0..16 {
+					let is_frame = !(3..13).contains(&x) || !(3..13).contains(&y);
+					let pixel = if is_frame {
+						let brown = rng.gen_range(110..150);
+						[brown, brown * 2 / 3, brown / 3, 255]
+					} else {
+						let shade = rng.gen_range(95..115);
+						[shade, shade, shade, 255]
+					};
+					view.put_pixel(x, y, image::Rgba::from(pixel));
+				}
+			}
+		}
+
+		// Piston head, wooden planks.
+		{
+			let mut view = atlas.image.sub_image(336, 32, 16, 16);
+			for y in 0..16 {
+				for x in 0..16 {
+					let brown = if y % 4 == 3 {
+						rng.gen_range(70..90)
+					} else {
+						rng.gen_range(150..180)
+					};
+					view.put_pixel(
+						x,
+						y,
+						image::Rgba::from([brown, brown * 2 / 3, brown / 3, 255]),
+					);
+				}
+			}
+		}
+
 		atlas
 	}
 
+	/// Paints the textures of the block types defined by the block types file
+	/// (see `block_definitions`). These are painted over whatever atlas was generated or loaded
+	/// since the block types file may have changed since then.
+	pub(crate) fn paint_defined_block_textures(
+		&mut self,
+		block_type_table: &BlockTypeTable,
+		definitions: &[BlockDefinition],
+		world_gen_seed: i32,
+	) {
+		for (id, definition) in block_type_table.iter_defined_block_types(definitions) {
+			let coords = block_type_table.get(id).unwrap().texture_coords_on_atlas().unwrap();
+			let mut view = self.image.sub_image(coords.x as u32, coords.y as u32, 16, 16);
+			let mut emissive_view =
+				self.emissive_mask.sub_image(coords.x as u32, coords.y as u32, 16, 16);
+			if !matches!(definition.texture, DefinedTexture::Recipe { .. }) {
+				// Only recipes can paint textures that glow.
+				for y in 0..16 {
+					for x in 0..16 {
+						emissive_view.put_pixel(x, y, image::Luma([0]));
+					}
+				}
+			}
+			match &definition.texture {
+				DefinedTexture::Generated { seed } => {
+					texture_gen::generate_texture(view, world_gen_seed, *seed);
+				},
+				DefinedTexture::Recipe { recipe, params } => {
+					recipe.paint(view, emissive_view, world_gen_seed, id as i32, params);
+				},
+				DefinedTexture::File(path) => match image::open(path) {
+					Ok(texture) => {
+						let texture = image::imageops::resize(
+							&texture.to_rgba8(),
+							16,
+							16,
+							image::imageops::FilterType::Nearest,
+						);
+						view.copy_from(&texture, 0, 0).unwrap();
+					},
+					Err(error) => log::error!(
+						"Could not read the texture \"{}\" of block type \"{}\": {error}",
+						path.display(),
+						definition.name,
+					),
+				},
+			}
+		}
+	}
+
+	/// The normal atlas has the same layout as the atlas, with the height and normal map of each
+	/// texture of the atlas (see `texture_gen::paint_normal_map`), for the normal mapping of blocks.
+	/// It also carries the emissive mask, for the block shader to sample only one more texture.
+	/// The average color of the (non-transparent pixels of the) texture of each block type,
+	/// indexed by block type id (for exports to formats that give one color per block).
+	pub(crate) fn block_type_colors(&self, block_type_table: &BlockTypeTable) -> Vec<[u8; 3]> {
+		block_type_table
+			.iter_ids()
+			.map(|id| {
+				let Some(coords) = block_type_table.get(id).unwrap().texture_coords_on_atlas() else {
+					return [255, 255, 255];
+				};
+				let texture = self.image.view(coords.x as u32, coords.y as u32, 16, 16);
+				let mut sum = [0u32; 3];
+				let mut count = 0;
+				for (_x, _y, pixel) in texture.pixels().filter(|(_x, _y, pixel)| pixel.0[3] > 0) {
+					for (channel_sum, channel) in sum.iter_mut().zip(pixel.0) {
+						*channel_sum += channel as u32;
+					}
+					count += 1;
+				}
+				sum.map(|channel_sum| (channel_sum / count.max(1)) as u8)
+			})
+			.collect()
+	}
+
+	pub(crate) fn normal_atlas_image(&self) -> image::RgbaImage {
+		let mut normal_image = image::RgbaImage::new(ATLAS_DIMS.0 as u32, ATLAS_DIMS.1 as u32);
+		for y in (0..ATLAS_DIMS.1 as u32).step_by(16) {
+			for x in (0..ATLAS_DIMS.0 as u32).step_by(16) {
+				let texture = self.image.view(x, y, 16, 16);
+				let emissive_mask = self.emissive_mask.view(x, y, 16, 16);
+				let normal_view = normal_image.sub_image(x, y, 16, 16);
+				texture_gen::paint_normal_map(&*texture, &*emissive_mask, normal_view);
+			}
+		}
+		normal_image
+	}
+
 	pub(crate) fn load_from_save(save: &Arc<Save>) -> Option<Atlas> {
 		let atlas_texture_file_path = &save.atlas_texture_file_path;
 		let atlas_texture = image::open(atlas_texture_file_path).ok()?;
 		let image = atlas_texture.to_rgba8();
-		Some(Atlas { image })
+		// Saves from before emissive masks have no glowing textures.
+		let emissive_mask = image::open(&save.atlas_emissive_mask_file_path)
+			.map(|emissive_mask| emissive_mask.to_luma8())
+			.unwrap_or_else(|_| image::GrayImage::new(image.width(), image.height()));
+		Some(Atlas { image, emissive_mask })
 	}
 
 	pub(crate) fn save(&self, save: &Arc<Save>) {
 		let atlas_texture_file_path = &save.atlas_texture_file_path;
 		self.image.save_with_format(atlas_texture_file_path, image::ImageFormat::Png).unwrap();
+		let emissive_mask_file_path = &save.atlas_emissive_mask_file_path;
+		self
+			.emissive_mask
+			.save_with_format(emissive_mask_file_path, image::ImageFormat::Png)
+			.unwrap();
 	}
 }
 
@@ -208,3 +582,18 @@ pub(crate) struct RectInAtlas {
 	pub(crate) texture_rect_in_atlas_xy: cgmath::Point2<f32>,
 	pub(crate) texture_rect_in_atlas_wh: cgmath::Vector2<f32>,
 }
+
+impl RectInAtlas {
+	/// The rect of the 16x16 block texture that has its top left corner at the given
+	/// pixel coords on the atlas, in texture coords.
+	pub(crate) fn of_block_texture(texture_coords_on_atlas: cgmath::Point2<i32>) -> RectInAtlas {
+		let (atlas_w, atlas_h) = (ATLAS_DIMS.0 as f32, ATLAS_DIMS.1 as f32);
+		RectInAtlas {
+			texture_rect_in_atlas_xy: cgmath::point2(
+				texture_coords_on_atlas.x as f32 / atlas_w,
+				texture_coords_on_atlas.y as f32 / atlas_h,
+			),
+			texture_rect_in_atlas_wh: cgmath::vec2(16.0 / atlas_w, 16.0 / atlas_h),
+		}
+	}
+}
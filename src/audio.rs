@@ -0,0 +1,667 @@
+//! Sound effects and ambient sounds.
+//!
+//! Sounds are synthesized procedurally (there are no sound asset files, just like there are no
+//! texture asset files) and mixed in stereo, with an attenuation and a panning that depend on
+//! the position of the sound source relative to the camera.
+//!
+//! The mixed audio is handed to `AudioOutput`s, the default audio device (see `DeviceOutput`,
+//! unless `--no-audio-device` is used) and/or a WAV file (see `--audio-dump`) that can be
+//! listened to afterwards.
+//!
+//! Background music is composed by `music_gen` and crossfaded here when the mood changes.
+
+use std::{
+	collections::VecDeque,
+	f32::consts::TAU,
+	io::Write,
+	sync::{
+		mpsc::{Receiver, TryRecvError},
+		Arc, Mutex,
+	},
+	time::Duration,
+};
+
+use cgmath::InnerSpace;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{
+	block_types::{BlockTypeId, BlockTypeTable},
+	music_gen::{compose_track, MusicMood, MusicTrack},
+	world_gen::Biome,
+};
+
+pub(crate) const SAMPLE_RATE: u32 = 44100;
+
+/// Sounds further than that (in blocks) from the camera are not heard at all.
+const MAX_HEARING_DISTANCE: f32 = 48.0;
+/// Distance (in blocks) at which a sound is heard at half its volume.
+const HALF_VOLUME_DISTANCE: f32 = 8.0;
+
+/// Horizontal distance (in blocks) walked on the ground between two footsteps.
+const FOOTSTEP_STRIDE: f32 = 1.7;
+
+/// Duration of the crossfade between two music tracks, in seconds.
+const MUSIC_CROSSFADE_DURATION: f32 = 5.0;
+/// A new mood must last that long (in seconds) before the music changes to match it,
+/// so that walking back and forth on a mood border does not keep changing the music.
+const MUSIC_MOOD_MIN_DURATION: f32 = 8.0;
+
+/// What a block sounds like when walked on, broken or placed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum SoundSurface {
+	Ground,
+	Grass,
+	Wood,
+	Leaf,
+}
+
+impl SoundSurface {
+	pub(crate) fn of_block_type(
+		block_type_table: &BlockTypeTable,
+		type_id: BlockTypeId,
+	) -> SoundSurface {
+		if type_id == block_type_table.kinda_grass_id()
+			|| type_id == block_type_table.kinda_grass_blades_id()
+			|| type_id == block_type_table.kinda_red_flower_id()
+			|| type_id == block_type_table.kinda_yellow_flower_id()
+			|| type_id == block_type_table.kinda_mushroom_id()
+		{
+			SoundSurface::Grass
+		} else if type_id == block_type_table.kinda_wood_id()
+			|| type_id == block_type_table.text_id()
+			|| type_id == block_type_table.kinda_door_id()
+			|| type_id == block_type_table.kinda_trapdoor_id()
+			|| type_id == block_type_table.kinda_chest_id()
+		{
+			SoundSurface::Wood
+		} else if type_id == block_type_table.kinda_leaf_id()
+			|| type_id == block_type_table.kinda_fallen_leaves_id()
+		{
+			SoundSurface::Leaf
+		} else {
+			SoundSurface::Ground
+		}
+	}
+
+	/// Coefficient of the low-pass filter applied to the noise of the sounds of this surface,
+	/// the higher the brighter.
+	fn brightness(self) -> f32 {
+		match self {
+			SoundSurface::Ground => 0.12,
+			SoundSurface::Grass => 0.45,
+			SoundSurface::Wood => 0.2,
+			SoundSurface::Leaf => 0.7,
+		}
+	}
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum SoundEffect {
+	Footstep(SoundSurface),
+	BlockBreak(SoundSurface),
+	BlockPlace(SoundSurface),
+	UiClick,
+	/// The player gets hit.
+	Hurt,
+}
+
+impl SoundEffect {
+	/// Synthesizes a new instance of the sound effect, as mono samples.
+	/// Each instance is a bit different so that repeated sounds (like footsteps) are less boring.
+	fn synthesize(self, rng: &mut SmallRng) -> Vec<f32> {
+		match self {
+			SoundEffect::Footstep(surface) => {
+				let duration = match surface {
+					SoundSurface::Leaf => 0.15,
+					_ => 0.08,
+				};
+				let mut samples = filtered_noise(rng, duration, surface.brightness(), 40.0);
+				if matches!(surface, SoundSurface::Wood) {
+					add_knock(&mut samples, rng.gen_range(160.0..200.0), 0.5, 60.0);
+				}
+				samples
+			},
+			SoundEffect::BlockBreak(surface) => {
+				let mut samples = filtered_noise(rng, 0.25, surface.brightness() * 0.8, 14.0);
+				if matches!(surface, SoundSurface::Wood) {
+					add_knock(&mut samples, rng.gen_range(110.0..130.0), 0.6, 25.0);
+				}
+				samples
+			},
+			SoundEffect::BlockPlace(surface) => {
+				let mut samples = filtered_noise(rng, 0.07, surface.brightness(), 60.0);
+				add_knock(&mut samples, rng.gen_range(100.0..140.0), 0.7, 50.0);
+				samples
+			},
+			SoundEffect::UiClick => {
+				let mut samples = vec![0.0; duration_to_sample_count(0.02)];
+				add_knock(&mut samples, 1500.0, 0.3, 200.0);
+				samples
+			},
+			SoundEffect::Hurt => {
+				let mut samples = filtered_noise(rng, 0.2, 0.3, 20.0);
+				add_knock(&mut samples, rng.gen_range(80.0..100.0), 0.8, 16.0);
+				samples
+			},
+		}
+	}
+}
+
+fn duration_to_sample_count(duration_in_seconds: f32) -> usize {
+	(duration_in_seconds * SAMPLE_RATE as f32) as usize
+}
+
+/// White noise that goes through a one-pole low-pass filter and that decays exponentially.
+fn filtered_noise(rng: &mut SmallRng, duration: f32, brightness: f32, decay: f32) -> Vec<f32> {
+	let mut filtered = 0.0;
+	(0..duration_to_sample_count(duration))
+		.map(|i| {
+			let t = i as f32 / SAMPLE_RATE as f32;
+			filtered += (rng.gen_range(-1.0..1.0) - filtered) * brightness;
+			filtered * (-t * decay).exp()
+		})
+		.collect()
+}
+
+/// Adds a sine wave that decays exponentially, which sounds like a knock on something.
+fn add_knock(samples: &mut [f32], frequency: f32, amplitude: f32, decay: f32) {
+	for (i, sample) in samples.iter_mut().enumerate() {
+		let t = i as f32 / SAMPLE_RATE as f32;
+		*sample += (t * frequency * TAU).sin() * amplitude * (-t * decay).exp();
+	}
+}
+
+/// Somewhere the mixed audio goes to be heard (eventually).
+pub(crate) trait AudioOutput {
+	fn write(&mut self, frames: &[[f32; 2]]);
+}
+
+/// Once the audio device has played all the frames it was given, it waits for that many frames
+/// (in seconds) to be queued before playing again, so that it does not stutter when the frames
+/// of a game frame come a bit late.
+const DEVICE_OUTPUT_LATENCY: f32 = 0.05;
+/// The frames queued for the audio device beyond that (in seconds) are dropped (the oldest
+/// first), so that the latency does not grow when the device plays slower than the game mixes.
+const DEVICE_OUTPUT_MAX_QUEUED: f32 = 0.25;
+
+/// The frames mixed by the game that the audio device has not played yet.
+struct DeviceQueue {
+	frames: VecDeque<[f32; 2]>,
+	/// Position (in mixed frames) between the two first queued frames, the device sample rate
+	/// may differ from `SAMPLE_RATE` so the frames it plays are interpolated.
+	position: f32,
+	/// False after the queue ran dry, until it fills up to `DEVICE_OUTPUT_LATENCY` again.
+	playing: bool,
+}
+
+/// Plays the mixed audio on the default audio device of the system.
+pub(crate) struct DeviceOutput {
+	queue: Arc<Mutex<DeviceQueue>>,
+	/// The audio stops when the stream is dropped.
+	_stream: cpal::Stream,
+}
+
+impl DeviceOutput {
+	pub(crate) fn open_default_device() -> Result<DeviceOutput, String> {
+		let device = cpal::default_host()
+			.default_output_device()
+			.ok_or_else(|| "no audio output device".to_string())?;
+		// A configuration at our sample rate is preferred, any other is resampled to.
+		let config = device
+			.supported_output_configs()
+			.map_err(|error| error.to_string())?
+			.filter(|config| {
+				config.min_sample_rate().0 <= SAMPLE_RATE && SAMPLE_RATE <= config.max_sample_rate().0
+			})
+			.max_by_key(|config| {
+				(
+					config.channels() == 2,
+					config.sample_format() == cpal::SampleFormat::F32,
+				)
+			})
+			.map(|config| config.with_sample_rate(cpal::SampleRate(SAMPLE_RATE)))
+			.map_or_else(|| device.default_output_config(), Ok)
+			.map_err(|error| error.to_string())?;
+
+		let queue = Arc::new(Mutex::new(DeviceQueue {
+			frames: VecDeque::new(),
+			position: 0.0,
+			playing: false,
+		}));
+		let sample_format = config.sample_format();
+		let config: cpal::StreamConfig = config.into();
+		let stream = match sample_format {
+			cpal::SampleFormat::F32 => build_device_stream::<f32>(&device, &config, &queue),
+			cpal::SampleFormat::I16 => build_device_stream::<i16>(&device, &config, &queue),
+			cpal::SampleFormat::U16 => build_device_stream::<u16>(&device, &config, &queue),
+			cpal::SampleFormat::I32 => build_device_stream::<i32>(&device, &config, &queue),
+			sample_format => return Err(format!("unsupported sample format {sample_format}")),
+		}?;
+		stream.play().map_err(|error| error.to_string())?;
+		Ok(DeviceOutput { queue, _stream: stream })
+	}
+}
+
+/// The stream calls back for samples to play from its own thread, it takes them from the queue.
+fn build_device_stream<T: cpal::SizedSample + cpal::FromSample<f32>>(
+	device: &cpal::Device,
+	config: &cpal::StreamConfig,
+	queue: &Arc<Mutex<DeviceQueue>>,
+) -> Result<cpal::Stream, String> {
+	let queue = Arc::clone(queue);
+	let channels = config.channels as usize;
+	let step = SAMPLE_RATE as f32 / config.sample_rate.0 as f32;
+	let latency_frame_count = duration_to_sample_count(DEVICE_OUTPUT_LATENCY);
+	device
+		.build_output_stream(
+			config,
+			move |data: &mut [T], _info| {
+				let mut queue = queue.lock().unwrap();
+				if !queue.playing && queue.frames.len() >= latency_frame_count {
+					queue.playing = true;
+				}
+				for device_frame in data.chunks_mut(channels) {
+					let frame = if queue.playing && queue.frames.len() >= 2 {
+						let t = queue.position;
+						let (a, b) = (queue.frames[0], queue.frames[1]);
+						queue.position += step;
+						while queue.position >= 1.0 && queue.frames.len() >= 2 {
+							queue.position -= 1.0;
+							queue.frames.pop_front();
+						}
+						[a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+					} else {
+						queue.playing = false;
+						[0.0, 0.0]
+					};
+					// Mono devices get both sides, devices with more channels get silence
+					// on the channels after the two first ones.
+					for (channel, sample) in device_frame.iter_mut().enumerate() {
+						let value = match (channels, channel) {
+							(1, _) => (frame[0] + frame[1]) / 2.0,
+							(_, 0 | 1) => frame[channel],
+							_ => 0.0,
+						};
+						*sample = T::from_sample(value.clamp(-1.0, 1.0));
+					}
+				}
+			},
+			|error| log::warn!("Audio device stream error: {error}"),
+			None,
+		)
+		.map_err(|error| error.to_string())
+}
+
+impl AudioOutput for DeviceOutput {
+	fn write(&mut self, frames: &[[f32; 2]]) {
+		let mut queue = self.queue.lock().unwrap();
+		queue.frames.extend(frames);
+		let max_queued_frame_count = duration_to_sample_count(DEVICE_OUTPUT_MAX_QUEUED);
+		let excess = queue.frames.len().saturating_sub(max_queued_frame_count);
+		queue.frames.drain(..excess);
+	}
+}
+
+/// Writes the mixed audio to a WAV file (16 bits stereo PCM).
+pub(crate) struct WavFileOutput {
+	file: std::io::BufWriter<std::fs::File>,
+	written_frame_count: u32,
+}
+
+impl WavFileOutput {
+	pub(crate) fn create(path: &std::path::Path) -> std::io::Result<WavFileOutput> {
+		let mut output = WavFileOutput {
+			file: std::io::BufWriter::new(std::fs::File::create(path)?),
+			written_frame_count: 0,
+		};
+		output.write_header()?;
+		Ok(output)
+	}
+
+	/// The header contains the size of the data, so it is rewritten when the file is closed.
+	fn write_header(&mut self) -> std::io::Result<()> {
+		let data_size = self.written_frame_count * 4;
+		let file = &mut self.file;
+		file.write_all(b"RIFF")?;
+		file.write_all(&(36 + data_size).to_le_bytes())?;
+		file.write_all(b"WAVEfmt ")?;
+		file.write_all(&16u32.to_le_bytes())?;
+		file.write_all(&1u16.to_le_bytes())?; // PCM
+		file.write_all(&2u16.to_le_bytes())?; // Stereo
+		file.write_all(&SAMPLE_RATE.to_le_bytes())?;
+		file.write_all(&(SAMPLE_RATE * 4).to_le_bytes())?; // Bytes per second
+		file.write_all(&4u16.to_le_bytes())?; // Bytes per frame
+		file.write_all(&16u16.to_le_bytes())?; // Bits per sample
+		file.write_all(b"data")?;
+		file.write_all(&data_size.to_le_bytes())?;
+		Ok(())
+	}
+}
+
+impl AudioOutput for WavFileOutput {
+	fn write(&mut self, frames: &[[f32; 2]]) {
+		for frame in frames {
+			for sample in frame {
+				let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+				self.file.write_all(&sample.to_le_bytes()).unwrap();
+			}
+		}
+		self.written_frame_count += frames.len() as u32;
+	}
+}
+
+impl Drop for WavFileOutput {
+	fn drop(&mut self) {
+		use std::io::Seek;
+		let result = self
+			.file
+			.seek(std::io::SeekFrom::Start(0))
+			.and_then(|_| self.write_header())
+			.and_then(|_| self.file.flush());
+		if let Err(error) = result {
+			log::warn!("Failed to finish writing the audio dump: {error}");
+		}
+	}
+}
+
+pub(crate) struct AudioSettings {
+	/// Applies to every sound.
+	pub(crate) master_volume: f32,
+	/// Applies to sound effects (so not to ambient sounds).
+	pub(crate) sfx_volume: f32,
+	pub(crate) music_volume: f32,
+	pub(crate) enable_music: bool,
+}
+
+/// A sound effect instance that is being played.
+struct Voice {
+	samples: Vec<f32>,
+	next_sample_index: usize,
+	/// Left and right gains.
+	gains: [f32; 2],
+}
+
+/// The ambient wind is an endless low-passed noise with a slowly varying intensity.
+struct Wind {
+	/// From 0.0 (no wind) to 1.0 (strong wind).
+	strength: f32,
+	filtered_noise: f32,
+	gust_phase: f32,
+}
+
+struct PlayingTrack {
+	track: MusicTrack,
+	next_sample_index: usize,
+}
+
+impl PlayingTrack {
+	fn next_sample(&mut self) -> f32 {
+		let sample = self.track.samples[self.next_sample_index];
+		self.next_sample_index = (self.next_sample_index + 1) % self.track.samples.len();
+		sample
+	}
+}
+
+struct Music {
+	world_seed: i32,
+	current: Option<PlayingTrack>,
+	/// The previous track, still heard during the crossfade.
+	fading_out: Option<PlayingTrack>,
+	/// From 0.0 (crossfade just started) to 1.0 (crossfade is over).
+	crossfade_progress: f32,
+	/// Composing a track takes a little while so it is done in its own thread.
+	being_composed: Option<(MusicMood, Receiver<MusicTrack>)>,
+	/// A mood that differs from the current music, and for how long it has (in seconds).
+	new_mood_candidate: Option<(MusicMood, f32)>,
+}
+
+impl Music {
+	fn mood(&self) -> Option<MusicMood> {
+		self
+			.being_composed
+			.as_ref()
+			.map(|(mood, _receiver)| *mood)
+			.or(self.current.as_ref().map(|playing| playing.track.mood))
+	}
+
+	fn start_composing(&mut self, mood: MusicMood) {
+		let (sender, receiver) = std::sync::mpsc::channel();
+		let world_seed = self.world_seed;
+		std::thread::spawn(move || {
+			// The receiver may have been dropped, it is not a problem.
+			let _ = sender.send(compose_track(world_seed, mood));
+		});
+		self.being_composed = Some((mood, receiver));
+		self.new_mood_candidate = None;
+	}
+
+	/// Starts the crossfade if the track that was being composed is ready.
+	fn receive_composed_track(&mut self) {
+		let Some((_mood, receiver)) = self.being_composed.as_ref() else {
+			return;
+		};
+		match receiver.try_recv() {
+			Ok(track) => {
+				self.fading_out = self.current.take();
+				self.current = Some(PlayingTrack { track, next_sample_index: 0 });
+				self.crossfade_progress = 0.0;
+				self.being_composed = None;
+			},
+			Err(TryRecvError::Empty) => {},
+			Err(TryRecvError::Disconnected) => self.being_composed = None,
+		}
+	}
+}
+
+pub(crate) struct Audio {
+	pub(crate) settings: AudioSettings,
+	/// The mixed audio is written to all of them.
+	outputs: Vec<Box<dyn AudioOutput>>,
+	voices: Vec<Voice>,
+	wind: Wind,
+	music: Music,
+	listener_position: cgmath::Point3<f32>,
+	listener_direction: cgmath::Vector3<f32>,
+	/// Horizontal distance walked on the ground since the last footstep.
+	distance_since_last_footstep: f32,
+	/// Fraction of a frame (of audio) left from the previous mixing,
+	/// so that we do not drift from the actual time.
+	frame_count_remainder: f32,
+	rng: SmallRng,
+}
+
+impl Audio {
+	pub(crate) fn new(
+		settings: AudioSettings,
+		outputs: Vec<Box<dyn AudioOutput>>,
+		world_seed: i32,
+	) -> Audio {
+		Audio {
+			settings,
+			outputs,
+			voices: vec![],
+			wind: Wind { strength: 0.0, filtered_noise: 0.0, gust_phase: 0.0 },
+			music: Music {
+				world_seed,
+				current: None,
+				fading_out: None,
+				crossfade_progress: 1.0,
+				being_composed: None,
+				new_mood_candidate: None,
+			},
+			listener_position: cgmath::point3(0.0, 0.0, 0.0),
+			listener_direction: cgmath::vec3(1.0, 0.0, 0.0),
+			distance_since_last_footstep: 0.0,
+			frame_count_remainder: 0.0,
+			rng: SmallRng::from_entropy(),
+		}
+	}
+
+	/// The listener is the camera, positional sounds are heard relative to it.
+	pub(crate) fn set_listener(
+		&mut self,
+		position: cgmath::Point3<f32>,
+		direction: cgmath::Vector3<f32>,
+	) {
+		self.listener_position = position;
+		self.listener_direction = direction;
+	}
+
+	/// The wind is stronger higher up, where nothing protects from it.
+	pub(crate) fn set_wind_altitude(&mut self, z: f32) {
+		self.wind.strength = ((z - 10.0) / 80.0).clamp(0.15, 1.0);
+	}
+
+	/// The music changes to match the mood of the surroundings if that mood lasts long enough.
+	/// The biome is the one the player is in, if the world generator has biomes.
+	/// Must be called at every frame.
+	pub(crate) fn update_music_mood(&mut self, altitude: f32, biome: Option<Biome>, dt: Duration) {
+		if self.outputs.is_empty() || !self.settings.enable_music {
+			return;
+		}
+		let music = &mut self.music;
+		let mood = MusicMood::new(altitude, biome);
+		match music.mood() {
+			None => music.start_composing(mood),
+			Some(current_mood) if current_mood == mood => music.new_mood_candidate = None,
+			Some(_) => match music.new_mood_candidate.as_mut() {
+				Some((candidate, duration)) if *candidate == mood => {
+					*duration += dt.as_secs_f32();
+					if *duration >= MUSIC_MOOD_MIN_DURATION {
+						music.start_composing(mood);
+					}
+				},
+				_ => music.new_mood_candidate = Some((mood, 0.0)),
+			},
+		}
+	}
+
+	/// Plays a sound that is not positioned in the world (like interface sounds).
+	pub(crate) fn play(&mut self, effect: SoundEffect) {
+		self.add_voice(effect, [1.0, 1.0]);
+	}
+
+	/// Plays a sound that is emitted from the given position in the world.
+	pub(crate) fn play_at(&mut self, effect: SoundEffect, position: cgmath::Point3<f32>) {
+		let to_source = position - self.listener_position;
+		let distance = to_source.magnitude();
+		if distance > MAX_HEARING_DISTANCE {
+			return;
+		}
+		// The attenuation goes smoothly to zero at the maximum hearing distance.
+		let attenuation = (1.0 / (1.0 + (distance / HALF_VOLUME_DISTANCE).powi(2)))
+			* (1.0 - distance / MAX_HEARING_DISTANCE);
+		// Panning is in -1.0 (left) to 1.0 (right), with equal-power gains.
+		let right = self.listener_direction.cross(cgmath::vec3(0.0, 0.0, 1.0));
+		let pan = if distance < 0.5 || right.magnitude() == 0.0 {
+			0.0
+		} else {
+			to_source.normalize().dot(right.normalize())
+		};
+		let angle = (pan + 1.0) * TAU / 8.0;
+		self.add_voice(
+			effect,
+			[angle.cos() * attenuation, angle.sin() * attenuation],
+		);
+	}
+
+	fn add_voice(&mut self, effect: SoundEffect, gains: [f32; 2]) {
+		if self.outputs.is_empty() {
+			// No one would hear it.
+			return;
+		}
+		let samples = effect.synthesize(&mut self.rng);
+		let volume = self.settings.sfx_volume;
+		self.voices.push(Voice {
+			samples,
+			next_sample_index: 0,
+			gains: gains.map(|gain| gain * volume),
+		});
+	}
+
+	/// Must be called after the player physics step with the player displacement of this step.
+	/// Plays footsteps at a regular pace while the player walks on the ground.
+	pub(crate) fn player_walked(
+		&mut self,
+		displacement: cgmath::Vector3<f32>,
+		on_ground: Option<(SoundSurface, cgmath::Point3<f32>)>,
+	) {
+		let Some((surface, feet_position)) = on_ground else {
+			// A footstep will be heard right away when landing.
+			self.distance_since_last_footstep = FOOTSTEP_STRIDE;
+			return;
+		};
+		self.distance_since_last_footstep += cgmath::vec2(displacement.x, displacement.y).magnitude();
+		if self.distance_since_last_footstep >= FOOTSTEP_STRIDE {
+			self.distance_since_last_footstep = 0.0;
+			self.play_at(SoundEffect::Footstep(surface), feet_position);
+		}
+	}
+
+	/// Mixes the audio that is to be played during the given time and sends it to the output.
+	/// Must be called at every frame.
+	pub(crate) fn advance(&mut self, dt: Duration) {
+		if self.outputs.is_empty() {
+			return;
+		}
+
+		let frame_count = dt.as_secs_f32() * SAMPLE_RATE as f32 + self.frame_count_remainder;
+		self.frame_count_remainder = frame_count.fract();
+		let frame_count = frame_count as usize;
+		let mut frames = vec![[0.0, 0.0]; frame_count];
+
+		for voice in self.voices.iter_mut() {
+			let remaining_samples = &voice.samples[voice.next_sample_index..];
+			for (frame, sample) in frames.iter_mut().zip(remaining_samples) {
+				frame[0] += sample * voice.gains[0];
+				frame[1] += sample * voice.gains[1];
+			}
+			voice.next_sample_index += frame_count.min(remaining_samples.len());
+		}
+		self.voices.retain(|voice| voice.next_sample_index < voice.samples.len());
+
+		let wind = &mut self.wind;
+		let wind_volume = wind.strength * 0.15;
+		for frame in frames.iter_mut() {
+			wind.gust_phase = (wind.gust_phase + 0.13 / SAMPLE_RATE as f32).fract();
+			let gust = 0.6 + 0.4 * (wind.gust_phase * TAU).sin();
+			wind.filtered_noise += (self.rng.gen_range(-1.0..1.0) - wind.filtered_noise) * 0.02 * gust;
+			let sample = wind.filtered_noise * wind_volume * gust;
+			frame[0] += sample;
+			frame[1] += sample;
+		}
+
+		if self.settings.enable_music {
+			let music = &mut self.music;
+			music.receive_composed_track();
+			let crossfade_step = 1.0 / (MUSIC_CROSSFADE_DURATION * SAMPLE_RATE as f32);
+			for frame in frames.iter_mut() {
+				// Equal-power crossfade.
+				music.crossfade_progress = (music.crossfade_progress + crossfade_step).min(1.0);
+				let angle = music.crossfade_progress * TAU / 4.0;
+				let mut sample = 0.0;
+				if let Some(current) = music.current.as_mut() {
+					sample += current.next_sample() * angle.sin();
+				}
+				if let Some(fading_out) = music.fading_out.as_mut() {
+					sample += fading_out.next_sample() * angle.cos();
+				}
+				sample *= self.settings.music_volume;
+				frame[0] += sample;
+				frame[1] += sample;
+			}
+			if music.crossfade_progress >= 1.0 {
+				music.fading_out = None;
+			}
+		}
+
+		for frame in frames.iter_mut() {
+			frame[0] *= self.settings.master_volume;
+			frame[1] *= self.settings.master_volume;
+		}
+		for output in self.outputs.iter_mut() {
+			output.write(&frames);
+		}
+	}
+}
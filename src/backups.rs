@@ -0,0 +1,127 @@
+//! Backups of saves, snapshots of the save directory that the world can be rolled back to.
+//! The `backup_create()` command makes one, and `backup_restore(backup_2)` rolls the world back
+//! to the backup `backup_2` (the game quits and the save is replaced when exiting, as the world
+//! cannot be replaced while it is running). Backups are also made automatically before risky
+//! operations, like editing a lot of blocks at once or migrating a save to new block type ids.
+//!
+//! The backups of the save `<name>` are in `backups/<name>`, they are named like `backup_3`
+//! (numbered in the order of their creation) with a suffix when made automatically (like
+//! `backup_4_before_mass_edit`). The files are copied, not hardlinked, as the save files are
+//! rewritten in place (which would also change the backups).
+
+use std::{
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use crate::{
+	game_init::{save_savable_state_in, Game},
+	saves::Save,
+};
+
+const BACKUPS_DIRECTORY: &str = "backups";
+
+fn backups_directory(save: &Save) -> PathBuf {
+	Path::new(BACKUPS_DIRECTORY).join(&save.name)
+}
+
+/// The number in the backup name, as in `backup_3` or `backup_4_before_mass_edit`.
+fn backup_number(backup_name: &str) -> Option<u32> {
+	let rest = backup_name.strip_prefix("backup_")?;
+	rest.split('_').next()?.parse().ok()
+}
+
+/// The names of the backups of the save, oldest first.
+pub(crate) fn backup_names(save: &Save) -> Vec<String> {
+	let Ok(directory) = std::fs::read_dir(backups_directory(save)) else {
+		return vec![];
+	};
+	let mut names: Vec<String> = directory
+		.filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+		.filter(|name| backup_number(name).is_some())
+		.collect();
+	names.sort_by_key(|name| backup_number(name));
+	names
+}
+
+/// Copies the files of the `from` directory (and of its sub directories) with the given function.
+fn copy_directory(
+	from: &Path,
+	to: &Path,
+	copy_file: &dyn Fn(&Path, &Path) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+	std::fs::create_dir_all(to)?;
+	for entry in std::fs::read_dir(from)? {
+		let entry = entry?;
+		let (from_path, to_path) = (entry.path(), to.join(entry.file_name()));
+		if entry.file_type()?.is_dir() {
+			copy_directory(&from_path, &to_path, copy_file)?;
+		} else {
+			copy_file(&from_path, &to_path)?;
+		}
+	}
+	Ok(())
+}
+
+/// Copies the files of the save to a new backup, returns the backup as a save (to be able
+/// to write more in it). The suffix is added to the backup name.
+pub(crate) fn create_backup(save: &Save, suffix: Option<&str>) -> Result<Save, String> {
+	let number = backup_names(save).last().and_then(|name| backup_number(name)).unwrap_or(0) + 1;
+	let name = match suffix {
+		Some(suffix) => format!("backup_{number}_{suffix}"),
+		None => format!("backup_{number}"),
+	};
+	let backup_directory = backups_directory(save).join(&name);
+	// The files are read through the save so that a file is not copied while it is being written
+	// by some other thread.
+	copy_directory(&save.main_directory, &backup_directory, &|from, to| {
+		let file_io = save.get_file_io(from.to_path_buf());
+		let data = file_io.lock().read();
+		match data {
+			Some(data) => std::fs::write(to, data),
+			// It was deleted in the meantime.
+			None => Ok(()),
+		}
+	})
+	.map_err(|error| error.to_string())?;
+	Ok(Save::create_in_directory(name, backup_directory))
+}
+
+/// Backs up the save of the game, including what is loaded and not saved yet.
+/// Returns the name of the backup.
+pub(crate) fn backup_game(game: &Game, suffix: Option<&str>) -> Result<String, String> {
+	let save = game.save.as_ref().ok_or("there is no save")?;
+	let backup = Arc::new(create_backup(save, suffix)?);
+	save_savable_state_in(game, &backup);
+	game.chunk_grid_shareable.get().save_all_chunks(Some(&backup), game.only_save_modified_chunks);
+	Ok(backup.name.clone())
+}
+
+/// Replaces the files of the save by the ones of the backup, the world must not be running.
+/// The save is backed up before that, so that the restoration can be undone.
+pub(crate) fn restore_backup(save: &Save, backup_name: &str) -> Result<(), String> {
+	let backup_directory = backups_directory(save).join(backup_name);
+	if !backup_directory.is_dir() {
+		return Err(format!("there is no backup \"{backup_name}\""));
+	}
+	let undo_backup = create_backup(save, Some("before_restore"))?;
+	log::info!("Backup \"{}\" created before restoring a backup", undo_backup.name);
+	std::fs::remove_dir_all(&save.main_directory).map_err(|error| error.to_string())?;
+	copy_directory(&backup_directory, &save.main_directory, &|from, to| {
+		std::fs::copy(from, to).map(|_| ())
+	})
+	.map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn backup_names_are_numbered() {
+		assert_eq!(backup_number("backup_3"), Some(3));
+		assert_eq!(backup_number("backup_12_before_mass_edit"), Some(12));
+		assert_eq!(backup_number("backup_"), None);
+		assert_eq!(backup_number("something_else"), None);
+	}
+}
@@ -0,0 +1,194 @@
+//! Block types can be defined without recompiling, in the `block_types.qwy3_blocks` file
+//! (if there is one) that has lines like
+//! `block kinda_marble solid texture_gen:17 hardness:1.5 tool:pickaxe tier:1`, meaning
+//! "a block type named `kinda_marble` that is a solid cube, textured by the texture generator
+//! of seed 17, that takes 1.5 seconds to break by hand and that must be broken with at least
+//! a wooden pickaxe to be harvested". These block types are added after the builtin block types
+//! (see `BlockTypeTable::add_defined_block_types`).
+//!
+//! The shape is one of `solid` (the only shape that boxes collide with), `x_shaped`, `billboard`,
+//! `liquid` or `layer`. The properties that can follow are:
+//! - `texture_gen:<seed>`, `texture_recipe:<recipe>,<palette>,<scale>,<roughness percent>`
+//!   (see `TextureRecipe`) or `texture:<path to a 16x16 image>` (a texture is required),
+//! - `hardness:<seconds>` (or `hardness:instant`), `tool:<pickaxe|shovel|axe>`, `tier:<tier>`,
+//! - `emission:<r>,<g>,<b>,<radius>` for a block that emits light,
+//! - `friction:<friction>` and `speed:<speed factor>` for a block that is walked on differently,
+//! - `flammable:true` for a block that catches fire and burns away (see `fire`).
+
+use crate::{
+	block_types::{GroundResponse, Hardness, ToolKind},
+	deferred_lighting::LightEmission,
+	texture_gen::{palette_from_name, RecipeParams, TextureRecipe},
+};
+
+/// The shapes that defined block types can have, see the corresponding `BlockType` variants.
+#[derive(Clone, Copy)]
+pub(crate) enum DefinedShape {
+	Solid,
+	XShaped,
+	Billboard,
+	Liquid,
+	Layer,
+}
+
+/// Where the texture of a defined block type comes from.
+#[derive(Clone)]
+pub(crate) enum DefinedTexture {
+	/// Painted by `texture_gen::generate_texture` with this texture seed.
+	Generated { seed: i32 },
+	/// Painted by the recipe with these parameters.
+	Recipe { recipe: TextureRecipe, params: RecipeParams },
+	/// Read from an image file.
+	File(std::path::PathBuf),
+}
+
+#[derive(Clone)]
+pub(crate) struct BlockDefinition {
+	pub(crate) name: String,
+	pub(crate) shape: DefinedShape,
+	pub(crate) texture: DefinedTexture,
+	pub(crate) hardness: Hardness,
+	pub(crate) light_emission: Option<LightEmission>,
+	pub(crate) ground_response: GroundResponse,
+	pub(crate) flammable: bool,
+}
+
+/// Reads the block types file (if any), the lines that are not valid are skipped
+/// (and reported as errors).
+pub(crate) fn parse_block_definitions_file() -> Vec<BlockDefinition> {
+	let mut definitions: Vec<BlockDefinition> = vec![];
+	let definitions_file_path = "block_types.qwy3_blocks";
+	let Ok(definitions_string) = std::fs::read_to_string(definitions_file_path) else {
+		// No block types file is fine, there are just no defined block types.
+		return definitions;
+	};
+	for (line_index, line) in definitions_string.lines().enumerate() {
+		let line_number = line_index + 1;
+		let mut words = line.split_whitespace();
+		match words.next() {
+			Some("block") => {
+				let (Some(name), Some(shape_name)) = (words.next(), words.next()) else {
+					log::error!(
+						"In file \"{definitions_file_path}\" at line {line_number}: \
+						Expected a name and a shape after \"block\""
+					);
+					continue;
+				};
+				if definitions.iter().any(|definition| definition.name == name) {
+					log::error!(
+						"In file \"{definitions_file_path}\" at line {line_number}: \
+						Block type \"{name}\" is already defined above"
+					);
+					continue;
+				}
+				match parse_definition(name, shape_name, words) {
+					Ok(definition) => definitions.push(definition),
+					Err(error) => {
+						log::error!("In file \"{definitions_file_path}\" at line {line_number}: {error}")
+					},
+				}
+			},
+			Some(word) if word.starts_with('#') => {},
+			None => {},
+			Some(unknown_command_name) => {
+				log::error!(
+					"In file \"{definitions_file_path}\" at line {line_number}: \
+					Command name \"{unknown_command_name}\" is unknown"
+				);
+			},
+		}
+	}
+	definitions
+}
+
+fn parse_definition<'a>(
+	name: &str,
+	shape_name: &str,
+	properties: impl Iterator<Item = &'a str>,
+) -> Result<BlockDefinition, String> {
+	let shape = match shape_name {
+		"solid" => DefinedShape::Solid,
+		"x_shaped" => DefinedShape::XShaped,
+		"billboard" => DefinedShape::Billboard,
+		"liquid" => DefinedShape::Liquid,
+		"layer" => DefinedShape::Layer,
+		unknown_shape_name => {
+			return Err(format!(
+				"Unknown shape \"{unknown_shape_name}\" \
+				(expected solid, x_shaped, billboard, liquid or layer)"
+			))
+		},
+	};
+	let mut texture = None;
+	let mut hardness = Hardness::DEFAULT;
+	let mut light_emission = None;
+	let mut ground_response = GroundResponse::DEFAULT;
+	let mut flammable = false;
+	for property in properties {
+		let Some((key, value)) = property.split_once(':') else {
+			return Err(format!("Expected \"key:value\" but got \"{property}\""));
+		};
+		let invalid = || format!("Invalid value \"{value}\" for \"{key}\"");
+		let parse_f32 = |value: &str| value.parse::<f32>().map_err(|_| invalid());
+		match key {
+			"texture_gen" => {
+				texture =
+					Some(DefinedTexture::Generated { seed: value.parse().map_err(|_| invalid())? })
+			},
+			"texture_recipe" => {
+				let &[recipe_name, palette_name, scale, roughness_percent] =
+					value.split(',').collect::<Vec<_>>().as_slice()
+				else {
+					return Err(invalid());
+				};
+				let recipe = TextureRecipe::from_name(recipe_name)
+					.ok_or_else(|| format!("Unknown texture recipe \"{recipe_name}\""))?;
+				let palette = palette_from_name(palette_name)
+					.ok_or_else(|| format!("Unknown palette \"{palette_name}\""))?;
+				let params = RecipeParams {
+					palette,
+					scale: parse_f32(scale)?,
+					roughness: parse_f32(roughness_percent)? / 100.0,
+				};
+				texture = Some(DefinedTexture::Recipe { recipe, params });
+			},
+			"texture" => texture = Some(DefinedTexture::File(value.into())),
+			"hardness" if value == "instant" => hardness.breaking_time = 0.0,
+			"hardness" => hardness.breaking_time = parse_f32(value)?.max(0.0),
+			"tool" => {
+				hardness.tool_kind = Some(match value {
+					"pickaxe" => ToolKind::Pickaxe,
+					"shovel" => ToolKind::Shovel,
+					"axe" => ToolKind::Axe,
+					_ => return Err(invalid()),
+				})
+			},
+			"tier" => hardness.required_tier = value.parse().map_err(|_| invalid())?,
+			"emission" => {
+				let components: Vec<f32> = value.split(',').map(parse_f32).collect::<Result<_, _>>()?;
+				let [r, g, b, radius] = components[..] else {
+					return Err(invalid());
+				};
+				light_emission = Some(LightEmission { color: [r, g, b], radius });
+			},
+			"friction" => ground_response.friction = parse_f32(value)?,
+			"speed" => ground_response.speed_factor = parse_f32(value)?,
+			"flammable" => flammable = value.parse().map_err(|_| invalid())?,
+			unknown_key => return Err(format!("Unknown property \"{unknown_key}\"")),
+		}
+	}
+	let Some(texture) = texture else {
+		return Err(
+			"Expected a \"texture_gen\", \"texture_recipe\" or \"texture\" property".to_string(),
+		);
+	};
+	Ok(BlockDefinition {
+		name: name.to_string(),
+		shape,
+		texture,
+		hardness,
+		light_emission,
+		ground_response,
+		flammable,
+	})
+}
@@ -0,0 +1,114 @@
+//! Light emitted by blocks (like lamps) that spreads through the blocks that are not opaque.
+//!
+//! The light is colored, each of the red, green and blue channels spreads on its own
+//! (losing one level per block), so that the light of an orange block stays orange around it
+//! and the light of different blocks mixes where they meet.
+//!
+//! The light is computed when meshing a chunk, from the snapshot of the chunk and its neighbors,
+//! and given to the vertices of the mesh. The light of blocks that are further than the
+//! neighbor chunks does not reach the chunk.
+
+use std::collections::VecDeque;
+
+use fxhash::FxHashMap;
+
+use crate::{
+	block_types::BlockTypeTable,
+	chunks::{ChunkGrid, ChunkGridSnapshot},
+	coords::{BlockCoords, OrientedAxis},
+};
+
+/// The light level of a block emitting light at full strength, no light goes further than that
+/// many blocks from its source.
+pub(crate) const MAX_BLOCK_LIGHT_LEVEL: u8 = 15;
+
+/// Light levels of the red, green and blue channels, from 0 to `MAX_BLOCK_LIGHT_LEVEL`.
+pub(crate) type BlockLight = [u8; 3];
+
+/// The block light in and around a chunk, as spread from the light emitting blocks of a snapshot.
+pub(crate) struct BlockLightAroundChunk {
+	/// Blocks that get no light at all are not in there.
+	light_levels: FxHashMap<BlockCoords, BlockLight>,
+}
+
+impl BlockLightAroundChunk {
+	pub(crate) fn get(&self, coords: BlockCoords) -> BlockLight {
+		self.light_levels.get(&coords).copied().unwrap_or([0, 0, 0])
+	}
+
+	/// The light as a color to add to the lit surfaces (from 0.0 to 1.0 for each channel).
+	pub(crate) fn get_as_color(&self, coords: BlockCoords) -> [f32; 3] {
+		self.get(coords).map(|level| level as f32 / MAX_BLOCK_LIGHT_LEVEL as f32)
+	}
+}
+
+impl ChunkGridSnapshot {
+	/// Spreads the light of the light emitting blocks of the snapshot. Only the blocks of the
+	/// snapshot that are in loaded chunks are considered, the others block the light.
+	pub(crate) fn block_light_around_chunk(
+		&self,
+		block_type_table: &BlockTypeTable,
+	) -> BlockLightAroundChunk {
+		let mut light_levels: FxHashMap<BlockCoords, BlockLight> = FxHashMap::default();
+		let mut queue = VecDeque::new();
+
+		for chunk_blocks in self.iter_loaded_chunks() {
+			let has_light_emitting_blocks = chunk_blocks
+				.block_type_ids_in_palette()
+				.any(|id| block_type_table.light_emission(id).is_some());
+			if !has_light_emitting_blocks {
+				// Most chunks have no light emitting block, no need to look at all their blocks.
+				continue;
+			}
+			for coords in chunk_blocks.coords_span.iter_coords() {
+				let type_id = chunk_blocks.get(coords).unwrap().type_id;
+				if let Some(emission) = block_type_table.light_emission(type_id) {
+					let level = emission.radius.clamp(0.0, MAX_BLOCK_LIGHT_LEVEL as f32);
+					let light = emission.color.map(|channel| (channel * level).round() as u8);
+					light_levels.insert(coords, light);
+					queue.push_back(coords);
+				}
+			}
+		}
+
+		// Breadth-first spreading, a block is visited again when one of its channels gets brighter.
+		while let Some(coords) = queue.pop_front() {
+			let light = light_levels[&coords];
+			let spread_light = light.map(|level| level.saturating_sub(1));
+			if spread_light == [0, 0, 0] {
+				continue;
+			}
+			for direction in OrientedAxis::all_the_six_possible_directions() {
+				let neighbor_coords = coords + direction.delta();
+				let lets_light_through = self
+					.get_block(neighbor_coords)
+					.is_some_and(|block| !block_type_table.get(block.type_id).unwrap().is_opaque());
+				if !lets_light_through {
+					continue;
+				}
+				let neighbor_light = light_levels.entry(neighbor_coords).or_insert([0, 0, 0]);
+				let brighter = std::array::from_fn(|i| neighbor_light[i].max(spread_light[i]));
+				if brighter != *neighbor_light {
+					*neighbor_light = brighter;
+					queue.push_back(neighbor_coords);
+				}
+			}
+		}
+
+		BlockLightAroundChunk { light_levels }
+	}
+}
+
+impl ChunkGrid {
+	/// The block light at the given coords, as it is computed when meshing the chunk that
+	/// contains these coords. It is computed from scratch, so it is better not to call it too often.
+	pub(crate) fn block_light_at(
+		&self,
+		coords: BlockCoords,
+		block_type_table: &BlockTypeTable,
+	) -> BlockLight {
+		let chunk_coords = self.cd().world_coords_to_containing_chunk_coords(coords);
+		let snapshot = self.snapshot_around(chunk_coords, 1);
+		snapshot.block_light_around_chunk(block_type_table).get(coords)
+	}
+}
@@ -0,0 +1,302 @@
+//! The block picker is a screen that displays a grid of all the block types,
+//! with a text filter to search among them, so that the player can pick one and hold it.
+
+use crate::{
+	atlas::RectInAtlas,
+	block_types::{BlockType, BlockTypeId, BlockTypeTable},
+	chunk_blocks::{Block, BlockData},
+	font::{self, Font},
+	localization::Localization,
+	simple_meshes::SimpleTextureMesh,
+	widgets::{simple_line_vertices_for_rect, InterfaceMeshesVertices},
+};
+
+/// Number of block icons per row in the grid.
+const COLUMN_COUNT: usize = 10;
+/// Block textures are 16x16 pixels in the atlas, they are stretched by this factor.
+const ICON_SCALE: f32 = 4.0;
+/// Size of a cell of the grid (icon and spacing around it), in screen pixels.
+const CELL_SIZE_IN_PIXELS: f32 = 16.0 * ICON_SCALE + 12.0;
+/// Space above the grid (where the filter is displayed), in screen pixels.
+const GRID_TOP_MARGIN_IN_PIXELS: f32 = 90.0;
+
+pub(crate) struct BlockPicker {
+	pub(crate) is_open: bool,
+	/// Only the block types which names contain this (case insensitive) are displayed.
+	filter: String,
+	/// Index of the first row of the grid that is displayed, scrolling is done by changing it.
+	first_displayed_row: usize,
+	/// Position of the mouse cursor in the window, in screen pixels (from the top left corner).
+	cursor_position: Option<cgmath::Point2<f32>>,
+}
+
+impl BlockPicker {
+	pub(crate) fn new() -> BlockPicker {
+		BlockPicker {
+			is_open: false,
+			filter: String::new(),
+			first_displayed_row: 0,
+			cursor_position: None,
+		}
+	}
+
+	pub(crate) fn open(&mut self) {
+		self.is_open = true;
+		self.filter.clear();
+		self.first_displayed_row = 0;
+	}
+
+	pub(crate) fn close(&mut self) {
+		self.is_open = false;
+	}
+
+	pub(crate) fn type_in_filter(&mut self, text: &str) {
+		self.filter += text;
+		self.first_displayed_row = 0;
+	}
+
+	pub(crate) fn erase_in_filter(&mut self) {
+		self.filter.pop();
+		self.first_displayed_row = 0;
+	}
+
+	pub(crate) fn set_cursor_position(&mut self, cursor_position: cgmath::Point2<f32>) {
+		self.cursor_position = Some(cursor_position);
+	}
+
+	/// The block types that pass the filter, in the order they are displayed in the grid.
+	fn filtered_block_type_ids(&self, block_type_table: &BlockTypeTable) -> Vec<BlockTypeId> {
+		let filter = self.filter.to_lowercase();
+		block_type_table
+			.iter_ids()
+			.filter(|&id| !block_type_table.get(id).unwrap().is_air())
+			.filter(|&id| block_type_table.name(id).unwrap().to_lowercase().contains(&filter))
+			.collect()
+	}
+
+	fn displayed_row_count(window_dimensions: cgmath::Vector2<f32>) -> usize {
+		let available_height = window_dimensions.y - GRID_TOP_MARGIN_IN_PIXELS - 20.0;
+		((available_height / CELL_SIZE_IN_PIXELS).floor() as usize).max(1)
+	}
+
+	fn grid_top_left_in_pixels(window_dimensions: cgmath::Vector2<f32>) -> cgmath::Point2<f32> {
+		let grid_width = COLUMN_COUNT as f32 * CELL_SIZE_IN_PIXELS;
+		cgmath::point2(
+			((window_dimensions.x - grid_width) / 2.0).floor(),
+			GRID_TOP_MARGIN_IN_PIXELS,
+		)
+	}
+
+	/// Scrolls the grid by the given number of rows (positive is down).
+	pub(crate) fn scroll(
+		&mut self,
+		rows: i32,
+		block_type_table: &BlockTypeTable,
+		window_dimensions: cgmath::Vector2<f32>,
+	) {
+		let row_count = self.filtered_block_type_ids(block_type_table).len().div_ceil(COLUMN_COUNT);
+		let displayed_row_count = BlockPicker::displayed_row_count(window_dimensions);
+		let max_first_displayed_row = row_count.saturating_sub(displayed_row_count);
+		self.first_displayed_row =
+			(self.first_displayed_row as i32 + rows).clamp(0, max_first_displayed_row as i32) as usize;
+	}
+
+	/// Returns the block type of the grid cell that is under the mouse cursor, if any.
+	pub(crate) fn hovered_block_type(
+		&self,
+		block_type_table: &BlockTypeTable,
+		window_dimensions: cgmath::Vector2<f32>,
+	) -> Option<BlockTypeId> {
+		let cursor_position = self.cursor_position?;
+		let grid_top_left = BlockPicker::grid_top_left_in_pixels(window_dimensions);
+		let in_grid = cursor_position - grid_top_left;
+		if in_grid.x < 0.0 || in_grid.y < 0.0 {
+			return None;
+		}
+		let column = (in_grid.x / CELL_SIZE_IN_PIXELS).floor() as usize;
+		let displayed_row = (in_grid.y / CELL_SIZE_IN_PIXELS).floor() as usize;
+		if column >= COLUMN_COUNT
+			|| displayed_row >= BlockPicker::displayed_row_count(window_dimensions)
+		{
+			return None;
+		}
+		let index = (self.first_displayed_row + displayed_row) * COLUMN_COUNT + column;
+		self.filtered_block_type_ids(block_type_table).get(index).copied()
+	}
+
+	/// Returns the first block type that passes the filter, if any.
+	pub(crate) fn first_filtered_block_type(
+		&self,
+		block_type_table: &BlockTypeTable,
+	) -> Option<BlockTypeId> {
+		self.filtered_block_type_ids(block_type_table).first().copied()
+	}
+
+	pub(crate) fn generate_mesh_vertices(
+		&self,
+		meshes: &mut InterfaceMeshesVertices,
+		font: &Font,
+		localization: &Localization,
+		block_type_table: &BlockTypeTable,
+		window_dimensions: cgmath::Vector2<f32>,
+	) {
+		// Converts screen pixels coords (from the top left corner) to interface coords.
+		let screen_pixel_size = 2.0 / window_dimensions.x;
+		let to_interface_coords = |pixel_coords: cgmath::Point2<f32>| {
+			cgmath::point3(
+				-1.0 + pixel_coords.x * screen_pixel_size,
+				window_dimensions.y / window_dimensions.x - pixel_coords.y * screen_pixel_size,
+				0.5,
+			)
+		};
+
+		let filtered_ids = self.filtered_block_type_ids(block_type_table);
+		let row_count = filtered_ids.len().div_ceil(COLUMN_COUNT);
+		let displayed_row_count = BlockPicker::displayed_row_count(window_dimensions);
+		let grid_top_left = BlockPicker::grid_top_left_in_pixels(window_dimensions);
+
+		// Filter and scrolling info above the grid.
+		{
+			let mut settings = font::TextRenderingSettings::with_scale(4.0);
+			settings.color = [1.0, 1.0, 1.0];
+			let text = format!(
+				"{}: {}█",
+				localization.get("block_picker.search"),
+				self.filter
+			);
+			let vertices = font.simple_texture_vertices_from_text(
+				window_dimensions.x,
+				to_interface_coords(grid_top_left - cgmath::vec2(0.0, 70.0)),
+				settings,
+				&text,
+			);
+			meshes.add_simple_texture_vertices(vertices);
+
+			let mut settings = font::TextRenderingSettings::with_scale(2.0);
+			settings.color = [1.0, 1.0, 1.0];
+			let last_displayed_row = (self.first_displayed_row + displayed_row_count).min(row_count);
+			let text = localization.get_with_args(
+				"block_picker.summary",
+				&[
+					("count", &filtered_ids.len()),
+					(
+						"first",
+						&(self.first_displayed_row + 1).min(last_displayed_row),
+					),
+					("last", &last_displayed_row),
+					("total", &row_count),
+				],
+			);
+			let vertices = font.simple_texture_vertices_from_text(
+				window_dimensions.x,
+				to_interface_coords(grid_top_left - cgmath::vec2(0.0, 25.0)),
+				settings,
+				&text,
+			);
+			meshes.add_simple_texture_vertices(vertices);
+		}
+
+		// Grid frame.
+		meshes.add_simple_line_vertices(simple_line_vertices_for_rect(
+			to_interface_coords(grid_top_left),
+			cgmath::vec2(COLUMN_COUNT as f32, displayed_row_count as f32)
+				* CELL_SIZE_IN_PIXELS
+				* screen_pixel_size,
+			[1.0, 1.0, 1.0],
+		));
+
+		// Block icons.
+		let displayed_ids = filtered_ids
+			.iter()
+			.skip(self.first_displayed_row * COLUMN_COUNT)
+			.take(displayed_row_count * COLUMN_COUNT);
+		for (index, &id) in displayed_ids.enumerate() {
+			let column = index % COLUMN_COUNT;
+			let row = index / COLUMN_COUNT;
+			let icon_margin = (CELL_SIZE_IN_PIXELS - 16.0 * ICON_SCALE) / 2.0;
+			let icon_top_left = grid_top_left
+				+ cgmath::vec2(column as f32, row as f32) * CELL_SIZE_IN_PIXELS
+				+ cgmath::vec2(icon_margin, icon_margin);
+			add_block_icon_vertices(
+				meshes,
+				font,
+				block_type_table.get(id).unwrap(),
+				to_interface_coords(icon_top_left),
+				ICON_SCALE,
+				window_dimensions,
+			);
+		}
+
+		// Hovered cell highlight and tooltip with the block type name.
+		if let Some(hovered_id) = self.hovered_block_type(block_type_table, window_dimensions) {
+			let cursor_position = self.cursor_position.unwrap();
+			let in_grid = cursor_position - grid_top_left;
+			let cell_top_left = grid_top_left
+				+ (in_grid / CELL_SIZE_IN_PIXELS).map(|x| x.floor()) * CELL_SIZE_IN_PIXELS;
+			meshes.add_simple_line_vertices(simple_line_vertices_for_rect(
+				to_interface_coords(cell_top_left + cgmath::vec2(2.0, 2.0)),
+				cgmath::vec2(1.0, 1.0) * (CELL_SIZE_IN_PIXELS - 4.0) * screen_pixel_size,
+				[1.0, 1.0, 0.0],
+			));
+
+			let mut settings = font::TextRenderingSettings::with_scale(3.0);
+			settings.color = [1.0, 1.0, 0.0];
+			let name = block_type_table.name(hovered_id).unwrap();
+			let vertices = font.simple_texture_vertices_from_text(
+				window_dimensions.x,
+				to_interface_coords(cursor_position + cgmath::vec2(16.0, 16.0)),
+				settings,
+				name,
+			);
+			meshes.add_simple_texture_vertices(vertices);
+		}
+	}
+}
+
+/// Adds the icon of a block type (its texture, or some text for text blocks) with its top left
+/// corner at the given interface coords, textures are stretched by `icon_scale`.
+pub(crate) fn add_block_icon_vertices(
+	meshes: &mut InterfaceMeshesVertices,
+	font: &Font,
+	block_type: &BlockType,
+	top_left: cgmath::Point3<f32>,
+	icon_scale: f32,
+	window_dimensions: cgmath::Vector2<f32>,
+) {
+	let screen_pixel_size = 2.0 / window_dimensions.x;
+	match block_type {
+		BlockType::Text => {
+			// No texture for text blocks, some text will do.
+			let mut settings = font::TextRenderingSettings::with_scale(icon_scale);
+			settings.color = [1.0, 1.0, 1.0];
+			let vertices = font.simple_texture_vertices_from_text(
+				window_dimensions.x,
+				top_left + cgmath::vec3(8.0, -20.0, 0.0) * screen_pixel_size,
+				settings,
+				"Aa",
+			);
+			meshes.add_simple_texture_vertices(vertices);
+		},
+		block_type => {
+			let rect_in_atlas =
+				RectInAtlas::of_block_texture(block_type.texture_coords_on_atlas().unwrap());
+			let vertices = SimpleTextureMesh::vertices_for_rect(
+				top_left,
+				cgmath::vec2(16.0, 16.0) * icon_scale * screen_pixel_size,
+				rect_in_atlas.texture_rect_in_atlas_xy,
+				rect_in_atlas.texture_rect_in_atlas_wh,
+				[1.0, 1.0, 1.0],
+			);
+			meshes.add_simple_texture_vertices(vertices);
+		},
+	}
+}
+
+/// The block that the player gets when picking the given block type.
+pub(crate) fn picked_block(block_type_table: &BlockTypeTable, id: BlockTypeId) -> Block {
+	match block_type_table.get(id).unwrap() {
+		BlockType::Text => Block { type_id: id, data: Some(BlockData::Text("Jaaj".to_string())) },
+		BlockType::Layer { .. } => Block::new_layer(id, 1),
+		_ => Block::from(id),
+	}
+}
@@ -0,0 +1,82 @@
+//! The block types of a save are identified by their names (see `BlockTypeTable::save_names`),
+//! but the blocks in the chunks of the save refer to block types by their ids. When the current
+//! `BlockTypeTable` gives different ids to some of these block types (like after adding new
+//! builtin block types), the save is migrated on load: its chunks are rewritten with the current
+//! ids so that blocks keep being of the same block types.
+
+use std::sync::Arc;
+
+use crate::{
+	block_types::{BlockTypeId, BlockTypeTable},
+	chunk_blocks::{Block, BlockData, ChunkBlocks},
+	coords::{ChunkCoordsSpan, ChunkDimensions},
+	entities::ChunkEntities,
+	saves::{Save, WhichChunkFile},
+};
+
+/// Gives the current id of the block types from their id in a save.
+pub(crate) struct BlockTypeIdRemapping {
+	/// Indexed by the ids of the save.
+	current_ids: Vec<BlockTypeId>,
+}
+
+impl BlockTypeIdRemapping {
+	/// The remapping from the ids of the save (given by the names of its block types, in the order
+	/// of their ids) to the ids of the given table, `None` if the ids are all the same. Block types
+	/// that are not in the table anymore become air.
+	pub(crate) fn between(
+		saved_names: &[String],
+		block_type_table: &BlockTypeTable,
+	) -> Option<BlockTypeIdRemapping> {
+		let current_ids: Vec<BlockTypeId> = saved_names
+			.iter()
+			.map(|name| {
+				block_type_table.id_from_name(name).unwrap_or_else(|| {
+					log::warn!("Block type \"{name}\" of the save is unknown, its blocks become air");
+					block_type_table.air_id()
+				})
+			})
+			.collect();
+		let is_identity =
+			current_ids.iter().enumerate().all(|(saved_id, id)| saved_id as BlockTypeId == *id);
+		(!is_identity).then_some(BlockTypeIdRemapping { current_ids })
+	}
+
+	/// Gives the current id to the block (and to the blocks it contains, if any).
+	pub(crate) fn remap_block(&self, block: &mut Block) {
+		if let Some(current_id) = self.current_ids.get(block.type_id as usize) {
+			block.type_id = *current_id;
+		}
+		if let Some(BlockData::ChestContents(contents)) = &mut block.data {
+			for slot_block in contents.iter_mut().flatten() {
+				self.remap_block(slot_block);
+			}
+		}
+	}
+
+	/// Rewrites all the chunks (blocks and entities) of the save with the current ids.
+	pub(crate) fn migrate_save(&self, save: &Arc<Save>, cd: ChunkDimensions) {
+		let chunks_with_blocks = save.chunks_with_a_file(WhichChunkFile::Blocks);
+		let chunks_with_entities = save.chunks_with_a_file(WhichChunkFile::Entities);
+		log::info!(
+			"Block type ids changed since the save was written, migrating {} chunks",
+			chunks_with_blocks.len().max(chunks_with_entities.len())
+		);
+		for chunk_coords in chunks_with_blocks {
+			let coords_span = ChunkCoordsSpan { cd, chunk_coords };
+			if let Some(mut chunk_blocks) = ChunkBlocks::load_from_save(coords_span, save) {
+				chunk_blocks.remap_block_type_ids(self);
+				chunk_blocks.save(save);
+			}
+		}
+		for chunk_coords in chunks_with_entities {
+			let coords_span = ChunkCoordsSpan { cd, chunk_coords };
+			if let Some(mut chunk_entities) =
+				ChunkEntities::load_from_save_while_removing_the_save(coords_span, save)
+			{
+				chunk_entities.remap_block_type_ids(self);
+				chunk_entities.save(save);
+			}
+		}
+	}
+}
@@ -1,12 +1,76 @@
-use crate::atlas::ATLAS_DIMS;
+use std::sync::Arc;
+
+use crate::{
+	atlas::ATLAS_DIMS,
+	block_definitions::{BlockDefinition, DefinedShape},
+	deferred_lighting::LightEmission,
+	saves::Save,
+	shaders::block::{MATERIAL_FLAG_FOLIAGE, MATERIAL_FLAG_WATER_SURFACE},
+};
 
 pub(crate) enum BlockType {
 	Air,
-	Solid { texture_coords_on_atlas: cgmath::Point2<i32> },
-	XShaped { texture_coords_on_atlas: cgmath::Point2<i32> },
+	Solid {
+		texture_coords_on_atlas: cgmath::Point2<i32>,
+	},
+	XShaped {
+		texture_coords_on_atlas: cgmath::Point2<i32>,
+	},
 	Text,
+	/// Not solid, a small decoration (like a mushroom) that is a sprite that turns to always
+	/// face the player (see `MATERIAL_FLAG_BILLBOARD`).
+	Billboard {
+		texture_coords_on_atlas: cgmath::Point2<i32>,
+	},
+	/// Not solid, it can be walked through. Only its faces that touch something else than the same
+	/// liquid or opaque blocks are visible.
+	Liquid {
+		texture_coords_on_atlas: cgmath::Point2<i32>,
+	},
+	/// Not opaque, a thin layer (like snow or fallen leaves) on top of what is below it.
+	/// Layers stack up in the same block (see `BlockData::LayerCount`), up to `MAX_LAYER_COUNT`
+	/// layers that fill the whole block.
+	Layer {
+		texture_coords_on_atlas: cgmath::Point2<i32>,
+	},
+	/// Not opaque, a thin vertical panel against a side of the block that can be opened
+	/// (it then turns to be against the next side) and closed, see `BlockData::DoorState`.
+	Door {
+		texture_coords_on_atlas: cgmath::Point2<i32>,
+	},
+	/// Not opaque, a thin panel on the bottom of the block that can be opened (it then stands
+	/// against a side of the block) and closed, see `BlockData::DoorState`.
+	Trapdoor {
+		texture_coords_on_atlas: cgmath::Point2<i32>,
+	},
+	/// A tool that makes breaking some blocks faster (see `Hardness`), it is meant to be equipped
+	/// by the player but it can also lie around in the world (not solid, meshed like an X-shaped
+	/// decoration). Its uses left are in `BlockData::ToolDurability`.
+	Tool {
+		kind: ToolKind,
+		/// The tier of the tool, 1 for wooden tools and 2 for stone tools.
+		tier: u8,
+		texture_coords_on_atlas: cgmath::Point2<i32>,
+	},
+}
+
+/// The kinds of tools, each is good at breaking a category of block types (see `Hardness`).
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ToolKind {
+	Pickaxe,
+	Shovel,
+	Axe,
 }
 
+/// The number of layers that fill a whole block, see `BlockType::Layer`.
+pub(crate) const MAX_LAYER_COUNT: u8 = 8;
+
+/// The thickness (in blocks) of the panels of doors and trapdoors.
+pub(crate) const DOOR_PANEL_THICKNESS: f32 = 3.0 / 16.0;
+
+/// The number of slots of a chest, see `BlockData::ChestContents`.
+pub(crate) const CHEST_SLOT_COUNT: usize = 18;
+
 impl BlockType {
 	pub(crate) fn is_opaque(&self) -> bool {
 		matches!(self, BlockType::Solid { .. })
@@ -16,18 +80,174 @@ impl BlockType {
 		matches!(self, BlockType::Air)
 	}
 
+	/// Doors and trapdoors, that can be opened and closed.
+	pub(crate) fn is_door(&self) -> bool {
+		matches!(self, BlockType::Door { .. } | BlockType::Trapdoor { .. })
+	}
+
+	/// The kind and tier of the tool, if this is a tool.
+	pub(crate) fn tool(&self) -> Option<(ToolKind, u8)> {
+		match self {
+			BlockType::Tool { kind, tier, .. } => Some((*kind, *tier)),
+			_ => None,
+		}
+	}
+
+	/// The number of blocks a new tool of this type can break before breaking itself.
+	pub(crate) fn tool_max_durability(&self) -> Option<u32> {
+		self.tool().map(|(_kind, tier)| if tier <= 1 { 60 } else { 130 })
+	}
+
+	pub(crate) fn targetability(&self) -> Targetability {
+		match self {
+			BlockType::Air => Targetability::NotTargetable,
+			BlockType::XShaped { .. } | BlockType::Billboard { .. } | BlockType::Tool { .. } => {
+				Targetability::Decoration
+			},
+			BlockType::Solid { .. }
+			| BlockType::Text
+			| BlockType::Liquid { .. }
+			| BlockType::Layer { .. }
+			| BlockType::Door { .. }
+			| BlockType::Trapdoor { .. } => Targetability::Targetable,
+		}
+	}
+
 	pub(crate) fn texture_coords_on_atlas(&self) -> Option<cgmath::Point2<i32>> {
 		match self {
 			BlockType::Solid { texture_coords_on_atlas } => Some(*texture_coords_on_atlas),
 			BlockType::XShaped { texture_coords_on_atlas } => Some(*texture_coords_on_atlas),
+			BlockType::Billboard { texture_coords_on_atlas } => Some(*texture_coords_on_atlas),
+			BlockType::Liquid { texture_coords_on_atlas } => Some(*texture_coords_on_atlas),
+			BlockType::Layer { texture_coords_on_atlas } => Some(*texture_coords_on_atlas),
+			BlockType::Door { texture_coords_on_atlas } => Some(*texture_coords_on_atlas),
+			BlockType::Trapdoor { texture_coords_on_atlas } => Some(*texture_coords_on_atlas),
+			BlockType::Tool { texture_coords_on_atlas, .. } => Some(*texture_coords_on_atlas),
 			BlockType::Air => None,
 			BlockType::Text => None,
 		}
 	}
 }
 
+/// How the targeting of blocks by the player (to break them or place blocks against them)
+/// treats a block type.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Targetability {
+	/// Never targeted, the targeting goes through it.
+	NotTargetable,
+	/// Non-colliding decorations (like grass blades), targeted unless the player asks to target
+	/// through them (to get to the block behind).
+	Decoration,
+	/// Always targeted.
+	Targetable,
+}
+
+/// How blocks of a type treat the boxes that walk on them (see `AlignedPhysBox`).
+#[derive(Clone, Copy)]
+pub(crate) struct GroundResponse {
+	/// Grip of the ground, 1.0 for most blocks. Less than that is slippery (like ice), walking then
+	/// takes some time to get up to speed or to stop, and pushed boxes slide further.
+	pub(crate) friction: f32,
+	/// The walking speed is multiplied by this, less than 1.0 slows walking down (like sand).
+	pub(crate) speed_factor: f32,
+}
+
+impl GroundResponse {
+	pub(crate) const DEFAULT: GroundResponse = GroundResponse { friction: 1.0, speed_factor: 1.0 };
+}
+
+/// How blocks of a type resist being broken by the player.
+#[derive(Clone, Copy)]
+pub(crate) struct Hardness {
+	/// Time (in seconds) it takes to break a block of this type by hand, zero breaks it at once.
+	pub(crate) breaking_time: f32,
+	/// The kind of tool that is good at breaking blocks of this type (the category of the block
+	/// type), a tool of this kind breaks them faster the higher its tier is.
+	pub(crate) tool_kind: Option<ToolKind>,
+	/// The tier that a tool of `tool_kind` must at least have for a broken block of this type to be
+	/// harvested (given to the player), 0 when it can be harvested even by hand. Blocks broken
+	/// without being harvested are lost, and they take longer to break.
+	pub(crate) required_tier: u8,
+}
+
+impl Hardness {
+	pub(crate) const DEFAULT: Hardness =
+		Hardness { breaking_time: 0.3, tool_kind: None, required_tier: 0 };
+
+	const fn new(breaking_time: f32, tool_kind: ToolKind, required_tier: u8) -> Hardness {
+		Hardness { breaking_time, tool_kind: Some(tool_kind), required_tier }
+	}
+
+	/// Breaks at once, by hand or with any tool.
+	const INSTANT: Hardness = Hardness { breaking_time: 0.0, tool_kind: None, required_tier: 0 };
+
+	/// Is a block of this type given to the player when broken with the given tool (or by hand)?
+	pub(crate) fn is_harvested_with(&self, tool: Option<(ToolKind, u8)>) -> bool {
+		let tier = match tool {
+			Some((kind, tier)) if Some(kind) == self.tool_kind => tier,
+			_ => 0,
+		};
+		tier >= self.required_tier
+	}
+
+	/// Time (in seconds) it takes to break a block of this type with the given tool (or by hand).
+	pub(crate) fn breaking_time_with(&self, tool: Option<(ToolKind, u8)>) -> f32 {
+		let speed = match tool {
+			Some((kind, tier)) if Some(kind) == self.tool_kind => 2.0 * tier as f32,
+			_ => 1.0,
+		};
+		let penalty = if self.is_harvested_with(tool) {
+			1.0
+		} else {
+			3.0
+		};
+		self.breaking_time * penalty / speed
+	}
+
+	/// Does breaking a block of this type with the given tool wear the tool?
+	pub(crate) fn wears(&self, tool: (ToolKind, u8)) -> bool {
+		self.breaking_time > 0.0 && Some(tool.0) == self.tool_kind
+	}
+}
+
 pub(crate) struct BlockTypeTable {
 	block_types: Vec<BlockType>,
+	/// Human-readable names of the block types, indexed the same way as `block_types`.
+	names: Vec<String>,
+	/// The light emitted by the block types (if any), indexed the same way as `block_types`.
+	light_emissions: Vec<Option<LightEmission>>,
+	/// How the block types treat the boxes that walk on them, indexed the same way as `block_types`.
+	ground_responses: Vec<GroundResponse>,
+	/// How the block types resist being broken, indexed the same way as `block_types`.
+	hardnesses: Vec<Hardness>,
+	/// Can the block types catch fire and burn away (see `fire`), indexed the same way
+	/// as `block_types`.
+	flammables: Vec<bool>,
+	/// The number of builtin block types, the block types with an id from there are defined
+	/// by the block types file (see `block_definitions`).
+	builtin_count: usize,
+	/// These are added after the generated test block types (so that adding them did not change
+	/// the ids of the existing block types).
+	water_id: BlockTypeId,
+	kinda_red_flower_id: BlockTypeId,
+	kinda_yellow_flower_id: BlockTypeId,
+	kinda_mushroom_id: BlockTypeId,
+	kinda_snow_layer_id: BlockTypeId,
+	kinda_fallen_leaves_id: BlockTypeId,
+	kinda_ice_id: BlockTypeId,
+	kinda_sand_id: BlockTypeId,
+	kinda_pressure_plate_id: BlockTypeId,
+	kinda_door_id: BlockTypeId,
+	kinda_trapdoor_id: BlockTypeId,
+	kinda_chest_id: BlockTypeId,
+	kinda_dirt_id: BlockTypeId,
+	kinda_fire_id: BlockTypeId,
+	kinda_signal_source_id: BlockTypeId,
+	kinda_signal_wire_id: BlockTypeId,
+	kinda_signal_lamp_id: BlockTypeId,
+	kinda_signal_lamp_lit_id: BlockTypeId,
+	kinda_piston_id: BlockTypeId,
+	kinda_piston_head_id: BlockTypeId,
 }
 
 impl BlockTypeTable {
@@ -41,21 +261,365 @@ impl BlockTypeTable {
 			BlockType::Solid { texture_coords_on_atlas: (64, 0).into() },
 			BlockType::Text,
 		];
+		let mut names: Vec<String> = [
+			"air",
+			"ground",
+			"kinda_grass",
+			"kinda_grass_blades",
+			"kinda_wood",
+			"kinda_leaf",
+			"text",
+		]
+		.into_iter()
+		.map(|name| name.to_string())
+		.collect();
 
 		for y in 4..(ATLAS_DIMS.1 / 16) {
 			for x in 0..(ATLAS_DIMS.0 / 16) {
 				let coords = (x as i32 * 16, y as i32 * 16);
 				block_types.push(BlockType::Solid { texture_coords_on_atlas: coords.into() });
+				names.push(format!("generated_test_{}", names.len() - 7));
 			}
 		}
 
-		BlockTypeTable { block_types }
+		let water_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Liquid { texture_coords_on_atlas: (80, 0).into() });
+		names.push("water".to_string());
+
+		let lamp_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Solid { texture_coords_on_atlas: (96, 0).into() });
+		names.push("kinda_lamp".to_string());
+
+		let lava_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Solid { texture_coords_on_atlas: (112, 0).into() });
+		names.push("kinda_lava".to_string());
+
+		let crystal_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Solid { texture_coords_on_atlas: (128, 0).into() });
+		names.push("kinda_crystal".to_string());
+
+		// Decorations that world generators scatter on the grass.
+		let kinda_red_flower_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::XShaped { texture_coords_on_atlas: (144, 0).into() });
+		names.push("kinda_red_flower".to_string());
+		let kinda_yellow_flower_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::XShaped { texture_coords_on_atlas: (160, 0).into() });
+		names.push("kinda_yellow_flower".to_string());
+		let kinda_mushroom_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Billboard { texture_coords_on_atlas: (176, 0).into() });
+		names.push("kinda_mushroom".to_string());
+
+		// Layers that pile up on exposed surfaces (see `surface_layers`).
+		let kinda_snow_layer_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Layer { texture_coords_on_atlas: (192, 0).into() });
+		names.push("kinda_snow_layer".to_string());
+		let kinda_fallen_leaves_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Layer { texture_coords_on_atlas: (208, 0).into() });
+		names.push("kinda_fallen_leaves".to_string());
+
+		// Grounds that are not walked on like the others (see `GroundResponse`).
+		let kinda_ice_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Solid { texture_coords_on_atlas: (224, 0).into() });
+		names.push("kinda_ice".to_string());
+		let kinda_sand_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Solid { texture_coords_on_atlas: (240, 0).into() });
+		names.push("kinda_sand".to_string());
+
+		// Fires events when stood on (see `triggers`).
+		let kinda_pressure_plate_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Solid { texture_coords_on_atlas: (256, 0).into() });
+		names.push("kinda_pressure_plate".to_string());
+
+		// Can be opened and closed by the player.
+		let kinda_door_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Door { texture_coords_on_atlas: (272, 0).into() });
+		names.push("kinda_door".to_string());
+		let kinda_trapdoor_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Trapdoor { texture_coords_on_atlas: (288, 0).into() });
+		names.push("kinda_trapdoor".to_string());
+
+		// Holds blocks in its slots (see `chest_screen`).
+		let kinda_chest_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Solid { texture_coords_on_atlas: (304, 0).into() });
+		names.push("kinda_chest".to_string());
+
+		// Equipped by the player to break blocks faster (see `Hardness`).
+		let tools = [
+			("kinda_wooden_pickaxe", ToolKind::Pickaxe, 1),
+			("kinda_wooden_shovel", ToolKind::Shovel, 1),
+			("kinda_wooden_axe", ToolKind::Axe, 1),
+			("kinda_stone_pickaxe", ToolKind::Pickaxe, 2),
+			("kinda_stone_shovel", ToolKind::Shovel, 2),
+			("kinda_stone_axe", ToolKind::Axe, 2),
+		];
+		for (i, (name, kind, tier)) in tools.into_iter().enumerate() {
+			let texture_coords_on_atlas = (320 + 16 * i as i32, 0).into();
+			block_types.push(BlockType::Tool { kind, tier, texture_coords_on_atlas });
+			names.push(name.to_string());
+		}
+
+		// Between the grass and the ground (see the heightmap world generator).
+		let kinda_dirt_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Solid { texture_coords_on_atlas: (416, 0).into() });
+		names.push("kinda_dirt".to_string());
+
+		// Spreads to and burns flammable blocks (see `fire`).
+		let kinda_fire_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::XShaped { texture_coords_on_atlas: (432, 0).into() });
+		names.push("kinda_fire".to_string());
+
+		// Power, carry and show signals (see `signals`).
+		let kinda_signal_source_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Solid { texture_coords_on_atlas: (448, 0).into() });
+		names.push("kinda_signal_source".to_string());
+		let kinda_signal_wire_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Layer { texture_coords_on_atlas: (464, 0).into() });
+		names.push("kinda_signal_wire".to_string());
+		let kinda_signal_lamp_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Solid { texture_coords_on_atlas: (480, 0).into() });
+		names.push("kinda_signal_lamp".to_string());
+		let kinda_signal_lamp_lit_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Solid { texture_coords_on_atlas: (96, 0).into() });
+		names.push("kinda_signal_lamp_lit".to_string());
+
+		// Push blocks when powered by signals (see `pistons`), their textures are on the third row
+		// of the atlas since the first row is full.
+		let kinda_piston_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Solid { texture_coords_on_atlas: (320, 32).into() });
+		names.push("kinda_piston".to_string());
+		let kinda_piston_head_id = block_types.len() as BlockTypeId;
+		block_types.push(BlockType::Solid { texture_coords_on_atlas: (336, 32).into() });
+		names.push("kinda_piston_head".to_string());
+
+		let mut light_emissions = vec![None; block_types.len()];
+		light_emissions[lamp_id as usize] =
+			Some(LightEmission { color: [1.0, 0.75, 0.4], radius: 10.0 });
+		light_emissions[lava_id as usize] =
+			Some(LightEmission { color: [1.0, 0.45, 0.1], radius: 12.0 });
+		light_emissions[crystal_id as usize] =
+			Some(LightEmission { color: [0.3, 0.5, 1.0], radius: 9.0 });
+		light_emissions[kinda_fire_id as usize] =
+			Some(LightEmission { color: [1.0, 0.55, 0.15], radius: 9.0 });
+		light_emissions[kinda_signal_lamp_lit_id as usize] =
+			Some(LightEmission { color: [1.0, 0.75, 0.4], radius: 10.0 });
+
+		let mut ground_responses = vec![GroundResponse::DEFAULT; block_types.len()];
+		ground_responses[kinda_ice_id as usize] =
+			GroundResponse { friction: 0.02, speed_factor: 1.0 };
+		ground_responses[kinda_sand_id as usize] =
+			GroundResponse { friction: 1.0, speed_factor: 0.6 };
+
+		let mut hardnesses = vec![Hardness::DEFAULT; block_types.len()];
+		// The first block types have fixed ids (see `ground_id` and the getters below it).
+		let (ground_id, kinda_grass_id, kinda_wood_id) = (1, 2, 4);
+		for id in [ground_id, kinda_grass_id, kinda_sand_id, kinda_dirt_id] {
+			hardnesses[id as usize] = Hardness::new(0.6, ToolKind::Shovel, 0);
+		}
+		for id in [
+			kinda_snow_layer_id,
+			kinda_fallen_leaves_id,
+			kinda_signal_wire_id,
+		] {
+			hardnesses[id as usize] = Hardness::new(0.2, ToolKind::Shovel, 0);
+		}
+		for id in [
+			kinda_wood_id,
+			kinda_door_id,
+			kinda_trapdoor_id,
+			kinda_chest_id,
+			kinda_piston_id,
+			kinda_piston_head_id,
+		] {
+			hardnesses[id as usize] = Hardness::new(1.2, ToolKind::Axe, 0);
+		}
+		for id in [
+			lamp_id,
+			kinda_pressure_plate_id,
+			kinda_signal_source_id,
+			kinda_signal_lamp_id,
+			kinda_signal_lamp_lit_id,
+		] {
+			hardnesses[id as usize] = Hardness::new(0.8, ToolKind::Pickaxe, 0);
+		}
+		hardnesses[kinda_ice_id as usize] = Hardness::new(0.8, ToolKind::Pickaxe, 1);
+		hardnesses[lava_id as usize] = Hardness::new(1.5, ToolKind::Pickaxe, 1);
+		hardnesses[crystal_id as usize] = Hardness::new(2.0, ToolKind::Pickaxe, 2);
+		for (id, block_type) in block_types.iter().enumerate() {
+			let is_instant = matches!(
+				block_type,
+				BlockType::XShaped { .. }
+					| BlockType::Billboard { .. }
+					| BlockType::Text
+					| BlockType::Liquid { .. }
+					| BlockType::Tool { .. }
+			);
+			if is_instant {
+				hardnesses[id] = Hardness::INSTANT;
+			} else if names[id].starts_with("generated_test_") {
+				hardnesses[id] = Hardness::new(1.0, ToolKind::Pickaxe, 0);
+			}
+		}
+
+		let mut flammables = vec![false; block_types.len()];
+		let (kinda_grass_blades_id, kinda_leaf_id) = (3, 5);
+		for id in [
+			kinda_grass_blades_id,
+			kinda_wood_id,
+			kinda_leaf_id,
+			kinda_red_flower_id,
+			kinda_yellow_flower_id,
+			kinda_fallen_leaves_id,
+			kinda_door_id,
+			kinda_trapdoor_id,
+		] {
+			flammables[id as usize] = true;
+		}
+
+		BlockTypeTable {
+			block_types,
+			names,
+			light_emissions,
+			ground_responses,
+			builtin_count: hardnesses.len(),
+			hardnesses,
+			flammables,
+			water_id,
+			kinda_red_flower_id,
+			kinda_yellow_flower_id,
+			kinda_mushroom_id,
+			kinda_snow_layer_id,
+			kinda_fallen_leaves_id,
+			kinda_ice_id,
+			kinda_sand_id,
+			kinda_pressure_plate_id,
+			kinda_door_id,
+			kinda_trapdoor_id,
+			kinda_chest_id,
+			kinda_dirt_id,
+			kinda_fire_id,
+			kinda_signal_source_id,
+			kinda_signal_wire_id,
+			kinda_signal_lamp_id,
+			kinda_signal_lamp_lit_id,
+			kinda_piston_id,
+			kinda_piston_head_id,
+		}
+	}
+
+	/// Adds the block types defined by the block types file. The ids of the defined block types
+	/// are kept the same as in the save (given by the names of the block types of the save, if any),
+	/// even when some of them are removed from the file: these become placeholders (solid blocks
+	/// with the missing texture) that keep their names and ids, so that their blocks are not turned
+	/// into other block types (and are back if their definitions are back).
+	pub(crate) fn add_defined_block_types(
+		&mut self,
+		definitions: &[BlockDefinition],
+		saved_names: Option<&[String]>,
+	) {
+		let builtin_names = &self.names[..self.builtin_count];
+		let mut names_in_order: Vec<&str> = saved_names
+			.unwrap_or_default()
+			.iter()
+			.map(|name| name.as_str())
+			.filter(|name| !builtin_names.iter().any(|builtin_name| builtin_name == name))
+			.collect();
+		for definition in definitions {
+			if !names_in_order.contains(&definition.name.as_str()) {
+				names_in_order.push(&definition.name);
+			}
+		}
+
+		let names_in_order: Vec<String> = names_in_order.into_iter().map(String::from).collect();
+		for (index, name) in names_in_order.into_iter().enumerate() {
+			if index >= BlockTypeTable::MAX_DEFINED_COUNT {
+				log::error!("Too many defined block types, \"{name}\" and the next ones are ignored");
+				break;
+			}
+			let texture_coords_on_atlas = BlockTypeTable::defined_texture_coords_on_atlas(index);
+			let definition = definitions.iter().find(|definition| definition.name == name);
+			let Some(definition) = definition else {
+				log::warn!("Block type \"{name}\" is no longer defined, it is kept as a placeholder");
+				self.block_types.push(BlockType::Solid { texture_coords_on_atlas });
+				self.names.push(name);
+				self.light_emissions.push(None);
+				self.ground_responses.push(GroundResponse::DEFAULT);
+				self.hardnesses.push(Hardness::DEFAULT);
+				self.flammables.push(false);
+				continue;
+			};
+			self.block_types.push(match definition.shape {
+				DefinedShape::Solid => BlockType::Solid { texture_coords_on_atlas },
+				DefinedShape::XShaped => BlockType::XShaped { texture_coords_on_atlas },
+				DefinedShape::Billboard => BlockType::Billboard { texture_coords_on_atlas },
+				DefinedShape::Liquid => BlockType::Liquid { texture_coords_on_atlas },
+				DefinedShape::Layer => BlockType::Layer { texture_coords_on_atlas },
+			});
+			self.names.push(name);
+			self.light_emissions.push(definition.light_emission);
+			self.ground_responses.push(definition.ground_response);
+			self.hardnesses.push(definition.hardness);
+			self.flammables.push(definition.flammable);
+		}
+	}
+
+	/// The defined block types have their textures on the second row of the atlas.
+	const MAX_DEFINED_COUNT: usize = ATLAS_DIMS.0 / 16;
+
+	/// Where the texture of the defined block type of the given index (in the order of their ids)
+	/// is on the atlas.
+	fn defined_texture_coords_on_atlas(index: usize) -> cgmath::Point2<i32> {
+		(index as i32 * 16, 16).into()
+	}
+
+	/// The defined block types (see `add_defined_block_types`) with their definitions (if they are
+	/// not placeholders for block types that are no longer defined).
+	pub(crate) fn iter_defined_block_types<'a>(
+		&'a self,
+		definitions: &'a [BlockDefinition],
+	) -> impl Iterator<Item = (BlockTypeId, &'a BlockDefinition)> + 'a {
+		(self.builtin_count..self.block_types.len()).filter_map(|id| {
+			let definition =
+				definitions.iter().find(|definition| definition.name == self.names[id])?;
+			Some((id as BlockTypeId, definition))
+		})
+	}
+
+	/// Reads the names of the block types of the save, in the order of their ids (if the save has
+	/// them, older saves do not).
+	pub(crate) fn load_names_from_save(save: &Arc<Save>) -> Option<Vec<String>> {
+		let names_string = std::fs::read_to_string(&save.block_type_names_file_path).ok()?;
+		Some(names_string.lines().map(|name| name.to_string()).collect())
+	}
+
+	/// A hash of the names of the block types in the order of their ids, tables with the same
+	/// fingerprint give the same ids to the same block types (so that data holding raw block type
+	/// ids, like the chunk cache, can tell whether it is still valid).
+	pub(crate) fn fingerprint(&self) -> u64 {
+		fxhash::hash64(&self.names)
+	}
+
+	pub(crate) fn save_names(&self, save: &Arc<Save>) {
+		let names_string: String = self.names.iter().map(|name| format!("{name}\n")).collect();
+		std::fs::write(&save.block_type_names_file_path, names_string).unwrap();
 	}
 
 	pub(crate) fn get(&self, id: BlockTypeId) -> Option<&BlockType> {
 		self.block_types.get(id as usize)
 	}
 
+	pub(crate) fn name(&self, id: BlockTypeId) -> Option<&str> {
+		self.names.get(id as usize).map(|name| name.as_str())
+	}
+
+	pub(crate) fn id_from_name(&self, name: &str) -> Option<BlockTypeId> {
+		self.names.iter().position(|other_name| other_name == name).map(|id| id as BlockTypeId)
+	}
+
+	/// Iterates over all the block type ids of the table, in order.
+	pub(crate) fn iter_ids(&self) -> impl Iterator<Item = BlockTypeId> {
+		0..(self.block_types.len() as BlockTypeId)
+	}
+
 	pub(crate) const AIR_ID: BlockTypeId = 0;
 
 	pub(crate) fn air_id(&self) -> BlockTypeId {
@@ -86,6 +650,125 @@ impl BlockTypeTable {
 		6
 	}
 
+	pub(crate) fn water_id(&self) -> BlockTypeId {
+		self.water_id
+	}
+
+	pub(crate) fn kinda_red_flower_id(&self) -> BlockTypeId {
+		self.kinda_red_flower_id
+	}
+
+	pub(crate) fn kinda_yellow_flower_id(&self) -> BlockTypeId {
+		self.kinda_yellow_flower_id
+	}
+
+	pub(crate) fn kinda_mushroom_id(&self) -> BlockTypeId {
+		self.kinda_mushroom_id
+	}
+
+	pub(crate) fn kinda_snow_layer_id(&self) -> BlockTypeId {
+		self.kinda_snow_layer_id
+	}
+
+	pub(crate) fn kinda_fallen_leaves_id(&self) -> BlockTypeId {
+		self.kinda_fallen_leaves_id
+	}
+
+	pub(crate) fn kinda_ice_id(&self) -> BlockTypeId {
+		self.kinda_ice_id
+	}
+
+	pub(crate) fn kinda_sand_id(&self) -> BlockTypeId {
+		self.kinda_sand_id
+	}
+
+	pub(crate) fn kinda_pressure_plate_id(&self) -> BlockTypeId {
+		self.kinda_pressure_plate_id
+	}
+
+	pub(crate) fn kinda_door_id(&self) -> BlockTypeId {
+		self.kinda_door_id
+	}
+
+	pub(crate) fn kinda_trapdoor_id(&self) -> BlockTypeId {
+		self.kinda_trapdoor_id
+	}
+
+	pub(crate) fn kinda_chest_id(&self) -> BlockTypeId {
+		self.kinda_chest_id
+	}
+
+	pub(crate) fn kinda_dirt_id(&self) -> BlockTypeId {
+		self.kinda_dirt_id
+	}
+
+	pub(crate) fn kinda_fire_id(&self) -> BlockTypeId {
+		self.kinda_fire_id
+	}
+
+	pub(crate) fn kinda_signal_source_id(&self) -> BlockTypeId {
+		self.kinda_signal_source_id
+	}
+
+	pub(crate) fn kinda_signal_wire_id(&self) -> BlockTypeId {
+		self.kinda_signal_wire_id
+	}
+
+	pub(crate) fn kinda_signal_lamp_id(&self) -> BlockTypeId {
+		self.kinda_signal_lamp_id
+	}
+
+	pub(crate) fn kinda_signal_lamp_lit_id(&self) -> BlockTypeId {
+		self.kinda_signal_lamp_lit_id
+	}
+
+	pub(crate) fn kinda_piston_id(&self) -> BlockTypeId {
+		self.kinda_piston_id
+	}
+
+	pub(crate) fn kinda_piston_head_id(&self) -> BlockTypeId {
+		self.kinda_piston_head_id
+	}
+
+	/// The light emitted by blocks of the given type, if any. It spreads around as block light
+	/// (see `block_light`), and the deferred rendering path also makes point lights out of it.
+	pub(crate) fn light_emission(&self, id: BlockTypeId) -> Option<LightEmission> {
+		self.light_emissions.get(id as usize).copied().flatten()
+	}
+
+	/// How blocks of the given type treat the boxes that walk on them.
+	pub(crate) fn ground_response(&self, id: BlockTypeId) -> GroundResponse {
+		self.ground_responses.get(id as usize).copied().unwrap_or(GroundResponse::DEFAULT)
+	}
+
+	/// Can blocks of the given type catch fire and burn away (see `fire`)?
+	pub(crate) fn is_flammable(&self, id: BlockTypeId) -> bool {
+		self.flammables.get(id as usize).copied().unwrap_or(false)
+	}
+
+	/// How blocks of the given type resist being broken by the player.
+	pub(crate) fn hardness(&self, id: BlockTypeId) -> Hardness {
+		self.hardnesses.get(id as usize).copied().unwrap_or(Hardness::DEFAULT)
+	}
+
+	/// Tells the block shader how to animate the vertices of blocks of the given type
+	/// (see `BlockVertexPod::material_flags`). For some flags, only some of the vertices of a block
+	/// should get them (like the top vertices of a water block with nothing above), the flags
+	/// returned here are the ones that a vertex could have.
+	pub(crate) fn material_flags(&self, id: BlockTypeId) -> u32 {
+		let is_foliage = id == self.kinda_leaf_id()
+			|| id == self.kinda_grass_blades_id()
+			|| id == self.kinda_red_flower_id()
+			|| id == self.kinda_yellow_flower_id();
+		if is_foliage {
+			MATERIAL_FLAG_FOLIAGE
+		} else if id == self.water_id() {
+			MATERIAL_FLAG_WATER_SURFACE
+		} else {
+			0
+		}
+	}
+
 	pub(crate) fn generated_test_id(&self, index: usize) -> BlockTypeId {
 		let id: BlockTypeId = (index + 7).try_into().unwrap();
 		id
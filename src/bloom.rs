@@ -0,0 +1,238 @@
+//! Bloom, what is bright in the scene (the glowing parts of the blocks, the sun) glows a bit
+//! around it.
+//!
+//! After the scene is rendered, what is brighter than the threshold is extracted to a texture of
+//! half the resolution (the bright pass), that is blurred (horizontally then vertically, as the
+//! gaussian blur is separable) and added back to the scene (the composite) before it is scaled
+//! to the window (see `RenderScaling`). The bloom needs the scene to be rendered to an offscreen
+//! texture, so it is not free even when nothing glows. An intensity of 0 disables it.
+
+use wgpu::util::DeviceExt;
+
+use crate::{
+	render_stats::RenderStats,
+	shaders::bloom::{
+		render_pipelines_and_bind_group_layout, BloomRenderPipelines, BloomSettingsPod, BLOOM_FORMAT,
+	},
+};
+
+/// The textures that the glow is extracted to and blurred in, they have half the size of the scene.
+struct BloomTargets {
+	/// Gets the bright pass, then the result of the vertical blur.
+	view_a: wgpu::TextureView,
+	/// Gets the result of the horizontal blur.
+	view_b: wgpu::TextureView,
+	scene_bind_group: wgpu::BindGroup,
+	a_bind_group: wgpu::BindGroup,
+	b_bind_group: wgpu::BindGroup,
+}
+
+pub(crate) struct Bloom {
+	/// From 0.0 to 1.0, the brightness above which the scene glows.
+	threshold: f32,
+	/// How much of the glow is added to the scene, 0.0 disables the bloom.
+	intensity: f32,
+	render_pipelines: BloomRenderPipelines,
+	bind_group_layout: wgpu::BindGroupLayout,
+	sampler: wgpu::Sampler,
+	settings_buffer: wgpu::Buffer,
+	/// Only there when the bloom is enabled.
+	targets: Option<BloomTargets>,
+}
+
+impl Bloom {
+	/// `resize` must be called before the bloom can be rendered.
+	pub(crate) fn new(
+		device: &wgpu::Device,
+		output_format: wgpu::TextureFormat,
+		threshold: f32,
+		intensity: f32,
+	) -> Bloom {
+		let (render_pipelines, bind_group_layout) =
+			render_pipelines_and_bind_group_layout(device, output_format);
+		let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+			label: Some("Bloom Sampler"),
+			address_mode_u: wgpu::AddressMode::ClampToEdge,
+			address_mode_v: wgpu::AddressMode::ClampToEdge,
+			address_mode_w: wgpu::AddressMode::ClampToEdge,
+			mag_filter: wgpu::FilterMode::Linear,
+			min_filter: wgpu::FilterMode::Linear,
+			mipmap_filter: wgpu::FilterMode::Nearest,
+			..Default::default()
+		});
+		let settings_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("Bloom Settings Buffer"),
+			contents: bytemuck::cast_slice(&[BloomSettingsPod {
+				threshold: 0.0,
+				intensity: 0.0,
+				_padding: [0.0; 2],
+			}]),
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+		});
+		let mut bloom = Bloom {
+			threshold: 0.0,
+			intensity: 0.0,
+			render_pipelines,
+			bind_group_layout,
+			sampler,
+			settings_buffer,
+			targets: None,
+		};
+		bloom.set_settings(threshold, intensity);
+		bloom
+	}
+
+	pub(crate) fn is_enabled(&self) -> bool {
+		self.intensity > 0.0
+	}
+
+	pub(crate) fn threshold(&self) -> f32 {
+		self.threshold
+	}
+
+	pub(crate) fn intensity(&self) -> f32 {
+		self.intensity
+	}
+
+	/// The threshold is clamped to the range from 0.0 to 1.0. If the bloom gets enabled or disabled
+	/// by that, the textures that have the scaled size must be recreated after that (the scene must
+	/// or need not be rendered to an offscreen texture anymore).
+	pub(crate) fn set_settings(&mut self, threshold: f32, intensity: f32) {
+		self.threshold = threshold.clamp(0.0, 1.0);
+		self.intensity = intensity.max(0.0);
+	}
+
+	/// The bloom targets must have half the size of the scene, and the bloom samples the scene
+	/// (that must then be rendered to the given offscreen texture).
+	pub(crate) fn resize(
+		&mut self,
+		device: &wgpu::Device,
+		scene_view: Option<&wgpu::TextureView>,
+		scene_width: u32,
+		scene_height: u32,
+	) {
+		let Some(scene_view) = scene_view.filter(|_| self.is_enabled()) else {
+			self.targets = None;
+			return;
+		};
+		let (width, height) = ((scene_width / 2).max(1), (scene_height / 2).max(1));
+		let make_view = |label| {
+			let texture = device.create_texture(&wgpu::TextureDescriptor {
+				label: Some(label),
+				size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+				mip_level_count: 1,
+				sample_count: 1,
+				dimension: wgpu::TextureDimension::D2,
+				format: BLOOM_FORMAT,
+				view_formats: &[],
+				usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+			});
+			texture.create_view(&wgpu::TextureViewDescriptor::default())
+		};
+		let make_bind_group = |label, view| {
+			device.create_bind_group(&wgpu::BindGroupDescriptor {
+				label: Some(label),
+				layout: &self.bind_group_layout,
+				entries: &[
+					wgpu::BindGroupEntry {
+						binding: 0,
+						resource: wgpu::BindingResource::TextureView(view),
+					},
+					wgpu::BindGroupEntry {
+						binding: 1,
+						resource: wgpu::BindingResource::Sampler(&self.sampler),
+					},
+					wgpu::BindGroupEntry {
+						binding: 2,
+						resource: self.settings_buffer.as_entire_binding(),
+					},
+				],
+			})
+		};
+		let view_a = make_view("Bloom Texture A");
+		let view_b = make_view("Bloom Texture B");
+		let scene_bind_group = make_bind_group("Bloom Scene Bind Group", scene_view);
+		let a_bind_group = make_bind_group("Bloom A Bind Group", &view_a);
+		let b_bind_group = make_bind_group("Bloom B Bind Group", &view_b);
+		self.targets =
+			Some(BloomTargets { view_a, view_b, scene_bind_group, a_bind_group, b_bind_group });
+	}
+
+	/// Adds the glow to the scene (that must have been rendered to the offscreen texture given
+	/// to `resize`).
+	pub(crate) fn render(
+		&self,
+		queue: &wgpu::Queue,
+		encoder: &mut wgpu::CommandEncoder,
+		scene_view: &wgpu::TextureView,
+		stats: &mut RenderStats,
+	) {
+		let Some(targets) = &self.targets else {
+			return;
+		};
+		queue.write_buffer(
+			&self.settings_buffer,
+			0,
+			bytemuck::cast_slice(&[BloomSettingsPod {
+				threshold: self.threshold,
+				intensity: self.intensity,
+				_padding: [0.0; 2],
+			}]),
+		);
+
+		let passes = [
+			(
+				"Render Pass for the bloom bright pass",
+				&self.render_pipelines.bright_pass,
+				&targets.scene_bind_group,
+				&targets.view_a,
+			),
+			(
+				"Render Pass for the bloom horizontal blur",
+				&self.render_pipelines.blur_horizontal,
+				&targets.a_bind_group,
+				&targets.view_b,
+			),
+			(
+				"Render Pass for the bloom vertical blur",
+				&self.render_pipelines.blur_vertical,
+				&targets.b_bind_group,
+				&targets.view_a,
+			),
+		];
+		for (label, render_pipeline, bind_group, target_view) in passes {
+			let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+				label: Some(label),
+				color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+					view: target_view,
+					resolve_target: None,
+					ops: wgpu::Operations {
+						load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+						store: wgpu::StoreOp::Store,
+					},
+				})],
+				depth_stencil_attachment: None,
+				timestamp_writes: None,
+				occlusion_query_set: None,
+			});
+			render_pass.set_pipeline(render_pipeline);
+			render_pass.set_bind_group(0, bind_group, &[]);
+			stats.draw_triangles(&mut render_pass, 3, 1);
+		}
+
+		let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+			label: Some("Render Pass to add the bloom to the scene"),
+			color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+				view: scene_view,
+				resolve_target: None,
+				ops: wgpu::Operations { load: wgpu::LoadOp::Load, store: wgpu::StoreOp::Store },
+			})],
+			depth_stencil_attachment: None,
+			timestamp_writes: None,
+			occlusion_query_set: None,
+		});
+		render_pass.set_pipeline(&self.render_pipelines.composite);
+		render_pass.set_bind_group(0, &targets.a_bind_group, &[]);
+		stats.draw_triangles(&mut render_pass, 3, 1);
+	}
+}
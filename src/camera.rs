@@ -48,14 +48,21 @@ pub(crate) trait CameraSettings {
 		// area from (-1, -1, 0) to (1, 1, 1). The difference is that on the Z axis (depth) the
 		// range is not (-1, 1) but instead is (0, 1).
 		// `cgmath` assumes OpenGL-like conventions and here we correct these assumptions to Wgpu.
+		//
+		// On top of that, the depth is reversed (the near plane is mapped to 1 and the far plane
+		// to 0), this is "reverse-Z". Floats are way more precise near 0, which compensates the
+		// perspective projection squishing the far away depths together. This is what allows
+		// far planes that are very far without distant terrain z-fighting all over the place.
+		// It means that depth buffers are cleared to 0 and that greater depth means closer.
 		#[rustfmt::skip]
-		pub(crate) const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
-			1.0, 0.0, 0.0, 0.0,
-			0.0, 1.0, 0.0, 0.0,
-			0.0, 0.0, 0.5, 0.0,
-			0.0, 0.0, 0.5, 1.0,
-		);
-		let view_projection_matrix = OPENGL_TO_WGPU_MATRIX * view_projection_matrix;
+		pub(crate) const OPENGL_TO_WGPU_REVERSE_Z_MATRIX: cgmath::Matrix4<f32> =
+			cgmath::Matrix4::new(
+				1.0, 0.0, 0.0, 0.0,
+				0.0, 1.0, 0.0, 0.0,
+				0.0, 0.0, -0.5, 0.0,
+				0.0, 0.0, 0.5, 1.0,
+			);
+		let view_projection_matrix = OPENGL_TO_WGPU_REVERSE_Z_MATRIX * view_projection_matrix;
 
 		Matrix4x4Pod { values: view_projection_matrix.into() }
 	}
@@ -120,3 +127,19 @@ impl CameraSettings for CameraOrthographicSettings {
 pub(crate) struct Matrix4x4Pod {
 	values: [[f32; 4]; 4],
 }
+
+impl Matrix4x4Pod {
+	pub(crate) fn to_matrix(self) -> cgmath::Matrix4<f32> {
+		self.values.into()
+	}
+
+	/// The view projection matrix of the reflection of the camera in a mirror that is
+	/// the horizontal plane at the given height (like the surface of some water).
+	/// The mirror flips the handedness of the space, so triangles get their winding reversed.
+	pub(crate) fn mirrored_across_horizontal_plane(self, plane_z: f32) -> Matrix4x4Pod {
+		let mirror = cgmath::Matrix4::from_translation(cgmath::vec3(0.0, 0.0, plane_z))
+			* cgmath::Matrix4::from_nonuniform_scale(1.0, 1.0, -1.0)
+			* cgmath::Matrix4::from_translation(cgmath::vec3(0.0, 0.0, -plane_z));
+		Matrix4x4Pod { values: (self.to_matrix() * mirror).into() }
+	}
+}
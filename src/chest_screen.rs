@@ -0,0 +1,218 @@
+//! The chest screen displays the slots of an open chest (see `BlockData::ChestContents`)
+//! and the block held by the player. Clicking a slot of the chest swaps what is in it with
+//! the held block, this is how blocks are put in chests and taken out of them.
+
+use crate::{
+	block_picker::add_block_icon_vertices,
+	block_types::{BlockTypeId, BlockTypeTable, CHEST_SLOT_COUNT},
+	chunk_blocks::Block,
+	coords::BlockCoords,
+	font::{self, Font},
+	localization::Localization,
+	widgets::{simple_line_vertices_for_rect, InterfaceMeshesVertices},
+};
+
+/// Number of slots per row in the grid of the chest slots.
+const COLUMN_COUNT: usize = 9;
+/// Block textures are 16x16 pixels in the atlas, they are stretched by this factor.
+const ICON_SCALE: f32 = 4.0;
+/// Size of a slot (icon and spacing around it), in screen pixels.
+const CELL_SIZE_IN_PIXELS: f32 = 16.0 * ICON_SCALE + 12.0;
+/// Space above the grid (where the title is displayed), in screen pixels.
+const GRID_TOP_MARGIN_IN_PIXELS: f32 = 90.0;
+/// Space between the grid and the slot of the held block (where its label is), in screen pixels.
+const HELD_SLOT_TOP_MARGIN_IN_PIXELS: f32 = 60.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Slot {
+	/// The index of a slot of the chest.
+	Chest(usize),
+	/// The block held by the player.
+	Held,
+}
+
+pub(crate) struct ChestScreen {
+	/// The coords of the open chest and a copy of its contents, kept up to date with what is done
+	/// through the screen (as the chest block in the world may only be updated later,
+	/// see `ChunkGridShareable::perform_now_or_later`).
+	open_chest: Option<(BlockCoords, Vec<Option<Block>>)>,
+	/// Position of the mouse cursor in the window, in screen pixels (from the top left corner).
+	cursor_position: Option<cgmath::Point2<f32>>,
+}
+
+impl ChestScreen {
+	pub(crate) fn new() -> ChestScreen {
+		ChestScreen { open_chest: None, cursor_position: None }
+	}
+
+	pub(crate) fn is_open(&self) -> bool {
+		self.open_chest.is_some()
+	}
+
+	pub(crate) fn open(&mut self, coords: BlockCoords, contents: Vec<Option<Block>>) {
+		self.open_chest = Some((coords, contents));
+	}
+
+	pub(crate) fn close(&mut self) {
+		self.open_chest = None;
+	}
+
+	pub(crate) fn open_chest_coords(&self) -> Option<BlockCoords> {
+		self.open_chest.as_ref().map(|(coords, _contents)| *coords)
+	}
+
+	pub(crate) fn set_cursor_position(&mut self, cursor_position: cgmath::Point2<f32>) {
+		self.cursor_position = Some(cursor_position);
+	}
+
+	fn grid_top_left_in_pixels(window_dimensions: cgmath::Vector2<f32>) -> cgmath::Point2<f32> {
+		let grid_width = COLUMN_COUNT as f32 * CELL_SIZE_IN_PIXELS;
+		cgmath::point2(
+			((window_dimensions.x - grid_width) / 2.0).floor(),
+			GRID_TOP_MARGIN_IN_PIXELS,
+		)
+	}
+
+	fn slot_top_left_in_pixels(
+		slot: Slot,
+		window_dimensions: cgmath::Vector2<f32>,
+	) -> cgmath::Point2<f32> {
+		let grid_top_left = ChestScreen::grid_top_left_in_pixels(window_dimensions);
+		match slot {
+			Slot::Chest(index) => {
+				let column = index % COLUMN_COUNT;
+				let row = index / COLUMN_COUNT;
+				grid_top_left + cgmath::vec2(column as f32, row as f32) * CELL_SIZE_IN_PIXELS
+			},
+			Slot::Held => {
+				let row_count = CHEST_SLOT_COUNT.div_ceil(COLUMN_COUNT);
+				grid_top_left
+					+ cgmath::vec2(
+						0.0,
+						row_count as f32 * CELL_SIZE_IN_PIXELS + HELD_SLOT_TOP_MARGIN_IN_PIXELS,
+					)
+			},
+		}
+	}
+
+	fn all_slots() -> impl Iterator<Item = Slot> {
+		(0..CHEST_SLOT_COUNT).map(Slot::Chest).chain(std::iter::once(Slot::Held))
+	}
+
+	/// Returns the slot that is under the mouse cursor, if any.
+	pub(crate) fn hovered_slot(&self, window_dimensions: cgmath::Vector2<f32>) -> Option<Slot> {
+		let cursor_position = self.cursor_position?;
+		ChestScreen::all_slots().find(|&slot| {
+			let in_slot =
+				cursor_position - ChestScreen::slot_top_left_in_pixels(slot, window_dimensions);
+			(0.0..CELL_SIZE_IN_PIXELS).contains(&in_slot.x)
+				&& (0.0..CELL_SIZE_IN_PIXELS).contains(&in_slot.y)
+		})
+	}
+
+	/// Swaps the block in the given slot of the chest with the held block. Returns the chest
+	/// block with its new contents and its coords, to be placed in the world.
+	pub(crate) fn swap_with_held_block(
+		&mut self,
+		slot_index: usize,
+		held_block: &mut Option<Block>,
+		chest_type_id: BlockTypeId,
+	) -> Option<(BlockCoords, Block)> {
+		let (coords, contents) = self.open_chest.as_mut()?;
+		std::mem::swap(&mut contents[slot_index], held_block);
+		Some((*coords, Block::new_chest(chest_type_id, contents.clone())))
+	}
+
+	pub(crate) fn generate_mesh_vertices(
+		&self,
+		meshes: &mut InterfaceMeshesVertices,
+		font: &Font,
+		localization: &Localization,
+		block_type_table: &BlockTypeTable,
+		held_block: Option<&Block>,
+		window_dimensions: cgmath::Vector2<f32>,
+	) {
+		let Some((_coords, contents)) = self.open_chest.as_ref() else {
+			return;
+		};
+
+		// Converts screen pixels coords (from the top left corner) to interface coords.
+		let screen_pixel_size = 2.0 / window_dimensions.x;
+		let to_interface_coords = |pixel_coords: cgmath::Point2<f32>| {
+			cgmath::point3(
+				-1.0 + pixel_coords.x * screen_pixel_size,
+				window_dimensions.y / window_dimensions.x - pixel_coords.y * screen_pixel_size,
+				0.5,
+			)
+		};
+		let block_in_slot = |slot: Slot| match slot {
+			Slot::Chest(index) => contents[index].as_ref(),
+			Slot::Held => held_block,
+		};
+
+		// Title above the grid and label above the held block slot.
+		let grid_top_left = ChestScreen::grid_top_left_in_pixels(window_dimensions);
+		let held_slot_top_left = ChestScreen::slot_top_left_in_pixels(Slot::Held, window_dimensions);
+		for (text_top_left, key) in [
+			(grid_top_left - cgmath::vec2(0.0, 50.0), "chest.title"),
+			(
+				held_slot_top_left - cgmath::vec2(0.0, 35.0),
+				"chest.held_block",
+			),
+		] {
+			let mut settings = font::TextRenderingSettings::with_scale(3.0);
+			settings.color = [1.0, 1.0, 1.0];
+			let vertices = font.simple_texture_vertices_from_text(
+				window_dimensions.x,
+				to_interface_coords(text_top_left),
+				settings,
+				localization.get(key),
+			);
+			meshes.add_simple_texture_vertices(vertices);
+		}
+
+		// Slot frames and the icons of the blocks in the slots.
+		for slot in ChestScreen::all_slots() {
+			let slot_top_left = ChestScreen::slot_top_left_in_pixels(slot, window_dimensions);
+			meshes.add_simple_line_vertices(simple_line_vertices_for_rect(
+				to_interface_coords(slot_top_left),
+				cgmath::vec2(1.0, 1.0) * CELL_SIZE_IN_PIXELS * screen_pixel_size,
+				[1.0, 1.0, 1.0],
+			));
+			if let Some(block) = block_in_slot(slot) {
+				let icon_margin = (CELL_SIZE_IN_PIXELS - 16.0 * ICON_SCALE) / 2.0;
+				add_block_icon_vertices(
+					meshes,
+					font,
+					block_type_table.get(block.type_id).unwrap(),
+					to_interface_coords(slot_top_left + cgmath::vec2(icon_margin, icon_margin)),
+					ICON_SCALE,
+					window_dimensions,
+				);
+			}
+		}
+
+		// Hovered slot highlight and tooltip with the block type name.
+		if let Some(hovered_slot) = self.hovered_slot(window_dimensions) {
+			let slot_top_left = ChestScreen::slot_top_left_in_pixels(hovered_slot, window_dimensions);
+			meshes.add_simple_line_vertices(simple_line_vertices_for_rect(
+				to_interface_coords(slot_top_left + cgmath::vec2(2.0, 2.0)),
+				cgmath::vec2(1.0, 1.0) * (CELL_SIZE_IN_PIXELS - 4.0) * screen_pixel_size,
+				[1.0, 1.0, 0.0],
+			));
+
+			if let Some(block) = block_in_slot(hovered_slot) {
+				let mut settings = font::TextRenderingSettings::with_scale(3.0);
+				settings.color = [1.0, 1.0, 0.0];
+				let name = block_type_table.name(block.type_id).unwrap();
+				let vertices = font.simple_texture_vertices_from_text(
+					window_dimensions.x,
+					to_interface_coords(self.cursor_position.unwrap() + cgmath::vec2(16.0, 16.0)),
+					settings,
+					name,
+				);
+				meshes.add_simple_texture_vertices(vertices);
+			}
+		}
+	}
+}
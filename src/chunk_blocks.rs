@@ -9,7 +9,8 @@ use fxhash::FxHashMap;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-	block_types::{BlockTypeId, BlockTypeTable},
+	block_type_remapping::BlockTypeIdRemapping,
+	block_types::{BlockType, BlockTypeId, BlockTypeTable, CHEST_SLOT_COUNT, MAX_LAYER_COUNT},
 	coords::{BlockCoords, ChunkCoordsSpan, OrientedAxis},
 	saves::{Save, WhichChunkFile},
 };
@@ -22,6 +23,28 @@ pub(crate) struct Block {
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub(crate) enum BlockData {
 	Text(String),
+	/// The number of stacked layers of a layer block (see `BlockType::Layer`).
+	LayerCount(u8),
+	/// The state of a door or trapdoor (see `BlockType::Door` and `BlockType::Trapdoor`).
+	/// A closed door panel is against the `facing` side of its block, and an open door panel is
+	/// against the next side (see `OrientedAxis::quarter_turned`). A closed trapdoor panel is on
+	/// the bottom of its block, and an open trapdoor panel is against the `facing` side.
+	DoorState {
+		facing: OrientedAxis,
+		open: bool,
+	},
+	/// The blocks in the slots of a chest (see `chest_screen`), `CHEST_SLOT_COUNT` slots.
+	ChestContents(Vec<Option<Block>>),
+	/// The number of blocks a tool (see `BlockType::Tool`) can still break before breaking itself.
+	ToolDurability(u32),
+	/// The signal power carried by a signal wire (see `signals`), 0 when unpowered.
+	SignalPower(u8),
+	/// The state of a piston or piston head (see `pistons`). A piston pushes towards its `facing`
+	/// side, and when it is extended its head is the block on that side (with the same `facing`).
+	PistonState {
+		facing: OrientedAxis,
+		extended: bool,
+	},
 }
 
 impl From<BlockTypeId> for Block {
@@ -35,7 +58,37 @@ impl Block {
 		Block { type_id: BlockTypeTable::AIR_ID, data: None }
 	}
 
-	fn as_view(&self) -> BlockView<'_> {
+	/// A layer block (see `BlockType::Layer`) with the given number of stacked layers.
+	pub(crate) fn new_layer(type_id: BlockTypeId, layer_count: u8) -> Block {
+		Block { type_id, data: Some(BlockData::LayerCount(layer_count)) }
+	}
+
+	/// A door or trapdoor (see `BlockData::DoorState`).
+	pub(crate) fn new_door(type_id: BlockTypeId, facing: OrientedAxis, open: bool) -> Block {
+		Block { type_id, data: Some(BlockData::DoorState { facing, open }) }
+	}
+
+	/// A chest with the given blocks in its slots.
+	pub(crate) fn new_chest(type_id: BlockTypeId, contents: Vec<Option<Block>>) -> Block {
+		Block { type_id, data: Some(BlockData::ChestContents(contents)) }
+	}
+
+	/// A tool that can still break the given number of blocks.
+	pub(crate) fn new_tool(type_id: BlockTypeId, durability: u32) -> Block {
+		Block { type_id, data: Some(BlockData::ToolDurability(durability)) }
+	}
+
+	/// A signal wire carrying the given signal power.
+	pub(crate) fn new_signal_wire(type_id: BlockTypeId, power: u8) -> Block {
+		Block { type_id, data: Some(BlockData::SignalPower(power)) }
+	}
+
+	/// A piston or piston head (see `BlockData::PistonState`).
+	pub(crate) fn new_piston(type_id: BlockTypeId, facing: OrientedAxis, extended: bool) -> Block {
+		Block { type_id, data: Some(BlockData::PistonState { facing, extended }) }
+	}
+
+	pub(crate) fn as_view(&self) -> BlockView<'_> {
 		BlockView { type_id: self.type_id, data: self.data.as_ref() }
 	}
 }
@@ -53,6 +106,79 @@ impl<'a> BlockView<'a> {
 	pub(crate) fn as_owned_block(&self) -> Block {
 		Block { type_id: self.type_id, data: self.data.cloned() }
 	}
+
+	/// The number of stacked layers, if this is a layer block (see `BlockType::Layer`).
+	/// A layer block without a layer count is one layer thick.
+	pub(crate) fn layer_count(&self) -> u8 {
+		match self.data {
+			Some(BlockData::LayerCount(count)) => *count,
+			_ => 1,
+		}
+	}
+
+	/// The height (in blocks) of the stacked layers, if this is a layer block.
+	pub(crate) fn layer_height(&self) -> f32 {
+		self.layer_count() as f32 / MAX_LAYER_COUNT as f32
+	}
+
+	/// The side the door faces and whether it is open, if this is a door or trapdoor
+	/// (see `BlockData::DoorState`). A door without a state is closed and faces X+.
+	pub(crate) fn door_state(&self) -> (OrientedAxis, bool) {
+		match self.data {
+			Some(BlockData::DoorState { facing, open }) => (*facing, *open),
+			_ => (OrientedAxis::X_PLUS, false),
+		}
+	}
+
+	/// The blocks in the slots of a chest, if this is a chest, there are always `CHEST_SLOT_COUNT`
+	/// slots. A chest without contents is empty.
+	pub(crate) fn chest_contents(&self) -> Vec<Option<Block>> {
+		let mut contents = match self.data {
+			Some(BlockData::ChestContents(contents)) => contents.clone(),
+			_ => vec![],
+		};
+		contents.resize(CHEST_SLOT_COUNT, None);
+		contents
+	}
+
+	/// The number of blocks the tool can still break, if this is a tool (see `BlockType::Tool`).
+	/// A tool without durability is new.
+	pub(crate) fn tool_durability(&self, block_type: &BlockType) -> Option<u32> {
+		match self.data {
+			Some(BlockData::ToolDurability(durability)) => Some(*durability),
+			_ => block_type.tool_max_durability(),
+		}
+	}
+
+	/// The signal power carried, if this is a signal wire. A wire without power data is unpowered.
+	pub(crate) fn signal_power(&self) -> u8 {
+		match self.data {
+			Some(BlockData::SignalPower(power)) => *power,
+			_ => 0,
+		}
+	}
+
+	/// The side the piston pushes towards and whether it is extended, if this is a piston or
+	/// piston head (see `BlockData::PistonState`). A piston without a state is retracted and
+	/// pushes upwards.
+	pub(crate) fn piston_state(&self) -> (OrientedAxis, bool) {
+		match self.data {
+			Some(BlockData::PistonState { facing, extended }) => (*facing, *extended),
+			_ => (OrientedAxis::Z_PLUS, false),
+		}
+	}
+
+	/// The side of its block that the panel is against, if this is a door or trapdoor.
+	pub(crate) fn door_panel_side(&self, block_type: &BlockType) -> Option<OrientedAxis> {
+		let (facing, open) = self.door_state();
+		match (block_type, open) {
+			(BlockType::Door { .. }, false) => Some(facing),
+			(BlockType::Door { .. }, true) => Some(facing.quarter_turned()),
+			(BlockType::Trapdoor { .. }, false) => Some(OrientedAxis::Z_MINUS),
+			(BlockType::Trapdoor { .. }, true) => Some(facing),
+			_ => None,
+		}
+	}
 }
 
 /// An entry in the palette of a chunk of a `ChunkBlocks`.
@@ -316,6 +442,72 @@ impl ChunkBlocks {
 		}
 	}
 
+	/// The types of the blocks that are in the chunk (at least once).
+	pub(crate) fn block_type_ids_in_palette(&self) -> impl Iterator<Item = BlockTypeId> + '_ {
+		self.savable.palette.values().map(|palette_entry| palette_entry.block.type_id)
+	}
+
+	/// How many blocks of each type there are in the chunk (a type may appear more than once,
+	/// when blocks of the same type have different data).
+	pub(crate) fn block_type_counts(&self) -> Vec<(BlockTypeId, u32)> {
+		if self.savable.block_keys_grid.is_empty() {
+			// Being empty represents being full of air.
+			let block_count = self.coords_span.cd.number_of_blocks_in_a_chunk() as u32;
+			vec![(BlockTypeTable::AIR_ID, block_count)]
+		} else {
+			self
+				.savable
+				.palette
+				.values()
+				.map(|palette_entry| (palette_entry.block.type_id, palette_entry.instance_count))
+				.collect()
+		}
+	}
+
+	/// Gives the current ids to the blocks of a chunk loaded from a save that had other ids
+	/// (see `block_type_remapping`). Palette entries that end up with the same block are merged.
+	pub(crate) fn remap_block_type_ids(&mut self, remapping: &BlockTypeIdRemapping) {
+		for entry in self.savable.palette.values_mut() {
+			remapping.remap_block(&mut entry.block);
+		}
+
+		let mut merged_keys: FxHashMap<PaletteKey, PaletteKey> = HashMap::default();
+		let keys: Vec<PaletteKey> = self.savable.palette.keys().copied().collect();
+		for (i, &key) in keys.iter().enumerate() {
+			if merged_keys.contains_key(&key) {
+				continue;
+			}
+			for &other_key in keys[(i + 1)..].iter() {
+				let is_same_block =
+					self.savable.palette[&key].block == self.savable.palette[&other_key].block;
+				if is_same_block && !merged_keys.contains_key(&other_key) {
+					merged_keys.insert(other_key, key);
+				}
+			}
+		}
+		if !merged_keys.is_empty() {
+			for index in 0..self.coords_span.cd.number_of_blocks_in_a_chunk() {
+				let key = self.get_block_key_from_grid(index);
+				if let Some(&merged_into_key) = merged_keys.get(&key) {
+					self.set_block_key_to_grid(index, merged_into_key);
+				}
+			}
+			for (key, merged_into_key) in merged_keys {
+				let removed_entry = self.savable.palette.remove(&key).unwrap();
+				self.savable.palette.get_mut(&merged_into_key).unwrap().instance_count +=
+					removed_entry.instance_count;
+				self.give_back_key_no_longer_in_use(key);
+			}
+		}
+
+		self.savable.air_key = self
+			.savable
+			.palette
+			.iter()
+			.find(|(_key, entry)| entry.block.type_id == BlockTypeTable::AIR_ID)
+			.map(|(&key, _entry)| key);
+	}
+
 	/// Just a look-up, no expensive counting.
 	pub(crate) fn contains_only_air(&self) -> bool {
 		if self.savable.block_keys_grid.is_empty() {
@@ -340,9 +532,25 @@ impl ChunkBlocks {
 	}
 
 	pub(crate) fn save(&self, save: &Arc<Save>) {
-		// TODO: Use buffered streams instead of full vecs of data as intermediary steps.
 		let chunk_file_path =
 			save.chunk_file_path(self.coords_span.chunk_coords, WhichChunkFile::Blocks);
+		let chunk_file = save.get_file_io(chunk_file_path);
+		chunk_file.write(&self.to_compressed_data());
+	}
+
+	pub(crate) fn load_from_save(
+		coords_span: ChunkCoordsSpan,
+		save: &Arc<Save>,
+	) -> Option<ChunkBlocks> {
+		let chunk_file_path = save.chunk_file_path(coords_span.chunk_coords, WhichChunkFile::Blocks);
+		let chunk_file = save.get_file_io(chunk_file_path);
+		let compressed_data = chunk_file.read(false)?;
+		Some(ChunkBlocks::from_compressed_data(coords_span, &compressed_data).unwrap())
+	}
+
+	/// The format in which chunk blocks are written on the disk.
+	pub(crate) fn to_compressed_data(&self) -> Vec<u8> {
+		// TODO: Use buffered streams instead of full vecs of data as intermediary steps.
 		let uncompressed_data = rmp_serde::encode::to_vec(&self.savable).unwrap();
 		let mut compressed_data = vec![];
 		{
@@ -352,24 +560,21 @@ impl ChunkBlocks {
 			);
 			encoder.write_all(&uncompressed_data).unwrap();
 		}
-		let chunk_file = save.get_file_io(chunk_file_path);
-		chunk_file.write(&compressed_data);
+		compressed_data
 	}
 
-	pub(crate) fn load_from_save(
+	/// `None` if the data is corrupted.
+	pub(crate) fn from_compressed_data(
 		coords_span: ChunkCoordsSpan,
-		save: &Arc<Save>,
+		compressed_data: &[u8],
 	) -> Option<ChunkBlocks> {
 		// TODO: Use buffered streams instead of full vecs of data as intermediary steps.
-		let chunk_file_path = save.chunk_file_path(coords_span.chunk_coords, WhichChunkFile::Blocks);
-		let chunk_file = save.get_file_io(chunk_file_path);
-		let compressed_data = chunk_file.read(false)?;
 		let mut uncompressed_data = vec![];
 		{
-			let mut decoder = flate2::bufread::DeflateDecoder::new(compressed_data.as_slice());
-			decoder.read_to_end(&mut uncompressed_data).unwrap();
+			let mut decoder = flate2::bufread::DeflateDecoder::new(compressed_data);
+			decoder.read_to_end(&mut uncompressed_data).ok()?;
 		}
-		let savable: ChunkBlocksSavable = rmp_serde::decode::from_slice(&uncompressed_data).unwrap();
+		let savable: ChunkBlocksSavable = rmp_serde::decode::from_slice(&uncompressed_data).ok()?;
 		Some(ChunkBlocks { coords_span, savable })
 	}
 }
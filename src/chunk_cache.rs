@@ -0,0 +1,316 @@
+//! Cache on the disk of the blocks of generated chunks, so that the chunks of an area that
+//! is visited again can be read from the disk instead of being generated again (which can be
+//! slow with some world generators).
+//!
+//! It is not a part of the save, chunks are only cached when they are not saved
+//! (because they were not modified, or because there is no save).
+//! The cache has a maximum size, when it is exceeded the chunks that were the least recently
+//! used are removed from it.
+
+use std::{path::PathBuf, sync::Mutex, time::UNIX_EPOCH};
+
+use clap::ValueEnum;
+use fxhash::FxHashMap;
+
+use crate::{
+	block_types::BlockTypeTable,
+	chunk_blocks::ChunkBlocks,
+	coords::{ChunkCoords, ChunkCoordsSpan, ChunkDimensions},
+	world_gen::{WhichWorldGenerator, WORLD_GEN_VERSION},
+};
+
+struct CachedChunk {
+	size_in_bytes: u64,
+	/// Greater is more recent.
+	last_use: u64,
+}
+
+struct ChunkCacheIndex {
+	chunks: FxHashMap<ChunkCoords, CachedChunk>,
+	total_size_in_bytes: u64,
+	/// The `last_use` to give to the next use.
+	use_counter: u64,
+}
+
+impl ChunkCacheIndex {
+	fn add_stored_chunk(&mut self, chunk_coords: ChunkCoords, size_in_bytes: u64) {
+		self.use_counter += 1;
+		let last_use = self.use_counter;
+		// An other thread may have stored the same chunk while the lock was released,
+		// its file was replaced so its size is replaced too (instead of being counted twice).
+		let replaced = self.chunks.insert(chunk_coords, CachedChunk { size_in_bytes, last_use });
+		if let Some(replaced) = replaced {
+			self.total_size_in_bytes -= replaced.size_in_bytes;
+		}
+		self.total_size_in_bytes += size_in_bytes;
+	}
+}
+
+/// The cache of the chunks of one world (different world generators or seeds or chunk sizes
+/// or block type tables or versions or settings of the world generators do not share
+/// their cached chunks).
+pub(crate) struct ChunkCache {
+	directory: PathBuf,
+	max_size_in_bytes: u64,
+	index: Mutex<ChunkCacheIndex>,
+}
+
+impl ChunkCache {
+	/// Opens the cache (that may have been filled in previous runs) of the world
+	/// that is generated with the given parameters.
+	///
+	/// Returns `None` (and logs an error) if the cache directory cannot be created or read
+	/// (like in a read-only working directory), the game then runs without the cache.
+	pub(crate) fn open(
+		which_world_generator: WhichWorldGenerator,
+		world_gen_seed: i32,
+		cd: ChunkDimensions,
+		block_type_table: &BlockTypeTable,
+		world_generator_settings: Option<u64>,
+		max_size_in_bytes: u64,
+	) -> Option<ChunkCache> {
+		let mut directory = PathBuf::new();
+		directory.push("chunk_cache");
+		directory.push(cache_directory_name(
+			which_world_generator,
+			world_gen_seed,
+			cd,
+			block_type_table,
+			world_generator_settings,
+		));
+		if let Err(error) = std::fs::create_dir_all(&directory) {
+			log::error!(
+				"Could not create the chunk cache directory \"{}\", the chunk cache is disabled: {error}",
+				directory.display()
+			);
+			return None;
+		}
+
+		// The chunks cached in previous runs are indexed with the most recently modified files
+		// being the most recently used.
+		let mut files = vec![];
+		let entries = match std::fs::read_dir(&directory) {
+			Ok(entries) => entries,
+			Err(error) => {
+				log::error!(
+					"Could not read the chunk cache directory \"{}\", the chunk cache is disabled: {error}",
+					directory.display()
+				);
+				return None;
+			},
+		};
+		for entry in entries.flatten() {
+			let Some(chunk_coords) = chunk_coords_from_file_name(&entry.file_name().to_string_lossy())
+			else {
+				continue;
+			};
+			let Ok(metadata) = entry.metadata() else {
+				continue;
+			};
+			let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+			files.push((chunk_coords, metadata.len(), modified));
+		}
+		files.sort_by_key(|&(_, _, modified)| modified);
+		let mut index = ChunkCacheIndex {
+			chunks: FxHashMap::default(),
+			total_size_in_bytes: 0,
+			use_counter: 0,
+		};
+		for (chunk_coords, size_in_bytes, _) in files {
+			index.chunks.insert(
+				chunk_coords,
+				CachedChunk { size_in_bytes, last_use: index.use_counter },
+			);
+			index.total_size_in_bytes += size_in_bytes;
+			index.use_counter += 1;
+		}
+
+		let chunk_cache = ChunkCache { directory, max_size_in_bytes, index: Mutex::new(index) };
+		chunk_cache.evict_if_too_big(&mut chunk_cache.index.lock().unwrap());
+		Some(chunk_cache)
+	}
+
+	fn chunk_file_path(&self, chunk_coords: ChunkCoords) -> PathBuf {
+		let mut path = self.directory.clone();
+		let cgmath::Point3 { x, y, z } = chunk_coords;
+		path.push(format!("{x},{y},{z}"));
+		path
+	}
+
+	/// Puts the given (unmodified since generation) chunk blocks in the cache.
+	pub(crate) fn store(&self, chunk_blocks: &ChunkBlocks) {
+		let chunk_coords = chunk_blocks.coords_span.chunk_coords;
+		{
+			let mut index = self.index.lock().unwrap();
+			index.use_counter += 1;
+			let last_use = index.use_counter;
+			if let Some(cached_chunk) = index.chunks.get_mut(&chunk_coords) {
+				// Already cached (it must have been loaded from the cache).
+				cached_chunk.last_use = last_use;
+				return;
+			}
+		}
+
+		let data = chunk_blocks.to_compressed_data();
+		// Written in an other file first and then renamed, so that a chunk file is never
+		// found half written.
+		let path = self.chunk_file_path(chunk_coords);
+		let temporary_path = path.with_extension("tmp");
+		if std::fs::write(&temporary_path, &data).is_err()
+			|| std::fs::rename(&temporary_path, &path).is_err()
+		{
+			return;
+		}
+
+		let mut index = self.index.lock().unwrap();
+		index.add_stored_chunk(chunk_coords, data.len() as u64);
+		self.evict_if_too_big(&mut index);
+	}
+
+	/// Gets the chunk blocks from the cache, if they are in it.
+	pub(crate) fn load(&self, coords_span: ChunkCoordsSpan) -> Option<ChunkBlocks> {
+		let chunk_coords = coords_span.chunk_coords;
+		{
+			let mut index = self.index.lock().unwrap();
+			index.use_counter += 1;
+			let last_use = index.use_counter;
+			index.chunks.get_mut(&chunk_coords)?.last_use = last_use;
+		}
+		let chunk_blocks = std::fs::read(self.chunk_file_path(chunk_coords))
+			.ok()
+			.and_then(|data| ChunkBlocks::from_compressed_data(coords_span, &data));
+		if chunk_blocks.is_none() {
+			// The file is missing (it may have been evicted in the meantime) or corrupted.
+			self.remove(&mut self.index.lock().unwrap(), chunk_coords);
+		}
+		chunk_blocks
+	}
+
+	fn remove(&self, index: &mut ChunkCacheIndex, chunk_coords: ChunkCoords) {
+		if let Some(cached_chunk) = index.chunks.remove(&chunk_coords) {
+			index.total_size_in_bytes -= cached_chunk.size_in_bytes;
+			std::fs::remove_file(self.chunk_file_path(chunk_coords)).ok();
+		}
+	}
+
+	/// Removes the least recently used chunks until the cache fits in its maximum size.
+	fn evict_if_too_big(&self, index: &mut ChunkCacheIndex) {
+		if index.total_size_in_bytes <= self.max_size_in_bytes {
+			return;
+		}
+		let mut chunks_by_last_use: Vec<_> = index
+			.chunks
+			.iter()
+			.map(|(&chunk_coords, cached_chunk)| (chunk_coords, cached_chunk.last_use))
+			.collect();
+		chunks_by_last_use.sort_unstable_by_key(|&(_, last_use)| last_use);
+		// Going a bit below the maximum size so that it does not happen again at every store.
+		let target_size_in_bytes = self.max_size_in_bytes / 10 * 9;
+		for (chunk_coords, _) in chunks_by_last_use {
+			if index.total_size_in_bytes <= target_size_in_bytes {
+				break;
+			}
+			self.remove(index, chunk_coords);
+		}
+	}
+}
+
+/// The cached chunks hold raw block type ids, so the block type table is a part of the key
+/// (adding or removing a block type can change the ids of the others). So is the version
+/// of the world generators code, as a change to a generator changes the chunks it generates,
+/// and so are the settings that the world generator loaded from editable files (if any,
+/// see `WorldGenerator::settings_fingerprint`).
+fn cache_directory_name(
+	which_world_generator: WhichWorldGenerator,
+	world_gen_seed: i32,
+	cd: ChunkDimensions,
+	block_type_table: &BlockTypeTable,
+	world_generator_settings: Option<u64>,
+) -> String {
+	let generator_name = which_world_generator.to_possible_value().unwrap();
+	let generator_name = generator_name.get_name();
+	let block_types = block_type_table.fingerprint();
+	let mut name = format!(
+		"{generator_name}_v{WORLD_GEN_VERSION}_{world_gen_seed}_{}_{block_types:016x}",
+		cd.edge
+	);
+	if let Some(settings) = world_generator_settings {
+		name.push_str(&format!("_{settings:016x}"));
+	}
+	name
+}
+
+fn chunk_coords_from_file_name(file_name: &str) -> Option<ChunkCoords> {
+	let mut coords = file_name.split(',').map(|coord| coord.parse::<i32>().ok());
+	let (x, y, z) = (coords.next()??, coords.next()??, coords.next()??);
+	coords.next().is_none().then_some(cgmath::point3(x, y, z))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		block_definitions::{BlockDefinition, DefinedShape, DefinedTexture},
+		block_types::{GroundResponse, Hardness},
+	};
+
+	#[test]
+	fn chunks_stored_twice_are_counted_once() {
+		let mut index = ChunkCacheIndex {
+			chunks: FxHashMap::default(),
+			total_size_in_bytes: 0,
+			use_counter: 0,
+		};
+		let chunk_coords = cgmath::point3(1, 2, 3);
+		index.add_stored_chunk(chunk_coords, 100);
+		index.add_stored_chunk(chunk_coords, 120);
+		index.add_stored_chunk(cgmath::point3(0, 0, 0), 50);
+		assert_eq!(index.chunks.len(), 2);
+		assert_eq!(index.total_size_in_bytes, 170);
+	}
+
+	#[test]
+	fn cache_key_changes_with_the_block_type_table() {
+		let cd = ChunkDimensions::from(16);
+		let generator = WhichWorldGenerator::Default;
+		let table = BlockTypeTable::new();
+		let key = cache_directory_name(generator, 42, cd, &table, None);
+		assert_eq!(
+			key,
+			cache_directory_name(generator, 42, cd, &BlockTypeTable::new(), None)
+		);
+		assert_ne!(key, cache_directory_name(generator, 43, cd, &table, None));
+		assert!(key.contains(&format!("_v{WORLD_GEN_VERSION}_")));
+
+		// The settings of the world generator (like edited terrain curves) change the key.
+		let splines = WhichWorldGenerator::Splines;
+		let key_with_settings = cache_directory_name(splines, 42, cd, &table, Some(1));
+		assert_ne!(
+			key_with_settings,
+			cache_directory_name(splines, 42, cd, &table, Some(2))
+		);
+		assert_ne!(
+			key_with_settings,
+			cache_directory_name(splines, 42, cd, &table, None)
+		);
+
+		// A defined block type changes the table (and maybe the ids of the other block types).
+		let mut table_with_defined_type = BlockTypeTable::new();
+		table_with_defined_type.add_defined_block_types(
+			&[BlockDefinition {
+				name: "kinda_marble".to_string(),
+				shape: DefinedShape::Solid,
+				texture: DefinedTexture::Generated { seed: 17 },
+				hardness: Hardness::DEFAULT,
+				light_emission: None,
+				ground_response: GroundResponse::DEFAULT,
+				flammable: false,
+			}],
+			None,
+		);
+		assert_ne!(
+			key,
+			cache_directory_name(generator, 42, cd, &table_with_defined_type, None)
+		);
+	}
+}
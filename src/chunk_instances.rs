@@ -0,0 +1,108 @@
+//! What the block shaders need to know about each chunk mesh (see `ChunkInstancePod`).
+//!
+//! All the chunk meshes of a frame get their data from one storage buffer, in one bind group that
+//! is set once per render pass. Each chunk mesh draw call has a single instance whose index is
+//! the index of the chunk mesh (in the order of `ChunkGrid::iter_chunk_meshes`), the shaders use it
+//! to fetch the data of the chunk mesh (like its offset) so that nothing has to be set between
+//! the draw calls other than the vertex buffer.
+
+use std::{mem::size_of, time::Instant};
+
+use crate::{
+	chunk_meshing::ChunkMesh,
+	shaders::block::{ChunkInstancePod, HeatmapTintPod},
+};
+
+/// The buffer starts with room for that many chunk meshes, and doubles in size when needed.
+const INITIAL_CAPACITY: usize = 1024;
+
+pub(crate) struct ChunkInstances {
+	bind_group_layout: wgpu::BindGroupLayout,
+	buffer: wgpu::Buffer,
+	/// How many chunk meshes the buffer has room for.
+	capacity: usize,
+	bind_group: wgpu::BindGroup,
+}
+
+impl ChunkInstances {
+	pub(crate) fn new(device: &wgpu::Device) -> ChunkInstances {
+		let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+			label: Some("Chunk Instances Bind Group Layout"),
+			entries: &[wgpu::BindGroupLayoutEntry {
+				binding: 0,
+				visibility: wgpu::ShaderStages::VERTEX,
+				ty: wgpu::BindingType::Buffer {
+					ty: wgpu::BufferBindingType::Storage { read_only: true },
+					has_dynamic_offset: false,
+					min_binding_size: None,
+				},
+				count: None,
+			}],
+		});
+		let buffer = make_buffer(device, INITIAL_CAPACITY);
+		let bind_group = make_bind_group(device, &bind_group_layout, &buffer);
+		ChunkInstances { bind_group_layout, buffer, capacity: INITIAL_CAPACITY, bind_group }
+	}
+
+	/// The layout of the bind group, that is the second bind group of the block render pipelines.
+	pub(crate) fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+		&self.bind_group_layout
+	}
+
+	pub(crate) fn bind_group(&self) -> &wgpu::BindGroup {
+		&self.bind_group
+	}
+
+	/// Sends the data of the given chunk meshes to the GPU, they must be given in the order
+	/// in which they will be drawn. The heatmap tints (if any) are given in the same order.
+	/// Must be called every frame before rendering.
+	pub(crate) fn update_chunk_meshes<'a>(
+		&mut self,
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		chunk_meshes: impl Iterator<Item = &'a ChunkMesh>,
+		heatmap_tints: Option<Vec<HeatmapTintPod>>,
+	) {
+		let now = Instant::now();
+		let mut instances: Vec<ChunkInstancePod> = chunk_meshes
+			.map(|mesh| ChunkInstancePod {
+				offset: mesh.offset.into(),
+				fade_in: mesh.fade_in_progress(now),
+				heatmap_tint: [1.0, 1.0, 1.0],
+				_padding: 0.0,
+			})
+			.collect();
+		if let Some(heatmap_tints) = heatmap_tints {
+			for (instance, tint) in instances.iter_mut().zip(heatmap_tints) {
+				instance.heatmap_tint = tint;
+			}
+		}
+		if instances.len() > self.capacity {
+			self.capacity = instances.len().next_power_of_two();
+			self.buffer = make_buffer(device, self.capacity);
+			self.bind_group = make_bind_group(device, &self.bind_group_layout, &self.buffer);
+		}
+		queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&instances));
+	}
+}
+
+fn make_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+	device.create_buffer(&wgpu::BufferDescriptor {
+		label: Some("Chunk Instances Buffer"),
+		size: (size_of::<ChunkInstancePod>() * capacity) as u64,
+		usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+		mapped_at_creation: false,
+	})
+}
+
+fn make_bind_group(
+	device: &wgpu::Device,
+	bind_group_layout: &wgpu::BindGroupLayout,
+	buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+	device.create_bind_group(&wgpu::BindGroupDescriptor {
+		label: Some("Chunk Instances Bind Group"),
+		layout: bind_group_layout,
+		entries: &[wgpu::BindGroupEntry { binding: 0, resource: buffer.as_entire_binding() }],
+	})
+}
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use cgmath::MetricSpace;
 use rand::Rng;
@@ -6,6 +6,7 @@ use rand::Rng;
 use crate::{
 	block_types::BlockTypeTable,
 	chunk_blocks::{ChunkCullingInfo, FaceCullingInfo},
+	chunk_cache::ChunkCache,
 	chunks::ChunkGrid,
 	coords::{ChunkCoords, ChunkDimensions, OrientedAxis},
 	entities::IdGenerator,
@@ -20,30 +21,135 @@ pub(crate) struct LoadingManager {
 	pub(crate) loading_enabled: bool,
 	/// Radius (in blocks) of the spherical area around the player
 	/// inside of which the world is to be loaded.
+	/// It may be less than what was asked for, see `AdaptiveLoadingDistance`.
 	pub(crate) loading_distance: f32,
 	/// When added to `loading_distance` it gives the radius of the spherical area around the player
 	/// outside of which the world is to not be loaded.
+	/// It is at least one chunk so that the chunks at the edge do not get loaded and unloaded
+	/// again and again as the player moves back and forth.
 	pub(crate) margin_before_unloading: f32,
+	/// The chunks that were loaded more recently than that are not unloaded yet,
+	/// even if they are too far.
+	pub(crate) min_resident_duration: Duration,
 	/// Chunks to consider for loading.
 	pub(crate) front_high_priority: Vec<ChunkCoords>,
 	/// Chunks that have to be loaded but are probably not intresting so thay are made to wait for now.
 	pub(crate) front_low_priority: Vec<ChunkCoords>,
 	/// Chunks that will have to be loaded if/when the loading area moves over them.
 	pub(crate) front_too_far: Vec<ChunkCoords>,
+	/// If present, `loading_distance` is adjusted to what the machine can handle.
+	pub(crate) adaptive: Option<AdaptiveLoadingDistance>,
 }
 
+/// The loading distance starts small (so that the area around the player gets playable quickly)
+/// and grows up to the asked loading distance as the loading catches up. It shrinks for a while
+/// when the frames take too long, so that the game stays responsive on slow machines.
+pub(crate) struct AdaptiveLoadingDistance {
+	/// The loading distance that was asked for, it is never exceeded.
+	pub(crate) max_loading_distance: f32,
+	/// Frames that take longer than that (in seconds) make the loading distance shrink.
+	slow_frame_duration: f32,
+	/// Duration of the recent frames (in seconds), smoothed so that a single slow frame
+	/// (like one in which a lot of meshes were sent to the GPU) does not trigger anything.
+	smoothed_frame_duration: f32,
+	/// Changes are spaced out so that the effect of a change can be seen before the next one.
+	time_since_last_change: Duration,
+}
+
+/// The loading distance that the adaptive loading distance starts with (in blocks).
+const STARTING_LOADING_DISTANCE: f32 = 50.0;
+/// The adaptive loading distance never shrinks below that (in blocks).
+const MIN_LOADING_DISTANCE: f32 = 40.0;
+/// Minimum time between two changes of the adaptive loading distance.
+const LOADING_DISTANCE_CHANGE_COOLDOWN: Duration = Duration::from_millis(500);
+
 impl LoadingManager {
-	pub(crate) fn new(loading_distance: f32, margin_before_unloading: f32) -> LoadingManager {
+	/// If `adaptive` then the loading distance starts small and is adjusted by
+	/// `adapt_loading_distance` (up to `loading_distance`), see `AdaptiveLoadingDistance`.
+	/// `max_fps` is the frame rate limit (if any), frames are not considered slow because of it.
+	pub(crate) fn new(
+		loading_distance: f32,
+		margin_before_unloading: f32,
+		min_resident_duration: Duration,
+		adaptive: bool,
+		max_fps: Option<i32>,
+	) -> LoadingManager {
+		let adaptive = adaptive.then(|| {
+			let slow_frame_duration = max_fps
+				.filter(|&max_fps| max_fps > 0)
+				.map_or(1.0 / 30.0, |max_fps| (1.5 / max_fps as f32).max(1.0 / 30.0));
+			AdaptiveLoadingDistance {
+				max_loading_distance: loading_distance,
+				slow_frame_duration,
+				smoothed_frame_duration: 0.0,
+				time_since_last_change: Duration::ZERO,
+			}
+		});
 		LoadingManager {
 			loading_enabled: true,
-			loading_distance,
+			loading_distance: if adaptive.is_some() {
+				STARTING_LOADING_DISTANCE.min(loading_distance)
+			} else {
+				loading_distance
+			},
 			margin_before_unloading,
+			min_resident_duration,
 			front_high_priority: vec![],
 			front_low_priority: vec![],
 			front_too_far: vec![],
+			adaptive,
+		}
+	}
+
+	/// Grows the loading distance (by one chunk) when all the chunks in range are loaded,
+	/// shrinks it (by one chunk) when the frames take too long.
+	/// Does nothing if the loading distance is not adaptive.
+	pub(crate) fn adapt_loading_distance(
+		&mut self,
+		frame_duration: Duration,
+		worker_tasks: &WorkerTasksManager,
+		cd: ChunkDimensions,
+	) {
+		let Some(adaptive) = self.adaptive.as_mut() else {
+			return;
+		};
+		let frame_duration_seconds = frame_duration.as_secs_f32();
+		let approach = (frame_duration_seconds * 4.0).min(1.0);
+		adaptive.smoothed_frame_duration +=
+			(frame_duration_seconds - adaptive.smoothed_frame_duration) * approach;
+		adaptive.time_since_last_change += frame_duration;
+		if adaptive.time_since_last_change < LOADING_DISTANCE_CHANGE_COOLDOWN {
+			return;
+		}
+
+		let step = cd.edge as f32;
+		let frames_are_slow = adaptive.smoothed_frame_duration > adaptive.slow_frame_duration;
+		// Some room is left between slow and fast so that it does not keep going back and forth.
+		let frames_are_fast = adaptive.smoothed_frame_duration < adaptive.slow_frame_duration * 0.7;
+		let loading_is_done = self.front_high_priority.is_empty()
+			&& self.front_low_priority.is_empty()
+			&& !worker_tasks.is_loading_any_chunk();
+		let new_loading_distance = if frames_are_slow {
+			(self.loading_distance - step).max(MIN_LOADING_DISTANCE)
+		} else if frames_are_fast && loading_is_done {
+			(self.loading_distance + step).min(adaptive.max_loading_distance)
+		} else {
+			self.loading_distance
+		};
+		if new_loading_distance > self.loading_distance {
+			// Some chunks that were too far may now be in range.
+			self.front_high_priority.append(&mut self.front_too_far);
+		}
+		if new_loading_distance != self.loading_distance {
+			self.loading_distance = new_loading_distance;
+			adaptive.time_since_last_change = Duration::ZERO;
 		}
 	}
 
+	pub(crate) fn unloading_distance(&self) -> f32 {
+		self.loading_distance + self.margin_before_unloading
+	}
+
 	#[allow(clippy::too_many_arguments)]
 	pub(crate) fn handle_loading(
 		&mut self,
@@ -54,6 +160,7 @@ impl LoadingManager {
 		world_generator: &Arc<dyn WorldGenerator + Sync + Send>,
 		block_type_table: &Arc<BlockTypeTable>,
 		save: Option<&Arc<Save>>,
+		chunk_cache: Option<&Arc<ChunkCache>>,
 		id_generator: &Arc<IdGenerator>,
 	) {
 		if !self.loading_enabled {
@@ -67,10 +174,7 @@ impl LoadingManager {
 		}
 
 		let loading_distance_in_chunks = self.loading_distance / chunk_grid.cd().edge as f32;
-		let unloading_distance_in_chunks = {
-			let unloading_distance = self.loading_distance + self.margin_before_unloading;
-			unloading_distance / chunk_grid.cd().edge as f32
-		};
+		let unloading_distance_in_chunks = self.unloading_distance() / chunk_grid.cd().edge as f32;
 
 		if self.front_high_priority.is_empty() {
 			self.front_high_priority.append(&mut self.front_low_priority);
@@ -150,6 +254,7 @@ impl LoadingManager {
 					world_generator: world_generator.clone(),
 					block_type_table: block_type_table.clone(),
 					save: save.cloned(),
+					chunk_cache: chunk_cache.cloned(),
 					cd: chunk_grid.cd(),
 				};
 				worker_tasks.run_chunk_loading_task(
@@ -200,5 +305,6 @@ pub(crate) struct DataForChunkLoading {
 	pub(crate) world_generator: Arc<dyn WorldGenerator + Sync + Send>,
 	pub(crate) block_type_table: Arc<BlockTypeTable>,
 	pub(crate) save: Option<Arc<Save>>,
+	pub(crate) chunk_cache: Option<Arc<ChunkCache>>,
 	pub(crate) cd: ChunkDimensions,
 }
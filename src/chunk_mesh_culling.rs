@@ -0,0 +1,170 @@
+//! Frustum culling of the chunk meshes on the GPU.
+//!
+//! The bounds and vertex counts of the chunk meshes are put in a buffer, then a compute pass
+//! writes the arguments of the draw call of each chunk mesh to an indirect buffer, with no
+//! instance to draw if the mesh is out of view. The block render passes draw the meshes with
+//! `draw_indirect` so the culling is entirely decided on the GPU and the CPU does not have to test
+//! every chunk against the view frustum every frame.
+
+use std::mem::size_of;
+
+use crate::{
+	chunk_meshing::ChunkMesh,
+	shaders::chunk_mesh_culling::{compute_pipeline_and_bind_group_layout, ChunkMeshMetadataPod},
+};
+
+/// Must match the workgroup size of the compute shader.
+const WORKGROUP_SIZE: u32 = 64;
+/// The buffers start with room for that many chunk meshes, and double in size when needed.
+const INITIAL_CAPACITY: usize = 1024;
+
+pub(crate) struct ChunkMeshCulling {
+	compute_pipeline: wgpu::ComputePipeline,
+	bind_group_layout: wgpu::BindGroupLayout,
+	chunk_mesh_count_buffer: wgpu::Buffer,
+	metadata_buffer: wgpu::Buffer,
+	draw_args_buffer: wgpu::Buffer,
+	/// How many chunk meshes the buffers have room for.
+	capacity: usize,
+	/// How many chunk meshes were given by the last call to `update_chunk_meshes`.
+	chunk_mesh_count: usize,
+	bind_group: wgpu::BindGroup,
+}
+
+impl ChunkMeshCulling {
+	/// The camera buffer is the one of the block shader, its matrix defines the view frustum.
+	pub(crate) fn new(device: &wgpu::Device, camera_buffer: &wgpu::Buffer) -> ChunkMeshCulling {
+		let (compute_pipeline, bind_group_layout) = compute_pipeline_and_bind_group_layout(device);
+		let chunk_mesh_count_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("Chunk Mesh Count Buffer"),
+			size: size_of::<[u32; 4]>() as u64,
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+			mapped_at_creation: false,
+		});
+		let (metadata_buffer, draw_args_buffer) = make_buffers(device, INITIAL_CAPACITY);
+		let bind_group = make_bind_group(
+			device,
+			&bind_group_layout,
+			[
+				camera_buffer,
+				&chunk_mesh_count_buffer,
+				&metadata_buffer,
+				&draw_args_buffer,
+			],
+		);
+		ChunkMeshCulling {
+			compute_pipeline,
+			bind_group_layout,
+			chunk_mesh_count_buffer,
+			metadata_buffer,
+			draw_args_buffer,
+			capacity: INITIAL_CAPACITY,
+			chunk_mesh_count: 0,
+			bind_group,
+		}
+	}
+
+	/// Sends the metadata of the given chunk meshes to the GPU, they must be given in the order
+	/// in which they will be drawn. Must be called every frame before rendering.
+	pub(crate) fn update_chunk_meshes<'a>(
+		&mut self,
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		camera_buffer: &wgpu::Buffer,
+		chunk_meshes: impl Iterator<Item = &'a ChunkMesh>,
+	) {
+		let metadata: Vec<ChunkMeshMetadataPod> = chunk_meshes
+			.map(|mesh| ChunkMeshMetadataPod {
+				bounds_inf: mesh.bounds_inf.into(),
+				vertex_count: mesh.block_vertex_count,
+				bounds_sup: mesh.bounds_sup.into(),
+				_padding: 0,
+			})
+			.collect();
+		if metadata.len() > self.capacity {
+			self.capacity = metadata.len().next_power_of_two();
+			(self.metadata_buffer, self.draw_args_buffer) = make_buffers(device, self.capacity);
+			self.bind_group = make_bind_group(
+				device,
+				&self.bind_group_layout,
+				[
+					camera_buffer,
+					&self.chunk_mesh_count_buffer,
+					&self.metadata_buffer,
+					&self.draw_args_buffer,
+				],
+			);
+		}
+		self.chunk_mesh_count = metadata.len();
+		queue.write_buffer(
+			&self.chunk_mesh_count_buffer,
+			0,
+			bytemuck::cast_slice(&[metadata.len() as u32, 0, 0, 0]),
+		);
+		queue.write_buffer(&self.metadata_buffer, 0, bytemuck::cast_slice(&metadata));
+	}
+
+	/// Fills the indirect buffer with the draw call arguments of the chunk meshes.
+	pub(crate) fn cull(&self, encoder: &mut wgpu::CommandEncoder) {
+		if self.chunk_mesh_count == 0 {
+			return;
+		}
+		let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+			label: Some("Compute Pass to cull the chunk meshes"),
+			timestamp_writes: None,
+		});
+		compute_pass.set_pipeline(&self.compute_pipeline);
+		compute_pass.set_bind_group(0, &self.bind_group, &[]);
+		compute_pass.dispatch_workgroups(
+			(self.chunk_mesh_count as u32).div_ceil(WORKGROUP_SIZE),
+			1,
+			1,
+		);
+	}
+
+	/// The indirect buffer and the offset in it of the draw call arguments of the chunk mesh
+	/// that has the given index in the order given to `update_chunk_meshes`.
+	pub(crate) fn draw_args(&self, chunk_mesh_index: usize) -> Option<(&wgpu::Buffer, u64)> {
+		(chunk_mesh_index < self.chunk_mesh_count).then(|| {
+			let offset = (chunk_mesh_index * size_of::<wgpu::util::DrawIndirectArgs>()) as u64;
+			(&self.draw_args_buffer, offset)
+		})
+	}
+}
+
+fn make_buffers(device: &wgpu::Device, capacity: usize) -> (wgpu::Buffer, wgpu::Buffer) {
+	let metadata_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+		label: Some("Chunk Mesh Metadata Buffer"),
+		size: (size_of::<ChunkMeshMetadataPod>() * capacity) as u64,
+		usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+		mapped_at_creation: false,
+	});
+	let draw_args_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+		label: Some("Chunk Mesh Draw Args Buffer"),
+		size: (size_of::<wgpu::util::DrawIndirectArgs>() * capacity) as u64,
+		usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT,
+		mapped_at_creation: false,
+	});
+	(metadata_buffer, draw_args_buffer)
+}
+
+/// The buffers are given in the order of their bindings.
+fn make_bind_group(
+	device: &wgpu::Device,
+	bind_group_layout: &wgpu::BindGroupLayout,
+	buffers: [&wgpu::Buffer; 4],
+) -> wgpu::BindGroup {
+	let entries: Vec<_> = buffers
+		.iter()
+		.enumerate()
+		.map(|(binding, buffer)| wgpu::BindGroupEntry {
+			binding: binding as u32,
+			resource: buffer.as_entire_binding(),
+		})
+		.collect();
+	device.create_bind_group(&wgpu::BindGroupDescriptor {
+		label: Some("Chunk Mesh Culling Shader Bind Group"),
+		layout: bind_group_layout,
+		entries: &entries,
+	})
+}
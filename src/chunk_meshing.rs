@@ -1,45 +1,84 @@
-use std::sync::Arc;
+use std::{
+	sync::Arc,
+	time::{Duration, Instant},
+};
 
 use cgmath::{EuclideanSpace, InnerSpace};
+use fxhash::FxHashMap;
 use wgpu::util::DeviceExt;
 
 use crate::{
-	block_types::{BlockType, BlockTypeTable},
+	block_types::{BlockType, BlockTypeTable, DOOR_PANEL_THICKNESS, MAX_LAYER_COUNT},
 	chunk_blocks::{BlockData, ChunkBlocks},
-	chunks::ChunkGrid,
+	chunks::{ChunkGrid, ChunkGridSnapshot},
 	coords::{
 		iter_3d_cube_center_radius, AxisOrientation, BitCube3, BitCube3Coords, BlockCoords,
 		ChunkCoords, ChunkCoordsSpan, ChunkDimensions, NonOrientedAxis, OrientedAxis,
 	},
+	deferred_lighting::PointLight,
 	font::{self, Font},
-	shaders::block::BlockVertexPod,
+	shaders::block::{BlockVertexPod, MATERIAL_FLAG_BILLBOARD, MATERIAL_FLAG_WATER_SURFACE},
 };
 
 /// All the data that is needed to generate the mesh of a chunk.
 pub(crate) struct DataForChunkMeshing {
 	chunk_blocks: Arc<ChunkBlocks>,
-	opaqueness_layer_for_face_culling: OpaquenessLayerAroundChunk,
-	opaqueness_layer_for_ambiant_occlusion: OpaquenessLayerAroundChunk,
+	/// The chunk and its neighbors, the blocks around the chunk affect its mesh.
+	snapshot: ChunkGridSnapshot,
 	block_type_table: Arc<BlockTypeTable>,
 	font: Arc<Font>,
 }
 
 impl DataForChunkMeshing {
-	pub(crate) fn generate_mesh_vertices(self) -> Vec<BlockVertexPod> {
+	pub(crate) fn snapshot(&self) -> &ChunkGridSnapshot {
+		&self.snapshot
+	}
+
+	/// The positions of the vertices of the mesh are relative to that (see `ChunkMesh::offset`).
+	pub(crate) fn mesh_offset(&self) -> cgmath::Vector3<f32> {
+		self.chunk_blocks.coords_span.block_coords_inf().to_vec().map(|x| x as f32)
+	}
+
+	/// Also gives the lights emitted by the blocks of the chunk (with world positions).
+	pub(crate) fn generate_mesh_vertices_and_lights(self) -> (Vec<BlockVertexPod>, Vec<PointLight>) {
+		let chunk_coords = self.chunk_blocks.coords_span.chunk_coords;
+		let opaqueness_layer_for_face_culling = self.snapshot.get_opaqueness_layer_around_chunk(
+			chunk_coords,
+			true,
+			&self.block_type_table,
+		);
+		let opaqueness_layer_for_ambiant_occlusion = self.snapshot.get_opaqueness_layer_around_chunk(
+			chunk_coords,
+			false,
+			&self.block_type_table,
+		);
 		let is_opaque = |coords: BlockCoords, for_ambiant_occlusion: bool| {
 			if let Some(block) = self.chunk_blocks.get(coords) {
 				self.block_type_table.get(block.type_id).unwrap().is_opaque()
 			} else if for_ambiant_occlusion {
-				self.opaqueness_layer_for_ambiant_occlusion.get(coords).unwrap()
+				opaqueness_layer_for_ambiant_occlusion.get(coords).unwrap()
 			} else {
-				self.opaqueness_layer_for_face_culling.get(coords).unwrap()
+				opaqueness_layer_for_face_culling.get(coords).unwrap()
 			}
 		};
 
+		let block_light = self.snapshot.block_light_around_chunk(&self.block_type_table);
+
+		// Neighbors outside of the chunk are not known here, they are considered different.
+		let is_same_liquid = |coords: BlockCoords, liquid_type_id| {
+			self.chunk_blocks.get(coords).is_some_and(|block| block.type_id == liquid_type_id)
+		};
+
 		let mut block_vertices = Vec::new();
+		let mut lights = Vec::new();
 		for coords in self.chunk_blocks.coords_span.iter_coords() {
 			let block = self.chunk_blocks.get(coords).unwrap();
-			match self.block_type_table.get(block.type_id).unwrap() {
+			if let Some(emission) = self.block_type_table.light_emission(block.type_id) {
+				lights.push(emission.at(coords.map(|x| x as f32)));
+			}
+			let first_vertex_index_of_block = block_vertices.len();
+			let block_type = self.block_type_table.get(block.type_id).unwrap();
+			match block_type {
 				BlockType::Air => {},
 				BlockType::Solid { texture_coords_on_atlas } => {
 					let opacity_bit_cube_3_for_ambiant_occlusion = {
@@ -66,7 +105,8 @@ impl DataForChunkMeshing {
 						}
 					}
 				},
-				BlockType::XShaped { texture_coords_on_atlas } => {
+				BlockType::XShaped { texture_coords_on_atlas }
+				| BlockType::Tool { texture_coords_on_atlas, .. } => {
 					let opacity_bit_cube_3_for_ambiant_occlusion = {
 						let mut cube = BitCube3::new_zero();
 						for delta in iter_3d_cube_center_radius((0, 0, 0).into(), 2) {
@@ -75,12 +115,7 @@ impl DataForChunkMeshing {
 						}
 						cube
 					};
-					for vertices_offets_xy in [
-						[[false, false], [true, true]],
-						[[true, true], [false, false]],
-						[[true, false], [false, true]],
-						[[false, true], [true, false]],
-					] {
+					for vertices_offets_xy in XSHAPED_BLOCK_FACES_VERTICES_OFFSETS_XY {
 						generate_xshaped_block_face_mesh(
 							&mut block_vertices,
 							coords.map(|x| x as f32),
@@ -90,6 +125,13 @@ impl DataForChunkMeshing {
 						);
 					}
 				},
+				BlockType::Billboard { texture_coords_on_atlas } => {
+					generate_billboard_block_mesh(
+						&mut block_vertices,
+						coords.map(|x| x as f32),
+						*texture_coords_on_atlas,
+					);
+				},
 				BlockType::Text => {
 					let text = match block.data {
 						Some(BlockData::Text(text)) => text,
@@ -120,36 +162,345 @@ impl DataForChunkMeshing {
 								coords_in_atlas: simple_texture_vertex.coords_in_atlas,
 								normal: cgmath::vec3(0.0, dy, 0.0).into(),
 								ambiant_occlusion: 1.0,
+								material_flags: 0,
+								block_light: [0.0, 0.0, 0.0],
 							})
 						}
 					}
 				},
+				BlockType::Liquid { texture_coords_on_atlas } => {
+					let opacity_bit_cube_3_for_ambiant_occlusion = {
+						let mut cube = BitCube3::new_zero();
+						for delta in iter_3d_cube_center_radius((0, 0, 0).into(), 2) {
+							let neighbor_coords = coords + delta.to_vec();
+							cube.set(delta.into(), is_opaque(neighbor_coords, true));
+						}
+						cube
+					};
+					for direction in OrientedAxis::all_the_six_possible_directions() {
+						let is_covered_by_neighbor = {
+							let neighbor_coords = coords + direction.delta();
+							is_opaque(neighbor_coords, false)
+								|| is_same_liquid(neighbor_coords, block.type_id)
+						};
+						if !is_covered_by_neighbor {
+							generate_block_face_mesh(
+								&mut block_vertices,
+								direction,
+								coords.map(|x| x as f32),
+								opacity_bit_cube_3_for_ambiant_occlusion,
+								*texture_coords_on_atlas,
+							);
+						}
+					}
+				},
+				BlockType::Layer { texture_coords_on_atlas } => {
+					let opacity_bit_cube_3_for_ambiant_occlusion = {
+						let mut cube = BitCube3::new_zero();
+						for delta in iter_3d_cube_center_radius((0, 0, 0).into(), 2) {
+							let neighbor_coords = coords + delta.to_vec();
+							cube.set(delta.into(), is_opaque(neighbor_coords, true));
+						}
+						cube
+					};
+					// The top face is below the top of the block (unless the layers fill it),
+					// so it can only be hidden by what is above when it reaches it.
+					let is_full = block.layer_count() >= MAX_LAYER_COUNT;
+					for direction in OrientedAxis::all_the_six_possible_directions() {
+						let is_covered_by_neighbor = {
+							let neighbor_coords = coords + direction.delta();
+							(direction != OrientedAxis::Z_PLUS || is_full)
+								&& is_opaque(neighbor_coords, false)
+						};
+						if !is_covered_by_neighbor {
+							generate_block_face_mesh(
+								&mut block_vertices,
+								direction,
+								coords.map(|x| x as f32),
+								opacity_bit_cube_3_for_ambiant_occlusion,
+								*texture_coords_on_atlas,
+							);
+						}
+					}
+					squash_vertices_to_height(
+						&mut block_vertices[first_vertex_index_of_block..],
+						coords.z as f32 - 0.5,
+						block.layer_height(),
+					);
+				},
+				BlockType::Door { texture_coords_on_atlas }
+				| BlockType::Trapdoor { texture_coords_on_atlas } => {
+					let opacity_bit_cube_3_for_ambiant_occlusion = {
+						let mut cube = BitCube3::new_zero();
+						for delta in iter_3d_cube_center_radius((0, 0, 0).into(), 2) {
+							let neighbor_coords = coords + delta.to_vec();
+							cube.set(delta.into(), is_opaque(neighbor_coords, true));
+						}
+						cube
+					};
+					// Only the face of the panel that is on the side of the block can be hidden,
+					// the other faces of the panel are inside the block.
+					let panel_side = block.door_panel_side(block_type).unwrap();
+					for direction in OrientedAxis::all_the_six_possible_directions() {
+						let is_covered_by_neighbor = {
+							let neighbor_coords = coords + direction.delta();
+							direction == panel_side && is_opaque(neighbor_coords, false)
+						};
+						if !is_covered_by_neighbor {
+							generate_block_face_mesh(
+								&mut block_vertices,
+								direction,
+								coords.map(|x| x as f32),
+								opacity_bit_cube_3_for_ambiant_occlusion,
+								*texture_coords_on_atlas,
+							);
+						}
+					}
+					squash_vertices_to_side(
+						&mut block_vertices[first_vertex_index_of_block..],
+						coords.map(|x| x as f32),
+						panel_side,
+						DOOR_PANEL_THICKNESS,
+					);
+				},
+			}
+
+			// Animated blocks get their material flags (see `BlockVertexPod::material_flags`).
+			// X-shaped blocks only sway at the top (they stay attached to what they stand on),
+			// and only the top of the top liquid block of a column is a surface with waves.
+			let mut material_flags = self.block_type_table.material_flags(block.type_id);
+			let is_liquid_below_more_liquid = matches!(block_type, BlockType::Liquid { .. })
+				&& is_same_liquid(coords + cgmath::vec3(0, 0, 1), block.type_id);
+			if is_liquid_below_more_liquid {
+				material_flags = 0;
+			}
+			if material_flags != 0 {
+				let only_top_vertices = !matches!(block_type, BlockType::Solid { .. });
+				let top_z = coords.z as f32 + 0.5;
+				for vertex in block_vertices[first_vertex_index_of_block..].iter_mut() {
+					if !only_top_vertices || vertex.position[2] > top_z - 0.01 {
+						vertex.material_flags |= material_flags;
+					}
+				}
+			}
+
+			// The faces of solid and liquid blocks get the block light of the space they face,
+			// the other blocks get the block light of their own space.
+			let lit_by_faced_space = matches!(
+				block_type,
+				BlockType::Solid { .. } | BlockType::Liquid { .. }
+			);
+			for vertex in block_vertices[first_vertex_index_of_block..].iter_mut() {
+				let lit_coords = if lit_by_faced_space {
+					coords + cgmath::Vector3::from(vertex.normal).map(|x| x.round() as i32)
+				} else {
+					coords
+				};
+				vertex.block_light = block_light.get_as_color(lit_coords);
 			}
 		}
-		block_vertices
+
+		// The offset is added back by the shaders, the positions stay small enough to keep all
+		// of their precision no matter how far from the origin the chunk is.
+		let offset = self.mesh_offset();
+		for vertex in block_vertices.iter_mut() {
+			vertex.position = (cgmath::Vector3::from(vertex.position) - offset).into();
+		}
+		(block_vertices, lights)
 	}
 }
 
 pub(crate) struct ChunkMesh {
 	pub(crate) block_vertex_count: u32,
 	pub(crate) block_vertex_buffer: wgpu::Buffer,
+	/// The positions of the vertices are relative to that, the shaders get it from `ChunkInstances`.
+	pub(crate) offset: cgmath::Vector3<f32>,
+	/// Lights emitted by blocks of the chunk, they are here because they change exactly when
+	/// the mesh has to be regenerated.
+	pub(crate) lights: Vec<PointLight>,
+	/// How long it took to generate this mesh (on a worker thread), for debugging purposes.
+	pub(crate) meshing_duration: Duration,
+	/// Bounding box of the vertices (with some margin for the animations done by the shader),
+	/// used to cull the meshes that are out of view (see `chunk_mesh_culling`).
+	pub(crate) bounds_inf: cgmath::Point3<f32>,
+	pub(crate) bounds_sup: cgmath::Point3<f32>,
+	/// The height of most of the water surfaces of the mesh (if any), used to pick the plane
+	/// in which the water reflects the world (see `water_reflection`).
+	pub(crate) water_surface_z: Option<f32>,
+	/// When the chunk first got a mesh (so when it appeared on screen), if it is to fade in,
+	/// see `ChunkGrid::add_chunk_meshing_results`.
+	pub(crate) appeared_at: Option<Instant>,
 }
 
+/// How long it takes for a chunk that just got meshed to fade in (instead of just popping into
+/// existence, which is rather distracting when a lot of chunks are being loaded).
+const FADE_IN_DURATION: Duration = Duration::from_millis(300);
+
 impl ChunkMesh {
-	pub(crate) fn from_vertices(
+	pub(crate) fn from_vertices_and_lights(
 		device: &wgpu::Device,
 		block_vertices: Vec<BlockVertexPod>,
+		offset: cgmath::Vector3<f32>,
+		lights: Vec<PointLight>,
+		meshing_duration: Duration,
 	) -> ChunkMesh {
 		let block_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
 			label: Some("Block Vertex Buffer"),
 			contents: bytemuck::cast_slice(&block_vertices),
 			usage: wgpu::BufferUsages::VERTEX,
 		});
+		let mut bounds_inf = cgmath::point3(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+		let mut bounds_sup = cgmath::point3(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+		for vertex in block_vertices.iter() {
+			// Billboard vertices are on the axis of their billboard, they get placed on the sides
+			// by the shader.
+			let is_billboard = vertex.material_flags & MATERIAL_FLAG_BILLBOARD != 0;
+			let horizontal_margin = if is_billboard {
+				vertex.normal[0].abs()
+			} else {
+				0.0
+			};
+			for axis in 0..3 {
+				let margin = if axis == 2 { 0.0 } else { horizontal_margin };
+				bounds_inf[axis] = bounds_inf[axis].min(vertex.position[axis] - margin);
+				bounds_sup[axis] = bounds_sup[axis].max(vertex.position[axis] + margin);
+			}
+		}
+		// The shader moves some vertices a bit (like the swaying foliage and the water waves).
+		let animation_margin = cgmath::vec3(0.2, 0.2, 0.2);
+		// Water surfaces are at the top of blocks, so their heights are multiples of one half.
+		let mut water_surface_counts: FxHashMap<i32, usize> = FxHashMap::default();
+		for vertex in block_vertices.iter() {
+			let is_water_surface =
+				vertex.material_flags & MATERIAL_FLAG_WATER_SURFACE != 0 && vertex.normal[2] > 0.5;
+			if is_water_surface {
+				*water_surface_counts.entry((vertex.position[2] * 2.0).round() as i32).or_default() +=
+					1;
+			}
+		}
+		let water_surface_z = water_surface_counts
+			.into_iter()
+			.max_by_key(|&(double_z, count)| (count, double_z))
+			.map(|(double_z, _count)| double_z as f32 / 2.0 + offset.z);
 		ChunkMesh {
 			block_vertex_count: block_vertices.len() as u32,
 			block_vertex_buffer,
+			offset,
+			lights,
+			meshing_duration,
+			bounds_inf: bounds_inf + offset - animation_margin,
+			bounds_sup: bounds_sup + offset + animation_margin,
+			water_surface_z,
+			appeared_at: None,
 		}
 	}
+
+	/// Goes from 0 (invisible) to 1 (fully there) as the chunk fades in, see `ChunkInstancePod`.
+	pub(crate) fn fade_in_progress(&self, now: Instant) -> f32 {
+		self.appeared_at.map_or(1.0, |appeared_at| {
+			let elapsed = now.saturating_duration_since(appeared_at);
+			(elapsed.as_secs_f32() / FADE_IN_DURATION.as_secs_f32()).min(1.0)
+		})
+	}
+}
+
+/// The mesh of a block on its own, centered on the origin and with all its faces (as there is
+/// no neighbor to hide them or to darken its corners), like the block held by the player.
+/// Text blocks get no mesh (their mesh depends on their text).
+pub(crate) fn generate_lone_block_mesh(block_type: &BlockType) -> Vec<BlockVertexPod> {
+	let mut vertices = vec![];
+	let block_center = cgmath::point3(0.0, 0.0, 0.0);
+	let no_neighbor = BitCube3::new_zero();
+	match block_type {
+		BlockType::Air | BlockType::Text => {},
+		BlockType::Solid { texture_coords_on_atlas }
+		| BlockType::Liquid { texture_coords_on_atlas } => {
+			for direction in OrientedAxis::all_the_six_possible_directions() {
+				generate_block_face_mesh(
+					&mut vertices,
+					direction,
+					block_center,
+					no_neighbor,
+					*texture_coords_on_atlas,
+				);
+			}
+		},
+		// A lone layer is as thick as a few layers, a single one would be too thin to be seen.
+		BlockType::Layer { texture_coords_on_atlas } => {
+			for direction in OrientedAxis::all_the_six_possible_directions() {
+				generate_block_face_mesh(
+					&mut vertices,
+					direction,
+					block_center,
+					no_neighbor,
+					*texture_coords_on_atlas,
+				);
+			}
+			squash_vertices_to_height(&mut vertices, -0.5, 0.25);
+		},
+		// Closed, so a door stands on a side and a trapdoor lies on the bottom.
+		BlockType::Door { texture_coords_on_atlas }
+		| BlockType::Trapdoor { texture_coords_on_atlas } => {
+			for direction in OrientedAxis::all_the_six_possible_directions() {
+				generate_block_face_mesh(
+					&mut vertices,
+					direction,
+					block_center,
+					no_neighbor,
+					*texture_coords_on_atlas,
+				);
+			}
+			let panel_side = if matches!(block_type, BlockType::Door { .. }) {
+				OrientedAxis::X_PLUS
+			} else {
+				OrientedAxis::Z_MINUS
+			};
+			squash_vertices_to_side(
+				&mut vertices,
+				block_center,
+				panel_side,
+				DOOR_PANEL_THICKNESS,
+			);
+		},
+		// Billboards would need the shader to face the player, the crossed version will do.
+		BlockType::XShaped { texture_coords_on_atlas }
+		| BlockType::Billboard { texture_coords_on_atlas }
+		| BlockType::Tool { texture_coords_on_atlas, .. } => {
+			for vertices_offets_xy in XSHAPED_BLOCK_FACES_VERTICES_OFFSETS_XY {
+				generate_xshaped_block_face_mesh(
+					&mut vertices,
+					block_center,
+					no_neighbor,
+					vertices_offets_xy,
+					*texture_coords_on_atlas,
+				);
+			}
+		},
+	}
+	vertices
+}
+
+/// Squashes the given vertices of a block (whose bottom is at `bottom_z`) vertically so that
+/// its height becomes the given height (in blocks), like for the layers of layer blocks.
+fn squash_vertices_to_height(vertices: &mut [BlockVertexPod], bottom_z: f32, height: f32) {
+	for vertex in vertices.iter_mut() {
+		vertex.position[2] = bottom_z + (vertex.position[2] - bottom_z) * height;
+	}
+}
+
+/// Squashes the given vertices of a block towards one of its sides so that the block becomes
+/// a panel against that side with the given thickness (in blocks), like for door panels.
+fn squash_vertices_to_side(
+	vertices: &mut [BlockVertexPod],
+	block_center: cgmath::Point3<f32>,
+	side: OrientedAxis,
+	thickness: f32,
+) {
+	let axis_i = side.axis.index();
+	let side_coord = block_center[axis_i] + 0.5 * side.orientation.sign() as f32;
+	for vertex in vertices.iter_mut() {
+		vertex.position[axis_i] = side_coord + (vertex.position[axis_i] - side_coord) * thickness;
+	}
 }
 
 /// Generate the mesh of a face of a block, adding it to `vertices`.
@@ -295,6 +646,8 @@ fn generate_block_face_mesh(
 			coords_in_atlas: coords_in_atlas_array[index].into(),
 			normal,
 			ambiant_occlusion: ambiant_occlusion_array[index],
+			material_flags: 0,
+			block_light: [0.0, 0.0, 0.0],
 		});
 	};
 	if !reverse_order {
@@ -308,6 +661,15 @@ fn generate_block_face_mesh(
 	}
 }
 
+/// The faces of X-shaped blocks (both sides of the two crossed rectangles),
+/// see `generate_xshaped_block_face_mesh`.
+const XSHAPED_BLOCK_FACES_VERTICES_OFFSETS_XY: [[[bool; 2]; 2]; 4] = [
+	[[false, false], [true, true]],
+	[[true, true], [false, false]],
+	[[true, false], [false, true]],
+	[[false, true], [true, false]],
+];
+
 /// Generate one of the two faces in the mesh of an X-shaped block, adding it to `vertices`.
 fn generate_xshaped_block_face_mesh(
 	vertices: &mut Vec<BlockVertexPod>,
@@ -427,6 +789,8 @@ fn generate_xshaped_block_face_mesh(
 			coords_in_atlas: coords_in_atlas_array[index].into(),
 			normal: normal.into(),
 			ambiant_occlusion: ambiant_occlusion_array[index],
+			material_flags: 0,
+			block_light: [0.0, 0.0, 0.0],
 		});
 	};
 	if !reverse_order {
@@ -440,6 +804,42 @@ fn generate_xshaped_block_face_mesh(
 	}
 }
 
+/// Generate the mesh of a billboard block (a sprite that faces the player),
+/// adding it to `vertices`. The vertices are placed by the shader, see `MATERIAL_FLAG_BILLBOARD`.
+fn generate_billboard_block_mesh(
+	vertices: &mut Vec<BlockVertexPod>,
+	block_center: cgmath::Point3<f32>,
+	texture_coords_on_atlas: cgmath::Point2<i32>,
+) {
+	let texture_rect_in_atlas_xy: cgmath::Point2<f32> =
+		texture_coords_on_atlas.map(|x| x as f32) * (1.0 / 512.0);
+	let texture_rect_in_atlas_wh: cgmath::Vector2<f32> = cgmath::vec2(16.0, 16.0) * (1.0 / 512.0);
+
+	// Corners of the rectangle, as (rightward, upward) offsets from the block center.
+	// Like for X-shaped blocks, the top of the texture is at the bottom of the block.
+	let corners = [(-0.5, -0.5), (0.5, -0.5), (-0.5, 0.5), (0.5, 0.5)];
+	// Both sides are meshed (the back side is only seen from the sun, to cast shadows).
+	// Triangles must be counter-clockwise when seen from their side, see the other meshers.
+	for (side, indices) in [(1.0, [0, 1, 3, 0, 3, 2]), (-1.0, [0, 3, 1, 0, 2, 3])] {
+		for index in indices {
+			let (rightward, upward): (f32, f32) = corners[index];
+			let coords_in_atlas = texture_rect_in_atlas_xy
+				+ cgmath::vec2(
+					texture_rect_in_atlas_wh.x * (rightward + 0.5),
+					texture_rect_in_atlas_wh.y * (upward + 0.5),
+				);
+			vertices.push(BlockVertexPod {
+				position: (block_center + cgmath::vec3(0.0, 0.0, upward)).into(),
+				coords_in_atlas: coords_in_atlas.into(),
+				normal: [rightward, side, 0.0],
+				ambiant_occlusion: 1.0,
+				material_flags: MATERIAL_FLAG_BILLBOARD,
+				block_light: [0.0, 0.0, 0.0],
+			});
+		}
+	}
+}
+
 /// Information about the opaqueness of each block
 /// contained in a 1-block-thick cubic layer around a chunk.
 ///
@@ -590,12 +990,12 @@ mod test {
 	}
 }
 
-impl ChunkGrid {
+impl ChunkGridSnapshot {
 	fn get_opaqueness_layer_around_chunk(
 		&self,
 		chunk_coords: ChunkCoords,
 		default_to_opaque: bool,
-		block_type_table: Arc<BlockTypeTable>,
+		block_type_table: &BlockTypeTable,
 	) -> OpaquenessLayerAroundChunk {
 		let surrounded_chunk_coords_span = ChunkCoordsSpan { cd: self.cd(), chunk_coords };
 		let mut layer = OpaquenessLayerAroundChunk::new(surrounded_chunk_coords_span);
@@ -627,7 +1027,9 @@ impl ChunkGrid {
 
 		layer
 	}
+}
 
+impl ChunkGrid {
 	pub(crate) fn get_data_for_chunk_meshing(
 		&self,
 		chunk_coords: ChunkCoords,
@@ -635,16 +1037,7 @@ impl ChunkGrid {
 		font: Arc<Font>,
 	) -> Option<DataForChunkMeshing> {
 		let chunk_blocks = Arc::clone(self.get_chunk_blocks(chunk_coords)?);
-		let opaqueness_layer_for_face_culling =
-			self.get_opaqueness_layer_around_chunk(chunk_coords, true, Arc::clone(&block_type_table));
-		let opaqueness_layer_for_ambiant_occlusion =
-			self.get_opaqueness_layer_around_chunk(chunk_coords, false, Arc::clone(&block_type_table));
-		Some(DataForChunkMeshing {
-			chunk_blocks,
-			opaqueness_layer_for_face_culling,
-			opaqueness_layer_for_ambiant_occlusion,
-			block_type_table,
-			font,
-		})
+		let snapshot = self.snapshot_around(chunk_coords, 1);
+		Some(DataForChunkMeshing { chunk_blocks, snapshot, block_type_table, font })
 	}
 }
@@ -1,6 +1,7 @@
 use std::{
 	collections::{hash_map::Entry, HashMap, HashSet},
 	sync::Arc,
+	time::{Duration, Instant},
 };
 
 use cgmath::{EuclideanSpace, MetricSpace};
@@ -9,17 +10,20 @@ use fxhash::{FxHashMap, FxHashSet};
 use crate::{
 	block_types::BlockTypeTable,
 	chunk_blocks::{Block, BlockView, ChunkBlocks, ChunkCullingInfo},
+	chunk_cache::ChunkCache,
 	chunk_meshing::ChunkMesh,
+	collision_shapes::CollisionShapes,
 	coords::{
 		iter_3d_cube_center_radius, iter_3d_rect_inf_sup_included, AlignedBox, BlockCoords,
 		ChunkCoords, ChunkCoordsSpan, ChunkDimensions, CubicCoordsSpan,
 	},
 	entities::{
 		ChunkEntities, EntitiesPhysicsStepCollector, EntitiesPhysicsStepResult, Entity,
-		ForPartManipulation, IdGenerator,
+		ForPartManipulation, Id as EntityId, IdGenerator, MountControl, PlayerHit, Tether,
 	},
 	entity_parts::PartTables,
 	font::Font,
+	heightmap::Heightmap,
 	saves::Save,
 	tasks::WorkerTasksManager,
 	threadpool::ThreadPool,
@@ -29,18 +33,35 @@ pub(crate) struct ChunkGrid {
 	cd: ChunkDimensions,
 	/// The block data for each loaded chunk.
 	blocks_map: FxHashMap<ChunkCoords, Arc<ChunkBlocks>>,
+	/// Incremented at every change to `blocks_map` (loading, unloading or modifying a chunk),
+	/// so that snapshots can tell cheaply that they are up to date.
+	blocks_generation: u64,
 	/// The culling data for each loaded chunk that hadn't underwent modification since loading.
 	// TODO: Remove it? This map is never used.
 	culling_info_map: FxHashMap<ChunkCoords, ChunkCullingInfo>,
+	/// The highest opaque block of each column of blocks, see `top_solid_z`.
+	heightmap: Heightmap,
+	/// The opaque blocks of each chunk merged into boxes, see `is_any_block_opaque_in`.
+	collision_shapes: CollisionShapes,
 	/// The mesh for each chunk that needs one.
 	mesh_map: FxHashMap<ChunkCoords, ChunkMesh>,
+	/// The loaded chunks that already got the results of a meshing once, the others have yet to
+	/// appear on screen and shall fade in when they do.
+	already_meshed_set: FxHashSet<ChunkCoords>,
 	/// The chunks that should be checked for remeshing.
 	remeshing_required_set: FxHashSet<ChunkCoords>,
-	/// The entities in chunks, for each chunk that has some.
+	/// The entities in chunks, for each chunk that has some (loaded or not, see `ChunkEntities`
+	/// about which chunk owns an entity).
 	entities_map: FxHashMap<ChunkCoords, ChunkEntities>,
 	/// The chunks that were already generated once
 	/// (and thus that shall not have their entities generated again).
 	already_generated_set: FxHashSet<ChunkCoords>,
+	/// When each loaded chunk was loaded, a chunk stays loaded for a while even if it gets too far
+	/// (see `unload_chunks_too_far`).
+	loaded_at: FxHashMap<ChunkCoords, Instant>,
+	/// The blocks that were set since the last `take_block_updates`, the blocks around them
+	/// may have to react (see `signals`).
+	block_updates: Vec<BlockCoords>,
 }
 
 impl ChunkGrid {
@@ -51,11 +72,17 @@ impl ChunkGrid {
 		ChunkGrid {
 			cd,
 			blocks_map: HashMap::default(),
+			blocks_generation: 0,
 			culling_info_map: HashMap::default(),
+			heightmap: Heightmap::new(cd),
+			collision_shapes: CollisionShapes::new(),
 			mesh_map: HashMap::default(),
+			already_meshed_set: HashSet::default(),
 			remeshing_required_set: HashSet::default(),
 			entities_map: HashMap::default(),
 			already_generated_set: already_generated_set.unwrap_or_default(),
+			block_updates: vec![],
+			loaded_at: HashMap::default(),
 		}
 	}
 
@@ -70,6 +97,10 @@ impl ChunkGrid {
 	pub(crate) fn was_already_generated_before(&self, chunk_coords: ChunkCoords) -> bool {
 		self.already_generated_set.contains(&chunk_coords)
 	}
+	/// For chunks that were generated without being loaded (see `pregen`).
+	pub(crate) fn mark_as_generated(&mut self, chunk_coords: ChunkCoords) {
+		self.already_generated_set.insert(chunk_coords);
+	}
 	pub(crate) fn set_of_already_generated_chunks(&self) -> &FxHashSet<ChunkCoords> {
 		&self.already_generated_set
 	}
@@ -143,17 +174,51 @@ impl ChunkGrid {
 		self.mesh_map.len()
 	}
 
+	/// Is the chunk loaded and meshed (chunks of only air don't need to be meshed)?
+	pub(crate) fn is_meshed(&self, chunk_coords: ChunkCoords) -> bool {
+		self.already_meshed_set.contains(&chunk_coords)
+			|| self.blocks_map.get(&chunk_coords).is_some_and(|blocks| blocks.contains_only_air())
+	}
+
+	/// Is the chunk loaded with up to date collision shapes (so that it can be collided with)?
+	pub(crate) fn is_collidable(&self, chunk_coords: ChunkCoords) -> bool {
+		self.collision_shapes.get(chunk_coords).is_some()
+	}
+
 	pub(crate) fn iter_chunk_meshes(&self) -> impl Iterator<Item = &ChunkMesh> + '_ {
 		self.mesh_map.values()
 	}
 
+	/// The height of the water surface that is the closest to the given position (at the scale of
+	/// the chunk meshes), this is the plane in which the water reflects the world.
+	pub(crate) fn dominant_water_plane_z(&self, position: cgmath::Point3<f32>) -> Option<f32> {
+		self
+			.mesh_map
+			.values()
+			.filter_map(|mesh| {
+				let water_surface_z = mesh.water_surface_z?;
+				let center = mesh.bounds_inf.midpoint(mesh.bounds_sup);
+				Some((center.distance2(position), water_surface_z))
+			})
+			.min_by(|(distance_a, _), (distance_b, _)| distance_a.total_cmp(distance_b))
+			.map(|(_distance, water_surface_z)| water_surface_z)
+	}
+
 	pub(crate) fn add_chunk_meshing_results(
 		&mut self,
 		chunk_coords: ChunkCoords,
 		chunk_mesh: Option<ChunkMesh>,
 	) {
 		if self.is_loaded(chunk_coords) {
-			if let Some(chunk_mesh) = chunk_mesh {
+			let first_meshing = self.already_meshed_set.insert(chunk_coords);
+			if let Some(mut chunk_mesh) = chunk_mesh {
+				// Only chunks that were not on screen fade in, remeshing (due to a block being
+				// edited or to a neighbor chunk being loaded) must not make a chunk blink.
+				chunk_mesh.appeared_at = if first_meshing {
+					Some(Instant::now())
+				} else {
+					self.mesh_map.get(&chunk_coords).and_then(|old_mesh| old_mesh.appeared_at)
+				};
 				self.mesh_map.insert(chunk_coords, chunk_mesh);
 			} else {
 				self.mesh_map.remove(&chunk_coords);
@@ -175,9 +240,13 @@ impl ChunkGrid {
 			let mut chunk_blocks = Arc::unwrap_or_clone(chunk_blocks_arc);
 			chunk_blocks.set(coords, block);
 			self.blocks_map.insert(chunk_coords, Arc::new(chunk_blocks));
+			self.blocks_generation += 1;
+			self.block_updates.push(coords);
 
 			// "Clear out" now maybe-invalidated culling info.
 			self.culling_info_map.remove(&chunk_coords);
+			self.heightmap.mark_stale(chunk_coords);
+			self.collision_shapes.mark_stale(chunk_coords);
 		}
 	}
 
@@ -187,37 +256,210 @@ impl ChunkGrid {
 		block: Block,
 	) {
 		self.set_block_but_do_not_update_meshes(coords, block);
+		for chunk_coords in self.chunks_whose_meshes_touch_block(coords) {
+			self.require_remeshing(chunk_coords);
+		}
+	}
 
-		// Request a mesh update in all the chunks that the block touches (even with vertices),
-		// so all the chunks that contain any of the blocks in the 3x3x3 blocks cube around.
+	/// All the chunks that the block touches (even with vertices), so all the chunks that contain
+	/// any of the blocks in the 3x3x3 blocks cube around. Their meshes may depend on the block.
+	fn chunks_whose_meshes_touch_block(
+		&self,
+		coords: BlockCoords,
+	) -> impl Iterator<Item = ChunkCoords> {
 		let block_span = CubicCoordsSpan::with_center_and_radius(coords, 2);
 		let chunk_inf = self.cd.world_coords_to_containing_chunk_coords(block_span.inf);
 		let chunk_sup_included =
 			self.cd.world_coords_to_containing_chunk_coords(block_span.sup_included());
-		for chunk_coords in iter_3d_rect_inf_sup_included(chunk_inf, chunk_sup_included) {
+		iter_3d_rect_inf_sup_included(chunk_inf, chunk_sup_included)
+	}
+
+	/// Starts collecting block writes that can then be applied all at once
+	/// with `ActionOnWorld::ApplyEditBatch`.
+	pub(crate) fn edit_batch(&self) -> EditBatch {
+		EditBatch { cd: self.cd, writes: HashMap::default() }
+	}
+
+	/// Each modified chunk is copied (if shared) once and remeshed once,
+	/// instead of once per modified block.
+	fn apply_edit_batch(&mut self, edit_batch: EditBatch) {
+		let mut chunks_to_remesh: FxHashSet<ChunkCoords> = HashSet::default();
+		for (chunk_coords, writes) in edit_batch.writes {
+			let Some(chunk_blocks_arc) = self.blocks_map.remove(&chunk_coords) else {
+				// The chunk was unloaded since the batch was made.
+				continue;
+			};
+			let mut chunk_blocks = Arc::unwrap_or_clone(chunk_blocks_arc);
+			for (coords, block) in writes {
+				chunk_blocks.set(coords, block);
+				chunks_to_remesh.extend(self.chunks_whose_meshes_touch_block(coords));
+				self.block_updates.push(coords);
+			}
+			self.blocks_map.insert(chunk_coords, Arc::new(chunk_blocks));
+			self.culling_info_map.remove(&chunk_coords);
+			self.heightmap.mark_stale(chunk_coords);
+			self.collision_shapes.mark_stale(chunk_coords);
+			self.blocks_generation += 1;
+		}
+		for chunk_coords in chunks_to_remesh {
 			self.require_remeshing(chunk_coords);
 		}
 	}
 
+	/// The blocks that were set since the last call, in the order they were set.
+	pub(crate) fn take_block_updates(&mut self) -> Vec<BlockCoords> {
+		std::mem::take(&mut self.block_updates)
+	}
+
 	pub(crate) fn get_block(&self, coords: BlockCoords) -> Option<BlockView> {
 		let chunk_coords = self.cd.world_coords_to_containing_chunk_coords(coords);
 		let chunk_blocks = self.blocks_map.get(&chunk_coords)?;
 		Some(chunk_blocks.get(coords).unwrap())
 	}
 
+	/// Walks along the segment from `from` to `to`, is there no opaque block in the way?
+	/// Blocks in chunks that are not loaded block the sight.
+	pub(crate) fn is_line_of_sight_clear(
+		&self,
+		from: cgmath::Point3<f32>,
+		to: cgmath::Point3<f32>,
+		block_type_table: &BlockTypeTable,
+	) -> bool {
+		// Small enough steps to not miss blocks, except the ones only crossed by a corner.
+		let step_length = 0.25;
+		let step_count = (from.distance(to) / step_length).ceil().max(1.0) as usize;
+		(0..=step_count).all(|step_i| {
+			let position = from + (to - from) * (step_i as f32 / step_count as f32);
+			let coords = position.map(|x| x.round() as i32);
+			self
+				.get_block(coords)
+				.is_some_and(|block| !block_type_table.get(block.type_id).unwrap().is_opaque())
+		})
+	}
+
+	/// Takes a snapshot of the chunks in the cube of the given radius (in chunks) around
+	/// the given chunk, for a task that reads these from an other thread.
+	pub(crate) fn snapshot_around(
+		&self,
+		chunk_coords: ChunkCoords,
+		radius: i32,
+	) -> ChunkGridSnapshot {
+		let blocks_map = iter_3d_cube_center_radius(chunk_coords, radius + 1)
+			.map(|chunk_coords| (chunk_coords, self.blocks_map.get(&chunk_coords).cloned()))
+			.collect();
+		ChunkGridSnapshot { cd: self.cd, generation: self.blocks_generation, blocks_map }
+	}
+
+	/// Must be called regularly (with write access to the world) so that `top_solid_z` takes
+	/// into account the chunks that were loaded or edited since the last call.
+	pub(crate) fn update_heightmap(&mut self, block_type_table: &BlockTypeTable) {
+		let blocks_map = &self.blocks_map;
+		self.heightmap.update(
+			|chunk_coords| blocks_map.get(&chunk_coords).map(|chunk_blocks| chunk_blocks.as_ref()),
+			block_type_table,
+		);
+	}
+
+	/// The z coordinate of the highest opaque block of the column of blocks at the given x and y
+	/// (among the loaded chunks), `None` if there is no opaque block in the loaded part of the column.
+	/// Blocks below it do not see the sky, blocks above it do.
+	pub(crate) fn top_solid_z(&self, x: i32, y: i32) -> Option<i32> {
+		self.heightmap.top_solid_z(x, y)
+	}
+
+	/// Must be called regularly (with write access to the world) so that `is_any_block_opaque_in`
+	/// can use the shapes of the chunks that were loaded or edited since the last call.
+	pub(crate) fn update_collision_shapes(&mut self, block_type_table: &BlockTypeTable) {
+		let blocks_map = &self.blocks_map;
+		self.collision_shapes.update(
+			|chunk_coords| blocks_map.get(&chunk_coords).map(|chunk_blocks| chunk_blocks.as_ref()),
+			block_type_table,
+		);
+	}
+
+	/// The parts of the given span that are in each of the loaded chunks it overlaps.
+	fn split_span_by_chunk(
+		&self,
+		span: CubicCoordsSpan,
+	) -> impl Iterator<Item = (ChunkCoords, CubicCoordsSpan)> + '_ {
+		let chunk_inf = self.cd.world_coords_to_containing_chunk_coords(span.inf);
+		let chunk_sup_included = self.cd.world_coords_to_containing_chunk_coords(span.sup_included());
+		iter_3d_rect_inf_sup_included(chunk_inf, chunk_sup_included)
+			.filter(|chunk_coords| self.is_loaded(*chunk_coords))
+			.filter_map(move |chunk_coords| {
+				let chunk_span =
+					CubicCoordsSpan::from_chunk_span(ChunkCoordsSpan { cd: self.cd, chunk_coords });
+				Some((chunk_coords, span.intersection(&chunk_span)?))
+			})
+	}
+
+	/// Is any of the blocks of the span opaque? The blocks in chunks that are not loaded are not.
+	/// This is what physics uses to check for collisions, it is faster than looking at each block.
+	pub(crate) fn is_any_block_opaque_in(
+		&self,
+		span: CubicCoordsSpan,
+		block_type_table: &BlockTypeTable,
+	) -> bool {
+		self.split_span_by_chunk(span).any(|(chunk_coords, span_in_chunk)| {
+			if let Some(shapes) = self.collision_shapes.get(chunk_coords) {
+				shapes.is_any_block_opaque_in(&span_in_chunk)
+			} else {
+				// The chunk is stale, its blocks have to be looked at one by one.
+				let chunk_blocks = &self.blocks_map[&chunk_coords];
+				span_in_chunk.iter().any(|coords| {
+					let type_id = chunk_blocks.get(coords).unwrap().type_id;
+					block_type_table.get(type_id).unwrap().is_opaque()
+				})
+			}
+		})
+	}
+
+	/// Could any of the blocks of the span be a layer block (see `BlockType::Layer`)?
+	/// If not, then physics does not have to look for layers there.
+	pub(crate) fn may_have_layers_in(&self, span: CubicCoordsSpan) -> bool {
+		self.split_span_by_chunk(span).any(|(chunk_coords, _span_in_chunk)| {
+			self.collision_shapes.get(chunk_coords).is_none_or(|shapes| shapes.has_layers())
+		})
+	}
+
+	/// Could any of the blocks of the span be a door or trapdoor (see `BlockType::is_door`)?
+	/// If not, then physics does not have to look for door panels there.
+	pub(crate) fn may_have_doors_in(&self, span: CubicCoordsSpan) -> bool {
+		self.split_span_by_chunk(span).any(|(chunk_coords, _span_in_chunk)| {
+			self.collision_shapes.get(chunk_coords).is_none_or(|shapes| shapes.has_doors())
+		})
+	}
+
 	pub(crate) fn count_chunks_that_have_blocks(&self) -> usize {
 		self.blocks_map.len()
 	}
 
+	#[allow(clippy::too_many_arguments)]
 	fn run_entities_tasks(
 		self_arc: &Arc<ChunkGrid>,
 		worker_tasks: &mut WorkerTasksManager,
 		pool: &mut ThreadPool,
 		block_type_table: &Arc<BlockTypeTable>,
 		entity_physics_dt: std::time::Duration,
+		simulation_area: Option<(ChunkCoords, f32)>,
 		part_manipulation: ForPartManipulation,
 		id_generator: &Arc<IdGenerator>,
+		player_box: Option<AlignedBox>,
 	) -> EntitiesPhysicsStepCollector {
+		// The entities of the chunks that are too far from the player are not simulated,
+		// they are preserved as they are (frozen) until the player gets closer.
+		let simulation_area = simulation_area.map(|(center_chunk_coords, simulation_distance)| {
+			(
+				center_chunk_coords.map(|x| x as f32),
+				simulation_distance / self_arc.cd.edge as f32,
+			)
+		});
+		let is_simulated = |chunk_coords: ChunkCoords| {
+			simulation_area.is_none_or(|(center, simulation_distance_in_chunks)| {
+				chunk_coords.map(|x| x as f32).distance(center) <= simulation_distance_in_chunks
+			})
+		};
+
 		let number_of_tasks = 3;
 
 		let mut chunk_entities_to_run_for_each_task = vec![];
@@ -227,7 +469,7 @@ impl ChunkGrid {
 		let mut chunk_entities_to_preserve = vec![];
 		let mut which_task_to_give_chunk = 0;
 		for chunk_coords in self_arc.entities_map.keys().copied() {
-			if self_arc.is_loaded(chunk_coords) {
+			if self_arc.is_loaded(chunk_coords) && is_simulated(chunk_coords) {
 				chunk_entities_to_run_for_each_task[which_task_to_give_chunk].push(chunk_coords);
 				which_task_to_give_chunk = (which_task_to_give_chunk + 1) % number_of_tasks;
 			} else {
@@ -245,6 +487,7 @@ impl ChunkGrid {
 				entity_physics_dt,
 				part_manipulation.clone(),
 				id_generator,
+				player_box.clone(),
 			);
 		}
 
@@ -261,8 +504,8 @@ impl ChunkGrid {
 		completed_step: EntitiesPhysicsStepCollector,
 		save: Option<&Arc<Save>>,
 		id_generator: &IdGenerator,
-	) {
-		let (mut next_entities_map, actions_on_world, chunk_entities_to_preserve) =
+	) -> Vec<PlayerHit> {
+		let (mut next_entities_map, actions_on_world, chunk_entities_to_preserve, player_hits) =
 			completed_step.into_complete_result();
 
 		std::mem::swap(&mut self.entities_map, &mut next_entities_map);
@@ -276,6 +519,8 @@ impl ChunkGrid {
 		for action_on_world in actions_on_world.into_iter() {
 			self.apply_actions_on_world(action_on_world, save, id_generator);
 		}
+
+		player_hits
 	}
 
 	pub(crate) fn can_entity_in_chunk_maybe_collide_with_box(
@@ -336,7 +581,28 @@ impl ChunkGrid {
 				// If there was a non-air block there before, then it is lost.
 				self.set_block_and_request_updates_to_meshes(coords, block);
 			},
+			ActionOnWorld::ApplyEditBatch(edit_batch) => self.apply_edit_batch(edit_batch),
 			ActionOnWorld::AddEntity(entity) => self.add_entity(entity, save),
+			ActionOnWorld::NameEntity { entity_id, chunk_coords, name } => {
+				if let Some(entity) = self.get_entity_mut_around(entity_id, chunk_coords) {
+					entity.set_name(name);
+				}
+			},
+			ActionOnWorld::HitEntity { entity_id, chunk_coords, damage, knockback } => {
+				if let Some(entity) = self.get_entity_mut_around(entity_id, chunk_coords) {
+					entity.hit(damage, knockback);
+				}
+			},
+			ActionOnWorld::SetMountControl { entity_id, chunk_coords, control } => {
+				if let Some(entity) = self.get_entity_mut_around(entity_id, chunk_coords) {
+					entity.set_mount_control(control);
+				}
+			},
+			ActionOnWorld::SetTether { entity_id, chunk_coords, tether } => {
+				if let Some(entity) = self.get_entity_mut_around(entity_id, chunk_coords) {
+					entity.set_tether(tether);
+				}
+			},
 			ActionOnWorld::AddChunkLoadingResults {
 				chunk_coords,
 				chunk_blocks,
@@ -353,8 +619,12 @@ impl ChunkGrid {
 					self.require_remeshing(neighbor_chunk_coords);
 				}
 			},
-			ActionOnWorld::AddChunkMeshingResults { chunk_coords, chunk_mesh } => {
+			ActionOnWorld::AddChunkMeshingResults { chunk_coords, chunk_mesh, snapshot } => {
 				self.add_chunk_meshing_results(chunk_coords, chunk_mesh);
+				if snapshot.is_stale(self) {
+					// The mesh is still better than no mesh until it is remeshed.
+					self.require_remeshing(chunk_coords);
+				}
 			},
 		}
 	}
@@ -407,6 +677,42 @@ impl ChunkGrid {
 		self.put_entity_in_chunk(chunk_coords, entity, save);
 	}
 
+	/// Looks for the entity in the given chunk and in the neighboring chunks
+	/// (as entities move, they may not be in the chunk they were in last time we checked).
+	pub(crate) fn get_entity_around(
+		&self,
+		entity_id: EntityId,
+		chunk_coords: ChunkCoords,
+	) -> Option<&Entity> {
+		iter_3d_cube_center_radius(chunk_coords, 2)
+			.filter_map(|neighbor_coords| self.iter_entities_in_chunk(neighbor_coords))
+			.flatten()
+			.find(|entity| entity.id() == entity_id)
+	}
+
+	fn get_entity_mut_around(
+		&mut self,
+		entity_id: EntityId,
+		chunk_coords: ChunkCoords,
+	) -> Option<&mut Entity> {
+		let chunk_coords_with_entity =
+			self.get_entity_around(entity_id, chunk_coords)?.chunk_coords(self.cd);
+		self.entities_map.get_mut(&chunk_coords_with_entity)?.get_entity_mut(entity_id)
+	}
+
+	/// Deletes the entities of the given chunk for which `should_be_removed` returns true,
+	/// returns how many were deleted.
+	pub(crate) fn remove_entities_in_chunk_that(
+		&mut self,
+		chunk_coords: ChunkCoords,
+		should_be_removed: impl FnMut(&Entity) -> bool,
+		part_tables: &PartTables,
+	) -> usize {
+		self.entities_map.get_mut(&chunk_coords).map_or(0, |chunk_entities| {
+			chunk_entities.remove_entities_that(should_be_removed, part_tables)
+		})
+	}
+
 	pub(crate) fn iter_entities(&self) -> impl Iterator<Item = &Entity> {
 		self.entities_map.values().flat_map(|chunk_entities| chunk_entities.iter_entities())
 	}
@@ -436,14 +742,18 @@ impl ChunkGrid {
 		chunk_entities: Option<ChunkEntities>,
 	) {
 		self.blocks_map.insert(chunk_coords, Arc::new(chunk_blocks));
+		self.blocks_generation += 1;
+		self.loaded_at.insert(chunk_coords, Instant::now());
 		self.culling_info_map.insert(chunk_coords, chunk_culling_info);
+		self.heightmap.mark_stale(chunk_coords);
+		self.collision_shapes.mark_stale(chunk_coords);
 		if let Some(chunk_entities) = chunk_entities {
 			self.add_chunk_entities(chunk_entities);
 		}
 		self.already_generated_set.insert(chunk_coords);
 	}
 
-	fn save_chunk(
+	pub(crate) fn save_chunk(
 		&self,
 		chunk_coords: ChunkCoords,
 		save: Option<&Arc<Save>>,
@@ -463,8 +773,16 @@ impl ChunkGrid {
 		}
 	}
 
+	/// The chunks that `save_all_chunks` saves (see `save_chunk`).
+	pub(crate) fn chunks_to_save(&self) -> Vec<ChunkCoords> {
+		// The chunks that are not loaded but that have entities (that moved there) are saved too.
+		let chunk_coords_set: FxHashSet<_> =
+			self.blocks_map.keys().copied().chain(self.entities_map.keys().copied()).collect();
+		chunk_coords_set.into_iter().collect()
+	}
+
 	pub(crate) fn save_all_chunks(&self, save: Option<&Arc<Save>>, only_save_modified_chunks: bool) {
-		for chunk_coords in self.blocks_map.keys().copied() {
+		for chunk_coords in self.chunks_to_save() {
 			self.save_chunk(chunk_coords, save, only_save_modified_chunks);
 		}
 	}
@@ -474,25 +792,45 @@ impl ChunkGrid {
 		chunk_coords: ChunkCoords,
 		save: Option<&Arc<Save>>,
 		only_save_modified_chunks: bool,
+		chunk_cache: Option<&Arc<ChunkCache>>,
 		part_tables: &PartTables,
 	) {
 		self.save_chunk(chunk_coords, save, only_save_modified_chunks);
+		if let (Some(chunk_cache), Some(chunk_blocks)) =
+			(chunk_cache, self.blocks_map.get(&chunk_coords))
+		{
+			let was_saved = save.is_some() && !only_save_modified_chunks;
+			if !was_saved && !chunk_blocks.was_modified_since_generation() {
+				chunk_cache.store(chunk_blocks);
+			}
+		}
 		self.blocks_map.remove(&chunk_coords);
+		self.blocks_generation += 1;
+		self.loaded_at.remove(&chunk_coords);
 		let chunk_entities = self.entities_map.remove(&chunk_coords);
 		if let Some(chunk_entities) = chunk_entities {
 			chunk_entities.handle_unloading(part_tables);
 		}
 		self.culling_info_map.remove(&chunk_coords);
+		self.heightmap.remove_chunk(chunk_coords);
+		self.collision_shapes.remove_chunk(chunk_coords);
 		self.mesh_map.remove(&chunk_coords);
+		self.already_meshed_set.remove(&chunk_coords);
 		self.remeshing_required_set.remove(&chunk_coords);
 	}
 
+	/// The chunks that were loaded less than `min_resident_duration` ago are kept for now,
+	/// so that going back and forth across the unloading distance does not unload chunks that
+	/// will have to be loaded again right away.
+	#[allow(clippy::too_many_arguments)]
 	pub(crate) fn unload_chunks_too_far(
 		&mut self,
 		player_chunk_coords: ChunkCoords,
 		unloading_distance_in_blocks: f32,
+		min_resident_duration: Duration,
 		save: Option<&Arc<Save>>,
 		only_save_modified_chunks: bool,
+		chunk_cache: Option<&Arc<ChunkCache>>,
 		part_tables: &PartTables,
 	) {
 		let unloading_distance_in_chunks = unloading_distance_in_blocks / self.cd.edge as f32;
@@ -502,8 +840,18 @@ impl ChunkGrid {
 		for chunk_coords in chunk_coords_list.into_iter() {
 			let dist_in_chunks =
 				chunk_coords.map(|x| x as f32).distance(player_chunk_coords.map(|x| x as f32));
-			if dist_in_chunks > unloading_distance_in_chunks {
-				self.unload_chunk(chunk_coords, save, only_save_modified_chunks, part_tables);
+			let loaded_recently = self
+				.loaded_at
+				.get(&chunk_coords)
+				.is_some_and(|loaded_at| loaded_at.elapsed() < min_resident_duration);
+			if dist_in_chunks > unloading_distance_in_chunks && !loaded_recently {
+				self.unload_chunk(
+					chunk_coords,
+					save,
+					only_save_modified_chunks,
+					chunk_cache,
+					part_tables,
+				);
 			}
 		}
 	}
@@ -512,11 +860,18 @@ impl ChunkGrid {
 		&mut self,
 		save: Option<&Arc<Save>>,
 		only_save_modified_chunks: bool,
+		chunk_cache: Option<&Arc<ChunkCache>>,
 		part_tables: &PartTables,
 	) {
 		let chunk_coords_list: Vec<_> = self.blocks_map.keys().copied().collect();
 		for chunk_coords in chunk_coords_list.into_iter() {
-			self.unload_chunk(chunk_coords, save, only_save_modified_chunks, part_tables);
+			self.unload_chunk(
+				chunk_coords,
+				save,
+				only_save_modified_chunks,
+				chunk_cache,
+				part_tables,
+			);
 		}
 	}
 }
@@ -533,7 +888,34 @@ pub(crate) enum ActionOnWorld {
 		block: Block,
 		coords: BlockCoords,
 	},
+	/// Many blocks placed at once (and if there were non-air blocks there before they are lost).
+	ApplyEditBatch(EditBatch),
 	AddEntity(Entity),
+	/// The entity was in the chunk at `chunk_coords` or near it (it may have moved a bit since).
+	NameEntity {
+		entity_id: EntityId,
+		chunk_coords: ChunkCoords,
+		name: String,
+	},
+	/// The entity is hurt and pushed away (it dies if it has no health left).
+	HitEntity {
+		entity_id: EntityId,
+		chunk_coords: ChunkCoords,
+		damage: u32,
+		knockback: cgmath::Vector3<f32>,
+	},
+	/// The entity is a mount, and its rider (the player) controls it (or stops controlling it).
+	SetMountControl {
+		entity_id: EntityId,
+		chunk_coords: ChunkCoords,
+		control: Option<MountControl>,
+	},
+	/// Tethers the entity to something (or unties it with `None`).
+	SetTether {
+		entity_id: EntityId,
+		chunk_coords: ChunkCoords,
+		tether: Option<Tether>,
+	},
 	AddChunkLoadingResults {
 		chunk_coords: ChunkCoords,
 		chunk_blocks: ChunkBlocks,
@@ -543,9 +925,85 @@ pub(crate) enum ActionOnWorld {
 	AddChunkMeshingResults {
 		chunk_coords: ChunkCoords,
 		chunk_mesh: Option<ChunkMesh>,
+		/// The blocks the mesh was made from.
+		snapshot: ChunkGridSnapshot,
 	},
 }
 
+/// A read-only view of the blocks of some chunks as they were when the snapshot was taken
+/// (see `ChunkGrid::snapshot_around`). It can be read from other threads without any access
+/// to the `ChunkGrid`, and taking it does not copy any block (the chunk blocks are shared,
+/// modifying them in the grid copies them instead).
+#[derive(Clone)]
+pub(crate) struct ChunkGridSnapshot {
+	cd: ChunkDimensions,
+	/// The `blocks_generation` of the grid when the snapshot was taken.
+	generation: u64,
+	/// For each chunk in the snapshot, its blocks if it was loaded.
+	blocks_map: FxHashMap<ChunkCoords, Option<Arc<ChunkBlocks>>>,
+}
+
+impl ChunkGridSnapshot {
+	pub(crate) fn cd(&self) -> ChunkDimensions {
+		self.cd
+	}
+
+	/// `None` if the block is not in a chunk of the snapshot or in a chunk that was not loaded.
+	pub(crate) fn get_block(&self, coords: BlockCoords) -> Option<BlockView<'_>> {
+		let chunk_coords = self.cd.world_coords_to_containing_chunk_coords(coords);
+		let chunk_blocks = self.blocks_map.get(&chunk_coords)?.as_ref()?;
+		Some(chunk_blocks.get(coords).unwrap())
+	}
+
+	/// The blocks of the chunks of the snapshot that were loaded.
+	pub(crate) fn iter_loaded_chunks(&self) -> impl Iterator<Item = &ChunkBlocks> {
+		self.blocks_map.values().filter_map(|chunk_blocks| chunk_blocks.as_deref())
+	}
+
+	/// Were the chunks of the snapshot modified (or loaded or unloaded) in the grid since
+	/// the snapshot was taken?
+	pub(crate) fn is_stale(&self, chunk_grid: &ChunkGrid) -> bool {
+		if self.generation == chunk_grid.blocks_generation {
+			// Nothing changed at all in the grid.
+			return false;
+		}
+		self.blocks_map.iter().any(|(chunk_coords, chunk_blocks)| {
+			match (chunk_blocks, chunk_grid.blocks_map.get(chunk_coords)) {
+				(Some(chunk_blocks), Some(current_chunk_blocks)) => {
+					!Arc::ptr_eq(chunk_blocks, current_chunk_blocks)
+				},
+				(None, None) => false,
+				_ => true,
+			}
+		})
+	}
+}
+
+/// Block writes collected by `ChunkGrid::edit_batch`, grouped by chunk.
+pub(crate) struct EditBatch {
+	cd: ChunkDimensions,
+	/// For each chunk, the writes in the order they were made (the last one wins).
+	writes: FxHashMap<ChunkCoords, Vec<(BlockCoords, Block)>>,
+}
+
+impl EditBatch {
+	pub(crate) fn set(&mut self, coords: BlockCoords, block: Block) {
+		let chunk_coords = self.cd.world_coords_to_containing_chunk_coords(coords);
+		self.writes.entry(chunk_coords).or_default().push((coords, block));
+	}
+
+	pub(crate) fn block_count(&self) -> usize {
+		self.writes.values().map(Vec::len).sum()
+	}
+
+	/// Adds the writes of the other batch after the writes of this batch.
+	pub(crate) fn append(&mut self, other: EditBatch) {
+		for (chunk_coords, mut writes) in other.writes {
+			self.writes.entry(chunk_coords).or_default().append(&mut writes);
+		}
+	}
+}
+
 /// The main thread holds the `ChunkGrid` but must be able to share it to threads sometimes.
 /// So it has two states:
 /// - Exclusively owned: grants write access.
@@ -567,11 +1025,22 @@ pub(crate) struct ChunkGridShareable {
 	/// This is `None` when the world is exclusively owned (because we apply the actions directly)
 	/// and is `Some` when the world is shared.
 	entities_step_collector: Option<EntitiesPhysicsStepCollector>,
+	/// The hits of the entities on the player during the entity physics steps that were applied,
+	/// for the game to apply them to the player (see `take_player_hits`).
+	player_hits: Vec<PlayerHit>,
 }
 
 impl ChunkGridShareable {
 	pub(crate) fn new(chunk_grid: ChunkGrid) -> ChunkGridShareable {
-		ChunkGridShareable { chunk_grid: Arc::new(chunk_grid), entities_step_collector: None }
+		ChunkGridShareable {
+			chunk_grid: Arc::new(chunk_grid),
+			entities_step_collector: None,
+			player_hits: vec![],
+		}
+	}
+
+	pub(crate) fn take_player_hits(&mut self) -> Vec<PlayerHit> {
+		std::mem::take(&mut self.player_hits)
 	}
 
 	pub(crate) fn get(&self) -> &ChunkGrid {
@@ -658,11 +1127,12 @@ impl ChunkGridShareable {
 		} else if self.can_become_exclusively_owned() {
 			// Let's apply the pending changes and make our exclusive ownership official.
 			if let Some(chunk_grid) = Arc::get_mut(&mut self.chunk_grid) {
-				chunk_grid.apply_completed_entities_step(
+				let player_hits = chunk_grid.apply_completed_entities_step(
 					self.entities_step_collector.take().unwrap(),
 					save,
 					id_generator,
 				);
+				self.player_hits.extend(player_hits);
 			} else {
 				panic!("We collected all the entities step results, there should be no more `Arc`s");
 			}
@@ -677,14 +1147,17 @@ impl ChunkGridShareable {
 	/// be pending now and applied later when we exclusively own the world again.
 	///
 	/// Returns whether or not that could be done.
+	#[allow(clippy::too_many_arguments)]
 	pub(crate) fn if_owned_then_share_to_run_entities_tasks(
 		&mut self,
 		worker_tasks: &mut WorkerTasksManager,
 		pool: &mut ThreadPool,
 		block_type_table: &Arc<BlockTypeTable>,
 		entity_physics_dt: std::time::Duration,
+		simulation_area: Option<(ChunkCoords, f32)>,
 		part_manipulation: ForPartManipulation,
 		id_generator: &Arc<IdGenerator>,
+		player_box: Option<AlignedBox>,
 	) -> bool {
 		if self.is_exclusively_owned() {
 			let entities_step_collector = ChunkGrid::run_entities_tasks(
@@ -693,8 +1166,10 @@ impl ChunkGridShareable {
 				pool,
 				block_type_table,
 				entity_physics_dt,
+				simulation_area,
 				part_manipulation,
 				id_generator,
+				player_box,
 			);
 			self.entities_step_collector = Some(entities_step_collector);
 			true
@@ -1,6 +1,13 @@
-use clap::Parser;
+use clap::{parser::ValueSource, CommandFactory, FromArgMatches, Parser};
 
-use crate::{game_init::PlayingMode, world_gen::WhichWorldGenerator};
+use crate::{
+	color_grading::Tonemapper,
+	config::Config,
+	display::{FullscreenMode, PresentModeChoice},
+	game_init::PlayingMode,
+	replay::PlaybackMode,
+	world_gen::WhichWorldGenerator,
+};
 
 #[derive(Parser)]
 #[command(color = clap::ColorChoice::Auto)]
@@ -36,6 +43,19 @@ pub(crate) struct CommandLineSettings {
 	)]
 	pub(crate) which_world_generator: WhichWorldGenerator,
 
+	/// Generates the terrain from a grayscale heightmap image (PNG) instead of using `--gen`.
+	/// One pixel is one column of blocks.
+	#[arg(long, value_name = "PATH")]
+	pub(crate) heightmap: Option<std::path::PathBuf>,
+
+	/// Height (in blocks) of the white pixels of the `--heightmap` image.
+	#[arg(long, default_value_t = 64.0, value_name = "HEIGHT")]
+	pub(crate) heightmap_scale: f32,
+
+	/// Beyond the `--heightmap` image, extends its borders instead of repeating it.
+	#[arg(long)]
+	pub(crate) heightmap_clamp: bool,
+
 	/// Prints the list of available world generators.
 	#[arg(long = "gen-names")]
 	pub(crate) display_world_generator_possible_names: bool,
@@ -49,6 +69,43 @@ pub(crate) struct CommandLineSettings {
 	)]
 	pub(crate) loading_distance: f32,
 
+	/// Loads up to the loading distance from the start and keeps it, instead of starting with
+	/// a small loading distance that grows as the loading catches up (and that shrinks for a while
+	/// when the frames take too long).
+	#[arg(long)]
+	pub(crate) no_adaptive_loading: bool,
+
+	/// Unloading distance in blocks, the chunks further than that from the player are unloaded.
+	/// It is kept at least one chunk further than the loading distance (and it follows the loading
+	/// distance when it adapts), so that walking back and forth across the edge of the loaded area
+	/// does not keep loading and unloading the same chunks. By default it is 60 blocks further.
+	#[arg(long = "unload-dist", value_name = "LENGTH")]
+	pub(crate) unloading_distance: Option<f32>,
+
+	/// Minimum time (in seconds) that a chunk stays loaded, even if the player went too far from it.
+	#[arg(long, default_value_t = 10.0, value_name = "SECONDS")]
+	pub(crate) min_resident_time: f32,
+
+	/// Simulation distance in blocks, the entities and the surface layers (snow and fallen leaves)
+	/// further than that from the player are not simulated (the entities are frozen) even if they
+	/// are loaded and rendered. By default everything that is loaded is simulated.
+	#[arg(long = "sim-dist", value_name = "LENGTH", value_parser = parse_length)]
+	pub(crate) simulation_distance: Option<f32>,
+
+	/// Entity render distance in blocks, the entities further than that from the camera are not
+	/// rendered. By default all the loaded entities in view are rendered.
+	#[arg(long = "entity-render-dist", value_name = "LENGTH")]
+	pub(crate) entity_render_distance: Option<f32>,
+
+	/// Entity level of detail distance in blocks, the entities further than that from the camera
+	/// are rendered without their small parts (like eyes).
+	#[arg(
+		long = "entity-lod-dist",
+		default_value_t = 64.0,
+		value_name = "LENGTH"
+	)]
+	pub(crate) entity_lod_distance: f32,
+
 	/// Length of the edge of the chunks, in blocks.
 	#[arg(long, default_value_t = 20, value_name = "LENGTH")]
 	pub(crate) chunk_edge: u32,
@@ -57,10 +114,24 @@ pub(crate) struct CommandLineSettings {
 	#[arg(long)]
 	pub(crate) fullscreen: bool,
 
+	/// Kind of fullscreen, `borderless` or `exclusive`, for `--fullscreen` and the toggle.
+	#[arg(
+		long,
+		value_enum,
+		default_value_t = FullscreenMode::Borderless,
+		value_name = "MODE"
+	)]
+	pub(crate) fullscreen_mode: FullscreenMode,
+
 	/// Disables V-Sync from the start.
 	#[arg(long)]
 	pub(crate) no_vsync: bool,
 
+	/// Presentation mode, `fifo` (V-Sync), `mailbox` or `immediate` (overrides `--no-vsync`).
+	/// Falls back to a supported mode if not supported.
+	#[arg(long, value_enum, value_name = "MODE")]
+	pub(crate) present_mode: Option<PresentModeChoice>,
+
 	/// Limit FPS to an arbitrary rate.
 	#[arg(long, value_name = "MAX_FRAMERATE")]
 	pub(crate) max_fps: Option<i32>,
@@ -73,6 +144,16 @@ pub(crate) struct CommandLineSettings {
 	#[arg(long, default_value_t = 60.0, value_name = "LENGTH")]
 	pub(crate) fog_margin: f32,
 
+	/// Disables the reflections on the surface of water from the start
+	/// (they cost a second rendering of the world).
+	#[arg(long)]
+	pub(crate) no_water_reflections: bool,
+
+	/// Disables the normal mapping of the blocks from the start
+	/// (the bumps of their textures catch the light).
+	#[arg(long)]
+	pub(crate) no_normal_mapping: bool,
+
 	/// Name by which the save is identified and retrieved/created.
 	#[arg(long = "save", short = 's', value_name = "NAME")]
 	pub(crate) save_name: Option<String>,
@@ -81,6 +162,18 @@ pub(crate) struct CommandLineSettings {
 	#[arg(long = "only-modified")]
 	pub(crate) only_save_modified_chunks: bool,
 
+	/// Caches the generated chunks that are not saved (in the `chunk_cache` directory)
+	/// so that they are not generated again when they are loaded again.
+	/// The cache is limited to the given size in MiB.
+	#[arg(long, value_name = "MAX_MIB")]
+	pub(crate) chunk_cache: Option<u64>,
+
+	/// Generates and saves all the chunks up to the given distance (in blocks) from the spawn,
+	/// in the background, so that exploring there later does not have to generate them.
+	/// Needs a save (see `--save`).
+	#[arg(long, value_name = "LENGTH")]
+	pub(crate) pregen: Option<i32>,
+
 	/// Selection of the playing mode, `free` or `play`.
 	#[arg(
 		long = "mode",
@@ -92,13 +185,157 @@ pub(crate) struct CommandLineSettings {
 	)]
 	pub(crate) playing_mode: PlayingMode,
 
+	/// Language of the interface, like `en` or `fr`.
+	#[arg(long = "lang", default_value = "en", value_name = "LANGUAGE")]
+	pub(crate) language: String,
+
+	/// Volume of all the sounds, from 0.0 (muted) to 1.0.
+	#[arg(long, default_value_t = 1.0, value_name = "VOLUME")]
+	pub(crate) master_volume: f32,
+
+	/// Volume of the sound effects (footsteps, blocks, interface), from 0.0 (muted) to 1.0.
+	#[arg(long, default_value_t = 1.0, value_name = "VOLUME")]
+	pub(crate) sfx_volume: f32,
+
+	/// Volume of the background music, from 0.0 (muted) to 1.0.
+	#[arg(long, default_value_t = 1.0, value_name = "VOLUME")]
+	pub(crate) music_volume: f32,
+
+	/// Disables the background music from the start.
+	#[arg(long)]
+	pub(crate) no_music: bool,
+
+	/// Does not play the game audio on the default audio device.
+	#[arg(long)]
+	pub(crate) no_audio_device: bool,
+
+	/// Writes the game audio to a WAV file (in addition to playing it on the audio device).
+	#[arg(long, value_name = "PATH")]
+	pub(crate) audio_dump: Option<std::path::PathBuf>,
+
+	/// Disables the natural spawning (and despawning) of creatures.
+	#[arg(long)]
+	pub(crate) no_creature_spawning: bool,
+
+	/// Makes it snow from the start (snow piles up on the exposed surfaces).
+	#[arg(long)]
+	pub(crate) snowing: bool,
+
+	/// Renders the blocks with a deferred rendering path with clustered lighting
+	/// (that supports many point lights, like light emitting blocks and lanterns),
+	/// instead of the default forward rendering path.
+	#[arg(long)]
+	pub(crate) deferred_lighting: bool,
+
+	/// Disables the culling of the chunk meshes that are out of view on the GPU
+	/// (that is done by a compute pass if the graphics adapter supports it).
+	#[arg(long)]
+	pub(crate) no_gpu_culling: bool,
+
+	/// Multisample anti-aliasing sample count, can be 1 (no anti-aliasing), 2, 4 or 8.
+	/// It is lowered to what the graphics adapter supports if needed.
+	/// Not supported by the deferred rendering path.
+	#[arg(long, default_value_t = 1, value_name = "SAMPLE_COUNT")]
+	pub(crate) msaa: u32,
+
+	/// Resolution at which the scene is rendered, in percents of the window resolution
+	/// (from 50 to 200). Lower is faster but blurrier, higher is slower but smoother.
+	#[arg(long, default_value_t = 100, value_name = "PERCENT")]
+	pub(crate) render_scale: u32,
+
+	/// How much what is nearer or farther than what is under the crosshair gets blurry,
+	/// in percents (0 disables the depth of field).
+	#[arg(long, default_value_t = 0, value_name = "PERCENT")]
+	pub(crate) depth_of_field: u32,
+
+	/// How much the scene is blurred along the motion of the camera, in percents of the motion
+	/// during a frame (0 disables the motion blur).
+	#[arg(long, default_value_t = 0, value_name = "PERCENT")]
+	pub(crate) motion_blur: u32,
+
+	/// Brightness above which the scene glows, in percents of the maximum brightness.
+	#[arg(long, default_value_t = 85, value_name = "PERCENT")]
+	pub(crate) bloom_threshold: u32,
+
+	/// How much the bright parts of the scene glow, in percents (0 disables the bloom).
+	#[arg(long, default_value_t = 60, value_name = "PERCENT")]
+	pub(crate) bloom_intensity: u32,
+
+	/// Tonemapping operator, `none`, `reinhard` or `aces`, that maps the colors (after
+	/// the exposure is applied) to the displayable range.
+	#[arg(long, value_enum, default_value_t = Tonemapper::None, value_name = "TONEMAPPER")]
+	pub(crate) tonemapper: Tonemapper,
+
+	/// Exposure of the scene, in percents (the colors are multiplied by it before tonemapping).
+	#[arg(long, default_value_t = 100, value_name = "PERCENT")]
+	pub(crate) exposure: u32,
+
+	/// Contrast of the scene, in percents (100 leaves it as it is).
+	#[arg(long, default_value_t = 100, value_name = "PERCENT")]
+	pub(crate) contrast: u32,
+
+	/// Saturation of the scene, in percents (100 leaves it as it is, 0 is black and white).
+	#[arg(long, default_value_t = 100, value_name = "PERCENT")]
+	pub(crate) saturation: u32,
+
+	/// Color lookup table applied to the scene after the other color grading settings,
+	/// read from `luts/<NAME>.png` (see `src/color_grading.rs` for the layout of the image).
+	#[arg(long, value_name = "NAME")]
+	pub(crate) color_lut: Option<String>,
+
+	/// Records the time spent in the major parts of the game (chunk generation, meshing,
+	/// physics, rendering, etc.) to the given file, in the Chrome trace event format
+	/// (that can be opened with Perfetto).
+	#[arg(long, value_name = "FILE")]
+	pub(crate) trace: Option<std::path::PathBuf>,
+
+	/// Records the inputs and the position of the player at every frame, and the blocks placed
+	/// and broken by the player, to the given replay file (see `--play-replay`).
+	#[arg(long, value_name = "FILE")]
+	pub(crate) record_replay: Option<std::path::PathBuf>,
+
+	/// Plays the given replay file, the player is replayed while the camera moves freely.
+	/// Uses the seed, the world generator and the chunk edge of the recording.
+	#[arg(long, value_name = "FILE")]
+	pub(crate) play_replay: Option<std::path::PathBuf>,
+
+	/// How a replay is played, `replay` (the player follows the recorded positions) or
+	/// `resimulate` (the recorded inputs are given to the physics again, and the differences
+	/// with the recorded positions are reported).
+	#[arg(long, value_enum, default_value_t = PlaybackMode::Replay, value_name = "MODE")]
+	pub(crate) replay_mode: PlaybackMode,
+
+	/// Generates the chunks around the origin (up to the given distance in chunks) twice,
+	/// on one thread and then on many threads in a random order, and reports the differences
+	/// instead of running the game. Uses `--gen`, `--seed` and `--chunk-edge`.
+	#[arg(long, value_name = "RADIUS")]
+	pub(crate) verify_determinism: Option<u32>,
+
 	/// Runs a specific Qwy Script test instead of running the game.
 	#[arg(long)]
 	pub(crate) test_lang: Option<u32>,
 }
 
-pub(crate) fn parse_command_line_arguments() -> CommandLineSettings {
-	CommandLineSettings::parse()
+/// A length (in blocks) that is finite and not negative.
+fn parse_length(string: &str) -> Result<f32, String> {
+	let length: f32 = string.parse().map_err(|error| format!("{error}"))?;
+	if length.is_finite() && length >= 0.0 {
+		Ok(length)
+	} else {
+		Err("expected a length that is finite and not negative".to_string())
+	}
+}
+
+/// The command line arguments override the settings of the config file (see `config`),
+/// that override the default values.
+pub(crate) fn parse_command_line_arguments(config: &Config) -> CommandLineSettings {
+	let matches = CommandLineSettings::command().get_matches();
+	let mut settings =
+		CommandLineSettings::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+	config.override_command_line_settings(&mut settings, |id| {
+		matches.value_source(id) == Some(ValueSource::CommandLine)
+	});
+	settings
 }
 
 pub(crate) fn display_world_generator_names() {
@@ -109,3 +346,21 @@ pub(crate) fn display_world_generator_names() {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn simulation_distance_must_be_a_length() {
+		let parse = |sim_dist: &str| {
+			CommandLineSettings::try_parse_from(["qwy3", &format!("--sim-dist={sim_dist}")])
+				.map(|settings| settings.simulation_distance)
+		};
+		assert_eq!(parse("40").unwrap(), Some(40.0));
+		assert_eq!(parse("0").unwrap(), Some(0.0));
+		assert!(parse("-5").is_err());
+		assert!(parse("inf").is_err());
+		assert!(parse("NaN").is_err());
+	}
+}
@@ -0,0 +1,189 @@
+//! The opaque blocks of each loaded chunk merged into as few boxes as possible, so that physics
+//! can check a span of blocks for collisions against a few boxes instead of looking at each block
+//! of the span (which costs a chunk lookup, a palette lookup and a block type lookup per block).
+//!
+//! It is kept up to date like the heightmap: the chunks that are loaded or edited are marked stale
+//! and their boxes are (re)computed on the next `update`. In the meantime, the blocks of the stale
+//! chunks are looked at one by one (see `ChunkGrid::is_any_block_opaque_in`).
+
+use fxhash::{FxHashMap, FxHashSet};
+
+use crate::{
+	block_types::{BlockType, BlockTypeTable},
+	chunk_blocks::ChunkBlocks,
+	coords::{BlockCoords, ChunkCoords, CubicCoordsSpan},
+};
+
+/// The collision shapes of one chunk.
+pub(crate) struct ChunkCollisionShapes {
+	/// Disjoint boxes that cover exactly the opaque blocks of the chunk.
+	solid_boxes: Vec<CubicCoordsSpan>,
+	/// Layer blocks (like snow) are not full blocks, they are not in the boxes and physics still
+	/// has to look at their blocks, but only in chunks that have some.
+	has_layers: bool,
+	/// Doors and trapdoors are thin panels, they are handled like the layers.
+	has_doors: bool,
+}
+
+impl ChunkCollisionShapes {
+	fn compute(
+		chunk_blocks: &ChunkBlocks,
+		block_type_table: &BlockTypeTable,
+	) -> ChunkCollisionShapes {
+		let mut has_opaque_blocks = false;
+		let mut has_layers = false;
+		let mut has_doors = false;
+		for type_id in chunk_blocks.block_type_ids_in_palette() {
+			let block_type = block_type_table.get(type_id).unwrap();
+			has_opaque_blocks |= block_type.is_opaque();
+			has_layers |= matches!(block_type, BlockType::Layer { .. });
+			has_doors |= block_type.is_door();
+		}
+		let solid_boxes = if has_opaque_blocks {
+			let span = CubicCoordsSpan::from_chunk_span(chunk_blocks.coords_span);
+			merge_into_boxes(span, |coords| {
+				let type_id = chunk_blocks.get(coords).unwrap().type_id;
+				block_type_table.get(type_id).unwrap().is_opaque()
+			})
+		} else {
+			// Most chunks are only air (or only air and things like grass blades and water).
+			vec![]
+		};
+		ChunkCollisionShapes { solid_boxes, has_layers, has_doors }
+	}
+
+	/// The given span must be in the chunk.
+	pub(crate) fn is_any_block_opaque_in(&self, span: &CubicCoordsSpan) -> bool {
+		self.solid_boxes.iter().any(|solid_box| solid_box.overlaps(span))
+	}
+
+	pub(crate) fn has_layers(&self) -> bool {
+		self.has_layers
+	}
+
+	pub(crate) fn has_doors(&self) -> bool {
+		self.has_doors
+	}
+}
+
+/// Greedy merging of the solid blocks of the span into disjoint boxes that cover exactly
+/// the solid blocks. Each box starts at the first solid block not yet covered (in x then y then z
+/// order) and is extended as far as possible along x, then y, then z.
+fn merge_into_boxes(
+	span: CubicCoordsSpan,
+	is_solid: impl Fn(BlockCoords) -> bool,
+) -> Vec<CubicCoordsSpan> {
+	let dims = span.sup_excluded - span.inf;
+	let index = |coords: BlockCoords| {
+		let relative = coords - span.inf;
+		((relative.z * dims.y + relative.y) * dims.x + relative.x) as usize
+	};
+	// The solid blocks that are not yet covered by a box.
+	let mut uncovered: Vec<bool> = span.iter().map(is_solid).collect();
+	let mut boxes = vec![];
+	for inf in span.iter() {
+		if !uncovered[index(inf)] {
+			continue;
+		}
+		let mut sup_excluded = inf + cgmath::vec3(1, 1, 1);
+		// Extending along an axis is possible if the whole face of the box on that side is made of
+		// blocks that are solid and not yet covered.
+		for axis in 0..3 {
+			loop {
+				if sup_excluded[axis] >= span.sup_excluded[axis] {
+					break;
+				}
+				let mut face_inf = inf;
+				face_inf[axis] = sup_excluded[axis];
+				let mut face_sup_excluded = sup_excluded;
+				face_sup_excluded[axis] = sup_excluded[axis] + 1;
+				let face =
+					CubicCoordsSpan::with_inf_sup_but_sup_is_excluded(face_inf, face_sup_excluded);
+				if face.iter().all(|coords| uncovered[index(coords)]) {
+					sup_excluded[axis] += 1;
+				} else {
+					break;
+				}
+			}
+		}
+		let solid_box = CubicCoordsSpan::with_inf_sup_but_sup_is_excluded(inf, sup_excluded);
+		for coords in solid_box.iter() {
+			uncovered[index(coords)] = false;
+		}
+		boxes.push(solid_box);
+	}
+	boxes
+}
+
+pub(crate) struct CollisionShapes {
+	/// The loaded chunks that are not stale.
+	chunk_shapes: FxHashMap<ChunkCoords, ChunkCollisionShapes>,
+	/// The chunks that were loaded or edited since the last `update`.
+	stale_chunks: FxHashSet<ChunkCoords>,
+}
+
+impl CollisionShapes {
+	pub(crate) fn new() -> CollisionShapes {
+		CollisionShapes {
+			chunk_shapes: FxHashMap::default(),
+			stale_chunks: FxHashSet::default(),
+		}
+	}
+
+	/// The chunk was loaded or had some of its blocks changed, its shapes are forgotten until they
+	/// are recomputed in the next call to `update`.
+	pub(crate) fn mark_stale(&mut self, chunk_coords: ChunkCoords) {
+		self.chunk_shapes.remove(&chunk_coords);
+		self.stale_chunks.insert(chunk_coords);
+	}
+
+	pub(crate) fn remove_chunk(&mut self, chunk_coords: ChunkCoords) {
+		self.chunk_shapes.remove(&chunk_coords);
+		self.stale_chunks.remove(&chunk_coords);
+	}
+
+	/// Computes the shapes of the stale chunks (see `mark_stale`), `get_chunk_blocks` gives
+	/// the blocks of a chunk if it is (still) loaded.
+	pub(crate) fn update<'a>(
+		&mut self,
+		get_chunk_blocks: impl Fn(ChunkCoords) -> Option<&'a ChunkBlocks>,
+		block_type_table: &BlockTypeTable,
+	) {
+		for chunk_coords in self.stale_chunks.drain() {
+			let Some(chunk_blocks) = get_chunk_blocks(chunk_coords) else {
+				continue;
+			};
+			let shapes = ChunkCollisionShapes::compute(chunk_blocks, block_type_table);
+			self.chunk_shapes.insert(chunk_coords, shapes);
+		}
+	}
+
+	/// `None` if the chunk is not loaded or is stale.
+	pub(crate) fn get(&self, chunk_coords: ChunkCoords) -> Option<&ChunkCollisionShapes> {
+		self.chunk_shapes.get(&chunk_coords)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	#[test]
+	fn merged_boxes_cover_exactly_the_solid_blocks() {
+		let span = CubicCoordsSpan::with_inf_sup_but_sup_is_excluded(
+			cgmath::point3(-4, 3, -7),
+			cgmath::point3(5, 11, 2),
+		);
+		let is_solid = |coords: BlockCoords| {
+			coords.z < -3 || (coords.x * 7 + coords.y * 3 + coords.z * 5).rem_euclid(4) == 0
+		};
+		let boxes = merge_into_boxes(span, is_solid);
+		for coords in span.iter() {
+			let covering_boxes = boxes.iter().filter(|solid_box| solid_box.contains(coords)).count();
+			assert_eq!(covering_boxes, if is_solid(coords) { 1 } else { 0 });
+		}
+		// The solid ground below -3 fills whole layers of the span, it must be one box.
+		assert!(boxes.len() < span.iter().filter(|&coords| is_solid(coords)).count());
+		assert!(boxes.iter().any(|solid_box| solid_box.inf == span.inf
+			&& solid_box.sup_excluded == cgmath::point3(5, 11, -3)));
+	}
+}
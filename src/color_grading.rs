@@ -0,0 +1,268 @@
+//! Color grading, the look of the scene can be adjusted when it is rendered to the window
+//! (see `RenderScaling`): its exposure, a tonemapping operator that rolls off the bright colors,
+//! its contrast, its saturation and an optional color lookup table.
+//!
+//! A color lookup table (LUT) is an image `luts/<name>.png` of size N²×N (like 256×16), made of N
+//! slices of N×N texels side by side: in the slice of index b (from the left), the texel at
+//! (r, g) gives the color that replaces the color (r, g, b) (all in sRGB, scaled to 0..N-1).
+//! Such images are exported by most photo editing tools (from a neutral LUT that was graded).
+
+use clap::ValueEnum;
+use wgpu::util::DeviceExt;
+
+use crate::shaders::upscale::{color_grading_bind_group_layout, ColorGradingPod};
+
+/// The directory in which the color lookup tables are.
+const LUTS_DIRECTORY: &str = "luts";
+
+/// How the colors are mapped to the displayable range after the exposure is applied.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Tonemapper {
+	/// Colors are just clamped.
+	None,
+	/// Bright colors are compressed smoothly, keeping their hue.
+	Reinhard,
+	/// A filmic curve, contrasted with desaturated highlights.
+	Aces,
+}
+
+impl Tonemapper {
+	pub(crate) fn from_name(name: &str) -> Option<Tonemapper> {
+		Tonemapper::from_str(name, true).ok()
+	}
+
+	pub(crate) fn name(self) -> &'static str {
+		match self {
+			Tonemapper::None => "none",
+			Tonemapper::Reinhard => "reinhard",
+			Tonemapper::Aces => "aces",
+		}
+	}
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct GradingSettings {
+	pub(crate) tonemapper: Tonemapper,
+	/// Multiplies the colors before the tonemapping, 1.0 leaves them as they are.
+	pub(crate) exposure: f32,
+	/// 1.0 leaves the contrast as it is, 0.0 makes everything grey.
+	pub(crate) contrast: f32,
+	/// 1.0 leaves the saturation as it is, 0.0 makes everything black and white.
+	pub(crate) saturation: f32,
+}
+
+pub(crate) struct ColorGrading {
+	settings: GradingSettings,
+	/// The name of the color lookup table in use, if any.
+	lut_name: Option<String>,
+	lut_size: u32,
+	settings_buffer: wgpu::Buffer,
+	sampler: wgpu::Sampler,
+	bind_group_layout: wgpu::BindGroupLayout,
+	/// Has the lookup table (or a 1×1×1 placeholder if there is none).
+	bind_group: wgpu::BindGroup,
+}
+
+impl ColorGrading {
+	pub(crate) fn new(
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		settings: GradingSettings,
+	) -> ColorGrading {
+		let settings_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("Color Grading Settings Buffer"),
+			contents: bytemuck::cast_slice(&[ColorGrading::settings_pod(&settings, 0)]),
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+		});
+		let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+			label: Some("Color Lookup Table Sampler"),
+			address_mode_u: wgpu::AddressMode::ClampToEdge,
+			address_mode_v: wgpu::AddressMode::ClampToEdge,
+			address_mode_w: wgpu::AddressMode::ClampToEdge,
+			mag_filter: wgpu::FilterMode::Linear,
+			min_filter: wgpu::FilterMode::Linear,
+			mipmap_filter: wgpu::FilterMode::Nearest,
+			..Default::default()
+		});
+		let bind_group_layout = color_grading_bind_group_layout(device);
+		let bind_group = ColorGrading::make_bind_group(
+			device,
+			queue,
+			&bind_group_layout,
+			&settings_buffer,
+			&sampler,
+			1,
+			&[255; 4],
+		);
+		ColorGrading {
+			settings,
+			lut_name: None,
+			lut_size: 0,
+			settings_buffer,
+			sampler,
+			bind_group_layout,
+			bind_group,
+		}
+	}
+
+	fn settings_pod(settings: &GradingSettings, lut_size: u32) -> ColorGradingPod {
+		ColorGradingPod {
+			exposure: settings.exposure,
+			contrast: settings.contrast,
+			saturation: settings.saturation,
+			tonemapper: match settings.tonemapper {
+				Tonemapper::None => 0,
+				Tonemapper::Reinhard => 1,
+				Tonemapper::Aces => 2,
+			},
+			lut_size: lut_size as f32,
+			_padding: [0.0; 3],
+		}
+	}
+
+	/// The lookup table of edge `lut_size` is given as RGBA texels, red varying the fastest.
+	fn make_bind_group(
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		bind_group_layout: &wgpu::BindGroupLayout,
+		settings_buffer: &wgpu::Buffer,
+		sampler: &wgpu::Sampler,
+		lut_size: u32,
+		lut_data: &[u8],
+	) -> wgpu::BindGroup {
+		let lut_texture = device.create_texture_with_data(
+			queue,
+			&wgpu::TextureDescriptor {
+				label: Some("Color Lookup Table Texture"),
+				size: wgpu::Extent3d {
+					width: lut_size,
+					height: lut_size,
+					depth_or_array_layers: lut_size,
+				},
+				mip_level_count: 1,
+				sample_count: 1,
+				dimension: wgpu::TextureDimension::D3,
+				// Not sRGB, the shader wants the sRGB values as they are.
+				format: wgpu::TextureFormat::Rgba8Unorm,
+				view_formats: &[],
+				usage: wgpu::TextureUsages::TEXTURE_BINDING,
+			},
+			wgpu::util::TextureDataOrder::LayerMajor,
+			lut_data,
+		);
+		let lut_view = lut_texture.create_view(&wgpu::TextureViewDescriptor::default());
+		device.create_bind_group(&wgpu::BindGroupDescriptor {
+			label: Some("Color Grading Bind Group"),
+			layout: bind_group_layout,
+			entries: &[
+				wgpu::BindGroupEntry { binding: 0, resource: settings_buffer.as_entire_binding() },
+				wgpu::BindGroupEntry {
+					binding: 1,
+					resource: wgpu::BindingResource::TextureView(&lut_view),
+				},
+				wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(sampler) },
+			],
+		})
+	}
+
+	pub(crate) fn settings(&self) -> GradingSettings {
+		self.settings
+	}
+
+	pub(crate) fn lut_name(&self) -> Option<&str> {
+		self.lut_name.as_deref()
+	}
+
+	pub(crate) fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+		&self.bind_group_layout
+	}
+
+	pub(crate) fn bind_group(&self) -> &wgpu::BindGroup {
+		&self.bind_group
+	}
+
+	/// Neutral grading leaves the scene as it is, so there is no need to render the scene
+	/// to an offscreen texture just for the grading.
+	pub(crate) fn is_neutral(&self) -> bool {
+		let GradingSettings { tonemapper, exposure, contrast, saturation } = self.settings;
+		tonemapper == Tonemapper::None
+			&& exposure == 1.0
+			&& contrast == 1.0
+			&& saturation == 1.0
+			&& self.lut_name.is_none()
+	}
+
+	/// If the grading gets neutral or not neutral anymore by that, the textures that have the
+	/// scaled size must be recreated after that (see `RenderScaling::set_needs_offscreen_target`).
+	pub(crate) fn set_settings(&mut self, queue: &wgpu::Queue, settings: GradingSettings) {
+		self.settings = GradingSettings {
+			exposure: settings.exposure.max(0.0),
+			contrast: settings.contrast.max(0.0),
+			saturation: settings.saturation.max(0.0),
+			..settings
+		};
+		self.write_settings(queue);
+	}
+
+	fn write_settings(&self, queue: &wgpu::Queue) {
+		queue.write_buffer(
+			&self.settings_buffer,
+			0,
+			bytemuck::cast_slice(&[ColorGrading::settings_pod(&self.settings, self.lut_size)]),
+		);
+	}
+
+	/// Uses the color lookup table `luts/<name>.png`, or none if `None`.
+	/// Same as `set_settings` about the textures that have the scaled size.
+	pub(crate) fn set_lut(
+		&mut self,
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		name: Option<&str>,
+	) -> Result<(), String> {
+		let (lut_size, lut_data) = match name {
+			None => (1, vec![255; 4]),
+			Some(name) => load_lut(name)?,
+		};
+		self.bind_group = ColorGrading::make_bind_group(
+			device,
+			queue,
+			&self.bind_group_layout,
+			&self.settings_buffer,
+			&self.sampler,
+			lut_size,
+			&lut_data,
+		);
+		self.lut_name = name.map(str::to_string);
+		self.lut_size = if name.is_some() { lut_size } else { 0 };
+		self.write_settings(queue);
+		Ok(())
+	}
+}
+
+/// Reads the color lookup table image of the given name, gives its edge and its RGBA texels
+/// in the order of a 3D texture (red varying the fastest, then green, then blue).
+fn load_lut(name: &str) -> Result<(u32, Vec<u8>), String> {
+	let path = std::path::Path::new(LUTS_DIRECTORY).join(format!("{name}.png"));
+	let image = image::open(&path)
+		.map_err(|error| format!("Could not read \"{}\": {error}", path.display()))?
+		.into_rgba8();
+	let size = image.height();
+	if size < 2 || image.width() != size * size {
+		return Err(format!(
+			"The color lookup table in \"{}\" is {}×{} but should be N²×N (like 256×16)",
+			path.display(),
+			image.width(),
+			image.height()
+		));
+	}
+	let mut lut_data = Vec::with_capacity((size * size * size * 4) as usize);
+	for b in 0..size {
+		for g in 0..size {
+			for r in 0..size {
+				lut_data.extend_from_slice(&image.get_pixel(b * size + r, g).0);
+			}
+		}
+	}
+	Ok((size, lut_data))
+}
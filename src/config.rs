@@ -0,0 +1,411 @@
+//! The config file (`config.toml`) keeps settings across runs, like the graphics settings,
+//! the render distance, the volumes, some controls and the last used world generator.
+//!
+//! Every setting of the config file is optional, the command line arguments override
+//! the settings of the config file, that override the default values of the command line
+//! arguments. The `config_save` command writes the current settings to the config file.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	cmdline::CommandLineSettings,
+	color_grading::Tonemapper,
+	commands::{self, Action, Control},
+	display::{FullscreenMode, PresentModeChoice},
+	game_init::Game,
+	player_model::PlayerAppearance,
+	world_gen::WhichWorldGenerator,
+};
+
+const CONFIG_FILE_PATH: &str = "config.toml";
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Config {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub(crate) language: Option<String>,
+	pub(crate) world: WorldConfig,
+	pub(crate) graphics: GraphicsConfig,
+	pub(crate) audio: AudioConfig,
+	/// The colors of the player model (see `player_model`).
+	pub(crate) appearance: PlayerAppearance,
+	/// Control bindings that are added to the ones of the controls file (or replace them),
+	/// like `"key:W" = "walk_forward"`.
+	pub(crate) controls: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct WorldConfig {
+	#[serde(with = "value_enum_name", skip_serializing_if = "Option::is_none")]
+	generator: Option<WhichWorldGenerator>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	loading_distance: Option<f32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	simulation_distance: Option<f32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	entity_render_distance: Option<f32>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct GraphicsConfig {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	fullscreen: Option<bool>,
+	#[serde(with = "value_enum_name", skip_serializing_if = "Option::is_none")]
+	fullscreen_mode: Option<FullscreenMode>,
+	#[serde(with = "value_enum_name", skip_serializing_if = "Option::is_none")]
+	present_mode: Option<PresentModeChoice>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	max_fps: Option<i32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	fog: Option<bool>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	water_reflections: Option<bool>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	normal_mapping: Option<bool>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	deferred_lighting: Option<bool>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	msaa: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	render_scale: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	depth_of_field: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	motion_blur: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	bloom_threshold: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	bloom_intensity: Option<u32>,
+	#[serde(with = "value_enum_name", skip_serializing_if = "Option::is_none")]
+	tonemapper: Option<Tonemapper>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	exposure: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	contrast: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	saturation: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	color_lut: Option<String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct AudioConfig {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	master_volume: Option<f32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	sfx_volume: Option<f32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	music_volume: Option<f32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	music: Option<bool>,
+}
+
+/// The enums that are command line argument values are written in the config file
+/// with the same names as on the command line (like `borderless` or `aces`).
+mod value_enum_name {
+	use clap::ValueEnum;
+	use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+	pub(super) fn serialize<S: Serializer, T: ValueEnum>(
+		value: &Option<T>,
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		match value.as_ref().and_then(ValueEnum::to_possible_value) {
+			Some(possible_value) => serializer.serialize_str(possible_value.get_name()),
+			None => serializer.serialize_none(),
+		}
+	}
+
+	pub(super) fn deserialize<'de, D: Deserializer<'de>, T: ValueEnum>(
+		deserializer: D,
+	) -> Result<Option<T>, D::Error> {
+		let name = String::deserialize(deserializer)?;
+		T::from_str(&name, true).map(Some).map_err(D::Error::custom)
+	}
+}
+
+/// Sets the setting to the value from the config file (if any)
+/// unless it was set on the command line.
+fn override_setting<T: Clone>(
+	setting: &mut T,
+	value: Option<T>,
+	id: &str,
+	is_set_on_command_line: &impl Fn(&str) -> bool,
+) {
+	if let Some(value) = value {
+		if !is_set_on_command_line(id) {
+			*setting = value;
+		}
+	}
+}
+
+impl Config {
+	/// No config file is not an error, it just means that there is nothing to override
+	/// the default settings.
+	pub(crate) fn load() -> Result<Config, String> {
+		match std::fs::read_to_string(CONFIG_FILE_PATH) {
+			Ok(content) => toml::from_str(&content).map_err(|error| error.to_string()),
+			Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+			Err(error) => Err(error.to_string()),
+		}
+	}
+
+	pub(crate) fn save(&self) -> Result<(), String> {
+		let content = toml::to_string(self).map_err(|error| error.to_string())?;
+		std::fs::write(CONFIG_FILE_PATH, content).map_err(|error| error.to_string())
+	}
+
+	/// The ids are the names of the fields of `CommandLineSettings`.
+	pub(crate) fn override_command_line_settings(
+		&self,
+		settings: &mut CommandLineSettings,
+		is_set_on_command_line: impl Fn(&str) -> bool,
+	) {
+		let is_set = &is_set_on_command_line;
+		let s = settings;
+		let (world, graphics, audio) = (&self.world, &self.graphics, &self.audio);
+		override_setting(&mut s.language, self.language.clone(), "language", is_set);
+		override_setting(
+			&mut s.which_world_generator,
+			world.generator,
+			"which_world_generator",
+			is_set,
+		);
+		override_setting(
+			&mut s.loading_distance,
+			world.loading_distance,
+			"loading_distance",
+			is_set,
+		);
+		override_setting(
+			&mut s.simulation_distance,
+			world.simulation_distance.map(Some),
+			"simulation_distance",
+			is_set,
+		);
+		override_setting(
+			&mut s.entity_render_distance,
+			world.entity_render_distance.map(Some),
+			"entity_render_distance",
+			is_set,
+		);
+		override_setting(&mut s.fullscreen, graphics.fullscreen, "fullscreen", is_set);
+		override_setting(
+			&mut s.fullscreen_mode,
+			graphics.fullscreen_mode,
+			"fullscreen_mode",
+			is_set,
+		);
+		// `--no-vsync` on the command line also overrides the present mode of the config file.
+		if !is_set("no_vsync") {
+			override_setting(
+				&mut s.present_mode,
+				graphics.present_mode.map(Some),
+				"present_mode",
+				is_set,
+			);
+		}
+		override_setting(&mut s.max_fps, graphics.max_fps.map(Some), "max_fps", is_set);
+		override_setting(&mut s.no_fog, graphics.fog.map(|fog| !fog), "no_fog", is_set);
+		override_setting(
+			&mut s.no_water_reflections,
+			graphics.water_reflections.map(|enabled| !enabled),
+			"no_water_reflections",
+			is_set,
+		);
+		override_setting(
+			&mut s.no_normal_mapping,
+			graphics.normal_mapping.map(|enabled| !enabled),
+			"no_normal_mapping",
+			is_set,
+		);
+		override_setting(
+			&mut s.deferred_lighting,
+			graphics.deferred_lighting,
+			"deferred_lighting",
+			is_set,
+		);
+		override_setting(&mut s.msaa, graphics.msaa, "msaa", is_set);
+		override_setting(&mut s.render_scale, graphics.render_scale, "render_scale", is_set);
+		override_setting(
+			&mut s.depth_of_field,
+			graphics.depth_of_field,
+			"depth_of_field",
+			is_set,
+		);
+		override_setting(&mut s.motion_blur, graphics.motion_blur, "motion_blur", is_set);
+		override_setting(
+			&mut s.bloom_threshold,
+			graphics.bloom_threshold,
+			"bloom_threshold",
+			is_set,
+		);
+		override_setting(
+			&mut s.bloom_intensity,
+			graphics.bloom_intensity,
+			"bloom_intensity",
+			is_set,
+		);
+		override_setting(&mut s.tonemapper, graphics.tonemapper, "tonemapper", is_set);
+		override_setting(&mut s.exposure, graphics.exposure, "exposure", is_set);
+		override_setting(&mut s.contrast, graphics.contrast, "contrast", is_set);
+		override_setting(&mut s.saturation, graphics.saturation, "saturation", is_set);
+		override_setting(
+			&mut s.color_lut,
+			graphics.color_lut.clone().map(Some),
+			"color_lut",
+			is_set,
+		);
+		override_setting(&mut s.master_volume, audio.master_volume, "master_volume", is_set);
+		override_setting(&mut s.sfx_volume, audio.sfx_volume, "sfx_volume", is_set);
+		override_setting(&mut s.music_volume, audio.music_volume, "music_volume", is_set);
+		override_setting(&mut s.no_music, audio.music.map(|music| !music), "no_music", is_set);
+	}
+
+	/// The config with all the settings that are used for this run (after the command line
+	/// arguments overrode the config file), the controls are kept.
+	pub(crate) fn with_settings(self, s: &CommandLineSettings) -> Config {
+		Config {
+			language: Some(s.language.clone()),
+			world: WorldConfig {
+				generator: Some(s.which_world_generator),
+				loading_distance: Some(s.loading_distance),
+				simulation_distance: s.simulation_distance,
+				entity_render_distance: s.entity_render_distance,
+			},
+			graphics: GraphicsConfig {
+				fullscreen: Some(s.fullscreen),
+				fullscreen_mode: Some(s.fullscreen_mode),
+				present_mode: s.present_mode,
+				max_fps: s.max_fps,
+				fog: Some(!s.no_fog),
+				water_reflections: Some(!s.no_water_reflections),
+				normal_mapping: Some(!s.no_normal_mapping),
+				deferred_lighting: Some(s.deferred_lighting),
+				msaa: Some(s.msaa),
+				render_scale: Some(s.render_scale),
+				depth_of_field: Some(s.depth_of_field),
+				motion_blur: Some(s.motion_blur),
+				bloom_threshold: Some(s.bloom_threshold),
+				bloom_intensity: Some(s.bloom_intensity),
+				tonemapper: Some(s.tonemapper),
+				exposure: Some(s.exposure),
+				contrast: Some(s.contrast),
+				saturation: Some(s.saturation),
+				color_lut: s.color_lut.clone(),
+			},
+			audio: AudioConfig {
+				master_volume: Some(s.master_volume),
+				sfx_volume: Some(s.sfx_volume),
+				music_volume: Some(s.music_volume),
+				music: Some(!s.no_music),
+			},
+			appearance: self.appearance,
+			controls: self.controls,
+		}
+	}
+
+	pub(crate) fn control_bindings(&self) -> Vec<(Control, Action)> {
+		self
+			.controls
+			.iter()
+			.map(|(control_name, action_name)| {
+				let location = format!("In file \"{CONFIG_FILE_PATH}\" in the controls");
+				commands::parse_control_binding(control_name, action_name, &location)
+			})
+			.collect()
+	}
+
+	/// The settings that can change while playing (via commands and controls) are taken from
+	/// the game, the others are the ones that are used since the start.
+	pub(crate) fn current(game: &Game) -> Config {
+		let mut config = game.config.clone();
+		config.world.generator = Some(game.which_world_generator);
+
+		let graphics = &mut config.graphics;
+		graphics.fullscreen = Some(game.enable_fullscreen);
+		graphics.fullscreen_mode = Some(game.fullscreen_mode);
+		graphics.present_mode = Some(match game.window_surface_config.present_mode {
+			wgpu::PresentMode::Mailbox => PresentModeChoice::Mailbox,
+			wgpu::PresentMode::Immediate | wgpu::PresentMode::AutoNoVsync => {
+				PresentModeChoice::Immediate
+			},
+			_ => PresentModeChoice::Fifo,
+		});
+		graphics.fog = Some(game.enable_fog);
+		graphics.water_reflections = Some(game.enable_water_reflections);
+		graphics.normal_mapping = Some(game.enable_normal_mapping);
+		let percent = |value: f32| (value * 100.0).round() as u32;
+		graphics.render_scale = Some(percent(game.render_scaling.scale()));
+		graphics.depth_of_field = Some(percent(game.lens_effects.depth_of_field()));
+		graphics.motion_blur = Some(percent(game.lens_effects.motion_blur()));
+		graphics.bloom_threshold = Some(percent(game.bloom.threshold()));
+		graphics.bloom_intensity = Some(percent(game.bloom.intensity()));
+		let grading_settings = game.color_grading.settings();
+		graphics.tonemapper = Some(grading_settings.tonemapper);
+		graphics.exposure = Some(percent(grading_settings.exposure));
+		graphics.contrast = Some(percent(grading_settings.contrast));
+		graphics.saturation = Some(percent(grading_settings.saturation));
+		graphics.color_lut = game.color_grading.lut_name().map(str::to_string);
+
+		config.appearance = game.player_model.appearance().clone();
+
+		let audio_settings = &game.audio.settings;
+		config.audio = AudioConfig {
+			master_volume: Some(audio_settings.master_volume),
+			sfx_volume: Some(audio_settings.sfx_volume),
+			music_volume: Some(audio_settings.music_volume),
+			music: Some(audio_settings.enable_music),
+		};
+		config
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn config_file_round_trip() {
+		let content = r#"
+			language = "fr"
+
+			[world]
+			generator = "default"
+			loading_distance = 150.0
+
+			[graphics]
+			fullscreen_mode = "exclusive"
+			tonemapper = "aces"
+			fog = false
+
+			[controls]
+			"key:W" = "walk_forward"
+		"#;
+		let config: Config = toml::from_str(content).unwrap();
+		assert_eq!(config.language.as_deref(), Some("fr"));
+		assert_eq!(config.world.loading_distance, Some(150.0));
+		assert!(config.graphics.fullscreen_mode == Some(FullscreenMode::Exclusive));
+		assert!(config.graphics.tonemapper == Some(Tonemapper::Aces));
+		assert_eq!(config.graphics.fog, Some(false));
+		assert_eq!(config.graphics.msaa, None);
+		assert_eq!(config.controls.get("key:W").map(String::as_str), Some("walk_forward"));
+
+		let written = toml::to_string(&config).unwrap();
+		let config_again: Config = toml::from_str(&written).unwrap();
+		assert!(config_again.graphics.fullscreen_mode == Some(FullscreenMode::Exclusive));
+		assert_eq!(config_again.world.loading_distance, Some(150.0));
+		assert_eq!(config_again.controls, config.controls);
+	}
+
+	#[test]
+	fn unknown_enum_name_is_an_error() {
+		let content = "[graphics]\ntonemapper = \"nope\"\n";
+		assert!(toml::from_str::<Config>(content).is_err());
+	}
+}
@@ -95,7 +95,7 @@ impl ChunkCoordsSpan {
 }
 
 /// Represents a cubic area of the voxel grid.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub(crate) struct CubicCoordsSpan {
 	pub(crate) inf: cgmath::Point3<i32>,
 	pub(crate) sup_excluded: cgmath::Point3<i32>,
@@ -427,6 +427,23 @@ impl OrientedAxis {
 		delta[self.axis.index()] = self.orientation.sign();
 		delta
 	}
+
+	/// The direction after a quarter turn around the vertical axis (counterclockwise when seen
+	/// from above), so X+ becomes Y+ which becomes X-. Vertical directions are not changed.
+	pub(crate) fn quarter_turned(self) -> OrientedAxis {
+		let delta = self.delta();
+		OrientedAxis::from_delta(cgmath::vec3(-delta.y, delta.x, delta.z)).unwrap()
+	}
+
+	/// The horizontal direction that is the closest to the given vector.
+	pub(crate) fn closest_horizontal(vector: cgmath::Vector3<f32>) -> OrientedAxis {
+		let delta = if vector.x.abs() >= vector.y.abs() {
+			cgmath::vec3(if vector.x >= 0.0 { 1 } else { -1 }, 0, 0)
+		} else {
+			cgmath::vec3(0, if vector.y >= 0.0 { 1 } else { -1 }, 0)
+		};
+		OrientedAxis::from_delta(delta).unwrap()
+	}
 }
 
 #[allow(dead_code)]
@@ -660,6 +677,16 @@ impl AlignedBox {
 		CubicCoordsSpan::with_inf_sup_but_sup_is_included(inf, sup_included)
 	}
 
+	/// Like `overlapping_block_coords_span` but without the blocks that the box only touches,
+	/// a side of the box that is on a block face (up to a small tolerance) does not overlap
+	/// the block on the other side of the face.
+	pub(crate) fn strictly_overlapping_block_coords_span(&self) -> CubicCoordsSpan {
+		let tolerance = 0.0005;
+		let inf = (self.pos - self.dims / 2.0).map(|x| (x + tolerance).round() as i32);
+		let sup_included = (self.pos + self.dims / 2.0).map(|x| (x - tolerance).round() as i32);
+		CubicCoordsSpan::with_inf_sup_but_sup_is_included(inf, sup_included)
+	}
+
 	pub(crate) fn overlaps(&self, other: &AlignedBox) -> bool {
 		let self_inf = self.pos - self.dims / 2.0;
 		let self_sup = self.pos + self.dims / 2.0;
@@ -671,4 +698,30 @@ impl AlignedBox {
 		let no_overlap = no_overlap_x || no_overlap_y || no_overlap_z;
 		!no_overlap
 	}
+
+	/// If the ray starting at `origin` and going in `direction` (normalized) hits the box,
+	/// then returns the distance from `origin` to the hit point.
+	pub(crate) fn ray_intersection(
+		&self,
+		origin: cgmath::Point3<f32>,
+		direction: cgmath::Vector3<f32>,
+	) -> Option<f32> {
+		let inf = self.pos - self.dims / 2.0;
+		let sup = self.pos + self.dims / 2.0;
+		let mut distance_min = 0.0f32;
+		let mut distance_max = f32::INFINITY;
+		for axis in 0..3 {
+			if direction[axis] == 0.0 {
+				if origin[axis] < inf[axis] || sup[axis] < origin[axis] {
+					return None;
+				}
+			} else {
+				let distance_a = (inf[axis] - origin[axis]) / direction[axis];
+				let distance_b = (sup[axis] - origin[axis]) / direction[axis];
+				distance_min = distance_min.max(distance_a.min(distance_b));
+				distance_max = distance_max.min(distance_a.max(distance_b));
+			}
+		}
+		(distance_min <= distance_max).then_some(distance_min)
+	}
 }
@@ -0,0 +1,77 @@
+//! Debug annotations, lines of text that float in the world above blocks to tell what is going on
+//! there (like the structure engine labeling the structure origins with their type).
+//!
+//! Annotations can be added from any thread (the chunk generation happens on worker threads)
+//! with `annotate`. They are only kept while enabled (see the `debug_annotations` command)
+//! so that annotating costs next to nothing the rest of the time.
+
+use std::sync::{
+	atomic::{AtomicBool, Ordering},
+	LazyLock, Mutex,
+};
+
+use cgmath::MetricSpace;
+use fxhash::FxHashMap;
+
+use crate::coords::BlockCoords;
+
+/// Annotations added beyond that many are dropped, so that flying around for a long time
+/// with the annotations enabled does not fill the memory.
+const MAX_ANNOTATIONS: usize = 4096;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// There is at most one annotation per block, annotating a block again replaces its annotation
+/// (the structure origins are annotated by every chunk that they overlap with).
+static ANNOTATIONS: LazyLock<Mutex<FxHashMap<BlockCoords, DebugAnnotation>>> =
+	LazyLock::new(|| Mutex::new(FxHashMap::default()));
+
+#[derive(Clone)]
+pub(crate) struct DebugAnnotation {
+	pub(crate) text: String,
+	pub(crate) color: [f32; 3],
+}
+
+pub(crate) fn enabled() -> bool {
+	ENABLED.load(Ordering::Relaxed)
+}
+
+/// Disabling does not forget the annotations, they are displayed again when enabled again.
+pub(crate) fn set_enabled(enabled: bool) {
+	ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub(crate) fn clear() {
+	ANNOTATIONS.lock().unwrap().clear();
+}
+
+/// Places (if enabled) the given text above the block at the given coords.
+pub(crate) fn annotate(coords: BlockCoords, text: impl Into<String>, color: [f32; 3]) {
+	if !enabled() {
+		return;
+	}
+	let mut annotations = ANNOTATIONS.lock().unwrap();
+	if annotations.len() < MAX_ANNOTATIONS || annotations.contains_key(&coords) {
+		annotations.insert(coords, DebugAnnotation { text: text.into(), color });
+	}
+}
+
+/// The annotations (if enabled) that are within the given distance of the given position,
+/// with the position (above the annotated block) at which their text is to be displayed.
+pub(crate) fn annotations_around(
+	position: cgmath::Point3<f32>,
+	distance: f32,
+) -> Vec<(cgmath::Point3<f32>, DebugAnnotation)> {
+	if !enabled() {
+		return vec![];
+	}
+	let annotations = ANNOTATIONS.lock().unwrap();
+	annotations
+		.iter()
+		.map(|(coords, annotation)| {
+			let text_pos = coords.map(|x| x as f32) + cgmath::vec3(0.0, 0.0, 0.7);
+			(text_pos, annotation)
+		})
+		.filter(|(text_pos, _)| text_pos.distance(position) <= distance)
+		.map(|(text_pos, annotation)| (text_pos, annotation.clone()))
+		.collect()
+}
@@ -0,0 +1,386 @@
+//! Deferred rendering path with clustered lighting, an alternative to the default forward path.
+//!
+//! Blocks are first rendered to G-buffers (the color lit by the sun as in the forward path, the
+//! albedo, the normal and the world position), then a fullscreen pass adds to that color the light
+//! of point lights (emitted by some blocks and carried by some entities). There can be many point
+//! lights, so the view frustum is cut in clusters (screen tiles times depth slices) and each
+//! cluster is given the list of the lights that may reach it, that way a pixel only has to
+//! consider a few lights. Entities and everything else are then rendered by the forward pipelines
+//! over the result (they are not lit by point lights, at least for now).
+
+use cgmath::{EuclideanSpace, InnerSpace, MetricSpace};
+
+use crate::shaders::deferred_lighting::{render_pipeline_and_bind_group_layout, GBUFFER_FORMATS};
+
+/// Number of clusters along the screen x axis, the screen y axis and the depth axis.
+const CLUSTER_COUNTS: (u32, u32, u32) = (16, 9, 24);
+/// The depth slicing of the clusters covers this range of distances (in blocks) from the camera,
+/// things closer are in the first slice and things further are in the last slice.
+const CLUSTER_DEPTH_NEAR: f32 = 1.0;
+const CLUSTER_DEPTH_FAR: f32 = 512.0;
+/// If there are more lights than that, only the ones closest to the camera are used.
+const MAX_LIGHTS: usize = 512;
+/// Size of the concatenation of the lists of light indices of all the clusters.
+const MAX_LIGHT_INDICES: usize = 65536;
+
+/// What a light source gives off, it becomes a `PointLight` once placed somewhere.
+#[derive(Clone, Copy)]
+pub(crate) struct LightEmission {
+	pub(crate) color: [f32; 3],
+	/// The light does not reach further than that (in blocks).
+	pub(crate) radius: f32,
+}
+
+impl LightEmission {
+	pub(crate) fn at(self, position: cgmath::Point3<f32>) -> PointLight {
+		PointLight { position, emission: self }
+	}
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct PointLight {
+	pub(crate) position: cgmath::Point3<f32>,
+	pub(crate) emission: LightEmission,
+}
+
+/// Certified Plain Old Data (so it can be sent to the GPU as a storage buffer element).
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+struct PointLightPod {
+	position_and_radius: [f32; 4],
+	color: [f32; 4],
+}
+
+/// Certified Plain Old Data (so it can be sent to the GPU as a uniform).
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+struct ClusterSettingsPod {
+	camera_position: [f32; 4],
+	camera_forward: [f32; 4],
+	tile_size_and_depth_range: [f32; 4],
+	cluster_counts: [u32; 4],
+}
+
+/// How the camera sees the world, needed to know which lights may reach which clusters.
+pub(crate) struct CameraForClustering {
+	pub(crate) position: cgmath::Point3<f32>,
+	pub(crate) forward: cgmath::Vector3<f32>,
+	/// If `None` then the lights are considered to possibly reach every screen tile,
+	/// which is correct but slower (it is fine for debugging cameras).
+	pub(crate) view_projection_matrix: Option<cgmath::Matrix4<f32>>,
+	pub(crate) screen_width: u32,
+	pub(crate) screen_height: u32,
+}
+
+pub(crate) struct DeferredLighting {
+	gbuffer_views: Vec<wgpu::TextureView>,
+	pub(crate) render_pipeline: wgpu::RenderPipeline,
+	bind_group_layout: wgpu::BindGroupLayout,
+	cluster_settings_buffer: wgpu::Buffer,
+	lights_buffer: wgpu::Buffer,
+	cluster_light_ranges_buffer: wgpu::Buffer,
+	light_indices_buffer: wgpu::Buffer,
+	pub(crate) bind_group: wgpu::BindGroup,
+}
+
+impl DeferredLighting {
+	pub(crate) fn new(
+		device: &wgpu::Device,
+		output_format: wgpu::TextureFormat,
+		width: u32,
+		height: u32,
+	) -> DeferredLighting {
+		let (render_pipeline, bind_group_layout) =
+			render_pipeline_and_bind_group_layout(device, output_format);
+		let make_buffer = |label: &str, size: usize, usage: wgpu::BufferUsages| {
+			device.create_buffer(&wgpu::BufferDescriptor {
+				label: Some(label),
+				size: size as u64,
+				usage: usage | wgpu::BufferUsages::COPY_DST,
+				mapped_at_creation: false,
+			})
+		};
+		let cluster_count = (CLUSTER_COUNTS.0 * CLUSTER_COUNTS.1 * CLUSTER_COUNTS.2) as usize;
+		let cluster_settings_buffer = make_buffer(
+			"Cluster Settings Buffer",
+			std::mem::size_of::<ClusterSettingsPod>(),
+			wgpu::BufferUsages::UNIFORM,
+		);
+		let lights_buffer = make_buffer(
+			"Point Lights Buffer",
+			std::mem::size_of::<PointLightPod>() * MAX_LIGHTS,
+			wgpu::BufferUsages::STORAGE,
+		);
+		let cluster_light_ranges_buffer = make_buffer(
+			"Cluster Light Ranges Buffer",
+			std::mem::size_of::<[u32; 2]>() * cluster_count,
+			wgpu::BufferUsages::STORAGE,
+		);
+		let light_indices_buffer = make_buffer(
+			"Cluster Light Indices Buffer",
+			std::mem::size_of::<u32>() * MAX_LIGHT_INDICES,
+			wgpu::BufferUsages::STORAGE,
+		);
+		let gbuffer_views = make_gbuffer_views(device, width, height);
+		let bind_group = make_bind_group(
+			device,
+			&bind_group_layout,
+			&gbuffer_views,
+			[
+				&cluster_settings_buffer,
+				&lights_buffer,
+				&cluster_light_ranges_buffer,
+				&light_indices_buffer,
+			],
+		);
+		DeferredLighting {
+			gbuffer_views,
+			render_pipeline,
+			bind_group_layout,
+			cluster_settings_buffer,
+			lights_buffer,
+			cluster_light_ranges_buffer,
+			light_indices_buffer,
+			bind_group,
+		}
+	}
+
+	/// The G-buffers must have the size of the window.
+	pub(crate) fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+		self.gbuffer_views = make_gbuffer_views(device, width, height);
+		self.bind_group = make_bind_group(
+			device,
+			&self.bind_group_layout,
+			&self.gbuffer_views,
+			[
+				&self.cluster_settings_buffer,
+				&self.lights_buffer,
+				&self.cluster_light_ranges_buffer,
+				&self.light_indices_buffer,
+			],
+		);
+	}
+
+	/// In the order of `GBUFFER_FORMATS`.
+	pub(crate) fn gbuffer_views(&self) -> &[wgpu::TextureView] {
+		&self.gbuffer_views
+	}
+
+	/// Assigns the given lights to the clusters they may reach and sends all that to the GPU.
+	/// Must be called every frame before rendering.
+	pub(crate) fn update_lights(
+		&self,
+		queue: &wgpu::Queue,
+		lights: &[PointLight],
+		camera: &CameraForClustering,
+	) {
+		// Keep the lights that can be seen, the closest first.
+		let mut lights: Vec<_> = lights
+			.iter()
+			.filter(|light| {
+				let depth = (light.position - camera.position).dot(camera.forward);
+				depth + light.emission.radius > 0.0
+			})
+			.copied()
+			.collect();
+		lights.sort_by(|light_a, light_b| {
+			let distance_a = light_a.position.distance2(camera.position);
+			let distance_b = light_b.position.distance2(camera.position);
+			distance_a.total_cmp(&distance_b)
+		});
+		lights.truncate(MAX_LIGHTS);
+
+		let (count_x, count_y, count_z) = CLUSTER_COUNTS;
+		let tile_width = camera.screen_width.div_ceil(count_x).max(1);
+		let tile_height = camera.screen_height.div_ceil(count_y).max(1);
+		let slice_of_depth = |depth: f32| {
+			let slice = (depth.max(CLUSTER_DEPTH_NEAR) / CLUSTER_DEPTH_NEAR).ln()
+				/ (CLUSTER_DEPTH_FAR / CLUSTER_DEPTH_NEAR).ln()
+				* count_z as f32;
+			(slice as u32).min(count_z - 1)
+		};
+
+		let mut clusters = vec![vec![]; (count_x * count_y * count_z) as usize];
+		for (light_index, light) in lights.iter().enumerate() {
+			let radius = light.emission.radius;
+			let depth = (light.position - camera.position).dot(camera.forward);
+			let slices = slice_of_depth(depth - radius)..=slice_of_depth(depth + radius);
+			let Some((tiles_x, tiles_y)) =
+				screen_tiles_reached(light, camera, (tile_width, tile_height))
+			else {
+				continue;
+			};
+			for slice in slices {
+				for tile_y in tiles_y.clone() {
+					for tile_x in tiles_x.clone() {
+						let cluster_index = (slice * count_y + tile_y) * count_x + tile_x;
+						clusters[cluster_index as usize].push(light_index as u32);
+					}
+				}
+			}
+		}
+
+		let mut cluster_light_ranges = Vec::with_capacity(clusters.len());
+		let mut light_indices = vec![];
+		for cluster in clusters {
+			let room_left = MAX_LIGHT_INDICES - light_indices.len();
+			let count = cluster.len().min(room_left);
+			cluster_light_ranges.push([light_indices.len() as u32, count as u32]);
+			light_indices.extend_from_slice(&cluster[..count]);
+		}
+
+		let lights: Vec<_> = lights
+			.iter()
+			.map(|light| {
+				let [r, g, b] = light.emission.color;
+				PointLightPod {
+					position_and_radius: light.position.to_vec().extend(light.emission.radius).into(),
+					color: [r, g, b, 1.0],
+				}
+			})
+			.collect();
+		let cluster_settings = ClusterSettingsPod {
+			camera_position: camera.position.to_vec().extend(0.0).into(),
+			camera_forward: camera.forward.normalize().extend(0.0).into(),
+			tile_size_and_depth_range: [
+				tile_width as f32,
+				tile_height as f32,
+				CLUSTER_DEPTH_NEAR,
+				CLUSTER_DEPTH_FAR,
+			],
+			cluster_counts: [count_x, count_y, count_z, 0],
+		};
+		queue.write_buffer(
+			&self.cluster_settings_buffer,
+			0,
+			bytemuck::cast_slice(&[cluster_settings]),
+		);
+		if !lights.is_empty() {
+			queue.write_buffer(&self.lights_buffer, 0, bytemuck::cast_slice(&lights));
+		}
+		queue.write_buffer(
+			&self.cluster_light_ranges_buffer,
+			0,
+			bytemuck::cast_slice(&cluster_light_ranges),
+		);
+		if !light_indices.is_empty() {
+			queue.write_buffer(
+				&self.light_indices_buffer,
+				0,
+				bytemuck::cast_slice(&light_indices),
+			);
+		}
+	}
+}
+
+/// The ranges of screen tiles (on the x axis and on the y axis) that the light may reach,
+/// or `None` if it is off-screen.
+fn screen_tiles_reached(
+	light: &PointLight,
+	camera: &CameraForClustering,
+	(tile_width, tile_height): (u32, u32),
+) -> Option<(std::ops::RangeInclusive<u32>, std::ops::RangeInclusive<u32>)> {
+	let (count_x, count_y, _) = CLUSTER_COUNTS;
+	let whole_screen = Some((0..=(count_x - 1), 0..=(count_y - 1)));
+	let Some(view_projection_matrix) = camera.view_projection_matrix else {
+		return whole_screen;
+	};
+
+	// Project the corners of the cube that contains the sphere of light.
+	let mut inf = cgmath::vec2(f32::INFINITY, f32::INFINITY);
+	let mut sup = cgmath::vec2(f32::NEG_INFINITY, f32::NEG_INFINITY);
+	let radius = light.emission.radius;
+	for corner_index in 0..8 {
+		let offset = cgmath::vec3(
+			if corner_index & 1 == 0 {
+				-radius
+			} else {
+				radius
+			},
+			if corner_index & 2 == 0 {
+				-radius
+			} else {
+				radius
+			},
+			if corner_index & 4 == 0 {
+				-radius
+			} else {
+				radius
+			},
+		);
+		let clip = view_projection_matrix * (light.position + offset).to_homogeneous();
+		if clip.w <= 0.0001 {
+			// The cube crosses the camera plane, the projection does not tell much.
+			return whole_screen;
+		}
+		let ndc = cgmath::vec2(clip.x / clip.w, clip.y / clip.w);
+		inf = cgmath::vec2(inf.x.min(ndc.x), inf.y.min(ndc.y));
+		sup = cgmath::vec2(sup.x.max(ndc.x), sup.y.max(ndc.y));
+	}
+	if sup.x < -1.0 || 1.0 < inf.x || sup.y < -1.0 || 1.0 < inf.y {
+		return None;
+	}
+
+	// From normalized device coordinates to pixels (the y axis goes down on the screen),
+	// then to tiles.
+	let to_tile_x = |ndc_x: f32| {
+		let pixel_x = (ndc_x.clamp(-1.0, 1.0) + 1.0) / 2.0 * camera.screen_width as f32;
+		((pixel_x as u32) / tile_width).min(count_x - 1)
+	};
+	let to_tile_y = |ndc_y: f32| {
+		let pixel_y = (1.0 - ndc_y.clamp(-1.0, 1.0)) / 2.0 * camera.screen_height as f32;
+		((pixel_y as u32) / tile_height).min(count_y - 1)
+	};
+	Some((
+		to_tile_x(inf.x)..=to_tile_x(sup.x),
+		to_tile_y(sup.y)..=to_tile_y(inf.y),
+	))
+}
+
+fn make_gbuffer_views(device: &wgpu::Device, width: u32, height: u32) -> Vec<wgpu::TextureView> {
+	GBUFFER_FORMATS
+		.iter()
+		.map(|&format| {
+			let texture = device.create_texture(&wgpu::TextureDescriptor {
+				label: Some("G-Buffer"),
+				size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+				mip_level_count: 1,
+				sample_count: 1,
+				dimension: wgpu::TextureDimension::D2,
+				format,
+				view_formats: &[],
+				usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+			});
+			texture.create_view(&wgpu::TextureViewDescriptor::default())
+		})
+		.collect()
+}
+
+fn make_bind_group(
+	device: &wgpu::Device,
+	bind_group_layout: &wgpu::BindGroupLayout,
+	gbuffer_views: &[wgpu::TextureView],
+	buffers: [&wgpu::Buffer; 4],
+) -> wgpu::BindGroup {
+	let mut entries: Vec<_> = gbuffer_views
+		.iter()
+		.enumerate()
+		.map(|(index, view)| wgpu::BindGroupEntry {
+			binding: index as u32,
+			resource: wgpu::BindingResource::TextureView(view),
+		})
+		.collect();
+	for (index, buffer) in buffers.into_iter().enumerate() {
+		entries.push(wgpu::BindGroupEntry {
+			binding: (gbuffer_views.len() + index) as u32,
+			resource: buffer.as_entire_binding(),
+		});
+	}
+	device.create_bind_group(&wgpu::BindGroupDescriptor {
+		label: Some("Deferred Lighting Bind Group"),
+		layout: bind_group_layout,
+		entries: &entries,
+	})
+}
@@ -0,0 +1,169 @@
+//! Checking that the world generation is deterministic, as asked for with
+//! `--verify-determinism`: the same chunks are generated twice (with a different number of
+//! threads and in a different order) and the results are compared.
+//!
+//! A world generator that does not always generate the same chunk from the same seed
+//! (because it depends on the order in which the chunks are generated, or on what happens
+//! on other threads) breaks the saves (that only keep the modified chunks, see `--only-modified`)
+//! and would break multiplayer.
+
+use std::sync::{mpsc, Arc};
+
+use rand::seq::SliceRandom;
+
+use crate::{
+	block_types::BlockTypeTable,
+	chunk_blocks::ChunkBlocks,
+	coords::{
+		iter_3d_cube_center_radius, BlockCoords, ChunkCoords, ChunkCoordsSpan, ChunkDimensions,
+	},
+	entities::IdGenerator,
+	threadpool::ThreadPool,
+	world_gen::WhichWorldGenerator,
+};
+
+/// Only the first few differences are logged, the others are just counted.
+const MAX_LOGGED_DIFFERENCES: usize = 10;
+
+struct GenerationRun {
+	number_of_workers: usize,
+	chunks: Vec<ChunkCoords>,
+}
+
+/// The result of generating a chunk, the entities are only compared by their number
+/// as the entity ids depend on the order of generation (and that is fine).
+struct GeneratedChunk {
+	blocks: ChunkBlocks,
+	entity_count: usize,
+}
+
+fn generate_chunks(
+	run: GenerationRun,
+	which_world_generator: WhichWorldGenerator,
+	seed: i32,
+	cd: ChunkDimensions,
+	block_type_table: &Arc<BlockTypeTable>,
+) -> Vec<(ChunkCoords, GeneratedChunk)> {
+	// Each run gets its own generator so that nothing it keeps (like caches) is shared.
+	let world_generator = which_world_generator.get_the_actual_generator(seed, block_type_table);
+	let id_generator = Arc::new(IdGenerator::new());
+	let pool = ThreadPool::new(run.number_of_workers);
+	let (sender, receiver) = mpsc::channel();
+	let number_of_chunks = run.chunks.len();
+	for chunk_coords in run.chunks {
+		let world_generator = Arc::clone(&world_generator);
+		let block_type_table = Arc::clone(block_type_table);
+		let id_generator = Arc::clone(&id_generator);
+		let sender = sender.clone();
+		pool.enqueue_task(Box::new(move || {
+			let coords_span = ChunkCoordsSpan { cd, chunk_coords };
+			let (blocks, entities) = world_generator.generate_chunk_blocks_and_entities(
+				coords_span,
+				&block_type_table,
+				&id_generator,
+			);
+			let entity_count = entities.count_entities();
+			let _ = sender.send((chunk_coords, GeneratedChunk { blocks, entity_count }));
+		}));
+	}
+	receiver.iter().take(number_of_chunks).collect()
+}
+
+/// Generates the chunks in a cube (of the given radius in chunks) around the origin twice
+/// and logs the differences. Returns `true` if both generations gave the same chunks.
+pub(crate) fn verify_determinism(
+	which_world_generator: WhichWorldGenerator,
+	seed: i32,
+	cd: ChunkDimensions,
+	radius_in_chunks: u32,
+	number_of_threads: u32,
+) -> bool {
+	let block_type_table = Arc::new(BlockTypeTable::new());
+	let chunks: Vec<ChunkCoords> =
+		iter_3d_cube_center_radius(cgmath::point3(0, 0, 0), radius_in_chunks as i32 + 1).collect();
+	log::info!(
+		"Verifying the determinism of the world generation on {} chunks (seed {seed})",
+		chunks.len()
+	);
+
+	// The first run is the reference, on one thread and in order.
+	let reference_run = GenerationRun { number_of_workers: 1, chunks: chunks.clone() };
+	let reference = generate_chunks(
+		reference_run,
+		which_world_generator,
+		seed,
+		cd,
+		&block_type_table,
+	);
+	let reference: fxhash::FxHashMap<_, _> = reference.into_iter().collect();
+
+	// The second run is on many threads and in a random order.
+	let mut shuffled_chunks = chunks;
+	shuffled_chunks.shuffle(&mut rand::thread_rng());
+	let other_run = GenerationRun {
+		number_of_workers: (number_of_threads as usize).max(2),
+		chunks: shuffled_chunks,
+	};
+	let other = generate_chunks(
+		other_run,
+		which_world_generator,
+		seed,
+		cd,
+		&block_type_table,
+	);
+
+	let mut differing_chunk_count = 0;
+	for (chunk_coords, chunk) in other.iter() {
+		let reference_chunk = &reference[chunk_coords];
+		let first_different_block = chunk.blocks.coords_span.iter_coords().find(|&coords| {
+			let block = chunk.blocks.get(coords).map(|block| block.as_owned_block());
+			let reference_block =
+				reference_chunk.blocks.get(coords).map(|block| block.as_owned_block());
+			block != reference_block
+		});
+		let different_entity_count = chunk.entity_count != reference_chunk.entity_count;
+		if first_different_block.is_none() && !different_entity_count {
+			continue;
+		}
+		differing_chunk_count += 1;
+		if differing_chunk_count <= MAX_LOGGED_DIFFERENCES {
+			log_difference(
+				*chunk_coords,
+				first_different_block,
+				(reference_chunk.entity_count, chunk.entity_count),
+			);
+		}
+	}
+
+	if differing_chunk_count == 0 {
+		log::info!("The world generation is deterministic (on these chunks)");
+		true
+	} else {
+		log::error!(
+			"The world generation is not deterministic, {differing_chunk_count} chunks differ \
+			out of {}",
+			other.len()
+		);
+		false
+	}
+}
+
+fn log_difference(
+	chunk_coords: ChunkCoords,
+	first_different_block: Option<BlockCoords>,
+	(reference_entity_count, entity_count): (usize, usize),
+) {
+	if let Some(coords) = first_different_block {
+		log::error!(
+			"Chunk {:?} differs, first at block {:?}",
+			(chunk_coords.x, chunk_coords.y, chunk_coords.z),
+			(coords.x, coords.y, coords.z)
+		);
+	}
+	if reference_entity_count != entity_count {
+		log::error!(
+			"Chunk {:?} has {entity_count} entities instead of {reference_entity_count}",
+			(chunk_coords.x, chunk_coords.y, chunk_coords.z)
+		);
+	}
+}
@@ -0,0 +1,189 @@
+//! How the window is displayed: fullscreen or not, which presentation mode (V-Sync or not),
+//! its size and position that are remembered across runs, and the grabbing of the cursor.
+
+use std::io::Write;
+
+use clap::ValueEnum;
+
+/// The kind of fullscreen used when fullscreen is enabled.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum FullscreenMode {
+	/// A borderless window that covers the whole screen, switching to it is quick.
+	Borderless,
+	/// Takes exclusive control of the screen with its video mode, which may help performances
+	/// but switching to it may take some time (and is not supported everywhere, like on Wayland).
+	Exclusive,
+}
+
+impl FullscreenMode {
+	pub(crate) fn from_name(name: &str) -> Option<FullscreenMode> {
+		FullscreenMode::from_str(name, true).ok()
+	}
+
+	pub(crate) fn to_winit(self, window: &winit::window::Window) -> winit::window::Fullscreen {
+		match self {
+			FullscreenMode::Borderless => winit::window::Fullscreen::Borderless(None),
+			FullscreenMode::Exclusive => {
+				// The video mode with the highest resolution, then the highest refresh rate.
+				let best_video_mode = window.current_monitor().and_then(|monitor| {
+					monitor.video_modes().max_by_key(|video_mode| {
+						let size = video_mode.size();
+						(
+							size.width * size.height,
+							video_mode.refresh_rate_millihertz(),
+						)
+					})
+				});
+				if let Some(video_mode) = best_video_mode {
+					winit::window::Fullscreen::Exclusive(video_mode)
+				} else {
+					log::warn!("No video mode available for exclusive fullscreen");
+					winit::window::Fullscreen::Borderless(None)
+				}
+			},
+		}
+	}
+}
+
+/// The presentation modes that can be asked for, see `supported_present_mode`.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum PresentModeChoice {
+	/// V-Sync on, the framerate is capped to the screen refresh rate.
+	Fifo,
+	/// No tearing, but the framerate is not capped (frames that were not shown are dropped).
+	Mailbox,
+	/// V-Sync off, the framerate is not capped but there may be tearing.
+	Immediate,
+}
+
+impl PresentModeChoice {
+	pub(crate) fn from_name(name: &str) -> Option<PresentModeChoice> {
+		PresentModeChoice::from_str(name, true).ok()
+	}
+}
+
+/// Gets the requested presentation mode if supported, or else falls back to the closest
+/// supported one. `Fifo` is always supported (it is guaranteed by wgpu).
+pub(crate) fn supported_present_mode(
+	requested: PresentModeChoice,
+	supported: &[wgpu::PresentMode],
+) -> wgpu::PresentMode {
+	let preferences: &[wgpu::PresentMode] = match requested {
+		PresentModeChoice::Fifo => &[],
+		PresentModeChoice::Mailbox => &[wgpu::PresentMode::Mailbox],
+		PresentModeChoice::Immediate => &[wgpu::PresentMode::Immediate, wgpu::PresentMode::Mailbox],
+	};
+	let present_mode = preferences
+		.iter()
+		.copied()
+		.find(|present_mode| supported.contains(present_mode))
+		.unwrap_or(wgpu::PresentMode::Fifo);
+	if preferences.first().is_some_and(|&preferred| preferred != present_mode) {
+		log::warn!(
+			"Present mode {:?} not available, using {present_mode:?}",
+			preferences[0]
+		);
+	}
+	present_mode
+}
+
+/// Size and position of the window when it is not fullscreen.
+pub(crate) struct WindowGeometry {
+	pub(crate) size: winit::dpi::PhysicalSize<u32>,
+	/// Some platforms (like Wayland) do not let us know or set the position of windows.
+	pub(crate) position: Option<winit::dpi::PhysicalPosition<i32>>,
+	pub(crate) maximized: bool,
+}
+
+const WINDOW_GEOMETRY_FILE_PATH: &str = "window.qwy3_window";
+
+impl WindowGeometry {
+	pub(crate) fn of_window(window: &winit::window::Window) -> WindowGeometry {
+		WindowGeometry {
+			size: window.inner_size(),
+			position: window.outer_position().ok(),
+			maximized: window.is_maximized(),
+		}
+	}
+
+	/// Updates the geometry with the current state of the window, unless the window is fullscreen
+	/// or maximized (in which case its size and position are not the ones we want to remember).
+	pub(crate) fn update_from_window(&mut self, window: &winit::window::Window) {
+		if window.fullscreen().is_some() {
+			return;
+		}
+		self.maximized = window.is_maximized();
+		if !self.maximized {
+			self.size = window.inner_size();
+			if let Ok(position) = window.outer_position() {
+				self.position = Some(position);
+			}
+		}
+	}
+
+	/// The file is made of lines like `size 1280 720`, `position 40 30` or `maximized true`.
+	pub(crate) fn load() -> Option<WindowGeometry> {
+		let file_content = std::fs::read_to_string(WINDOW_GEOMETRY_FILE_PATH).ok()?;
+		let mut size = None;
+		let mut position = None;
+		let mut maximized = false;
+		for line in file_content.lines() {
+			let words: Vec<&str> = line.split_whitespace().collect();
+			match words.as_slice() {
+				["size", width, height] => {
+					size = Some(winit::dpi::PhysicalSize::new(
+						width.parse().ok()?,
+						height.parse().ok()?,
+					));
+				},
+				["position", x, y] => {
+					position = Some(winit::dpi::PhysicalPosition::new(
+						x.parse().ok()?,
+						y.parse().ok()?,
+					));
+				},
+				["maximized", value] => maximized = value.parse().ok()?,
+				_ => {},
+			}
+		}
+		Some(WindowGeometry { size: size?, position, maximized })
+	}
+
+	pub(crate) fn save(&self) {
+		let mut file_content = format!("size {} {}\n", self.size.width, self.size.height);
+		if let Some(position) = self.position {
+			file_content += &format!("position {} {}\n", position.x, position.y);
+		}
+		file_content += &format!("maximized {}\n", self.maximized);
+		let result = std::fs::File::create(WINDOW_GEOMETRY_FILE_PATH)
+			.and_then(|mut file| file.write_all(file_content.as_bytes()));
+		if let Err(error) = result {
+			log::warn!("Could not save the window geometry: {error}");
+		}
+	}
+}
+
+/// Hides the cursor and keeps it in the window so that mouse motions can move the camera
+/// (the camera uses raw mouse motions, so it is not affected by the OS mouse acceleration).
+///
+/// Locking the cursor in place is preferred, but it is not supported on X11 (and confining
+/// it to the window is not supported on macOS). Wayland only allows it for focused windows.
+/// Returns `false` if the cursor could not be grabbed (it is then left visible).
+pub(crate) fn grab_cursor(window: &winit::window::Window) -> bool {
+	let grabbed = window
+		.set_cursor_grab(winit::window::CursorGrabMode::Locked)
+		.or_else(|_| window.set_cursor_grab(winit::window::CursorGrabMode::Confined))
+		.is_ok();
+	if grabbed {
+		window.set_cursor_visible(false);
+	} else {
+		log::warn!("Could not grab the cursor");
+	}
+	grabbed
+}
+
+pub(crate) fn release_cursor(window: &winit::window::Window) {
+	// Releasing can only fail on platforms that do not support grabbing at all.
+	let _ = window.set_cursor_grab(winit::window::CursorGrabMode::None);
+	window.set_cursor_visible(true);
+}
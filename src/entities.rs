@@ -4,7 +4,8 @@ use std::{
 	collections::hash_map::Entry,
 	f32::consts::TAU,
 	io::{Read, Write},
-	sync::{Arc, Mutex},
+	sync::{Arc, LazyLock, Mutex},
+	time::{Duration, Instant},
 };
 
 use cgmath::{EuclideanSpace, InnerSpace, MetricSpace, Zero};
@@ -14,13 +15,15 @@ use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 
 use crate::{
+	block_type_remapping::BlockTypeIdRemapping,
 	block_types::BlockTypeTable,
 	chunk_blocks::Block,
 	chunks::{ActionOnWorld, ChunkGrid},
 	coords::{
-		iter_3d_cube_center_radius, AlignedBox, AngularDirection, ChunkCoords, ChunkCoordsSpan,
-		ChunkDimensions,
+		iter_3d_cube_center_radius, AlignedBox, AngularDirection, BlockCoords, ChunkCoords,
+		ChunkCoordsSpan, ChunkDimensions,
 	},
+	deferred_lighting::{LightEmission, PointLight},
 	entity_parts::{
 		colored_cube::{ColoredCubePartKind, PartColoredCubeInstanceData},
 		colored_icosahedron::{ColoredIcosahedronPartKind, PartColoredIcosahedronInstanceData},
@@ -28,7 +31,9 @@ use crate::{
 		PartHandler, PartInstance, PartTables, TextureMappingAndColoringTableRwLock,
 		WhichIcosahedronColoring,
 	},
-	physics::AlignedPhysBox,
+	models::{delete_parts, Model, ModelPart},
+	pathfinding::first_step_towards,
+	physics::{AlignedPhysBox, PlatformSurface},
 	rendering_init::BindingThingy,
 	saves::{Save, WhichChunkFile},
 };
@@ -52,6 +57,22 @@ use crate::{
 pub(crate) struct Entity {
 	id: Id,
 	typed: EntityTyped,
+	/// Was spawned by the creature spawner (see `spawning`) instead of being placed or generated
+	/// with the world. Such entities may despawn when far from the player, unless persistent.
+	#[serde(default)]
+	naturally_spawned: bool,
+	/// Given by the player, displayed above the entity.
+	#[serde(default)]
+	name: Option<String>,
+	/// Keeps the entity within some distance of something else (like a leash would).
+	#[serde(default)]
+	tether: Option<Tether>,
+	/// The entity dies when this reaches its max health (see `Entity::max_health`).
+	#[serde(default)]
+	damage_taken: u32,
+	/// When it was last hit, it flashes red and shows its health bar for some time after that.
+	#[serde(skip)]
+	last_hit_time: Option<Instant>,
 }
 #[derive(Clone, Serialize, Deserialize)]
 enum EntityTyped {
@@ -73,11 +94,229 @@ enum EntityTyped {
 		#[serde(skip)]
 		right_eye_part: PartHandler<ColoredCubePartKind>,
 	},
+	/// Lives in a village, wanders from house to house and idles a bit at each.
+	Villager {
+		phys: AlignedPhysBox,
+		facing_direction: AngularDirection,
+		/// Positions in front of the doors of the houses of the village.
+		homes: Vec<cgmath::Point3<f32>>,
+		target_home_index: usize,
+		/// While this is positive, the villager stands still (and this decreases, in seconds).
+		idle_time_left: f32,
+		/// Time spent walking to the current target, to give up if it seems unreachable.
+		walking_time: f32,
+		#[serde(skip)]
+		body_part: PartHandler<ColoredCubePartKind>,
+		#[serde(skip)]
+		head_part: PartHandler<ColoredCubePartKind>,
+	},
+	/// Creature that the player can ride. When not ridden, it wanders around slowly.
+	Mount {
+		phys: AlignedPhysBox,
+		facing_direction: AngularDirection,
+		/// While there is a rider, this is what the rider asks.
+		#[serde(skip)]
+		rider_control: Option<MountControl>,
+		/// When not ridden, it alternates between walking (while this is positive)
+		/// and standing still (while this is negative), this goes towards zero (in seconds).
+		wandering_time_left: f32,
+		#[serde(skip)]
+		body_part: PartHandler<ColoredCubePartKind>,
+		#[serde(skip)]
+		head_part: PartHandler<ColoredCubePartKind>,
+		#[serde(skip)]
+		leg_parts: [PartHandler<ColoredCubePartKind>; 4],
+	},
+	/// Floating raft that goes back and forth between two points, ignoring gravity and blocks.
+	/// Things can stand on it and get carried around.
+	Platform {
+		phys: AlignedPhysBox,
+		path_start: cgmath::Point3<f32>,
+		path_end: cgmath::Point3<f32>,
+		/// Goes from 0 to 2 and loops, the platform is at the start at 0 and at the end at 1.
+		path_progress: f32,
+		/// Velocity during the last step, in blocks per second.
+		velocity: cgmath::Vector3<f32>,
+		#[serde(skip)]
+		raft_part: PartHandler<ColoredCubePartKind>,
+	},
+	/// Creature whose look is given by a model (see `models`), it wanders around slowly.
+	Creature {
+		phys: AlignedPhysBox,
+		facing_direction: AngularDirection,
+		/// The name of the model file the model was loaded from.
+		model_name: String,
+		/// A copy of the model, so that the creature keeps its look even if the model file changes
+		/// or goes away.
+		model: Model,
+		/// It alternates between walking (while this is positive) and standing still
+		/// (while this is negative), this goes towards zero (in seconds).
+		wandering_time_left: f32,
+		#[serde(skip)]
+		parts: Vec<PartHandler<ColoredCubePartKind>>,
+	},
+	/// Creature that chases the player when it sees it and hits it when close enough.
+	/// It lurks in dark places and despawns in bright light (see `spawning`).
+	Hostile {
+		phys: AlignedPhysBox,
+		facing_direction: AngularDirection,
+		/// Where it last saw the player, it goes there when it loses sight of the player.
+		last_seen_player_pos: Option<cgmath::Point3<f32>>,
+		/// It can only attack when this is zero, this goes towards zero (in seconds).
+		attack_cooldown: f32,
+		/// When not chasing the player, it wanders around like a creature.
+		wandering_time_left: f32,
+		#[serde(skip)]
+		parts: Vec<PartHandler<ColoredCubePartKind>>,
+	},
+}
+
+const VILLAGER_DIMS: cgmath::Vector3<f32> = cgmath::vec3(0.6, 0.6, 1.7);
+const MOUNT_DIMS: cgmath::Vector3<f32> = cgmath::vec3(1.2, 1.2, 1.3);
+pub(crate) const PLATFORM_DIMS: cgmath::Vector3<f32> = cgmath::vec3(3.0, 3.0, 0.4);
+/// In blocks per second.
+const PLATFORM_SPEED: f32 = 2.0;
+/// Height of the back of a mount (where the rider sits) above its position.
+const MOUNT_SEAT_HEIGHT: f32 = 0.4;
+const HOSTILE_DIMS: cgmath::Vector3<f32> = cgmath::vec3(0.7, 0.7, 1.8);
+/// A hostile notices the player closer than that (in blocks), if nothing blocks its sight.
+const HOSTILE_AGGRO_RADIUS: f32 = 16.0;
+/// A hostile can hit the player if their boxes are closer than that (in blocks).
+const HOSTILE_ATTACK_REACH: f32 = 0.5;
+/// Time between two hits of a hostile (in seconds).
+const HOSTILE_ATTACK_COOLDOWN: f32 = 1.2;
+const HOSTILE_ATTACK_DAMAGE: u32 = 1;
+/// In blocks per second.
+const HOSTILE_CHASING_SPEED: f32 = 3.5;
+/// How long an entity flashes red after being hit.
+const HIT_TINT_DURATION: Duration = Duration::from_millis(300);
+/// How long the health bar of an entity is displayed after being hit.
+const HEALTH_BAR_DURATION: Duration = Duration::from_secs(4);
+
+static HOSTILE_MODEL: LazyLock<Model> = LazyLock::new(|| {
+	let part = |name: &str, offset: [f32; 3], scale: [f32; 3], color| ModelPart {
+		name: name.to_string(),
+		offset: offset.into(),
+		scale: scale.into(),
+		color,
+	};
+	Model {
+		parts: vec![
+			part(
+				"left_leg",
+				[0.0, 0.13, -0.55],
+				[0.25, 0.22, 0.7],
+				[40, 50, 40],
+			),
+			part(
+				"right_leg",
+				[0.0, -0.13, -0.55],
+				[0.25, 0.22, 0.7],
+				[40, 50, 40],
+			),
+			part("body", [0.0, 0.0, 0.15], [0.35, 0.6, 0.7], [60, 80, 50]),
+			// Arms reaching forward.
+			part(
+				"left_arm",
+				[0.3, 0.38, 0.4],
+				[0.65, 0.18, 0.18],
+				[90, 120, 70],
+			),
+			part(
+				"right_arm",
+				[0.3, -0.38, 0.4],
+				[0.65, 0.18, 0.18],
+				[90, 120, 70],
+			),
+			part("head", [0.0, 0.0, 0.7], [0.4, 0.4, 0.4], [90, 120, 70]),
+			part(
+				"left_eye",
+				[0.2, 0.09, 0.74],
+				[0.02, 0.08, 0.05],
+				[230, 40, 30],
+			),
+			part(
+				"right_eye",
+				[0.2, -0.09, 0.74],
+				[0.02, 0.08, 0.05],
+				[230, 40, 30],
+			),
+		],
+	}
+});
+
+/// An entity hitting the player. Entities cannot modify the player, so the hits are collected
+/// during the entity physics step and applied to the player afterwards.
+pub(crate) struct PlayerHit {
+	pub(crate) damage: u32,
+	/// Position of the entity that hit.
+	pub(crate) attacker_pos: cgmath::Point3<f32>,
+}
+
+/// What the rider of a mount asks it to do.
+#[derive(Clone)]
+pub(crate) struct MountControl {
+	/// Horizontal walking direction, its length is the fraction of the full speed.
+	pub(crate) walking: cgmath::Vector3<f32>,
+	/// Jump as soon as possible (stays set until the jump happens).
+	pub(crate) jump: bool,
+}
+
+/// A tether (a leash, a chain, etc.) keeps an entity within some distance of an anchor.
+/// It is a distance constraint that is solved at each physics step (after the entity moved),
+/// only the tethered entity is pulled (the anchor does not feel the tether).
+/// Entities tethered to entities tethered to something else make chains.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Tether {
+	pub(crate) anchor: TetherAnchor,
+	pub(crate) length: f32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) enum TetherAnchor {
+	/// The center of a block.
+	Block(BlockCoords),
+	/// The position is where the anchor entity was last time it was seen, it is used to find it
+	/// again (and as the anchor position while the anchor entity cannot be found).
+	Entity { id: Id, last_known_pos: cgmath::Point3<f32> },
+}
+
+const TETHER_MIN_LENGTH: f32 = 1.5;
+const TETHER_MAX_LENGTH: f32 = 16.0;
+
+impl Tether {
+	/// The length of the tether is the current distance between its ends (within some limits).
+	pub(crate) fn new(anchor: TetherAnchor, tethered_entity_pos: cgmath::Point3<f32>) -> Tether {
+		let mut tether = Tether { anchor, length: 0.0 };
+		tether.length = tethered_entity_pos
+			.distance(tether.anchor_pos())
+			.clamp(TETHER_MIN_LENGTH, TETHER_MAX_LENGTH);
+		tether
+	}
+
+	pub(crate) fn anchor_pos(&self) -> cgmath::Point3<f32> {
+		match &self.anchor {
+			TetherAnchor::Block(coords) => coords.map(|x| x as f32),
+			TetherAnchor::Entity { last_known_pos, .. } => *last_known_pos,
+		}
+	}
+
+	/// Updates the last known position of the anchor entity (if any, and if it can be found).
+	fn follow_anchor(&mut self, chunk_grid: &ChunkGrid) {
+		if let TetherAnchor::Entity { id, last_known_pos } = &mut self.anchor {
+			let chunk_coords = chunk_grid
+				.cd()
+				.world_coords_to_containing_chunk_coords(last_known_pos.map(|x| x.round() as i32));
+			if let Some(anchor) = chunk_grid.get_entity_around(*id, chunk_coords) {
+				*last_known_pos = anchor.pos();
+			}
+		}
+	}
 }
 
 /// Entity id generated by `IdGenerator`.
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-struct Id(u64);
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub(crate) struct Id(u64);
 
 /// Generator of unique `Id`s. Sharable, put it in an `Arc` and pass it around.
 pub(crate) struct IdGenerator {
@@ -123,6 +362,11 @@ impl Entity {
 				),
 				part: PartHandler::default(),
 			},
+			naturally_spawned: false,
+			name: None,
+			tether: None,
+			damage_taken: 0,
+			last_hit_time: None,
 		}
 	}
 
@@ -147,6 +391,357 @@ impl Entity {
 				left_eye_part: PartHandler::default(),
 				right_eye_part: PartHandler::default(),
 			},
+			naturally_spawned: false,
+			name: None,
+			tether: None,
+			damage_taken: 0,
+			last_hit_time: None,
+		}
+	}
+
+	/// The `homes` are the positions between which the villager will wander.
+	pub(crate) fn new_villager(
+		id_generator: &IdGenerator,
+		pos: cgmath::Point3<f32>,
+		homes: Vec<cgmath::Point3<f32>>,
+	) -> Entity {
+		assert!(!homes.is_empty());
+		Entity {
+			id: id_generator.generate_id(),
+			typed: EntityTyped::Villager {
+				phys: AlignedPhysBox::new(
+					AlignedBox { pos, dims: VILLAGER_DIMS },
+					cgmath::vec3(0.0, 0.0, 0.0),
+				),
+				facing_direction: AngularDirection::from_angle_horizontal(
+					thread_rng().gen_range(0.0..TAU),
+				),
+				target_home_index: thread_rng().gen_range(0..homes.len()),
+				homes,
+				idle_time_left: thread_rng().gen_range(0.0..5.0),
+				walking_time: 0.0,
+				body_part: PartHandler::default(),
+				head_part: PartHandler::default(),
+			},
+			naturally_spawned: false,
+			name: None,
+			tether: None,
+			damage_taken: 0,
+			last_hit_time: None,
+		}
+	}
+
+	/// Marks the entity as spawned by the creature spawner.
+	pub(crate) fn spawned_naturally(mut self) -> Entity {
+		self.naturally_spawned = true;
+		self
+	}
+
+	/// Persistent entities are never despawned, only naturally spawned creatures can be
+	/// despawned (when they are far from the player), unless they were given a name.
+	pub(crate) fn is_persistent(&self) -> bool {
+		!self.naturally_spawned || self.name.is_some()
+	}
+
+	pub(crate) fn id(&self) -> Id {
+		self.id
+	}
+
+	pub(crate) fn set_name(&mut self, name: String) {
+		self.name = Some(name);
+	}
+
+	pub(crate) fn tether(&self) -> Option<&Tether> {
+		self.tether.as_ref()
+	}
+
+	pub(crate) fn set_tether(&mut self, tether: Option<Tether>) {
+		self.tether = tether;
+	}
+
+	fn phys_mut(&mut self) -> &mut AlignedPhysBox {
+		match &mut self.typed {
+			EntityTyped::Block { phys, .. } => phys,
+			EntityTyped::TestBall { phys, .. } => phys,
+			EntityTyped::Villager { phys, .. } => phys,
+			EntityTyped::Mount { phys, .. } => phys,
+			EntityTyped::Platform { phys, .. } => phys,
+			EntityTyped::Creature { phys, .. } => phys,
+			EntityTyped::Hostile { phys, .. } => phys,
+		}
+	}
+
+	/// Pulls the entity back towards the anchor of its tether if it went too far.
+	fn solve_tether_constraint(&mut self, chunk_grid: &ChunkGrid) {
+		let Some(tether) = &mut self.tether else {
+			return;
+		};
+		tether.follow_anchor(chunk_grid);
+		let (anchor_pos, length) = (tether.anchor_pos(), tether.length);
+		self.phys_mut().constrain_max_distance_to(anchor_pos, length);
+	}
+
+	pub(crate) fn kind_name(&self) -> &'static str {
+		match &self.typed {
+			EntityTyped::Block { .. } => "block",
+			EntityTyped::TestBall { .. } => "test_ball",
+			EntityTyped::Villager { .. } => "villager",
+			EntityTyped::Mount { .. } => "mount",
+			EntityTyped::Platform { .. } => "platform",
+			EntityTyped::Creature { .. } => "creature",
+			EntityTyped::Hostile { .. } => "hostile",
+		}
+	}
+
+	/// The text to display above the entity, if any.
+	/// The debug details (id and kind) are only there if `with_debug_details` is set.
+	pub(crate) fn label_text(&self, with_debug_details: bool) -> Option<String> {
+		let debug_details =
+			with_debug_details.then(|| format!("#{} {}", self.id.0, self.kind_name()));
+		match (&self.name, debug_details) {
+			(Some(name), Some(debug_details)) => Some(format!("{name}\n{debug_details}")),
+			(Some(name), None) => Some(name.clone()),
+			(None, debug_details) => debug_details,
+		}
+	}
+
+	/// Where the bottom of the label of the entity should be.
+	pub(crate) fn label_pos(&self) -> cgmath::Point3<f32> {
+		let height = self.aligned_box().map_or(0.0, |aligned_box| aligned_box.dims.z);
+		self.pos() + cgmath::vec3(0.0, 0.0, height / 2.0 + 0.3)
+	}
+
+	/// The light carried by the entity, if any (villagers carry a little lantern).
+	/// Only the deferred rendering path makes use of it.
+	pub(crate) fn light(&self) -> Option<PointLight> {
+		match &self.typed {
+			EntityTyped::Villager { .. } => Some(
+				LightEmission { color: [1.0, 0.8, 0.5], radius: 7.0 }
+					.at(self.pos() + cgmath::vec3(0.0, 0.0, 0.5)),
+			),
+			_ => None,
+		}
+	}
+
+	pub(crate) fn new_mount(id_generator: &IdGenerator, pos: cgmath::Point3<f32>) -> Entity {
+		Entity {
+			id: id_generator.generate_id(),
+			typed: EntityTyped::Mount {
+				phys: AlignedPhysBox::new(
+					AlignedBox { pos, dims: MOUNT_DIMS },
+					cgmath::vec3(0.0, 0.0, 0.0),
+				),
+				facing_direction: AngularDirection::from_angle_horizontal(
+					thread_rng().gen_range(0.0..TAU),
+				),
+				rider_control: None,
+				wandering_time_left: 0.0,
+				body_part: PartHandler::default(),
+				head_part: PartHandler::default(),
+				leg_parts: Default::default(),
+			},
+			naturally_spawned: false,
+			name: None,
+			tether: None,
+			damage_taken: 0,
+			last_hit_time: None,
+		}
+	}
+
+	/// The platform starts at `path_start` and goes back and forth to `path_end`.
+	pub(crate) fn new_platform(
+		id_generator: &IdGenerator,
+		path_start: cgmath::Point3<f32>,
+		path_end: cgmath::Point3<f32>,
+	) -> Entity {
+		Entity {
+			id: id_generator.generate_id(),
+			typed: EntityTyped::Platform {
+				phys: AlignedPhysBox::new(
+					AlignedBox { pos: path_start, dims: PLATFORM_DIMS },
+					cgmath::vec3(0.0, 0.0, 0.0),
+				),
+				path_start,
+				path_end,
+				path_progress: 0.0,
+				velocity: cgmath::vec3(0.0, 0.0, 0.0),
+				raft_part: PartHandler::default(),
+			},
+			naturally_spawned: false,
+			name: None,
+			tether: None,
+			damage_taken: 0,
+			last_hit_time: None,
+		}
+	}
+
+	/// The box of the creature is made to fit its model.
+	pub(crate) fn new_creature(
+		id_generator: &IdGenerator,
+		pos: cgmath::Point3<f32>,
+		model_name: String,
+		model: Model,
+	) -> Entity {
+		Entity {
+			id: id_generator.generate_id(),
+			typed: EntityTyped::Creature {
+				phys: AlignedPhysBox::new(
+					AlignedBox { pos, dims: model.dims() },
+					cgmath::vec3(0.0, 0.0, 0.0),
+				),
+				facing_direction: AngularDirection::from_angle_horizontal(
+					thread_rng().gen_range(0.0..TAU),
+				),
+				model_name,
+				model,
+				wandering_time_left: 0.0,
+				parts: vec![],
+			},
+			naturally_spawned: false,
+			name: None,
+			tether: None,
+			damage_taken: 0,
+			last_hit_time: None,
+		}
+	}
+
+	pub(crate) fn new_hostile(id_generator: &IdGenerator, pos: cgmath::Point3<f32>) -> Entity {
+		Entity {
+			id: id_generator.generate_id(),
+			typed: EntityTyped::Hostile {
+				phys: AlignedPhysBox::new(
+					AlignedBox { pos, dims: HOSTILE_DIMS },
+					cgmath::vec3(0.0, 0.0, 0.0),
+				),
+				facing_direction: AngularDirection::from_angle_horizontal(
+					thread_rng().gen_range(0.0..TAU),
+				),
+				last_seen_player_pos: None,
+				attack_cooldown: 0.0,
+				wandering_time_left: 0.0,
+				parts: vec![],
+			},
+			naturally_spawned: false,
+			name: None,
+			tether: None,
+			damage_taken: 0,
+			last_hit_time: None,
+		}
+	}
+
+	/// Hostiles do not stand bright light, they despawn there (see `spawning`).
+	pub(crate) fn despawns_in_light(&self) -> bool {
+		matches!(self.typed, EntityTyped::Hostile { .. })
+	}
+
+	/// How much damage it takes to kill the entity, `None` if it cannot be hurt.
+	fn max_health(&self) -> Option<u32> {
+		match &self.typed {
+			EntityTyped::Block { .. } | EntityTyped::Platform { .. } => None,
+			EntityTyped::TestBall { .. } => Some(3),
+			EntityTyped::Villager { .. } | EntityTyped::Mount { .. } => Some(10),
+			EntityTyped::Creature { .. } => Some(6),
+			EntityTyped::Hostile { .. } => Some(8),
+		}
+	}
+
+	pub(crate) fn can_be_hit(&self) -> bool {
+		self.max_health().is_some()
+	}
+
+	/// Hurts the entity and pushes it away, the knockback is added to its motion.
+	pub(crate) fn hit(&mut self, damage: u32, knockback: cgmath::Vector3<f32>) {
+		if !self.can_be_hit() {
+			return;
+		}
+		self.damage_taken += damage;
+		self.last_hit_time = Some(Instant::now());
+		self.phys_mut().add_motion(knockback);
+	}
+
+	/// Would the entity die from being hit with that much damage?
+	pub(crate) fn is_killed_by(&self, damage: u32) -> bool {
+		self.max_health().is_some_and(|max_health| {
+			self.damage_taken < max_health && self.damage_taken + damage >= max_health
+		})
+	}
+
+	/// Dead entities are removed at the next entity physics step.
+	pub(crate) fn is_dead(&self) -> bool {
+		self.max_health().is_some_and(|max_health| self.damage_taken >= max_health)
+	}
+
+	/// The health bar displayed above the entity for a few seconds after it was hit,
+	/// like `███__` with one character per health point.
+	pub(crate) fn health_bar_text(&self) -> Option<String> {
+		let max_health = self.max_health()?;
+		let recently_hit =
+			self.last_hit_time.is_some_and(|hit_time| hit_time.elapsed() < HEALTH_BAR_DURATION);
+		recently_hit.then(|| {
+			let health = max_health.saturating_sub(self.damage_taken);
+			format!(
+				"{}{}",
+				"█".repeat(health as usize),
+				"_".repeat((max_health - health) as usize)
+			)
+		})
+	}
+
+	/// Gives to the colored parts the red tint that fades away after a hit
+	/// (see `PartColoredInstancePod::hit_tint`).
+	fn update_hit_tint(&mut self, part_tables: &PartTables) {
+		let Some(since_hit) = self.last_hit_time.map(|hit_time| hit_time.elapsed()) else {
+			return;
+		};
+		if since_hit > HEALTH_BAR_DURATION {
+			// The tint faded away long ago.
+			return;
+		}
+		let hit_tint = 1.0 - (since_hit.as_secs_f32() / HIT_TINT_DURATION.as_secs_f32()).min(1.0);
+		let mut colored_cubes = part_tables.colored_cubes.lock().unwrap();
+		let mut set_tint = |part: &mut PartHandler<ColoredCubePartKind>| {
+			part.modify_instance(&mut colored_cubes, |instance| instance.hit_tint = hit_tint);
+		};
+		match &mut self.typed {
+			EntityTyped::Block { .. } | EntityTyped::Platform { .. } => {},
+			EntityTyped::TestBall { ball_part, left_eye_part, right_eye_part, .. } => {
+				set_tint(left_eye_part);
+				set_tint(right_eye_part);
+				ball_part.modify_instance(
+					&mut part_tables.colored_icosahedron.lock().unwrap(),
+					|instance| instance.hit_tint = hit_tint,
+				);
+			},
+			EntityTyped::Villager { body_part, head_part, .. } => {
+				set_tint(body_part);
+				set_tint(head_part);
+			},
+			EntityTyped::Mount { body_part, head_part, leg_parts, .. } => {
+				set_tint(body_part);
+				set_tint(head_part);
+				leg_parts.iter_mut().for_each(set_tint);
+			},
+			EntityTyped::Creature { parts, .. } | EntityTyped::Hostile { parts, .. } => {
+				parts.iter_mut().for_each(set_tint);
+			},
+		}
+	}
+
+	/// If other things can stand on this entity, this is its surface.
+	pub(crate) fn platform_surface(&self) -> Option<PlatformSurface> {
+		match &self.typed {
+			EntityTyped::Platform { phys, velocity, .. } => {
+				Some(PlatformSurface { aligned_box: phys.aligned_box().clone(), velocity: *velocity })
+			},
+			_ => None,
+		}
+	}
+
+	/// Gives the current ids to the blocks of an entity loaded from a save that had other ids
+	/// (see `block_type_remapping`).
+	fn remap_block_type_ids(&mut self, remapping: &BlockTypeIdRemapping) {
+		if let EntityTyped::Block { block, .. } = &mut self.typed {
+			remapping.remap_block(block);
 		}
 	}
 
@@ -154,6 +749,25 @@ impl Entity {
 		match &self.typed {
 			EntityTyped::Block { phys, .. } => phys.aligned_box().pos,
 			EntityTyped::TestBall { phys, .. } => phys.aligned_box().pos,
+			EntityTyped::Villager { phys, .. } => phys.aligned_box().pos,
+			EntityTyped::Mount { phys, .. } => phys.aligned_box().pos,
+			EntityTyped::Platform { phys, .. } => phys.aligned_box().pos,
+			EntityTyped::Creature { phys, .. } => phys.aligned_box().pos,
+			EntityTyped::Hostile { phys, .. } => phys.aligned_box().pos,
+		}
+	}
+
+	/// Only moves the entity, other positions it may remember (like the path of a platform)
+	/// are left as they are.
+	pub(crate) fn impose_position(&mut self, pos: cgmath::Point3<f32>) {
+		match &mut self.typed {
+			EntityTyped::Block { phys, .. } => phys.impose_position(pos),
+			EntityTyped::TestBall { phys, .. } => phys.impose_position(pos),
+			EntityTyped::Villager { phys, .. } => phys.impose_position(pos),
+			EntityTyped::Mount { phys, .. } => phys.impose_position(pos),
+			EntityTyped::Platform { phys, .. } => phys.impose_position(pos),
+			EntityTyped::Creature { phys, .. } => phys.impose_position(pos),
+			EntityTyped::Hostile { phys, .. } => phys.impose_position(pos),
 		}
 	}
 
@@ -166,6 +780,35 @@ impl Entity {
 		match &self.typed {
 			EntityTyped::Block { phys, .. } => Some(phys.aligned_box().clone()),
 			EntityTyped::TestBall { phys, .. } => Some(phys.aligned_box().clone()),
+			EntityTyped::Villager { phys, .. } => Some(phys.aligned_box().clone()),
+			EntityTyped::Mount { phys, .. } => Some(phys.aligned_box().clone()),
+			EntityTyped::Platform { phys, .. } => Some(phys.aligned_box().clone()),
+			EntityTyped::Creature { phys, .. } => Some(phys.aligned_box().clone()),
+			EntityTyped::Hostile { phys, .. } => Some(phys.aligned_box().clone()),
+		}
+	}
+
+	pub(crate) fn is_mountable(&self) -> bool {
+		matches!(self.typed, EntityTyped::Mount { .. })
+	}
+
+	/// Where the rider should be (the bottom of its box) if this entity is a mount.
+	pub(crate) fn seat_pos(&self) -> Option<cgmath::Point3<f32>> {
+		match &self.typed {
+			EntityTyped::Mount { phys, .. } => {
+				Some(phys.aligned_box().pos + cgmath::vec3(0.0, 0.0, MOUNT_SEAT_HEIGHT))
+			},
+			_ => None,
+		}
+	}
+
+	/// Gives the control of a mount to its rider (or gives it back to the mount with `None`).
+	/// A requested jump is kept until it happens even if a new control says otherwise.
+	pub(crate) fn set_mount_control(&mut self, control: Option<MountControl>) {
+		if let EntityTyped::Mount { rider_control, .. } = &mut self.typed {
+			let jump_requested = rider_control.as_ref().is_some_and(|control| control.jump);
+			*rider_control =
+				control.map(|control| MountControl { jump: control.jump || jump_requested, ..control });
 		}
 	}
 
@@ -183,6 +826,8 @@ impl Entity {
 		entity_physics_dt: std::time::Duration,
 		part_manipulation: &ForPartManipulation,
 		id_generator: &IdGenerator,
+		player_box: Option<&AlignedBox>,
+		player_hits: &mut Vec<PlayerHit>,
 	) {
 		match self.typed {
 			EntityTyped::Block { .. } => {
@@ -195,6 +840,7 @@ impl Entity {
 						block_type_table,
 						entity_physics_dt,
 						true,
+						false,
 					);
 
 					phys.on_ground_and_not_overlapping()
@@ -337,6 +983,7 @@ impl Entity {
 						block_type_table,
 						entity_physics_dt,
 						true,
+						false,
 					);
 
 					// Just to see if it worked, it sometimes throw a leaf block.
@@ -445,6 +1092,517 @@ impl Entity {
 
 				entities_for_next_step.push(next_ball);
 			},
+
+			EntityTyped::Villager { .. } => {
+				let mut next_villager = self.clone();
+
+				if let EntityTyped::Villager {
+					phys,
+					facing_direction,
+					homes,
+					target_home_index,
+					idle_time_left,
+					walking_time,
+					..
+				} = &mut next_villager.typed
+				{
+					let dt = entity_physics_dt.as_secs_f32();
+					let mut walking = cgmath::vec3(0.0, 0.0, 0.0);
+					if *idle_time_left > 0.0 {
+						*idle_time_left -= dt;
+					} else {
+						let target = homes[*target_home_index];
+						let mut to_target = target - phys.aligned_box().pos;
+						to_target.z = 0.0;
+						let arrived = to_target.magnitude() < 1.0;
+						let gave_up = *walking_time > 40.0;
+						if arrived || gave_up {
+							// Rest a bit, then go visit some other house.
+							*idle_time_left = thread_rng().gen_range(3.0..12.0);
+							*walking_time = 0.0;
+							*target_home_index = thread_rng().gen_range(0..homes.len());
+						} else {
+							*walking_time += dt;
+							*facing_direction = AngularDirection::from_angle_horizontal(f32::atan2(
+								to_target.y,
+								to_target.x,
+							));
+							walking = to_target.normalize() * 2.0;
+						}
+					}
+
+					// Jump over single blocks that are in the way.
+					let blocked = phys.is_blocked_horizontally_towards(walking);
+					if blocked && phys.on_ground_and_not_overlapping() {
+						phys.add_motion(cgmath::vec3(0.0, 0.0, 0.1));
+					}
+
+					phys.apply_one_physics_step(
+						walking,
+						chunk_grid,
+						block_type_table,
+						entity_physics_dt,
+						true,
+						false,
+					);
+				} else {
+					unreachable!()
+				};
+
+				// Manage the parts.
+				let pos = next_villager.pos();
+				if let EntityTyped::Villager { body_part, head_part, facing_direction, .. } =
+					&mut next_villager.typed
+				{
+					let rotation = cgmath::Matrix4::<f32>::from_angle_z(cgmath::Rad(
+						facing_direction.angle_horizontal,
+					));
+					let parts = [
+						(
+							body_part,
+							[120, 80, 50],
+							-0.3,
+							cgmath::vec3(0.35, 0.55, 1.1),
+						),
+						(
+							head_part,
+							[230, 190, 150],
+							0.55,
+							cgmath::vec3(0.45, 0.45, 0.45),
+						),
+					];
+					for (part, color, height_offset, scale) in parts {
+						part.ensure_is_allocated(
+							&mut part_manipulation.part_tables.colored_cubes.lock().unwrap(),
+							|| {
+								let coloring_offset = part_manipulation
+									.texture_mapping_and_coloring_table
+									.get_offset_of_cube_coloring_uni(
+										color,
+										&part_manipulation.texturing_and_coloring_array_thingy,
+										&part_manipulation.queue,
+									);
+								PartColoredCubeInstanceData::new(pos, coloring_offset).into_pod()
+							},
+						);
+						part.modify_instance(
+							&mut part_manipulation.part_tables.colored_cubes.lock().unwrap(),
+							|instance| {
+								instance.set_model_matrix(
+									&(cgmath::Matrix4::<f32>::from_translation(
+										pos.to_vec() + cgmath::vec3(0.0, 0.0, height_offset),
+									) * rotation * cgmath::Matrix4::<f32>::from_nonuniform_scale(
+										scale.x, scale.y, scale.z,
+									)),
+								);
+							},
+						);
+					}
+				}
+
+				entities_for_next_step.push(next_villager);
+			},
+
+			EntityTyped::Mount { .. } => {
+				let mut next_mount = self.clone();
+
+				if let EntityTyped::Mount {
+					phys,
+					facing_direction,
+					rider_control,
+					wandering_time_left,
+					..
+				} = &mut next_mount.typed
+				{
+					let dt = entity_physics_dt.as_secs_f32();
+					let walking = if let Some(control) = rider_control {
+						if control.jump && phys.on_ground_and_not_overlapping() {
+							phys.add_motion(cgmath::vec3(0.0, 0.0, 0.13));
+							control.jump = false;
+						}
+						control.walking * 18.0
+					} else {
+						// Wander around.
+						if *wandering_time_left > 0.0 {
+							*wandering_time_left -= dt;
+							if *wandering_time_left <= 0.0 {
+								*wandering_time_left = -thread_rng().gen_range(2.0..10.0);
+							}
+						} else {
+							*wandering_time_left += dt;
+							if *wandering_time_left >= 0.0 {
+								*wandering_time_left = thread_rng().gen_range(1.0..4.0);
+								*facing_direction = AngularDirection::from_angle_horizontal(
+									thread_rng().gen_range(0.0..TAU),
+								);
+							}
+						}
+						if *wandering_time_left > 0.0 {
+							facing_direction.to_horizontal().to_vec3() * 2.5
+						} else {
+							cgmath::vec3(0.0, 0.0, 0.0)
+						}
+					};
+					if walking.magnitude() > 0.0 {
+						*facing_direction =
+							AngularDirection::from_angle_horizontal(f32::atan2(walking.y, walking.x));
+					}
+
+					// Jump over single blocks that are in the way.
+					let blocked = phys.is_blocked_horizontally_towards(walking);
+					if blocked && phys.on_ground_and_not_overlapping() {
+						phys.add_motion(cgmath::vec3(0.0, 0.0, 0.1));
+					}
+
+					phys.apply_one_physics_step(
+						walking,
+						chunk_grid,
+						block_type_table,
+						entity_physics_dt,
+						true,
+						false,
+					);
+				} else {
+					unreachable!()
+				};
+
+				// Manage the parts.
+				let pos = next_mount.pos();
+				if let EntityTyped::Mount {
+					body_part, head_part, leg_parts, facing_direction, ..
+				} = &mut next_mount.typed
+				{
+					let rotation = cgmath::Matrix4::<f32>::from_angle_z(cgmath::Rad(
+						facing_direction.angle_horizontal,
+					));
+					// The offsets are in the frame of the mount (x is forward, z is up).
+					let [leg_a, leg_b, leg_c, leg_d] = leg_parts;
+					let leg_scale = cgmath::vec3(0.2, 0.2, 0.55);
+					let parts = [
+						(
+							body_part,
+							[150, 100, 60],
+							cgmath::vec3(0.0, 0.0, 0.15),
+							cgmath::vec3(1.3, 0.6, 0.55),
+						),
+						(
+							head_part,
+							[120, 80, 50],
+							cgmath::vec3(0.75, 0.0, 0.45),
+							cgmath::vec3(0.5, 0.4, 0.45),
+						),
+						(
+							leg_a,
+							[90, 60, 40],
+							cgmath::vec3(0.45, 0.2, -0.37),
+							leg_scale,
+						),
+						(
+							leg_b,
+							[90, 60, 40],
+							cgmath::vec3(0.45, -0.2, -0.37),
+							leg_scale,
+						),
+						(
+							leg_c,
+							[90, 60, 40],
+							cgmath::vec3(-0.45, 0.2, -0.37),
+							leg_scale,
+						),
+						(
+							leg_d,
+							[90, 60, 40],
+							cgmath::vec3(-0.45, -0.2, -0.37),
+							leg_scale,
+						),
+					];
+					for (part, color, offset, scale) in parts {
+						part.ensure_is_allocated(
+							&mut part_manipulation.part_tables.colored_cubes.lock().unwrap(),
+							|| {
+								let coloring_offset = part_manipulation
+									.texture_mapping_and_coloring_table
+									.get_offset_of_cube_coloring_uni(
+										color,
+										&part_manipulation.texturing_and_coloring_array_thingy,
+										&part_manipulation.queue,
+									);
+								PartColoredCubeInstanceData::new(pos, coloring_offset).into_pod()
+							},
+						);
+						part.modify_instance(
+							&mut part_manipulation.part_tables.colored_cubes.lock().unwrap(),
+							|instance| {
+								instance.set_model_matrix(
+									&(cgmath::Matrix4::<f32>::from_translation(pos.to_vec())
+										* rotation * cgmath::Matrix4::<f32>::from_translation(offset)
+										* cgmath::Matrix4::<f32>::from_nonuniform_scale(
+											scale.x, scale.y, scale.z,
+										)),
+								);
+							},
+						);
+					}
+				}
+
+				entities_for_next_step.push(next_mount);
+			},
+
+			EntityTyped::Platform { .. } => {
+				let mut next_platform = self.clone();
+
+				if let EntityTyped::Platform {
+					phys,
+					path_start,
+					path_end,
+					path_progress,
+					velocity,
+					..
+				} = &mut next_platform.typed
+				{
+					let dt = entity_physics_dt.as_secs_f32();
+					let path_length = path_start.distance(*path_end).max(0.1);
+					*path_progress =
+						(*path_progress + PLATFORM_SPEED * dt / path_length).rem_euclid(2.0);
+					let t = if *path_progress < 1.0 {
+						*path_progress
+					} else {
+						2.0 - *path_progress
+					};
+					let previous_pos = phys.aligned_box().pos;
+					let pos = *path_start + (*path_end - *path_start) * t;
+					*velocity = if dt == 0.0 {
+						cgmath::vec3(0.0, 0.0, 0.0)
+					} else {
+						(pos - previous_pos) / dt
+					};
+					phys.impose_position(pos);
+				} else {
+					unreachable!()
+				};
+
+				// Manage the part.
+				let pos = next_platform.pos();
+				if let EntityTyped::Platform { raft_part, .. } = &mut next_platform.typed {
+					raft_part.ensure_is_allocated(
+						&mut part_manipulation.part_tables.colored_cubes.lock().unwrap(),
+						|| {
+							let coloring_offset = part_manipulation
+								.texture_mapping_and_coloring_table
+								.get_offset_of_cube_coloring_uni(
+									[150, 110, 70],
+									&part_manipulation.texturing_and_coloring_array_thingy,
+									&part_manipulation.queue,
+								);
+							PartColoredCubeInstanceData::new(pos, coloring_offset).into_pod()
+						},
+					);
+					raft_part.modify_instance(
+						&mut part_manipulation.part_tables.colored_cubes.lock().unwrap(),
+						|instance| {
+							instance.set_model_matrix(
+								&(cgmath::Matrix4::<f32>::from_translation(pos.to_vec())
+									* cgmath::Matrix4::<f32>::from_nonuniform_scale(
+										PLATFORM_DIMS.x,
+										PLATFORM_DIMS.y,
+										PLATFORM_DIMS.z,
+									)),
+							);
+						},
+					);
+				}
+
+				entities_for_next_step.push(next_platform);
+			},
+
+			EntityTyped::Creature { .. } => {
+				let mut next_creature = self.clone();
+
+				if let EntityTyped::Creature { phys, facing_direction, wandering_time_left, .. } =
+					&mut next_creature.typed
+				{
+					// Wander around, like a mount that is not ridden.
+					let dt = entity_physics_dt.as_secs_f32();
+					if *wandering_time_left > 0.0 {
+						*wandering_time_left -= dt;
+						if *wandering_time_left <= 0.0 {
+							*wandering_time_left = -thread_rng().gen_range(2.0..10.0);
+						}
+					} else {
+						*wandering_time_left += dt;
+						if *wandering_time_left >= 0.0 {
+							*wandering_time_left = thread_rng().gen_range(1.0..4.0);
+							*facing_direction =
+								AngularDirection::from_angle_horizontal(thread_rng().gen_range(0.0..TAU));
+						}
+					}
+					let walking = if *wandering_time_left > 0.0 {
+						facing_direction.to_horizontal().to_vec3() * 2.0
+					} else {
+						cgmath::vec3(0.0, 0.0, 0.0)
+					};
+
+					// Jump over single blocks that are in the way.
+					let blocked = phys.is_blocked_horizontally_towards(walking);
+					if blocked && phys.on_ground_and_not_overlapping() {
+						phys.add_motion(cgmath::vec3(0.0, 0.0, 0.1));
+					}
+
+					phys.apply_one_physics_step(
+						walking,
+						chunk_grid,
+						block_type_table,
+						entity_physics_dt,
+						true,
+						false,
+					);
+				} else {
+					unreachable!()
+				};
+
+				// Manage the parts.
+				let pos = next_creature.pos();
+				if let EntityTyped::Creature { model, parts, facing_direction, .. } =
+					&mut next_creature.typed
+				{
+					let transform = cgmath::Matrix4::<f32>::from_translation(pos.to_vec())
+						* cgmath::Matrix4::<f32>::from_angle_z(cgmath::Rad(
+							facing_direction.angle_horizontal,
+						));
+					model.update_parts(parts, transform, part_manipulation);
+				}
+
+				entities_for_next_step.push(next_creature);
+			},
+
+			EntityTyped::Hostile { .. } => {
+				let mut next_hostile = self.clone();
+
+				if let EntityTyped::Hostile {
+					phys,
+					facing_direction,
+					last_seen_player_pos,
+					attack_cooldown,
+					wandering_time_left,
+					..
+				} = &mut next_hostile.typed
+				{
+					let dt = entity_physics_dt.as_secs_f32();
+					*attack_cooldown = (*attack_cooldown - dt).max(0.0);
+					let pos = phys.aligned_box().pos;
+					let eye_pos = pos + cgmath::vec3(0.0, 0.0, HOSTILE_DIMS.z * 0.35);
+					let seen_player_box = player_box.filter(|player_box| {
+						player_box.pos.distance(pos) <= HOSTILE_AGGRO_RADIUS
+							&& chunk_grid.is_line_of_sight_clear(eye_pos, player_box.pos, block_type_table)
+					});
+					if let Some(player_box) = seen_player_box {
+						*last_seen_player_pos = Some(player_box.pos);
+					}
+
+					let walking = if let Some(target) = *last_seen_player_pos {
+						// Chase the player, or go where it was last seen.
+						let mut to_target = target - pos;
+						to_target.z = 0.0;
+						if to_target.magnitude() < 0.5 {
+							if seen_player_box.is_none() {
+								// Lost track of the player.
+								*last_seen_player_pos = None;
+							}
+							cgmath::vec3(0.0, 0.0, 0.0)
+						} else {
+							// Along a path around the obstacles if there is one, in a straight line
+							// (hoping for the best) when in the air or when there is nowhere to go.
+							let feet_cell = |center: cgmath::Point3<f32>| -> BlockCoords {
+								let feet = center - cgmath::vec3(0.0, 0.0, HOSTILE_DIMS.z / 2.0);
+								cgmath::point3(feet.x.round(), feet.y.round(), (feet.z + 0.5).round())
+									.map(|x| x as i32)
+							};
+							let is_opaque = |coords: BlockCoords| -> Option<bool> {
+								chunk_grid
+									.get_block(coords)
+									.map(|block| block_type_table.get(block.type_id).unwrap().is_opaque())
+							};
+							let step = first_step_towards(feet_cell(pos), feet_cell(target), is_opaque);
+							let mut direction = match step {
+								Some(step) => step.map(|x| x as f32) - pos,
+								None => to_target,
+							};
+							direction.z = 0.0;
+							if direction.magnitude() < 0.01 {
+								direction = to_target;
+							}
+							*facing_direction = AngularDirection::from_angle_horizontal(f32::atan2(
+								direction.y,
+								direction.x,
+							));
+							direction.normalize() * HOSTILE_CHASING_SPEED
+						}
+					} else {
+						// Wander around, like a creature.
+						if *wandering_time_left > 0.0 {
+							*wandering_time_left -= dt;
+							if *wandering_time_left <= 0.0 {
+								*wandering_time_left = -thread_rng().gen_range(2.0..10.0);
+							}
+						} else {
+							*wandering_time_left += dt;
+							if *wandering_time_left >= 0.0 {
+								*wandering_time_left = thread_rng().gen_range(1.0..4.0);
+								*facing_direction = AngularDirection::from_angle_horizontal(
+									thread_rng().gen_range(0.0..TAU),
+								);
+							}
+						}
+						if *wandering_time_left > 0.0 {
+							facing_direction.to_horizontal().to_vec3() * 1.5
+						} else {
+							cgmath::vec3(0.0, 0.0, 0.0)
+						}
+					};
+
+					// Hit the player when close enough.
+					if let Some(player_box) = seen_player_box {
+						let reach_box = AlignedBox {
+							pos,
+							dims: HOSTILE_DIMS + cgmath::vec3(1.0, 1.0, 1.0) * HOSTILE_ATTACK_REACH * 2.0,
+						};
+						if *attack_cooldown <= 0.0 && reach_box.overlaps(player_box) {
+							player_hits
+								.push(PlayerHit { damage: HOSTILE_ATTACK_DAMAGE, attacker_pos: pos });
+							*attack_cooldown = HOSTILE_ATTACK_COOLDOWN;
+						}
+					}
+
+					// Jump over single blocks that are in the way.
+					let blocked = phys.is_blocked_horizontally_towards(walking);
+					if blocked && phys.on_ground_and_not_overlapping() {
+						phys.add_motion(cgmath::vec3(0.0, 0.0, 0.1));
+					}
+
+					phys.apply_one_physics_step(
+						walking,
+						chunk_grid,
+						block_type_table,
+						entity_physics_dt,
+						true,
+						false,
+					);
+				} else {
+					unreachable!()
+				};
+
+				// Manage the parts.
+				let pos = next_hostile.pos();
+				if let EntityTyped::Hostile { parts, facing_direction, .. } = &mut next_hostile.typed {
+					let transform = cgmath::Matrix4::<f32>::from_translation(pos.to_vec())
+						* cgmath::Matrix4::<f32>::from_angle_z(cgmath::Rad(
+							facing_direction.angle_horizontal,
+						));
+					HOSTILE_MODEL.update_parts(parts, transform, part_manipulation);
+				}
+
+				entities_for_next_step.push(next_hostile);
+			},
 		}
 	}
 
@@ -463,6 +1621,23 @@ impl Entity {
 				left_eye_part.delete(&mut part_tables.colored_cubes.lock().unwrap());
 				right_eye_part.delete(&mut part_tables.colored_cubes.lock().unwrap());
 			},
+			EntityTyped::Villager { body_part, head_part, .. } => {
+				body_part.delete(&mut part_tables.colored_cubes.lock().unwrap());
+				head_part.delete(&mut part_tables.colored_cubes.lock().unwrap());
+			},
+			EntityTyped::Mount { body_part, head_part, leg_parts, .. } => {
+				body_part.delete(&mut part_tables.colored_cubes.lock().unwrap());
+				head_part.delete(&mut part_tables.colored_cubes.lock().unwrap());
+				for leg_part in leg_parts {
+					leg_part.delete(&mut part_tables.colored_cubes.lock().unwrap());
+				}
+			},
+			EntityTyped::Platform { raft_part, .. } => {
+				raft_part.delete(&mut part_tables.colored_cubes.lock().unwrap());
+			},
+			EntityTyped::Creature { parts, .. } | EntityTyped::Hostile { parts, .. } => {
+				delete_parts(parts, part_tables);
+			},
 		}
 	}
 }
@@ -477,11 +1652,39 @@ pub(crate) struct ForPartManipulation {
 }
 
 /// The entities of a chunk.
+///
+/// An entity is owned by the chunk that contains its position, and an entity is always owned
+/// by exactly one place at a time, it is either:
+/// - in the `ChunkEntities` of its chunk in the `ChunkGrid` (even if the chunk is not loaded,
+///   an entity can move to a chunk that is not loaded, it is then frozen there),
+/// - in the middle of an entity physics step (which hands the entities over to the chunks they
+///   end up in, see `ChunkEntities::hand_over_to_their_chunks`),
+/// - in a chunk loading task (between the moment it takes the entities from the save and
+///   the moment the loading results are added to the `ChunkGrid`),
+/// - or in the save, in the entity file of its chunk.
+///
+/// Going to and from the save is what must be done carefully: reading entities from the save
+/// removes them from the save (atomically, so that they cannot be read twice), and writing
+/// entities to the save adds them to the entities that may already be there (as these are not
+/// owned by anything else). This way the entities are neither duplicated nor lost when
+/// the chunks are loaded and unloaded as the entities move around.
 #[derive(Clone)]
 pub(crate) struct ChunkEntities {
 	pub(crate) coords_span: ChunkCoordsSpan,
 	savable: ChunkEntitiesSavable,
 }
+/// Version of the format in which the entities of a chunk are saved.
+///
+/// Version 0 is the format from before there were versions, where the fields of entities
+/// were encoded in order without their names. Since version 1 the fields are encoded with
+/// their names, so that entity fields can be added (with `#[serde(default)]`) or removed
+/// without breaking existing saves. Changes that cannot be handled that way should bump
+/// the version and handle the previous versions in `ChunkEntitiesSavable::decode`.
+const ENTITIES_SAVE_FORMAT_VERSION: u16 = 1;
+/// Entity chunk files start with this and then the format version (as little endian `u16`),
+/// except version 0 files that start right away with the MessagePack data.
+const ENTITIES_SAVE_MAGIC: &[u8; 4] = b"qwye";
+
 #[derive(Clone, Serialize, Deserialize)]
 struct ChunkEntitiesSavable {
 	entities: Vec<Entity>,
@@ -491,6 +1694,38 @@ struct ChunkEntitiesSavable {
 	max_entity_dims: cgmath::Vector3<f32>,
 }
 
+impl ChunkEntitiesSavable {
+	fn encode(&self) -> Vec<u8> {
+		let mut data = ENTITIES_SAVE_MAGIC.to_vec();
+		data.extend(ENTITIES_SAVE_FORMAT_VERSION.to_le_bytes());
+		data.extend(rmp_serde::encode::to_vec_named(self).unwrap());
+		data
+	}
+
+	fn decompress_and_decode(compressed_data: &[u8]) -> Result<ChunkEntitiesSavable, String> {
+		let mut uncompressed_data = vec![];
+		let mut decoder = flate2::bufread::DeflateDecoder::new(compressed_data);
+		decoder.read_to_end(&mut uncompressed_data).map_err(|error| error.to_string())?;
+		ChunkEntitiesSavable::decode(&uncompressed_data)
+	}
+
+	fn decode(data: &[u8]) -> Result<ChunkEntitiesSavable, String> {
+		let (version, data) = match data.strip_prefix(ENTITIES_SAVE_MAGIC) {
+			Some(&[low, high, ref data @ ..]) => (u16::from_le_bytes([low, high]), data),
+			Some(_) => return Err("truncated header".to_string()),
+			None => (0, data),
+		};
+		match version {
+			// Version 0 only differs in the encoding of fields, which the decoder handles.
+			0 | 1 => rmp_serde::decode::from_slice(data).map_err(|error| error.to_string()),
+			_ if version > ENTITIES_SAVE_FORMAT_VERSION => Err(format!(
+				"format version {version} is from a more recent version of the game"
+			)),
+			_ => Err(format!("unknown format version {version}")),
+		}
+	}
+}
+
 impl ChunkEntities {
 	pub(crate) fn new_empty(coords_span: ChunkCoordsSpan) -> ChunkEntities {
 		ChunkEntities {
@@ -524,6 +1759,11 @@ impl ChunkEntities {
 	pub(crate) fn count_entities(&self) -> usize {
 		self.savable.entities.len()
 	}
+	pub(crate) fn remap_block_type_ids(&mut self, remapping: &BlockTypeIdRemapping) {
+		for entity in self.savable.entities.iter_mut() {
+			entity.remap_block_type_ids(remapping);
+		}
+	}
 	pub(crate) fn max_entity_dims(&self) -> cgmath::Vector3<f32> {
 		self.savable.max_entity_dims
 	}
@@ -556,9 +1796,16 @@ impl ChunkEntities {
 		entity_physics_dt: std::time::Duration,
 		part_manipulation: &ForPartManipulation,
 		id_generator: &IdGenerator,
+		player_box: Option<&AlignedBox>,
+		player_hits: &mut Vec<PlayerHit>,
 	) {
 		let mut entities_for_next_step = vec![];
 		for entity in chunk_grid.get_chunk_entities(chunk_coords).unwrap().savable.entities.iter() {
+			if entity.is_dead() {
+				entity.handle_unloading_or_deletion(&part_manipulation.part_tables);
+				continue;
+			}
+			let first_next_entity_index = entities_for_next_step.len();
 			entity.apply_one_physics_step(
 				&mut entities_for_next_step,
 				chunk_grid,
@@ -567,20 +1814,55 @@ impl ChunkEntities {
 				entity_physics_dt,
 				part_manipulation,
 				id_generator,
+				player_box,
+				player_hits,
 			);
+			for next_entity in entities_for_next_step[first_next_entity_index..].iter_mut() {
+				next_entity.solve_tether_constraint(chunk_grid);
+				next_entity.update_hit_tint(&part_manipulation.part_tables);
+			}
 		}
-		for entity in entities_for_next_step {
+		ChunkEntities::hand_over_to_their_chunks(entities_for_next_step, cd, next_entities_map);
+	}
+
+	/// Puts the given entities in the `ChunkEntities` of the chunks that contain them,
+	/// this is how entities move from chunk to chunk.
+	pub(crate) fn hand_over_to_their_chunks(
+		entities: Vec<Entity>,
+		cd: ChunkDimensions,
+		next_entities_map: &mut FxHashMap<ChunkCoords, ChunkEntities>,
+	) {
+		for entity in entities {
 			let chunk_coords = entity.chunk_coords(cd);
 			next_entities_map
 				.entry(chunk_coords)
-				.or_insert(ChunkEntities::new_empty(ChunkCoordsSpan {
-					cd,
-					chunk_coords,
-				}))
+				.or_insert_with(|| ChunkEntities::new_empty(ChunkCoordsSpan { cd, chunk_coords }))
 				.add_entity(entity);
 		}
 	}
 
+	pub(crate) fn get_entity_mut(&mut self, id: Id) -> Option<&mut Entity> {
+		self.savable.entities.iter_mut().find(|entity| entity.id == id)
+	}
+
+	/// Removes the entities for which `should_be_removed` returns true,
+	/// returns how many were removed.
+	pub(crate) fn remove_entities_that(
+		&mut self,
+		mut should_be_removed: impl FnMut(&Entity) -> bool,
+		part_tables: &PartTables,
+	) -> usize {
+		let count_before = self.count_entities();
+		self.savable.entities.retain(|entity| {
+			let remove = should_be_removed(entity);
+			if remove {
+				entity.handle_unloading_or_deletion(part_tables);
+			}
+			!remove
+		});
+		count_before - self.count_entities()
+	}
+
 	/// Tells the entities that they are being unloaded.
 	pub(crate) fn handle_unloading(&self, part_tables: &PartTables) {
 		for entity in self.savable.entities.iter() {
@@ -592,7 +1874,42 @@ impl ChunkEntities {
 		// TODO: Use buffered streams instead of full vecs of data as intermediary steps.
 		let chunk_file_path =
 			save.chunk_file_path(self.coords_span.chunk_coords, WhichChunkFile::Entities);
-		let uncompressed_data = rmp_serde::encode::to_vec(&self.savable).unwrap();
+		let chunk_file = save.get_file_io(chunk_file_path.clone());
+		let chunk_file = chunk_file.lock();
+		// The entities that are already saved in this chunk are not loaded (else they would have
+		// been removed from the save), they are kept along with the ones saved now.
+		let mut savable = self.savable.clone();
+		if let Some(compressed_data) = chunk_file.read() {
+			match ChunkEntitiesSavable::decompress_and_decode(&compressed_data) {
+				Ok(already_saved) => {
+					for entity in already_saved.entities {
+						ChunkEntities::extended_max_entity_dims(&mut savable.max_entity_dims, &entity);
+						savable.entities.push(entity);
+					}
+				},
+				Err(error) => {
+					// The file is never overwritten, its data is kept aside.
+					match chunk_file.move_aside_as_corrupt() {
+						Ok(aside_path) => log::error!(
+							"Failed to read the entities already saved in \"{}\", \
+							the file is kept as \"{}\": {error}",
+							chunk_file_path.display(),
+							aside_path.display()
+						),
+						Err(move_error) => {
+							log::error!(
+								"Failed to read the entities already saved in \"{}\": {error} \
+								(and failed to move the file aside: {move_error}), \
+								the entities of the chunk are not saved",
+								chunk_file_path.display()
+							);
+							return;
+						},
+					}
+				},
+			}
+		}
+		let uncompressed_data = savable.encode();
 		let mut compressed_data = vec![];
 		{
 			let mut encoder = flate2::write::DeflateEncoder::new(
@@ -601,7 +1918,6 @@ impl ChunkEntities {
 			);
 			encoder.write_all(&uncompressed_data).unwrap();
 		}
-		let chunk_file = save.get_file_io(chunk_file_path);
 		chunk_file.write(&compressed_data);
 	}
 
@@ -612,22 +1928,43 @@ impl ChunkEntities {
 		// TODO: Use buffered streams instead of full vecs of data as intermediary steps.
 		let chunk_file_path =
 			save.chunk_file_path(coords_span.chunk_coords, WhichChunkFile::Entities);
-		let chunk_file = save.get_file_io(chunk_file_path);
-		let compressed_data = chunk_file.read(true)?;
-		let mut uncompressed_data = vec![];
-		{
-			let mut decoder = flate2::bufread::DeflateDecoder::new(compressed_data.as_slice());
-			decoder.read_to_end(&mut uncompressed_data).unwrap();
+		let chunk_file = save.get_file_io(chunk_file_path.clone());
+		// The file stays locked until it is deleted, so that no other thread can read the same
+		// entities in the meantime (which would duplicate them).
+		let chunk_file = chunk_file.lock();
+		let compressed_data = chunk_file.read()?;
+		match ChunkEntitiesSavable::decompress_and_decode(&compressed_data) {
+			Ok(savable) => {
+				// The entities now live in the loaded world, they will be saved again on unloading
+				// (maybe in some other chunk if they move), so the file must not stay around.
+				chunk_file.delete();
+				Some(ChunkEntities { coords_span, savable })
+			},
+			Err(error) => {
+				// The file is moved aside so that its entities are not lost for good (it would be
+				// overwritten when entities are saved in this chunk).
+				match chunk_file.move_aside_as_corrupt() {
+					Ok(aside_path) => log::error!(
+						"Failed to load the entities from \"{}\", the file is kept as \"{}\": {error}",
+						chunk_file_path.display(),
+						aside_path.display()
+					),
+					Err(move_error) => log::error!(
+						"Failed to load the entities from \"{}\": {error} \
+						(and failed to move the file aside: {move_error})",
+						chunk_file_path.display()
+					),
+				}
+				None
+			},
 		}
-		let savable: ChunkEntitiesSavable =
-			rmp_serde::decode::from_slice(&uncompressed_data).unwrap();
-		Some(ChunkEntities { coords_span, savable })
 	}
 }
 
 pub(crate) struct EntitiesPhysicsStepResult {
 	pub(crate) next_entities_map: FxHashMap<ChunkCoords, ChunkEntities>,
 	pub(crate) actions_on_world: Vec<ActionOnWorld>,
+	pub(crate) player_hits: Vec<PlayerHit>,
 }
 
 /// When entity physics tasks are done, they are not all done at the same time,
@@ -639,6 +1976,7 @@ pub(crate) struct EntitiesPhysicsStepCollector {
 	chunk_entities_to_preserve: Vec<ChunkCoords>,
 	next_entities_map: FxHashMap<ChunkCoords, ChunkEntities>,
 	actions_on_world: Vec<ActionOnWorld>,
+	player_hits: Vec<PlayerHit>,
 }
 
 impl EntitiesPhysicsStepCollector {
@@ -653,6 +1991,7 @@ impl EntitiesPhysicsStepCollector {
 			chunk_entities_to_preserve,
 			next_entities_map,
 			actions_on_world,
+			player_hits: vec![],
 		}
 	}
 
@@ -669,6 +2008,7 @@ impl EntitiesPhysicsStepCollector {
 			}
 		}
 		self.actions_on_world.append(&mut task_result.actions_on_world);
+		self.player_hits.append(&mut task_result.player_hits);
 		assert!(self.number_of_tasks_not_yet_completed >= 1);
 		self.number_of_tasks_not_yet_completed -= 1;
 	}
@@ -688,12 +2028,265 @@ impl EntitiesPhysicsStepCollector {
 		FxHashMap<ChunkCoords, ChunkEntities>,
 		Vec<ActionOnWorld>,
 		Vec<ChunkCoords>,
+		Vec<PlayerHit>,
 	) {
 		assert_eq!(self.number_of_tasks_not_yet_completed, 0);
 		(
 			self.next_entities_map,
 			self.actions_on_world,
 			self.chunk_entities_to_preserve,
+			self.player_hits,
 		)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	#[test]
+	fn entities_save_format_versions() {
+		let id_generator = IdGenerator::new();
+		let savable = ChunkEntitiesSavable {
+			entities: vec![Entity::new_test_ball(
+				&id_generator,
+				cgmath::point3(1.0, 2.0, 3.0),
+				cgmath::vec3(0.0, 0.0, 0.0),
+			)],
+			max_entity_dims: cgmath::vec3(1.0, 1.0, 1.0),
+		};
+		// Version 0 files have no header and unnamed fields.
+		let version_0_data = rmp_serde::encode::to_vec(&savable).unwrap();
+		for data in [savable.encode(), version_0_data] {
+			let decoded = ChunkEntitiesSavable::decode(&data).unwrap();
+			assert_eq!(decoded.entities.len(), 1);
+			assert!(decoded.entities[0].id == savable.entities[0].id);
+			assert_eq!(decoded.entities[0].pos(), cgmath::point3(1.0, 2.0, 3.0));
+		}
+		let mut future_data = ENTITIES_SAVE_MAGIC.to_vec();
+		future_data.extend((ENTITIES_SAVE_FORMAT_VERSION + 1).to_le_bytes());
+		assert!(ChunkEntitiesSavable::decode(&future_data).is_err());
+	}
+
+	#[test]
+	fn hit_entities_die_when_out_of_health() {
+		let id_generator = IdGenerator::new();
+		let mut ball = Entity::new_test_ball(
+			&id_generator,
+			cgmath::point3(0.0, 0.0, 0.0),
+			cgmath::vec3(0.0, 0.0, 0.0),
+		);
+		assert!(ball.health_bar_text().is_none());
+		ball.hit(2, cgmath::vec3(0.1, 0.0, 0.0));
+		assert_eq!(ball.health_bar_text().as_deref(), Some("█__"));
+		assert!(!ball.is_dead());
+		ball.hit(2, cgmath::vec3(0.1, 0.0, 0.0));
+		assert_eq!(ball.health_bar_text().as_deref(), Some("___"));
+		assert!(ball.is_dead());
+
+		let mut block = Entity::new_block(
+			&id_generator,
+			Block { type_id: BlockTypeTable::AIR_ID, data: None },
+			cgmath::point3(0.0, 0.0, 0.0),
+			cgmath::vec3(0.0, 0.0, 0.0),
+		);
+		block.hit(100, cgmath::vec3(0.0, 0.0, 0.0));
+		assert!(!block.can_be_hit() && !block.is_dead());
+	}
+
+	/// A save in a temporary directory that is removed when dropped.
+	struct TemporarySave(Arc<Save>);
+	impl TemporarySave {
+		fn new(name: &str) -> TemporarySave {
+			let directory =
+				std::env::temp_dir().join(format!("qwy3_test_{name}_{}", std::process::id()));
+			std::fs::remove_dir_all(&directory).ok();
+			TemporarySave(Arc::new(Save::create_in_directory(
+				name.to_string(),
+				directory,
+			)))
+		}
+	}
+	impl Drop for TemporarySave {
+		fn drop(&mut self) {
+			std::fs::remove_dir_all(&self.0.main_directory).ok();
+		}
+	}
+
+	fn sorted_ids<'a>(entities: impl Iterator<Item = &'a Entity>) -> Vec<Id> {
+		let mut ids: Vec<_> = entities.map(|entity| entity.id()).collect();
+		ids.sort();
+		ids
+	}
+
+	/// Thousands of entities move around (crossing chunk borders a lot) while the chunks
+	/// they are in get unloaded (saved) and loaded again, none must be lost or duplicated.
+	#[test]
+	fn moving_entities_are_never_lost_nor_duplicated() {
+		use rand::SeedableRng;
+		let save = TemporarySave::new("moving_entities");
+		let save = &save.0;
+		let cd = ChunkDimensions::from(8);
+		let id_generator = IdGenerator::new();
+		let mut rng = rand::rngs::SmallRng::seed_from_u64(42);
+		let area_radius = 40.0;
+
+		let mut entities_map: FxHashMap<ChunkCoords, ChunkEntities> = FxHashMap::default();
+		let entities: Vec<_> = (0..4000)
+			.map(|_| {
+				let pos = cgmath::point3(
+					rng.gen_range(-area_radius..area_radius),
+					rng.gen_range(-area_radius..area_radius),
+					rng.gen_range(-area_radius..area_radius),
+				);
+				Entity::new_test_ball(&id_generator, pos, cgmath::vec3(0.0, 0.0, 0.0))
+			})
+			.collect();
+		let all_ids = sorted_ids(entities.iter());
+		ChunkEntities::hand_over_to_their_chunks(entities, cd, &mut entities_map);
+
+		for _step in 0..60 {
+			// Physics step, the entities move (some by more than a chunk), and are handed over
+			// to the chunks they end up in.
+			let mut next_entities_map = FxHashMap::default();
+			for chunk_entities in entities_map.into_values() {
+				let mut moved_entities = chunk_entities.savable.entities;
+				for entity in moved_entities.iter_mut() {
+					let motion = cgmath::vec3(
+						rng.gen_range(-10.0..10.0),
+						rng.gen_range(-10.0..10.0),
+						rng.gen_range(-10.0..10.0),
+					);
+					// They stay around so that they keep meeting the ones that were unloaded.
+					let pos = (entity.pos() + motion).map(|x| x.clamp(-area_radius, area_radius));
+					entity.impose_position(pos);
+				}
+				ChunkEntities::hand_over_to_their_chunks(moved_entities, cd, &mut next_entities_map);
+			}
+			entities_map = next_entities_map;
+			for (chunk_coords, chunk_entities) in entities_map.iter() {
+				assert!(chunk_entities
+					.iter_entities()
+					.all(|entity| entity.chunk_coords(cd) == *chunk_coords));
+			}
+
+			// Some chunks get unloaded (in the middle of the motion of their entities).
+			let chunks_to_unload: Vec<_> =
+				entities_map.keys().copied().filter(|_| rng.gen_bool(0.2)).collect();
+			for chunk_coords in chunks_to_unload {
+				entities_map.remove(&chunk_coords).unwrap().save(save);
+			}
+
+			// Some chunks get loaded, some of them were never unloaded or have entities in the map
+			// that moved in since they were unloaded.
+			for _ in 0..40 {
+				let chunk_coords = cgmath::point3(
+					rng.gen_range(-6..6),
+					rng.gen_range(-6..6),
+					rng.gen_range(-6..6),
+				);
+				let coords_span = ChunkCoordsSpan { cd, chunk_coords };
+				if let Some(loaded) =
+					ChunkEntities::load_from_save_while_removing_the_save(coords_span, save)
+				{
+					match entities_map.entry(chunk_coords) {
+						Entry::Occupied(mut occupied) => occupied.get_mut().merge_to(loaded),
+						Entry::Vacant(vacant) => {
+							vacant.insert(loaded);
+						},
+					}
+				}
+			}
+		}
+
+		// Everything gets unloaded and then loaded back.
+		for chunk_entities in entities_map.into_values() {
+			chunk_entities.save(save);
+		}
+		let mut loaded_entities = vec![];
+		for chunk_coords in save.chunks_with_a_file(WhichChunkFile::Entities) {
+			let coords_span = ChunkCoordsSpan { cd, chunk_coords };
+			let chunk_entities =
+				ChunkEntities::load_from_save_while_removing_the_save(coords_span, save).unwrap();
+			assert!(chunk_entities
+				.iter_entities()
+				.all(|entity| entity.chunk_coords(cd) == chunk_coords));
+			loaded_entities.extend(chunk_entities.savable.entities);
+		}
+		assert_eq!(sorted_ids(loaded_entities.iter()), all_ids);
+		assert!(save.chunks_with_a_file(WhichChunkFile::Entities).is_empty());
+	}
+
+	/// Many threads try to load the same saved entities at the same time (like a chunk loading task
+	/// and the entity physics could), only one must get them.
+	#[test]
+	fn saved_entities_are_loaded_only_once() {
+		let save = TemporarySave::new("loaded_only_once");
+		let save = &save.0;
+		let cd = ChunkDimensions::from(8);
+		let coords_span = ChunkCoordsSpan { cd, chunk_coords: cgmath::point3(0, 0, 0) };
+		let id_generator = IdGenerator::new();
+		for _attempt in 0..50 {
+			let mut chunk_entities = ChunkEntities::new_empty(coords_span);
+			for _ in 0..100 {
+				chunk_entities.add_entity(Entity::new_test_ball(
+					&id_generator,
+					cgmath::point3(1.0, 2.0, 3.0),
+					cgmath::vec3(0.0, 0.0, 0.0),
+				));
+			}
+			chunk_entities.save(save);
+			let loaded_counts: Vec<_> = std::thread::scope(|scope| {
+				let threads: Vec<_> = (0..8)
+					.map(|_| {
+						scope.spawn(|| {
+							ChunkEntities::load_from_save_while_removing_the_save(coords_span, save)
+								.map_or(0, |chunk_entities| chunk_entities.count_entities())
+						})
+					})
+					.collect();
+				threads.into_iter().map(|thread| thread.join().unwrap()).collect()
+			});
+			assert_eq!(loaded_counts.iter().sum::<usize>(), 100);
+			assert_eq!(loaded_counts.iter().filter(|&&count| count != 0).count(), 1);
+		}
+	}
+
+	/// The data of an entities file that cannot be decoded must survive the chunk being loaded
+	/// and then saved again (with other entities in it).
+	#[test]
+	fn corrupted_entities_files_are_not_lost() {
+		let save = TemporarySave::new("corrupted_entities");
+		let save = &save.0;
+		let cd = ChunkDimensions::from(8);
+		let coords_span = ChunkCoordsSpan { cd, chunk_coords: cgmath::point3(0, 0, 0) };
+		let chunk_file_path =
+			save.chunk_file_path(coords_span.chunk_coords, WhichChunkFile::Entities);
+		let corrupted_data = b"not entities at all".to_vec();
+		std::fs::write(&chunk_file_path, &corrupted_data).unwrap();
+
+		assert!(ChunkEntities::load_from_save_while_removing_the_save(coords_span, save).is_none());
+		let mut chunk_entities = ChunkEntities::new_empty(coords_span);
+		chunk_entities.add_entity(Entity::new_test_ball(
+			&IdGenerator::new(),
+			cgmath::point3(1.0, 2.0, 3.0),
+			cgmath::vec3(0.0, 0.0, 0.0),
+		));
+		chunk_entities.save(save);
+
+		let mut aside_path = chunk_file_path.clone().into_os_string();
+		aside_path.push(".corrupt");
+		assert_eq!(std::fs::read(aside_path).unwrap(), corrupted_data);
+		let loaded = ChunkEntities::load_from_save_while_removing_the_save(coords_span, save);
+		assert_eq!(loaded.unwrap().count_entities(), 1);
+
+		// Saving in a chunk whose file cannot be decoded (without loading it first) also keeps
+		// the file, next to the previous corrupted one.
+		std::fs::write(&chunk_file_path, &corrupted_data).unwrap();
+		chunk_entities.save(save);
+		let mut second_aside_path = chunk_file_path.clone().into_os_string();
+		second_aside_path.push(".corrupt_2");
+		assert_eq!(std::fs::read(second_aside_path).unwrap(), corrupted_data);
+		let loaded = ChunkEntities::load_from_save_while_removing_the_save(coords_span, save);
+		assert_eq!(loaded.unwrap().count_entities(), 1);
+	}
+}
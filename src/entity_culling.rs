@@ -0,0 +1,131 @@
+//! Culling and level of detail of the entity parts (see `entity_parts`).
+//!
+//! The parts of all the entities are drawn with one instanced draw call per part table, so the
+//! parts that should not be rendered are sent to the GPU as zeroed instances (like deleted parts,
+//! they are collapsed to a point and draw nothing). A part is not rendered if the chunk it is in
+//! is out of the view frustum, or if it is further than the entity render distance. Beyond the
+//! entity level of detail distance, entities lose their small parts (eyes and other details)
+//! that would only be a few pixels wide anyway.
+//!
+//! The frustum of the last rendered frame is used since the parts are sent to the GPU before the
+//! camera of the frame is known. The shadows and reflections of the entities that are culled
+//! are not rendered either.
+
+use std::cell::RefCell;
+
+use cgmath::{InnerSpace, MetricSpace};
+use fxhash::FxHashMap;
+
+use crate::coords::{ChunkCoords, ChunkCoordsSpan, ChunkDimensions};
+
+/// The parts that are smaller than that (in blocks, along their largest dimension) are left out
+/// beyond the level of detail distance.
+const LOD_MIN_PART_SIZE: f32 = 0.3;
+/// Parts can stick out of the chunk they are in by that much (in blocks), the chunk boxes are
+/// extended by that when tested against the frustum.
+const CHUNK_BOX_MARGIN: f32 = 2.0;
+
+pub(crate) struct EntityCulling {
+	cd: ChunkDimensions,
+	/// Entities further than that from the camera are not rendered, `None` means no limit.
+	pub(crate) render_distance: Option<f32>,
+	/// Entities further than that from the camera lose their small parts.
+	pub(crate) lod_distance: f32,
+	/// The view projection matrix and the position of the camera of the last rendered frame,
+	/// `None` before the first frame (then nothing is culled).
+	camera: Option<(cgmath::Matrix4<f32>, cgmath::Point3<f32>)>,
+	/// Is each chunk that was tested since the camera was set in the view frustum?
+	chunk_in_frustum_cache: RefCell<FxHashMap<ChunkCoords, bool>>,
+}
+
+impl EntityCulling {
+	pub(crate) fn new(
+		cd: ChunkDimensions,
+		render_distance: Option<f32>,
+		lod_distance: f32,
+	) -> EntityCulling {
+		EntityCulling {
+			cd,
+			render_distance,
+			lod_distance,
+			camera: None,
+			chunk_in_frustum_cache: RefCell::new(FxHashMap::default()),
+		}
+	}
+
+	/// Must be called every frame with the camera that the frame is rendered with.
+	pub(crate) fn set_camera(
+		&mut self,
+		view_projection_matrix: cgmath::Matrix4<f32>,
+		camera_position: cgmath::Point3<f32>,
+	) {
+		self.camera = Some((view_projection_matrix, camera_position));
+		self.chunk_in_frustum_cache.get_mut().clear();
+	}
+
+	/// Should the part instance with the given model matrix be rendered?
+	pub(crate) fn is_part_rendered(&self, model_matrix: &[[f32; 4]; 4]) -> bool {
+		let Some((_, camera_position)) = self.camera else {
+			return true;
+		};
+		let position = cgmath::point3(model_matrix[3][0], model_matrix[3][1], model_matrix[3][2]);
+		let distance = position.distance(camera_position);
+		if self.render_distance.is_some_and(|render_distance| distance > render_distance) {
+			return false;
+		}
+		if distance > self.lod_distance {
+			let size = (0..3)
+				.map(|i| {
+					cgmath::vec3(model_matrix[i][0], model_matrix[i][1], model_matrix[i][2]).magnitude()
+				})
+				.fold(0.0, f32::max);
+			if size < LOD_MIN_PART_SIZE {
+				return false;
+			}
+		}
+		let chunk_coords =
+			self.cd.world_coords_to_containing_chunk_coords(position.map(|x| x.round() as i32));
+		*self
+			.chunk_in_frustum_cache
+			.borrow_mut()
+			.entry(chunk_coords)
+			.or_insert_with(|| self.is_chunk_in_frustum(chunk_coords))
+	}
+
+	/// A chunk is in the frustum unless all the corners of its box (with the margin) are on
+	/// the outer side of one of the planes of the frustum.
+	fn is_chunk_in_frustum(&self, chunk_coords: ChunkCoords) -> bool {
+		let Some((view_projection_matrix, _)) = self.camera else {
+			return true;
+		};
+		let span = ChunkCoordsSpan { cd: self.cd, chunk_coords };
+		let inf = span.block_coords_inf().map(|x| x as f32 - 0.5 - CHUNK_BOX_MARGIN);
+		let sup = span.block_coords_sup_excluded().map(|x| x as f32 - 0.5 + CHUNK_BOX_MARGIN);
+		let corners = (0..8).map(|i| {
+			let corner = cgmath::vec4(
+				if i & 1 == 0 { inf.x } else { sup.x },
+				if i & 2 == 0 { inf.y } else { sup.y },
+				if i & 4 == 0 { inf.z } else { sup.z },
+				1.0,
+			);
+			view_projection_matrix * corner
+		});
+		// In clip space, the inside of the frustum is where -w <= x <= w, -w <= y <= w
+		// and 0 <= z <= w (the depth is reversed, see `CameraSettings::view_projection_matrix`).
+		let mut outside = [true; 6];
+		for clip in corners {
+			let distances_to_planes = [
+				clip.w + clip.x,
+				clip.w - clip.x,
+				clip.w + clip.y,
+				clip.w - clip.y,
+				clip.z,
+				clip.w - clip.z,
+			];
+			for (outside, distance) in outside.iter_mut().zip(distances_to_planes) {
+				*outside &= distance < 0.0;
+			}
+		}
+		!outside.into_iter().any(|outside| outside)
+	}
+}
@@ -23,6 +23,7 @@ use std::{
 	collections::HashMap,
 	marker::PhantomData,
 	sync::{Arc, Mutex, RwLock},
+	time::{Duration, Instant},
 };
 
 use bytemuck::Zeroable;
@@ -33,6 +34,7 @@ use wgpu::util::DeviceExt;
 use crate::{
 	block_types::{BlockType, BlockTypeId, BlockTypeTable},
 	coords::{AxisOrientation, NonOrientedAxis, OrientedAxis},
+	entity_culling::EntityCulling,
 	rendering_init::BindingThingy,
 	shaders::{part_colored::PartColoredInstancePod, part_textured::PartTexturedInstancePod},
 	table_allocator::{AllocationDecision, FreeingAdvice, TableAllocator},
@@ -123,8 +125,8 @@ pub(crate) struct PartTables {
 	pub(crate) textured_cubes: Mutex<PartTable<TexturedCubePartKind>>,
 	pub(crate) colored_cubes: Mutex<PartTable<ColoredCubePartKind>>,
 	pub(crate) colored_icosahedron: Mutex<PartTable<ColoredIcosahedronPartKind>>,
-	// NOTE: Added tables should also be handled in `PartTables::part_tables_for_rendering` and
-	// in `PartTables::cup_to_gpu_update_if_required`.
+	// NOTE: Added tables should also be handled in `PartTables::part_tables_for_rendering`,
+	// in `PartTables::cup_to_gpu_update_if_required` and in `PartTables::start_interpolation`.
 }
 
 /// Shared portion of the `PartTables` that is enough to render them.
@@ -148,10 +150,30 @@ impl PartTables {
 		&mut self,
 		device: &wgpu::Device,
 		queue: &wgpu::Queue,
+		entity_culling: &EntityCulling,
 	) {
-		self.textured_cubes.lock().unwrap().cup_to_gpu_update_if_required(device, queue);
-		self.colored_cubes.lock().unwrap().cup_to_gpu_update_if_required(device, queue);
-		self.colored_icosahedron.lock().unwrap().cup_to_gpu_update_if_required(device, queue);
+		self.textured_cubes.lock().unwrap().cup_to_gpu_update_if_required(
+			device,
+			queue,
+			entity_culling,
+		);
+		self.colored_cubes.lock().unwrap().cup_to_gpu_update_if_required(
+			device,
+			queue,
+			entity_culling,
+		);
+		self.colored_icosahedron.lock().unwrap().cup_to_gpu_update_if_required(
+			device,
+			queue,
+			entity_culling,
+		);
+	}
+
+	/// See `PartsInterpolation::apply_step_if_any`.
+	fn start_interpolation(&mut self, factor: f32) {
+		self.textured_cubes.get_mut().unwrap().start_interpolation(factor);
+		self.colored_cubes.get_mut().unwrap().start_interpolation(factor);
+		self.colored_icosahedron.get_mut().unwrap().start_interpolation(factor);
 	}
 
 	/// Returns what is needed to render the part tables, in a shared way.
@@ -166,11 +188,75 @@ impl PartTables {
 	}
 }
 
+/// Entity parts only move when the results of an entity physics step are applied, which does not
+/// happen every frame when the steps take longer than a frame (and the steps do not all simulate
+/// the same duration). To make the entities move smoothly at any framerate, each part instance
+/// also has a previous model matrix and is rendered somewhere between it and its model matrix
+/// (the shaders do that with the `factor`).
+///
+/// When the results of a step are applied, the previous model matrices become where the parts
+/// are rendered at that moment, and then the parts move towards where the step put them over
+/// the duration that the step simulated. If the next step is late, the parts keep going a bit
+/// further in the same direction (but not too far as they would end up in walls and stuff).
+pub(crate) struct PartsInterpolation {
+	/// When the results of the last entity physics step were applied.
+	last_step_applied: Option<Instant>,
+	/// The duration that the last applied step simulated.
+	last_step_dt: Duration,
+	/// The duration that the step that is running simulates, if a step is running.
+	running_step_dt: Option<Duration>,
+}
+
+/// How far the parts can go past where the last step put them (in durations of that step).
+const MAX_EXTRAPOLATION: f32 = 0.5;
+
+impl PartsInterpolation {
+	pub(crate) fn new() -> PartsInterpolation {
+		PartsInterpolation {
+			last_step_applied: None,
+			last_step_dt: Duration::ZERO,
+			running_step_dt: None,
+		}
+	}
+
+	/// An entity physics step that simulates the given duration just started.
+	pub(crate) fn step_started(&mut self, dt: Duration) {
+		self.running_step_dt = Some(dt);
+	}
+
+	/// Must be called when the part tables are exclusively owned (so that the step that was
+	/// running, if any, is done) and before they are sent to the GPU.
+	pub(crate) fn apply_step_if_any(&mut self, part_tables: &mut PartTables) {
+		if let Some(running_step_dt) = self.running_step_dt.take() {
+			part_tables.start_interpolation(self.factor());
+			self.last_step_applied = Some(Instant::now());
+			self.last_step_dt = running_step_dt;
+		}
+	}
+
+	/// Where the parts are rendered, 0 is at their previous model matrix and 1 is at their
+	/// model matrix (where the last step put them), above 1 they go further.
+	pub(crate) fn factor(&self) -> f32 {
+		match self.last_step_applied {
+			Some(last_step_applied) if !self.last_step_dt.is_zero() => {
+				let factor =
+					last_step_applied.elapsed().as_secs_f32() / self.last_step_dt.as_secs_f32();
+				factor.min(1.0 + MAX_EXTRAPOLATION)
+			},
+			_ => 1.0,
+		}
+	}
+}
+
 /// Trait that declares that a type can be the raw data of a part instance.
 /// There can be a `PartTable` of these.
 pub(crate) trait PartInstance: bytemuck::Pod + bytemuck::Zeroable + Clone {
 	/// Set the transform matrix of the instance.
 	fn set_model_matrix(&mut self, model_matrix: &cgmath::Matrix4<f32>);
+	fn model_matrix(&self) -> [[f32; 4]; 4];
+	/// The model matrix that the rendered model matrix comes from, see `PartsInterpolation`.
+	fn previous_model_matrix(&self) -> [[f32; 4]; 4];
+	fn set_previous_model_matrix(&mut self, previous_model_matrix: [[f32; 4]; 4]);
 }
 
 pub(crate) trait PartKind {
@@ -195,6 +281,12 @@ pub(crate) struct PartTable<T: PartKind> {
 	cpu_to_gpu_update_required_for_instances: bool,
 	/// If the size of the instance table was modified, the buffer must be recreated to fit.
 	cpu_to_gpu_update_required_for_buffer_length_change: bool,
+	/// The model matrices of the instances as they were last sent to the GPU (as the instances
+	/// can be modified on the CPU-side before being sent), see `PartTable::start_interpolation`.
+	uploaded_model_matrices: Vec<[[f32; 4]; 4]>,
+	/// Which instances were sent to the GPU as zeroed instances to not be rendered
+	/// (see `entity_culling`).
+	culled_instances: Vec<bool>,
 	name: &'static str,
 }
 
@@ -202,6 +294,8 @@ impl<T: PartKind> PartTable<T> {
 	pub(crate) fn allocate_instance(&mut self, instance: T::Instance) -> usize {
 		match self.instance_table_allocator.allocate_one() {
 			AllocationDecision::AllocateIndex(index) => {
+				// A new part is rendered where it is, it does not come from anywhere.
+				self.uploaded_model_matrices[index] = instance.model_matrix();
 				self.instance_table[index] = instance;
 				self.cpu_to_gpu_update_required_for_instances = true;
 				index
@@ -210,12 +304,14 @@ impl<T: PartKind> PartTable<T> {
 				let growing_factor = 1.25;
 				let new_length = (self.instance_table.len() as f32 * growing_factor) as usize + 4;
 				self.instance_table.resize(new_length, T::Instance::zeroed());
+				self.uploaded_model_matrices.resize(new_length, [[0.0; 4]; 4]);
 				self.instance_table_allocator.length_increased_to(new_length);
 				let AllocationDecision::AllocateIndex(index) =
 					self.instance_table_allocator.allocate_one()
 				else {
 					unreachable!("The length of the table increased by at least 4, there must be room");
 				};
+				self.uploaded_model_matrices[index] = instance.model_matrix();
 				self.instance_table[index] = instance;
 				self.cpu_to_gpu_update_required_for_instances = true;
 				self.cpu_to_gpu_update_required_for_buffer_length_change = true;
@@ -232,6 +328,7 @@ impl<T: PartKind> PartTable<T> {
 
 	pub(crate) fn delete_instance(&mut self, index: usize) {
 		self.instance_table[index] = T::Instance::zeroed();
+		self.uploaded_model_matrices[index] = [[0.0; 4]; 4];
 		self.cpu_to_gpu_update_required_for_instances = true;
 		match self.instance_table_allocator.free_one(index) {
 			FreeingAdvice::NothingToDo => {},
@@ -239,13 +336,53 @@ impl<T: PartKind> PartTable<T> {
 				self.instance_table.resize_with(advised_new_smaller_length, || {
 					panic!("There should be no element creation, we only shrink")
 				});
+				self.uploaded_model_matrices.truncate(advised_new_smaller_length);
 				self.cpu_to_gpu_update_required_for_buffer_length_change = true;
 				self.instance_table_allocator.length_shriked_to(advised_new_smaller_length);
 			},
 		}
 	}
 
-	fn cup_to_gpu_update_if_required(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+	/// The instances are about to be sent to the GPU, and they are currently rendered with the
+	/// given interpolation factor between their previous model matrix and their model matrix
+	/// on the GPU-side, this is where they will now start from (see `PartsInterpolation`).
+	fn start_interpolation(&mut self, factor: f32) {
+		for (instance, uploaded) in self.instance_table.iter_mut().zip(&self.uploaded_model_matrices)
+		{
+			let previous = instance.previous_model_matrix();
+			let rendered = std::array::from_fn(|i| {
+				std::array::from_fn(|j| previous[i][j] + (uploaded[i][j] - previous[i][j]) * factor)
+			});
+			instance.set_previous_model_matrix(rendered);
+		}
+		self.cpu_to_gpu_update_required_for_instances = true;
+	}
+
+	fn cup_to_gpu_update_if_required(
+		&mut self,
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		entity_culling: &EntityCulling,
+	) {
+		let culled_instances: Vec<bool> = self
+			.instance_table
+			.iter()
+			.map(|instance| !entity_culling.is_part_rendered(&instance.model_matrix()))
+			.collect();
+		if culled_instances != self.culled_instances {
+			self.culled_instances = culled_instances;
+			self.cpu_to_gpu_update_required_for_instances = true;
+		}
+
+		if self.cpu_to_gpu_update_required_for_instances
+			|| self.cpu_to_gpu_update_required_for_buffer_length_change
+		{
+			for (instance, uploaded) in
+				self.instance_table.iter().zip(self.uploaded_model_matrices.iter_mut())
+			{
+				*uploaded = instance.model_matrix();
+			}
+		}
 		if self.cpu_to_gpu_update_required_for_buffer_length_change {
 			// TODO: See the TODO at the end of `allocate_instance`.
 			self.cpu_to_gpu_update_required_for_buffer_length_change = false;
@@ -254,7 +391,7 @@ impl<T: PartKind> PartTable<T> {
 			self.instance_table_buffer = Arc::new(device.create_buffer_init(
 				&wgpu::util::BufferInitDescriptor {
 					label: Some(&format!("{name} Instance Buffer")),
-					contents: bytemuck::cast_slice(&self.instance_table),
+					contents: bytemuck::cast_slice(&self.rendered_instance_table()),
 					usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
 				},
 			));
@@ -263,11 +400,27 @@ impl<T: PartKind> PartTable<T> {
 			queue.write_buffer(
 				&self.instance_table_buffer,
 				0,
-				bytemuck::cast_slice(&self.instance_table),
+				bytemuck::cast_slice(&self.rendered_instance_table()),
 			);
 		}
 	}
 
+	/// The instance table as it is sent to the GPU, with the culled instances zeroed.
+	fn rendered_instance_table(&self) -> Vec<T::Instance> {
+		self
+			.instance_table
+			.iter()
+			.zip(self.culled_instances.iter())
+			.map(|(instance, &culled)| {
+				if culled {
+					T::Instance::zeroed()
+				} else {
+					*instance
+				}
+			})
+			.collect()
+	}
+
 	fn get_data_for_rendering(&self) -> DataForPartTableRendering {
 		DataForPartTableRendering {
 			mesh_vertices_count: self.mesh.vertex_count,
@@ -365,7 +518,11 @@ impl TextureMappingAndColoringTableRwLock {
 		}
 		// Not found, we have to write it in.
 		let texture_coords_on_atlas = match block_type_table.get(block_type_id)? {
-			BlockType::Solid { texture_coords_on_atlas } => *texture_coords_on_atlas,
+			BlockType::Solid { texture_coords_on_atlas }
+			| BlockType::Liquid { texture_coords_on_atlas }
+			| BlockType::Layer { texture_coords_on_atlas }
+			| BlockType::Door { texture_coords_on_atlas }
+			| BlockType::Trapdoor { texture_coords_on_atlas } => *texture_coords_on_atlas,
 			_ => return None,
 		};
 		let mappings = textured_cube::texture_mappings_for_cube(texture_coords_on_atlas);
@@ -470,6 +627,31 @@ impl TextureMappingAndColoringTableRwLock {
 }
 
 impl PartInstance for PartTexturedInstancePod {
+	fn model_matrix(&self) -> [[f32; 4]; 4] {
+		[
+			self.model_matrix_1_of_4,
+			self.model_matrix_2_of_4,
+			self.model_matrix_3_of_4,
+			self.model_matrix_4_of_4,
+		]
+	}
+
+	fn previous_model_matrix(&self) -> [[f32; 4]; 4] {
+		[
+			self.previous_model_matrix_1_of_4,
+			self.previous_model_matrix_2_of_4,
+			self.previous_model_matrix_3_of_4,
+			self.previous_model_matrix_4_of_4,
+		]
+	}
+
+	fn set_previous_model_matrix(&mut self, previous_model_matrix: [[f32; 4]; 4]) {
+		self.previous_model_matrix_1_of_4 = previous_model_matrix[0];
+		self.previous_model_matrix_2_of_4 = previous_model_matrix[1];
+		self.previous_model_matrix_3_of_4 = previous_model_matrix[2];
+		self.previous_model_matrix_4_of_4 = previous_model_matrix[3];
+	}
+
 	fn set_model_matrix(&mut self, model_matrix: &cgmath::Matrix4<f32>) {
 		let inv_trans_model_matrix =
 			cgmath::conv::array4x4(model_matrix.transpose().invert().unwrap());
@@ -486,6 +668,31 @@ impl PartInstance for PartTexturedInstancePod {
 }
 
 impl PartInstance for PartColoredInstancePod {
+	fn model_matrix(&self) -> [[f32; 4]; 4] {
+		[
+			self.model_matrix_1_of_4,
+			self.model_matrix_2_of_4,
+			self.model_matrix_3_of_4,
+			self.model_matrix_4_of_4,
+		]
+	}
+
+	fn previous_model_matrix(&self) -> [[f32; 4]; 4] {
+		[
+			self.previous_model_matrix_1_of_4,
+			self.previous_model_matrix_2_of_4,
+			self.previous_model_matrix_3_of_4,
+			self.previous_model_matrix_4_of_4,
+		]
+	}
+
+	fn set_previous_model_matrix(&mut self, previous_model_matrix: [[f32; 4]; 4]) {
+		self.previous_model_matrix_1_of_4 = previous_model_matrix[0];
+		self.previous_model_matrix_2_of_4 = previous_model_matrix[1];
+		self.previous_model_matrix_3_of_4 = previous_model_matrix[2];
+		self.previous_model_matrix_4_of_4 = previous_model_matrix[3];
+	}
+
 	fn set_model_matrix(&mut self, model_matrix: &cgmath::Matrix4<f32>) {
 		let inv_trans_model_matrix =
 			cgmath::conv::array4x4(model_matrix.transpose().invert().unwrap());
@@ -534,6 +741,8 @@ pub(crate) mod textured_cube {
 			instance_table_allocator: TableAllocator::new(0, 200),
 			cpu_to_gpu_update_required_for_instances: false,
 			cpu_to_gpu_update_required_for_buffer_length_change: false,
+			uploaded_model_matrices: vec![],
+			culled_instances: vec![],
 			name,
 		}
 	}
@@ -572,6 +781,10 @@ pub(crate) mod textured_cube {
 				inv_trans_model_matrix_3_of_4: inv_trans_model_matrix[2],
 				inv_trans_model_matrix_4_of_4: inv_trans_model_matrix[3],
 				texture_mapping_offset: self.texture_mapping_offset,
+				previous_model_matrix_1_of_4: model_matrix[0],
+				previous_model_matrix_2_of_4: model_matrix[1],
+				previous_model_matrix_3_of_4: model_matrix[2],
+				previous_model_matrix_4_of_4: model_matrix[3],
 			}
 		}
 	}
@@ -741,6 +954,8 @@ pub(crate) mod colored_cube {
 			instance_table_allocator: TableAllocator::new(0, 200),
 			cpu_to_gpu_update_required_for_instances: false,
 			cpu_to_gpu_update_required_for_buffer_length_change: false,
+			uploaded_model_matrices: vec![],
+			culled_instances: vec![],
 			name,
 		}
 	}
@@ -776,6 +991,11 @@ pub(crate) mod colored_cube {
 				inv_trans_model_matrix_3_of_4: inv_trans_model_matrix[2],
 				inv_trans_model_matrix_4_of_4: inv_trans_model_matrix[3],
 				coloring_offset: self.coloring_offset,
+				previous_model_matrix_1_of_4: model_matrix[0],
+				previous_model_matrix_2_of_4: model_matrix[1],
+				previous_model_matrix_3_of_4: model_matrix[2],
+				previous_model_matrix_4_of_4: model_matrix[3],
+				hit_tint: 0.0,
 			}
 		}
 	}
@@ -895,6 +1115,8 @@ pub(crate) mod colored_icosahedron {
 			instance_table_allocator: TableAllocator::new(0, 200),
 			cpu_to_gpu_update_required_for_instances: false,
 			cpu_to_gpu_update_required_for_buffer_length_change: false,
+			uploaded_model_matrices: vec![],
+			culled_instances: vec![],
 			name,
 		}
 	}
@@ -933,6 +1155,11 @@ pub(crate) mod colored_icosahedron {
 				inv_trans_model_matrix_3_of_4: inv_trans_model_matrix[2],
 				inv_trans_model_matrix_4_of_4: inv_trans_model_matrix[3],
 				coloring_offset: self.coloring_offset,
+				previous_model_matrix_1_of_4: model_matrix[0],
+				previous_model_matrix_2_of_4: model_matrix[1],
+				previous_model_matrix_3_of_4: model_matrix[2],
+				previous_model_matrix_4_of_4: model_matrix[3],
+				hit_tint: 0.0,
 			}
 		}
 	}
@@ -0,0 +1,98 @@
+//! Events are things that happen in the game that game logic or Qwy Script code may react to.
+//! The Qwy Script code that reacts to events is given by event hooks, that are read from
+//! the `event_hooks.qwy3_hooks` file (if there is one) that has lines like
+//! `on trigger_entered pressure_plate set_snowfall(on)`, meaning "when the event
+//! `trigger_entered` happens to a trigger named `pressure_plate`, run `set_snowfall(on)`"
+//! (the name `*` matches any trigger).
+
+use crate::triggers::{Occupant, Trigger};
+
+pub(crate) enum GameEvent {
+	/// The occupant started to stand in or on the trigger.
+	TriggerEntered { trigger: Trigger, occupant: Occupant },
+	/// The occupant stopped standing in or on the trigger.
+	TriggerLeft { trigger: Trigger, occupant: Occupant },
+}
+
+impl GameEvent {
+	/// The name that event hooks use to refer to this kind of events.
+	fn hook_name(&self) -> &'static str {
+		match self {
+			GameEvent::TriggerEntered { .. } => "trigger_entered",
+			GameEvent::TriggerLeft { .. } => "trigger_left",
+		}
+	}
+
+	fn trigger(&self) -> &Trigger {
+		match self {
+			GameEvent::TriggerEntered { trigger, .. } | GameEvent::TriggerLeft { trigger, .. } => {
+				trigger
+			},
+		}
+	}
+}
+
+struct EventHook {
+	event_name: String,
+	/// The name of the trigger, or `*` for any trigger.
+	trigger_name: String,
+	/// Qwy Script code to run when the event happens.
+	code: String,
+}
+
+pub(crate) struct EventHooks {
+	hooks: Vec<EventHook>,
+}
+
+impl EventHooks {
+	pub(crate) fn parse_event_hooks_file() -> EventHooks {
+		let mut hooks = vec![];
+		let hooks_file_path = "event_hooks.qwy3_hooks";
+		let Ok(hooks_string) = std::fs::read_to_string(hooks_file_path) else {
+			// No hooks file is fine, there are just no hooks.
+			return EventHooks { hooks };
+		};
+		for (line_index, line) in hooks_string.lines().enumerate() {
+			let line_number = line_index + 1;
+			let mut words = line.trim().splitn(4, char::is_whitespace);
+			match words.next() {
+				Some("on") => {
+					let (Some(event_name), Some(trigger_name), Some(code)) =
+						(words.next(), words.next(), words.next())
+					else {
+						log::error!(
+							"In file \"{hooks_file_path}\" at line {line_number}: \
+							Expected an event name, a trigger name and some code after \"on\""
+						);
+						continue;
+					};
+					hooks.push(EventHook {
+						event_name: event_name.to_string(),
+						trigger_name: trigger_name.to_string(),
+						code: code.to_string(),
+					});
+				},
+				Some("") | None => {},
+				Some(unknown_command_name) => {
+					log::error!(
+						"In file \"{hooks_file_path}\" at line {line_number}: \
+						Command name \"{unknown_command_name}\" is unknown"
+					);
+				},
+			}
+		}
+		EventHooks { hooks }
+	}
+
+	/// The Qwy Script code of the hooks to run for the given event.
+	pub(crate) fn codes_to_run<'a>(
+		&'a self,
+		event: &'a GameEvent,
+	) -> impl Iterator<Item = &'a str> + 'a {
+		self.hooks.iter().filter_map(move |hook| {
+			let is_hooked = hook.event_name == event.hook_name()
+				&& (hook.trigger_name == "*" || hook.trigger_name == event.trigger().name());
+			is_hooked.then_some(hook.code.as_str())
+		})
+	}
+}
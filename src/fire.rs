@@ -0,0 +1,155 @@
+//! Fire, that spreads to the flammable blocks (see `BlockTypeTable::is_flammable`) around it
+//! and burns them away.
+//!
+//! A few times per second, each fire block around the player gets a random tick in which it may
+//! spread to the air next to flammable blocks nearby, set the flammable blocks it touches on fire
+//! (so they burn away when that fire dies out), and die out if there is nothing left to burn.
+//! Water next to a fire puts it out, and so does snowfall (there is no rain) if the fire is under
+//! the open sky.
+
+use std::time::Duration;
+
+use rand::{thread_rng, Rng};
+
+use crate::{
+	block_types::{BlockTypeId, BlockTypeTable},
+	chunk_blocks::Block,
+	chunks::{ChunkGrid, EditBatch},
+	coords::{iter_3d_cube_center_radius, BlockCoords, OrientedAxis},
+};
+
+/// Fire only burns at most that far from the player (on each axis, in blocks).
+const FIRE_RADIUS: i32 = 48;
+/// How many random ticks each fire block gets per second.
+const FIRE_TICKS_PER_SECOND: f32 = 4.0;
+/// Chance for a fire to spread to each air block around it that touches a flammable block,
+/// on each tick.
+const SPREAD_PROBABILITY: f64 = 0.03;
+/// Chance for each flammable block touched by a fire to catch fire, on each tick.
+const BURN_PROBABILITY: f64 = 0.02;
+/// Chance for a fire that has nothing to burn to die out, on each tick.
+const DIE_OUT_PROBABILITY: f64 = 0.15;
+
+pub(crate) struct Fire {
+	/// The fraction of a tick that was not done yet, it adds up over the frames.
+	pending_tick: f32,
+}
+
+impl Fire {
+	pub(crate) fn new() -> Fire {
+		Fire { pending_tick: 0.0 }
+	}
+
+	/// Must be called every frame (with an up-to-date heightmap, see `ChunkGrid::update_heightmap`),
+	/// returns the blocks that burned and the fires that spread or died out (if any).
+	pub(crate) fn update(
+		&mut self,
+		chunk_grid: &ChunkGrid,
+		player_pos: cgmath::Point3<f32>,
+		block_type_table: &BlockTypeTable,
+		dt: Duration,
+		simulation_distance: Option<f32>,
+		snowing: bool,
+	) -> Option<EditBatch> {
+		self.pending_tick += FIRE_TICKS_PER_SECOND * dt.as_secs_f32();
+		if self.pending_tick < 1.0 {
+			return None;
+		}
+		// If the frames are too slow for all the ticks then some ticks are skipped.
+		self.pending_tick = self.pending_tick.fract();
+
+		let fire_id = block_type_table.kinda_fire_id();
+		let radius = simulation_distance.map_or(FIRE_RADIUS, |distance| {
+			FIRE_RADIUS.min(distance as i32).max(0)
+		});
+		let fires = fires_around(
+			chunk_grid,
+			player_pos.map(|x| x.round() as i32),
+			radius,
+			fire_id,
+		);
+		if fires.is_empty() {
+			return None;
+		}
+
+		let mut rng = thread_rng();
+		let mut edit_batch = chunk_grid.edit_batch();
+		let is_flammable = |coords: BlockCoords| {
+			chunk_grid
+				.get_block(coords)
+				.is_some_and(|block| block_type_table.is_flammable(block.type_id))
+		};
+		for coords in fires {
+			let is_put_out = face_neighbors(coords).any(|neighbor_coords| {
+				chunk_grid
+					.get_block(neighbor_coords)
+					.is_some_and(|block| block.type_id == block_type_table.water_id())
+			}) || (snowing
+				&& chunk_grid.top_solid_z(coords.x, coords.y).is_none_or(|top_z| top_z < coords.z));
+			if is_put_out {
+				edit_batch.set(coords, Block::from(block_type_table.air_id()));
+				continue;
+			}
+
+			let flammable_neighbors: Vec<BlockCoords> = face_neighbors(coords)
+				.filter(|&neighbor_coords| is_flammable(neighbor_coords))
+				.collect();
+			if flammable_neighbors.is_empty() {
+				if rng.gen_bool(DIE_OUT_PROBABILITY) {
+					edit_batch.set(coords, Block::from(block_type_table.air_id()));
+				}
+				continue;
+			}
+			for neighbor_coords in flammable_neighbors {
+				if rng.gen_bool(BURN_PROBABILITY) {
+					edit_batch.set(neighbor_coords, Block::from(fire_id));
+				}
+			}
+			for target_coords in iter_3d_cube_center_radius(coords, 2) {
+				let is_air = chunk_grid
+					.get_block(target_coords)
+					.is_some_and(|block| block.type_id == block_type_table.air_id());
+				if is_air
+					&& rng.gen_bool(SPREAD_PROBABILITY)
+					&& face_neighbors(target_coords).any(is_flammable)
+				{
+					edit_batch.set(target_coords, Block::from(fire_id));
+				}
+			}
+		}
+
+		(edit_batch.block_count() > 0).then_some(edit_batch)
+	}
+}
+
+fn face_neighbors(coords: BlockCoords) -> impl Iterator<Item = BlockCoords> {
+	OrientedAxis::all_the_six_possible_directions().map(move |direction| coords + direction.delta())
+}
+
+/// The fire blocks in the loaded chunks that are at most `radius` blocks from `center`
+/// (on each axis). Only the chunks that have fire in their palette are looked into.
+fn fires_around(
+	chunk_grid: &ChunkGrid,
+	center: BlockCoords,
+	radius: i32,
+	fire_id: BlockTypeId,
+) -> Vec<BlockCoords> {
+	let is_close = |coords: BlockCoords| {
+		(coords.x - center.x).abs() <= radius
+			&& (coords.y - center.y).abs() <= radius
+			&& (coords.z - center.z).abs() <= radius
+	};
+	let mut fires = vec![];
+	for chunk_coords in chunk_grid.iter_loaded_chunk_coords() {
+		let chunk_blocks = chunk_grid.get_chunk_blocks(chunk_coords).unwrap();
+		if !chunk_blocks.block_type_ids_in_palette().any(|type_id| type_id == fire_id) {
+			continue;
+		}
+		for coords in chunk_blocks.coords_span.iter_coords() {
+			if is_close(coords) && chunk_blocks.get(coords).unwrap().type_id == fire_id {
+				fires.push(coords);
+			}
+		}
+	}
+	fires
+}
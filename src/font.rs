@@ -213,7 +213,26 @@ impl Font {
 	}
 
 	fn character_details(&self, character: char) -> Option<CharacterDetails> {
-		self.character_details_map.get(&character).cloned()
+		self.character_details_map.get(&character).cloned().or_else(|| {
+			// Localized text can have accented letters that are not in the font,
+			// it is still readable without the accents.
+			let character_without_diacritic = match character {
+				'à' | 'â' | 'ä' => 'a',
+				'ç' => 'c',
+				'é' | 'è' | 'ê' | 'ë' => 'e',
+				'î' | 'ï' => 'i',
+				'ô' | 'ö' => 'o',
+				'ù' | 'û' | 'ü' => 'u',
+				'À' | 'Â' | 'Ä' => 'A',
+				'Ç' => 'C',
+				'É' | 'È' | 'Ê' | 'Ë' => 'E',
+				'Î' | 'Ï' => 'I',
+				'Ô' | 'Ö' => 'O',
+				'Ù' | 'Û' | 'Ü' => 'U',
+				_ => return None,
+			};
+			self.character_details_map.get(&character_without_diacritic).cloned()
+		})
 	}
 
 	/// Returns (width, height) in screen pixels of the smallest rect
@@ -303,6 +322,33 @@ impl Font {
 		vertices
 	}
 
+	/// Same as `simple_texture_vertices_from_text` but in world space, for text that floats
+	/// in the world facing the camera (the `right` and `up` vectors are the ones of the camera).
+	/// The text is centered horizontally on `bottom_center`, and `pixel_size` is the size
+	/// (in blocks) of a pixel of the font.
+	pub(crate) fn billboard_vertices_from_text(
+		&self,
+		bottom_center: cgmath::Point3<f32>,
+		right: cgmath::Vector3<f32>,
+		up: cgmath::Vector3<f32>,
+		pixel_size: f32,
+		settings: TextRenderingSettings,
+		text: &str,
+	) -> Vec<SimpleTextureVertexPod> {
+		// The text is layed out in a plane in which screen pixels have the size of font pixels,
+		// then this plane is placed in the world.
+		let plane_width = 2.0 / pixel_size;
+		let dimensions = self.dimensions_of_text(plane_width, settings.clone(), text);
+		let top_left = cgmath::point3(-dimensions.x / 2.0, dimensions.y, 0.0);
+		let mut vertices =
+			self.simple_texture_vertices_from_text(plane_width, top_left, settings, text);
+		for vertex in vertices.iter_mut() {
+			let [x, y, _z] = vertex.position;
+			vertex.position = (bottom_center + right * x + up * y).into();
+		}
+		vertices
+	}
+
 	pub(crate) fn _simple_texture_mesh_from_text(
 		&self,
 		device: &wgpu::Device,
@@ -0,0 +1,848 @@
+//! Here are performed the commands that Qwy Script programs ask the game to perform.
+
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+	atlas::TEXTURE_PREVIEW_COORDS_ON_ATLAS,
+	backups,
+	chunk_blocks::Block,
+	chunks::ActionOnWorld,
+	color_grading::{GradingSettings, Tonemapper},
+	config::Config,
+	coords::{AlignedBox, BlockCoords, CubicCoordsSpan},
+	debug_annotations,
+	display::{FullscreenMode, PresentModeChoice},
+	entities::{Entity, PLATFORM_DIMS},
+	game_init::Game,
+	lang::{self, GameCommand, LogItem},
+	localization::Localization,
+	logging,
+	map_export::{DataForMapExport, MapProjection},
+	models::Model,
+	photo_mode::screenshots_are_supported,
+	physics::UnstuckOutcome,
+	player_model::{Emote, PlayerAppearance},
+	pregen::Pregen,
+	render_debug::RenderDebugMode,
+	render_stats::format_bytes,
+	rendering_init::update_atlas_texture_rect,
+	selection::{hollow_interior, BlockClipboard},
+	shaders::simple_line::SimpleLineVertexPod,
+	simple_meshes::SimpleLineMesh,
+	texture_gen::{palette_from_name, RecipeParams, TextureRecipe},
+	triggers::TriggerVolume,
+	vox_export::export_vox,
+	world_gen::StructureOriginsDebugView,
+	world_stats::DataForWorldStats,
+};
+
+/// The structure cells can be small, displaying them much further would make a lot of lines.
+const MAX_DEBUG_STRUCTURES_RADIUS: i32 = 256;
+
+/// Runs the given Qwy Script code and performs the commands it asks for, returns the lines of text
+/// to log as feedback (can be empty).
+pub(crate) fn run_qwy_script(game: &mut Game, code: &str) -> String {
+	let mut log = lang::Log::new();
+	let res = lang::run(code, &mut lang::Context::with_builtins(), &mut log);
+	if let Err(error) = res {
+		format!("{error:?}")
+	} else {
+		let lines: Vec<_> = log
+			.log_items
+			.into_iter()
+			.map(|item| match item {
+				LogItem::Text(text) => text,
+				LogItem::Command(command) => perform_game_command(game, command),
+			})
+			.collect();
+		lines.join("\n")
+	}
+}
+
+/// Performs the given command, returns a line of text to log as feedback (can be empty).
+pub(crate) fn perform_game_command(game: &mut Game, command: GameCommand) -> String {
+	match command {
+		GameCommand::SetLanguage(language) => {
+			let (localization, language_was_found) = Localization::load(&language);
+			if language_was_found {
+				game.localization = localization;
+				game.config.language = Some(language.clone());
+				game.localization.get_with_args("log.language_set", &[("language", &language)])
+			} else {
+				game.localization.get_with_args("log.language_unknown", &[("language", &language)])
+			}
+		},
+		GameCommand::SetVolume { channel, percent } => {
+			let settings = &mut game.audio.settings;
+			let volume = match channel.as_str() {
+				"master" => &mut settings.master_volume,
+				"sfx" => &mut settings.sfx_volume,
+				"music" => &mut settings.music_volume,
+				_ => {
+					return game
+						.localization
+						.get_with_args("log.volume_channel_unknown", &[("channel", &channel)]);
+				},
+			};
+			let percent = percent.clamp(0, 100);
+			*volume = percent as f32 / 100.0;
+			game.localization.get_with_args(
+				"log.volume_set",
+				&[("channel", &channel), ("percent", &percent)],
+			)
+		},
+		GameCommand::NameTargetedEntity(name) => {
+			let Some((entity_id, chunk_coords)) = game.targeted_entity else {
+				return game.localization.get("log.no_targeted_entity").to_string();
+			};
+			game.chunk_grid_shareable.perform_now_or_later(
+				ActionOnWorld::NameEntity { entity_id, chunk_coords, name: name.clone() },
+				game.save.as_ref(),
+				&game.id_generator,
+			);
+			game.localization.get_with_args("log.entity_named", &[("name", &name)])
+		},
+		GameCommand::SpawnPlatform { path_length } => {
+			let player_box = game.player_phys.aligned_box();
+			let path_start = player_box.pos
+				- cgmath::vec3(
+					0.0,
+					0.0,
+					player_box.dims.z / 2.0 + PLATFORM_DIMS.z / 2.0 + 0.01,
+				);
+			let direction = game.camera_direction.to_horizontal().to_vec3();
+			let path_end = path_start + direction * path_length.max(1) as f32;
+			game.chunk_grid_shareable.perform_now_or_later(
+				ActionOnWorld::AddEntity(Entity::new_platform(
+					&game.id_generator,
+					path_start,
+					path_end,
+				)),
+				game.save.as_ref(),
+				&game.id_generator,
+			);
+			game.localization.get("log.platform_spawned").to_string()
+		},
+		GameCommand::SetRenderScale { percent } => {
+			game.render_scaling.set_scale(percent as f32 / 100.0);
+			game.recreate_screen_textures();
+			let percent = (game.render_scaling.scale() * 100.0).round() as i32;
+			game.localization.get_with_args("log.render_scale_set", &[("percent", &percent)])
+		},
+		GameCommand::SetLensEffects { depth_of_field_percent, motion_blur_percent } => {
+			game.lens_effects.set_settings(
+				depth_of_field_percent as f32 / 100.0,
+				motion_blur_percent as f32 / 100.0,
+			);
+			game.recreate_screen_textures();
+			let depth_of_field = (game.lens_effects.depth_of_field() * 100.0).round() as i32;
+			let motion_blur = (game.lens_effects.motion_blur() * 100.0).round() as i32;
+			game.localization.get_with_args(
+				"log.lens_effects_set",
+				&[
+					("depth_of_field", &depth_of_field),
+					("motion_blur", &motion_blur),
+				],
+			)
+		},
+		GameCommand::SetBloom { threshold_percent, intensity_percent } => {
+			game.bloom.set_settings(
+				threshold_percent as f32 / 100.0,
+				intensity_percent as f32 / 100.0,
+			);
+			game.recreate_screen_textures();
+			if game.bloom.is_enabled() {
+				let threshold = (game.bloom.threshold() * 100.0).round() as i32;
+				let intensity = (game.bloom.intensity() * 100.0).round() as i32;
+				game.localization.get_with_args(
+					"log.bloom_set",
+					&[("threshold", &threshold), ("intensity", &intensity)],
+				)
+			} else {
+				game.localization.get("log.bloom_disabled").to_string()
+			}
+		},
+		GameCommand::SetColorGrading {
+			tonemapper_name,
+			exposure_percent,
+			contrast_percent,
+			saturation_percent,
+		} => {
+			let Some(tonemapper) = Tonemapper::from_name(&tonemapper_name) else {
+				return game.localization.get_with_args(
+					"log.tonemapper_unknown",
+					&[("tonemapper", &tonemapper_name)],
+				);
+			};
+			game.color_grading.set_settings(
+				&game.queue,
+				GradingSettings {
+					tonemapper,
+					exposure: exposure_percent as f32 / 100.0,
+					contrast: contrast_percent as f32 / 100.0,
+					saturation: saturation_percent as f32 / 100.0,
+				},
+			);
+			game.recreate_screen_textures();
+			let settings = game.color_grading.settings();
+			let percent = |value: f32| (value * 100.0).round() as i32;
+			game.localization.get_with_args(
+				"log.grading_set",
+				&[
+					("tonemapper", &settings.tonemapper.name()),
+					("exposure", &percent(settings.exposure)),
+					("contrast", &percent(settings.contrast)),
+					("saturation", &percent(settings.saturation)),
+				],
+			)
+		},
+		GameCommand::SetColorLut { name } => {
+			let name = (name != "none").then_some(name);
+			let result = game.color_grading.set_lut(&game.device, &game.queue, name.as_deref());
+			game.recreate_screen_textures();
+			match (result, name) {
+				(Err(error), _) => {
+					game.localization.get_with_args("log.grading_lut_error", &[("error", &error)])
+				},
+				(Ok(()), Some(name)) => {
+					game.localization.get_with_args("log.grading_lut_set", &[("name", &name)])
+				},
+				(Ok(()), None) => game.localization.get("log.grading_lut_removed").to_string(),
+			}
+		},
+		GameCommand::ExportMap { projection_name, pixels_per_block } => {
+			let Some(projection) = MapProjection::from_name(&projection_name) else {
+				return game
+					.localization
+					.get_with_args("log.map_projection_unknown", &[("name", &projection_name)]);
+			};
+			// Chunk meshes may have been added or removed since the last frame.
+			game.chunk_instances.update_chunk_meshes(
+				&game.device,
+				&game.queue,
+				game.chunk_grid_shareable.get().iter_chunk_meshes(),
+				None,
+			);
+			let data_for_map_export = DataForMapExport {
+				device: &game.device,
+				queue: &game.queue,
+				rendering: &game.rendering,
+				chunk_grid: game.chunk_grid_shareable.get(),
+				chunk_instances: &game.chunk_instances,
+				map_export_camera_matrix_thingy: &game.map_export_camera_matrix_thingy,
+				water_reflection_settings_thingy: &game.water_reflection_stuff.settings_thingy,
+				z_buffer_format: game.z_buffer_format,
+				center: game.player_phys.aligned_box().pos,
+				radius: game.loading_manager.loading_distance,
+			};
+			match data_for_map_export.export(projection, pixels_per_block.max(1) as u32) {
+				Ok((path, side)) => game.localization.get_with_args(
+					"log.map_exported",
+					&[("path", &path.display()), ("side", &side)],
+				),
+				Err(error) => {
+					game.localization.get_with_args("log.map_export_error", &[("error", &error)])
+				},
+			}
+		},
+		GameCommand::ExportVox => {
+			match export_vox(
+				game.chunk_grid_shareable.get(),
+				game.selection.span(),
+				&game.block_type_table,
+				&game.block_type_colors,
+			) {
+				Ok(Some(summary)) => game.localization.get_with_args(
+					"log.vox_exported",
+					&[
+						("path", &summary.path.display()),
+						("count", &summary.voxel_count),
+						("models", &summary.model_count),
+					],
+				),
+				Ok(None) => game.localization.get("log.vox_export_empty").to_string(),
+				Err(error) => {
+					game.localization.get_with_args("log.vox_export_error", &[("error", &error)])
+				},
+			}
+		},
+		GameCommand::PrintRenderStats => {
+			let stats = game.render_stats;
+			game.localization.get_with_args(
+				"log.render_stats",
+				&[
+					("draw_calls", &stats.draw_calls),
+					("triangles", &stats.triangles),
+					("chunk_meshes", &format_bytes(stats.memory.chunk_meshes)),
+					("entities", &format_bytes(stats.memory.entities)),
+					("interface", &format_bytes(stats.memory.interface)),
+					("total", &format_bytes(stats.memory.total())),
+				],
+			)
+		},
+		GameCommand::WorldStats { export } => {
+			let player_block_coords = game.player_phys.aligned_box().pos.map(|x| x.round() as i32);
+			let data_for_world_stats = DataForWorldStats::new(
+				game.chunk_grid_shareable.get(),
+				Arc::clone(&game.world_generator),
+				player_block_coords,
+				game.loading_manager.loading_distance as i32,
+				Arc::clone(&game.block_type_table),
+				export,
+			);
+			game.worker_tasks.run_world_stats_task(&mut game.pool, data_for_world_stats);
+			game.localization.get("log.stats_started").to_string()
+		},
+		GameCommand::Pregen { radius } => {
+			let possible = Pregen::check_possible(
+				game.save.as_ref(),
+				game.only_save_modified_chunks,
+				game.chunk_cache.as_ref(),
+			);
+			if let Err(error_key) = possible {
+				return game.localization.get(error_key).to_string();
+			}
+			let pregen = Pregen::new(game.player_chunk(), radius, game.cd);
+			let chunk_count = pregen.chunk_count();
+			game.pregen = Some(pregen);
+			game.localization.get_with_args(
+				"log.pregen_started",
+				&[("radius", &radius), ("chunks", &chunk_count)],
+			)
+		},
+		GameCommand::TogglePhotoMode => {
+			if game.toggle_photo_mode() {
+				game.localization.get("log.photo_mode_entered").to_string()
+			} else {
+				game.localization.get("log.photo_mode_left").to_string()
+			}
+		},
+		GameCommand::SetPhotoRoll { degrees } => {
+			let Some(photo_mode) = game.photo_mode.as_mut() else {
+				return game.localization.get("log.photo_mode_required").to_string();
+			};
+			photo_mode.set_roll((degrees as f32).to_radians());
+			game.localization.get_with_args("log.photo_roll_set", &[("degrees", &degrees)])
+		},
+		GameCommand::SetPhotoFieldOfView { degrees } => {
+			let Some(photo_mode) = game.photo_mode.as_mut() else {
+				return game.localization.get("log.photo_mode_required").to_string();
+			};
+			photo_mode.set_field_of_view((degrees as f32).to_radians());
+			game.localization.get_with_args("log.photo_fov_set", &[("degrees", &degrees)])
+		},
+		GameCommand::CameraPathAddKeyframe => {
+			let Some(photo_mode) = game.photo_mode.as_mut() else {
+				return game.localization.get("log.photo_mode_required").to_string();
+			};
+			let count = photo_mode.add_keyframe();
+			game.localization.get_with_args("log.camera_path_keyframe_added", &[("count", &count)])
+		},
+		GameCommand::CameraPathClear => {
+			let Some(photo_mode) = game.photo_mode.as_mut() else {
+				return game.localization.get("log.photo_mode_required").to_string();
+			};
+			photo_mode.clear_keyframes();
+			game.localization.get("log.camera_path_cleared").to_string()
+		},
+		GameCommand::CameraPathPlay { seconds_per_keyframe, recording_fps } => {
+			if recording_fps.is_some() && !screenshots_are_supported(&game.window_surface_config) {
+				return game.localization.get("log.screenshots_unsupported").to_string();
+			}
+			let Some(photo_mode) = game.photo_mode.as_mut() else {
+				return game.localization.get("log.photo_mode_required").to_string();
+			};
+			let duration_per_keyframe = Duration::from_secs(seconds_per_keyframe.max(1) as u64);
+			let recording_fps = recording_fps.map(|fps| fps.max(1) as u32);
+			match photo_mode.play_camera_path(duration_per_keyframe, recording_fps) {
+				Ok(Some(directory)) => game.localization.get_with_args(
+					"log.camera_path_recording",
+					&[("path", &directory.display())],
+				),
+				Ok(None) => game.localization.get("log.camera_path_playing").to_string(),
+				Err(error) => {
+					game.localization.get_with_args("log.camera_path_error", &[("error", &error)])
+				},
+			}
+		},
+		GameCommand::TakeScreenshot => {
+			if !screenshots_are_supported(&game.window_surface_config) {
+				return game.localization.get("log.screenshots_unsupported").to_string();
+			}
+			game.screenshot_requested = true;
+			game.localization.get("log.screenshot_requested").to_string()
+		},
+		GameCommand::SetRenderDebugMode(mode_name) => {
+			let Some(mode) = RenderDebugMode::from_name(&mode_name) else {
+				return game
+					.localization
+					.get_with_args("log.render_debug_mode_unknown", &[("mode", &mode_name)]);
+			};
+			if mode == RenderDebugMode::Wireframe
+				&& game.rendering.block_wireframe_render_pipeline.is_none()
+			{
+				return game.localization.get("log.wireframe_unsupported").to_string();
+			}
+			game.render_debug_mode = mode;
+			game.localization.get_with_args("log.render_debug_mode_set", &[("mode", &mode_name)])
+		},
+		GameCommand::SetFullscreen(mode_name) => {
+			if mode_name == "windowed" {
+				game.enable_fullscreen = false;
+			} else if let Some(mode) = FullscreenMode::from_name(&mode_name) {
+				game.enable_fullscreen = true;
+				game.fullscreen_mode = mode;
+			} else {
+				return game
+					.localization
+					.get_with_args("log.fullscreen_mode_unknown", &[("mode", &mode_name)]);
+			}
+			game.apply_fullscreen();
+			game.localization.get_with_args("log.fullscreen_mode_set", &[("mode", &mode_name)])
+		},
+		GameCommand::SetPresentMode(mode_name) => {
+			let Some(present_mode_choice) = PresentModeChoice::from_name(&mode_name) else {
+				return game
+					.localization
+					.get_with_args("log.present_mode_unknown", &[("mode", &mode_name)]);
+			};
+			game.set_present_mode(present_mode_choice);
+			let present_mode = format!("{:?}", game.window_surface_config.present_mode);
+			game.localization.get_with_args("log.present_mode_set", &[("mode", &present_mode)])
+		},
+		GameCommand::SelectionFill(block_type_name) => {
+			let Some(span) = game.selection.span() else {
+				return game.localization.get("log.no_selection").to_string();
+			};
+			let Some(type_id) = game.block_type_table.id_from_name(&block_type_name) else {
+				return game
+					.localization
+					.get_with_args("log.block_type_unknown", &[("name", &block_type_name)]);
+			};
+			let blocks = span.iter().map(|coords| (coords, Block::from(type_id))).collect();
+			let count = place_blocks(game, blocks);
+			game.localization.get_with_args("log.selection_filled", &[("count", &count)])
+		},
+		GameCommand::SelectionCopy => {
+			let Some(span) = game.selection.span() else {
+				return game.localization.get("log.no_selection").to_string();
+			};
+			let clipboard = BlockClipboard::copy(game.chunk_grid_shareable.get(), span);
+			let count = clipboard.block_count();
+			game.block_clipboard = Some(clipboard);
+			game.localization.get_with_args("log.selection_copied", &[("count", &count)])
+		},
+		GameCommand::SelectionPaste => {
+			let Some(clipboard) = game.block_clipboard.as_ref() else {
+				return game.localization.get("log.clipboard_empty").to_string();
+			};
+			let Some(targeted_face) = game.targeted_face.as_ref() else {
+				return game.localization.get("log.no_targeted_block").to_string();
+			};
+			let blocks = clipboard.pasted_at(targeted_face.exterior_coords()).collect();
+			let count = place_blocks(game, blocks);
+			game.localization.get_with_args("log.selection_pasted", &[("count", &count)])
+		},
+		GameCommand::SelectionMove { delta } => {
+			let Some(span) = game.selection.span() else {
+				return game.localization.get("log.no_selection").to_string();
+			};
+			let clipboard = BlockClipboard::copy(game.chunk_grid_shareable.get(), span);
+			let air = Block::from(game.block_type_table.air_id());
+			let mut blocks: Vec<_> = span.iter().map(|coords| (coords, air.clone())).collect();
+			blocks.extend(clipboard.pasted_at(span.inf + delta));
+			place_blocks(game, blocks);
+			game.selection.translate(delta);
+			let (x, y, z) = delta.into();
+			game.localization.get_with_args("log.selection_moved", &[("x", &x), ("y", &y), ("z", &z)])
+		},
+		GameCommand::SelectionHollow => {
+			let Some(span) = game.selection.span() else {
+				return game.localization.get("log.no_selection").to_string();
+			};
+			let air = Block::from(game.block_type_table.air_id());
+			let blocks = hollow_interior(span).map(|coords| (coords, air.clone())).collect();
+			let count = place_blocks(game, blocks);
+			game.localization.get_with_args("log.selection_hollowed", &[("count", &count)])
+		},
+		GameCommand::SelectionClear => {
+			game.selection.clear();
+			game.localization.get("log.selection_cleared").to_string()
+		},
+		GameCommand::Unstuck => {
+			let outcome =
+				game.player_phys.unstuck(game.chunk_grid_shareable.get(), &game.block_type_table);
+			let key = match outcome {
+				UnstuckOutcome::NotStuck => "log.unstuck_not_stuck",
+				UnstuckOutcome::Moved => "log.unstuck_moved",
+				UnstuckOutcome::NoFreeSpaceFound => "log.unstuck_no_free_space",
+				UnstuckOutcome::NotLoaded => "log.unstuck_not_loaded",
+			};
+			game.localization.get(key).to_string()
+		},
+		GameCommand::SaveAndQuit => {
+			if game.start_save_and_quit() {
+				game.localization.get("log.save_and_quit_started").to_string()
+			} else {
+				String::new()
+			}
+		},
+		GameCommand::ConfigSave => match Config::current(game).save() {
+			Ok(()) => game.localization.get("log.config_saved").to_string(),
+			Err(error) => {
+				game.localization.get_with_args("log.config_save_error", &[("error", &error)])
+			},
+		},
+		GameCommand::BackupCreate => match game.save.as_ref() {
+			None => game.localization.get("log.backup_no_save").to_string(),
+			Some(_) => match backups::backup_game(game, None) {
+				Ok(name) => game.localization.get_with_args("log.backup_created", &[("name", &name)]),
+				Err(error) => game.localization.get_with_args("log.backup_error", &[("error", &error)]),
+			},
+		},
+		GameCommand::BackupRestore(backup_name) => {
+			let Some(save) = game.save.as_ref() else {
+				return game.localization.get("log.backup_no_save").to_string();
+			};
+			let backup_names = backups::backup_names(save);
+			if !backup_names.contains(&backup_name) {
+				return game.localization.get_with_args(
+					"log.backup_unknown",
+					&[
+						("name", &backup_name),
+						("backups", &backup_names.join(", ")),
+					],
+				);
+			}
+			game.backup_to_restore = Some(backup_name.clone());
+			game.start_save_and_quit();
+			game.localization.get_with_args("log.backup_restoring", &[("name", &backup_name)])
+		},
+		GameCommand::SetPlayerColor { color_name, color } => {
+			let mut appearance = game.player_model.appearance().clone();
+			let Some(appearance_color) = appearance.color_mut(&color_name) else {
+				return game.localization.get_with_args(
+					"log.player_color_unknown",
+					&[
+						("name", &color_name),
+						("names", &PlayerAppearance::COLOR_NAMES.join(", ")),
+					],
+				);
+			};
+			*appearance_color = color.map(|component| component.clamp(0, 255) as u8);
+			let [r, g, b] = *appearance_color;
+			game.player_model.set_appearance(appearance, &game.part_tables);
+			game.localization.get_with_args(
+				"log.player_color_set",
+				&[("name", &color_name), ("r", &r), ("g", &g), ("b", &b)],
+			)
+		},
+		GameCommand::Emote(emote_name) => {
+			let Some(emote) = Emote::from_name(&emote_name) else {
+				return game.localization.get_with_args(
+					"log.emote_unknown",
+					&[("name", &emote_name), ("names", &Emote::NAMES.join(", "))],
+				);
+			};
+			let player_pos = game.player_phys.aligned_box().pos;
+			game.player_model.play_emote(emote, player_pos);
+			game.localization.get_with_args("log.emote_played", &[("name", &emote_name)])
+		},
+		GameCommand::FillChest(loot_table_name) => {
+			let Some(targeted_face) = game.targeted_face.as_ref() else {
+				return game.localization.get("log.no_targeted_block").to_string();
+			};
+			let coords = targeted_face.interior_coords;
+			let chest_type_id = game.block_type_table.kinda_chest_id();
+			let is_chest = game
+				.chunk_grid_shareable
+				.get()
+				.get_block(coords)
+				.is_some_and(|block| block.type_id == chest_type_id);
+			if !is_chest {
+				return game.localization.get("log.not_a_chest").to_string();
+			}
+			let Some(contents) =
+				game.loot_tables.chest_contents(&loot_table_name, &mut rand::thread_rng())
+			else {
+				let names = game.loot_tables.chest_names().join(", ");
+				return game.localization.get_with_args(
+					"log.chest_loot_unknown",
+					&[("name", &loot_table_name), ("names", &names)],
+				);
+			};
+			let count = contents.iter().flatten().count();
+			game.chunk_grid_shareable.perform_now_or_later(
+				ActionOnWorld::PlaceBlockAndMaybeLoseWhatWasThereBefore {
+					block: Block::new_chest(chest_type_id, contents),
+					coords,
+				},
+				game.save.as_ref(),
+				&game.id_generator,
+			);
+			game.localization.get_with_args(
+				"log.chest_filled",
+				&[("name", &loot_table_name), ("count", &count)],
+			)
+		},
+		GameCommand::SetLogLevel { module, level } => match level.parse::<log::LevelFilter>() {
+			Ok(level_filter) => {
+				logging::set_module_level(&module, level_filter);
+				game.localization.get_with_args(
+					"log.log_level_set",
+					&[("module", &module), ("level", &level)],
+				)
+			},
+			Err(_) => game.localization.get_with_args("log.log_level_unknown", &[("level", &level)]),
+		},
+		GameCommand::SetSnowfall(mode) => {
+			let snowing = match mode.as_str() {
+				"on" => true,
+				"off" => false,
+				_ => {
+					return game
+						.localization
+						.get_with_args("log.snowfall_mode_unknown", &[("mode", &mode)]);
+				},
+			};
+			game.surface_layers.snowing = snowing;
+			let key = if snowing {
+				"log.snowfall_on"
+			} else {
+				"log.snowfall_off"
+			};
+			game.localization.get(key).to_string()
+		},
+		GameCommand::SetDebugAnnotations(mode) => {
+			let key = match mode.as_str() {
+				"on" => {
+					debug_annotations::set_enabled(true);
+					"log.debug_annotations_on"
+				},
+				"off" => {
+					debug_annotations::set_enabled(false);
+					"log.debug_annotations_off"
+				},
+				"clear" => {
+					debug_annotations::clear();
+					"log.debug_annotations_cleared"
+				},
+				_ => {
+					return game
+						.localization
+						.get_with_args("log.debug_annotations_mode_unknown", &[("mode", &mode)]);
+				},
+			};
+			game.localization.get(key).to_string()
+		},
+		GameCommand::DebugStructures { radius } => {
+			if radius <= 0 {
+				game.structure_debug_mesh = None;
+				return game.localization.get("log.debug_structures_hidden").to_string();
+			}
+			let radius = radius.min(MAX_DEBUG_STRUCTURES_RADIUS);
+			let player_block_coords = game.player_phys.aligned_box().pos.map(|x| x.round() as i32);
+			let span = CubicCoordsSpan::with_center_and_radius(player_block_coords, radius);
+			let Some(views) = game.world_generator.structure_origins_debug_views(span) else {
+				return game.localization.get("log.stats_structures_unsupported").to_string();
+			};
+			let origin_count: usize = views.iter().map(|view| view.origins.len()).sum();
+			let vertices = structure_debug_line_vertices(&views);
+			game.structure_debug_mesh = Some(SimpleLineMesh::from_vertices(&game.device, vertices));
+			game.localization.get_with_args(
+				"log.debug_structures_displayed",
+				&[("count", &origin_count), ("radius", &radius)],
+			)
+		},
+		GameCommand::TriggerAdd(name) => {
+			let Some(span) = game.selection.span() else {
+				return game.localization.get("log.no_selection").to_string();
+			};
+			let count = span.iter().count();
+			game.triggers.add_volume(TriggerVolume { name: name.clone(), span });
+			game.localization.get_with_args("log.trigger_added", &[("name", &name), ("count", &count)])
+		},
+		GameCommand::TriggerRemove(name) => {
+			let key = if game.triggers.remove_volume(&name) {
+				"log.trigger_removed"
+			} else {
+				"log.trigger_unknown"
+			};
+			game.localization.get_with_args(key, &[("name", &name)])
+		},
+		GameCommand::ModelEdit(name) => {
+			let model = match Model::load(&name) {
+				Ok(model) => model,
+				Err(_) if !Model::file_exists(&name) => Model::default_rig(),
+				Err(error) => {
+					return game.localization.get_with_args("log.model_error", &[("error", &error)]);
+				},
+			};
+			let origin = in_front_of_player(game, 3.0);
+			game.model_editor.open(name.clone(), model, origin, &game.part_tables);
+			game.localization.get_with_args("log.model_editor_opened", &[("name", &name)])
+		},
+		GameCommand::ModelExport => match game.model_editor.edited_model() {
+			None => game.localization.get("log.model_editor_not_open").to_string(),
+			Some((name, model)) => match model.save(name) {
+				Ok(path) => {
+					game.localization.get_with_args("log.model_exported", &[("path", &path.display())])
+				},
+				Err(error) => game.localization.get_with_args("log.model_error", &[("error", &error)]),
+			},
+		},
+		GameCommand::ModelClose => {
+			if !game.model_editor.is_open() {
+				return game.localization.get("log.model_editor_not_open").to_string();
+			}
+			game.model_editor.close(&game.part_tables);
+			game.localization.get("log.model_editor_closed").to_string()
+		},
+		GameCommand::ModelPartAdd(name) => {
+			if game.model_editor.add_part(name.clone()) {
+				game.localization.get_with_args("log.model_part_added", &[("name", &name)])
+			} else {
+				game.localization.get("log.model_editor_not_open").to_string()
+			}
+		},
+		GameCommand::ModelPartRemove => {
+			if !game.model_editor.is_open() {
+				game.localization.get("log.model_editor_not_open").to_string()
+			} else if game.model_editor.remove_selected_part(&game.part_tables) {
+				game.localization.get("log.model_part_removed").to_string()
+			} else {
+				game.localization.get("log.model_last_part").to_string()
+			}
+		},
+		GameCommand::SpawnCreature(name) => match Model::load(&name) {
+			Ok(model) => {
+				let pos = in_front_of_player(game, 3.0);
+				game.chunk_grid_shareable.perform_now_or_later(
+					ActionOnWorld::AddEntity(Entity::new_creature(
+						&game.id_generator,
+						pos,
+						name.clone(),
+						model,
+					)),
+					game.save.as_ref(),
+					&game.id_generator,
+				);
+				game.localization.get_with_args("log.creature_spawned", &[("name", &name)])
+			},
+			Err(error) => game.localization.get_with_args("log.model_error", &[("error", &error)]),
+		},
+		GameCommand::SpawnHostile => {
+			let pos = in_front_of_player(game, 5.0);
+			game.chunk_grid_shareable.perform_now_or_later(
+				ActionOnWorld::AddEntity(Entity::new_hostile(&game.id_generator, pos)),
+				game.save.as_ref(),
+				&game.id_generator,
+			);
+			game.localization.get("log.hostile_spawned").to_string()
+		},
+		GameCommand::TexgenPreview {
+			recipe: recipe_name,
+			palette: palette_name,
+			scale,
+			roughness_percent,
+		} => {
+			let Some(recipe) = TextureRecipe::from_name(&recipe_name) else {
+				return game
+					.localization
+					.get_with_args("log.texgen_recipe_unknown", &[("recipe", &recipe_name)]);
+			};
+			let Some(palette) = palette_from_name(&palette_name) else {
+				return game
+					.localization
+					.get_with_args("log.texgen_palette_unknown", &[("palette", &palette_name)]);
+			};
+			let params = RecipeParams {
+				palette,
+				scale: scale as f32,
+				roughness: roughness_percent as f32 / 100.0,
+			};
+			let mut image: image::RgbaImage = image::ImageBuffer::new(16, 16);
+			let view = image::GenericImage::sub_image(&mut image, 0, 0, 16, 16);
+			// The preview is in the interface, it does not glow.
+			let mut emissive_mask = image::GrayImage::new(16, 16);
+			let emissive_view = image::GenericImage::sub_image(&mut emissive_mask, 0, 0, 16, 16);
+			recipe.paint(view, emissive_view, game.world_gen_seed, 0, &params);
+			update_atlas_texture_rect(
+				&game.queue,
+				&game.atlas_texture,
+				TEXTURE_PREVIEW_COORDS_ON_ATLAS,
+				&image,
+			);
+			let caption = format!("{recipe_name} {palette_name} {scale} {roughness_percent}");
+			game.interface.update_texture_preview(Some(caption));
+			game.localization.get_with_args("log.texgen_previewed", &[("recipe", &recipe_name)])
+		},
+		GameCommand::TexgenPreviewClose => {
+			game.interface.update_texture_preview(None);
+			game.localization.get("log.texgen_preview_closed").to_string()
+		},
+	}
+}
+
+/// The cell edges of each view, and a marker on each origin (a box around the origin block
+/// and a line going up from it so that it can be seen from afar), the origins are also annotated
+/// with their structure type (see `debug_annotations`).
+fn structure_debug_line_vertices(views: &[StructureOriginsDebugView]) -> Vec<SimpleLineVertexPod> {
+	let mut vertices = vec![];
+	for view in views {
+		for cell in view.cells.iter() {
+			vertices.extend(SimpleLineMesh::vertices_for_aligned_box(
+				&cell.to_aligned_box(),
+				view.cell_color,
+			));
+		}
+		for &(coords, name, color) in view.origins.iter() {
+			let pos = coords.map(|x| x as f32);
+			vertices.extend(SimpleLineMesh::vertices_for_aligned_box(
+				&AlignedBox { pos, dims: cgmath::vec3(1.2, 1.2, 1.2) },
+				color,
+			));
+			vertices.push(SimpleLineVertexPod { position: pos.into(), color });
+			vertices.push(SimpleLineVertexPod {
+				position: (pos + cgmath::vec3(0.0, 0.0, 8.0)).into(),
+				color,
+			});
+			debug_annotations::annotate(coords, name, color);
+		}
+	}
+	vertices
+}
+
+/// The point that is at the given horizontal distance in front of the player.
+fn in_front_of_player(game: &Game, distance: f32) -> cgmath::Point3<f32> {
+	let direction = game.camera_direction.to_horizontal().to_vec3();
+	game.player_phys.aligned_box().pos + direction * distance
+}
+
+/// Editing at least that many blocks at once backs up the save first (see `backups`).
+const MASS_EDIT_BACKUP_BLOCK_COUNT: usize = 10_000;
+
+/// Places the given blocks in order (in one edit batch), except those that are in chunks that
+/// are not loaded (which are skipped). Returns the number of blocks that were placed.
+fn place_blocks(game: &mut Game, blocks: Vec<(BlockCoords, Block)>) -> usize {
+	let chunk_grid = game.chunk_grid_shareable.get();
+	let mut edit_batch = chunk_grid.edit_batch();
+	for (coords, block) in blocks {
+		if chunk_grid.get_block(coords).is_some() {
+			edit_batch.set(coords, block);
+		}
+	}
+	let count = edit_batch.block_count();
+	if count >= MASS_EDIT_BACKUP_BLOCK_COUNT && game.save.is_some() {
+		match backups::backup_game(game, Some("before_mass_edit")) {
+			Ok(name) => log::info!("Backup \"{name}\" created before editing {count} blocks"),
+			Err(error) => log::error!("Failed to back up the save before editing blocks: {error}"),
+		}
+	}
+	game.chunk_grid_shareable.perform_now_or_later(
+		ActionOnWorld::ApplyEditBatch(edit_batch),
+		game.save.as_ref(),
+		&game.id_generator,
+	);
+	count
+}
@@ -7,42 +7,90 @@ use std::{
 };
 
 use crate::{
+	achievements::Achievements,
 	atlas::Atlas,
+	audio::{Audio, AudioOutput, AudioSettings, DeviceOutput, WavFileOutput},
+	backups,
+	block_definitions::parse_block_definitions_file,
+	block_picker::BlockPicker,
+	block_type_remapping::BlockTypeIdRemapping,
 	block_types::BlockTypeTable,
+	bloom::Bloom,
 	camera::{CameraOrthographicSettings, CameraPerspectiveSettings},
+	chest_screen::ChestScreen,
 	chunk_blocks::Block,
+	chunk_cache::ChunkCache,
+	chunk_instances::ChunkInstances,
 	chunk_loading::LoadingManager,
+	chunk_mesh_culling::ChunkMeshCulling,
 	chunks::{ChunkGrid, ChunkGridShareable},
 	cmdline,
+	color_grading::{ColorGrading, GradingSettings},
 	commands::{self, Action, Control, ControlEvent},
-	coords::{AlignedBox, AngularDirection, ChunkCoords, ChunkDimensions, OrientedFaceCoords},
-	entities::{IdGenerator, IdGeneratorState},
+	config::Config,
+	coords::{
+		AlignedBox, AngularDirection, BlockCoords, ChunkCoords, ChunkDimensions, OrientedFaceCoords,
+	},
+	deferred_lighting::DeferredLighting,
+	determinism,
+	display::{
+		grab_cursor, supported_present_mode, FullscreenMode, PresentModeChoice, WindowGeometry,
+	},
+	entities::{Id as EntityId, IdGenerator, IdGeneratorState},
+	entity_culling::EntityCulling,
 	entity_parts::{
-		PartTables, PartTablesForRendering, TextureMappingAndColoringTable,
+		PartTables, PartTablesForRendering, PartsInterpolation, TextureMappingAndColoringTable,
 		TextureMappingAndColoringTableRwLock,
 	},
+	events::EventHooks,
+	fire::Fire,
 	font::{self, Font},
+	gameplay_stats::GameplayStats,
+	held_block::HeldBlockView,
 	interface::Interface,
 	lang,
-	physics::{AlignedPhysBox, PlayerJumpManager},
+	lens_effects::LensEffects,
+	loading_screen::LoadingScreen,
+	localization::Localization,
+	logging,
+	loot_tables::LootTables,
+	model_editor::ModelEditor,
+	photo_mode::PhotoMode,
+	physics::{AlignedPhysBox, PlayerJumpManager, PlayerSneakManager},
+	player_model::PlayerModel,
+	pregen::Pregen,
+	render_debug::RenderDebugMode,
+	render_scale::RenderScaling,
+	render_stats::RenderStats,
 	rendering_init::{
-		self, init_aspect_ratio_thingy, init_atlas_stuff, init_camera_matrix_thingy, init_fog_stuff,
-		init_shadow_map_stuff, init_skybox_stuff, init_sun_camera_matrices_thingy,
-		init_sun_light_direction_thingy, init_texturing_and_coloring_array_thingy,
-		make_z_buffer_texture_view, AllBindingThingies, AtlasStuff, BindingThingy, FogStuff,
-		RenderPipelinesAndBindGroups, ShadowMapStuff, SkyboxStuff, SunCameraStuff,
+		self, init_aspect_ratio_thingy, init_atlas_stuff, init_camera_matrix_thingy,
+		init_entity_interpolation_factor_thingy, init_fog_stuff, init_held_block_settings_thingy,
+		init_normal_mapping_settings_thingy, init_shadow_map_stuff, init_skybox_stuff,
+		init_sun_camera_matrices_thingy, init_sun_light_direction_thingy,
+		init_texturing_and_coloring_array_thingy, init_time_thingy, init_water_reflection_stuff,
+		make_msaa_color_texture_view, make_z_buffer_texture_view, supported_msaa_sample_count,
+		AllBindingThingies, AtlasStuff, BindingThingy, FogStuff, RenderPipelinesAndBindGroups,
+		ShadowMapStuff, SkyboxStuff, SunCameraStuff, WaterReflectionStuff,
 	},
+	replay::{ReplayHeader, ReplayPlayback, ReplayRecorder},
+	save_and_quit::SaveAndQuit,
 	saves::Save,
-	shaders::{Vector2Pod, Vector3Pod},
+	selection::{BlockClipboard, Selection},
+	shaders::{block::NormalMappingSettingsPod, Vector2Pod, Vector3Pod},
+	signals::Signals,
 	simple_meshes::SimpleLineMesh,
 	skybox::{
 		default_skybox_painter, default_skybox_painter_3, generate_skybox_cubemap_faces_images,
 		SkyboxFaces,
 	},
+	spawn_point,
+	spawning::Spawner,
+	surface_layers::SurfaceLayers,
 	tasks::{WorkerTask, WorkerTasksManager},
-	threadpool,
+	threadpool, trace,
+	triggers::{TriggerVolume, Triggers},
 	widgets::Widget,
-	world_gen::{WhichWorldGenerator, WorldGenerator},
+	world_gen::{HeightmapSettings, WhichWorldGenerator, WorldGenerator, WorldGeneratorHeightmap},
 };
 
 use clap::ValueEnum;
@@ -55,23 +103,49 @@ struct StateSavable {
 	chunk_dimensions_edge: i32,
 	world_gen_seed: i32,
 	which_world_generator: WhichWorldGenerator,
+	/// Overrides `which_world_generator` if the world is generated from a heightmap.
+	#[serde(default)]
+	heightmap: Option<HeightmapSettings>,
 	only_save_modified_chunks: bool,
 	set_of_already_generated_chunks: FxHashSet<ChunkCoords>,
 	player_pos: [f32; 3],
+	/// Where the player respawns, older saves do not have it.
+	#[serde(default)]
+	player_spawn_pos: Option<[f32; 3]>,
 	player_angular_direction: [f32; 2],
 	world_time: Duration,
 	player_held_block: Option<Block>,
+	#[serde(default)]
+	player_tool: Option<Block>,
 	enable_player_physics: bool,
 	id_generator_state: IdGeneratorState,
+	#[serde(default)]
+	trigger_volumes: Vec<TriggerVolume>,
+	#[serde(default)]
+	gameplay_stats: GameplayStats,
+	#[serde(default)]
+	achievements: Achievements,
 }
 
+/// In play mode, in hearts.
+pub(crate) const PLAYER_MAX_HEALTH: u32 = 5;
+/// The damage of the player hitting an entity.
+pub(crate) const PLAYER_HIT_DAMAGE: u32 = 2;
+/// Horizontal motion given to an entity hit by the player (it also gets half of that upwards).
+pub(crate) const PLAYER_HIT_KNOCKBACK: f32 = 0.15;
+
 pub(crate) fn save_savable_state(game: &Game) {
-	let mut state_file =
-		std::fs::File::create(&game.save.as_ref().unwrap().state_file_path).unwrap();
+	save_savable_state_in(game, game.save.as_ref().unwrap());
+}
+
+/// Saves the state of the game in the given save, which may not be the save of the game
+/// (like a backup, see `backups`).
+pub(crate) fn save_savable_state_in(game: &Game, save: &Arc<Save>) {
 	let savable = StateSavable {
 		chunk_dimensions_edge: game.cd.edge,
 		world_gen_seed: game.world_gen_seed,
 		which_world_generator: game.which_world_generator,
+		heightmap: game.heightmap.clone(),
 		only_save_modified_chunks: game.only_save_modified_chunks,
 		set_of_already_generated_chunks: game
 			.chunk_grid_shareable
@@ -79,13 +153,23 @@ pub(crate) fn save_savable_state(game: &Game) {
 			.set_of_already_generated_chunks()
 			.clone(),
 		player_pos: game.player_phys.aligned_box().pos.into(),
+		player_spawn_pos: Some(game.player_spawn_pos.into()),
 		player_angular_direction: game.camera_direction.into(),
 		world_time: game.world_time,
 		player_held_block: game.player_held_block.clone(),
+		player_tool: game.player_tool.clone(),
 		enable_player_physics: game.enable_player_physics,
 		id_generator_state: game.id_generator.state(),
+		trigger_volumes: game.triggers.volumes().to_vec(),
+		gameplay_stats: game.gameplay_stats.clone(),
+		achievements: game.achievements.clone(),
 	};
-	let data = rmp_serde::encode::to_vec(&savable).unwrap();
+	save_savable_state_to_save(save, &savable);
+}
+
+fn save_savable_state_to_save(save: &Arc<Save>, savable: &StateSavable) {
+	let mut state_file = std::fs::File::create(&save.state_file_path).unwrap();
+	let data = rmp_serde::encode::to_vec(savable).unwrap();
 	state_file.write_all(&data).unwrap();
 }
 
@@ -105,11 +189,34 @@ pub(crate) struct Game {
 	pub(crate) queue: Arc<wgpu::Queue>,
 	pub(crate) window_surface_config: wgpu::SurfaceConfiguration,
 	pub(crate) aspect_ratio_thingy: BindingThingy<wgpu::Buffer>,
+	pub(crate) time_thingy: BindingThingy<wgpu::Buffer>,
+	pub(crate) entity_interpolation_factor_thingy: BindingThingy<wgpu::Buffer>,
 	pub(crate) z_buffer_view: wgpu::TextureView,
 	pub(crate) z_buffer_format: wgpu::TextureFormat,
+	/// Sample count of the multisample anti-aliasing (1 if disabled).
+	pub(crate) msaa_sample_count: u32,
+	/// The screen is rendered to it (and resolved to the window surface) if multisampling.
+	pub(crate) msaa_color_view: Option<wgpu::TextureView>,
+	/// The scene may be rendered at a different resolution than the window's.
+	/// The z buffer, the multisampled color buffer and the G-buffers have the scaled size.
+	pub(crate) render_scaling: RenderScaling,
+	pub(crate) lens_effects: LensEffects,
+	pub(crate) bloom: Bloom,
+	pub(crate) color_grading: ColorGrading,
+	/// The interface is rendered at the resolution of the window, without multisampling.
+	pub(crate) interface_z_buffer_view: wgpu::TextureView,
+	/// Used instead of the forward rendering of blocks if the deferred rendering path is enabled.
+	pub(crate) deferred_lighting: Option<DeferredLighting>,
+	/// Culls the chunk meshes that are out of view on the GPU, if supported and not disabled.
+	pub(crate) chunk_mesh_culling: Option<ChunkMeshCulling>,
+	pub(crate) chunk_instances: ChunkInstances,
+	/// Statistics of the last rendered frame, displayed in the debug info.
+	pub(crate) render_stats: RenderStats,
 	pub(crate) camera_direction: AngularDirection,
 	pub(crate) camera_settings: CameraPerspectiveSettings,
 	pub(crate) camera_matrix_thingy: BindingThingy<wgpu::Buffer>,
+	/// The camera of the map exports, see `map_export`.
+	pub(crate) map_export_camera_matrix_thingy: BindingThingy<wgpu::Buffer>,
 	pub(crate) sun_position_in_sky: AngularDirection,
 	pub(crate) sun_light_direction_thingy: BindingThingy<wgpu::Buffer>,
 	pub(crate) sun_cameras: Vec<CameraOrthographicSettings>,
@@ -117,14 +224,43 @@ pub(crate) struct Game {
 	pub(crate) sun_camera_single_matrix_thingy: BindingThingy<wgpu::Buffer>,
 	pub(crate) shadow_map_cascade_view_thingies: Vec<BindingThingy<wgpu::TextureView>>,
 	pub(crate) targeted_face: Option<OrientedFaceCoords>,
+	/// The entity that the player is looking at (if close enough), and the chunk it is in.
+	pub(crate) targeted_entity: Option<(EntityId, ChunkCoords)>,
+	/// The mount that the player is riding (if any), and the chunk it is in.
+	pub(crate) ridden_mount: Option<(EntityId, ChunkCoords)>,
+	/// The player asked the ridden mount to jump, the request is to be sent to the mount.
+	pub(crate) mount_jump_requested: bool,
+	/// The entity that the player picked to tether to something (if any), and the chunk it is in.
+	pub(crate) tether_held_entity: Option<(EntityId, ChunkCoords)>,
+	/// When enabled, clicking on blocks sets the corners of the `selection`
+	/// instead of breaking or placing blocks.
+	pub(crate) selection_tool_enabled: bool,
+	pub(crate) selection: Selection,
+	pub(crate) block_clipboard: Option<BlockClipboard>,
+	pub(crate) triggers: Triggers,
+	/// Qwy Script code to run when some events happen.
+	pub(crate) event_hooks: EventHooks,
 	pub(crate) player_phys: AlignedPhysBox,
 	pub(crate) player_jump_manager: PlayerJumpManager,
+	pub(crate) player_sneak_manager: PlayerSneakManager,
+	/// When a saved world is loaded, the player may be stuck in blocks (if the world generation
+	/// changed since the save for example). It is checked (and fixed) as soon as the blocks
+	/// around the player are loaded.
+	pub(crate) unstuck_player_when_loaded: bool,
 	pub(crate) cd: ChunkDimensions,
 	pub(crate) chunk_grid_shareable: ChunkGridShareable,
 	pub(crate) loading_manager: LoadingManager,
+	/// Radius (in blocks) of the area around the player in which the world is simulated,
+	/// independently of the loading distance (see `--sim-dist`), `None` means no limit.
+	pub(crate) simulation_distance: Option<f32>,
 	pub(crate) controls_to_trigger: Vec<ControlEvent>,
 	pub(crate) control_bindings: HashMap<Control, Action>,
+	/// The settings loaded from the config file (with the command line overrides applied),
+	/// kept to be written back by the `config_save` command.
+	pub(crate) config: Config,
 	pub(crate) block_type_table: Arc<BlockTypeTable>,
+	/// What blocks and entities drop and what chests can be filled with (see `loot_tables`).
+	pub(crate) loot_tables: LootTables,
 	pub(crate) rendering: RenderPipelinesAndBindGroups,
 	pub(crate) close_after_one_frame: bool,
 	pub(crate) cursor_mesh: SimpleLineMesh,
@@ -136,8 +272,42 @@ pub(crate) struct Game {
 	pub(crate) command_confirmed: bool,
 	pub(crate) world_generator: Arc<dyn WorldGenerator + Sync + Send>,
 	pub(crate) which_world_generator: WhichWorldGenerator,
+	/// Set if the world is generated from a heightmap (instead of `which_world_generator`).
+	pub(crate) heightmap: Option<HeightmapSettings>,
 	pub(crate) world_gen_seed: i32,
 	pub(crate) interface: Interface,
+	pub(crate) localization: Localization,
+	pub(crate) block_picker: BlockPicker,
+	pub(crate) chest_screen: ChestScreen,
+	pub(crate) model_editor: ModelEditor,
+	/// The player as seen from a third person view.
+	pub(crate) player_model: PlayerModel,
+	pub(crate) audio: Audio,
+	/// The replay being recorded, if any (see `--record-replay`).
+	pub(crate) replay_recorder: Option<ReplayRecorder>,
+	/// The replay being played, if any (see `--play-replay`).
+	pub(crate) replay_playback: Option<ReplayPlayback>,
+	/// Displayed (with the player waiting) until the spawn area is loaded.
+	pub(crate) loading_screen: Option<LoadingScreen>,
+	/// While in photo mode, the simulation is frozen and the camera is detached from the player.
+	pub(crate) photo_mode: Option<PhotoMode>,
+	/// A screenshot of the next frame is to be taken (see `photo_mode`).
+	pub(crate) screenshot_requested: bool,
+	/// The chunks being pregenerated in the background, if any (see `pregen`).
+	pub(crate) pregen: Option<Pregen>,
+	/// The game is quitting, everything is being saved before exiting (see `save_and_quit`).
+	pub(crate) save_and_quit: Option<SaveAndQuit>,
+	/// The backup that the save is to be rolled back to when exiting (see `backups`).
+	pub(crate) backup_to_restore: Option<String>,
+	/// What the player did in this world, kept in the save (see `gameplay_stats`).
+	pub(crate) gameplay_stats: GameplayStats,
+	pub(crate) stats_screen_is_open: bool,
+	/// The achievements unlocked in this world, kept in the save (see `achievements`).
+	pub(crate) achievements: Achievements,
+	pub(crate) spawner: Spawner,
+	pub(crate) surface_layers: SurfaceLayers,
+	pub(crate) fire: Fire,
+	pub(crate) signals: Signals,
 	pub(crate) enable_interface_draw_debug_boxes: bool,
 	pub(crate) skybox_cubemap_texture: wgpu::Texture,
 	pub(crate) fog_center_position_thingy: BindingThingy<wgpu::Buffer>,
@@ -145,21 +315,36 @@ pub(crate) struct Game {
 	pub(crate) fog_inf_sup_radiuses: (f32, f32),
 	pub(crate) fog_margin: f32,
 	pub(crate) output_atlas_when_generated: bool,
+	/// The average color of the texture of each block type (see `Atlas::block_type_colors`).
+	pub(crate) block_type_colors: Vec<[u8; 3]>,
 	pub(crate) atlas_texture: wgpu::Texture,
+	pub(crate) normal_atlas_texture: wgpu::Texture,
 	pub(crate) save: Option<Arc<Save>>,
 	pub(crate) only_save_modified_chunks: bool,
+	pub(crate) chunk_cache: Option<Arc<ChunkCache>>,
 	pub(crate) max_fps: Option<i32>,
-	pub(crate) no_vsync: bool,
+	/// The present mode can be changed while running, to the ones that are supported.
+	pub(crate) supported_present_modes: Vec<wgpu::PresentMode>,
 	pub(crate) part_tables: Arc<PartTables>,
 	pub(crate) part_tables_for_rendering: PartTablesForRendering,
 	pub(crate) texturing_and_coloring_array_thingy: Arc<BindingThingy<wgpu::Buffer>>,
 	pub(crate) texture_mapping_table: Arc<TextureMappingAndColoringTableRwLock>,
 	pub(crate) player_held_block: Option<Block>,
+	/// The tool equipped by the player (see `BlockType::Tool`), used to break blocks.
+	pub(crate) player_tool: Option<Block>,
+	/// The block being broken by the player and the progress of its breaking (from 0 to 1,
+	/// it breaks at 1), see `Hardness`.
+	pub(crate) block_breaking: Option<(BlockCoords, f32)>,
 	pub(crate) world_time: Duration,
 	pub(crate) playing_mode: PlayingMode,
 	pub(crate) player_health: Option<u32>,
+	/// Where the player appeared in the world, and where it respawns when it dies.
+	pub(crate) player_spawn_pos: cgmath::Point3<f32>,
 	pub(crate) id_generator: Arc<IdGenerator>,
 	pub(crate) last_entity_physics_start: Option<std::time::Instant>,
+	pub(crate) parts_interpolation: PartsInterpolation,
+	/// Which entity parts are rendered (see `--entity-render-dist` and `--entity-lod-dist`).
+	pub(crate) entity_culling: EntityCulling,
 
 	pub(crate) worker_tasks: WorkerTasksManager,
 	pub(crate) pool: threadpool::ThreadPool,
@@ -171,32 +356,54 @@ pub(crate) struct Game {
 	pub(crate) walking_backward: bool,
 	pub(crate) walking_leftward: bool,
 	pub(crate) walking_rightward: bool,
+	/// Held down to sneak (see `PlayerSneakManager`).
+	pub(crate) sneak_control_held: bool,
+	/// Held down to break the targeted block (see `block_breaking`).
+	pub(crate) breaking_control_held: bool,
+	/// Held down to target the blocks behind decorations like grass blades.
+	pub(crate) targeting_through_decorations: bool,
 	pub(crate) enable_player_physics: bool,
 	pub(crate) enable_world_generation: bool,
 	pub(crate) selected_camera: WhichCameraToUse,
+	pub(crate) render_debug_mode: RenderDebugMode,
+	/// The structure cells and origins displayed by the `debug_structures` command, if any.
+	pub(crate) structure_debug_mesh: Option<SimpleLineMesh>,
 	pub(crate) enable_display_phys_box: bool,
 	pub(crate) cursor_is_captured: bool,
+	/// The game is paused while the window is not focused.
+	pub(crate) paused: bool,
+	/// If the cursor was captured when the window lost focus, then it is captured again
+	/// when the window gets the focus back.
+	pub(crate) capture_cursor_when_focused: bool,
 	pub(crate) enable_display_interface: bool,
 	pub(crate) enable_display_not_surrounded_chunks_as_boxes: bool,
 	pub(crate) enable_display_chunks_with_entities_as_boxes: bool,
 	pub(crate) enable_display_entity_boxes: bool,
+	/// Display the id and kind of entities in their labels.
+	pub(crate) enable_display_entity_ids: bool,
+	/// Entity labels can be seen through the world.
+	pub(crate) entity_labels_see_through: bool,
 	pub(crate) enable_fog: bool,
+	pub(crate) enable_water_reflections: bool,
+	pub(crate) water_reflection_stuff: WaterReflectionStuff,
+	pub(crate) enable_normal_mapping: bool,
+	/// See `NormalMappingSettingsPod`.
+	pub(crate) normal_mapping_settings_thingy: BindingThingy<wgpu::Buffer>,
+	pub(crate) held_block_view: HeldBlockView,
+	pub(crate) held_block_settings_thingy: BindingThingy<wgpu::Buffer>,
 	pub(crate) enable_fullscreen: bool,
+	pub(crate) fullscreen_mode: FullscreenMode,
+	/// Saved when closing the game so that the window is restored as it was on the next run.
+	pub(crate) window_geometry: WindowGeometry,
 }
 
 pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game {
-	// Wgpu uses the `log`/`env_logger` crates to log errors and stuff,
-	// and we do want to see the errors very much.
-	env_logger::init();
-
-	if cfg!(debug_assertions) {
-		println!(
-			"Running a debug build.\n\
-			Note that better performances are possible with a release build,\n\
-			using the command `cargo run --release -- [arguments for Qwy3]`"
-		);
-	}
-
+	let (config, config_error) = match Config::load() {
+		Ok(config) => (config, None),
+		Err(error) => (Config::default(), Some(error)),
+	};
+	let command_line_settings = cmdline::parse_command_line_arguments(&config);
+	let config = config.with_settings(&command_line_settings);
 	let cmdline::CommandLineSettings {
 		number_of_threads,
 		close_after_one_frame,
@@ -204,19 +411,76 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 		output_atlas,
 		world_gen_seed,
 		which_world_generator,
+		heightmap,
+		heightmap_scale,
+		heightmap_clamp,
 		display_world_generator_possible_names,
 		loading_distance,
+		no_adaptive_loading,
+		unloading_distance,
+		min_resident_time,
+		simulation_distance,
+		entity_render_distance,
+		entity_lod_distance,
 		chunk_edge,
 		fullscreen,
+		fullscreen_mode,
 		no_vsync,
+		present_mode,
 		max_fps,
 		no_fog,
 		fog_margin,
+		no_water_reflections,
+		no_normal_mapping,
 		save_name,
 		only_save_modified_chunks,
+		chunk_cache,
+		pregen,
 		playing_mode,
+		language,
+		master_volume,
+		sfx_volume,
+		music_volume,
+		no_music,
+		no_audio_device,
+		audio_dump,
+		no_creature_spawning,
+		snowing,
+		deferred_lighting,
+		no_gpu_culling,
+		msaa,
+		render_scale,
+		bloom_threshold,
+		bloom_intensity,
+		depth_of_field,
+		motion_blur,
+		tonemapper,
+		exposure,
+		contrast,
+		saturation,
+		color_lut,
+		trace,
+		record_replay,
+		play_replay,
+		replay_mode,
+		verify_determinism,
 		test_lang,
-	} = cmdline::parse_command_line_arguments();
+	} = command_line_settings;
+
+	// Wgpu uses the `log` crate to log errors and stuff,
+	// and we do want to see the errors very much.
+	logging::init(verbose);
+	if let Some(error) = config_error {
+		log::error!("Failed to load the config file, using the default settings: {error}");
+	}
+
+	if cfg!(debug_assertions) {
+		log::info!(
+			"Running a debug build. \
+			Note that better performances are possible with a release build, \
+			using the command `cargo run --release -- [arguments for Qwy3]`"
+		);
+	}
 
 	if display_world_generator_possible_names {
 		crate::cmdline::display_world_generator_names();
@@ -224,19 +488,78 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 	}
 
 	if let Some(test_id) = test_lang {
-		println!("Test lang: test id {test_id}");
+		log::info!("Test lang: test id {test_id}");
 		lang::test_lang(test_id);
 		std::process::exit(0);
 	}
 
+	if let Some(radius_in_chunks) = verify_determinism {
+		let seed = world_gen_seed.unwrap_or_else(|| rand::thread_rng().gen());
+		let number_of_threads = number_of_threads.unwrap_or_else(|| {
+			std::thread::available_parallelism().map_or(4, |threads| threads.get() as u32)
+		});
+		let is_deterministic = determinism::verify_determinism(
+			which_world_generator,
+			seed,
+			ChunkDimensions::from(chunk_edge as i32),
+			radius_in_chunks,
+			number_of_threads,
+		);
+		std::process::exit(if is_deterministic { 0 } else { 1 });
+	}
+
+	if let Some(trace_file_path) = trace {
+		if let Err(error) = trace::start_tracing(&trace_file_path) {
+			log::warn!("Could not create the trace file {trace_file_path:?}: {error}");
+		}
+	}
+
+	// A replay is played in the world it was recorded in.
+	let replay_playback =
+		play_replay.and_then(|path| match ReplayPlayback::open(&path, replay_mode) {
+			Ok(playback) => {
+				log::info!(
+					"Playing the replay \"{}\" ({} frames)",
+					path.display(),
+					playback.frame_count()
+				);
+				Some(playback)
+			},
+			Err(error) => {
+				log::error!("Failed to read the replay \"{}\": {error}", path.display());
+				None
+			},
+		});
+	let (world_gen_seed, which_world_generator, chunk_edge) = match replay_playback.as_ref() {
+		Some(playback) => {
+			let header = playback.header();
+			(
+				Some(header.world_gen_seed),
+				header.which_world_generator,
+				header.chunk_edge as u32,
+			)
+		},
+		None => (world_gen_seed, which_world_generator, chunk_edge),
+	};
+
 	let enable_fullscreen = fullscreen;
-	let window_attributes = winit::window::Window::default_attributes()
+	let saved_window_geometry = WindowGeometry::load();
+	let mut window_attributes = winit::window::Window::default_attributes()
 		.with_title("Qwy3")
-		.with_maximized(true)
-		.with_resizable(true)
-		.with_fullscreen(enable_fullscreen.then_some(winit::window::Fullscreen::Borderless(None)));
+		.with_maximized(saved_window_geometry.as_ref().is_none_or(|geometry| geometry.maximized))
+		.with_resizable(true);
+	if let Some(geometry) = &saved_window_geometry {
+		window_attributes = window_attributes.with_inner_size(geometry.size);
+		if let Some(position) = geometry.position {
+			window_attributes = window_attributes.with_position(position);
+		}
+	}
 	let window = event_loop.create_window(window_attributes).unwrap();
 	let window = Arc::new(window);
+	// The geometry to remember is the one of the window before it gets fullscreen.
+	let window_geometry = WindowGeometry::of_window(&window);
+	// Exclusive fullscreen needs the window (to know on which monitor it is).
+	window.set_fullscreen(enable_fullscreen.then(|| fullscreen_mode.to_winit(&window)));
 
 	let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
 	let window_surface = instance.create_surface(Arc::clone(&window)).unwrap();
@@ -261,23 +584,32 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 	});
 	let adapter = adapter.unwrap();
 
-	if verbose {
-		// At some point it could be nice to allow the user to choose their preferred adapter.
-		// No one should have to struggle to make some game use the big GPU instead of the tiny one.
-		println!("AVAILABLE ADAPTERS:");
-		for adapter in instance.enumerate_adapters(wgpu::Backends::all()) {
-			dbg!(adapter.get_info());
-			dbg!(adapter.limits().max_bind_groups);
-		}
-		println!("SELECTED ADAPTER:");
-		dbg!(adapter.get_info());
+	// At some point it could be nice to allow the user to choose their preferred adapter.
+	// No one should have to struggle to make some game use the big GPU instead of the tiny one.
+	for available_adapter in instance.enumerate_adapters(wgpu::Backends::all()) {
+		log::debug!(
+			"Available adapter: {:?} (max bind groups: {})",
+			available_adapter.get_info(),
+			available_adapter.limits().max_bind_groups
+		);
 	}
+	log::debug!("Selected adapter: {:?}", adapter.get_info());
 
 	let (device, queue) = futures::executor::block_on(async {
 		adapter
 			.request_device(
 				&wgpu::DeviceDescriptor {
-					required_features: wgpu::Features::empty(),
+					// Allows for more multisampling sample counts than the ones guaranteed by WebGPU,
+					// for the wireframe debug rendering mode and for the culling of chunk meshes
+					// on the GPU (if available).
+					required_features: adapter.features()
+						& (wgpu::Features::POLYGON_MODE_LINE
+							| wgpu::Features::INDIRECT_FIRST_INSTANCE
+							| if msaa > 1 {
+								wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+							} else {
+								wgpu::Features::empty()
+							}),
 					required_limits: wgpu::Limits { ..wgpu::Limits::default() },
 					label: None,
 				},
@@ -296,20 +628,23 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 		.copied()
 		.find(|f| f.is_srgb())
 		.unwrap_or(surface_capabilities.formats[0]);
-	let desired_present_mode = if no_vsync {
-		if !surface_capabilities.present_modes.contains(&wgpu::PresentMode::Immediate) {
-			println!("Warning: Immediate present mode (V-Sync Off) not available.");
-			wgpu::PresentMode::Fifo
-		} else {
-			wgpu::PresentMode::Immediate
-		}
+	let present_mode_choice = present_mode.unwrap_or(if no_vsync {
+		PresentModeChoice::Immediate
 	} else {
-		wgpu::PresentMode::Fifo
-	};
-	assert!(surface_capabilities.present_modes.contains(&desired_present_mode));
+		PresentModeChoice::Fifo
+	});
+	let supported_present_modes = surface_capabilities.present_modes.clone();
+	let desired_present_mode = supported_present_mode(present_mode_choice, &supported_present_modes);
 	let size = window.inner_size();
+	// Reading the window back is needed to take screenshots (see `photo_mode`).
+	let window_surface_usage = if surface_capabilities.usages.contains(wgpu::TextureUsages::COPY_SRC)
+	{
+		wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC
+	} else {
+		wgpu::TextureUsages::RENDER_ATTACHMENT
+	};
 	let window_surface_config = wgpu::SurfaceConfiguration {
-		usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+		usage: window_surface_usage,
 		format: surface_format,
 		width: size.width,
 		height: size.height,
@@ -321,13 +656,25 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 	window_surface.configure(&device, &window_surface_config);
 
 	let aspect_ratio_thingy = init_aspect_ratio_thingy(Arc::clone(&device));
+	let time_thingy = init_time_thingy(Arc::clone(&device));
+	let entity_interpolation_factor_thingy =
+		init_entity_interpolation_factor_thingy(Arc::clone(&device));
+	let held_block_settings_thingy = init_held_block_settings_thingy(Arc::clone(&device));
+	let enable_normal_mapping = !no_normal_mapping;
+	let normal_mapping_settings_thingy = init_normal_mapping_settings_thingy(
+		Arc::clone(&device),
+		NormalMappingSettingsPod::new(enable_normal_mapping),
+	);
+	let held_block_view = HeldBlockView::new(&device);
 
 	let save = save_name.map(|name| Arc::new(Save::create(name)));
-	let saved_state = save.as_ref().and_then(load_savable_state_from_save);
+	let mut saved_state = save.as_ref().and_then(load_savable_state_from_save);
 
 	if save.is_none() {
-		println!("Warning: No save specified, nothing will persist.");
-		println!("A save name can be specified using `-s <NAME>` or `--save <NAME>`.");
+		log::warn!(
+			"No save specified, nothing will persist. \
+			A save name can be specified using `-s <NAME>` or `--save <NAME>`."
+		);
 	}
 
 	let only_save_modified_chunks = saved_state
@@ -347,18 +694,54 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 			.unwrap_or_else(IdGenerator::new),
 	);
 
-	let block_type_table = Arc::new(BlockTypeTable::new());
+	let block_definitions = parse_block_definitions_file();
+	let saved_block_type_names = save.as_ref().and_then(BlockTypeTable::load_names_from_save);
+	let mut block_type_table = BlockTypeTable::new();
+	block_type_table.add_defined_block_types(&block_definitions, saved_block_type_names.as_deref());
+	if let Some(save) = save.as_ref() {
+		let remapping = saved_block_type_names
+			.as_deref()
+			.and_then(|names| BlockTypeIdRemapping::between(names, &block_type_table));
+		if let (Some(remapping), Some(state)) = (remapping, saved_state.as_mut()) {
+			match backups::create_backup(save, Some("before_migration")) {
+				Ok(backup) => log::info!(
+					"Backup \"{}\" created before migrating the save",
+					backup.name
+				),
+				Err(error) => log::error!("Failed to back up the save before migrating it: {error}"),
+			}
+			remapping.migrate_save(save, ChunkDimensions::from(state.chunk_dimensions_edge));
+			for block in [&mut state.player_held_block, &mut state.player_tool].into_iter().flatten() {
+				remapping.remap_block(block);
+			}
+			// The state is saved now too so that it does not keep the ids of the save
+			// (it would be remapped again on next load).
+			save_savable_state_to_save(save, state);
+		}
+		block_type_table.save_names(save);
+	}
+	let block_type_table = Arc::new(block_type_table);
+	let loot_tables = LootTables::load(&block_type_table);
 
 	let atlas_loaded_from_save = save.as_ref().and_then(Atlas::load_from_save);
 	let need_generation_of_the_complete_atlas = atlas_loaded_from_save.is_none();
 
-	let atlas = atlas_loaded_from_save.unwrap_or_else(Atlas::new_fast_incomplete);
+	let mut atlas = atlas_loaded_from_save.unwrap_or_else(Atlas::new_fast_incomplete);
+	atlas.paint_defined_block_textures(&block_type_table, &block_definitions, world_gen_seed);
 	let AtlasStuff {
 		atlas_texture_view_thingy,
 		atlas_texture_sampler_thingy,
 		atlas_texture,
-	} = init_atlas_stuff(Arc::clone(&device), &queue, atlas.image.as_ref());
+		normal_atlas_texture_view_thingy,
+		normal_atlas_texture,
+	} = init_atlas_stuff(
+		Arc::clone(&device),
+		&queue,
+		atlas.image.as_ref(),
+		atlas.normal_atlas_image().as_ref(),
+	);
 	let output_atlas_when_generated = output_atlas;
+	let block_type_colors = atlas.block_type_colors(&block_type_table);
 
 	let font = Arc::new(Font::font_02());
 
@@ -403,9 +786,11 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 		aspect_ratio: window_surface_config.width as f32 / window_surface_config.height as f32,
 		field_of_view_y: TAU / 4.0,
 		near_plane: 0.1,
-		far_plane: 1000.0,
+		// Reverse-Z allows the far plane to be really far without distant terrain z-fighting.
+		far_plane: 16384.0,
 	};
 	let camera_matrix_thingy = init_camera_matrix_thingy(Arc::clone(&device));
+	let map_export_camera_matrix_thingy = init_camera_matrix_thingy(Arc::clone(&device));
 
 	let camera_direction: AngularDirection = saved_state
 		.as_ref()
@@ -414,36 +799,45 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 
 	let selected_camera = WhichCameraToUse::FirstPerson;
 
-	let cursor_is_captured = true;
-	let cursor_was_actually_captured =
-		window.set_cursor_grab(winit::window::CursorGrabMode::Confined).is_ok();
-	if cursor_was_actually_captured {
-		window.set_cursor_visible(false);
-	}
+	let cursor_is_captured = grab_cursor(&window);
 
 	let targeted_face = None;
+	let targeted_entity = None;
+	let ridden_mount = None;
+	let mount_jump_requested = false;
+	let tether_held_entity = None;
 
 	let walking_forward = false;
 	let walking_backward = false;
 	let walking_leftward = false;
 	let walking_rightward = false;
+	let sneak_control_held = false;
+	let breaking_control_held = false;
+	let targeting_through_decorations = false;
 
-	let player_pos: cgmath::Point3<f32> =
-		(*saved_state.as_ref().map(|state| &state.player_pos).unwrap_or(&[0.0, 0.0, 2.0])).into();
-	let player_phys = AlignedPhysBox::new(
-		AlignedBox { pos: player_pos, dims: (0.8, 0.8, 1.8).into() },
-		cgmath::vec3(0.0, 0.0, 0.0),
-	);
 	let player_jump_manager = PlayerJumpManager::new();
+	let player_sneak_manager = PlayerSneakManager::new();
 	let enable_player_physics =
 		saved_state.as_ref().map(|state| state.enable_player_physics).unwrap_or(true);
 	let enable_display_phys_box = false;
 
 	let player_held_block = saved_state.as_ref().and_then(|state| state.player_held_block.clone());
+	let player_tool = saved_state.as_ref().and_then(|state| state.player_tool.clone());
+	let gameplay_stats =
+		saved_state.as_ref().map(|state| state.gameplay_stats.clone()).unwrap_or_default();
+	let achievements =
+		saved_state.as_ref().map(|state| state.achievements.clone()).unwrap_or_default();
+	let block_breaking = None;
+
+	let trigger_volumes =
+		saved_state.as_ref().map(|state| state.trigger_volumes.clone()).unwrap_or_default();
+	let triggers = Triggers::new(trigger_volumes);
+	let event_hooks = EventHooks::parse_event_hooks_file();
 
-	let player_health = (playing_mode == PlayingMode::Play).then_some(5);
+	let player_health = (playing_mode == PlayingMode::Play).then_some(PLAYER_MAX_HEALTH);
 
 	let last_entity_physics_start = None;
+	let parts_interpolation = PartsInterpolation::new();
 
 	let sun_position_in_sky = AngularDirection::from_angles(TAU / 16.0, TAU / 8.0);
 	let sun_light_direction_thingy = init_sun_light_direction_thingy(Arc::clone(&device));
@@ -477,30 +871,129 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 	} = init_shadow_map_stuff(Arc::clone(&device), shadow_map_cascade_count);
 
 	let z_buffer_format = wgpu::TextureFormat::Depth32Float;
+	let msaa_sample_count = if deferred_lighting {
+		if msaa > 1 {
+			log::warn!("Multisample anti-aliasing not supported by the deferred rendering path.");
+		}
+		1
+	} else {
+		let sample_count = supported_msaa_sample_count(
+			&adapter,
+			msaa,
+			&[window_surface_config.format, z_buffer_format],
+		);
+		if sample_count < msaa {
+			log::warn!("Multisample anti-aliasing sample count {msaa} not supported.");
+		}
+		sample_count
+	};
+	let mut lens_effects = LensEffects::new(
+		&device,
+		window_surface_config.format,
+		msaa_sample_count,
+		depth_of_field as f32 / 100.0,
+		motion_blur as f32 / 100.0,
+	);
+	let mut bloom = Bloom::new(
+		&device,
+		window_surface_config.format,
+		bloom_threshold as f32 / 100.0,
+		bloom_intensity as f32 / 100.0,
+	);
+	let mut color_grading = ColorGrading::new(
+		&device,
+		&queue,
+		GradingSettings {
+			tonemapper,
+			exposure: exposure as f32 / 100.0,
+			contrast: contrast as f32 / 100.0,
+			saturation: saturation as f32 / 100.0,
+		},
+	);
+	if let Some(color_lut) = color_lut {
+		if let Err(error) = color_grading.set_lut(&device, &queue, Some(&color_lut)) {
+			log::error!("{error}");
+		}
+	}
+	let render_scaling = RenderScaling::new(
+		&device,
+		window_surface_config.format,
+		&color_grading,
+		render_scale as f32 / 100.0,
+		lens_effects.is_enabled() || bloom.is_enabled() || !color_grading.is_neutral(),
+		window_surface_config.width,
+		window_surface_config.height,
+	);
+	let (scene_width, scene_height) = render_scaling.scaled_size(
+		&device,
+		window_surface_config.width,
+		window_surface_config.height,
+	);
+	bloom.resize(
+		&device,
+		render_scaling.scene_view(),
+		scene_width,
+		scene_height,
+	);
 	let z_buffer_view = make_z_buffer_texture_view(
+		&device,
+		z_buffer_format,
+		scene_width,
+		scene_height,
+		msaa_sample_count,
+	);
+	lens_effects.resize(
+		&device,
+		render_scaling.scene_view(),
+		&z_buffer_view,
+		scene_width,
+		scene_height,
+	);
+	let msaa_color_view = make_msaa_color_texture_view(
+		&device,
+		window_surface_config.format,
+		scene_width,
+		scene_height,
+		msaa_sample_count,
+	);
+	let interface_z_buffer_view = make_z_buffer_texture_view(
 		&device,
 		z_buffer_format,
 		window_surface_config.width,
 		window_surface_config.height,
+		1,
 	);
 
 	let time_beginning = std::time::Instant::now();
 	let time_from_last_iteration = std::time::Instant::now();
 
-	let control_bindings = commands::parse_control_binding_file();
+	let mut control_bindings = commands::parse_control_binding_file();
+	// The bindings of the config file come last so that they take precedence.
+	control_bindings.extend(config.control_bindings());
 	let controls_to_trigger: Vec<ControlEvent> = vec![];
 
 	let chunk_edge =
 		saved_state.as_ref().map(|state| state.chunk_dimensions_edge).unwrap_or(chunk_edge as i32);
 	let cd = ChunkDimensions::from(chunk_edge as i32);
+	let entity_culling = EntityCulling::new(cd, entity_render_distance, entity_lod_distance);
 	let already_generated_set = saved_state.as_ref().map(|state| {
 		// TODO: Avoid cloning here.
 		state.set_of_already_generated_chunks.clone()
 	});
 	let chunk_grid_shareable = ChunkGridShareable::new(ChunkGrid::new(cd, already_generated_set));
 
-	let margin_before_unloading = 60.0;
-	let loading_manager = LoadingManager::new(loading_distance, margin_before_unloading);
+	let margin_before_unloading = unloading_distance
+		.map_or(60.0, |unloading_distance| {
+			unloading_distance - loading_distance
+		})
+		.max(cd.edge as f32);
+	let loading_manager = LoadingManager::new(
+		loading_distance,
+		margin_before_unloading,
+		Duration::from_secs_f32(min_resident_time.max(0.0)),
+		!no_adaptive_loading,
+		max_fps,
+	);
 
 	let enable_world_generation = true;
 
@@ -525,15 +1018,15 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 			// This is probably not very important anyway, `available_parallelism` seems to
 			// cover many cases and has fallbacks and all.
 			let number_of_threads_auto_no_hint = 9;
-			println!(
-				"Warning: Hint about hardware capabilities coult not get obtained, \
+			log::warn!(
+				"Hint about hardware capabilities coult not get obtained, \
 				so the number of working threads cannot be chosen in a way that makes sense. \
 				Thus there will be {number_of_threads_auto_no_hint} threads, but it may be way \
 				too low or way too high depending on the number of virtual cores (\"threads\") \
 				of the hardware."
 			);
-			println!(
-				"Note: To make sure the game uses a sane number of working threads, that number \
+			log::info!(
+				"To make sure the game uses a sane number of working threads, that number \
 				can be manually specified using `-t N` or `--threads N` with N being replaced by \
 				the desired number of worker threads, which is recommended to be almost the number \
 				of virtual cores of the hardware (a little bit below, leaving 2-5 virtucal cores \
@@ -544,8 +1037,8 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 		})
 	});
 	let number_of_threads = if number_of_threads == 0 {
-		println!(
-			"Warning: Asked for 0 threads, but the game is built for multithreading \
+		log::warn!(
+			"Asked for 0 threads, but the game is built for multithreading \
 			and will not work without at least 1 worker thread. \
 			Thus there will actually be 1 thread."
 		);
@@ -554,8 +1047,8 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 		number_of_threads
 	};
 	if number_of_threads == 1 {
-		println!(
-			"Note: The experience with only 1 thread is bad, maybe try at least 4 \
+		log::info!(
+			"The experience with only 1 thread is bad, maybe try at least 4 \
 			using `-t 4` or `--threads 4`, or let the game decide in regards to the hardware \
 			by not specifying any number of threads."
 		);
@@ -568,8 +1061,10 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 	if need_generation_of_the_complete_atlas {
 		let (sender, receiver) = std::sync::mpsc::channel();
 		worker_tasks.current_tasks.push(WorkerTask::GenerateAtlas(receiver));
+		let block_type_table = Arc::clone(&block_type_table);
 		pool.enqueue_task(Box::new(move || {
-			let atlas = Atlas::new_slow_complete(world_gen_seed);
+			let mut atlas = Atlas::new_slow_complete(world_gen_seed);
+			atlas.paint_defined_block_textures(&block_type_table, &block_definitions, world_gen_seed);
 			let _ = sender.send(atlas);
 		}));
 	}
@@ -599,6 +1094,14 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 		TextureMappingAndColoringTable::new(),
 	)));
 
+	let water_reflection_stuff = init_water_reflection_stuff(
+		Arc::clone(&device),
+		window_surface_config.format,
+		z_buffer_format,
+	);
+
+	let chunk_instances = ChunkInstances::new(&device);
+
 	let rendering = rendering_init::init_rendering_stuff(
 		Arc::clone(&device),
 		AllBindingThingies {
@@ -611,17 +1114,51 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 			shadow_map_sampler_thingy: &shadow_map_sampler_thingy,
 			atlas_texture_view_thingy: &atlas_texture_view_thingy,
 			atlas_texture_sampler_thingy: &atlas_texture_sampler_thingy,
+			normal_atlas_texture_view_thingy: &normal_atlas_texture_view_thingy,
+			normal_mapping_settings_thingy: &normal_mapping_settings_thingy,
 			skybox_cubemap_texture_view_thingy: &skybox_cubemap_texture_view_thingy,
 			skybox_cubemap_texture_sampler_thingy: &skybox_cubemap_texture_sampler_thingy,
 			fog_center_position_thingy: &fog_center_position_thingy,
 			fog_inf_sup_radiuses_thingy: &fog_inf_sup_radiuses_thingy,
 			texturing_and_coloring_array_thingy: &texturing_and_coloring_array_thingy,
+			time_thingy: &time_thingy,
+			entity_interpolation_factor_thingy: &entity_interpolation_factor_thingy,
+			water_reflection_stuff: &water_reflection_stuff,
+			held_block_settings_thingy: &held_block_settings_thingy,
+			map_export_camera_matrix_thingy: &map_export_camera_matrix_thingy,
 		},
+		chunk_instances.bind_group_layout(),
 		shadow_map_format,
 		window_surface_config.format,
 		z_buffer_format,
+		msaa_sample_count,
 	);
 
+	let deferred_lighting = deferred_lighting.then(|| {
+		DeferredLighting::new(
+			&device,
+			window_surface_config.format,
+			scene_width,
+			scene_height,
+		)
+	});
+
+	// The indirect draw calls give the index of the chunk mesh as their first instance
+	// (see `ChunkInstances`).
+	let gpu_culling_support = wgpu::DownlevelFlags::COMPUTE_SHADERS
+		| wgpu::DownlevelFlags::INDIRECT_EXECUTION
+		| wgpu::DownlevelFlags::VERTEX_AND_INSTANCE_INDEX_RESPECTS_RESPECTIVE_FIRST_VALUE_IN_INDIRECT_DRAW;
+	let gpu_culling_supported =
+		adapter.get_downlevel_capabilities().flags.contains(gpu_culling_support)
+			&& device.features().contains(wgpu::Features::INDIRECT_FIRST_INSTANCE);
+	if !no_gpu_culling && !gpu_culling_supported {
+		log::warn!(
+			"Culling chunk meshes on the GPU not supported, all the chunk meshes will be drawn."
+		);
+	}
+	let chunk_mesh_culling = (!no_gpu_culling && gpu_culling_supported)
+		.then(|| ChunkMeshCulling::new(&device, &camera_matrix_thingy.resource));
+
 	let cursor_mesh = SimpleLineMesh::interface_2d_cursor(&device);
 
 	// Most useful feature in the known universe.
@@ -657,20 +1194,106 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 		.as_ref()
 		.map(|state| state.which_world_generator)
 		.unwrap_or(which_world_generator);
-	let world_generator =
-		which_world_generator.get_the_actual_generator(world_gen_seed, &block_type_table);
+	// A world generated from a heightmap keeps being generated from it when it is loaded again.
+	let heightmap = match saved_state.as_ref() {
+		Some(state) => state.heightmap.clone(),
+		None => heightmap.map(|path| HeightmapSettings {
+			path,
+			height_scale: heightmap_scale,
+			clamp: heightmap_clamp,
+		}),
+	};
+	let heightmap_world_generator =
+		heightmap.as_ref().and_then(|settings| match WorldGeneratorHeightmap::load(settings) {
+			Ok(world_generator) => {
+				log::info!(
+					"Generating the terrain from the heightmap {:?}",
+					settings.path
+				);
+				Some(world_generator)
+			},
+			Err(error) => {
+				log::error!("Failed to read the heightmap {:?}: {error}", settings.path);
+				None
+			},
+		});
+	let world_generator: Arc<dyn WorldGenerator + Sync + Send> = match heightmap_world_generator {
+		Some(world_generator) => Arc::new(world_generator),
+		None => which_world_generator.get_the_actual_generator(world_gen_seed, &block_type_table),
+	};
+	// The cache of the `which_world_generator` world must not get chunks from a heightmap.
+	let chunk_cache = chunk_cache.filter(|_| {
+		if heightmap.is_some() {
+			log::warn!("The chunk cache is not used for worlds generated from a heightmap");
+		}
+		heightmap.is_none()
+	});
+	let chunk_cache = chunk_cache
+		.and_then(|max_size_in_mib| {
+			ChunkCache::open(
+				which_world_generator,
+				world_gen_seed,
+				cd,
+				&block_type_table,
+				world_generator.settings_fingerprint(),
+				max_size_in_mib * 1024 * 1024,
+			)
+		})
+		.map(Arc::new);
+
+	let player_dims: cgmath::Vector3<f32> = (0.8, 0.8, 1.8).into();
+	let player_spawn_pos: cgmath::Point3<f32> = match saved_state.as_ref() {
+		Some(state) => state.player_spawn_pos.unwrap_or(state.player_pos).into(),
+		None => {
+			// New world, the player should not appear in the ground or above the void.
+			let spawn_ground = spawn_point::find_safe_spawn_ground(
+				&world_generator,
+				&block_type_table,
+				&id_generator,
+				cd,
+			);
+			match spawn_ground {
+				Some(ground) => {
+					ground.map(|x| x as f32) + cgmath::vec3(0.0, 0.0, 0.5 + player_dims.z / 2.0 + 0.01)
+				},
+				None => cgmath::point3(0.0, 0.0, 2.0),
+			}
+		},
+	};
+	let player_pos: cgmath::Point3<f32> = match saved_state.as_ref() {
+		Some(state) => state.player_pos.into(),
+		None => player_spawn_pos,
+	};
+	let player_phys = AlignedPhysBox::new(
+		AlignedBox { pos: player_pos, dims: player_dims },
+		cgmath::vec3(0.0, 0.0, 0.0),
+	);
 
 	let enable_display_not_surrounded_chunks_as_boxes = false;
 
 	let enable_display_chunks_with_entities_as_boxes = false;
 	let enable_display_entity_boxes = false;
+	let enable_display_entity_ids = false;
+	let entity_labels_see_through = false;
+
+	let (localization, language_was_found) = Localization::load(&language);
 
 	let mut interface = Interface::new();
 
+	if !language_was_found {
+		let mut settings = font::TextRenderingSettings::with_scale(3.0);
+		settings.color = [0.4, 0.0, 0.0];
+		interface.log_widget(Widget::new_simple_text(
+			localization.get_with_args("log.language_unknown", &[("language", &language)]),
+			settings,
+		));
+	}
+
 	if let Some(face_counter) = face_counter {
 		interface.log_widget(Widget::new_disappear_when_complete(
 			std::time::Duration::from_secs_f32(2.0),
 			Box::new(Widget::new_face_counter(
+				localization.get("skybox_generation").to_string(),
 				font::TextRenderingSettings::with_scale(3.0),
 				face_counter,
 			)),
@@ -682,27 +1305,117 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 		let save_name = &save.name;
 		let save_path = save.main_directory.display();
 		interface.log_widget(Widget::new_simple_text(
-			format!("Save \"{save_name}\""),
+			localization.get_with_args("log.save_name", &[("name", save_name)]),
 			settings.clone(),
 		));
 		interface.log_widget(Widget::new_simple_text(
-			format!("Save path \"{save_path}\""),
+			localization.get_with_args("log.save_path", &[("path", &save_path)]),
 			settings,
 		));
 	} else {
 		let mut settings = font::TextRenderingSettings::with_scale(3.0);
 		settings.color = [0.4, 0.0, 0.0];
 		interface.log_widget(Widget::new_simple_text(
-			"No save, nothing will persist".to_string(),
+			localization.get("log.no_save").to_string(),
 			settings.clone(),
 		));
 	}
 
-	let enable_interface_draw_debug_boxes = false;
+	// The pregeneration asked for on the command line is around the spawn.
+	let pregen = pregen.and_then(|radius| {
+		let possible = Pregen::check_possible(
+			save.as_ref(),
+			only_save_modified_chunks,
+			chunk_cache.as_ref(),
+		);
+		match possible {
+			Ok(()) => {
+				let spawn_block_coords = player_phys.aligned_box().pos.map(|x| x.round() as i32);
+				let pregen = Pregen::new(
+					cd.world_coords_to_containing_chunk_coords(spawn_block_coords),
+					radius,
+					cd,
+				);
+				interface.log_widget(Widget::new_simple_text(
+					localization.get_with_args(
+						"log.pregen_started",
+						&[("radius", &radius), ("chunks", &pregen.chunk_count())],
+					),
+					font::TextRenderingSettings::with_scale(3.0),
+				));
+				Some(pregen)
+			},
+			Err(error_key) => {
+				let mut settings = font::TextRenderingSettings::with_scale(3.0);
+				settings.color = [0.4, 0.0, 0.0];
+				interface.log_widget(Widget::new_simple_text(
+					localization.get(error_key).to_string(),
+					settings,
+				));
+				None
+			},
+		}
+	});
 
-	if verbose {
-		println!("End of initialization");
+	let block_picker = BlockPicker::new();
+	let chest_screen = ChestScreen::new();
+	let model_editor = ModelEditor::new();
+	let player_model = PlayerModel::new(config.appearance.clone());
+
+	let mut audio_outputs: Vec<Box<dyn AudioOutput>> = vec![];
+	if !no_audio_device {
+		match DeviceOutput::open_default_device() {
+			Ok(output) => audio_outputs.push(Box::new(output)),
+			Err(error) => {
+				log::warn!("Failed to open the audio device, the game will be silent: {error}")
+			},
+		}
+	}
+	if let Some(path) = audio_dump {
+		match WavFileOutput::create(&path) {
+			Ok(output) => audio_outputs.push(Box::new(output)),
+			Err(error) => {
+				log::warn!(
+					"Failed to create the audio dump file \"{}\": {error}",
+					path.display()
+				);
+			},
+		}
 	}
+	let audio = Audio::new(
+		AudioSettings {
+			master_volume: master_volume.clamp(0.0, 1.0),
+			sfx_volume: sfx_volume.clamp(0.0, 1.0),
+			music_volume: music_volume.clamp(0.0, 1.0),
+			enable_music: !no_music,
+		},
+		audio_outputs,
+		world_gen_seed,
+	);
+
+	let spawner = Spawner::new(!no_creature_spawning);
+	let surface_layers = SurfaceLayers::new(snowing);
+	let fire = Fire::new();
+	let signals = Signals::new();
+
+	// Recording a replay while playing one would record the replayed player, not the user.
+	let replay_recorder = record_replay.filter(|_| replay_playback.is_none()).and_then(|path| {
+		let header = ReplayHeader::new(world_gen_seed, which_world_generator, chunk_edge);
+		match ReplayRecorder::create(&path, &header) {
+			Ok(recorder) => Some(recorder),
+			Err(error) => {
+				log::warn!(
+					"Failed to create the replay file \"{}\": {error}",
+					path.display()
+				);
+				None
+			},
+		}
+	});
+
+	let enable_interface_draw_debug_boxes = false;
+
+	log::debug!("End of initialization");
 
 	Game {
 		window,
@@ -711,11 +1424,25 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 		queue,
 		window_surface_config,
 		aspect_ratio_thingy,
+		time_thingy,
+		entity_interpolation_factor_thingy,
 		z_buffer_format,
 		z_buffer_view,
+		msaa_sample_count,
+		msaa_color_view,
+		render_scaling,
+		lens_effects,
+		bloom,
+		color_grading,
+		interface_z_buffer_view,
+		deferred_lighting,
+		chunk_mesh_culling,
+		chunk_instances,
+		render_stats: RenderStats::default(),
 		camera_direction,
 		camera_settings,
 		camera_matrix_thingy,
+		map_export_camera_matrix_thingy,
 		sun_position_in_sky,
 		sun_light_direction_thingy,
 		sun_cameras,
@@ -723,14 +1450,28 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 		sun_camera_single_matrix_thingy,
 		shadow_map_cascade_view_thingies,
 		targeted_face,
+		targeted_entity,
+		ridden_mount,
+		mount_jump_requested,
+		tether_held_entity,
+		selection_tool_enabled: false,
+		selection: Selection::default(),
+		block_clipboard: None,
+		triggers,
+		event_hooks,
 		player_phys,
 		player_jump_manager,
+		player_sneak_manager,
+		unstuck_player_when_loaded: saved_state.is_some(),
 		cd,
 		chunk_grid_shareable,
 		loading_manager,
+		simulation_distance,
 		controls_to_trigger,
 		control_bindings,
+		config,
 		block_type_table,
+		loot_tables,
 		rendering,
 		close_after_one_frame,
 		cursor_mesh,
@@ -742,8 +1483,30 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 		command_confirmed,
 		world_generator,
 		which_world_generator,
+		heightmap,
 		world_gen_seed,
 		interface,
+		localization,
+		block_picker,
+		chest_screen,
+		model_editor,
+		player_model,
+		audio,
+		replay_recorder,
+		replay_playback,
+		loading_screen: Some(LoadingScreen::new()),
+		photo_mode: None,
+		screenshot_requested: false,
+		pregen,
+		save_and_quit: None,
+		backup_to_restore: None,
+		gameplay_stats,
+		stats_screen_is_open: false,
+		achievements,
+		spawner,
+		surface_layers,
+		fire,
+		signals,
 		enable_interface_draw_debug_boxes,
 		skybox_cubemap_texture,
 		fog_center_position_thingy,
@@ -751,21 +1514,29 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 		fog_inf_sup_radiuses,
 		fog_margin,
 		output_atlas_when_generated,
+		block_type_colors,
 		atlas_texture,
+		normal_atlas_texture,
 		save,
 		only_save_modified_chunks,
+		chunk_cache,
 		max_fps,
-		no_vsync,
+		supported_present_modes,
 		part_tables,
 		part_tables_for_rendering,
 		texturing_and_coloring_array_thingy,
 		texture_mapping_table,
 		player_held_block,
+		player_tool,
+		block_breaking,
 		world_time,
 		playing_mode,
 		player_health,
+		player_spawn_pos,
 		id_generator,
 		last_entity_physics_start,
+		parts_interpolation,
+		entity_culling,
 
 		worker_tasks,
 		pool,
@@ -777,17 +1548,34 @@ pub(crate) fn init_game(event_loop: &winit::event_loop::ActiveEventLoop) -> Game
 		walking_backward,
 		walking_leftward,
 		walking_rightward,
+		sneak_control_held,
+		breaking_control_held,
+		targeting_through_decorations,
 		enable_player_physics,
 		enable_world_generation,
 		selected_camera,
+		render_debug_mode: RenderDebugMode::Normal,
+		structure_debug_mesh: None,
 		enable_display_phys_box,
 		cursor_is_captured,
+		paused: false,
+		capture_cursor_when_focused: false,
 		enable_display_interface,
 		enable_display_not_surrounded_chunks_as_boxes,
 		enable_display_chunks_with_entities_as_boxes,
 		enable_display_entity_boxes,
+		enable_display_entity_ids,
+		entity_labels_see_through,
 		enable_fog,
+		enable_water_reflections: !no_water_reflections,
+		water_reflection_stuff,
+		enable_normal_mapping,
+		normal_mapping_settings_thingy,
+		held_block_view,
+		held_block_settings_thingy,
 		enable_fullscreen,
+		fullscreen_mode,
+		window_geometry,
 	}
 }
 
@@ -798,6 +1586,112 @@ impl Game {
 			.map(|x| x.round() as i32);
 		self.cd.world_coords_to_containing_chunk_coords(player_block_coords)
 	}
+
+	/// Recreates the textures that depend on the size of the window or on the render scale,
+	/// must be called when any of these change (or when the post-processing gets enabled or
+	/// disabled, as it may need the scene to be rendered to an offscreen texture).
+	pub(crate) fn recreate_screen_textures(&mut self) {
+		let window_width = self.window_surface_config.width;
+		let window_height = self.window_surface_config.height;
+		self.render_scaling.set_needs_offscreen_target(
+			self.lens_effects.is_enabled()
+				|| self.bloom.is_enabled()
+				|| !self.color_grading.is_neutral(),
+		);
+		self.render_scaling.resize(&self.device, window_width, window_height);
+		let (width, height) =
+			self.render_scaling.scaled_size(&self.device, window_width, window_height);
+		self.bloom.resize(
+			&self.device,
+			self.render_scaling.scene_view(),
+			width,
+			height,
+		);
+		self.z_buffer_view = make_z_buffer_texture_view(
+			&self.device,
+			self.z_buffer_format,
+			width,
+			height,
+			self.msaa_sample_count,
+		);
+		self.lens_effects.resize(
+			&self.device,
+			self.render_scaling.scene_view(),
+			&self.z_buffer_view,
+			width,
+			height,
+		);
+		self.msaa_color_view = make_msaa_color_texture_view(
+			&self.device,
+			self.window_surface_config.format,
+			width,
+			height,
+			self.msaa_sample_count,
+		);
+		if let Some(deferred_lighting) = self.deferred_lighting.as_mut() {
+			deferred_lighting.resize(&self.device, width, height);
+		}
+		self.interface_z_buffer_view = make_z_buffer_texture_view(
+			&self.device,
+			self.z_buffer_format,
+			window_width,
+			window_height,
+			1,
+		);
+	}
+
+	/// Falls back to a supported present mode if the asked one is not supported.
+	pub(crate) fn set_present_mode(&mut self, present_mode_choice: PresentModeChoice) {
+		self.window_surface_config.present_mode =
+			supported_present_mode(present_mode_choice, &self.supported_present_modes);
+		self.window_surface.configure(&self.device, &self.window_surface_config);
+	}
+
+	pub(crate) fn apply_fullscreen(&self) {
+		self.window.set_fullscreen(
+			self.enable_fullscreen.then(|| self.fullscreen_mode.to_winit(&self.window)),
+		);
+	}
+
+	/// Stops starting new work and begins saving everything, the game exits when it is done
+	/// (see `save_and_quit`). Returns `false` if it was already quitting.
+	pub(crate) fn start_save_and_quit(&mut self) -> bool {
+		if self.save_and_quit.is_some() {
+			return false;
+		}
+		self.save_and_quit = Some(SaveAndQuit::new());
+		self.pregen = None;
+		self.walking_forward = false;
+		self.walking_backward = false;
+		self.walking_leftward = false;
+		self.walking_rightward = false;
+		self.breaking_control_held = false;
+		true
+	}
+
+	/// Enters or leaves the photo mode, returns `true` if it entered it.
+	/// The camera starts where the first-person camera is.
+	pub(crate) fn toggle_photo_mode(&mut self) -> bool {
+		match self.photo_mode.take() {
+			Some(photo_mode) => {
+				self.camera_direction = photo_mode.player_camera_direction();
+				// The entity physics should not try to catch up with the time spent frozen.
+				self.last_entity_physics_start = None;
+				false
+			},
+			None => {
+				let aligned_box = self.player_phys.aligned_box();
+				let eye_position =
+					aligned_box.pos + cgmath::vec3(0.0, 0.0, aligned_box.dims.z / 2.0 * 0.7);
+				self.photo_mode = Some(PhotoMode::new(
+					eye_position,
+					self.camera_direction,
+					&self.camera_settings,
+				));
+				true
+			},
+		}
+	}
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
@@ -1,8 +1,14 @@
 use std::{f32::consts::TAU, sync::Arc, time::Duration};
 
 use crate::{
+	achievements::AchievementEvent,
 	atlas::RectInAtlas,
+	audio::{SoundEffect, SoundSurface},
+	backups,
+	block_picker::picked_block,
+	block_types::{BlockType, BlockTypeId, Targetability, ToolKind},
 	camera::{aspect_ratio, CameraSettings},
+	chest_screen::Slot,
 	chunk_blocks::{Block, BlockData},
 	chunks::ActionOnWorld,
 	commands::{Action, Control, ControlEvent},
@@ -10,16 +16,35 @@ use crate::{
 		iter_3d_cube_center_radius, AlignedBox, AxisOrientation, BlockCoords, ChunkCoordsSpan,
 		NonOrientedAxis, OrientedAxis, OrientedFaceCoords,
 	},
-	entities::{Entity, ForPartManipulation},
+	debug_annotations,
+	deferred_lighting::CameraForClustering,
+	display::{grab_cursor, release_cursor, supported_present_mode, PresentModeChoice},
+	entities::{Entity, ForPartManipulation, MountControl, PlayerHit, Tether, TetherAnchor},
+	events::GameEvent,
 	font,
-	game_init::{init_game, save_savable_state, Game, PlayingMode, WhichCameraToUse},
-	lang::{self, LogItem},
+	game_commands::run_qwy_script,
+	game_init::{
+		init_game, save_savable_state, Game, PlayingMode, WhichCameraToUse, PLAYER_HIT_DAMAGE,
+		PLAYER_HIT_KNOCKBACK, PLAYER_MAX_HEALTH,
+	},
+	logging,
+	photo_mode::{screenshots_are_supported, unused_screenshot_path},
+	physics::UnstuckOutcome,
+	player_model::Emote,
+	render_debug::{heatmap_tints, RenderDebugMode},
+	render_stats::format_bytes,
 	rendering,
-	rendering_init::{make_z_buffer_texture_view, update_atlas_texture, update_skybox_texture},
-	shaders::{Vector2Pod, Vector3Pod},
+	rendering_init::{update_atlas_texture, update_skybox_texture},
+	replay::PlayerInputs,
+	shaders::{
+		block::{NormalMappingSettingsPod, WaterReflectionSettingsPod},
+		Vector2Pod, Vector3Pod,
+	},
 	simple_meshes::{SimpleLineMesh, SimpleTextureMesh},
 	skybox::SkyboxMesh,
 	tasks::WorkerTask,
+	trace,
+	triggers::Occupant,
 	widgets::{InterfaceMeshesVertices, Widget, WidgetLabel},
 };
 
@@ -45,7 +70,7 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 
 	fn window_event(
 		&mut self,
-		event_loop: &winit::event_loop::ActiveEventLoop,
+		_event_loop: &winit::event_loop::ActiveEventLoop,
 		_window_id: winit::window::WindowId,
 		event: winit::event::WindowEvent,
 	) {
@@ -54,6 +79,32 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 		use winit::event::*;
 		use winit::keyboard::*;
 		match event {
+			// While saving before quitting, the inputs are ignored.
+			WindowEvent::CloseRequested
+			| WindowEvent::KeyboardInput { .. }
+			| WindowEvent::MouseInput { .. }
+				if game.save_and_quit.is_some() => {},
+
+			WindowEvent::KeyboardInput {
+				event:
+					KeyEvent {
+						logical_key: Key::Named(NamedKey::Escape),
+						state: ElementState::Pressed,
+						..
+					},
+				..
+			} if game.block_picker.is_open => close_block_picker(game),
+
+			WindowEvent::KeyboardInput {
+				event:
+					KeyEvent {
+						logical_key: Key::Named(NamedKey::Escape),
+						state: ElementState::Pressed,
+						..
+					},
+				..
+			} if game.chest_screen.is_open() => close_chest_screen(game),
+
 			WindowEvent::CloseRequested
 			| WindowEvent::KeyboardInput {
 				event:
@@ -63,15 +114,22 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 						..
 					},
 				..
-			} => event_loop.exit(),
+			} => {
+				// The game exits once everything is saved.
+				game.start_save_and_quit();
+				if game.cursor_is_captured {
+					game.cursor_is_captured = false;
+					release_cursor(&game.window);
+				}
+			},
 
 			WindowEvent::Resized(new_size) => {
 				let winit::dpi::PhysicalSize { width, height } = new_size;
 				game.window_surface_config.width = width;
 				game.window_surface_config.height = height;
 				game.window_surface.configure(&game.device, &game.window_surface_config);
-				game.z_buffer_view =
-					make_z_buffer_texture_view(&game.device, game.z_buffer_format, width, height);
+				game.recreate_screen_textures();
+				game.window_geometry.update_from_window(&game.window);
 				game.camera_settings.aspect_ratio = aspect_ratio(width, height);
 
 				game.queue.write_buffer(
@@ -81,21 +139,148 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 				);
 			},
 
+			WindowEvent::Moved(_) => game.window_geometry.update_from_window(&game.window),
+
+			WindowEvent::CursorMoved { position, .. } if game.block_picker.is_open => {
+				game
+					.block_picker
+					.set_cursor_position(cgmath::point2(position.x as f32, position.y as f32));
+			},
+
+			WindowEvent::MouseInput {
+				state: winit::event::ElementState::Pressed,
+				button: winit::event::MouseButton::Left,
+				..
+			} if game.block_picker.is_open => {
+				let window_dimensions = cgmath::vec2(
+					game.window_surface_config.width as f32,
+					game.window_surface_config.height as f32,
+				);
+				if let Some(picked_block_type_id) =
+					game.block_picker.hovered_block_type(&game.block_type_table, window_dimensions)
+				{
+					take_picked_block(game, picked_block_type_id);
+					game.audio.play(SoundEffect::UiClick);
+					close_block_picker(game);
+				}
+			},
+
+			WindowEvent::CursorMoved { position, .. } if game.chest_screen.is_open() => {
+				game
+					.chest_screen
+					.set_cursor_position(cgmath::point2(position.x as f32, position.y as f32));
+			},
+
+			WindowEvent::MouseInput {
+				state: winit::event::ElementState::Pressed,
+				button: winit::event::MouseButton::Left,
+				..
+			} if game.chest_screen.is_open() => {
+				let window_dimensions = cgmath::vec2(
+					game.window_surface_config.width as f32,
+					game.window_surface_config.height as f32,
+				);
+				if let Some(Slot::Chest(slot_index)) = game.chest_screen.hovered_slot(window_dimensions)
+				{
+					let chest = game.chest_screen.swap_with_held_block(
+						slot_index,
+						&mut game.player_held_block,
+						game.block_type_table.kinda_chest_id(),
+					);
+					if let Some((coords, chest)) = chest {
+						game.audio.play(SoundEffect::UiClick);
+						if let Some(recorder) = game.replay_recorder.as_mut() {
+							recorder.record_edit(coords, &chest);
+						}
+						game.chunk_grid_shareable.perform_now_or_later(
+							ActionOnWorld::PlaceBlockAndMaybeLoseWhatWasThereBefore {
+								block: chest,
+								coords,
+							},
+							game.save.as_ref(),
+							&game.id_generator,
+						);
+					}
+				}
+			},
+
+			WindowEvent::MouseWheel { delta, .. } if game.block_picker.is_open => {
+				let rows = match delta {
+					winit::event::MouseScrollDelta::LineDelta(_horizontal, vertical) => {
+						-vertical.signum()
+					},
+					winit::event::MouseScrollDelta::PixelDelta(position) => {
+						-(position.y as f32).signum()
+					},
+				};
+				let window_dimensions = cgmath::vec2(
+					game.window_surface_config.width as f32,
+					game.window_surface_config.height as f32,
+				);
+				game.block_picker.scroll(rows as i32, &game.block_type_table, window_dimensions);
+			},
+
 			WindowEvent::MouseInput {
 				state: winit::event::ElementState::Pressed,
 				button: winit::event::MouseButton::Left,
 				..
 			} if !game.cursor_is_captured => {
-				game.cursor_is_captured = true;
-				game.window.set_cursor_grab(winit::window::CursorGrabMode::Confined).unwrap();
-				game.window.set_cursor_visible(false);
+				game.cursor_is_captured = grab_cursor(&game.window);
+			},
+
+			WindowEvent::Focused(false) => {
+				// The cursor is given back to the other windows, and keys released while not
+				// focused will not be seen so we stop all that would be held down.
+				game.capture_cursor_when_focused = game.cursor_is_captured;
+				if game.cursor_is_captured {
+					game.cursor_is_captured = false;
+					release_cursor(&game.window);
+				}
+				game.walking_forward = false;
+				game.walking_backward = false;
+				game.walking_leftward = false;
+				game.walking_rightward = false;
+				game.sneak_control_held = false;
+				game.breaking_control_held = false;
+				game.targeting_through_decorations = false;
+				game.paused = true;
+			},
+
+			WindowEvent::Focused(true) => {
+				game.paused = false;
+				// The entity physics should not try to catch up with the time spent paused.
+				game.last_entity_physics_start = None;
+				if game.capture_cursor_when_focused
+					&& !game.block_picker.is_open
+					&& !game.chest_screen.is_open()
+				{
+					// If it fails (some X11 window managers give the focus before the window can
+					// grab the cursor) then clicking in the window will try again.
+					game.cursor_is_captured = grab_cursor(&game.window);
+				}
 			},
 
 			WindowEvent::KeyboardInput {
 				event: ref event @ KeyEvent { ref logical_key, state, repeat, .. },
 				..
 			} => {
-				if game.typing_in_command_line && state == ElementState::Pressed {
+				if game.block_picker.is_open {
+					if state == ElementState::Pressed {
+						if matches!(logical_key, Key::Named(NamedKey::Enter)) {
+							if let Some(picked_block_type_id) =
+								game.block_picker.first_filtered_block_type(&game.block_type_table)
+							{
+								take_picked_block(game, picked_block_type_id);
+								game.audio.play(SoundEffect::UiClick);
+								close_block_picker(game);
+							}
+						} else if matches!(logical_key, Key::Named(NamedKey::Backspace)) {
+							game.block_picker.erase_in_filter();
+						} else if let Key::Character(string) = logical_key {
+							game.block_picker.type_in_filter(string);
+						}
+					}
+				} else if game.typing_in_command_line && state == ElementState::Pressed {
 					if matches!(logical_key, Key::Named(NamedKey::Enter)) {
 						game.command_confirmed = true;
 						game.typing_in_command_line = false;
@@ -148,8 +333,28 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 				}
 			},
 
+			winit::event::DeviceEvent::MouseWheel { delta } if game.photo_mode.is_some() => {
+				// Wheel zooms in and out (changes the field of view of the photo mode camera).
+				let notches = match delta {
+					winit::event::MouseScrollDelta::LineDelta(_horizontal, vertical) => vertical,
+					winit::event::MouseScrollDelta::PixelDelta(position) => (position.y as f32).signum(),
+				};
+				game.photo_mode.as_mut().unwrap().zoom(notches);
+			},
+
+			winit::event::DeviceEvent::MouseWheel { delta }
+				if game.model_editor.is_open() && !game.block_picker.is_open =>
+			{
+				// Wheel edits the selected part of the model being edited.
+				let notches = match delta {
+					winit::event::MouseScrollDelta::LineDelta(_horizontal, vertical) => vertical,
+					winit::event::MouseScrollDelta::PixelDelta(position) => (position.y as f32).signum(),
+				};
+				game.model_editor.apply_wheel(notches, &game.part_tables);
+			},
+
 			winit::event::DeviceEvent::MouseWheel { delta }
-				if game.playing_mode == PlayingMode::Free =>
+				if game.playing_mode == PlayingMode::Free && !game.block_picker.is_open =>
 			{
 				// Wheel moves the player along the vertical axis.
 				// Useful when physics are disabled.
@@ -179,18 +384,46 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 	fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
 		// Here shall begin the body of the gameloop.
 		let game = self.game_opt.as_mut().unwrap();
+		let iteration_span = trace::span("game_loop_iteration");
 
 		let _time_since_beginning = game.time_beginning.elapsed();
 		let now = std::time::Instant::now();
-		let dt = now - game.time_from_last_iteration;
+		let real_dt = now - game.time_from_last_iteration;
+		// Time does not pass in the game while it is paused, in photo mode or quitting.
+		let dt = if game.paused || game.photo_mode.is_some() || game.save_and_quit.is_some() {
+			Duration::ZERO
+		} else {
+			real_dt
+		};
 		game.time_from_last_iteration = now;
 
+		// While a replay is played, the game advances by the time steps of the recording.
+		let replay_frame = game
+			.replay_playback
+			.as_mut()
+			.filter(|_| !game.paused && game.loading_screen.is_none() && game.photo_mode.is_none())
+			.and_then(|playback| playback.next_frame());
+		let dt = replay_frame.as_ref().map_or(dt, |frame| frame.dt);
+
 		game.world_time += dt;
+		if game.loading_screen.is_none() {
+			game.gameplay_stats.add_playtime(dt);
+		}
 
 		// Perform actions triggered by controls.
+		// Toggling a door or the photo mode, or handling the achievement events,
+		// needs the whole game, it is done after the controls are done with it.
+		let mut door_to_toggle = None;
+		let mut photo_mode_to_toggle = false;
+		let mut block_was_placed = false;
+		// The loot of the entities killed by the player, with where they were.
+		let mut blocks_to_drop = vec![];
 		for control_event in game.controls_to_trigger.iter() {
 			let pressed = control_event.pressed;
-			if let Some(action) = game.control_bindings.get(&control_event.control) {
+			if let Some(action) = game.control_bindings.get(&control_event.control).filter(|action| {
+				(game.replay_playback.is_none() && game.photo_mode.is_none())
+					|| !action.acts_on_the_player_or_the_world()
+			}) {
 				match (action, pressed) {
 					(Action::WalkForward, pressed) => {
 						game.walking_forward = pressed;
@@ -204,8 +437,21 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 					(Action::WalkRightward, pressed) => {
 						game.walking_rightward = pressed;
 					},
+					(Action::Sneak, pressed) => {
+						game.sneak_control_held = pressed;
+					},
+					(Action::TargetThroughDecorations, pressed) => {
+						game.targeting_through_decorations = pressed;
+					},
 					(Action::Jump, true) => {
-						game.player_jump_manager.jump(&mut game.player_phys);
+						if game.ridden_mount.is_some() {
+							game.mount_jump_requested = true;
+						} else {
+							game.player_jump_manager.jump(&mut game.player_phys);
+							if let Some(recorder) = game.replay_recorder.as_mut() {
+								recorder.record_jump();
+							}
+						}
 					},
 					(Action::TogglePhysics, true) => {
 						if game.playing_mode == PlayingMode::Free {
@@ -235,24 +481,22 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 						};
 					},
 					(Action::ToggleCursorCaptured, true) => {
-						game.cursor_is_captured = !game.cursor_is_captured;
 						if game.cursor_is_captured {
-							game.window.set_cursor_grab(winit::window::CursorGrabMode::Confined).unwrap();
-							game.window.set_cursor_visible(false);
+							game.cursor_is_captured = false;
+							release_cursor(&game.window);
 						} else {
-							game.window.set_cursor_grab(winit::window::CursorGrabMode::None).unwrap();
-							game.window.set_cursor_visible(true);
+							game.cursor_is_captured = grab_cursor(&game.window);
 						}
 					},
 					(Action::PrintCoords, true) => {
-						dbg!(game.player_phys.aligned_box().pos);
+						let player_pos = game.player_phys.aligned_box().pos;
 						let player_bottom = game.player_phys.aligned_box().pos
 							- cgmath::Vector3::<f32>::from((
 								0.0,
 								0.0,
 								game.player_phys.aligned_box().dims.z / 2.0,
 							));
-						dbg!(player_bottom);
+						log::info!("Player position: {player_pos:?}, bottom: {player_bottom:?}");
 					},
 					(Action::PlaceOrRemoveBlockUnderPlayer, true) => {
 						todo!("fix with an `ActionOnWorld`");
@@ -277,6 +521,41 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 						}
 						*/
 					},
+					(Action::ToggleSelectionTool, true) => {
+						game.selection_tool_enabled = !game.selection_tool_enabled;
+					},
+					(Action::RemoveBlockAtTarget, true) if game.selection_tool_enabled => {
+						if let Some(targeted_face) = game.targeted_face.as_ref() {
+							game.selection.first_corner = Some(targeted_face.interior_coords);
+						}
+					},
+					(Action::PlaceBlockAtTarget, true) if game.selection_tool_enabled => {
+						if let Some(targeted_face) = game.targeted_face.as_ref() {
+							game.selection.second_corner = Some(targeted_face.interior_coords);
+						}
+					},
+					// Doors are opened and closed instead of getting blocks placed against them,
+					// unless sneaking.
+					(Action::PlaceBlockAtTarget, true)
+						if is_targeting_door(game) && !game.sneak_control_held =>
+					{
+						door_to_toggle = Some(game.targeted_face.as_ref().unwrap().interior_coords);
+					},
+					// Chests are opened instead of getting blocks placed against them,
+					// unless sneaking.
+					(Action::PlaceBlockAtTarget, true)
+						if targeted_block_type_id(game)
+							== Some(game.block_type_table.kinda_chest_id())
+							&& !game.sneak_control_held =>
+					{
+						let coords = game.targeted_face.as_ref().unwrap().interior_coords;
+						let chest = game.chunk_grid_shareable.get().get_block(coords).unwrap();
+						game.chest_screen.open(coords, chest.chest_contents());
+						game.audio.play(SoundEffect::UiClick);
+						// The chest screen is used with the mouse cursor.
+						game.cursor_is_captured = false;
+						release_cursor(&game.window);
+					},
 					(Action::PlaceBlockAtTarget, true) => {
 						if let Some(targeted_face) = game.targeted_face.as_ref() {
 							let block_to_place = game.player_held_block.take().or_else(|| {
@@ -285,7 +564,41 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 									data: Some(BlockData::Text("Jaaj".to_string())),
 								})
 							});
+							// Doors are placed closed and facing away from the player.
+							// Pistons are placed retracted and facing out of the targeted face.
+							let block_to_place = block_to_place.map(|block| {
+								let block_type = game.block_type_table.get(block.type_id).unwrap();
+								if block_type.is_door() {
+									let facing =
+										OrientedAxis::closest_horizontal(game.camera_direction.to_vec3());
+									Block::new_door(block.type_id, facing, false)
+								} else if block.type_id == game.block_type_table.kinda_piston_id() {
+									let facing = OrientedAxis::from_delta(
+										targeted_face.exterior_coords() - targeted_face.interior_coords,
+									)
+									.unwrap();
+									Block::new_piston(block.type_id, facing, false)
+								} else {
+									block
+								}
+							});
 							if let Some(block_to_place) = block_to_place {
+								game.held_block_view.swing();
+								let coords = targeted_face.exterior_coords();
+								let surface = SoundSurface::of_block_type(
+									&game.block_type_table,
+									block_to_place.type_id,
+								);
+								game
+									.audio
+									.play_at(SoundEffect::BlockPlace(surface), coords.map(|x| x as f32));
+								if let Some(recorder) = game.replay_recorder.as_mut() {
+									recorder.record_edit(coords, &block_to_place);
+								}
+								if let Some(name) = game.block_type_table.name(block_to_place.type_id) {
+									game.gameplay_stats.record_block_placed(name);
+								}
+								block_was_placed = true;
 								game.chunk_grid_shareable.perform_now_or_later(
 									ActionOnWorld::PlaceBlockAndMaybeLoseWhatWasThereBefore {
 										block: block_to_place,
@@ -297,10 +610,68 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 							}
 						}
 					},
+					(Action::RemoveBlockAtTarget, false) => {
+						game.breaking_control_held = false;
+					},
 					(Action::RemoveBlockAtTarget, true) => {
-						if let Some(targeted_face) = game.targeted_face.as_ref() {
+						let targeted_hittable_entity =
+							game.targeted_entity.and_then(|(id, chunk_coords)| {
+								let entity =
+									game.chunk_grid_shareable.get().get_entity_around(id, chunk_coords)?;
+								entity.can_be_hit().then(|| {
+									let killed_kind =
+										entity.is_killed_by(PLAYER_HIT_DAMAGE).then(|| entity.kind_name());
+									(id, chunk_coords, entity.pos(), killed_kind)
+								})
+							});
+						if let Some((entity_id, chunk_coords, entity_pos, killed_kind)) =
+							targeted_hittable_entity
+						{
+							if let Some(kind_name) = killed_kind {
+								// The entity dies from this hit, it drops its loot.
+								let drops =
+									game.loot_tables.entity_drops(kind_name, &mut rand::thread_rng());
+								blocks_to_drop.extend(drops.into_iter().map(|block| (block, entity_pos)));
+							}
+							// Hitting an entity pushes it away from the player (and a bit up).
+							game.held_block_view.swing();
+							game.audio.play_at(SoundEffect::Hurt, entity_pos);
+							let mut away = game.camera_direction.to_vec3();
+							away.z = 0.0;
+							let mut knockback = cgmath::vec3(0.0, 0.0, PLAYER_HIT_KNOCKBACK / 2.0);
+							if away.magnitude() > 0.0 {
+								knockback += away.normalize_to(PLAYER_HIT_KNOCKBACK);
+							}
+							game.chunk_grid_shareable.perform_now_or_later(
+								ActionOnWorld::HitEntity {
+									entity_id,
+									chunk_coords,
+									damage: PLAYER_HIT_DAMAGE,
+									knockback,
+								},
+								game.save.as_ref(),
+								&game.id_generator,
+							);
+						} else if let Some(targeted_face) = game.targeted_face.as_ref() {
 							let block_to_place_back = game.player_held_block.take();
 							if let Some(block_to_place_back) = block_to_place_back {
+								game.held_block_view.swing();
+								let coords = targeted_face.exterior_coords();
+								let surface = SoundSurface::of_block_type(
+									&game.block_type_table,
+									block_to_place_back.type_id,
+								);
+								game
+									.audio
+									.play_at(SoundEffect::BlockPlace(surface), coords.map(|x| x as f32));
+								if let Some(recorder) = game.replay_recorder.as_mut() {
+									recorder.record_edit(coords, &block_to_place_back);
+								}
+								if let Some(name) = game.block_type_table.name(block_to_place_back.type_id)
+								{
+									game.gameplay_stats.record_block_placed(name);
+								}
+								block_was_placed = true;
 								game.chunk_grid_shareable.perform_now_or_later(
 									ActionOnWorld::PlaceBlockAndMaybeLoseWhatWasThereBefore {
 										block: block_to_place_back,
@@ -310,23 +681,11 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 									&game.id_generator,
 								);
 							} else {
-								let broken_block = game
-									.chunk_grid_shareable
-									.get()
-									.get_block(targeted_face.interior_coords)
-									.unwrap()
-									.as_owned_block();
-								game.chunk_grid_shareable.perform_now_or_later(
-									ActionOnWorld::PlaceBlockAndMaybeLoseWhatWasThereBefore {
-										block: game.block_type_table.air_id().into(),
-										coords: targeted_face.interior_coords,
-									},
-									game.save.as_ref(),
-									&game.id_generator,
-								);
-								game.player_held_block = Some(broken_block);
+								// Breaking takes some time, see `advance_block_breaking`.
+								game.breaking_control_held = true;
 							}
 						} else if let Some(block_to_throw) = game.player_held_block.take() {
+							game.held_block_view.swing();
 							let motion = game.camera_direction.to_vec3() * 0.5;
 							game.chunk_grid_shareable.perform_now_or_later(
 								ActionOnWorld::AddEntity(Entity::new_block(
@@ -340,6 +699,19 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 							);
 						}
 					},
+					(Action::SwapHeldBlockAndTool, true) => {
+						let is_holding_tool_or_nothing =
+							game.player_held_block.as_ref().is_none_or(|block| {
+								game.block_type_table.get(block.type_id).unwrap().tool().is_some()
+							});
+						if is_holding_tool_or_nothing {
+							std::mem::swap(&mut game.player_held_block, &mut game.player_tool);
+							game.audio.play(SoundEffect::UiClick);
+						}
+					},
+					(Action::ModelEditorNextPart, true) => game.model_editor.select_next_part(),
+					(Action::ModelEditorNextAxis, true) => game.model_editor.select_next_axis(),
+					(Action::ModelEditorNextMode, true) => game.model_editor.select_next_mode(),
 					(Action::ToggleDisplayInterface, true) => {
 						game.enable_display_interface = !game.enable_display_interface;
 					},
@@ -354,6 +726,9 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 					(Action::ToggleDisplayInterfaceDebugBoxes, true) => {
 						game.enable_interface_draw_debug_boxes = !game.enable_interface_draw_debug_boxes;
 					},
+					(Action::ToggleMusic, true) => {
+						game.audio.settings.enable_music = !game.audio.settings.enable_music;
+					},
 					(Action::ToggleFog, true) => {
 						game.enable_fog = !game.enable_fog;
 						let (inf, sup) = if game.enable_fog {
@@ -367,14 +742,59 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 							bytemuck::cast_slice(&[Vector2Pod { values: [inf, sup] }]),
 						);
 					},
+					(Action::ToggleWaterReflections, true) => {
+						game.enable_water_reflections = !game.enable_water_reflections;
+					},
+					(Action::TogglePhotoMode, true) => {
+						photo_mode_to_toggle = true;
+					},
+					(Action::ToggleStatsScreen, true) => {
+						game.stats_screen_is_open = !game.stats_screen_is_open;
+					},
+					(Action::EmoteWave | Action::EmoteSit | Action::EmotePoint, true) => {
+						let emote = match action {
+							Action::EmoteWave => Emote::Wave,
+							Action::EmoteSit => Emote::Sit,
+							_ => Emote::Point,
+						};
+						let player_pos = game.player_phys.aligned_box().pos;
+						game.player_model.play_emote(emote, player_pos);
+					},
+					(Action::TakeScreenshot, true) => {
+						if screenshots_are_supported(&game.window_surface_config) {
+							game.screenshot_requested = true;
+						} else {
+							log::warn!("Screenshots are not supported by this window surface");
+						}
+					},
+					(Action::ToggleNormalMapping, true) => {
+						game.enable_normal_mapping = !game.enable_normal_mapping;
+						game.queue.write_buffer(
+							&game.normal_mapping_settings_thingy.resource,
+							0,
+							bytemuck::cast_slice(&[NormalMappingSettingsPod::new(
+								game.enable_normal_mapping,
+							)]),
+						);
+					},
 					(Action::ToggleFullscreen, true) => {
 						game.enable_fullscreen = !game.enable_fullscreen;
-						game.window.set_fullscreen(
-							game.enable_fullscreen.then_some(winit::window::Fullscreen::Borderless(None)),
-						);
+						game.apply_fullscreen();
+					},
+					(Action::ToggleVsync, true) => {
+						let vsync = game.window_surface_config.present_mode == wgpu::PresentMode::Fifo;
+						let present_mode_choice = if vsync {
+							PresentModeChoice::Immediate
+						} else {
+							PresentModeChoice::Fifo
+						};
+						game.window_surface_config.present_mode =
+							supported_present_mode(present_mode_choice, &game.supported_present_modes);
+						game.window_surface.configure(&game.device, &game.window_surface_config);
 					},
 					(Action::ThrowBlock, true) => {
 						if let Some(block_to_throw) = game.player_held_block.take() {
+							game.held_block_view.swing();
 							let motion = game.camera_direction.to_vec3() * 0.5;
 							game.chunk_grid_shareable.perform_now_or_later(
 								ActionOnWorld::AddEntity(Entity::new_block(
@@ -451,11 +871,156 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 						game.enable_display_chunks_with_entities_as_boxes =
 							!game.enable_display_chunks_with_entities_as_boxes;
 					},
+					(Action::ToggleDisplayEntityIds, true) => {
+						game.enable_display_entity_ids = !game.enable_display_entity_ids;
+					},
+					(Action::ToggleEntityLabelsSeeThrough, true) => {
+						game.entity_labels_see_through = !game.entity_labels_see_through;
+					},
+					(Action::Interact, true) => {
+						if let Some((entity_id, chunk_coords)) = game.ridden_mount {
+							// Dismount, the mount gets its own control back.
+							game.chunk_grid_shareable.perform_now_or_later(
+								ActionOnWorld::SetMountControl { entity_id, chunk_coords, control: None },
+								game.save.as_ref(),
+								&game.id_generator,
+							);
+							game.ridden_mount = None;
+							game.player_phys.impose_displacement(cgmath::vec3(0.0, 0.0, 0.5));
+						} else if let Some((entity_id, chunk_coords)) = game.targeted_entity {
+							let is_mountable = game
+								.chunk_grid_shareable
+								.get()
+								.get_entity_around(entity_id, chunk_coords)
+								.is_some_and(|entity| entity.is_mountable());
+							if is_mountable {
+								game.ridden_mount = Some((entity_id, chunk_coords));
+								game.mount_jump_requested = false;
+							}
+						}
+					},
+					(Action::Tether, true) => {
+						if let Some((entity_id, chunk_coords)) = game.tether_held_entity.take() {
+							// The held entity gets tethered to what is targeted, or untied if it is
+							// itself targeted. Targeting nothing just lets go of it.
+							let (held_entity_pos, anchor, untie) = {
+								let chunk_grid = game.chunk_grid_shareable.get();
+								let held_entity_pos = chunk_grid
+									.get_entity_around(entity_id, chunk_coords)
+									.map(|entity| entity.pos());
+								let targeted_entity =
+									game.targeted_entity.and_then(|(id, chunk_coords)| {
+										chunk_grid.get_entity_around(id, chunk_coords)
+									});
+								let untie = targeted_entity.is_some_and(|entity| entity.id() == entity_id);
+								let anchor = if let Some(targeted_entity) = targeted_entity {
+									(!untie).then(|| TetherAnchor::Entity {
+										id: targeted_entity.id(),
+										last_known_pos: targeted_entity.pos(),
+									})
+								} else {
+									game.targeted_face.as_ref().map(|targeted_face| {
+										TetherAnchor::Block(targeted_face.interior_coords)
+									})
+								};
+								(held_entity_pos, anchor, untie)
+							};
+							let held_entity_pos = held_entity_pos.filter(|_| anchor.is_some() || untie);
+							if let Some(held_entity_pos) = held_entity_pos {
+								let tether = anchor.map(|anchor| Tether::new(anchor, held_entity_pos));
+								game.chunk_grid_shareable.perform_now_or_later(
+									ActionOnWorld::SetTether { entity_id, chunk_coords, tether },
+									game.save.as_ref(),
+									&game.id_generator,
+								);
+							}
+						} else {
+							game.tether_held_entity = game.targeted_entity;
+						}
+					},
+					(Action::OpenBlockPicker, true) => {
+						if game.playing_mode == PlayingMode::Free {
+							game.block_picker.open();
+							game.audio.play(SoundEffect::UiClick);
+							// The picker is used with the mouse cursor.
+							game.cursor_is_captured = false;
+							release_cursor(&game.window);
+							// Keys released while the picker is open will not be seen by the controls.
+							game.walking_forward = false;
+							game.walking_backward = false;
+							game.walking_leftward = false;
+							game.walking_rightward = false;
+							game.sneak_control_held = false;
+						}
+					},
 					(_, false) => {},
 				}
 			}
 		}
 		game.controls_to_trigger.clear();
+		if let Some(coords) = door_to_toggle {
+			toggle_door(game, coords);
+		}
+		if photo_mode_to_toggle {
+			game.toggle_photo_mode();
+		}
+		if block_was_placed {
+			handle_achievement_event(game, AchievementEvent::BlockPlaced);
+		}
+		for (block, pos) in blocks_to_drop {
+			drop_block(game, block, pos);
+		}
+
+		// The replayed player places the blocks that it placed when it was recorded.
+		if let Some(frame) = replay_frame.as_ref() {
+			for (coords, block) in frame.edits.iter() {
+				game.chunk_grid_shareable.perform_now_or_later(
+					ActionOnWorld::PlaceBlockAndMaybeLoseWhatWasThereBefore {
+						block: block.clone(),
+						coords: *coords,
+					},
+					game.save.as_ref(),
+					&game.id_generator,
+				);
+			}
+		}
+
+		advance_block_breaking(game, dt);
+
+		// The chest screen closes when its chest is gone or too far from the player.
+		if let Some(coords) = game.chest_screen.open_chest_coords() {
+			let chest_is_still_there = game
+				.chunk_grid_shareable
+				.get()
+				.get_block(coords)
+				.is_some_and(|block| block.type_id == game.block_type_table.kinda_chest_id());
+			let player_distance =
+				game.player_phys.aligned_box().pos.distance(coords.map(|x| x as f32));
+			if !chest_is_still_there || player_distance > 8.0 {
+				close_chest_screen(game);
+			}
+		}
+
+		// Loading screen, the player waits for the spawn area to be loaded.
+		if let Some(loading_screen) = game.loading_screen.as_ref() {
+			let progress =
+				loading_screen.progress(game.chunk_grid_shareable.get(), game.player_chunk());
+			if let Some(loading_duration) = loading_screen.is_done(&progress) {
+				log::info!(
+					"Spawn area loaded in {:.2} seconds",
+					loading_duration.as_secs_f32()
+				);
+				game.loading_screen = None;
+				game.interface.update_loading_screen(None, &game.localization);
+			} else {
+				game.interface.update_loading_screen(Some(&progress), &game.localization);
+			}
+		}
+
+		// Saving screen, the game exits when everything is saved.
+		if let Some(save_and_quit) = game.save_and_quit.as_ref() {
+			game.interface.update_saving_screen(Some(&save_and_quit.progress()), &game.localization);
+		}
 
 		let mut interface_meshes_vertices = InterfaceMeshesVertices::new();
 
@@ -465,7 +1030,7 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 			if let Some(general_debug_info_widget) =
 				game.interface.widget_tree_root.find_label_content(WidgetLabel::GeneralDebugInfo)
 			{
-				let fps = 1.0 / dt.as_secs_f32();
+				let fps = 1.0 / real_dt.as_secs_f32();
 				let worker_threads = game.pool.number_of_workers();
 				let chunk_count = game.chunk_grid_shareable.get().count_chunks_that_have_blocks();
 				let block_count = chunk_count * game.cd.number_of_blocks_in_a_chunk();
@@ -483,27 +1048,104 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 					game.chunk_grid_shareable.get().count_entities_and_chunks_that_have_entities();
 				let seed = game.world_gen_seed;
 				let world_time = game.world_time.as_secs_f32();
+				let draw_calls = game.render_stats.draw_calls;
+				let triangles = game.render_stats.triangles;
+				let gpu_memory = format_bytes(game.render_stats.memory.total());
+				let loading_distance = game.loading_manager.loading_distance;
+				let max_loading_distance = game
+					.loading_manager
+					.adaptive
+					.as_ref()
+					.map_or(loading_distance, |adaptive| adaptive.max_loading_distance);
 				let random_message = game.random_message;
 				let settings = font::TextRenderingSettings::with_scale(3.0);
-				let text = format!(
-					"fps: {fps:.1}\n\
-					worker threads: {worker_threads}\n\
-					chunks loaded: {chunk_count}\n\
-					blocks loaded: {block_count}\n\
-					chunks meshed: {chunk_meshed_count}\n\
-					entities: {entity_count}\n\
-					chunk with entities: {chunk_entity_count}\n\
-					player coords: {player_block_coords_str}\n\
-					seed: {seed}\n\
-					world time: {world_time:.0}s\n\
-					{random_message}"
+				let l = |key| game.localization.get(key);
+				let mut text = format!(
+					"{}: {fps:.1}\n\
+					{}: {worker_threads}\n\
+					{}: {chunk_count}\n\
+					{}: {block_count}\n\
+					{}: {chunk_meshed_count}\n\
+					{}: {entity_count}\n\
+					{}: {chunk_entity_count}\n\
+					{}: {player_block_coords_str}\n\
+					{}: {seed}\n\
+					{}: {world_time:.0}s\n\
+					{}: {draw_calls}\n\
+					{}: {triangles}\n\
+					{}: {gpu_memory}\n\
+					{}: {loading_distance:.0}/{max_loading_distance:.0}\n\
+					{random_message}",
+					l("debug_info.fps"),
+					l("debug_info.worker_threads"),
+					l("debug_info.chunks_loaded"),
+					l("debug_info.blocks_loaded"),
+					l("debug_info.chunks_meshed"),
+					l("debug_info.entities"),
+					l("debug_info.chunks_with_entities"),
+					l("debug_info.player_coords"),
+					l("debug_info.seed"),
+					l("debug_info.world_time"),
+					l("debug_info.draw_calls"),
+					l("debug_info.triangles"),
+					l("debug_info.gpu_memory"),
+					l("debug_info.loading_distance"),
 				);
+				if let RenderDebugMode::LightLevel { .. } = game.render_debug_mode {
+					let block_light =
+						game.targeted_face.as_ref().map_or("?".to_string(), |targeted_face| {
+							let coords = targeted_face.exterior_coords();
+							let [r, g, b] = game
+								.chunk_grid_shareable
+								.get()
+								.block_light_at(coords, &game.block_type_table);
+							format!("{r} {g} {b}")
+						});
+					text.push_str(&format!("\n{}: {block_light}", l("debug_info.block_light")));
+				}
+				if let Some(simulation_distance) = game.simulation_distance {
+					text.push_str(&format!(
+						"\n{}: {simulation_distance:.0}",
+						l("debug_info.simulation_distance")
+					));
+				}
 				*general_debug_info_widget = Widget::new_simple_text(text, settings);
 			}
 
 			// Health bar info.
 			game.interface.update_health_bar(game.player_health);
 
+			// Stats screen.
+			game.interface.update_stats_screen(
+				game.stats_screen_is_open.then_some((&game.gameplay_stats, &game.achievements)),
+				&game.localization,
+			);
+
+			// Selection info.
+			game.interface.update_selection_info(
+				&game.selection,
+				game.selection_tool_enabled,
+				&game.localization,
+			);
+
+			// Tool info.
+			let tool_info = game.player_tool.as_ref().map(|tool| {
+				let tool_type = game.block_type_table.get(tool.type_id).unwrap();
+				let durability = tool.as_view().tool_durability(tool_type).unwrap_or(0);
+				let max_durability = tool_type.tool_max_durability().unwrap_or(0);
+				(
+					game.block_type_table.name(tool.type_id).unwrap(),
+					durability,
+					max_durability,
+				)
+			});
+			let breaking_progress = game.block_breaking.map(|(_coords, progress)| progress);
+			game.interface.update_tool_info(tool_info, breaking_progress, &game.localization);
+
+			// Model editor info.
+			let model_editor_info_lines = game.model_editor.info_lines(&game.localization);
+			game.interface.update_model_editor_info(model_editor_info_lines);
+
 			// Item held info.
 			if let Some(item_held_widget) =
 				game.interface.widget_tree_root.find_label_content(WidgetLabel::ItemHeld)
@@ -529,23 +1171,8 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 
 			// Command line handling.
 			if game.command_confirmed {
-				let text = game.command_line_content.clone();
-
-				let mut log = lang::Log::new();
-				let res = lang::run(&text, &mut lang::Context::with_builtins(), &mut log);
-
-				let text = if let Err(error) = res {
-					format!("{error:?}")
-				} else {
-					let lines: Vec<_> = log
-						.log_items
-						.into_iter()
-						.map(|item| match item {
-							LogItem::Text(text) => text,
-						})
-						.collect();
-					lines.join("\n")
-				};
+				let code = game.command_line_content.clone();
+				let text = run_qwy_script(game, &code);
 
 				let widget = if text.is_empty() {
 					let scale = rand::thread_rng().gen_range(1..=3) as f32;
@@ -556,38 +1183,22 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 					let settings = font::TextRenderingSettings::with_scale(3.0);
 					Widget::new_simple_text(text, settings)
 				};
-
-				if let Some(Widget::List { sub_widgets, .. }) =
-					game.interface.widget_tree_root.find_label_content(WidgetLabel::LogLineList)
-				{
-					sub_widgets.push(Widget::new_smoothly_incoming(
-						cgmath::point2(0.0, 0.0),
-						std::time::Instant::now(),
-						std::time::Duration::from_secs_f32(1.0),
-						Box::new(widget),
-					));
-
-					if sub_widgets.iter().filter(|widget| !widget.is_diappearing()).count() > 25 {
-						let window_dimensions = cgmath::vec2(
-							game.window_surface_config.width as f32,
-							game.window_surface_config.height as f32,
-						);
-						sub_widgets
-							.iter_mut()
-							.find(|widget| !widget.is_diappearing())
-							.expect("we just checked that there are at least some amout of them")
-							.pop_while_smoothly_closing_space(
-								std::time::Instant::now(),
-								std::time::Duration::from_secs_f32(1.0),
-								&game.font,
-								window_dimensions,
-							);
-					}
-				}
+				push_log_line(game, widget);
 
 				game.command_line_content.clear();
 				game.command_confirmed = false;
 			}
+
+			// What got logged (by the game and by crates like Wgpu) also gets in the log lines.
+			for logged_line in logging::take_lines_for_interface() {
+				let mut settings = font::TextRenderingSettings::with_scale(2.0);
+				settings.color = match logged_line.level {
+					log::Level::Error => [0.4, 0.0, 0.0],
+					log::Level::Warn => [0.4, 0.25, 0.0],
+					log::Level::Info | log::Level::Debug | log::Level::Trace => settings.color,
+				};
+				push_log_line(game, Widget::new_simple_text(logged_line.text, settings));
+			}
 			{
 				let carret_blinking_speed = 1.5;
 				let carret_blinking_visibility_ratio = 0.5;
@@ -661,10 +1272,31 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 					window_dimensions,
 					game.enable_interface_draw_debug_boxes,
 				);
+
+				if game.block_picker.is_open {
+					game.block_picker.generate_mesh_vertices(
+						&mut interface_meshes_vertices,
+						&game.font,
+						&game.localization,
+						&game.block_type_table,
+						window_dimensions,
+					);
+				}
+				if game.chest_screen.is_open() {
+					game.chest_screen.generate_mesh_vertices(
+						&mut interface_meshes_vertices,
+						&game.font,
+						&game.localization,
+						&game.block_type_table,
+						game.player_held_block.as_ref(),
+						window_dimensions,
+					);
+				}
 			}
 		}
 
 		// Recieve task results from workers.
+		let mut world_stats_log_lines = vec![];
 		game.worker_tasks.current_tasks.retain_mut(|worker_task| {
 			let is_not_done_yet = match worker_task {
 				WorkerTask::LoadChunkBlocksAndEntities(chunk_coords, receiver) => {
@@ -700,12 +1332,14 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 					is_not_done_yet
 				},
 				WorkerTask::MeshChunk(chunk_coords, receiver) => {
-					let chunk_coords_and_result_opt =
-						receiver.try_recv().ok().map(|chunk_mesh| (*chunk_coords, chunk_mesh));
+					let chunk_coords_and_result_opt = receiver
+						.try_recv()
+						.ok()
+						.map(|(chunk_mesh, snapshot)| (*chunk_coords, chunk_mesh, snapshot));
 					let is_not_done_yet = chunk_coords_and_result_opt.is_none();
-					if let Some((chunk_coords, chunk_mesh)) = chunk_coords_and_result_opt {
+					if let Some((chunk_coords, chunk_mesh, snapshot)) = chunk_coords_and_result_opt {
 						game.chunk_grid_shareable.perform_now_or_later(
-							ActionOnWorld::AddChunkMeshingResults { chunk_coords, chunk_mesh },
+							ActionOnWorld::AddChunkMeshingResults { chunk_coords, chunk_mesh, snapshot },
 							game.save.as_ref(),
 							&game.id_generator,
 						);
@@ -741,36 +1375,145 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 					if let Some(completed_atlas) = result_opt {
 						if game.output_atlas_when_generated {
 							let path = "atlas.png";
-							println!("Outputting atlas to \"{path}\"");
+							log::info!("Outputting atlas to \"{path}\"");
 							completed_atlas.image.save_with_format(path, image::ImageFormat::Png).unwrap();
 						}
 						if let Some(save) = game.save.as_ref() {
 							completed_atlas.save(save);
 						}
+						game.block_type_colors =
+							completed_atlas.block_type_colors(&game.block_type_table);
 						update_atlas_texture(
 							&game.queue,
 							&game.atlas_texture,
 							&completed_atlas.image.as_ref(),
 						);
+						update_atlas_texture(
+							&game.queue,
+							&game.normal_atlas_texture,
+							&completed_atlas.normal_atlas_image().as_ref(),
+						);
+					}
+					is_not_done_yet
+				},
+				WorkerTask::ComputeWorldStats(receiver) => {
+					let result_opt = receiver.try_recv().ok();
+					let is_not_done_yet = result_opt.is_none();
+					if let Some(world_stats) = result_opt {
+						world_stats_log_lines.extend(world_stats.log_lines(&game.localization));
+					}
+					is_not_done_yet
+				},
+				WorkerTask::PregenerateChunk(_chunk_coords, receiver) => {
+					let is_not_done_yet = receiver.try_recv().is_err();
+					if !is_not_done_yet {
+						if let Some(pregen) = game.pregen.as_mut() {
+							pregen.handle_pregen_result();
+						}
 					}
 					is_not_done_yet
 				},
 			};
 			is_not_done_yet
 		});
+		for line in world_stats_log_lines {
+			let settings = font::TextRenderingSettings::with_scale(3.0);
+			push_log_line(game, Widget::new_simple_text(line, settings));
+		}
+		if let Some(pregen) = game.pregen.as_mut() {
+			let line = if pregen.is_finished(&game.worker_tasks) {
+				let chunk_count = pregen.chunk_count();
+				game.pregen = None;
+				Some(
+					game.localization.get_with_args("log.pregen_finished", &[("chunks", &chunk_count)]),
+				)
+			} else {
+				pregen.progress_to_report().map(|finished_count| {
+					let chunk_count = pregen.chunk_count();
+					let percent = finished_count * 100 / chunk_count.max(1);
+					game.localization.get_with_args(
+						"log.pregen_progress",
+						&[
+							("finished", &finished_count),
+							("chunks", &chunk_count),
+							("percent", &percent),
+						],
+					)
+				})
+			};
+			if let Some(line) = line {
+				let settings = font::TextRenderingSettings::with_scale(2.0);
+				push_log_line(game, Widget::new_simple_text(line, settings));
+			}
+		}
 
-		if game.chunk_grid_shareable.is_or_can_become_exclusively_owned() {
-			// If necessary, apply the results of tasks on the world and pending operations.
-			// We now have write access to the `ChunkGrid` inside until we share it again.
-			game
-				.chunk_grid_shareable
+		// The loading distance may grow as the loading catches up or shrink when frames are slow.
+		game.loading_manager.adapt_loading_distance(real_dt, &game.worker_tasks, game.cd);
+
+		if let Some(save_and_quit) = game.save_and_quit.as_mut() {
+			// While quitting, no new work is started and the world is saved a bit at a time
+			// (once the tasks that affect it are done and their results are applied).
+			if game.chunk_grid_shareable.is_or_can_become_exclusively_owned() {
+				game
+					.chunk_grid_shareable
+					.make_sure_is_owned_by_applying_pending(game.save.as_ref(), &game.id_generator);
+				save_and_quit.advance(
+					game.chunk_grid_shareable.get(),
+					&game.worker_tasks,
+					game.save.as_ref(),
+					game.only_save_modified_chunks,
+				);
+			}
+			if save_and_quit.is_done() {
+				event_loop.exit();
+			}
+		} else if game.chunk_grid_shareable.is_or_can_become_exclusively_owned() {
+			// If necessary, apply the results of tasks on the world and pending operations.
+			// We now have write access to the `ChunkGrid` inside until we share it again.
+			game
+				.chunk_grid_shareable
 				.make_sure_is_owned_by_applying_pending(game.save.as_ref(), &game.id_generator);
 
+			for player_hit in game.chunk_grid_shareable.take_player_hits() {
+				hurt_player(game, player_hit);
+			}
+
+			// The parts of the model being edited and of the player are updated here (and not by
+			// entity tasks) so that they make it to the GPU right below.
+			{
+				let part_manipulation = ForPartManipulation {
+					part_tables: Arc::clone(&game.part_tables),
+					texture_mapping_and_coloring_table: Arc::clone(&game.texture_mapping_table),
+					texturing_and_coloring_array_thingy: Arc::clone(
+						&game.texturing_and_coloring_array_thingy,
+					),
+					queue: Arc::clone(&game.queue),
+				};
+				game.model_editor.update_parts(&part_manipulation);
+				let player_is_seen = game.photo_mode.is_some()
+					|| !matches!(game.selected_camera, WhichCameraToUse::FirstPerson);
+				if player_is_seen {
+					game.player_model.update_parts(
+						game.player_phys.aligned_box().pos,
+						game.camera_direction.angle_horizontal,
+						&part_manipulation,
+					);
+				} else {
+					game.player_model.hide(&game.part_tables);
+				}
+			}
+
 			if let Some(part_tables) = Arc::get_mut(&mut game.part_tables) {
 				// The part tables were also shared to the same tasks as the world was,
 				// and here we are sure that we also have exclusive ownership of them,
-				// so we can update their new state to the GPU buffer,
-				part_tables.cup_to_gpu_update_if_required(&game.device, &game.queue);
+				// so we can update their new state to the GPU buffer
+				// (starting from where the parts are rendered now, see `PartsInterpolation`),
+				game.parts_interpolation.apply_step_if_any(part_tables);
+				part_tables.cup_to_gpu_update_if_required(
+					&game.device,
+					&game.queue,
+					&game.entity_culling,
+				);
 				// And we will keep (here in this thread) the current state of
 				// `part_tables_for_rendering` (as we are sure that they correspond to what
 				// is on the GPU now since we just synced it).
@@ -801,19 +1544,113 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 					&game.world_generator,
 					&game.block_type_table,
 					game.save.as_ref(),
+					game.chunk_cache.as_ref(),
 					&game.id_generator,
 				)
 			});
 
-			// Unload chunks that are a bit too far.
-			let unloading_distance =
-				game.loading_manager.loading_distance + game.loading_manager.margin_before_unloading;
+			// Pregenerate chunks with the workers that the loading left available.
+			if let (Some(pregen), Some(save)) = (game.pregen.as_mut(), game.save.as_ref()) {
+				game.chunk_grid_shareable.perform_now_or_dont(|chunk_grid| {
+					pregen.handle_pregen(
+						chunk_grid,
+						&mut game.worker_tasks,
+						&mut game.pool,
+						&game.world_generator,
+						&game.block_type_table,
+						save,
+						game.only_save_modified_chunks,
+						game.chunk_cache.as_ref(),
+						&game.id_generator,
+					)
+				});
+			}
+
+			// Unload chunks that are a bit too far (and that were not loaded too recently).
+			let unloading_distance = game.loading_manager.unloading_distance();
+			let min_resident_duration = game.loading_manager.min_resident_duration;
 			game.chunk_grid_shareable.perform_now_or_dont(|chunk_grid| {
 				chunk_grid.unload_chunks_too_far(
 					player_chunk,
 					unloading_distance,
+					min_resident_duration,
 					game.save.as_ref(),
 					game.only_save_modified_chunks,
+					game.chunk_cache.as_ref(),
+					&game.part_tables,
+				)
+			});
+
+			// Take the loaded and edited chunks into account for the highest opaque blocks
+			// and for the collision shapes used by physics.
+			game.chunk_grid_shareable.perform_now_or_dont(|chunk_grid| {
+				chunk_grid.update_heightmap(&game.block_type_table);
+				chunk_grid.update_collision_shapes(&game.block_type_table);
+			});
+
+			// Let snow and fallen leaves pile up on the exposed surfaces around the player.
+			let player_pos = game.player_phys.aligned_box().pos;
+			let surface_layers_edit_batch = game.surface_layers.update(
+				game.chunk_grid_shareable.get(),
+				player_pos,
+				&game.block_type_table,
+				dt,
+				game.simulation_distance,
+			);
+			if let Some(edit_batch) = surface_layers_edit_batch {
+				game.chunk_grid_shareable.perform_now_or_later(
+					ActionOnWorld::ApplyEditBatch(edit_batch),
+					game.save.as_ref(),
+					&game.id_generator,
+				);
+			}
+
+			// Let fire spread and burn flammable blocks around the player.
+			let fire_edit_batch = game.fire.update(
+				game.chunk_grid_shareable.get(),
+				player_pos,
+				&game.block_type_table,
+				dt,
+				game.simulation_distance,
+				game.surface_layers.snowing,
+			);
+			if let Some(edit_batch) = fire_edit_batch {
+				game.chunk_grid_shareable.perform_now_or_later(
+					ActionOnWorld::ApplyEditBatch(edit_batch),
+					game.save.as_ref(),
+					&game.id_generator,
+				);
+			}
+
+			// Let the signal wires, lamps and pistons react to the blocks that were set. If the world
+			// is shared then the block updates are kept for a later frame.
+			let mut block_updates = vec![];
+			game.chunk_grid_shareable.perform_now_or_dont(|chunk_grid| {
+				block_updates = chunk_grid.take_block_updates();
+			});
+			let signals_edit_batch = game.signals.update(
+				game.chunk_grid_shareable.get(),
+				block_updates,
+				&game.block_type_table,
+				game.player_phys.aligned_box(),
+			);
+			if let Some(edit_batch) = signals_edit_batch {
+				game.chunk_grid_shareable.perform_now_or_later(
+					ActionOnWorld::ApplyEditBatch(edit_batch),
+					game.save.as_ref(),
+					&game.id_generator,
+				);
+			}
+
+			// Spawn and despawn creatures around the player.
+			game.chunk_grid_shareable.perform_now_or_dont(|chunk_grid| {
+				game.spawner.update(
+					chunk_grid,
+					player_pos,
+					&game.block_type_table,
+					game.world_generator.as_ref(),
+					&game.id_generator,
+					game.save.as_ref(),
 					&game.part_tables,
 				)
 			});
@@ -822,9 +1659,13 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 		// Handle fog adjustment.
 		// Current fog fix (the fox has max radius and is not adjusting its radius),
 		// works fine when the loading of chunks is finished or almost finished.
+		// The loading distance changes by steps (when adaptive) so the fog follows it smoothly.
 		let sqrt_3 = 3.0_f32.sqrt();
 		let distance = game.loading_manager.loading_distance - game.cd.edge as f32 * sqrt_3 / 2.0;
-		game.fog_inf_sup_radiuses.1 = distance.max(game.fog_margin);
+		let target_fog_sup_radius = distance.max(game.fog_margin);
+		let approach = (real_dt.as_secs_f32() * 3.0).min(1.0);
+		game.fog_inf_sup_radiuses.1 +=
+			(target_fog_sup_radius - game.fog_inf_sup_radiuses.1) * approach;
 		game.fog_inf_sup_radiuses.0 = game.fog_inf_sup_radiuses.1 - game.fog_margin;
 		if game.enable_fog {
 			game.queue.write_buffer(
@@ -838,10 +1679,12 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 
 		// Walking.
 		let walking_vector = {
-			let walking_factor = if game.enable_player_physics {
-				12.0
-			} else {
+			let walking_factor = if !game.enable_player_physics {
 				50.0
+			} else if game.player_sneak_manager.is_sneaking() {
+				4.0
+			} else {
+				12.0
 			};
 			let walking_forward_factor =
 				if game.walking_forward { 1 } else { 0 } + if game.walking_backward { -1 } else { 0 };
@@ -860,20 +1703,186 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 			} * walking_factor)
 		};
 
+		// While a replay is played, the walking of the user moves the free camera
+		// and the replayed player gets the recorded inputs instead.
+		let replay_inputs = game
+			.replay_playback
+			.is_some()
+			.then(|| replay_frame.as_ref().map_or(PlayerInputs::none(), |frame| frame.inputs));
+		let free_camera_walking_vector = walking_vector;
+		let walking_vector = replay_inputs.map_or(walking_vector, |inputs| inputs.walking_vector);
+		let sneak_held = replay_inputs.map_or(game.sneak_control_held, |inputs| inputs.sneak);
+		if replay_inputs.is_some_and(|inputs| inputs.jump) {
+			game.player_jump_manager.jump(&mut game.player_phys);
+		}
+		let imposed_player_position = game
+			.replay_playback
+			.as_ref()
+			.zip(replay_frame.as_ref())
+			.and_then(|(playback, frame)| playback.imposed_player_position(frame));
+
 		// Player physics.
-		if game.enable_player_physics {
-			game.player_phys.apply_one_physics_step(
-				walking_vector,
-				game.chunk_grid_shareable.get(),
-				&game.block_type_table,
-				dt,
-				true,
-			);
+		let player_position_before_physics = game.player_phys.aligned_box().pos;
+		game.player_sneak_manager.manage(
+			sneak_held && game.enable_player_physics && game.ridden_mount.is_none(),
+			&mut game.player_phys,
+			game.chunk_grid_shareable.get(),
+			&game.block_type_table,
+		);
+		if game.loading_screen.is_some() {
+			// The player does not move until the spawn area is collidable, so that it does not
+			// fall through the world that is not loaded yet.
+		} else if game.photo_mode.is_some() {
+			// The player is frozen with the rest of the world, the walking moves the camera.
+		} else if let Some(position) = imposed_player_position {
+			game.player_phys.impose_position(position);
+		} else if let Some((entity_id, chunk_coords)) = game.ridden_mount {
+			// The player rides a mount, the mount moves according to the player's walking
+			// and the player just sits on it.
+			let mount = game.chunk_grid_shareable.get().get_entity_around(entity_id, chunk_coords);
+			if let Some(seat_pos) = mount.and_then(|mount| mount.seat_pos()) {
+				let chunk_coords = mount.unwrap().chunk_coords(game.cd);
+				game.ridden_mount = Some((entity_id, chunk_coords));
+				let walking = if walking_vector.magnitude() == 0.0 {
+					walking_vector
+				} else {
+					walking_vector.normalize()
+				};
+				let control = MountControl { walking, jump: game.mount_jump_requested };
+				game.mount_jump_requested = false;
+				game.chunk_grid_shareable.perform_now_or_later(
+					ActionOnWorld::SetMountControl { entity_id, chunk_coords, control: Some(control) },
+					game.save.as_ref(),
+					&game.id_generator,
+				);
+				let player_dims = game.player_phys.aligned_box().dims;
+				game
+					.player_phys
+					.impose_position(seat_pos + cgmath::vec3(0.0, 0.0, player_dims.z / 2.0));
+				game.player_phys.impose_null_horizontal_motion();
+			} else {
+				// The mount is gone (unloaded or despawned).
+				game.ridden_mount = None;
+			}
+		} else if game.enable_player_physics {
+			if game.unstuck_player_when_loaded {
+				let outcome =
+					game.player_phys.unstuck(game.chunk_grid_shareable.get(), &game.block_type_table);
+				game.unstuck_player_when_loaded = matches!(outcome, UnstuckOutcome::NotLoaded);
+			}
+			let previous_pos = game.player_phys.aligned_box().pos;
+			{
+				let _span = trace::span("player_physics");
+				game.player_phys.apply_one_physics_step(
+					walking_vector,
+					game.chunk_grid_shareable.get(),
+					&game.block_type_table,
+					dt,
+					true,
+					game.player_sneak_manager.is_sneaking(),
+				);
+			}
+			let platforms: Vec<_> = game
+				.chunk_grid_shareable
+				.get()
+				.iter_entities()
+				.filter_map(|entity| entity.platform_surface())
+				.collect();
+			game.player_phys.stand_on_platforms(&platforms, previous_pos);
 			game.player_jump_manager.manage(&game.player_phys);
 		} else {
 			game.player_phys.impose_displacement(walking_vector * dt.as_secs_f32());
 		}
 
+		let player_position = game.player_phys.aligned_box().pos;
+		if game.loading_screen.is_none() {
+			game.gameplay_stats.record_player_pos(player_position);
+			let on_sky_island = game.player_phys.on_ground_and_not_overlapping()
+				&& game.heightmap.is_none()
+				&& game.which_world_generator.makes_sky_islands();
+			handle_achievement_event(
+				game,
+				AchievementEvent::PlayerMoved { pos: player_position, on_sky_island },
+			);
+		}
+
+		// Replays.
+		if let Some((playback, frame)) = game.replay_playback.as_mut().zip(replay_frame.as_ref()) {
+			playback.compare_player_position(frame, player_position);
+		}
+		if let Some(recorder) = game
+			.replay_recorder
+			.as_mut()
+			.filter(|_| game.loading_screen.is_none() && game.photo_mode.is_none())
+		{
+			let result = recorder.record_frame(dt, walking_vector, sneak_held, player_position);
+			if let Err(error) = result {
+				log::warn!("Failed to record the replay, the recording stops: {error}");
+				game.replay_recorder = None;
+			}
+		}
+
+		// Footsteps and music mood.
+		{
+			let player_box = game.player_phys.aligned_box();
+			let feet_position = player_box.pos - cgmath::vec3(0.0, 0.0, player_box.dims.z / 2.0);
+			let block_below_coords =
+				(feet_position - cgmath::vec3(0.0, 0.0, 0.1)).map(|x| x.round() as i32);
+			let surface_below = game
+				.chunk_grid_shareable
+				.get()
+				.get_block(block_below_coords)
+				.filter(|block| !game.block_type_table.get(block.type_id).unwrap().is_air())
+				.map(|block| SoundSurface::of_block_type(&game.block_type_table, block.type_id));
+			let on_ground = surface_below
+				.filter(|_surface| {
+					game.enable_player_physics && game.player_phys.on_ground_and_not_overlapping()
+				})
+				.map(|surface| (surface, feet_position));
+			let displacement = player_box.pos - player_position_before_physics;
+			let walking_speed = if on_ground.is_some() && !dt.is_zero() {
+				displacement.truncate().magnitude() / dt.as_secs_f32()
+			} else {
+				0.0
+			};
+			game.held_block_view.advance(dt, walking_speed);
+			game.audio.player_walked(displacement, on_ground);
+			let biome = game.world_generator.biome_at(block_below_coords);
+			game.audio.update_music_mood(feet_position.z, biome, dt);
+		}
+
+		// Triggers and the event hooks that react to them.
+		{
+			let chunk_grid = game.chunk_grid_shareable.get();
+			let player_occupant = (Occupant::Player, game.player_phys.aligned_box().clone());
+			let entity_occupants = chunk_grid.iter_entities().filter_map(|entity| {
+				entity.aligned_box().map(|aligned_box| (Occupant::Entity(entity.id()), aligned_box))
+			});
+			let events = game.triggers.update(
+				std::iter::once(player_occupant).chain(entity_occupants),
+				chunk_grid,
+				&game.block_type_table,
+			);
+			for event in events {
+				match &event {
+					GameEvent::TriggerEntered { trigger, occupant } => {
+						log::debug!("{occupant:?} entered trigger {trigger:?}")
+					},
+					GameEvent::TriggerLeft { trigger, occupant } => {
+						log::debug!("{occupant:?} left trigger {trigger:?}")
+					},
+				}
+				let codes: Vec<_> = game.event_hooks.codes_to_run(&event).map(str::to_string).collect();
+				for code in codes {
+					let text = run_qwy_script(game, &code);
+					if !text.is_empty() {
+						let settings = font::TextRenderingSettings::with_scale(3.0);
+						push_log_line(game, Widget::new_simple_text(text, settings));
+					}
+				}
+			}
+		}
+
 		// Entities physics.
 		// The `dt` used by entity physics is not the `dt` of the framerate, but the `dt`
 		// of the entity physics iteration rate. If the entity physics take too long
@@ -886,32 +1895,49 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 					.clamp(Duration::from_secs_f32(0.0), Duration::from_secs_f32(1.0))
 			})
 			.unwrap_or(Duration::from_secs_f32(0.01));
-		if game.chunk_grid_shareable.if_owned_then_share_to_run_entities_tasks(
-			&mut game.worker_tasks,
-			&mut game.pool,
-			&game.block_type_table,
-			entities_physics_dt,
-			ForPartManipulation {
-				part_tables: Arc::clone(&game.part_tables),
-				texture_mapping_and_coloring_table: Arc::clone(&game.texture_mapping_table),
-				texturing_and_coloring_array_thingy: Arc::clone(
-					&game.texturing_and_coloring_array_thingy,
-				),
-				queue: Arc::clone(&game.queue),
-			},
-			&game.id_generator,
-		) {
+		let simulation_area =
+			game.simulation_distance.map(|distance| (game.player_chunk(), distance));
+		// No entity physics while paused, in photo mode or quitting.
+		if !game.paused
+			&& game.photo_mode.is_none()
+			&& game.save_and_quit.is_none()
+			&& game.chunk_grid_shareable.if_owned_then_share_to_run_entities_tasks(
+				&mut game.worker_tasks,
+				&mut game.pool,
+				&game.block_type_table,
+				entities_physics_dt,
+				simulation_area,
+				ForPartManipulation {
+					part_tables: Arc::clone(&game.part_tables),
+					texture_mapping_and_coloring_table: Arc::clone(&game.texture_mapping_table),
+					texturing_and_coloring_array_thingy: Arc::clone(
+						&game.texturing_and_coloring_array_thingy,
+					),
+					queue: Arc::clone(&game.queue),
+				},
+				&game.id_generator,
+				// Only the player that can be hurt is noticed by hostiles,
+				// and not while sneaking.
+				(game.player_health.is_some() && !game.player_sneak_manager.is_sneaking())
+					.then(|| game.player_phys.aligned_box().clone()),
+			) {
 			game.last_entity_physics_start = Some(std::time::Instant::now());
+			game.parts_interpolation.step_started(entities_physics_dt);
 		} else {
 			// TODO: Do something about this.
-			// We get here when the entity physics misses a frame.
-			// When it happens once in a while it is not a problem (not even noticeable),
-			// but when it starts to happen more frequently the entities get laggy
-			// (not slower, just less smooth) and it becomes noticeable.
+			// We get here when the entity physics misses a frame (or when paused).
+			// The entities are still rendered moving smoothly (see `PartsInterpolation`),
+			// but when it starts to happen more frequently the entities react later and later
+			// to what happens around them.
 			// We could do all of:
 			// - adjusting the number of tasks used by entity physics, and
 			// - focus on entities close to the player, reducing the frequency of entities too far.
 		}
+		game.queue.write_buffer(
+			&game.entity_interpolation_factor_thingy.resource,
+			0,
+			bytemuck::cast_slice(&[game.parts_interpolation.factor()]),
+		);
 
 		game.queue.write_buffer(
 			&game.fog_center_position_thingy.resource,
@@ -945,39 +1971,65 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 		let direction = game.camera_direction.to_vec3();
 		let mut position = first_person_camera_position;
 		let mut last_position_int: Option<BlockCoords> = None;
-		game.targeted_face = loop {
-			if first_person_camera_position.distance(position) > 6.0 {
-				break None;
-			}
-			let position_int = position.map(|x| x.round() as i32);
-			if game
-				.chunk_grid_shareable
-				.get()
-				.get_block(position_int)
-				.is_some_and(|block| !game.block_type_table.get(block.type_id).unwrap().is_air())
-			{
-				if let Some(last_position_int) = last_position_int {
-					let interior_coords = position_int;
-					let exterior_coords = last_position_int;
-					let direction_to_exterior = exterior_coords - interior_coords;
-					let direction_to_exterior = OrientedAxis::from_delta(direction_to_exterior)
-						.unwrap_or(OrientedAxis {
-							axis: NonOrientedAxis::Z,
-							orientation: AxisOrientation::Positivewards,
-						});
-					break Some(OrientedFaceCoords { interior_coords, direction_to_exterior });
-				} else {
+		game.targeted_face =
+			loop {
+				if first_person_camera_position.distance(position) > 6.0 {
 					break None;
 				}
-			}
-			if last_position_int != Some(position_int) {
-				last_position_int = Some(position_int);
-			}
-			// TODO: Advance directly to the next block with exactly the right step distance,
-			// also do not skip blocks (even a small arbitrary step can be too big sometimes).
-			// TODO: Actually, we should have proper ray casting!
-			position += direction * 0.01;
+				let position_int = position.map(|x| x.round() as i32);
+				if game.chunk_grid_shareable.get().get_block(position_int).is_some_and(|block| {
+					match game.block_type_table.get(block.type_id).unwrap().targetability() {
+						Targetability::NotTargetable => false,
+						Targetability::Decoration => !game.targeting_through_decorations,
+						Targetability::Targetable => true,
+					}
+				}) {
+					if let Some(last_position_int) = last_position_int {
+						let interior_coords = position_int;
+						let exterior_coords = last_position_int;
+						let direction_to_exterior = exterior_coords - interior_coords;
+						let direction_to_exterior = OrientedAxis::from_delta(direction_to_exterior)
+							.unwrap_or(OrientedAxis {
+								axis: NonOrientedAxis::Z,
+								orientation: AxisOrientation::Positivewards,
+							});
+						break Some(OrientedFaceCoords { interior_coords, direction_to_exterior });
+					} else {
+						break None;
+					}
+				}
+				if last_position_int != Some(position_int) {
+					last_position_int = Some(position_int);
+				}
+				// TODO: Advance directly to the next block with exactly the right step distance,
+				// also do not skip blocks (even a small arbitrary step can be too big sometimes).
+				// TODO: Actually, we should have proper ray casting!
+				position += direction * 0.01;
+			};
+
+		// Targeted entity update, it must be closer than the targeted block (if any).
+		let max_targeting_distance = if game.targeted_face.is_some() {
+			first_person_camera_position.distance(position)
+		} else {
+			6.0
 		};
+		let player_chunk = game.player_chunk();
+		game.targeted_entity = iter_3d_cube_center_radius(player_chunk, 2)
+			.filter_map(|chunk_coords| {
+				game.chunk_grid_shareable.get().iter_entities_in_chunk(chunk_coords)
+			})
+			.flatten()
+			.filter(|entity| {
+				// The mount being ridden is in the way of the view but it makes no sense to target it.
+				game.ridden_mount.is_none_or(|(ridden_mount_id, _)| entity.id() != ridden_mount_id)
+			})
+			.filter_map(|entity| {
+				let aligned_box = entity.aligned_box()?;
+				let distance = aligned_box.ray_intersection(first_person_camera_position, direction)?;
+				(distance <= max_targeting_distance).then_some((distance, entity))
+			})
+			.min_by(|(distance_a, _), (distance_b, _)| distance_a.total_cmp(distance_b))
+			.map(|(_distance, entity)| (entity.id(), entity.chunk_coords(game.cd)));
 
 		// The targeted face is hilighted by a mesh of a square around it.
 		// To avoid Z-fighting and make that mesh be more visible, we move it a little towards
@@ -996,6 +2048,14 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 			)
 		});
 
+		// The selection box is made a little bigger than the selected blocks so that its edges
+		// are not hidden by the blocks around.
+		let selection_box_mesh_opt = game.selection.displayed_span().map(|span| {
+			let mut aligned_box = span.to_aligned_box();
+			aligned_box.dims += cgmath::vec3(0.04, 0.04, 0.04);
+			SimpleLineMesh::from_aligned_box_with_color(&game.device, &aligned_box, [1.0, 0.8, 0.0])
+		});
+
 		let mut chunk_box_meshes = vec![];
 		if game.enable_display_not_surrounded_chunks_as_boxes {
 			for chunk_coords in game.chunk_grid_shareable.get().iter_loaded_chunk_coords() {
@@ -1037,6 +2097,11 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 		}
 
 		game.sun_position_in_sky.angle_horizontal = (TAU / 150.0) * game.world_time.as_secs_f32();
+		game.queue.write_buffer(
+			&game.time_thingy.resource,
+			0,
+			bytemuck::cast_slice(&[game.world_time.as_secs_f32()]),
+		);
 
 		let sun_camera_view_projection_matrices: Vec<_> = game
 			.sun_cameras
@@ -1061,8 +2126,23 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 		let (camera_view_projection_matrix, camera_position_ifany) = {
 			if matches!(game.selected_camera, WhichCameraToUse::Sun) {
 				(sun_camera_view_projection_matrices[0], None)
+			} else if let Some(photo_mode) = game.photo_mode.as_mut() {
+				let view =
+					photo_mode.advance(free_camera_walking_vector, game.camera_direction, real_dt);
+				let camera_direction_vector = view.direction.to_vec3();
+				let camera_view_projection_matrix = view
+					.camera_settings(&game.camera_settings)
+					.view_projection_matrix(view.position, camera_direction_vector, view.up_vector());
+				(camera_view_projection_matrix, Some(view.position))
 			} else {
-				let mut camera_position = first_person_camera_position;
+				let mut camera_position = match game.replay_playback.as_mut() {
+					Some(playback) => playback.move_free_camera(
+						free_camera_walking_vector,
+						real_dt,
+						first_person_camera_position,
+					),
+					None => first_person_camera_position,
+				};
 				let camera_direction_vector = game.camera_direction.to_vec3();
 				match game.selected_camera {
 					WhichCameraToUse::FirstPerson | WhichCameraToUse::Sun => {},
@@ -1090,17 +2170,146 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 				(camera_view_projection_matrix, Some(camera_position))
 			}
 		};
+
+		game.audio.set_listener(
+			camera_position_ifany.unwrap_or(first_person_camera_position),
+			game.camera_direction.to_vec3(),
+		);
+		game.audio.set_wind_altitude(first_person_camera_position.z);
+		game.audio.advance(dt);
 		game.queue.write_buffer(
 			&game.camera_matrix_thingy.resource,
 			0,
 			bytemuck::cast_slice(&[camera_view_projection_matrix]),
 		);
+		game.entity_culling.set_camera(
+			camera_view_projection_matrix.to_matrix(),
+			camera_position_ifany.unwrap_or(first_person_camera_position),
+		);
+		game.lens_effects.update_camera(
+			&game.queue,
+			camera_view_projection_matrix,
+			camera_position_ifany.unwrap_or(first_person_camera_position),
+		);
 
 		let skybox_mesh = SkyboxMesh::new(
 			&game.device,
 			camera_position_ifany.unwrap_or(point3(0.0, 0.0, 0.0)),
 		);
 
+		// The held block (or hand) is only seen from the first-person view.
+		let display_held_block = game.enable_display_interface
+			&& game.photo_mode.is_none()
+			&& matches!(game.selected_camera, WhichCameraToUse::FirstPerson);
+		if display_held_block {
+			game.held_block_view.update_mesh(
+				&game.device,
+				game.player_held_block.as_ref(),
+				&game.block_type_table,
+			);
+			game.queue.write_buffer(
+				&game.held_block_settings_thingy.resource,
+				0,
+				bytemuck::cast_slice(&[game
+					.held_block_view
+					.settings(game.camera_settings.aspect_ratio)]),
+			);
+		}
+
+		// The water reflects the world in the water plane that is closest to the camera (when seen
+		// from above), the sun view does not show the skybox so it does not show reflections either.
+		let water_reflection_camera_and_plane =
+			camera_position_ifany.filter(|_| game.enable_water_reflections).and_then(|position| {
+				let plane_z = game.chunk_grid_shareable.get().dominant_water_plane_z(position)?;
+				(plane_z < position.z).then_some((position, plane_z))
+			});
+		let water_reflection_settings = WaterReflectionSettingsPod {
+			camera_position: camera_position_ifany.unwrap_or(first_person_camera_position).into(),
+			plane_z: water_reflection_camera_and_plane.map_or(0.0, |(_, plane_z)| plane_z),
+			strength: if water_reflection_camera_and_plane.is_some() {
+				0.8
+			} else {
+				0.0
+			},
+			_padding: [0.0; 3],
+		};
+		game.queue.write_buffer(
+			&game.water_reflection_stuff.settings_thingy.resource,
+			0,
+			bytemuck::cast_slice(&[water_reflection_settings]),
+		);
+		let water_reflection_skybox_mesh =
+			water_reflection_camera_and_plane.map(|(camera_position, plane_z)| {
+				game.queue.write_buffer(
+					&game.water_reflection_stuff.reflection_camera_matrix_thingy.resource,
+					0,
+					bytemuck::cast_slice(&[
+						camera_view_projection_matrix.mirrored_across_horizontal_plane(plane_z)
+					]),
+				);
+				// The skybox is around the reflection of the camera so that its reflection
+				// is around the camera.
+				let mut mirrored_camera_position = camera_position;
+				mirrored_camera_position.z = 2.0 * plane_z - camera_position.z;
+				SkyboxMesh::new(&game.device, mirrored_camera_position)
+			});
+
+		if let Some(deferred_lighting) = game.deferred_lighting.as_ref() {
+			let chunk_grid = game.chunk_grid_shareable.get();
+			let lights: Vec<_> = chunk_grid
+				.iter_chunk_meshes()
+				.flat_map(|mesh| mesh.lights.iter().copied())
+				.chain(chunk_grid.iter_entities().filter_map(|entity| entity.light()))
+				.collect();
+			let (screen_width, screen_height) = game.render_scaling.scaled_size(
+				&game.device,
+				game.window_surface_config.width,
+				game.window_surface_config.height,
+			);
+			let camera_for_clustering = if matches!(game.selected_camera, WhichCameraToUse::Sun) {
+				CameraForClustering {
+					position: first_person_camera_position,
+					forward: -game.sun_position_in_sky.to_vec3(),
+					view_projection_matrix: None,
+					screen_width,
+					screen_height,
+				}
+			} else {
+				CameraForClustering {
+					position: camera_position_ifany.unwrap_or(first_person_camera_position),
+					forward: game.camera_direction.to_vec3(),
+					view_projection_matrix: Some(camera_view_projection_matrix.to_matrix()),
+					screen_width,
+					screen_height,
+				}
+			};
+			deferred_lighting.update_lights(&game.queue, &lights, &camera_for_clustering);
+		}
+
+		if let Some(chunk_mesh_culling) = game.chunk_mesh_culling.as_mut() {
+			chunk_mesh_culling.update_chunk_meshes(
+				&game.device,
+				&game.queue,
+				&game.camera_matrix_thingy.resource,
+				game.chunk_grid_shareable.get().iter_chunk_meshes(),
+			);
+		}
+
+		// In the chunk heatmap debug mode, each chunk mesh is tinted by how hot it is.
+		let heatmap_tints = match game.render_debug_mode {
+			RenderDebugMode::ChunkHeatmap(measure) => Some(heatmap_tints(
+				game.chunk_grid_shareable.get().iter_chunk_meshes(),
+				measure,
+			)),
+			_ => None,
+		};
+		game.chunk_instances.update_chunk_meshes(
+			&game.device,
+			&game.queue,
+			game.chunk_grid_shareable.get().iter_chunk_meshes(),
+			heatmap_tints,
+		);
+
 		let sun_light_direction = Vector3Pod { values: (-game.sun_position_in_sky.to_vec3()).into() };
 		game.queue.write_buffer(
 			&game.sun_light_direction_thingy.resource,
@@ -1108,6 +2317,98 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 			bytemuck::cast_slice(&[sun_light_direction]),
 		);
 
+		// Labels above entities, facing the camera.
+		let mut entity_labels_vertices = vec![];
+		let label_viewer_position = camera_position_ifany.unwrap_or(first_person_camera_position);
+		let label_up = game.camera_direction.add_to_vertical_angle(-TAU / 4.0).to_vec3();
+		let label_right = game.camera_direction.to_vec3().cross(label_up).normalize();
+		let label_settings = font::TextRenderingSettings {
+			color: [1.0, 1.0, 1.0],
+			..font::TextRenderingSettings::with_scale(1.0)
+		};
+		let health_bar_settings =
+			font::TextRenderingSettings { color: [0.9, 0.15, 0.1], ..label_settings.clone() };
+		for entity in game.chunk_grid_shareable.get().iter_entities() {
+			let mut label_pos = entity.label_pos();
+			if label_pos.distance(label_viewer_position) > 48.0 {
+				continue;
+			}
+			// Damaged entities show their health bar for a while, under their label.
+			if let Some(health_bar) = entity.health_bar_text() {
+				entity_labels_vertices.extend(game.font.billboard_vertices_from_text(
+					label_pos,
+					label_right,
+					label_up,
+					0.04,
+					health_bar_settings.clone(),
+					&health_bar,
+				));
+				label_pos += label_up * 0.4;
+			}
+			let Some(text) = entity.label_text(game.enable_display_entity_ids) else {
+				continue;
+			};
+			entity_labels_vertices.extend(game.font.billboard_vertices_from_text(
+				label_pos,
+				label_right,
+				label_up,
+				0.04,
+				label_settings.clone(),
+				&text,
+			));
+		}
+		// Debug annotations are displayed like the labels (see `debug_annotations`).
+		for (text_pos, annotation) in
+			debug_annotations::annotations_around(label_viewer_position, 64.0)
+		{
+			let annotation_settings =
+				font::TextRenderingSettings { color: annotation.color, ..label_settings.clone() };
+			entity_labels_vertices.extend(game.font.billboard_vertices_from_text(
+				text_pos,
+				label_right,
+				label_up,
+				0.04,
+				annotation_settings,
+				&annotation.text,
+			));
+		}
+		let entity_labels_mesh =
+			SimpleTextureMesh::from_vertices(&game.device, entity_labels_vertices);
+
+		// Tethers are ropes, and the entity that the player is about to tether is held by a rope.
+		let rope_color = [0.55, 0.4, 0.25];
+		let mut tether_lines_vertices = vec![];
+		for entity in game.chunk_grid_shareable.get().iter_entities() {
+			if let Some(tether) = entity.tether() {
+				tether_lines_vertices.extend(SimpleLineMesh::vertices_for_sagging_rope(
+					entity.pos(),
+					tether.anchor_pos(),
+					tether.length,
+					rope_color,
+				));
+			}
+		}
+		if let Some((entity_id, chunk_coords)) = game.tether_held_entity {
+			let held_entity_pos_opt = game
+				.chunk_grid_shareable
+				.get()
+				.get_entity_around(entity_id, chunk_coords)
+				.map(|entity| entity.pos());
+			if let Some(held_entity_pos) = held_entity_pos_opt {
+				let hand_pos = game.player_phys.aligned_box().pos;
+				tether_lines_vertices.extend(SimpleLineMesh::vertices_for_sagging_rope(
+					held_entity_pos,
+					hand_pos,
+					held_entity_pos.distance(hand_pos) * 1.1,
+					rope_color,
+				));
+			}
+		}
+		let tether_lines_mesh = SimpleLineMesh::from_vertices(&game.device, tether_lines_vertices);
+
+		let model_editor_gizmo_mesh =
+			SimpleLineMesh::from_vertices(&game.device, game.model_editor.gizmo_line_vertices());
+
 		let interface_simple_texture_mesh = SimpleTextureMesh::from_vertices(
 			&game.device,
 			interface_meshes_vertices.simple_texture_vertices,
@@ -1117,12 +2418,19 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 			interface_meshes_vertices.simple_line_vertices,
 		);
 
+		// The frames of a camera path being recorded, and the screenshots asked for.
+		let recorded_frame_path =
+			game.photo_mode.as_mut().and_then(|photo_mode| photo_mode.recorded_frame_path());
+		let screenshot_path = recorded_frame_path
+			.or_else(|| std::mem::take(&mut game.screenshot_requested).then(unused_screenshot_path));
+
 		let data_for_rendering = rendering::DataForRendering {
 			device: &game.device,
 			queue: &game.queue,
 			window_surface: &game.window_surface,
 			window_surface_config: &game.window_surface_config,
-			force_block_on_the_presentation: !game.no_vsync,
+			force_block_on_the_presentation: game.window_surface_config.present_mode
+				== wgpu::PresentMode::Fifo,
 			rendering: &game.rendering,
 			sun_cameras: &game.sun_cameras,
 			sun_camera_matrices_thingy: &game.sun_camera_matrices_thingy,
@@ -1130,23 +2438,54 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 			shadow_map_cascade_view_thingies: &game.shadow_map_cascade_view_thingies,
 			chunk_grid: game.chunk_grid_shareable.get(),
 			z_buffer_view: &game.z_buffer_view,
+			msaa_color_view: game.msaa_color_view.as_ref(),
+			render_scaling: &game.render_scaling,
+			lens_effects: &game.lens_effects,
+			bloom: &game.bloom,
+			color_grading: &game.color_grading,
+			interface_z_buffer_view: &game.interface_z_buffer_view,
+			deferred_lighting: game.deferred_lighting.as_ref(),
+			chunk_mesh_culling: game.chunk_mesh_culling.as_ref(),
+			chunk_instances: &game.chunk_instances,
+			water_reflection_stuff: &game.water_reflection_stuff,
+			water_reflection_skybox_mesh: water_reflection_skybox_mesh.as_ref(),
 			selected_camera: game.selected_camera,
+			render_debug_mode: game.render_debug_mode,
 			enable_display_phys_box: game.enable_display_phys_box,
 			player_box_mesh: &player_box_mesh,
 			player_blocks_box_mesh: &player_blocks_box_mesh,
 			entities_box_meshes: &entities_box_meshes,
 			chunk_with_entities_box_meshes: &chunk_with_entities_box_meshes,
-			targeted_face_mesh_opt: &targeted_face_mesh_opt,
-			enable_display_interface: game.enable_display_interface,
+			entity_labels_mesh: &entity_labels_mesh,
+			tether_lines_mesh: &tether_lines_mesh,
+			model_editor_gizmo_mesh: &model_editor_gizmo_mesh,
+			entity_labels_see_through: game.entity_labels_see_through,
+			targeted_face_mesh_opt: if game.photo_mode.is_some() {
+				&None
+			} else {
+				&targeted_face_mesh_opt
+			},
+			selection_box_mesh_opt: &selection_box_mesh_opt,
+			structure_debug_mesh: game.structure_debug_mesh.as_ref(),
+			// In photo mode the interface is hidden, but the command line is still seen when used.
+			enable_display_interface: game.enable_display_interface
+				&& (game.photo_mode.is_none() || game.typing_in_command_line),
 			chunk_box_meshes: &chunk_box_meshes,
 			skybox_mesh: &skybox_mesh,
 			typing_in_command_line: game.typing_in_command_line,
+			held_block_mesh: display_held_block.then(|| game.held_block_view.mesh()),
 			cursor_mesh: &game.cursor_mesh,
 			interface_simple_texture_mesh: &interface_simple_texture_mesh,
 			interface_simple_line_mesh: &interface_simple_line_mesh,
 			part_tables: &game.part_tables_for_rendering,
+			screenshot_path: screenshot_path.as_deref(),
 		};
-		data_for_rendering.render();
+		{
+			let _span = trace::span("render");
+			game.render_stats = data_for_rendering.render();
+		}
+		// The time spent waiting to limit the FPS is not a part of the iteration.
+		drop(iteration_span);
 
 		// Limit FPS if asked for and needed.
 		if let Some(max_fps) = game.max_fps {
@@ -1160,7 +2499,7 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 		}
 
 		if game.close_after_one_frame {
-			println!("Closing after one frame, as asked via command line arguments");
+			log::info!("Closing after one frame, as asked via command line arguments");
 			event_loop.exit();
 		}
 	}
@@ -1168,19 +2507,284 @@ impl winit::application::ApplicationHandler for StateUsedInEventLoop {
 	fn exiting(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
 		let game = self.game_opt.as_mut().unwrap();
 
+		game.window_geometry.save();
+
 		if game.save.is_some() {
 			save_savable_state(game);
-			game
-				.chunk_grid_shareable
-				.get()
-				.save_all_chunks(game.save.as_ref(), game.only_save_modified_chunks);
+			// The chunks may already have been saved by the save and quit flow, else (if the game
+			// is exiting some other way) they are all saved here right away.
+			let chunks_were_saved =
+				game.save_and_quit.as_ref().is_some_and(|save_and_quit| save_and_quit.is_done());
+			if !chunks_were_saved {
+				game
+					.chunk_grid_shareable
+					.get()
+					.save_all_chunks(game.save.as_ref(), game.only_save_modified_chunks);
+			}
+			// Everything is saved, the save can be replaced by the backup.
+			if let (Some(backup_name), Some(save)) = (game.backup_to_restore.take(), &game.save) {
+				match backups::restore_backup(save, &backup_name) {
+					Ok(()) => log::info!("The save was rolled back to the backup \"{backup_name}\""),
+					Err(error) => log::error!("Failed to restore the backup \"{backup_name}\": {error}"),
+				}
+			}
+		}
+
+		if let Some(recorder) = game.replay_recorder.take() {
+			match recorder.finish() {
+				Ok(frame_count) => log::info!("Replay recorded ({frame_count} frames)"),
+				Err(error) => log::warn!("Failed to finish the replay file: {error}"),
+			}
 		}
 
+		trace::finish_tracing();
+
 		//game.window.set_visible(false);
 		//game.pool._end_blocking();
 	}
 }
 
+fn targeted_block_type_id(game: &Game) -> Option<BlockTypeId> {
+	let targeted_face = game.targeted_face.as_ref()?;
+	let chunk_grid = game.chunk_grid_shareable.get();
+	Some(chunk_grid.get_block(targeted_face.interior_coords)?.type_id)
+}
+
+fn is_targeting_door(game: &Game) -> bool {
+	targeted_block_type_id(game).is_some_and(|id| game.block_type_table.get(id).unwrap().is_door())
+}
+
+/// The kind and tier of the tool equipped by the player, if any.
+fn equipped_tool(game: &Game) -> Option<(ToolKind, u8)> {
+	let tool = game.player_tool.as_ref()?;
+	game.block_type_table.get(tool.type_id).unwrap().tool()
+}
+
+/// Gives the player a block of the type picked in the block picker, tools get equipped.
+fn take_picked_block(game: &mut Game, type_id: BlockTypeId) {
+	let block = picked_block(&game.block_type_table, type_id);
+	if game.block_type_table.get(type_id).unwrap().tool().is_some() {
+		game.player_tool = Some(block);
+	} else {
+		game.player_held_block = Some(block);
+	}
+}
+
+/// Makes progress on breaking the targeted block while the breaking control is held down,
+/// the block breaks when the progress gets to 1 (see `Hardness`). Blocks are broken by hand or
+/// with the equipped tool, not while holding a block.
+fn advance_block_breaking(game: &mut Game, dt: Duration) {
+	let can_break = game.breaking_control_held
+		&& game.player_held_block.is_none()
+		&& !game.selection_tool_enabled
+		&& !game.chest_screen.is_open();
+	let targeted_coords = game.targeted_face.as_ref().map(|face| face.interior_coords);
+	let Some(coords) = targeted_coords.filter(|_| can_break) else {
+		game.block_breaking = None;
+		return;
+	};
+	let Some(block) =
+		game.chunk_grid_shareable.get().get_block(coords).map(|block| block.as_owned_block())
+	else {
+		game.block_breaking = None;
+		return;
+	};
+
+	let tool = equipped_tool(game);
+	let hardness = game.block_type_table.hardness(block.type_id);
+	let progress = match game.block_breaking {
+		Some((breaking_coords, progress)) if breaking_coords == coords => progress,
+		_ => {
+			game.held_block_view.swing();
+			0.0
+		},
+	};
+	let breaking_time = hardness.breaking_time_with(tool);
+	let progress = if breaking_time <= 0.0 {
+		1.0
+	} else {
+		progress + dt.as_secs_f32() / breaking_time
+	};
+	if progress < 1.0 {
+		game.block_breaking = Some((coords, progress));
+		return;
+	}
+	game.block_breaking = None;
+
+	game.held_block_view.swing();
+	let surface = SoundSurface::of_block_type(&game.block_type_table, block.type_id);
+	game.audio.play_at(SoundEffect::BlockBreak(surface), coords.map(|x| x as f32));
+	let air: Block = game.block_type_table.air_id().into();
+	if let Some(recorder) = game.replay_recorder.as_mut() {
+		recorder.record_edit(coords, &air);
+	}
+	if let Some(name) = game.block_type_table.name(block.type_id) {
+		game.gameplay_stats.record_block_broken(name);
+	}
+	handle_achievement_event(game, AchievementEvent::BlockBroken);
+	game.chunk_grid_shareable.perform_now_or_later(
+		ActionOnWorld::PlaceBlockAndMaybeLoseWhatWasThereBefore { block: air, coords },
+		game.save.as_ref(),
+		&game.id_generator,
+	);
+	if hardness.is_harvested_with(tool) {
+		// The hand holds the first drop, the other drops fall where the block was.
+		let mut drops = game.loot_tables.block_drops(block, &mut rand::thread_rng()).into_iter();
+		game.player_held_block = drops.next();
+		for drop in drops {
+			drop_block(game, drop, coords.map(|x| x as f32));
+		}
+	}
+
+	// The tool wears out and breaks when its durability gets to 0.
+	if tool.is_some_and(|tool| hardness.wears(tool)) {
+		let tool_block = game.player_tool.take().unwrap();
+		let tool_type = game.block_type_table.get(tool_block.type_id).unwrap();
+		let durability = tool_block.as_view().tool_durability(tool_type).unwrap_or(0);
+		if durability > 1 {
+			game.player_tool = Some(Block::new_tool(tool_block.type_id, durability - 1));
+		} else {
+			let player_pos = game.player_phys.aligned_box().pos;
+			game.audio.play_at(SoundEffect::BlockBreak(SoundSurface::Wood), player_pos);
+		}
+	}
+}
+
+/// Makes the block fall from the given position (as a block entity), with a little random
+/// motion so that several dropped blocks scatter a bit.
+fn drop_block(game: &mut Game, block: Block, pos: cgmath::Point3<f32>) {
+	let mut rng = rand::thread_rng();
+	let motion = cgmath::vec3(rng.gen_range(-0.05..0.05), rng.gen_range(-0.05..0.05), 0.1);
+	game.chunk_grid_shareable.perform_now_or_later(
+		ActionOnWorld::AddEntity(Entity::new_block(&game.id_generator, block, pos, motion)),
+		game.save.as_ref(),
+		&game.id_generator,
+	);
+}
+
+fn close_chest_screen(game: &mut Game) {
+	game.chest_screen.close();
+	game.cursor_is_captured = grab_cursor(&game.window);
+}
+
+/// Opens the door or trapdoor at the given coords if it is closed, and closes it if it is open.
+/// The doors that are stacked right above and below it (of the same type, facing the same side
+/// and in the same state) are opened or closed with it, so that stacked doors make a taller door.
+fn toggle_door(game: &mut Game, coords: BlockCoords) {
+	let chunk_grid = game.chunk_grid_shareable.get();
+	let Some(block) = chunk_grid.get_block(coords) else {
+		return;
+	};
+	let type_id = block.type_id;
+	let (facing, open) = block.door_state();
+	let mut door_coords = vec![coords];
+	if matches!(
+		game.block_type_table.get(type_id).unwrap(),
+		BlockType::Door { .. }
+	) {
+		let is_same_door = |coords: BlockCoords| {
+			chunk_grid
+				.get_block(coords)
+				.is_some_and(|block| block.type_id == type_id && block.door_state() == (facing, open))
+		};
+		for direction in [OrientedAxis::Z_PLUS, OrientedAxis::Z_MINUS] {
+			let mut stacked_coords = coords + direction.delta();
+			while is_same_door(stacked_coords) {
+				door_coords.push(stacked_coords);
+				stacked_coords += direction.delta();
+			}
+		}
+	}
+	let mut edit_batch = chunk_grid.edit_batch();
+	for coords in door_coords {
+		let door = Block::new_door(type_id, facing, !open);
+		if let Some(recorder) = game.replay_recorder.as_mut() {
+			recorder.record_edit(coords, &door);
+		}
+		edit_batch.set(coords, door);
+	}
+	game.held_block_view.swing();
+	let surface = SoundSurface::of_block_type(&game.block_type_table, type_id);
+	game.audio.play_at(SoundEffect::BlockPlace(surface), coords.map(|x| x as f32));
+	game.chunk_grid_shareable.perform_now_or_later(
+		ActionOnWorld::ApplyEditBatch(edit_batch),
+		game.save.as_ref(),
+		&game.id_generator,
+	);
+}
+
+fn close_block_picker(game: &mut Game) {
+	game.block_picker.close();
+	game.cursor_is_captured = grab_cursor(&game.window);
+}
+
+/// Unlocks the achievements triggered by the event, with a toast for each.
+/// Applies the hit of an entity on the player, who respawns if it takes the last of its health.
+fn hurt_player(game: &mut Game, player_hit: PlayerHit) {
+	let Some(health) = game.player_health.as_mut() else {
+		return;
+	};
+	*health = health.saturating_sub(player_hit.damage);
+	game.audio.play(SoundEffect::Hurt);
+	// Pushed away from the attacker.
+	let mut away = game.player_phys.aligned_box().pos - player_hit.attacker_pos;
+	away.z = 0.0;
+	if away.magnitude() > 0.0 {
+		game.player_phys.add_motion(away.normalize() * 0.1 + cgmath::vec3(0.0, 0.0, 0.05));
+	}
+	if *health == 0 {
+		*health = PLAYER_MAX_HEALTH;
+		game.gameplay_stats.record_death();
+		game.player_phys.impose_position(game.player_spawn_pos);
+		game.player_phys.impose_null_horizontal_motion();
+		log::info!("The player died and respawned");
+		let settings = font::TextRenderingSettings::with_scale(3.0);
+		let text = game.localization.get("log.player_died").to_string();
+		push_log_line(game, Widget::new_simple_text(text, settings));
+	}
+}
+
+fn handle_achievement_event(game: &mut Game, event: AchievementEvent) {
+	for achievement in game.achievements.handle_event(&event) {
+		let name = achievement.localized_name(&game.localization);
+		log::info!("Achievement unlocked: {name}");
+		let title = game.localization.get("toast.achievement_unlocked").to_string();
+		game.interface.push_toast(title, name);
+		game.audio.play(SoundEffect::UiClick);
+	}
+}
+
+/// Adds a line at the bottom of the log lines, the oldest line goes away if there are too many.
+fn push_log_line(game: &mut Game, widget: Widget) {
+	if let Some(Widget::List { sub_widgets, .. }) =
+		game.interface.widget_tree_root.find_label_content(WidgetLabel::LogLineList)
+	{
+		sub_widgets.push(Widget::new_smoothly_incoming(
+			cgmath::point2(0.0, 0.0),
+			std::time::Instant::now(),
+			std::time::Duration::from_secs_f32(1.0),
+			Box::new(widget),
+		));
+
+		if sub_widgets.iter().filter(|widget| !widget.is_diappearing()).count() > 25 {
+			let window_dimensions = cgmath::vec2(
+				game.window_surface_config.width as f32,
+				game.window_surface_config.height as f32,
+			);
+			sub_widgets
+				.iter_mut()
+				.find(|widget| !widget.is_diappearing())
+				.expect("we just checked that there are at least some amout of them")
+				.pop_while_smoothly_closing_space(
+					std::time::Instant::now(),
+					std::time::Duration::from_secs_f32(1.0),
+					&game.font,
+					window_dimensions,
+				);
+		}
+	}
+}
+
 /// Initializes the game and runs the main game loop.
 pub fn init_and_run_game_loop() {
 	let event_loop = winit::event_loop::EventLoop::new().unwrap();
@@ -0,0 +1,118 @@
+//! Gameplay statistics, what the player did in a world (time played, blocks placed and broken,
+//! distance traveled, deaths). They are kept in the save and displayed on the stats screen.
+//!
+//! Not to be confused with the world stats (see `world_stats`) that are about the content
+//! of the world.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::localization::Localization;
+
+/// A player that moves further than that in one frame is teleported and does not travel.
+const MAX_TRAVELED_DISTANCE_PER_FRAME: f32 = 20.0;
+/// How many block types are displayed (the most placed and the most broken) on the stats screen.
+const DISPLAYED_BLOCK_TYPE_COUNT: usize = 5;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(crate) struct GameplayStats {
+	/// Time played, not counting the time when the game is paused or loading.
+	playtime: Duration,
+	/// The counts are by block type name, so that the stats survive block type id changes.
+	blocks_placed: BTreeMap<String, u64>,
+	blocks_broken: BTreeMap<String, u64>,
+	/// In blocks.
+	distance_traveled: f64,
+	deaths: u32,
+	/// Where the player was last frame, to measure the distance traveled since then.
+	#[serde(skip)]
+	last_player_pos: Option<cgmath::Point3<f32>>,
+}
+
+impl GameplayStats {
+	pub(crate) fn add_playtime(&mut self, dt: Duration) {
+		self.playtime += dt;
+	}
+
+	pub(crate) fn record_block_placed(&mut self, block_type_name: &str) {
+		*self.blocks_placed.entry(block_type_name.to_string()).or_default() += 1;
+	}
+
+	pub(crate) fn record_block_broken(&mut self, block_type_name: &str) {
+		*self.blocks_broken.entry(block_type_name.to_string()).or_default() += 1;
+	}
+
+	pub(crate) fn record_death(&mut self) {
+		self.deaths += 1;
+	}
+
+	/// Is to be called every frame with the position of the player.
+	pub(crate) fn record_player_pos(&mut self, player_pos: cgmath::Point3<f32>) {
+		if let Some(last_player_pos) = self.last_player_pos {
+			let distance = cgmath::MetricSpace::distance(last_player_pos, player_pos);
+			if distance <= MAX_TRAVELED_DISTANCE_PER_FRAME {
+				self.distance_traveled += distance as f64;
+			}
+		}
+		self.last_player_pos = Some(player_pos);
+	}
+
+	/// The lines of text of the stats screen.
+	pub(crate) fn lines(&self, localization: &Localization) -> Vec<String> {
+		let seconds = self.playtime.as_secs();
+		let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+		let playtime = format!("{hours}:{minutes:02}:{seconds:02}");
+		let distance = format!("{:.0}", self.distance_traveled);
+		let placed: u64 = self.blocks_placed.values().sum();
+		let broken: u64 = self.blocks_broken.values().sum();
+		let mut lines = vec![
+			localization.get_with_args("stats_screen.playtime", &[("time", &playtime)]),
+			localization.get_with_args("stats_screen.distance", &[("distance", &distance)]),
+			localization.get_with_args("stats_screen.deaths", &[("deaths", &self.deaths)]),
+			localization.get_with_args("stats_screen.blocks_placed", &[("count", &placed)]),
+		];
+		lines.extend(most_common_block_types(&self.blocks_placed));
+		lines.push(localization.get_with_args("stats_screen.blocks_broken", &[("count", &broken)]));
+		lines.extend(most_common_block_types(&self.blocks_broken));
+		lines
+	}
+}
+
+/// Indented lines like `  stone: 12`, the most common first.
+fn most_common_block_types(counts: &BTreeMap<String, u64>) -> Vec<String> {
+	let mut counts: Vec<_> = counts.iter().collect();
+	counts.sort_by(|(_, count_a), (_, count_b)| count_b.cmp(count_a));
+	counts
+		.into_iter()
+		.take(DISPLAYED_BLOCK_TYPE_COUNT)
+		.map(|(name, count)| format!("  {name}: {count}"))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn teleports_are_not_traveled() {
+		let mut stats = GameplayStats::default();
+		stats.record_player_pos(cgmath::point3(0.0, 0.0, 0.0));
+		stats.record_player_pos(cgmath::point3(3.0, 4.0, 0.0));
+		stats.record_player_pos(cgmath::point3(1000.0, 4.0, 0.0));
+		stats.record_player_pos(cgmath::point3(1000.0, 5.0, 0.0));
+		assert_eq!(stats.distance_traveled, 6.0);
+	}
+
+	#[test]
+	fn most_common_block_types_come_first() {
+		let mut stats = GameplayStats::default();
+		for name in ["dirt", "stone", "stone", "grass", "stone", "dirt"] {
+			stats.record_block_broken(name);
+		}
+		assert_eq!(
+			most_common_block_types(&stats.blocks_broken),
+			vec!["  stone: 3", "  dirt: 2", "  grass: 1"]
+		);
+	}
+}
@@ -0,0 +1,128 @@
+//! The highest opaque block of each column of blocks of the loaded world, kept up to date as
+//! chunks are loaded, unloaded and edited, so that finding out if something is under the open sky
+//! does not require looking at all the blocks above it.
+//!
+//! Only the loaded chunks are known, so the highest opaque block of a column is the highest one
+//! among the loaded chunks, there may be higher ones in the chunks that are not loaded.
+
+use std::collections::BTreeSet;
+
+use fxhash::{FxHashMap, FxHashSet};
+
+use crate::{
+	block_types::BlockTypeTable,
+	chunk_blocks::ChunkBlocks,
+	coords::{ChunkCoords, ChunkDimensions},
+};
+
+/// For each column of blocks of a chunk, the z coordinate of its highest opaque block in the chunk.
+struct ChunkColumnTops {
+	/// Indexed by x then y (relative to the chunk), `None` for columns with no opaque block.
+	tops: Vec<Option<i32>>,
+}
+
+impl ChunkColumnTops {
+	/// Returns `None` if the chunk does not have any opaque block.
+	fn compute(
+		chunk_blocks: &ChunkBlocks,
+		block_type_table: &BlockTypeTable,
+	) -> Option<ChunkColumnTops> {
+		let has_opaque_blocks = chunk_blocks
+			.block_type_ids_in_palette()
+			.any(|id| block_type_table.get(id).unwrap().is_opaque());
+		if !has_opaque_blocks {
+			// Most chunks are only air (or only air and things like grass blades and water).
+			return None;
+		}
+		let edge = chunk_blocks.coords_span.cd.edge;
+		let inf = chunk_blocks.coords_span.block_coords_inf();
+		let mut tops = Vec::with_capacity((edge * edge) as usize);
+		for dx in 0..edge {
+			for dy in 0..edge {
+				let top = (0..edge).rev().map(|dz| inf + cgmath::vec3(dx, dy, dz)).find(|&coords| {
+					let type_id = chunk_blocks.get(coords).unwrap().type_id;
+					block_type_table.get(type_id).unwrap().is_opaque()
+				});
+				tops.push(top.map(|coords| coords.z));
+			}
+		}
+		Some(ChunkColumnTops { tops })
+	}
+}
+
+pub(crate) struct Heightmap {
+	cd: ChunkDimensions,
+	/// The loaded chunks that have opaque blocks.
+	chunk_tops: FxHashMap<ChunkCoords, ChunkColumnTops>,
+	/// For each column of chunks (given by the x and y of its chunks), the z coordinates of its
+	/// chunks that are in `chunk_tops`.
+	chunk_columns: FxHashMap<(i32, i32), BTreeSet<i32>>,
+	/// The chunks that were loaded or edited since the last `update`.
+	stale_chunks: FxHashSet<ChunkCoords>,
+}
+
+impl Heightmap {
+	pub(crate) fn new(cd: ChunkDimensions) -> Heightmap {
+		Heightmap {
+			cd,
+			chunk_tops: FxHashMap::default(),
+			chunk_columns: FxHashMap::default(),
+			stale_chunks: FxHashSet::default(),
+		}
+	}
+
+	/// The chunk was loaded or had some of its blocks changed, it will be looked at in the next
+	/// call to `update`.
+	pub(crate) fn mark_stale(&mut self, chunk_coords: ChunkCoords) {
+		self.stale_chunks.insert(chunk_coords);
+	}
+
+	pub(crate) fn remove_chunk(&mut self, chunk_coords: ChunkCoords) {
+		self.stale_chunks.remove(&chunk_coords);
+		if self.chunk_tops.remove(&chunk_coords).is_some() {
+			let column = (chunk_coords.x, chunk_coords.y);
+			let chunk_zs = self.chunk_columns.get_mut(&column).unwrap();
+			chunk_zs.remove(&chunk_coords.z);
+			if chunk_zs.is_empty() {
+				self.chunk_columns.remove(&column);
+			}
+		}
+	}
+
+	/// Recomputes the column tops of the stale chunks (see `mark_stale`), `get_chunk_blocks` gives
+	/// the blocks of a chunk if it is (still) loaded.
+	pub(crate) fn update<'a>(
+		&mut self,
+		get_chunk_blocks: impl Fn(ChunkCoords) -> Option<&'a ChunkBlocks>,
+		block_type_table: &BlockTypeTable,
+	) {
+		let stale_chunks: Vec<_> = self.stale_chunks.drain().collect();
+		for chunk_coords in stale_chunks {
+			self.remove_chunk(chunk_coords);
+			let Some(chunk_blocks) = get_chunk_blocks(chunk_coords) else {
+				continue;
+			};
+			if let Some(tops) = ChunkColumnTops::compute(chunk_blocks, block_type_table) {
+				self.chunk_tops.insert(chunk_coords, tops);
+				self
+					.chunk_columns
+					.entry((chunk_coords.x, chunk_coords.y))
+					.or_default()
+					.insert(chunk_coords.z);
+			}
+		}
+	}
+
+	/// The z coordinate of the highest opaque block of the column of blocks at the given x and y,
+	/// among the loaded chunks (as of the last `update`).
+	pub(crate) fn top_solid_z(&self, x: i32, y: i32) -> Option<i32> {
+		let chunk_coords = self.cd.world_coords_to_containing_chunk_coords(cgmath::point3(x, y, 0));
+		let chunk_zs = self.chunk_columns.get(&(chunk_coords.x, chunk_coords.y))?;
+		let edge = self.cd.edge;
+		let index = (x - chunk_coords.x * edge) * edge + (y - chunk_coords.y * edge);
+		chunk_zs.iter().rev().find_map(|&chunk_z| {
+			let chunk_coords = cgmath::point3(chunk_coords.x, chunk_coords.y, chunk_z);
+			self.chunk_tops[&chunk_coords].tops[index as usize]
+		})
+	}
+}
@@ -0,0 +1,176 @@
+//! The block held by the player, rendered in the bottom-right corner of the first-person view
+//! (or the hand of the player when no block is held).
+//!
+//! It is not in the world, it is rendered after the world with its own camera and depth buffer
+//! (see `shaders::held_block`) so that it never clips into the blocks the player is pressed against.
+
+use std::{f32::consts::TAU, time::Duration};
+
+use cgmath::{Matrix4, Rad};
+use wgpu::util::DeviceExt;
+
+use crate::{
+	block_types::{BlockType, BlockTypeId, BlockTypeTable},
+	camera::{AspectRatio, CameraPerspectiveSettings, CameraSettings},
+	chunk_blocks::Block,
+	chunk_meshing::generate_lone_block_mesh,
+	shaders::held_block::HeldBlockSettingsPod,
+};
+
+/// Color of the hand of the player.
+const HAND_COLOR: [f32; 3] = [0.85, 0.66, 0.52];
+/// How long the swing animation lasts (when a block is placed, broken or thrown).
+const SWING_DURATION: Duration = Duration::from_millis(250);
+
+pub(crate) struct HeldBlockMesh {
+	pub(crate) vertex_count: u32,
+	pub(crate) vertex_buffer: wgpu::Buffer,
+}
+
+impl HeldBlockMesh {
+	fn of_block_type(device: &wgpu::Device, block_type: &BlockType) -> Option<HeldBlockMesh> {
+		let vertices = generate_lone_block_mesh(block_type);
+		if vertices.is_empty() {
+			return None;
+		}
+		let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("Held Block Vertex Buffer"),
+			contents: bytemuck::cast_slice(&vertices),
+			usage: wgpu::BufferUsages::VERTEX,
+		});
+		Some(HeldBlockMesh { vertex_count: vertices.len() as u32, vertex_buffer })
+	}
+}
+
+pub(crate) struct HeldBlockView {
+	/// The mesh of the held block, remade only when the type of the held block changes.
+	/// It is `None` when the hand is to be displayed instead.
+	held_block_mesh: Option<(BlockTypeId, Option<HeldBlockMesh>)>,
+	/// The hand is a cube (stretched by the model matrix), its texture is not used.
+	hand_mesh: HeldBlockMesh,
+	/// Advances as the player walks, makes the held block bob up and down with the steps.
+	bobbing_phase: f32,
+	/// Between 0 (not walking) and 1, smoothly follows the walking speed so that the bobbing
+	/// does not stop abruptly.
+	bobbing_amplitude: f32,
+	/// Time since the beginning of the swing animation, if any.
+	swing_time: Option<Duration>,
+}
+
+impl HeldBlockView {
+	pub(crate) fn new(device: &wgpu::Device) -> HeldBlockView {
+		let hand_block_type = BlockType::Solid { texture_coords_on_atlas: cgmath::point2(0, 0) };
+		let hand_mesh = HeldBlockMesh::of_block_type(device, &hand_block_type).unwrap();
+		HeldBlockView {
+			held_block_mesh: None,
+			hand_mesh,
+			bobbing_phase: 0.0,
+			bobbing_amplitude: 0.0,
+			swing_time: None,
+		}
+	}
+
+	/// Starts the swing animation (like the player's arm moving to place or break a block).
+	pub(crate) fn swing(&mut self) {
+		self.swing_time = Some(Duration::ZERO);
+	}
+
+	/// Advances the animations.
+	/// `walking_speed` is the horizontal speed of the player (in blocks per second), it is
+	/// expected to be zero when the player is not walking on the ground.
+	pub(crate) fn advance(&mut self, dt: Duration, walking_speed: f32) {
+		let dt_seconds = dt.as_secs_f32();
+		// About two steps per block walked.
+		self.bobbing_phase = (self.bobbing_phase + walking_speed * dt_seconds * TAU / 4.0) % TAU;
+		let target_amplitude = (walking_speed / 8.0).clamp(0.0, 1.0);
+		let approach = (dt_seconds * 8.0).min(1.0);
+		self.bobbing_amplitude += (target_amplitude - self.bobbing_amplitude) * approach;
+		if let Some(swing_time) = self.swing_time.as_mut() {
+			*swing_time += dt;
+			if *swing_time >= SWING_DURATION {
+				self.swing_time = None;
+			}
+		}
+	}
+
+	/// Makes the mesh of the held block if the type of the held block changed since last time.
+	pub(crate) fn update_mesh(
+		&mut self,
+		device: &wgpu::Device,
+		held_block: Option<&Block>,
+		block_type_table: &BlockTypeTable,
+	) {
+		let held_type_id = held_block.map(|block| block.type_id);
+		let cached_type_id = self.held_block_mesh.as_ref().map(|(type_id, _mesh)| *type_id);
+		if held_type_id != cached_type_id {
+			self.held_block_mesh = held_type_id.map(|type_id| {
+				let mesh = block_type_table
+					.get(type_id)
+					.and_then(|block_type| HeldBlockMesh::of_block_type(device, block_type));
+				(type_id, mesh)
+			});
+		}
+	}
+
+	/// The mesh to render, the held block (see `update_mesh`) or the hand.
+	pub(crate) fn mesh(&self) -> &HeldBlockMesh {
+		match &self.held_block_mesh {
+			Some((_type_id, Some(mesh))) => mesh,
+			_ => &self.hand_mesh,
+		}
+	}
+
+	/// Whether the mesh returned by `mesh` is the held block (and not the hand).
+	fn is_holding_a_block(&self) -> bool {
+		matches!(self.held_block_mesh, Some((_, Some(_))))
+	}
+
+	/// The settings to send to the GPU to render the mesh given by `mesh`.
+	pub(crate) fn settings(&self, aspect_ratio: AspectRatio) -> HeldBlockSettingsPod {
+		// The camera is at the origin and looks towards +X, so +Z is up and -Y is right.
+		// It does not depend on the field of view of the world camera so that the held block
+		// always keeps the same size and position on the screen.
+		let camera_settings = CameraPerspectiveSettings {
+			up_direction: (0.0, 0.0, 1.0).into(),
+			aspect_ratio,
+			field_of_view_y: TAU / 6.0,
+			near_plane: 0.05,
+			far_plane: 10.0,
+		};
+		let view_projection_matrix = camera_settings.view_projection_matrix(
+			(0.0, 0.0, 0.0).into(),
+			(1.0, 0.0, 0.0).into(),
+			(0.0, 0.0, 1.0).into(),
+		);
+		let view_projection_matrix = view_projection_matrix.to_matrix();
+
+		// Bobbing follows the steps, it goes down at each step and sways left and right.
+		let bob_sideways = self.bobbing_phase.sin() * 0.03 * self.bobbing_amplitude;
+		let bob_down = self.bobbing_phase.cos().abs() * 0.04 * self.bobbing_amplitude;
+		// The swing goes forward and down and comes back.
+		let swing = self.swing_time.map_or(0.0, |swing_time| {
+			(swing_time.as_secs_f32() / SWING_DURATION.as_secs_f32() * TAU / 2.0).sin()
+		});
+
+		let rest_position = cgmath::vec3(1.2, -0.65, -0.55);
+		let animation_offset = cgmath::vec3(swing * 0.25, bob_sideways + swing * 0.1, -bob_down);
+		let rotation = Matrix4::from_angle_y(Rad(swing * 0.6))
+			* Matrix4::from_angle_z(Rad(TAU / 8.0 - TAU / 40.0))
+			* Matrix4::from_angle_y(Rad(TAU / 40.0));
+		let (scale, textured) = if self.is_holding_a_block() {
+			(Matrix4::from_scale(0.4), true)
+		} else {
+			// The hand is more of an arm going away from the camera.
+			(Matrix4::from_nonuniform_scale(0.9, 0.2, 0.2), false)
+		};
+		let model_matrix =
+			Matrix4::from_translation(rest_position + animation_offset) * rotation * scale;
+
+		HeldBlockSettingsPod {
+			view_projection_model_matrix: (view_projection_matrix * model_matrix).into(),
+			rotation_matrix: rotation.into(),
+			hand_color: HAND_COLOR,
+			textured: textured as u32,
+		}
+	}
+}
@@ -1,6 +1,13 @@
 use crate::{
-	atlas::RectInAtlas,
+	achievements::Achievements,
+	atlas::{RectInAtlas, TEXTURE_PREVIEW_COORDS_ON_ATLAS},
+	coords::BlockCoords,
 	font,
+	gameplay_stats::GameplayStats,
+	loading_screen::LoadingProgress,
+	localization::Localization,
+	save_and_quit::SavingProgress,
+	selection::Selection,
 	widgets::{
 		BoxContentPlacement, BoxDimensions, ListAlignmentHorizontal, ListAlignmentVertical,
 		ListOrientationAndAlignment, ListOrientationHorizontal, ListOrientationVertical, Widget,
@@ -59,6 +66,10 @@ impl Interface {
 						vec![
 							Widget::new_labeled_nothing(WidgetLabel::HealthBar),
 							Widget::new_labeled_nothing(WidgetLabel::ItemHeld),
+							Widget::new_labeled_nothing(WidgetLabel::ToolInfo),
+							Widget::new_labeled_nothing(WidgetLabel::SelectionInfo),
+							Widget::new_labeled_nothing(WidgetLabel::ModelEditorInfo),
+							Widget::new_labeled_nothing(WidgetLabel::TexturePreview),
 						],
 						5.0,
 						ListOrientationAndAlignment::Vertical(
@@ -67,6 +78,38 @@ impl Interface {
 						),
 					)),
 				),
+			)
+			.set_a_box_sub_widget(
+				BoxContentPlacement::TopRight,
+				Widget::new_margins(
+					(0.0, 5.0, 5.0, 0.0),
+					Box::new(Widget::new_label(
+						WidgetLabel::Toasts,
+						Box::new(Widget::new_list(
+							vec![],
+							5.0,
+							ListOrientationAndAlignment::Vertical(
+								ListOrientationVertical::TopToBottom,
+								ListAlignmentVertical::Right,
+							),
+						)),
+					)),
+				),
+			)
+			.set_a_box_sub_widget(
+				BoxContentPlacement::Center,
+				Widget::new_list(
+					vec![
+						Widget::new_labeled_nothing(WidgetLabel::LoadingScreen),
+						Widget::new_labeled_nothing(WidgetLabel::SavingScreen),
+						Widget::new_labeled_nothing(WidgetLabel::StatsScreen),
+					],
+					10.0,
+					ListOrientationAndAlignment::Vertical(
+						ListOrientationVertical::TopToBottom,
+						ListAlignmentVertical::Center,
+					),
+				),
 			);
 
 		Interface { widget_tree_root }
@@ -80,6 +123,34 @@ impl Interface {
 		}
 	}
 
+	/// Displays a notification (like an achievement being unlocked) for a few seconds.
+	pub(crate) fn push_toast(&mut self, title: String, text: String) {
+		if let Some(Widget::List { sub_widgets, .. }) =
+			self.widget_tree_root.find_label_content(WidgetLabel::Toasts)
+		{
+			let toast = Widget::new_list(
+				vec![
+					Widget::new_simple_text(title, font::TextRenderingSettings::with_scale(2.0)),
+					Widget::new_simple_text(text, font::TextRenderingSettings::with_scale(3.0)),
+				],
+				3.0,
+				ListOrientationAndAlignment::Vertical(
+					ListOrientationVertical::TopToBottom,
+					ListAlignmentVertical::Right,
+				),
+			);
+			sub_widgets.push(Widget::new_smoothly_incoming(
+				cgmath::point2(1.0, 0.0),
+				std::time::Instant::now(),
+				std::time::Duration::from_secs_f32(0.5),
+				Box::new(Widget::new_disappear_after(
+					std::time::Duration::from_secs_f32(5.0),
+					Box::new(toast),
+				)),
+			));
+		}
+	}
+
 	pub(crate) fn update_health_bar(&mut self, health: Option<u32>) {
 		if let Some(health_bar_widget) =
 			self.widget_tree_root.find_label_content(WidgetLabel::HealthBar)
@@ -109,4 +180,251 @@ impl Interface {
 			}
 		}
 	}
+	pub(crate) fn update_selection_info(
+		&mut self,
+		selection: &Selection,
+		selection_tool_enabled: bool,
+		localization: &Localization,
+	) {
+		if let Some(selection_info_widget) =
+			self.widget_tree_root.find_label_content(WidgetLabel::SelectionInfo)
+		{
+			let nothing_to_show = !selection_tool_enabled
+				&& selection.first_corner.is_none()
+				&& selection.second_corner.is_none();
+			if nothing_to_show {
+				*selection_info_widget = Widget::Nothing;
+				return;
+			}
+			let corner_to_string = |corner: Option<BlockCoords>| {
+				corner.map_or("?".to_string(), |cgmath::Point3 { x, y, z }| {
+					format!("{x},{y},{z}")
+				})
+			};
+			let mut lines = vec![];
+			if selection_tool_enabled {
+				lines.push(localization.get("selection.tool_enabled").to_string());
+			}
+			lines.push(localization.get_with_args(
+				"selection.corners",
+				&[
+					("first", &corner_to_string(selection.first_corner)),
+					("second", &corner_to_string(selection.second_corner)),
+				],
+			));
+			if let Some(span) = selection.span() {
+				let cgmath::Vector3 { x, y, z } = span.sup_excluded - span.inf;
+				let count = x * y * z;
+				lines.push(localization.get_with_args(
+					"selection.size",
+					&[("x", &x), ("y", &y), ("z", &z), ("count", &count)],
+				));
+			}
+			let settings = font::TextRenderingSettings::with_scale(2.0);
+			*selection_info_widget = Widget::new_simple_text(lines.join("\n"), settings);
+		}
+	}
+
+	/// The equipped tool is given as its name, durability and max durability.
+	/// The breaking progress is from 0 to 1.
+	pub(crate) fn update_tool_info(
+		&mut self,
+		tool: Option<(&str, u32, u32)>,
+		breaking_progress: Option<f32>,
+		localization: &Localization,
+	) {
+		if let Some(tool_info_widget) =
+			self.widget_tree_root.find_label_content(WidgetLabel::ToolInfo)
+		{
+			let mut lines = vec![];
+			if let Some((name, durability, max_durability)) = tool {
+				lines.push(localization.get_with_args(
+					"tool.equipped",
+					&[
+						("name", &name),
+						("durability", &durability),
+						("max", &max_durability),
+					],
+				));
+			}
+			if let Some(progress) = breaking_progress {
+				let percent = (progress * 100.0) as u32;
+				lines.push(localization.get_with_args("tool.breaking", &[("percent", &percent)]));
+			}
+			if lines.is_empty() {
+				*tool_info_widget = Widget::Nothing;
+			} else {
+				let settings = font::TextRenderingSettings::with_scale(2.0);
+				*tool_info_widget = Widget::new_simple_text(lines.join("\n"), settings);
+			}
+		}
+	}
+
+	/// The lines are given by `ModelEditor::info_lines` (no lines when the editor is closed).
+	pub(crate) fn update_model_editor_info(&mut self, lines: Vec<String>) {
+		if let Some(model_editor_info_widget) =
+			self.widget_tree_root.find_label_content(WidgetLabel::ModelEditorInfo)
+		{
+			if lines.is_empty() {
+				*model_editor_info_widget = Widget::Nothing;
+			} else {
+				let settings = font::TextRenderingSettings::with_scale(2.0);
+				*model_editor_info_widget = Widget::new_simple_text(lines.join("\n"), settings);
+			}
+		}
+	}
+
+	/// Displays the progress of the loading of the spawn area, or stops displaying it.
+	pub(crate) fn update_loading_screen(
+		&mut self,
+		progress: Option<&LoadingProgress>,
+		localization: &Localization,
+	) {
+		if let Some(loading_screen_widget) =
+			self.widget_tree_root.find_label_content(WidgetLabel::LoadingScreen)
+		{
+			*loading_screen_widget = match progress {
+				None => Widget::Nothing,
+				Some(progress) => {
+					let title = localization.get("loading_screen.title").to_string();
+					let details = localization.get_with_args(
+						"loading_screen.details",
+						&[
+							("generated", &progress.generated),
+							("meshed", &progress.meshed),
+							("collidable", &progress.collidable),
+							("requested", &progress.requested),
+						],
+					);
+					Widget::new_list(
+						vec![
+							Widget::new_simple_text(title, font::TextRenderingSettings::with_scale(4.0)),
+							Widget::new_progress_bar(
+								progress.ratio(),
+								cgmath::vec2(400.0, 20.0),
+								[1.0, 1.0, 1.0],
+							),
+							Widget::new_simple_text(details, font::TextRenderingSettings::with_scale(2.0)),
+						],
+						10.0,
+						ListOrientationAndAlignment::Vertical(
+							ListOrientationVertical::TopToBottom,
+							ListAlignmentVertical::Center,
+						),
+					)
+				},
+			};
+		}
+	}
+
+	/// Displays the progress of the saving before quitting, or stops displaying it.
+	pub(crate) fn update_saving_screen(
+		&mut self,
+		progress: Option<&SavingProgress>,
+		localization: &Localization,
+	) {
+		if let Some(saving_screen_widget) =
+			self.widget_tree_root.find_label_content(WidgetLabel::SavingScreen)
+		{
+			*saving_screen_widget = match progress {
+				None => Widget::Nothing,
+				Some(progress) => {
+					let title = localization.get("saving_screen.title").to_string();
+					let details = if progress.tasks_to_wait_for >= 1 {
+						localization.get_with_args(
+							"saving_screen.waiting",
+							&[("tasks", &progress.tasks_to_wait_for)],
+						)
+					} else {
+						localization.get_with_args(
+							"saving_screen.details",
+							&[
+								("saved", &progress.saved_chunk_count),
+								("chunks", &progress.chunk_count),
+							],
+						)
+					};
+					Widget::new_list(
+						vec![
+							Widget::new_simple_text(title, font::TextRenderingSettings::with_scale(4.0)),
+							Widget::new_progress_bar(
+								progress.ratio(),
+								cgmath::vec2(400.0, 20.0),
+								[1.0, 1.0, 1.0],
+							),
+							Widget::new_simple_text(details, font::TextRenderingSettings::with_scale(2.0)),
+						],
+						10.0,
+						ListOrientationAndAlignment::Vertical(
+							ListOrientationVertical::TopToBottom,
+							ListAlignmentVertical::Center,
+						),
+					)
+				},
+			};
+		}
+	}
+
+	/// Displays the gameplay stats and the achievements, or stops displaying them.
+	pub(crate) fn update_stats_screen(
+		&mut self,
+		stats: Option<(&GameplayStats, &Achievements)>,
+		localization: &Localization,
+	) {
+		if let Some(stats_screen_widget) =
+			self.widget_tree_root.find_label_content(WidgetLabel::StatsScreen)
+		{
+			*stats_screen_widget = match stats {
+				None => Widget::Nothing,
+				Some((stats, achievements)) => {
+					let title = localization.get("stats_screen.title").to_string();
+					let mut lines = stats.lines(localization);
+					lines.extend(achievements.lines(localization));
+					let lines = lines.join("\n");
+					Widget::new_list(
+						vec![
+							Widget::new_simple_text(title, font::TextRenderingSettings::with_scale(4.0)),
+							Widget::new_simple_text(lines, font::TextRenderingSettings::with_scale(2.0)),
+						],
+						10.0,
+						ListOrientationAndAlignment::Vertical(
+							ListOrientationVertical::TopToBottom,
+							ListAlignmentVertical::Center,
+						),
+					)
+				},
+			};
+		}
+	}
+
+	/// Displays the texture painted by the `texgen_preview` command (under the given caption),
+	/// or stops displaying it.
+	pub(crate) fn update_texture_preview(&mut self, caption: Option<String>) {
+		if let Some(texture_preview_widget) =
+			self.widget_tree_root.find_label_content(WidgetLabel::TexturePreview)
+		{
+			*texture_preview_widget = match caption {
+				None => Widget::Nothing,
+				Some(caption) => {
+					let (x, y) = TEXTURE_PREVIEW_COORDS_ON_ATLAS;
+					let rect_in_atlas = RectInAtlas {
+						texture_rect_in_atlas_xy: cgmath::point2(x as f32, y as f32) * (1.0 / 512.0),
+						texture_rect_in_atlas_wh: cgmath::vec2(16.0, 16.0) * (1.0 / 512.0),
+					};
+					let settings = font::TextRenderingSettings::with_scale(2.0);
+					Widget::new_list(
+						vec![
+							Widget::new_simple_texture(rect_in_atlas, 10.0),
+							Widget::new_simple_text(caption, settings),
+						],
+						5.0,
+						ListOrientationAndAlignment::Vertical(
+							ListOrientationVertical::BottomToTop,
+							ListAlignmentVertical::Right,
+						),
+					)
+				},
+			};
+		}
+	}
 }
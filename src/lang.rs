@@ -81,6 +81,61 @@ enum BuiltInFunctionBody {
 	PrintType,
 	/// TODO: Maybe move this feature somewhere else than a function >w<.
 	DeclareAndSetGlobalVariable,
+	SetLanguage,
+	SetVolume,
+	NameEntity,
+	SpawnPlatform,
+	SetRenderScale,
+	SetLensEffects,
+	SetBloom,
+	SetColorGrading,
+	SetColorLut,
+	ExportMap,
+	ExportVox,
+	PrintRenderStats,
+	WorldStats,
+	WorldStatsExport,
+	Pregen,
+	TogglePhotoMode,
+	SetPhotoRoll,
+	SetPhotoFieldOfView,
+	CameraPathAddKeyframe,
+	CameraPathClear,
+	CameraPathPlay,
+	CameraPathRecord,
+	TakeScreenshot,
+	SetRenderDebugMode,
+	SetFullscreen,
+	SetPresentMode,
+	SelectionFill,
+	SelectionCopy,
+	SelectionPaste,
+	SelectionMove,
+	SelectionHollow,
+	SelectionClear,
+	Unstuck,
+	SaveAndQuit,
+	ConfigSave,
+	BackupCreate,
+	BackupRestore,
+	SetPlayerColor,
+	Emote,
+	FillChest,
+	SetLogLevel,
+	SetSnowfall,
+	SetDebugAnnotations,
+	DebugStructures,
+	TriggerAdd,
+	TriggerRemove,
+	ModelEdit,
+	ModelExport,
+	ModelClose,
+	ModelPartAdd,
+	ModelPartRemove,
+	SpawnCreature,
+	SpawnHostile,
+	TexgenPreview,
+	TexgenPreviewClose,
 }
 
 impl BuiltInFunctionBody {
@@ -91,7 +146,7 @@ impl BuiltInFunctionBody {
 					Value::Integer(integer_value) => integer_value,
 					_ => todo!(),
 				};
-				println!("printing integer {integer_value}");
+				log::debug!("printing integer {integer_value}");
 				log.log_items.push(LogItem::Text(format!("{integer_value}")));
 				Value::Nothing
 			},
@@ -103,7 +158,7 @@ impl BuiltInFunctionBody {
 						_ => todo!(),
 					})
 					.collect();
-				println!("printing three integers {integer_values:?}");
+				log::debug!("printing three integers {integer_values:?}");
 				log.log_items.push(LogItem::Text(format!("{integer_values:?}")));
 				Value::Nothing
 			},
@@ -116,7 +171,7 @@ impl BuiltInFunctionBody {
 					Value::Type(type_value) => type_value,
 					_ => todo!(),
 				};
-				println!("printing type {type_value:?}");
+				log::debug!("printing type {type_value:?}");
 				log.log_items.push(LogItem::Text(format!("{type_value:?}")));
 				Value::Nothing
 			},
@@ -127,13 +182,465 @@ impl BuiltInFunctionBody {
 					_ => todo!(),
 				};
 				let value = arg_values.next().unwrap();
-				println!("declaring {name_as_string} and setting it to {value:?}");
+				log::debug!("declaring {name_as_string} and setting it to {value:?}");
 				let previous_value = context.variables.insert(name_as_string, value);
 				if previous_value.is_some() {
 					panic!("declaring global variable that was already declared");
 				}
 				Value::Nothing
 			},
+			BuiltInFunctionBody::SetLanguage => {
+				let language = match arg_values.into_iter().next().unwrap() {
+					Value::Name(language) => language,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SetLanguage(language)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SetVolume => {
+				let mut arg_values = arg_values.into_iter();
+				let channel = match arg_values.next().unwrap() {
+					Value::Name(channel) => channel,
+					_ => todo!(),
+				};
+				let percent = match arg_values.next().unwrap() {
+					Value::Integer(percent) => percent,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SetVolume {
+					channel,
+					percent,
+				}));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::NameEntity => {
+				let name = match arg_values.into_iter().next().unwrap() {
+					Value::Name(name) => name,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::NameTargetedEntity(name)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SpawnPlatform => {
+				let path_length = match arg_values[0] {
+					Value::Integer(integer_value) => integer_value,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SpawnPlatform { path_length }));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SetRenderScale => {
+				let percent = match arg_values[0] {
+					Value::Integer(integer_value) => integer_value,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SetRenderScale { percent }));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SetLensEffects => {
+				let mut arg_values = arg_values.into_iter();
+				let depth_of_field_percent = match arg_values.next().unwrap() {
+					Value::Integer(depth_of_field_percent) => depth_of_field_percent,
+					_ => todo!(),
+				};
+				let motion_blur_percent = match arg_values.next().unwrap() {
+					Value::Integer(motion_blur_percent) => motion_blur_percent,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SetLensEffects {
+					depth_of_field_percent,
+					motion_blur_percent,
+				}));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SetBloom => {
+				let mut arg_values = arg_values.into_iter();
+				let threshold_percent = match arg_values.next().unwrap() {
+					Value::Integer(threshold_percent) => threshold_percent,
+					_ => todo!(),
+				};
+				let intensity_percent = match arg_values.next().unwrap() {
+					Value::Integer(intensity_percent) => intensity_percent,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SetBloom {
+					threshold_percent,
+					intensity_percent,
+				}));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SetColorGrading => {
+				let mut arg_values = arg_values.into_iter();
+				let tonemapper_name = match arg_values.next().unwrap() {
+					Value::Name(tonemapper_name) => tonemapper_name,
+					_ => todo!(),
+				};
+				let mut next_integer = || match arg_values.next().unwrap() {
+					Value::Integer(integer_value) => integer_value,
+					_ => todo!(),
+				};
+				let exposure_percent = next_integer();
+				let contrast_percent = next_integer();
+				let saturation_percent = next_integer();
+				log.log_items.push(LogItem::Command(GameCommand::SetColorGrading {
+					tonemapper_name,
+					exposure_percent,
+					contrast_percent,
+					saturation_percent,
+				}));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SetColorLut => {
+				let name = match arg_values.into_iter().next().unwrap() {
+					Value::Name(name) => name,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SetColorLut { name }));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::ExportMap => {
+				let mut arg_values = arg_values.into_iter();
+				let projection_name = match arg_values.next().unwrap() {
+					Value::Name(projection_name) => projection_name,
+					_ => todo!(),
+				};
+				let pixels_per_block = match arg_values.next().unwrap() {
+					Value::Integer(pixels_per_block) => pixels_per_block,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::ExportMap {
+					projection_name,
+					pixels_per_block,
+				}));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::ExportVox => {
+				log.log_items.push(LogItem::Command(GameCommand::ExportVox));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::PrintRenderStats => {
+				log.log_items.push(LogItem::Command(GameCommand::PrintRenderStats));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::WorldStats => {
+				log.log_items.push(LogItem::Command(GameCommand::WorldStats { export: false }));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::Pregen => {
+				let radius = match arg_values.into_iter().next().unwrap() {
+					Value::Integer(radius) => radius,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::Pregen { radius }));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::WorldStatsExport => {
+				log.log_items.push(LogItem::Command(GameCommand::WorldStats { export: true }));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::TogglePhotoMode => {
+				log.log_items.push(LogItem::Command(GameCommand::TogglePhotoMode));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SetPhotoRoll => {
+				let degrees = match arg_values.into_iter().next().unwrap() {
+					Value::Integer(degrees) => degrees,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SetPhotoRoll { degrees }));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SetPhotoFieldOfView => {
+				let degrees = match arg_values.into_iter().next().unwrap() {
+					Value::Integer(degrees) => degrees,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SetPhotoFieldOfView {
+					degrees,
+				}));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::CameraPathAddKeyframe => {
+				log.log_items.push(LogItem::Command(GameCommand::CameraPathAddKeyframe));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::CameraPathClear => {
+				log.log_items.push(LogItem::Command(GameCommand::CameraPathClear));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::CameraPathPlay => {
+				let seconds_per_keyframe = match arg_values.into_iter().next().unwrap() {
+					Value::Integer(seconds_per_keyframe) => seconds_per_keyframe,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::CameraPathPlay {
+					seconds_per_keyframe,
+					recording_fps: None,
+				}));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::CameraPathRecord => {
+				let mut arg_values = arg_values.into_iter();
+				let seconds_per_keyframe = match arg_values.next().unwrap() {
+					Value::Integer(seconds_per_keyframe) => seconds_per_keyframe,
+					_ => todo!(),
+				};
+				let fps = match arg_values.next().unwrap() {
+					Value::Integer(fps) => fps,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::CameraPathPlay {
+					seconds_per_keyframe,
+					recording_fps: Some(fps),
+				}));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::TakeScreenshot => {
+				log.log_items.push(LogItem::Command(GameCommand::TakeScreenshot));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SetRenderDebugMode => {
+				let mode = match arg_values.into_iter().next().unwrap() {
+					Value::Name(mode) => mode,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SetRenderDebugMode(mode)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SetFullscreen => {
+				let mode = match arg_values.into_iter().next().unwrap() {
+					Value::Name(mode) => mode,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SetFullscreen(mode)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SetPresentMode => {
+				let mode = match arg_values.into_iter().next().unwrap() {
+					Value::Name(mode) => mode,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SetPresentMode(mode)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SelectionFill => {
+				let block_type_name = match arg_values.into_iter().next().unwrap() {
+					Value::Name(block_type_name) => block_type_name,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SelectionFill(
+					block_type_name,
+				)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SelectionCopy => {
+				log.log_items.push(LogItem::Command(GameCommand::SelectionCopy));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SelectionPaste => {
+				log.log_items.push(LogItem::Command(GameCommand::SelectionPaste));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SelectionMove => {
+				let delta: Vec<_> = arg_values
+					.iter()
+					.map(|arg| match arg {
+						Value::Integer(integer_value) => *integer_value,
+						_ => todo!(),
+					})
+					.collect();
+				let delta = cgmath::vec3(delta[0], delta[1], delta[2]);
+				log.log_items.push(LogItem::Command(GameCommand::SelectionMove { delta }));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SelectionHollow => {
+				log.log_items.push(LogItem::Command(GameCommand::SelectionHollow));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SelectionClear => {
+				log.log_items.push(LogItem::Command(GameCommand::SelectionClear));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::Unstuck => {
+				log.log_items.push(LogItem::Command(GameCommand::Unstuck));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SaveAndQuit => {
+				log.log_items.push(LogItem::Command(GameCommand::SaveAndQuit));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SpawnHostile => {
+				log.log_items.push(LogItem::Command(GameCommand::SpawnHostile));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::ConfigSave => {
+				log.log_items.push(LogItem::Command(GameCommand::ConfigSave));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::BackupCreate => {
+				log.log_items.push(LogItem::Command(GameCommand::BackupCreate));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::BackupRestore => {
+				let backup_name = match arg_values.into_iter().next().unwrap() {
+					Value::Name(backup_name) => backup_name,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::BackupRestore(backup_name)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SetPlayerColor => {
+				let mut arg_values = arg_values.into_iter();
+				let color_name = match arg_values.next().unwrap() {
+					Value::Name(color_name) => color_name,
+					_ => todo!(),
+				};
+				let mut next_integer = || match arg_values.next().unwrap() {
+					Value::Integer(integer_value) => integer_value,
+					_ => todo!(),
+				};
+				let color = [next_integer(), next_integer(), next_integer()];
+				log.log_items.push(LogItem::Command(GameCommand::SetPlayerColor {
+					color_name,
+					color,
+				}));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::Emote => {
+				let emote_name = match arg_values.into_iter().next().unwrap() {
+					Value::Name(emote_name) => emote_name,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::Emote(emote_name)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::FillChest => {
+				let loot_table_name = match arg_values.into_iter().next().unwrap() {
+					Value::Name(loot_table_name) => loot_table_name,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::FillChest(loot_table_name)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SetLogLevel => {
+				let mut arg_values = arg_values.into_iter();
+				let module = match arg_values.next().unwrap() {
+					Value::Name(module) => module,
+					_ => todo!(),
+				};
+				let level = match arg_values.next().unwrap() {
+					Value::Name(level) => level,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SetLogLevel { module, level }));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SetSnowfall => {
+				let mode = match arg_values.into_iter().next().unwrap() {
+					Value::Name(mode) => mode,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SetSnowfall(mode)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SetDebugAnnotations => {
+				let mode = match arg_values.into_iter().next().unwrap() {
+					Value::Name(mode) => mode,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SetDebugAnnotations(mode)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::DebugStructures => {
+				let radius = match arg_values.into_iter().next().unwrap() {
+					Value::Integer(radius) => radius,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::DebugStructures { radius }));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::TriggerAdd => {
+				let name = match arg_values.into_iter().next().unwrap() {
+					Value::Name(name) => name,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::TriggerAdd(name)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::TriggerRemove => {
+				let name = match arg_values.into_iter().next().unwrap() {
+					Value::Name(name) => name,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::TriggerRemove(name)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::ModelEdit => {
+				let name = match arg_values.into_iter().next().unwrap() {
+					Value::Name(name) => name,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::ModelEdit(name)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::ModelExport => {
+				log.log_items.push(LogItem::Command(GameCommand::ModelExport));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::ModelClose => {
+				log.log_items.push(LogItem::Command(GameCommand::ModelClose));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::ModelPartAdd => {
+				let name = match arg_values.into_iter().next().unwrap() {
+					Value::Name(name) => name,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::ModelPartAdd(name)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::ModelPartRemove => {
+				log.log_items.push(LogItem::Command(GameCommand::ModelPartRemove));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::SpawnCreature => {
+				let name = match arg_values.into_iter().next().unwrap() {
+					Value::Name(name) => name,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::SpawnCreature(name)));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::TexgenPreview => {
+				let mut arg_values = arg_values.into_iter();
+				let recipe = match arg_values.next().unwrap() {
+					Value::Name(recipe) => recipe,
+					_ => todo!(),
+				};
+				let palette = match arg_values.next().unwrap() {
+					Value::Name(palette) => palette,
+					_ => todo!(),
+				};
+				let scale = match arg_values.next().unwrap() {
+					Value::Integer(scale) => scale,
+					_ => todo!(),
+				};
+				let roughness_percent = match arg_values.next().unwrap() {
+					Value::Integer(roughness_percent) => roughness_percent,
+					_ => todo!(),
+				};
+				log.log_items.push(LogItem::Command(GameCommand::TexgenPreview {
+					recipe,
+					palette,
+					scale,
+					roughness_percent,
+				}));
+				Value::Nothing
+			},
+			BuiltInFunctionBody::TexgenPreviewClose => {
+				log.log_items.push(LogItem::Command(GameCommand::TexgenPreviewClose));
+				Value::Nothing
+			},
 		}
 	}
 
@@ -144,6 +651,61 @@ impl BuiltInFunctionBody {
 			BuiltInFunctionBody::ToType => "type_of",
 			BuiltInFunctionBody::PrintType => "print_type",
 			BuiltInFunctionBody::DeclareAndSetGlobalVariable => "declare_and_set_global_variable",
+			BuiltInFunctionBody::SetLanguage => "set_language",
+			BuiltInFunctionBody::SetVolume => "set_volume",
+			BuiltInFunctionBody::NameEntity => "name_entity",
+			BuiltInFunctionBody::SpawnPlatform => "spawn_platform",
+			BuiltInFunctionBody::SetRenderScale => "set_render_scale",
+			BuiltInFunctionBody::SetLensEffects => "set_lens_effects",
+			BuiltInFunctionBody::SetBloom => "set_bloom",
+			BuiltInFunctionBody::SetColorGrading => "grading",
+			BuiltInFunctionBody::SetColorLut => "grading_lut",
+			BuiltInFunctionBody::ExportMap => "export_map",
+			BuiltInFunctionBody::ExportVox => "export_vox",
+			BuiltInFunctionBody::PrintRenderStats => "render_stats",
+			BuiltInFunctionBody::WorldStats => "stats",
+			BuiltInFunctionBody::WorldStatsExport => "stats_export",
+			BuiltInFunctionBody::Pregen => "pregen",
+			BuiltInFunctionBody::TogglePhotoMode => "photo_mode",
+			BuiltInFunctionBody::SetPhotoRoll => "photo_roll",
+			BuiltInFunctionBody::SetPhotoFieldOfView => "photo_fov",
+			BuiltInFunctionBody::CameraPathAddKeyframe => "camera_path_add",
+			BuiltInFunctionBody::CameraPathClear => "camera_path_clear",
+			BuiltInFunctionBody::CameraPathPlay => "camera_path_play",
+			BuiltInFunctionBody::CameraPathRecord => "camera_path_record",
+			BuiltInFunctionBody::TakeScreenshot => "screenshot",
+			BuiltInFunctionBody::SetRenderDebugMode => "set_render_debug_mode",
+			BuiltInFunctionBody::SetFullscreen => "set_fullscreen",
+			BuiltInFunctionBody::SetPresentMode => "set_present_mode",
+			BuiltInFunctionBody::SelectionFill => "selection_fill",
+			BuiltInFunctionBody::SelectionCopy => "selection_copy",
+			BuiltInFunctionBody::SelectionPaste => "selection_paste",
+			BuiltInFunctionBody::SelectionMove => "selection_move",
+			BuiltInFunctionBody::SelectionHollow => "selection_hollow",
+			BuiltInFunctionBody::SelectionClear => "selection_clear",
+			BuiltInFunctionBody::Unstuck => "unstuck",
+			BuiltInFunctionBody::SaveAndQuit => "save_and_quit",
+			BuiltInFunctionBody::ConfigSave => "config_save",
+			BuiltInFunctionBody::BackupCreate => "backup_create",
+			BuiltInFunctionBody::BackupRestore => "backup_restore",
+			BuiltInFunctionBody::SetPlayerColor => "player_color",
+			BuiltInFunctionBody::Emote => "emote",
+			BuiltInFunctionBody::FillChest => "fill_chest",
+			BuiltInFunctionBody::SetLogLevel => "log_level",
+			BuiltInFunctionBody::SetSnowfall => "set_snowfall",
+			BuiltInFunctionBody::SetDebugAnnotations => "debug_annotations",
+			BuiltInFunctionBody::DebugStructures => "debug_structures",
+			BuiltInFunctionBody::TriggerAdd => "trigger_add",
+			BuiltInFunctionBody::TriggerRemove => "trigger_remove",
+			BuiltInFunctionBody::ModelEdit => "model_edit",
+			BuiltInFunctionBody::ModelExport => "model_export",
+			BuiltInFunctionBody::ModelClose => "model_close",
+			BuiltInFunctionBody::ModelPartAdd => "model_part_add",
+			BuiltInFunctionBody::ModelPartRemove => "model_part_remove",
+			BuiltInFunctionBody::SpawnCreature => "spawn_creature",
+			BuiltInFunctionBody::SpawnHostile => "spawn_hostile",
+			BuiltInFunctionBody::TexgenPreview => "texgen_preview",
+			BuiltInFunctionBody::TexgenPreviewClose => "texgen_preview_close",
 		}
 	}
 
@@ -173,6 +735,176 @@ impl BuiltInFunctionBody {
 				arg_types: vec![TypeConstraints::Only(Type::Name), TypeConstraints::Any],
 				return_type: Box::new(Type::Nothing),
 			},
+			BuiltInFunctionBody::SetLanguage => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Name)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::SetVolume => FunctionTypeSignature {
+				arg_types: vec![
+					TypeConstraints::Only(Type::Name),
+					TypeConstraints::Only(Type::Integer),
+				],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::SetLogLevel => FunctionTypeSignature {
+				arg_types: vec![
+					TypeConstraints::Only(Type::Name),
+					TypeConstraints::Only(Type::Name),
+				],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::NameEntity => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Name)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::SpawnPlatform => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Integer)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::SetRenderScale => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Integer)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::SetLensEffects => FunctionTypeSignature {
+				arg_types: vec![
+					TypeConstraints::Only(Type::Integer),
+					TypeConstraints::Only(Type::Integer),
+				],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::SetBloom => FunctionTypeSignature {
+				arg_types: vec![
+					TypeConstraints::Only(Type::Integer),
+					TypeConstraints::Only(Type::Integer),
+				],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::SetColorGrading | BuiltInFunctionBody::SetPlayerColor => {
+				FunctionTypeSignature {
+					arg_types: vec![
+						TypeConstraints::Only(Type::Name),
+						TypeConstraints::Only(Type::Integer),
+						TypeConstraints::Only(Type::Integer),
+						TypeConstraints::Only(Type::Integer),
+					],
+					return_type: Box::new(Type::Nothing),
+				}
+			},
+			BuiltInFunctionBody::SetColorLut => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Name)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::ExportMap => FunctionTypeSignature {
+				arg_types: vec![
+					TypeConstraints::Only(Type::Name),
+					TypeConstraints::Only(Type::Integer),
+				],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::ExportVox
+			| BuiltInFunctionBody::PrintRenderStats
+			| BuiltInFunctionBody::WorldStats
+			| BuiltInFunctionBody::WorldStatsExport
+			| BuiltInFunctionBody::TogglePhotoMode
+			| BuiltInFunctionBody::CameraPathAddKeyframe
+			| BuiltInFunctionBody::CameraPathClear
+			| BuiltInFunctionBody::TakeScreenshot => {
+				FunctionTypeSignature { arg_types: vec![], return_type: Box::new(Type::Nothing) }
+			},
+			BuiltInFunctionBody::Pregen
+			| BuiltInFunctionBody::SetPhotoRoll
+			| BuiltInFunctionBody::SetPhotoFieldOfView
+			| BuiltInFunctionBody::CameraPathPlay => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Integer)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::CameraPathRecord => FunctionTypeSignature {
+				arg_types: vec![
+					TypeConstraints::Only(Type::Integer),
+					TypeConstraints::Only(Type::Integer),
+				],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::SetRenderDebugMode => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Name)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::TexgenPreview => FunctionTypeSignature {
+				arg_types: vec![
+					TypeConstraints::Only(Type::Name),
+					TypeConstraints::Only(Type::Name),
+					TypeConstraints::Only(Type::Integer),
+					TypeConstraints::Only(Type::Integer),
+				],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::SetSnowfall => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Name)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::SetDebugAnnotations => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Name)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::DebugStructures => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Integer)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::TriggerAdd
+			| BuiltInFunctionBody::TriggerRemove
+			| BuiltInFunctionBody::ModelEdit
+			| BuiltInFunctionBody::ModelPartAdd
+			| BuiltInFunctionBody::SpawnCreature => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Name)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::SetFullscreen => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Name)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::SetPresentMode => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Name)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::SelectionFill => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Name)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::BackupRestore => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Name)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::Emote => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Name)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::FillChest => FunctionTypeSignature {
+				arg_types: vec![TypeConstraints::Only(Type::Name)],
+				return_type: Box::new(Type::Nothing),
+			},
+			BuiltInFunctionBody::SelectionCopy
+			| BuiltInFunctionBody::SelectionPaste
+			| BuiltInFunctionBody::SelectionHollow
+			| BuiltInFunctionBody::SelectionClear
+			| BuiltInFunctionBody::Unstuck
+			| BuiltInFunctionBody::SaveAndQuit
+			| BuiltInFunctionBody::ConfigSave
+			| BuiltInFunctionBody::BackupCreate
+			| BuiltInFunctionBody::SpawnHostile
+			| BuiltInFunctionBody::ModelExport
+			| BuiltInFunctionBody::ModelClose
+			| BuiltInFunctionBody::ModelPartRemove
+			| BuiltInFunctionBody::TexgenPreviewClose => {
+				FunctionTypeSignature { arg_types: vec![], return_type: Box::new(Type::Nothing) }
+			},
+			BuiltInFunctionBody::SelectionMove => FunctionTypeSignature {
+				arg_types: vec![
+					TypeConstraints::Only(Type::Integer),
+					TypeConstraints::Only(Type::Integer),
+					TypeConstraints::Only(Type::Integer),
+				],
+				return_type: Box::new(Type::Nothing),
+			},
 		}
 	}
 
@@ -675,6 +1407,187 @@ fn check_function_call_argument_types(
 
 pub(crate) enum LogItem {
 	Text(String),
+	/// Something for the game to do once the program has run.
+	Command(GameCommand),
+}
+
+/// Qwy Script has no direct access to the game, so the functions that affect the game
+/// just log what they want the game to do, and the game does it after the program has run.
+pub(crate) enum GameCommand {
+	/// Change the language of the interface (the name is a language like `fr`).
+	SetLanguage(String),
+	/// Change the volume of an audio channel (`master`, `sfx` or `music`), in percents.
+	SetVolume {
+		channel: String,
+		percent: i32,
+	},
+	/// Give a name to the entity the player is looking at, it is displayed above it.
+	NameTargetedEntity(String),
+	/// Spawn a platform under the player's feet, it goes back and forth horizontally
+	/// in the direction the player is looking at, on a path of the given length (in blocks).
+	SpawnPlatform {
+		path_length: i32,
+	},
+	/// Change the resolution at which the scene is rendered, in percents of the window resolution.
+	SetRenderScale {
+		percent: i32,
+	},
+	/// Change how much what is out of focus is blurred and how much the camera motion blurs
+	/// (in percents, 0 disables them).
+	SetLensEffects {
+		depth_of_field_percent: i32,
+		motion_blur_percent: i32,
+	},
+	/// Change the brightness (in percents) above which the scene glows, and how much of the glow
+	/// is added to the scene (in percents, 0 disables the bloom).
+	SetBloom {
+		threshold_percent: i32,
+		intensity_percent: i32,
+	},
+	/// Change the tonemapper (`none`, `reinhard` or `aces`) and the exposure, the contrast and
+	/// the saturation of the scene (in percents, 100 leaves them as they are).
+	SetColorGrading {
+		tonemapper_name: String,
+		exposure_percent: i32,
+		contrast_percent: i32,
+		saturation_percent: i32,
+	},
+	/// Use the color lookup table `luts/<name>.png`, or none if the name is `none`.
+	SetColorLut {
+		name: String,
+	},
+	/// Render the loaded world from above with an orthographic camera (`top_down` or `isometric`)
+	/// and save it as an image in `maps`, with the given number of pixels per block.
+	ExportMap {
+		projection_name: String,
+		pixels_per_block: i32,
+	},
+	/// Write the selected blocks (or the loaded world if there is no selection) to a MagicaVoxel
+	/// `.vox` file in `exports`.
+	ExportVox,
+	/// Log the draw calls, triangles and GPU memory usage (by category) of the last frame.
+	PrintRenderStats,
+	/// Count the blocks by type, the entities by kind and the structure origins around the player
+	/// (on a worker thread, the result is logged when it is ready), see `world_stats`.
+	/// The stats are also written to a file in `stats` if `export` is set.
+	WorldStats {
+		export: bool,
+	},
+	/// Generate and save all the chunks up to `radius` blocks from the player in the background,
+	/// see `pregen`.
+	Pregen {
+		radius: i32,
+	},
+	/// Enter or leave the photo mode, see `photo_mode`.
+	TogglePhotoMode,
+	/// Roll the photo mode camera (in degrees, clockwise).
+	SetPhotoRoll {
+		degrees: i32,
+	},
+	/// Change the vertical field of view of the photo mode camera (in degrees).
+	SetPhotoFieldOfView {
+		degrees: i32,
+	},
+	/// Add the current view of the photo mode camera at the end of the camera path.
+	CameraPathAddKeyframe,
+	CameraPathClear,
+	/// Play the camera path, taking a screenshot at every frame if `recording_fps` is given.
+	CameraPathPlay {
+		seconds_per_keyframe: i32,
+		recording_fps: Option<i32>,
+	},
+	/// Take a screenshot of the next frame.
+	TakeScreenshot,
+	/// Change how the blocks are rendered (`normal`, `wireframe`, `heatmap_meshing`,
+	/// `heatmap_memory`, `light_level` or `light_level_blocks`), see `RenderDebugMode`.
+	SetRenderDebugMode(String),
+	/// Change the fullscreen mode (`windowed`, `borderless` or `exclusive`).
+	SetFullscreen(String),
+	/// Change the present mode (`fifo`, `mailbox` or `immediate`), see `PresentModeChoice`.
+	SetPresentMode(String),
+	/// Set all the blocks of the selection to the block type of the given name.
+	SelectionFill(String),
+	/// Copy the blocks of the selection, to be pasted with `SelectionPaste`.
+	SelectionCopy,
+	/// Paste the copied blocks so that their lowest corner is on the targeted face.
+	SelectionPaste,
+	/// Move the blocks of the selection (and the selection) by the given offset (in blocks),
+	/// leaving air where they were.
+	SelectionMove {
+		delta: cgmath::Vector3<i32>,
+	},
+	/// Replace the blocks inside the selection by air, leaving only its faces.
+	SelectionHollow,
+	/// Unset the corners of the selection.
+	SelectionClear,
+	/// Move the player out of the blocks it is stuck in (if it is), to the closest free space.
+	Unstuck,
+	/// Save everything and quit the game, see `save_and_quit`.
+	SaveAndQuit,
+	/// Write the current settings to the config file, see `config`.
+	ConfigSave,
+	/// Copy the save to a new backup, see `backups`.
+	BackupCreate,
+	/// Roll the save back to the given backup (the game quits to do it), see `backups`.
+	BackupRestore(String),
+	/// Set one of the colors of the player model (`skin`, `hair`, `shirt` or `pants`),
+	/// the components are from 0 to 255.
+	SetPlayerColor {
+		color_name: String,
+		color: [i32; 3],
+	},
+	/// Make the player model play an emote (`wave`, `sit` or `point`).
+	Emote(String),
+	/// Fill the targeted chest with what the chest loot table of that name gives
+	/// (see `loot_tables`), replacing its contents.
+	FillChest(String),
+	/// Change the level of verbosity (`off`, `error`, `warn`, `info`, `debug` or `trace`)
+	/// of the logs of a module (like `chunks` or `wgpu_core`, or `all` for the default level).
+	SetLogLevel {
+		module: String,
+		level: String,
+	},
+	/// Make it snow or not (`on` or `off`), snow piles up on the exposed surfaces while it snows.
+	SetSnowfall(String),
+	/// Display the debug annotations or not (`on` or `off`), or forget them (`clear`),
+	/// see `debug_annotations`.
+	SetDebugAnnotations(String),
+	/// Display the structure origins and the cells in which they are generated up to `radius`
+	/// blocks from the player (a radius of 0 stops displaying them).
+	DebugStructures {
+		radius: i32,
+	},
+	/// Make the selection a trigger volume of the given name (replacing the one that had
+	/// that name), it fires events when something stands in it (see `EventHooks`).
+	TriggerAdd(String),
+	/// Remove the trigger volume of the given name.
+	TriggerRemove(String),
+	/// Open the model editor (see `ModelEditor`) on the model file of the given name (or on a new
+	/// model if there is no such file), the model is displayed in front of the player.
+	ModelEdit(String),
+	/// Write the model being edited to its model file.
+	ModelExport,
+	/// Close the model editor (without exporting).
+	ModelClose,
+	/// Add a copy of the selected part of the model being edited, with the given name.
+	ModelPartAdd(String),
+	/// Remove the selected part of the model being edited.
+	ModelPartRemove,
+	/// Spawn a creature that looks like the model of the given name, in front of the player.
+	SpawnCreature(String),
+	/// Spawn a hostile creature in front of the player.
+	SpawnHostile,
+	/// Paint a texture with the given texture recipe (see `TextureRecipe`) and parameters,
+	/// and display it in the interface.
+	TexgenPreview {
+		recipe: String,
+		palette: String,
+		/// Size (in pixels) of the features of the texture.
+		scale: i32,
+		roughness_percent: i32,
+	},
+	/// Stop displaying the texture painted by `TexgenPreview`.
+	TexgenPreviewClose,
 }
 
 pub(crate) struct Log {
@@ -780,7 +1693,7 @@ pub(crate) fn test_lang(test_id: u32) {
 		5 => {
 			let context = Context::with_builtins();
 			let parsing_error = parse("print_integer()", &context.get_type_context()).unwrap_err();
-			dbg!(parsing_error);
+			log::info!("{parsing_error:?}");
 		},
 		6 => {
 			let mut context = Context::with_builtins();
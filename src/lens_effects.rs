@@ -0,0 +1,241 @@
+//! Lens effects for cinematic screenshots and timelapses, off by default: depth of field (what is
+//! nearer or farther than what is under the crosshair gets blurry) and camera motion blur (the
+//! scene is blurred along the motion of the camera since the last frame).
+//!
+//! Both need to know where in the world the pixels of the scene are, which is given by the depth
+//! buffer (and the inverse of the view projection matrix). They are applied to the scene before
+//! the bloom, and like the bloom they need the scene to be rendered to an offscreen texture.
+
+use cgmath::SquareMatrix;
+use wgpu::util::DeviceExt;
+
+use crate::{
+	camera::Matrix4x4Pod,
+	render_stats::RenderStats,
+	shaders::lens_effects::{
+		render_pipelines_and_bind_group_layout, LensEffectsRenderPipelines, LensEffectsSettingsPod,
+	},
+};
+
+/// The texture that the effects are rendered to before being copied back to the scene texture.
+struct LensEffectsTarget {
+	view: wgpu::TextureView,
+	scene_bind_group: wgpu::BindGroup,
+	copy_bind_group: wgpu::BindGroup,
+}
+
+pub(crate) struct LensEffects {
+	/// How much what is out of focus is blurred, 0.0 disables the depth of field.
+	depth_of_field: f32,
+	/// How much of the motion of the camera during the last frame blurs, 0.0 disables it.
+	motion_blur: f32,
+	/// The view projection matrix of the last frame, if any.
+	previous_view_projection: Option<cgmath::Matrix4<f32>>,
+	output_format: wgpu::TextureFormat,
+	render_pipelines: LensEffectsRenderPipelines,
+	bind_group_layout: wgpu::BindGroupLayout,
+	sampler: wgpu::Sampler,
+	settings_buffer: wgpu::Buffer,
+	/// Only there when an effect is enabled.
+	target: Option<LensEffectsTarget>,
+}
+
+impl LensEffects {
+	/// `resize` must be called before the effects can be rendered.
+	pub(crate) fn new(
+		device: &wgpu::Device,
+		output_format: wgpu::TextureFormat,
+		sample_count: u32,
+		depth_of_field: f32,
+		motion_blur: f32,
+	) -> LensEffects {
+		let (render_pipelines, bind_group_layout) =
+			render_pipelines_and_bind_group_layout(device, output_format, sample_count);
+		let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+			label: Some("Lens Effects Sampler"),
+			address_mode_u: wgpu::AddressMode::ClampToEdge,
+			address_mode_v: wgpu::AddressMode::ClampToEdge,
+			address_mode_w: wgpu::AddressMode::ClampToEdge,
+			mag_filter: wgpu::FilterMode::Linear,
+			min_filter: wgpu::FilterMode::Linear,
+			mipmap_filter: wgpu::FilterMode::Nearest,
+			..Default::default()
+		});
+		let settings_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("Lens Effects Settings Buffer"),
+			contents: bytemuck::cast_slice(&[LensEffectsSettingsPod {
+				inverse_view_projection: cgmath::Matrix4::identity().into(),
+				previous_view_projection: cgmath::Matrix4::identity().into(),
+				camera_position: [0.0; 4],
+				depth_of_field: 0.0,
+				motion_blur: 0.0,
+				_padding: [0.0; 2],
+			}]),
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+		});
+		let mut lens_effects = LensEffects {
+			depth_of_field: 0.0,
+			motion_blur: 0.0,
+			previous_view_projection: None,
+			output_format,
+			render_pipelines,
+			bind_group_layout,
+			sampler,
+			settings_buffer,
+			target: None,
+		};
+		lens_effects.set_settings(depth_of_field, motion_blur);
+		lens_effects
+	}
+
+	pub(crate) fn is_enabled(&self) -> bool {
+		self.depth_of_field > 0.0 || self.motion_blur > 0.0
+	}
+
+	pub(crate) fn depth_of_field(&self) -> f32 {
+		self.depth_of_field
+	}
+
+	pub(crate) fn motion_blur(&self) -> f32 {
+		self.motion_blur
+	}
+
+	/// If the effects get enabled or disabled by that, the textures that have the scaled size must
+	/// be recreated after that (the scene must or need not be rendered to an offscreen texture).
+	pub(crate) fn set_settings(&mut self, depth_of_field: f32, motion_blur: f32) {
+		self.depth_of_field = depth_of_field.max(0.0);
+		self.motion_blur = motion_blur.max(0.0);
+	}
+
+	/// The target must have the size of the scene, and the effects sample the scene (that must then
+	/// be rendered to the given offscreen texture) and its depth buffer.
+	pub(crate) fn resize(
+		&mut self,
+		device: &wgpu::Device,
+		scene_view: Option<&wgpu::TextureView>,
+		z_buffer_view: &wgpu::TextureView,
+		scene_width: u32,
+		scene_height: u32,
+	) {
+		let Some(scene_view) = scene_view.filter(|_| self.is_enabled()) else {
+			self.target = None;
+			return;
+		};
+		let texture = device.create_texture(&wgpu::TextureDescriptor {
+			label: Some("Lens Effects Texture"),
+			size: wgpu::Extent3d {
+				width: scene_width,
+				height: scene_height,
+				depth_or_array_layers: 1,
+			},
+			mip_level_count: 1,
+			sample_count: 1,
+			dimension: wgpu::TextureDimension::D2,
+			format: self.output_format,
+			view_formats: &[],
+			usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+		});
+		let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+		let make_bind_group = |label, source_view| {
+			device.create_bind_group(&wgpu::BindGroupDescriptor {
+				label: Some(label),
+				layout: &self.bind_group_layout,
+				entries: &[
+					wgpu::BindGroupEntry {
+						binding: 0,
+						resource: wgpu::BindingResource::TextureView(source_view),
+					},
+					wgpu::BindGroupEntry {
+						binding: 1,
+						resource: wgpu::BindingResource::Sampler(&self.sampler),
+					},
+					wgpu::BindGroupEntry {
+						binding: 2,
+						resource: wgpu::BindingResource::TextureView(z_buffer_view),
+					},
+					wgpu::BindGroupEntry {
+						binding: 3,
+						resource: self.settings_buffer.as_entire_binding(),
+					},
+				],
+			})
+		};
+		let scene_bind_group = make_bind_group("Lens Effects Scene Bind Group", scene_view);
+		let copy_bind_group = make_bind_group("Lens Effects Copy Bind Group", &view);
+		self.target = Some(LensEffectsTarget { view, scene_bind_group, copy_bind_group });
+	}
+
+	/// Must be called every frame with the camera that the scene is rendered from.
+	pub(crate) fn update_camera(
+		&mut self,
+		queue: &wgpu::Queue,
+		view_projection: Matrix4x4Pod,
+		camera_position: cgmath::Point3<f32>,
+	) {
+		let view_projection = view_projection.to_matrix();
+		let previous_view_projection =
+			self.previous_view_projection.replace(view_projection).unwrap_or(view_projection);
+		if self.target.is_none() {
+			return;
+		}
+		let inverse_view_projection = view_projection.invert().unwrap_or(cgmath::Matrix4::identity());
+		queue.write_buffer(
+			&self.settings_buffer,
+			0,
+			bytemuck::cast_slice(&[LensEffectsSettingsPod {
+				inverse_view_projection: inverse_view_projection.into(),
+				previous_view_projection: previous_view_projection.into(),
+				camera_position: camera_position.to_homogeneous().into(),
+				depth_of_field: self.depth_of_field,
+				motion_blur: self.motion_blur,
+				_padding: [0.0; 2],
+			}]),
+		);
+	}
+
+	/// Applies the effects to the scene (that must have been rendered to the offscreen texture
+	/// given to `resize`).
+	pub(crate) fn render(
+		&self,
+		encoder: &mut wgpu::CommandEncoder,
+		scene_view: &wgpu::TextureView,
+		stats: &mut RenderStats,
+	) {
+		let Some(target) = &self.target else {
+			return;
+		};
+		let passes = [
+			(
+				"Render Pass for the lens effects",
+				&self.render_pipelines.effects,
+				&target.scene_bind_group,
+				&target.view,
+			),
+			(
+				"Render Pass to copy the lens effects to the scene",
+				&self.render_pipelines.copy,
+				&target.copy_bind_group,
+				scene_view,
+			),
+		];
+		for (label, render_pipeline, bind_group, target_view) in passes {
+			let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+				label: Some(label),
+				color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+					view: target_view,
+					resolve_target: None,
+					ops: wgpu::Operations {
+						load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+						store: wgpu::StoreOp::Store,
+					},
+				})],
+				depth_stencil_attachment: None,
+				timestamp_writes: None,
+				occlusion_query_set: None,
+			});
+			render_pass.set_pipeline(render_pipeline);
+			render_pass.set_bind_group(0, bind_group, &[]);
+			stats.draw_triangles(&mut render_pass, 3, 1);
+		}
+	}
+}
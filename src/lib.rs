@@ -1,35 +1,89 @@
 #![allow(clippy::items_after_test_module)]
 
+mod achievements;
 mod atlas;
+mod audio;
+mod backups;
+mod block_definitions;
+mod block_light;
+mod block_picker;
+mod block_type_remapping;
 mod block_types;
+mod bloom;
 mod camera;
+mod chest_screen;
 mod chunk_blocks;
+mod chunk_cache;
+mod chunk_instances;
 mod chunk_loading;
+mod chunk_mesh_culling;
 mod chunk_meshing;
 mod chunks;
 mod cmdline;
+mod collision_shapes;
+mod color_grading;
 mod commands;
+mod config;
 mod coords;
+mod debug_annotations;
+mod deferred_lighting;
+mod determinism;
+mod display;
 mod entities;
+mod entity_culling;
 mod entity_parts;
+mod events;
+mod fire;
 mod font;
+mod game_commands;
+mod gameplay_stats;
 mod game_init;
 mod game_loop;
+mod heightmap;
+mod held_block;
 mod interface;
 mod lang;
+mod lens_effects;
+mod loading_screen;
+mod localization;
+mod logging;
+mod loot_tables;
+mod map_export;
+mod model_editor;
+mod models;
+mod music_gen;
 mod noise;
+mod pathfinding;
+mod photo_mode;
 mod physics;
+mod pistons;
+mod player_model;
+mod pregen;
+mod render_debug;
+mod render_scale;
+mod render_stats;
 mod rendering;
 mod rendering_init;
+mod replay;
+mod save_and_quit;
 mod saves;
+mod selection;
 mod shaders;
+mod signals;
 mod simple_meshes;
 mod skybox;
+mod spawn_point;
+mod spawning;
+mod surface_layers;
 mod table_allocator;
 mod tasks;
 mod texture_gen;
 mod threadpool;
+mod trace;
+mod triggers;
+mod vox_export;
 mod widgets;
 mod world_gen;
+mod world_stats;
 
 pub use game_loop::init_and_run_game_loop;
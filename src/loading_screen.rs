@@ -0,0 +1,81 @@
+//! The loading screen displayed when the game starts, while the chunks around the player
+//! (the spawn area) are generated, meshed and get their collision shapes.
+//!
+//! The player does not move until the spawn area is collidable, so that it does not fall
+//! through the world that is not there yet.
+
+use std::time::{Duration, Instant};
+
+use crate::{
+	chunks::ChunkGrid,
+	coords::{iter_3d_cube_center_radius, ChunkCoords},
+};
+
+/// The spawn area is the cube of chunks of this radius (in chunks) around the chunk of the player.
+const SPAWN_AREA_RADIUS: i32 = 1;
+/// If the spawn area is not loaded after that long (if the loading is disabled for example),
+/// the loading screen gives up so that the game can still be played.
+const MAX_LOADING_DURATION: Duration = Duration::from_secs(60);
+
+/// How much of the spawn area is loaded, in numbers of chunks.
+pub(crate) struct LoadingProgress {
+	pub(crate) requested: usize,
+	pub(crate) generated: usize,
+	pub(crate) meshed: usize,
+	pub(crate) collidable: usize,
+}
+
+impl LoadingProgress {
+	/// From 0.0 to 1.0, with the generation, the meshing and the collision shapes
+	/// counting for the same part.
+	pub(crate) fn ratio(&self) -> f32 {
+		(self.generated + self.meshed + self.collidable) as f32 / (self.requested * 3) as f32
+	}
+
+	fn is_complete(&self) -> bool {
+		self.collidable == self.requested
+	}
+}
+
+pub(crate) struct LoadingScreen {
+	started_at: Instant,
+}
+
+impl LoadingScreen {
+	pub(crate) fn new() -> LoadingScreen {
+		LoadingScreen { started_at: Instant::now() }
+	}
+
+	pub(crate) fn progress(
+		&self,
+		chunk_grid: &ChunkGrid,
+		player_chunk: ChunkCoords,
+	) -> LoadingProgress {
+		let mut progress = LoadingProgress { requested: 0, generated: 0, meshed: 0, collidable: 0 };
+		for chunk_coords in iter_3d_cube_center_radius(player_chunk, SPAWN_AREA_RADIUS + 1) {
+			progress.requested += 1;
+			progress.generated += chunk_grid.is_loaded(chunk_coords) as usize;
+			progress.meshed += chunk_grid.is_meshed(chunk_coords) as usize;
+			progress.collidable += chunk_grid.is_collidable(chunk_coords) as usize;
+		}
+		progress
+	}
+
+	/// Is it time to leave the loading screen and let the player play?
+	/// Returns the time the loading took if it is.
+	pub(crate) fn is_done(&self, progress: &LoadingProgress) -> Option<Duration> {
+		let elapsed = self.started_at.elapsed();
+		if progress.is_complete() {
+			Some(elapsed)
+		} else if elapsed > MAX_LOADING_DURATION {
+			log::warn!(
+				"The spawn area is still not loaded after {} seconds, \
+				leaving the loading screen anyway",
+				MAX_LOADING_DURATION.as_secs()
+			);
+			Some(elapsed)
+		} else {
+			None
+		}
+	}
+}
@@ -0,0 +1,125 @@
+//! Localization of the strings displayed in the interface.
+//!
+//! Each user-facing string is identified by a key, and each language has a file that maps keys
+//! to strings in that language. A language can lack some keys, in which case we fall back
+//! to less specific languages (like from `fr_CA` to `fr`) and then to English.
+
+use std::collections::HashMap;
+
+/// The language that every other language falls back to, it should have all the keys.
+const FALLBACK_LANGUAGE: &str = "en";
+
+/// Languages for which a localization file is embedded in the binary.
+const BUILT_IN_LANGUAGE_FILES: &[(&str, &str)] = &[
+	(
+		"en",
+		include_str!("../assets/localization/en.qwy3_localization"),
+	),
+	(
+		"fr",
+		include_str!("../assets/localization/fr.qwy3_localization"),
+	),
+];
+
+/// A localization file found in this directory takes precedence over a built-in one,
+/// so that languages can be added or tweaked without recompiling.
+const LOCALIZATION_DIRECTORY: &str = "localization";
+
+pub(crate) struct Localization {
+	/// Key-to-string tables, from the most specific language to the fallback language.
+	fallback_chain: Vec<HashMap<String, String>>,
+}
+
+impl Localization {
+	/// Loads the given language (and the languages it falls back to).
+	/// The returned boolean is false if no localization file was found for the language
+	/// (in which case the returned localization is still usable, it just falls back to English).
+	pub(crate) fn load(language: &str) -> (Localization, bool) {
+		let mut fallback_chain = vec![];
+		let mut found_the_asked_language = false;
+		for language_in_chain in fallback_chain_languages(language) {
+			if let Some(table) = load_language_table(&language_in_chain) {
+				if language_in_chain != FALLBACK_LANGUAGE || language == FALLBACK_LANGUAGE {
+					found_the_asked_language = true;
+				}
+				fallback_chain.push(table);
+			}
+		}
+		(Localization { fallback_chain }, found_the_asked_language)
+	}
+
+	/// Returns the string in the current language that is identified by the given key.
+	/// If the key is unknown to all the languages in the fallback chain then the key itself
+	/// is returned, which is better than nothing and helps in spotting the missing key.
+	pub(crate) fn get<'a>(&'a self, key: &'a str) -> &'a str {
+		self
+			.fallback_chain
+			.iter()
+			.find_map(|table| table.get(key))
+			.map(|string| string.as_str())
+			.unwrap_or(key)
+	}
+
+	/// Same as `get` but with `{name}`-like placeholders replaced by the given values.
+	pub(crate) fn get_with_args(
+		&self,
+		key: &str,
+		args: &[(&str, &dyn std::fmt::Display)],
+	) -> String {
+		let mut string = self.get(key).to_string();
+		for (name, value) in args {
+			string = string.replace(&format!("{{{name}}}"), &value.to_string());
+		}
+		string
+	}
+}
+
+/// The languages to look into for a key, in order, when looking for it in the given language.
+fn fallback_chain_languages(language: &str) -> Vec<String> {
+	let mut languages = vec![];
+	// From `fr_CA` we fall back to `fr`.
+	let mut language = language.to_string();
+	loop {
+		languages.push(language.clone());
+		match language.rfind(['_', '-']) {
+			Some(index) => language.truncate(index),
+			None => break,
+		}
+	}
+	if !languages.iter().any(|language| language == FALLBACK_LANGUAGE) {
+		languages.push(FALLBACK_LANGUAGE.to_string());
+	}
+	languages
+}
+
+fn load_language_table(language: &str) -> Option<HashMap<String, String>> {
+	let file_path = format!("{LOCALIZATION_DIRECTORY}/{language}.qwy3_localization");
+	let content = std::fs::read_to_string(&file_path).ok().or_else(|| {
+		BUILT_IN_LANGUAGE_FILES
+			.iter()
+			.find(|(built_in_language, _content)| *built_in_language == language)
+			.map(|(_language, content)| content.to_string())
+	})?;
+	Some(parse_localization_file(&content, &file_path))
+}
+
+/// A localization file has one `key: string` per line, and `#` starts a comment line.
+fn parse_localization_file(content: &str, file_path: &str) -> HashMap<String, String> {
+	let mut table = HashMap::new();
+	for (line_index, line) in content.lines().enumerate() {
+		let line_number = line_index + 1;
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		if let Some((key, string)) = line.split_once(':') {
+			table.insert(key.trim().to_string(), string.trim().replace("\\n", "\n"));
+		} else {
+			log::warn!(
+				"In file \"{file_path}\" at line {line_number}: \
+				Expected a \"key: string\" line"
+			);
+		}
+	}
+	table
+}
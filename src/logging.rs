@@ -0,0 +1,155 @@
+//! Logging (with the `log` crate macros) to stdout and to the log lines of the interface,
+//! with levels of verbosity that can be set per module (even while running with the
+//! `log_level` command).
+//!
+//! The `RUST_LOG` environment variable can be used to set the levels at startup, it is
+//! a comma separated list of `module=level` (or just `level` to set the default level).
+
+use std::sync::{Mutex, RwLock};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// The name of the modules of the game in the log targets (like `qwy3::chunks`).
+const CRATE_NAME: &str = "qwy3";
+/// The lines that were not taken by the interface are dropped after that many.
+const MAX_PENDING_INTERFACE_LINES: usize = 100;
+
+struct LevelFilters {
+	/// For the targets that do not match any module.
+	default: LevelFilter,
+	/// Module names and their levels (the most specific match wins).
+	modules: Vec<(String, LevelFilter)>,
+}
+
+impl LevelFilters {
+	fn level_for(&self, target: &str) -> LevelFilter {
+		self
+			.modules
+			.iter()
+			.filter(|(module, _)| target_is_in_module(target, module))
+			.max_by_key(|(module, _)| module.len())
+			.map_or(self.default, |&(_, level)| level)
+	}
+
+	/// The most verbose of the levels, nothing more verbose gets to the logger at all.
+	fn max_level(&self) -> LevelFilter {
+		self.modules.iter().map(|&(_, level)| level).fold(self.default, Ord::max)
+	}
+
+	fn set(&mut self, module: &str, level: LevelFilter) {
+		match self.modules.iter_mut().find(|(name, _)| name == module) {
+			Some((_, module_level)) => *module_level = level,
+			None => self.modules.push((module.to_string(), level)),
+		}
+	}
+}
+
+/// The module can be given with or without the crate name for the modules of the game
+/// (like `chunks` or `qwy3::chunks`), and the submodules are in the module.
+fn target_is_in_module(target: &str, module: &str) -> bool {
+	let is_in = |target: &str| {
+		target.strip_prefix(module).is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+	};
+	is_in(target)
+		|| target.strip_prefix(CRATE_NAME).and_then(|rest| rest.strip_prefix("::")).is_some_and(is_in)
+}
+
+/// A line that was logged, for the interface to display.
+pub(crate) struct LoggedLine {
+	pub(crate) level: Level,
+	pub(crate) text: String,
+}
+
+struct GameLogger {
+	filters: RwLock<LevelFilters>,
+	/// Lines waiting to be taken by the interface.
+	pending_interface_lines: Mutex<Vec<LoggedLine>>,
+}
+
+static LOGGER: GameLogger = GameLogger {
+	filters: RwLock::new(LevelFilters { default: LevelFilter::Warn, modules: Vec::new() }),
+	pending_interface_lines: Mutex::new(Vec::new()),
+};
+
+impl Log for GameLogger {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		metadata.level() <= self.filters.read().unwrap().level_for(metadata.target())
+	}
+
+	fn log(&self, record: &Record) {
+		if !self.enabled(record.metadata()) {
+			return;
+		}
+		let level = record.level();
+		let target = record.target();
+		let message = record.args().to_string();
+		let color = match level {
+			Level::Error => "\x1b[31m",
+			Level::Warn => "\x1b[33m",
+			Level::Info | Level::Debug | Level::Trace => "",
+		};
+		let color_end = if color.is_empty() { "" } else { "\x1b[39m" };
+		println!("{color}[{level} {target}] {message}{color_end}");
+
+		let mut pending_interface_lines = self.pending_interface_lines.lock().unwrap();
+		if pending_interface_lines.len() < MAX_PENDING_INTERFACE_LINES {
+			let text = match target.strip_prefix(CRATE_NAME).and_then(|rest| rest.strip_prefix("::")) {
+				Some(module) => format!("[{module}] {message}"),
+				None => format!("[{target}] {message}"),
+			};
+			pending_interface_lines.push(LoggedLine { level, text });
+		}
+	}
+
+	fn flush(&self) {}
+}
+
+/// Sets up the logger, the game modules log their infos (or debug infos if `verbose`)
+/// and the other crates (like Wgpu) only log their warnings and errors.
+pub(crate) fn init(verbose: bool) {
+	{
+		let mut filters = LOGGER.filters.write().unwrap();
+		let game_level = if verbose {
+			LevelFilter::Debug
+		} else {
+			LevelFilter::Info
+		};
+		filters.set(CRATE_NAME, game_level);
+		if let Ok(rust_log) = std::env::var("RUST_LOG") {
+			for directive in rust_log.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+				match directive.split_once('=') {
+					Some((module, level)) => {
+						if let Ok(level) = level.parse() {
+							filters.set(module, level);
+						}
+					},
+					None => {
+						if let Ok(level) = directive.parse() {
+							filters.default = level;
+						}
+					},
+				}
+			}
+		}
+	}
+	if log::set_logger(&LOGGER).is_ok() {
+		log::set_max_level(LOGGER.filters.read().unwrap().max_level());
+	}
+}
+
+/// Sets the level of the given module (or of all the modules that do not have a level
+/// if `module` is `all`).
+pub(crate) fn set_module_level(module: &str, level: LevelFilter) {
+	let mut filters = LOGGER.filters.write().unwrap();
+	if module == "all" {
+		filters.default = level;
+	} else {
+		filters.set(module, level);
+	}
+	log::set_max_level(filters.max_level());
+}
+
+/// The lines logged since the last call, to be displayed by the interface.
+pub(crate) fn take_lines_for_interface() -> Vec<LoggedLine> {
+	std::mem::take(&mut *LOGGER.pending_interface_lines.lock().unwrap())
+}
@@ -0,0 +1,276 @@
+//! Loot tables, what is dropped when a block is broken or when an entity dies, and what can fill
+//! a chest. A loot table is rolled a number of times, each roll picks one of its weighted entries
+//! that gives some blocks (or nothing).
+//!
+//! The loot tables can be edited without recompiling, in the `loot_tables.qwy3_loot` file
+//! (if there is one) that has lines like `block kinda_leaf 6:nothing 3:kinda_leaf 1:kinda_wood:1-2`,
+//! meaning "breaking a `kinda_leaf` block drops nothing 6 times out of 10, drops itself 3 times
+//! out of 10, and drops 1 to 2 `kinda_wood` blocks 1 time out of 10". The first word is `block`
+//! (followed by a block type name), `entity` (followed by an entity kind, like `hostile`) or
+//! `chest` (followed by any name, for the `fill_chest($name)` command). Then `rolls:<count>`
+//! can set how many times the table is rolled (once by default), and the entries follow as
+//! `<weight>:<block type name or nothing>:<count>`, the count being optional (1 by default).
+//! Counts can be ranges like `1-3`. The tables of the file replace the builtin ones.
+//!
+//! Blocks without a loot table drop themselves, entities without a loot table drop nothing.
+
+use std::{collections::HashMap, ops::RangeInclusive};
+
+use fxhash::FxHashMap;
+use rand::Rng;
+
+use crate::{
+	block_types::{BlockTypeId, BlockTypeTable, CHEST_SLOT_COUNT},
+	chunk_blocks::Block,
+};
+
+/// The file in which the loot tables can be edited (see the module documentation).
+const LOOT_TABLES_FILE_PATH: &str = "loot_tables.qwy3_loot";
+
+/// The loot tables that are there even without a loot tables file,
+/// in the same format as the loot tables file.
+const BUILTIN_LOOT_TABLES: &str = "\
+block kinda_grass 1:kinda_dirt
+block kinda_leaf 4:nothing 1:kinda_leaf
+entity hostile 3:nothing 1:kinda_crystal:1-2
+chest treasure rolls:3-6 4:kinda_wood:1-3 2:kinda_lamp 1:kinda_crystal:1-2 3:nothing
+";
+
+struct LootEntry {
+	/// The chances of this entry being picked are its weight over the sum of the weights.
+	weight: u32,
+	/// `None` for entries that give nothing.
+	block_type_id: Option<BlockTypeId>,
+	count: RangeInclusive<u32>,
+}
+
+pub(crate) struct LootTable {
+	rolls: RangeInclusive<u32>,
+	entries: Vec<LootEntry>,
+}
+
+impl LootTable {
+	/// The blocks given by rolling the table.
+	pub(crate) fn roll(&self, rng: &mut impl Rng) -> Vec<Block> {
+		let mut blocks = vec![];
+		let total_weight: u32 = self.entries.iter().map(|entry| entry.weight).sum();
+		if total_weight == 0 {
+			return blocks;
+		}
+		for _ in 0..rng.gen_range(self.rolls.clone()) {
+			let mut picked_weight = rng.gen_range(0..total_weight);
+			let entry = self
+				.entries
+				.iter()
+				.find(|entry| {
+					let picked = picked_weight < entry.weight;
+					picked_weight = picked_weight.saturating_sub(entry.weight);
+					picked
+				})
+				.unwrap();
+			if let Some(block_type_id) = entry.block_type_id {
+				let count = rng.gen_range(entry.count.clone());
+				blocks.extend((0..count).map(|_| Block::from(block_type_id)));
+			}
+		}
+		blocks
+	}
+}
+
+/// What the loot table of a line of the loot tables file is for.
+enum LootTableTarget {
+	Block(BlockTypeId),
+	Entity(String),
+	Chest(String),
+}
+
+#[derive(Default)]
+pub(crate) struct LootTables {
+	blocks: FxHashMap<BlockTypeId, LootTable>,
+	/// By entity kind name (see `Entity::kind_name`).
+	entities: HashMap<String, LootTable>,
+	chests: HashMap<String, LootTable>,
+}
+
+impl LootTables {
+	/// The builtin loot tables, then the ones of the loot tables file (if any) that replace them.
+	/// The lines that are not valid are skipped (and reported as errors).
+	pub(crate) fn load(block_type_table: &BlockTypeTable) -> LootTables {
+		let mut loot_tables = LootTables::default();
+		loot_tables.add_from_str(BUILTIN_LOOT_TABLES, "builtin loot tables", block_type_table);
+		if let Ok(loot_tables_string) = std::fs::read_to_string(LOOT_TABLES_FILE_PATH) {
+			loot_tables.add_from_str(&loot_tables_string, LOOT_TABLES_FILE_PATH, block_type_table);
+		}
+		loot_tables
+	}
+
+	fn add_from_str(&mut self, string: &str, file_path: &str, block_type_table: &BlockTypeTable) {
+		for (line_index, line) in string.lines().enumerate() {
+			let line_number = line_index + 1;
+			match parse_loot_table_line(line, block_type_table) {
+				Ok(Some((LootTableTarget::Block(block_type_id), table))) => {
+					self.blocks.insert(block_type_id, table);
+				},
+				Ok(Some((LootTableTarget::Entity(kind_name), table))) => {
+					self.entities.insert(kind_name, table);
+				},
+				Ok(Some((LootTableTarget::Chest(name), table))) => {
+					self.chests.insert(name, table);
+				},
+				Ok(None) => {},
+				Err(error) => log::error!("In file \"{file_path}\" at line {line_number}: {error}"),
+			}
+		}
+	}
+
+	/// What breaking (and harvesting) the block drops.
+	pub(crate) fn block_drops(&self, block: Block, rng: &mut impl Rng) -> Vec<Block> {
+		match self.blocks.get(&block.type_id) {
+			Some(table) => table.roll(rng),
+			// The block itself is dropped with its data (like the contents of a chest).
+			None => vec![block],
+		}
+	}
+
+	/// What an entity of the given kind drops when it dies.
+	pub(crate) fn entity_drops(&self, kind_name: &str, rng: &mut impl Rng) -> Vec<Block> {
+		self.entities.get(kind_name).map_or(vec![], |table| table.roll(rng))
+	}
+
+	/// The slots of a chest filled by the chest loot table of the given name, if there is one.
+	/// Each block takes a slot, the blocks that do not fit are lost.
+	pub(crate) fn chest_contents(
+		&self,
+		name: &str,
+		rng: &mut impl Rng,
+	) -> Option<Vec<Option<Block>>> {
+		let mut contents: Vec<Option<Block>> =
+			self.chests.get(name)?.roll(rng).into_iter().map(Some).collect();
+		contents.resize(CHEST_SLOT_COUNT, None);
+		Some(contents)
+	}
+
+	/// The names of the chest loot tables, sorted.
+	pub(crate) fn chest_names(&self) -> Vec<&str> {
+		let mut names: Vec<&str> = self.chests.keys().map(|name| name.as_str()).collect();
+		names.sort();
+		names
+	}
+}
+
+/// A count like `2` or a range of counts like `1-3`.
+fn parse_count_range(string: &str) -> Option<RangeInclusive<u32>> {
+	let (min, max) = string.split_once('-').unwrap_or((string, string));
+	let (min, max) = (min.parse().ok()?, max.parse().ok()?);
+	(min <= max).then_some(min..=max)
+}
+
+/// Parses a line of the loot tables file, `None` for the empty lines and the comments.
+fn parse_loot_table_line(
+	line: &str,
+	block_type_table: &BlockTypeTable,
+) -> Result<Option<(LootTableTarget, LootTable)>, String> {
+	let mut words = line.split_whitespace();
+	let target = match (words.next(), words.next()) {
+		(None, _) => return Ok(None),
+		(Some(word), _) if word.starts_with('#') => return Ok(None),
+		(Some("block"), Some(name)) => LootTableTarget::Block(
+			block_type_table
+				.id_from_name(name)
+				.ok_or_else(|| format!("Unknown block type \"{name}\""))?,
+		),
+		(Some("entity"), Some(kind_name)) => LootTableTarget::Entity(kind_name.to_string()),
+		(Some("chest"), Some(name)) => LootTableTarget::Chest(name.to_string()),
+		(Some(target_kind @ ("block" | "entity" | "chest")), None) => {
+			return Err(format!("Expected a name after \"{target_kind}\""));
+		},
+		(Some(unknown_target_kind), _) => {
+			return Err(format!(
+				"Unknown loot table kind \"{unknown_target_kind}\" (expected block, entity or chest)"
+			));
+		},
+	};
+	let mut table = LootTable { rolls: 1..=1, entries: vec![] };
+	for word in words {
+		if let Some(rolls) = word.strip_prefix("rolls:") {
+			table.rolls =
+				parse_count_range(rolls).ok_or_else(|| format!("Invalid rolls \"{rolls}\""))?;
+			continue;
+		}
+		let invalid = || format!("Expected an entry like \"3:kinda_wood:1-2\" but got \"{word}\"");
+		let mut parts = word.split(':');
+		let (Some(weight), Some(name)) = (parts.next(), parts.next()) else {
+			return Err(invalid());
+		};
+		let weight = weight.parse().map_err(|_| invalid())?;
+		let block_type_id = match name {
+			"nothing" => None,
+			name => Some(
+				block_type_table
+					.id_from_name(name)
+					.ok_or_else(|| format!("Unknown block type \"{name}\""))?,
+			),
+		};
+		let count = match parts.next() {
+			Some(count) => parse_count_range(count).ok_or_else(invalid)?,
+			None => 1..=1,
+		};
+		if parts.next().is_some() {
+			return Err(invalid());
+		}
+		table.entries.push(LootEntry { weight, block_type_id, count });
+	}
+	// Rolling picks a number below the total weight, that must fit in a `u32`.
+	let total_weight =
+		table.entries.iter().try_fold(0u32, |total, entry| total.checked_add(entry.weight));
+	if total_weight.is_none() {
+		return Err("The weights of the entries add up to too much".to_string());
+	}
+	Ok(Some((target, table)))
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::{rngs::SmallRng, SeedableRng};
+
+	use super::*;
+
+	#[test]
+	fn loot_tables_roll_their_entries() {
+		let block_type_table = BlockTypeTable::new();
+		let mut loot_tables = LootTables::default();
+		loot_tables.add_from_str(
+			"entity test_ball rolls:2 1:kinda_wood:3\n\
+			chest empty 1:nothing\n\
+			block kinda_wood 1:kinda_leaf:1-2 1:nothing",
+			"test",
+			&block_type_table,
+		);
+		let mut rng = SmallRng::seed_from_u64(0);
+		let wood_id = block_type_table.kinda_wood_id();
+		let drops = loot_tables.entity_drops("test_ball", &mut rng);
+		assert_eq!(drops.len(), 6);
+		assert!(drops.iter().all(|block| block.type_id == wood_id));
+		assert!(loot_tables.entity_drops("villager", &mut rng).is_empty());
+		let contents = loot_tables.chest_contents("empty", &mut rng).unwrap();
+		assert_eq!(contents.len(), CHEST_SLOT_COUNT);
+		assert!(contents.iter().all(|slot| slot.is_none()));
+		for _ in 0..20 {
+			let drops = loot_tables.block_drops(Block::from(wood_id), &mut rng);
+			assert!(drops.len() <= 2);
+		}
+		// Blocks without a loot table drop themselves.
+		let grass_blades_id = block_type_table.kinda_grass_blades_id();
+		let drops = loot_tables.block_drops(Block::from(grass_blades_id), &mut rng);
+		assert!(drops.len() == 1 && drops[0].type_id == grass_blades_id);
+		assert!(
+			parse_loot_table_line("block kinda_wood 1:unknown_block", &block_type_table).is_err()
+		);
+		assert!(parse_loot_table_line("chest box 1:kinda_wood:3-1", &block_type_table).is_err());
+		assert!(parse_loot_table_line(
+			"chest box 4000000000:kinda_wood 4000000000:nothing",
+			&block_type_table
+		)
+		.is_err());
+	}
+}
@@ -0,0 +1,226 @@
+//! Map export, the loaded world is rendered from above (top-down or isometric) by an orthographic
+//! camera to a large offscreen texture that is saved as an image in the `maps` directory.
+//!
+//! Only the blocks are rendered (see `BlockRenderPipelines::map_export`), lit like in the game,
+//! over a transparent background. The loaded world is about a ball of the loading distance around
+//! the player so the camera frames that ball, and the fog makes the edges of the map fade out.
+
+use bytemuck::Zeroable;
+
+use crate::{
+	camera::{CameraOrthographicSettings, CameraSettings},
+	chunk_instances::ChunkInstances,
+	chunks::ChunkGrid,
+	rendering_init::{make_z_buffer_texture_view, BindingThingy, RenderPipelinesAndBindGroups},
+	shaders::block::WaterReflectionSettingsPod,
+};
+
+/// The format of the map export texture, it is also the format of the pixels of the saved images.
+pub(crate) const MAP_EXPORT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+/// The directory in which the maps are saved.
+const MAPS_DIRECTORY: &str = "maps";
+/// The maps are square images, with at most this many pixels on a side (the limit on the size of
+/// the textures of the device may be lower).
+const MAX_MAP_DIMENSION: u32 = 8192;
+
+/// The direction from which the world is seen in an exported map.
+#[derive(Clone, Copy)]
+pub(crate) enum MapProjection {
+	/// Seen from straight above, with the +Y direction at the top of the image.
+	TopDown,
+	/// Seen from above at the isometric angle (the three axes look the same length).
+	Isometric,
+}
+
+impl MapProjection {
+	pub(crate) fn from_name(name: &str) -> Option<MapProjection> {
+		match name {
+			"top_down" => Some(MapProjection::TopDown),
+			"isometric" => Some(MapProjection::Isometric),
+			_ => None,
+		}
+	}
+
+	pub(crate) fn name(self) -> &'static str {
+		match self {
+			MapProjection::TopDown => "top_down",
+			MapProjection::Isometric => "isometric",
+		}
+	}
+
+	/// The direction in which the camera looks and the vector that points to the top of the image.
+	fn direction_and_up(self) -> (cgmath::Vector3<f32>, cgmath::Vector3<f32>) {
+		match self {
+			MapProjection::TopDown => (cgmath::vec3(0.0, 0.0, -1.0), cgmath::vec3(0.0, 1.0, 0.0)),
+			MapProjection::Isometric => {
+				use cgmath::InnerSpace;
+				(
+					cgmath::vec3(1.0, 1.0, -1.0).normalize(),
+					cgmath::vec3(0.0, 0.0, 1.0),
+				)
+			},
+		}
+	}
+}
+
+pub(crate) struct DataForMapExport<'a> {
+	pub(crate) device: &'a wgpu::Device,
+	pub(crate) queue: &'a wgpu::Queue,
+	pub(crate) rendering: &'a RenderPipelinesAndBindGroups,
+	pub(crate) chunk_grid: &'a ChunkGrid,
+	/// Must be up to date with the chunk meshes of `chunk_grid`.
+	pub(crate) chunk_instances: &'a ChunkInstances,
+	pub(crate) map_export_camera_matrix_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) water_reflection_settings_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) z_buffer_format: wgpu::TextureFormat,
+	/// The center of the exported area.
+	pub(crate) center: cgmath::Point3<f32>,
+	/// The radius of the exported area, in blocks.
+	pub(crate) radius: f32,
+}
+
+impl<'a> DataForMapExport<'a> {
+	/// Renders the map and saves it (blocking until it is done), returns the path of the image
+	/// and its size (in pixels on a side).
+	pub(crate) fn export(
+		&self,
+		projection: MapProjection,
+		pixels_per_block: u32,
+	) -> Result<(std::path::PathBuf, u32), String> {
+		let max_dimension = self.device.limits().max_texture_dimension_2d.min(MAX_MAP_DIMENSION);
+		let side =
+			((self.radius * 2.0 * pixels_per_block as f32).round() as u32).clamp(1, max_dimension);
+
+		let (direction, up) = projection.direction_and_up();
+		let camera_settings = CameraOrthographicSettings {
+			up_direction: up,
+			width: self.radius * 2.0,
+			height: self.radius * 2.0,
+			depth: self.radius * 2.5,
+		};
+		let view_projection_matrix =
+			camera_settings.view_projection_matrix(self.center, direction, up);
+		self.queue.write_buffer(
+			&self.map_export_camera_matrix_thingy.resource,
+			0,
+			bytemuck::cast_slice(&[view_projection_matrix]),
+		);
+		// The water reflection is not rendered for the map camera. The settings are written again
+		// for the next frame anyway.
+		self.queue.write_buffer(
+			&self.water_reflection_settings_thingy.resource,
+			0,
+			bytemuck::cast_slice(&[WaterReflectionSettingsPod::zeroed()]),
+		);
+
+		let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+			label: Some("Map Export Texture"),
+			size: wgpu::Extent3d { width: side, height: side, depth_or_array_layers: 1 },
+			mip_level_count: 1,
+			sample_count: 1,
+			dimension: wgpu::TextureDimension::D2,
+			format: MAP_EXPORT_FORMAT,
+			view_formats: &[],
+			usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+		});
+		let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+		let z_buffer_view =
+			make_z_buffer_texture_view(self.device, self.z_buffer_format, side, side, 1);
+		// The rows of pixels copied to a buffer must be aligned.
+		let bytes_per_row = (side * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+		let readback_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("Map Export Readback Buffer"),
+			size: bytes_per_row as u64 * side as u64,
+			usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+			mapped_at_creation: false,
+		});
+
+		let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+			label: Some("Map Export Encoder"),
+		});
+		{
+			// Reverse-Z, the depth buffer is cleared to 0 (see `CameraSettings::view_projection_matrix`).
+			let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+				label: Some("Render Pass to render the blocks to the map export"),
+				color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+					view: &view,
+					resolve_target: None,
+					ops: wgpu::Operations {
+						load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+						store: wgpu::StoreOp::Store,
+					},
+				})],
+				depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+					view: &z_buffer_view,
+					depth_ops: Some(wgpu::Operations {
+						load: wgpu::LoadOp::Clear(0.0),
+						store: wgpu::StoreOp::Discard,
+					}),
+					stencil_ops: None,
+				}),
+				timestamp_writes: None,
+				occlusion_query_set: None,
+			});
+			render_pass.set_pipeline(&self.rendering.block_map_export_render_pipeline);
+			render_pass.set_bind_group(0, &self.rendering.block_map_export_bind_group, &[]);
+			render_pass.set_bind_group(1, self.chunk_instances.bind_group(), &[]);
+			for (mesh_index, mesh) in self.chunk_grid.iter_chunk_meshes().enumerate() {
+				let mesh_index = mesh_index as u32;
+				render_pass.set_vertex_buffer(0, mesh.block_vertex_buffer.slice(..));
+				render_pass.draw(0..mesh.block_vertex_count, mesh_index..(mesh_index + 1));
+			}
+		}
+		encoder.copy_texture_to_buffer(
+			texture.as_image_copy(),
+			wgpu::ImageCopyBuffer {
+				buffer: &readback_buffer,
+				layout: wgpu::ImageDataLayout {
+					offset: 0,
+					bytes_per_row: Some(bytes_per_row),
+					rows_per_image: Some(side),
+				},
+			},
+			wgpu::Extent3d { width: side, height: side, depth_or_array_layers: 1 },
+		);
+		self.queue.submit(std::iter::once(encoder.finish()));
+
+		let buffer_slice = readback_buffer.slice(..);
+		let (sender, receiver) = std::sync::mpsc::channel();
+		buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+			let _ = sender.send(result);
+		});
+		self.device.poll(wgpu::Maintain::Wait);
+		receiver
+			.recv()
+			.map_err(|error| error.to_string())?
+			.map_err(|error| format!("Could not read the map back from the GPU: {error}"))?;
+		let mut image = image::RgbaImage::new(side, side);
+		{
+			let data = buffer_slice.get_mapped_range();
+			for (y, row) in data.chunks_exact(bytes_per_row as usize).enumerate() {
+				let row_start = y * side as usize * 4;
+				image.as_mut()[row_start..(row_start + side as usize * 4)]
+					.copy_from_slice(&row[..(side as usize * 4)]);
+			}
+		}
+		readback_buffer.unmap();
+
+		let path = unused_map_path(projection);
+		std::fs::create_dir_all(MAPS_DIRECTORY)
+			.map_err(|error| format!("Could not create \"{MAPS_DIRECTORY}\": {error}"))?;
+		image
+			.save_with_format(&path, image::ImageFormat::Png)
+			.map_err(|error| format!("Could not write \"{}\": {error}", path.display()))?;
+		Ok((path, side))
+	}
+}
+
+/// `maps/<projection>_<number>.png` with the first number that is not already taken.
+fn unused_map_path(projection: MapProjection) -> std::path::PathBuf {
+	(1..)
+		.map(|number| {
+			std::path::Path::new(MAPS_DIRECTORY).join(format!("{}_{number}.png", projection.name()))
+		})
+		.find(|path| !path.exists())
+		.unwrap()
+}
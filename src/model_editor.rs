@@ -0,0 +1,251 @@
+//! The model editor displays a model (see `models`) in the world, where the parts of the model
+//! can be moved, scaled and recolored one by one. The selected part has a gizmo (its box and three
+//! axis lines, the selected axis being brighter), the mouse wheel moves or scales the selected part
+//! along the selected axis, or changes the color component of the selected axis (x is red,
+//! y is green and z is blue). The result can be exported to a model file, to be loaded by
+//! creatures.
+
+use cgmath::EuclideanSpace;
+
+use crate::{
+	coords::{AlignedBox, NonOrientedAxis},
+	entities::ForPartManipulation,
+	entity_parts::{colored_cube::ColoredCubePartKind, PartHandler, PartTables},
+	localization::Localization,
+	models::{delete_parts, Model, ModelPart},
+	shaders::simple_line::SimpleLineVertexPod,
+	simple_meshes::SimpleLineMesh,
+};
+
+/// One notch of the mouse wheel moves or scales a part by that much (in blocks).
+const LENGTH_STEP: f32 = 1.0 / 16.0;
+/// One notch of the mouse wheel changes a color component by that much.
+const COLOR_STEP: i32 = 15;
+
+/// What the mouse wheel does to the selected part.
+#[derive(Clone, Copy)]
+enum GizmoMode {
+	Move,
+	Scale,
+	Recolor,
+}
+
+impl GizmoMode {
+	fn next(self) -> GizmoMode {
+		match self {
+			GizmoMode::Move => GizmoMode::Scale,
+			GizmoMode::Scale => GizmoMode::Recolor,
+			GizmoMode::Recolor => GizmoMode::Move,
+		}
+	}
+
+	fn localization_key(self) -> &'static str {
+		match self {
+			GizmoMode::Move => "model_editor.mode_move",
+			GizmoMode::Scale => "model_editor.mode_scale",
+			GizmoMode::Recolor => "model_editor.mode_recolor",
+		}
+	}
+}
+
+struct EditedModel {
+	/// The name of the model file that the model is exported to.
+	name: String,
+	model: Model,
+	/// Where the origin of the model is in the world.
+	origin: cgmath::Point3<f32>,
+	selected_part_index: usize,
+	gizmo_mode: GizmoMode,
+	gizmo_axis: NonOrientedAxis,
+	parts: Vec<PartHandler<ColoredCubePartKind>>,
+}
+
+pub(crate) struct ModelEditor {
+	edited: Option<EditedModel>,
+}
+
+impl ModelEditor {
+	pub(crate) fn new() -> ModelEditor {
+		ModelEditor { edited: None }
+	}
+
+	pub(crate) fn is_open(&self) -> bool {
+		self.edited.is_some()
+	}
+
+	/// Starts editing the given model (that will be exported to the model file of the given name),
+	/// it is displayed with its origin at `origin`. The model that was edited (if any) is closed
+	/// without being exported.
+	pub(crate) fn open(
+		&mut self,
+		name: String,
+		model: Model,
+		origin: cgmath::Point3<f32>,
+		part_tables: &PartTables,
+	) {
+		self.close(part_tables);
+		self.edited = Some(EditedModel {
+			name,
+			model,
+			origin,
+			selected_part_index: 0,
+			gizmo_mode: GizmoMode::Move,
+			gizmo_axis: NonOrientedAxis::X,
+			parts: vec![],
+		});
+	}
+
+	pub(crate) fn close(&mut self, part_tables: &PartTables) {
+		if let Some(edited) = self.edited.take() {
+			delete_parts(&edited.parts, part_tables);
+		}
+	}
+
+	pub(crate) fn select_next_part(&mut self) {
+		if let Some(edited) = self.edited.as_mut() {
+			edited.selected_part_index = (edited.selected_part_index + 1) % edited.model.parts.len();
+		}
+	}
+
+	pub(crate) fn select_next_axis(&mut self) {
+		if let Some(edited) = self.edited.as_mut() {
+			edited.gizmo_axis = match edited.gizmo_axis {
+				NonOrientedAxis::X => NonOrientedAxis::Y,
+				NonOrientedAxis::Y => NonOrientedAxis::Z,
+				NonOrientedAxis::Z => NonOrientedAxis::X,
+			};
+		}
+	}
+
+	pub(crate) fn select_next_mode(&mut self) {
+		if let Some(edited) = self.edited.as_mut() {
+			edited.gizmo_mode = edited.gizmo_mode.next();
+		}
+	}
+
+	/// Applies the given number of mouse wheel notches to the selected part, see `GizmoMode`.
+	pub(crate) fn apply_wheel(&mut self, notches: f32, part_tables: &PartTables) {
+		let Some(edited) = self.edited.as_mut() else {
+			return;
+		};
+		let axis_i = edited.gizmo_axis.index();
+		let part = &mut edited.model.parts[edited.selected_part_index];
+		match edited.gizmo_mode {
+			GizmoMode::Move => part.offset[axis_i] += notches * LENGTH_STEP,
+			GizmoMode::Scale => {
+				part.scale[axis_i] = (part.scale[axis_i] + notches * LENGTH_STEP).max(LENGTH_STEP);
+			},
+			GizmoMode::Recolor => {
+				let component = part.color[axis_i] as i32 + (notches * COLOR_STEP as f32) as i32;
+				part.color[axis_i] = component.clamp(0, 255) as u8;
+				// The color of a part instance is chosen when it is allocated,
+				// the recolored part will be allocated again with its new color.
+				if let Some(part_handler) = edited.parts.get_mut(edited.selected_part_index) {
+					part_handler.delete(&mut part_tables.colored_cubes.lock().unwrap());
+					*part_handler = PartHandler::default();
+				}
+			},
+		}
+	}
+
+	/// Adds a copy of the selected part with the given name, it becomes the selected part.
+	pub(crate) fn add_part(&mut self, name: String) -> bool {
+		let Some(edited) = self.edited.as_mut() else {
+			return false;
+		};
+		let selected_part = &edited.model.parts[edited.selected_part_index];
+		let new_part = ModelPart { name, ..selected_part.clone() };
+		edited.model.parts.push(new_part);
+		edited.selected_part_index = edited.model.parts.len() - 1;
+		true
+	}
+
+	/// Removes the selected part, unless it is the only part left.
+	pub(crate) fn remove_selected_part(&mut self, part_tables: &PartTables) -> bool {
+		let Some(edited) = self.edited.as_mut() else {
+			return false;
+		};
+		if edited.model.parts.len() <= 1 {
+			return false;
+		}
+		edited.model.parts.remove(edited.selected_part_index);
+		if edited.selected_part_index < edited.parts.len() {
+			let part_handler = edited.parts.remove(edited.selected_part_index);
+			part_handler.delete(&mut part_tables.colored_cubes.lock().unwrap());
+		}
+		edited.selected_part_index %= edited.model.parts.len();
+		true
+	}
+
+	/// The name and the model being edited, if any.
+	pub(crate) fn edited_model(&self) -> Option<(&str, &Model)> {
+		self.edited.as_ref().map(|edited| (edited.name.as_str(), &edited.model))
+	}
+
+	/// Makes the parts of the edited model render it as it is.
+	pub(crate) fn update_parts(&mut self, part_manipulation: &ForPartManipulation) {
+		if let Some(edited) = self.edited.as_mut() {
+			let transform = cgmath::Matrix4::<f32>::from_translation(edited.origin.to_vec());
+			edited.model.update_parts(&mut edited.parts, transform, part_manipulation);
+		}
+	}
+
+	/// The lines of the box of the model, and of the gizmo on the selected part.
+	pub(crate) fn gizmo_line_vertices(&self) -> Vec<SimpleLineVertexPod> {
+		let Some(edited) = self.edited.as_ref() else {
+			return vec![];
+		};
+		let mut vertices = SimpleLineMesh::vertices_for_aligned_box(
+			&AlignedBox { pos: edited.origin, dims: edited.model.dims() },
+			[0.5, 0.5, 0.5],
+		);
+		let part = &edited.model.parts[edited.selected_part_index];
+		let part_center = edited.origin + part.offset;
+		vertices.extend(SimpleLineMesh::vertices_for_aligned_box(
+			&AlignedBox { pos: part_center, dims: part.scale },
+			[1.0, 1.0, 0.0],
+		));
+		for axis in NonOrientedAxis::iter_over_the_three_possible_axes() {
+			let axis_i = axis.index();
+			let is_selected = axis == edited.gizmo_axis;
+			let mut color = [0.0, 0.0, 0.0];
+			color[axis_i] = if is_selected { 1.0 } else { 0.35 };
+			let mut direction = cgmath::vec3(0.0, 0.0, 0.0);
+			direction[axis_i] = part.scale[axis_i] / 2.0 + if is_selected { 0.6 } else { 0.3 };
+			vertices.push(SimpleLineVertexPod { position: part_center.into(), color });
+			vertices.push(SimpleLineVertexPod { position: (part_center + direction).into(), color });
+		}
+		vertices
+	}
+
+	/// Lines of text that tell what is being edited, for the interface.
+	pub(crate) fn info_lines(&self, localization: &Localization) -> Vec<String> {
+		let Some(edited) = self.edited.as_ref() else {
+			return vec![];
+		};
+		let part = &edited.model.parts[edited.selected_part_index];
+		let [r, g, b] = part.color;
+		vec![
+			localization.get_with_args("model_editor.title", &[("name", &edited.name)]),
+			localization.get_with_args(
+				"model_editor.part",
+				&[
+					("name", &part.name),
+					("index", &(edited.selected_part_index + 1)),
+					("count", &edited.model.parts.len()),
+				],
+			),
+			localization.get_with_args(
+				"model_editor.gizmo",
+				&[
+					(
+						"mode",
+						&localization.get(edited.gizmo_mode.localization_key()),
+					),
+					("axis", &edited.gizmo_axis.as_char()),
+					("color", &format!("{r},{g},{b}")),
+				],
+			),
+		]
+	}
+}
@@ -0,0 +1,216 @@
+//! Models of creatures made of colored cube parts, that can be authored in the game with the model
+//! editor (see `model_editor`) and saved to model files, so that new creatures can be made without
+//! recompiling. A model file `models/<name>.qwy3_model` has lines like
+//! `part head 0.55 0 0.45 0.5 0.4 0.45 120 80 50`, meaning "a part named `head` centered at
+//! (0.55, 0, 0.45), scaled by (0.5, 0.4, 0.45), of color (120, 80, 50)". The offsets are in the
+//! frame of the creature (x is forward and z is up) from the center of its box.
+
+use cgmath::EuclideanSpace;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	entities::ForPartManipulation,
+	entity_parts::{
+		colored_cube::{ColoredCubePartKind, PartColoredCubeInstanceData},
+		PartHandler, PartInstance, PartTables,
+	},
+};
+
+/// The directory in which the model files are.
+const MODELS_DIRECTORY: &str = "models";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct ModelPart {
+	pub(crate) name: String,
+	/// Position of the center of the part from the center of the box of the creature.
+	pub(crate) offset: cgmath::Vector3<f32>,
+	/// Dimensions of the part (it is a cube of edge 1 scaled by this).
+	pub(crate) scale: cgmath::Vector3<f32>,
+	pub(crate) color: [u8; 3],
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Model {
+	pub(crate) parts: Vec<ModelPart>,
+}
+
+impl Model {
+	/// A humanoid to start from (a body and a head, like a villager), for new models.
+	pub(crate) fn default_rig() -> Model {
+		let part = |name: &str, offset: [f32; 3], scale: [f32; 3], color| ModelPart {
+			name: name.to_string(),
+			offset: offset.into(),
+			scale: scale.into(),
+			color,
+		};
+		Model {
+			parts: vec![
+				part("body", [0.0, 0.0, -0.3], [0.35, 0.55, 1.1], [120, 80, 50]),
+				part(
+					"head",
+					[0.0, 0.0, 0.55],
+					[0.45, 0.45, 0.45],
+					[230, 190, 150],
+				),
+			],
+		}
+	}
+
+	fn file_path(name: &str) -> std::path::PathBuf {
+		std::path::Path::new(MODELS_DIRECTORY).join(format!("{name}.qwy3_model"))
+	}
+
+	pub(crate) fn file_exists(name: &str) -> bool {
+		Model::file_path(name).exists()
+	}
+
+	/// Reads the model file of the given name.
+	pub(crate) fn load(name: &str) -> Result<Model, String> {
+		let path = Model::file_path(name);
+		let model_string = std::fs::read_to_string(&path)
+			.map_err(|error| format!("Could not read \"{}\": {error}", path.display()))?;
+		let mut parts = vec![];
+		for (line_index, line) in model_string.lines().enumerate() {
+			let line_number = line_index + 1;
+			let words: Vec<_> = line.split_whitespace().collect();
+			match words.first() {
+				Some(&"part") => {
+					let error = || {
+						format!(
+							"In file \"{}\" at line {line_number}: Expected a name, \
+							3 offset coords, 3 scale factors and 3 color components after \"part\"",
+							path.display()
+						)
+					};
+					if words.len() != 11 {
+						return Err(error());
+					}
+					let floats: Vec<f32> = words[2..8]
+						.iter()
+						.map(|word| word.parse())
+						.collect::<Result<_, _>>()
+						.map_err(|_| error())?;
+					let color: Vec<u8> = words[8..11]
+						.iter()
+						.map(|word| word.parse())
+						.collect::<Result<_, _>>()
+						.map_err(|_| error())?;
+					parts.push(ModelPart {
+						name: words[1].to_string(),
+						offset: cgmath::vec3(floats[0], floats[1], floats[2]),
+						scale: cgmath::vec3(floats[3], floats[4], floats[5]),
+						color: [color[0], color[1], color[2]],
+					});
+				},
+				Some(word) if word.starts_with('#') => {},
+				None => {},
+				Some(unknown_command_name) => {
+					return Err(format!(
+						"In file \"{}\" at line {line_number}: \
+						Command name \"{unknown_command_name}\" is unknown",
+						path.display()
+					));
+				},
+			}
+		}
+		if parts.is_empty() {
+			return Err(format!("The model in \"{}\" has no parts", path.display()));
+		}
+		Ok(Model { parts })
+	}
+
+	/// Writes the model file of the given name, returns its path.
+	pub(crate) fn save(&self, name: &str) -> Result<std::path::PathBuf, String> {
+		let path = Model::file_path(name);
+		let mut model_string =
+			"# part <name> <offset x y z> <scale x y z> <color r g b>\n".to_string();
+		for part in self.parts.iter() {
+			let ModelPart { name, offset, scale, color } = part;
+			model_string += &format!(
+				"part {name} {} {} {} {} {} {} {} {} {}\n",
+				offset.x, offset.y, offset.z, scale.x, scale.y, scale.z, color[0], color[1], color[2],
+			);
+		}
+		std::fs::create_dir_all(MODELS_DIRECTORY)
+			.and_then(|()| std::fs::write(&path, model_string))
+			.map_err(|error| format!("Could not write \"{}\": {error}", path.display()))?;
+		Ok(path)
+	}
+
+	/// Dimensions of the box of a creature of this model, the smallest box centered on the origin
+	/// of the model that contains all the parts.
+	pub(crate) fn dims(&self) -> cgmath::Vector3<f32> {
+		let mut half_dims = cgmath::vec3(0.1f32, 0.1, 0.1);
+		for part in self.parts.iter() {
+			for axis_i in 0..3 {
+				let extent = part.offset[axis_i].abs() + part.scale[axis_i] / 2.0;
+				half_dims[axis_i] = half_dims[axis_i].max(extent);
+			}
+		}
+		half_dims * 2.0
+	}
+
+	/// Makes the given part handlers (one per part of the model, as many as needed are added or
+	/// removed) render the model with its origin transformed by `transform`.
+	pub(crate) fn update_parts(
+		&self,
+		parts: &mut Vec<PartHandler<ColoredCubePartKind>>,
+		transform: cgmath::Matrix4<f32>,
+		part_manipulation: &ForPartManipulation,
+	) {
+		self.update_parts_posed(
+			parts,
+			transform,
+			&|_part| cgmath::SquareMatrix::identity(),
+			part_manipulation,
+		);
+	}
+
+	/// Same as `update_parts` but each part is also transformed by what `pose` gives for it
+	/// (in the frame of the model, before `transform`), to animate the model.
+	pub(crate) fn update_parts_posed(
+		&self,
+		parts: &mut Vec<PartHandler<ColoredCubePartKind>>,
+		transform: cgmath::Matrix4<f32>,
+		pose: &dyn Fn(&ModelPart) -> cgmath::Matrix4<f32>,
+		part_manipulation: &ForPartManipulation,
+	) {
+		let mut colored_cubes = part_manipulation.part_tables.colored_cubes.lock().unwrap();
+		for extra_part in parts.drain(self.parts.len().min(parts.len())..) {
+			extra_part.delete(&mut colored_cubes);
+		}
+		parts.resize_with(self.parts.len(), PartHandler::default);
+		for (part, model_part) in parts.iter_mut().zip(self.parts.iter()) {
+			let model_matrix = transform
+				* pose(model_part)
+				* cgmath::Matrix4::<f32>::from_translation(model_part.offset)
+				* cgmath::Matrix4::<f32>::from_nonuniform_scale(
+					model_part.scale.x,
+					model_part.scale.y,
+					model_part.scale.z,
+				);
+			part.ensure_is_allocated(&mut colored_cubes, || {
+				let coloring_offset = part_manipulation
+					.texture_mapping_and_coloring_table
+					.get_offset_of_cube_coloring_uni(
+						model_part.color,
+						&part_manipulation.texturing_and_coloring_array_thingy,
+						&part_manipulation.queue,
+					);
+				let pos = cgmath::Point3::from_vec(model_matrix.w.truncate());
+				PartColoredCubeInstanceData::new(pos, coloring_offset).into_pod()
+			});
+			part.modify_instance(&mut colored_cubes, |instance| {
+				instance.set_model_matrix(&model_matrix);
+			});
+		}
+	}
+}
+
+/// Deletes the parts that `Model::update_parts` allocated.
+pub(crate) fn delete_parts(parts: &[PartHandler<ColoredCubePartKind>], part_tables: &PartTables) {
+	let mut colored_cubes = part_tables.colored_cubes.lock().unwrap();
+	for part in parts {
+		part.delete(&mut colored_cubes);
+	}
+}
@@ -0,0 +1,166 @@
+//! Procedural composition of ambient background music.
+//!
+//! A track is composed from a seed and a mood (the biome and altitude of the player), it is a slow
+//! chord progression played by a soft pad with a sparse melody played by a bell on top.
+//! The same world seed and mood always give the same track.
+
+use std::f32::consts::TAU;
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{audio::SAMPLE_RATE, world_gen::Biome};
+
+/// Length of one chord of the progression, in seconds.
+const CHORD_DURATION: f32 = 6.0;
+/// Number of chords in a track, the track then loops.
+const CHORD_COUNT: usize = 4;
+
+/// What the surroundings of the player feel like, the music is composed accordingly.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct MusicMood {
+	/// Altitude divided in bands of `ALTITUDE_BAND_HEIGHT` blocks.
+	altitude_band: i32,
+	/// `None` in worlds whose generator has no biomes.
+	biome: Option<Biome>,
+}
+
+const ALTITUDE_BAND_HEIGHT: f32 = 40.0;
+
+impl MusicMood {
+	pub(crate) fn new(altitude: f32, biome: Option<Biome>) -> MusicMood {
+		MusicMood {
+			altitude_band: (altitude / ALTITUDE_BAND_HEIGHT).floor() as i32,
+			biome,
+		}
+	}
+
+	/// Intervals (in semitones from the root) of the scale used for the whole track.
+	fn scale(self) -> &'static [i32] {
+		if self.altitude_band < 0 {
+			// Natural minor, underground is a bit gloomy.
+			&[0, 2, 3, 5, 7, 8, 10]
+		} else if self.altitude_band >= 2 {
+			// Lydian, feels airy up there.
+			&[0, 2, 4, 6, 7, 9, 11]
+		} else {
+			match self.biome {
+				Some(Biome::Forest) => &[0, 3, 5, 7, 10], // Minor pentatonic.
+				Some(Biome::Desert) => &[0, 1, 4, 5, 7, 8, 10], // Phrygian dominant, hot and dry.
+				Some(Biome::Plains) | None => &[0, 2, 4, 7, 9], // Major pentatonic.
+			}
+		}
+	}
+
+	fn seed(self, world_seed: i32) -> u64 {
+		let biome_index = self.biome.map_or(0, |biome| biome as u64 + 1);
+		(world_seed as u32 as u64) << 32 | (self.altitude_band as u32 as u64) << 8 | biome_index
+	}
+}
+
+#[derive(Clone, Copy)]
+enum Instrument {
+	/// Slow attack and release, a few slightly detuned sines.
+	Pad,
+	/// Fast attack and long decay, with a bit of inharmonic overtone.
+	Bell,
+}
+
+struct Note {
+	/// Start time in seconds from the beginning of the track.
+	start: f32,
+	duration: f32,
+	frequency: f32,
+	amplitude: f32,
+	instrument: Instrument,
+}
+
+/// A composed track, rendered to mono samples, meant to be looped.
+pub(crate) struct MusicTrack {
+	pub(crate) mood: MusicMood,
+	pub(crate) samples: Vec<f32>,
+}
+
+/// Composes and renders a track. This takes a little while, it should not be called
+/// on the main thread.
+pub(crate) fn compose_track(world_seed: i32, mood: MusicMood) -> MusicTrack {
+	let mut rng = SmallRng::seed_from_u64(mood.seed(world_seed));
+	let scale = mood.scale();
+	// The root is somewhere around A2, lower when deeper.
+	let root_semitone = -24 + rng.gen_range(-5..=5) + mood.altitude_band.clamp(-3, 3) * 2;
+	let note_frequency = |degree: i32| -> f32 {
+		let octave = degree.div_euclid(scale.len() as i32);
+		let semitone = scale[degree.rem_euclid(scale.len() as i32) as usize] + octave * 12;
+		440.0 * 2.0f32.powf((root_semitone + semitone) as f32 / 12.0)
+	};
+
+	let mut notes = vec![];
+	let mut chord_root_degree = 0;
+	let mut melody_degree = scale.len() as i32 * 2;
+	for chord_index in 0..CHORD_COUNT {
+		let chord_start = chord_index as f32 * CHORD_DURATION;
+
+		// Chord made of scale degrees stacked in thirds-ish, held for the whole chord duration
+		// (and a bit more so that chords blend into each other).
+		for stacked in [0, 2, 4] {
+			notes.push(Note {
+				start: chord_start,
+				duration: CHORD_DURATION + 1.5,
+				frequency: note_frequency(chord_root_degree + stacked),
+				amplitude: 0.08,
+				instrument: Instrument::Pad,
+			});
+		}
+
+		// Sparse melody, a random walk on the scale with rests.
+		let mut time = chord_start + rng.gen_range(0.0..1.0);
+		while time < chord_start + CHORD_DURATION {
+			if rng.gen_bool(0.6) {
+				melody_degree = (melody_degree + rng.gen_range(-2..=2))
+					.clamp(scale.len() as i32, scale.len() as i32 * 3);
+				notes.push(Note {
+					start: time,
+					duration: 2.5,
+					frequency: note_frequency(melody_degree),
+					amplitude: rng.gen_range(0.04..0.08),
+					instrument: Instrument::Bell,
+				});
+			}
+			time += [0.75, 1.0, 1.5][rng.gen_range(0..3)];
+		}
+
+		chord_root_degree = (chord_root_degree + rng.gen_range(2..=5)) % scale.len() as i32;
+	}
+
+	let track_duration = CHORD_COUNT as f32 * CHORD_DURATION;
+	let sample_count = (track_duration * SAMPLE_RATE as f32) as usize;
+	let mut samples = vec![0.0; sample_count];
+	for note in notes.iter() {
+		render_note(note, &mut samples);
+	}
+	MusicTrack { mood, samples }
+}
+
+/// Adds the note to the samples, the parts of the note that go past the end of the track
+/// wrap around to the beginning so that the track loops seamlessly.
+fn render_note(note: &Note, samples: &mut [f32]) {
+	let start_index = (note.start * SAMPLE_RATE as f32) as usize;
+	let note_sample_count = (note.duration * SAMPLE_RATE as f32) as usize;
+	for i in 0..note_sample_count {
+		let t = i as f32 / SAMPLE_RATE as f32;
+		let phase = t * note.frequency * TAU;
+		let value = match note.instrument {
+			Instrument::Pad => {
+				let attack = (t / 1.5).min(1.0);
+				let release = ((note.duration - t) / 1.5).min(1.0);
+				let detuned = (phase * 1.003).sin() + (phase * 0.997).sin();
+				(phase.sin() + detuned * 0.5 + (phase * 2.0).sin() * 0.15) * attack * release
+			},
+			Instrument::Bell => {
+				let attack = (t / 0.01).min(1.0);
+				let decay = (-t * 2.5).exp();
+				(phase.sin() + (phase * 2.76).sin() * 0.3 * (-t * 6.0).exp()) * attack * decay
+			},
+		};
+		samples[(start_index + i) % samples.len()] += value * note.amplitude;
+	}
+}
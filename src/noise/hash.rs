@@ -0,0 +1,141 @@
+//! White noise, integer coordinates (or any integer channels) are hashed into values that
+//! look random and unrelated even for neighboring coordinates (as opposed to the smooth noise
+//! of `OctavedNoise` where close coords get close values).
+//!
+//! The same channels fed in the same order always give the same value, so feeding the seed
+//! first and then the coords (and maybe some more channels to get several independent values
+//! for the same coords) is the way to get deterministic randomness in world generation.
+
+use std::ops::BitXor;
+
+/// Same as the one used by FxHash, the hashing step is also the same
+/// (this used to be an `FxHasher64` and the hashes must not change).
+const SEED: u64 = 0x517cc1b727220a95;
+const ROTATE: u32 = 5;
+
+/// A hash state that can be extended with more channels, it is cheap to copy so that a base hash
+/// (like one with only the seed) can be extended in different ways.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub(crate) struct Hash {
+	state: u64,
+}
+
+impl Hash {
+	pub(crate) fn new(channels: &[i32]) -> Hash {
+		Hash::default().with(channels)
+	}
+
+	pub(crate) fn with_channel(self, channel: i32) -> Hash {
+		let word = channel as u32 as u64;
+		Hash {
+			state: self.state.rotate_left(ROTATE).bitxor(word).wrapping_mul(SEED),
+		}
+	}
+
+	pub(crate) fn with(self, channels: &[i32]) -> Hash {
+		channels.iter().fold(self, |hash, &channel| hash.with_channel(channel))
+	}
+
+	pub(crate) fn with_coords_3d(self, coords: cgmath::Point3<i32>) -> Hash {
+		self.with(&[coords.x, coords.y, coords.z])
+	}
+
+	/// The raw state, its low bits are not that well mixed, use `bits` to get good bits.
+	pub(crate) fn state(self) -> u64 {
+		self.state
+	}
+
+	/// All the bits of the state mixed into all the bits of the result (a SplitMix64 finalizer),
+	/// so that any subset of the bits is as good as any other.
+	pub(crate) fn bits(self) -> u64 {
+		let mut x = self.state;
+		x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+		x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+		x ^ (x >> 31)
+	}
+
+	/// Uniformly distributed between 0.0 (included) and 1.0 (excluded).
+	pub(crate) fn unit(self) -> f32 {
+		(self.bits() >> 40) as f32 / (1u64 << 24) as f32
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Pearson's chi-squared statistic of the bucket counts against a uniform distribution.
+	fn chi_squared(buckets: &[u32]) -> f64 {
+		let total: u32 = buckets.iter().sum();
+		let expected = total as f64 / buckets.len() as f64;
+		buckets.iter().map(|&count| (count as f64 - expected).powi(2) / expected).sum()
+	}
+
+	/// With 63 degrees of freedom, the statistic is above this with a probability of about 0.1%.
+	const CHI_SQUARED_64_BUCKETS_THRESHOLD: f64 = 103.4;
+
+	#[test]
+	fn unit_is_uniform_over_neighboring_coords() {
+		let base = Hash::new(&[42, 7]);
+		let mut buckets = [0; 64];
+		for x in -40..40 {
+			for y in -40..40 {
+				for z in -4..4 {
+					let value = base.with_coords_3d(cgmath::point3(x, y, z)).unit();
+					assert!((0.0..1.0).contains(&value));
+					buckets[(value * 64.0) as usize] += 1;
+				}
+			}
+		}
+		assert!(chi_squared(&buckets) < CHI_SQUARED_64_BUCKETS_THRESHOLD);
+	}
+
+	#[test]
+	fn low_bits_are_uniform_over_channels() {
+		let base = Hash::new(&[-3]);
+		let mut buckets = [0; 64];
+		for channel in 0..51200 {
+			buckets[(base.with_channel(channel).bits() % 64) as usize] += 1;
+		}
+		assert!(chi_squared(&buckets) < CHI_SQUARED_64_BUCKETS_THRESHOLD);
+	}
+
+	#[test]
+	fn seeds_give_unrelated_values() {
+		// The values for the same coords with two different seeds should be independent,
+		// so the bucket of their difference should be uniform too.
+		let seed_a = Hash::new(&[1]);
+		let seed_b = Hash::new(&[2]);
+		let mut buckets = [0; 64];
+		for x in 0..256 {
+			for y in 0..200 {
+				let coords = cgmath::point3(x, y, 0);
+				let a = seed_a.with_coords_3d(coords).unit();
+				let b = seed_b.with_coords_3d(coords).unit();
+				buckets[((a - b).rem_euclid(1.0) * 64.0) as usize % 64] += 1;
+			}
+		}
+		assert!(chi_squared(&buckets) < CHI_SQUARED_64_BUCKETS_THRESHOLD);
+	}
+
+	#[test]
+	fn same_as_fxhash() {
+		use std::hash::Hasher;
+		let mut hasher = fxhash::FxHasher64::default();
+		for channel in [0, 1, -1, i32::MAX, i32::MIN, 12345] {
+			hasher.write_i32(channel);
+		}
+		let hash = Hash::new(&[0, 1, -1, i32::MAX, i32::MIN, 12345]);
+		assert_eq!(hash.state(), hasher.finish());
+	}
+
+	#[test]
+	fn same_channels_same_value() {
+		let coords = cgmath::point3(5, -8, 13);
+		assert_eq!(
+			Hash::new(&[1]).with_coords_3d(coords),
+			Hash::new(&[1, 5, -8, 13])
+		);
+		assert_ne!(Hash::new(&[1, 2]), Hash::new(&[2, 1]));
+	}
+}
@@ -0,0 +1,341 @@
+//! N-dimensional noise!
+//!
+//! This is a very "we have noise at home" implementation, slow and all.
+//! Though, maybe not as slow now as it once was.
+//!
+//! The idea is that we consider an N-dimensional grid of nodes
+//! where nodes are at every interger coordinates
+//! and each node is given a noise value via `raw_noise_node`.
+//! Then, for points that don't fall on nodes, they fall in N-dimensional
+//! (hyper)cubic cells of 2^N nodes as vertices,
+//! we then interpolate the nodes' noise values with `raw_noise`.
+//! Then we can do the usual stuff and add octaves with `octaves_noise`.
+//!
+//! Octaves can also be shaped into ridges or billows (see `FractalVariant`),
+//! and coordinates can be deformed before sampling anything with a `DomainWarp`.
+//!
+//! For white noise (no interpolation, just a random-looking value per integer coords),
+//! see `hash`.
+
+pub(crate) mod hash;
+
+use std::f32::consts::TAU;
+
+use crate::coords::AngularDirection;
+
+use self::hash::Hash;
+
+fn positive_fract(x: f32) -> f32 {
+	x - f32::floor(x)
+}
+
+fn unit_to_i32(x: f32) -> i32 {
+	(x * (i32::MAX as f32 - i32::MIN as f32) + i32::MIN as f32).round() as i32
+}
+
+fn _smoothstep(x: f32) -> f32 {
+	if x < 0.0 {
+		0.0
+	} else if 1.0 < x {
+		1.0
+	} else {
+		x * x * (3.0 - 2.0 * x)
+	}
+}
+
+fn smoothcos(x: f32) -> f32 {
+	if x < 0.0 {
+		0.0
+	} else if 1.0 < x {
+		1.0
+	} else {
+		use std::f32::consts::TAU;
+		(f32::cos((1.0 - x) * TAU / 2.0) + 1.0) / 2.0
+	}
+}
+
+/// If `x == x_inf` then the interpolation result is `dst_inf`,
+/// if `x == x_sup` then the interpolation result is `dst_sup`,
+/// and any value in betwee will lead to some interpolation between `dst_inf` and `dst_sup`.
+/// `x` is expected to be between `x_inf` (included) and `x_sup` (also included).
+/// The given `smoothing` function is used to smooth out the curve when x is near its edges.
+#[inline]
+fn interpolate(
+	smoothing: &dyn Fn(f32) -> f32,
+	x: f32,
+	x_inf: f32,
+	x_sup: f32,
+	dst_inf: f32,
+	dst_sup: f32,
+) -> f32 {
+	let ratio = (x - x_inf) / (x_sup - x_inf);
+	let smooth_ratio = smoothing(ratio);
+	dst_inf + smooth_ratio * (dst_sup - dst_inf)
+}
+
+#[inline]
+fn raw_noise_node(hash: Hash) -> f32 {
+	f32::cos(hash.state() as f32) * 0.5 + 0.5
+}
+
+#[inline]
+fn raw_noise_rec(xs: &[f32], factor: f32, hash: Hash) -> f32 {
+	if !xs.is_empty() {
+		let x = xs[0] * factor;
+		// For every continuous coordinate, we interpolate between
+		// the two closest discreet node values on that axis.
+		// In one dimension (with N <= x < N+1), it looks like this:
+		// ... --|------#----|--> ...
+		//       N      x   N+1
+		//      inf         sup
+		// And we can do that by calling this recursively
+		// with N and N+1 as additional channel parameters.
+		let channel_inf = f32::floor(x) as i32;
+		let sub_noise_inf = raw_noise_rec(&xs[1..], factor, hash.with_channel(channel_inf));
+		let channel_sup = channel_inf + 1;
+		let sub_noise_sup = raw_noise_rec(&xs[1..], factor, hash.with_channel(channel_sup));
+		let x_fract = positive_fract(x);
+		interpolate(&smoothcos, x_fract, 0.0, 1.0, sub_noise_inf, sub_noise_sup)
+	} else {
+		// No more continuous coordinates, we are on a node and can get its noise value.
+		raw_noise_node(hash)
+	}
+}
+
+#[inline]
+fn raw_noise(xs: &[f32], factor: f32, hash: Hash) -> f32 {
+	raw_noise_rec(xs, factor, hash)
+}
+
+fn octaves_noise(number_of_octaves: u32, xs: &[f32], hash: Hash) -> f32 {
+	let mut value_sum = 0.0;
+	let mut coef_sum = 0.0;
+	let mut coef = 1.0;
+	let mut factor = 1.0;
+	for _i in 0..number_of_octaves {
+		value_sum += coef * raw_noise(xs, factor, hash);
+		coef_sum += coef;
+		coef /= 2.0;
+		factor *= 2.0;
+	}
+	value_sum / coef_sum
+}
+
+/// Ridged multifractal, each octave is folded around the middle value so that it makes sharp
+/// ridges where it crosses it, and the detail of an octave is weighted by the value of the
+/// previous octaves so that the detail is on the ridges and not in the valleys.
+fn ridged_octaves_noise(number_of_octaves: u32, xs: &[f32], hash: Hash) -> f32 {
+	let mut value_sum = 0.0;
+	let mut coef_sum = 0.0;
+	let mut coef = 1.0;
+	let mut factor = 1.0;
+	let mut weight = 1.0;
+	for _i in 0..number_of_octaves {
+		let ridge = 1.0 - (raw_noise(xs, factor, hash) * 2.0 - 1.0).abs();
+		let ridge = ridge * ridge * weight;
+		value_sum += coef * ridge;
+		coef_sum += coef;
+		weight = (ridge * 2.0).clamp(0.0, 1.0);
+		coef /= 2.0;
+		factor *= 2.0;
+	}
+	value_sum / coef_sum
+}
+
+/// Each octave is folded around the middle value so that it makes round bumps (billows)
+/// separated by sharp creases.
+fn billow_octaves_noise(number_of_octaves: u32, xs: &[f32], hash: Hash) -> f32 {
+	let mut value_sum = 0.0;
+	let mut coef_sum = 0.0;
+	let mut coef = 1.0;
+	let mut factor = 1.0;
+	for _i in 0..number_of_octaves {
+		value_sum += coef * (raw_noise(xs, factor, hash) * 2.0 - 1.0).abs();
+		coef_sum += coef;
+		coef /= 2.0;
+		factor *= 2.0;
+	}
+	value_sum / coef_sum
+}
+
+/// How the octaves of an `OctavedNoise` are combined,
+/// all the variants give values between 0 and 1.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum FractalVariant {
+	/// The octaves are just summed (with smaller weights for the higher frequencies).
+	Standard,
+	/// Sharp ridges with detail concentrated on them, good for mountain ranges.
+	Ridged,
+	/// Round bumps separated by creases, good for hills or clouds.
+	Billow,
+}
+
+pub(crate) struct OctavedNoise {
+	number_of_octaves: u32,
+	base_hash: Hash,
+	variant: FractalVariant,
+}
+
+impl OctavedNoise {
+	pub(crate) fn new(number_of_octaves: u32, base_channels: Vec<i32>) -> OctavedNoise {
+		let base_hash = Hash::new(&base_channels);
+		OctavedNoise { number_of_octaves, base_hash, variant: FractalVariant::Standard }
+	}
+
+	pub(crate) fn new_ridged(number_of_octaves: u32, base_channels: Vec<i32>) -> OctavedNoise {
+		OctavedNoise::new(number_of_octaves, base_channels).with_variant(FractalVariant::Ridged)
+	}
+
+	pub(crate) fn new_billow(number_of_octaves: u32, base_channels: Vec<i32>) -> OctavedNoise {
+		OctavedNoise::new(number_of_octaves, base_channels).with_variant(FractalVariant::Billow)
+	}
+
+	pub(crate) fn with_variant(self, variant: FractalVariant) -> OctavedNoise {
+		OctavedNoise { variant, ..self }
+	}
+
+	/// The hash of the base channels, to get white noise from the same seed.
+	pub(crate) fn hash(&self) -> Hash {
+		self.base_hash
+	}
+
+	pub(crate) fn sample(&self, xs: &[f32], additional_channels: &[&[i32]]) -> f32 {
+		let hash =
+			additional_channels.iter().fold(self.base_hash, |hash, channels| hash.with(channels));
+		match self.variant {
+			FractalVariant::Standard => octaves_noise(self.number_of_octaves, xs, hash),
+			FractalVariant::Ridged => ridged_octaves_noise(self.number_of_octaves, xs, hash),
+			FractalVariant::Billow => billow_octaves_noise(self.number_of_octaves, xs, hash),
+		}
+	}
+
+	pub(crate) fn sample_2d_1d(
+		&self,
+		coords: cgmath::Point2<f32>,
+		additional_channels: &[i32],
+	) -> f32 {
+		let xs: [f32; 2] = coords.into();
+		self.sample(&xs, &[additional_channels])
+	}
+	pub(crate) fn sample_3d_1d(
+		&self,
+		coords: cgmath::Point3<f32>,
+		additional_channels: &[i32],
+	) -> f32 {
+		let xs: [f32; 3] = coords.into();
+		self.sample(&xs, &[additional_channels])
+	}
+	pub(crate) fn _sample_3d_3d(
+		&self,
+		coords: cgmath::Point3<f32>,
+		additional_channels: &[i32],
+	) -> cgmath::Point3<f32> {
+		let xs: [f32; 3] = coords.into();
+		let x = self.sample(&xs, &[additional_channels, &[1]]);
+		let y = self.sample(&xs, &[additional_channels, &[2]]);
+		let z = self.sample(&xs, &[additional_channels, &[3]]);
+		cgmath::point3(x, y, z)
+	}
+	pub(crate) fn sample_i1d_1d(&self, coord: i32, additional_channels: &[i32]) -> f32 {
+		self.sample(&[], &[additional_channels, &[coord]])
+	}
+	pub(crate) fn sample_i1d_i1d(&self, coord: i32, additional_channels: &[i32]) -> i32 {
+		unit_to_i32(self.sample_i1d_1d(coord, additional_channels))
+	}
+	pub(crate) fn sample_i2d_1d(
+		&self,
+		coords: cgmath::Point2<i32>,
+		additional_channels: &[i32],
+	) -> f32 {
+		let xs: [i32; 2] = coords.into();
+		self.sample(&[], &[additional_channels, &xs])
+	}
+	pub(crate) fn sample_i3d_1d(
+		&self,
+		coords: cgmath::Point3<i32>,
+		additional_channels: &[i32],
+	) -> f32 {
+		let xs: [i32; 3] = coords.into();
+		self.sample(&[], &[additional_channels, &xs])
+	}
+	pub(crate) fn sample_i3d_i1d(
+		&self,
+		coords: cgmath::Point3<i32>,
+		additional_channels: &[i32],
+	) -> i32 {
+		unit_to_i32(self.sample_i3d_1d(coords, additional_channels))
+	}
+	pub(crate) fn sample_i3d_3d(
+		&self,
+		coords: cgmath::Point3<i32>,
+		additional_channels: &[i32],
+	) -> cgmath::Point3<f32> {
+		let xs: [i32; 3] = coords.into();
+		let x = self.sample(&[], &[additional_channels, &xs, &[1]]);
+		let y = self.sample(&[], &[additional_channels, &xs, &[2]]);
+		let z = self.sample(&[], &[additional_channels, &xs, &[3]]);
+		cgmath::point3(x, y, z)
+	}
+}
+
+/// Domain warping, coordinates are displaced by a vector that varies smoothly with them,
+/// so that anything sampled at the displaced coordinates (noise or any shape really)
+/// gets deformed (straight tubes wiggle, flat planes get bumpy, etc.).
+pub(crate) struct DomainWarp {
+	noise_angle_horizontal: OctavedNoise,
+	noise_angle_vertical: OctavedNoise,
+	noise_length: OctavedNoise,
+	/// Size (in blocks) of the features of the deformation.
+	scale: f32,
+	/// Maximum length of the displacement on each axis.
+	strength: cgmath::Vector3<f32>,
+}
+
+impl DomainWarp {
+	/// The displacement has a direction given by the first two noises and a length given by
+	/// the third one, up to `strength`.
+	pub(crate) fn new(
+		noise_angle_horizontal: OctavedNoise,
+		noise_angle_vertical: OctavedNoise,
+		noise_length: OctavedNoise,
+		scale: f32,
+		strength: f32,
+	) -> DomainWarp {
+		DomainWarp {
+			noise_angle_horizontal,
+			noise_angle_vertical,
+			noise_length,
+			scale,
+			strength: cgmath::vec3(strength, strength, strength),
+		}
+	}
+
+	/// Uses a different strength for the horizontal and vertical displacements,
+	/// for example to deform terrain a lot horizontally but keep it mostly flat.
+	pub(crate) fn with_vertical_strength(self, vertical_strength: f32) -> DomainWarp {
+		DomainWarp {
+			strength: cgmath::vec3(self.strength.x, self.strength.y, vertical_strength),
+			..self
+		}
+	}
+
+	pub(crate) fn displacement_3d(&self, coords: cgmath::Point3<f32>) -> cgmath::Vector3<f32> {
+		let scaled_coords = coords / self.scale;
+		let angle_horizontal = self.noise_angle_horizontal.sample_3d_1d(scaled_coords, &[]);
+		let angle_vertical = self.noise_angle_vertical.sample_3d_1d(scaled_coords, &[]);
+		let length = self.noise_length.sample_3d_1d(scaled_coords, &[]);
+		let direction =
+			AngularDirection::from_angles(angle_horizontal * TAU, angle_vertical * (TAU / 2.0))
+				.to_vec3();
+		let displacement = direction * length;
+		cgmath::vec3(
+			displacement.x * self.strength.x,
+			displacement.y * self.strength.y,
+			displacement.z * self.strength.z,
+		)
+	}
+
+	pub(crate) fn warp_3d(&self, coords: cgmath::Point3<f32>) -> cgmath::Point3<f32> {
+		coords + self.displacement_3d(coords)
+	}
+}
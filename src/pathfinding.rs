@@ -0,0 +1,160 @@
+//! Short path searches for walking entities (like hostiles chasing the player), so that they
+//! go around walls and climb up or drop down ledges instead of walking straight into obstacles.
+//!
+//! The search is local (it gives up after a few hundred cells) and is done on the cells where
+//! a walker about two blocks tall can stand, which are two free cells above an opaque block.
+
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use fxhash::FxHashMap;
+
+use crate::coords::BlockCoords;
+
+/// The search gives up after looking at that many cells (the walker then goes towards
+/// the closest cell to the destination that it found).
+const MAX_EXPLORED_CELLS: usize = 400;
+/// The highest ledge (in blocks) that a walker is willing to drop from.
+const MAX_DROP: i32 = 3;
+
+/// The first cell to walk to on a path from the cell `from` to the cell `to` (the cells are
+/// where the feet are), or on a path to the reachable cell closest to `to` if `to` cannot be
+/// reached (or is too far). A step can go one block up (by jumping) or a few blocks down.
+///
+/// `None` if there is nowhere to go or if one cannot stand at `from` (when in the air, etc.).
+/// `is_opaque` gives `None` for the blocks that are not loaded, that are not walked in nor on.
+pub(crate) fn first_step_towards(
+	from: BlockCoords,
+	to: BlockCoords,
+	is_opaque: impl Fn(BlockCoords) -> Option<bool>,
+) -> Option<BlockCoords> {
+	let up = cgmath::vec3(0, 0, 1);
+	let is_free = |coords: BlockCoords| is_opaque(coords) == Some(false);
+	let is_solid = |coords: BlockCoords| is_opaque(coords) == Some(true);
+	let can_stand_at =
+		|coords: BlockCoords| is_free(coords) && is_free(coords + up) && is_solid(coords - up);
+	if !can_stand_at(from) {
+		return None;
+	}
+	let distance_to_destination = |coords: BlockCoords| -> i32 {
+		(coords.x - to.x).abs() + (coords.y - to.y).abs() + (coords.z - to.z).abs()
+	};
+	// Where one lands when walking off a ledge into the given cell.
+	let landing = |coords: BlockCoords| -> Option<BlockCoords> {
+		(1..=MAX_DROP)
+			.map(|drop| coords - up * drop)
+			.take_while(|&below| is_free(below))
+			.find(|&below| is_solid(below - up))
+	};
+
+	// A* with one step costing one, the cells are in the heap as tuples (that are `Ord`).
+	let mut came_from: FxHashMap<BlockCoords, BlockCoords> = FxHashMap::default();
+	let mut cost: FxHashMap<BlockCoords, i32> = FxHashMap::default();
+	let mut heap = BinaryHeap::new();
+	cost.insert(from, 0);
+	heap.push(Reverse((
+		distance_to_destination(from),
+		0,
+		(from.x, from.y, from.z),
+	)));
+	let mut closest = from;
+	let mut explored_count = 0;
+	while let Some(Reverse((_estimate, coords_cost, coords))) = heap.pop() {
+		let coords = BlockCoords::from(coords);
+		if cost[&coords] < coords_cost {
+			// Already explored from a shorter path.
+			continue;
+		}
+		if distance_to_destination(coords) < distance_to_destination(closest) {
+			closest = coords;
+		}
+		explored_count += 1;
+		if coords == to || explored_count >= MAX_EXPLORED_CELLS {
+			break;
+		}
+		for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+			let side = coords + cgmath::vec3(dx, dy, 0);
+			let next = if can_stand_at(side) {
+				Some(side)
+			} else if is_free(coords + up * 2) && can_stand_at(side + up) {
+				Some(side + up)
+			} else if is_free(side) && is_free(side + up) {
+				landing(side)
+			} else {
+				None
+			};
+			let Some(next) = next else {
+				continue;
+			};
+			let next_cost = coords_cost + 1;
+			if cost.get(&next).is_some_and(|&cost| cost <= next_cost) {
+				continue;
+			}
+			cost.insert(next, next_cost);
+			came_from.insert(next, coords);
+			let estimate = next_cost + distance_to_destination(next);
+			heap.push(Reverse((estimate, next_cost, (next.x, next.y, next.z))));
+		}
+	}
+
+	// Going back from the end of the path to its first step.
+	let mut step = closest;
+	while let Some(&previous) = came_from.get(&step) {
+		if previous == from {
+			return Some(step);
+		}
+		step = previous;
+	}
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A flat ground at z = 0 (the cells where one stands are at z = 1) with a wall along
+	/// the x axis at y = 0 that has a gap at x = 6, and a one block step at x = -3.
+	fn is_opaque(coords: BlockCoords) -> Option<bool> {
+		let is_wall = coords.y == 0 && coords.x != 6 && (1..=4).contains(&coords.z);
+		let is_step = coords.x <= -3 && coords.z == 1 && coords.y > 0;
+		Some(coords.z <= 0 || is_wall || is_step)
+	}
+
+	/// The number of steps taken to walk from `from` to `to`, following the first steps.
+	fn walk(mut from: BlockCoords, to: BlockCoords) -> Option<usize> {
+		for step_count in 0..50 {
+			if from == to {
+				return Some(step_count);
+			}
+			from = first_step_towards(from, to, is_opaque)?;
+		}
+		None
+	}
+
+	#[test]
+	fn paths_go_around_walls_and_up_and_down_steps() {
+		// Straight ahead when nothing is in the way.
+		assert_eq!(
+			walk(cgmath::point3(0, 3, 1), cgmath::point3(0, 8, 1)),
+			Some(5)
+		);
+		// Through the gap in the wall (to x = 6 and back).
+		assert_eq!(
+			walk(cgmath::point3(0, 3, 1), cgmath::point3(0, -3, 1)),
+			Some(18)
+		);
+		// Up and down the step.
+		assert_eq!(
+			walk(cgmath::point3(-2, 3, 1), cgmath::point3(-8, 3, 2)),
+			Some(6)
+		);
+		assert_eq!(
+			walk(cgmath::point3(-8, 3, 2), cgmath::point3(0, 3, 1)),
+			Some(8)
+		);
+		// Not standing on anything.
+		assert_eq!(
+			first_step_towards(cgmath::point3(0, 3, 5), cgmath::point3(0, 8, 1), is_opaque),
+			None
+		);
+	}
+}
@@ -0,0 +1,388 @@
+//! Photo mode, for screenshots and cinematics. The simulation is frozen (like when the game is
+//! paused), the camera is detached from the player and moves freely, it can be rolled and its
+//! field of view can be changed, and the interface is hidden (except while typing a command).
+//!
+//! Keyframes (camera positions and orientations) can be added to define a camera path that is
+//! then played back, the camera going through the keyframes along a Catmull-Rom spline.
+//! A path can also be recorded, a screenshot is then taken at every frame (the path advancing by
+//! a fixed time step per frame, so that the frames make a smooth video whatever the framerate).
+
+use std::{
+	f32::consts::TAU,
+	path::{Path, PathBuf},
+	time::Duration,
+};
+
+use cgmath::{InnerSpace, Rotation, Rotation3};
+
+use crate::{camera::CameraPerspectiveSettings, coords::AngularDirection};
+
+/// The directory in which the screenshots (and the frames of the recorded camera paths) are saved.
+const PHOTOS_DIRECTORY: &str = "photos";
+/// Speed (in blocks per second) of the camera in photo mode.
+const CAMERA_SPEED: f32 = 20.0;
+/// Range of the field of view (vertical, in radians).
+const MIN_FIELD_OF_VIEW_Y: f32 = TAU / 360.0 * 5.0;
+const MAX_FIELD_OF_VIEW_Y: f32 = TAU / 360.0 * 150.0;
+
+/// Where the camera is and how it looks at the world.
+#[derive(Clone, Copy)]
+pub(crate) struct CameraView {
+	pub(crate) position: cgmath::Point3<f32>,
+	pub(crate) direction: AngularDirection,
+	/// Rotation (in radians) of the camera around its direction, clockwise.
+	pub(crate) roll: f32,
+	/// Angle (in radians) of view on the vertical axis.
+	pub(crate) field_of_view_y: f32,
+}
+
+impl CameraView {
+	/// The vector from the bottom to the top of the screen, in the world.
+	pub(crate) fn up_vector(&self) -> cgmath::Vector3<f32> {
+		let unrolled_up = self.direction.add_to_vertical_angle(-TAU / 4.0).to_vec3();
+		let rotation =
+			cgmath::Basis3::from_axis_angle(self.direction.to_vec3(), cgmath::Rad(self.roll));
+		rotation.rotate_vector(unrolled_up)
+	}
+
+	/// The given camera settings with the roll and the field of view of this view.
+	pub(crate) fn camera_settings(
+		&self,
+		settings: &CameraPerspectiveSettings,
+	) -> CameraPerspectiveSettings {
+		CameraPerspectiveSettings {
+			up_direction: self.up_vector(),
+			aspect_ratio: settings.aspect_ratio,
+			field_of_view_y: self.field_of_view_y,
+			near_plane: settings.near_plane,
+			far_plane: settings.far_plane,
+		}
+	}
+}
+
+/// A camera path being played back.
+struct CameraPathPlayback {
+	/// Where we are on the path, in keyframes (`1.5` is halfway between the second and the third).
+	progress: f32,
+	/// Time it takes to go from a keyframe to the next.
+	duration_per_keyframe: Duration,
+	recording: Option<CameraPathRecording>,
+}
+
+/// A screenshot is taken at every frame of the camera path being played.
+struct CameraPathRecording {
+	directory: PathBuf,
+	/// The path advances by this much at every frame, whatever the time the frame took.
+	frame_duration: Duration,
+	frame_count: u32,
+}
+
+pub(crate) struct PhotoMode {
+	/// The direction the player was looking at when entering the photo mode,
+	/// it is given back when leaving.
+	player_camera_direction: AngularDirection,
+	free_camera: CameraView,
+	keyframes: Vec<CameraView>,
+	playback: Option<CameraPathPlayback>,
+}
+
+impl PhotoMode {
+	pub(crate) fn new(
+		camera_position: cgmath::Point3<f32>,
+		camera_direction: AngularDirection,
+		camera_settings: &CameraPerspectiveSettings,
+	) -> PhotoMode {
+		PhotoMode {
+			player_camera_direction: camera_direction,
+			free_camera: CameraView {
+				position: camera_position,
+				direction: camera_direction,
+				roll: 0.0,
+				field_of_view_y: camera_settings.field_of_view_y,
+			},
+			keyframes: vec![],
+			playback: None,
+		}
+	}
+
+	pub(crate) fn player_camera_direction(&self) -> AngularDirection {
+		self.player_camera_direction
+	}
+
+	pub(crate) fn set_roll(&mut self, roll: f32) {
+		self.free_camera.roll = roll;
+	}
+
+	pub(crate) fn set_field_of_view(&mut self, field_of_view_y: f32) {
+		self.free_camera.field_of_view_y =
+			field_of_view_y.clamp(MIN_FIELD_OF_VIEW_Y, MAX_FIELD_OF_VIEW_Y);
+	}
+
+	/// Zooms in (with positive notches) or out, by changing the field of view.
+	pub(crate) fn zoom(&mut self, notches: f32) {
+		self.set_field_of_view(self.free_camera.field_of_view_y * 0.9f32.powf(notches));
+	}
+
+	/// Adds the current view of the free camera at the end of the camera path,
+	/// returns the number of keyframes.
+	pub(crate) fn add_keyframe(&mut self) -> usize {
+		self.keyframes.push(self.free_camera);
+		self.keyframes.len()
+	}
+
+	pub(crate) fn clear_keyframes(&mut self) {
+		self.keyframes.clear();
+		self.playback = None;
+	}
+
+	/// Plays the camera path from its first keyframe, and records it if `recording_fps` is given.
+	/// Returns the directory in which the frames will be saved if recording.
+	pub(crate) fn play_camera_path(
+		&mut self,
+		duration_per_keyframe: Duration,
+		recording_fps: Option<u32>,
+	) -> Result<Option<PathBuf>, String> {
+		if self.keyframes.len() < 2 {
+			return Err("A camera path needs at least 2 keyframes".to_string());
+		}
+		let recording = match recording_fps {
+			Some(fps) => {
+				let directory = unused_photo_path("camera_path", "");
+				std::fs::create_dir_all(&directory)
+					.map_err(|error| format!("Could not create \"{}\": {error}", directory.display()))?;
+				Some(CameraPathRecording {
+					directory,
+					frame_duration: Duration::from_secs_f32(1.0 / fps.max(1) as f32),
+					frame_count: 0,
+				})
+			},
+			None => None,
+		};
+		let recording_directory = recording.as_ref().map(|recording| recording.directory.clone());
+		self.playback = Some(CameraPathPlayback { progress: 0.0, duration_per_keyframe, recording });
+		Ok(recording_directory)
+	}
+
+	/// Moves the camera (the free camera by the given walking, or along the camera path if one
+	/// is being played) and returns the view for this frame. The free camera looks in the given
+	/// direction, and moving forward follows where it looks so that it can fly up and down.
+	pub(crate) fn advance(
+		&mut self,
+		walking_vector: cgmath::Vector3<f32>,
+		direction: AngularDirection,
+		real_dt: Duration,
+	) -> CameraView {
+		if let Some(playback) = self.playback.as_mut() {
+			let view = view_on_path(&self.keyframes, playback.progress);
+			let dt = playback.recording.as_ref().map_or(real_dt, |recording| recording.frame_duration);
+			playback.progress += dt.as_secs_f32() / playback.duration_per_keyframe.as_secs_f32();
+			return view;
+		}
+
+		let horizontal_forward = direction.to_horizontal().to_vec3();
+		let forward_amount = walking_vector.dot(horizontal_forward);
+		let motion = walking_vector - horizontal_forward * forward_amount
+			+ direction.to_vec3() * forward_amount;
+		let motion = if motion.magnitude() == 0.0 {
+			motion
+		} else {
+			motion.normalize()
+		};
+		self.free_camera.position += motion * CAMERA_SPEED * real_dt.as_secs_f32();
+		self.free_camera.direction = direction;
+		self.free_camera
+	}
+
+	/// Where to save the frame that was just given by `advance`, if the camera path is being
+	/// recorded. When a recorded camera path is over, the end of the recording is logged.
+	pub(crate) fn recorded_frame_path(&mut self) -> Option<PathBuf> {
+		let playback = self.playback.as_mut()?;
+		// The playback ends with the first frame that reaches the last keyframe.
+		let last_keyframe_index = (self.keyframes.len() - 1) as f32;
+		let frame_path = playback.recording.as_mut().map(|recording| {
+			recording.frame_count += 1;
+			recording.directory.join(format!("frame_{:05}.png", recording.frame_count))
+		});
+		if playback.progress > last_keyframe_index {
+			if let Some(recording) = playback.recording.as_ref() {
+				log::info!(
+					"Camera path recorded to \"{}\" ({} frames)",
+					recording.directory.display(),
+					recording.frame_count
+				);
+			}
+			self.playback = None;
+		}
+		frame_path
+	}
+}
+
+/// The view at the given progress along the camera path, which goes through all the keyframes.
+fn view_on_path(keyframes: &[CameraView], progress: f32) -> CameraView {
+	let last_index = keyframes.len() - 1;
+	let progress = progress.clamp(0.0, last_index as f32);
+	let index = (progress.floor() as usize).min(last_index - 1);
+	let t = progress - index as f32;
+	let keyframe = |index: isize| keyframes[index.clamp(0, last_index as isize) as usize];
+	let [a, b, c, d] = [-1, 0, 1, 2].map(|offset| keyframe(index as isize + offset));
+
+	// The horizontal angles are taken the shortest way around from one keyframe to the next.
+	let unwrap = |angle: f32, reference: f32| {
+		reference + (angle - reference + TAU / 2.0).rem_euclid(TAU) - TAU / 2.0
+	};
+	let b_horizontal = b.direction.angle_horizontal;
+	let a_horizontal = unwrap(a.direction.angle_horizontal, b_horizontal);
+	let c_horizontal = unwrap(c.direction.angle_horizontal, b_horizontal);
+	let d_horizontal = unwrap(d.direction.angle_horizontal, c_horizontal);
+
+	let spline = |a: f32, b: f32, c: f32, d: f32| catmull_rom(a, b, c, d, t);
+	CameraView {
+		position: cgmath::point3(
+			spline(a.position.x, b.position.x, c.position.x, d.position.x),
+			spline(a.position.y, b.position.y, c.position.y, d.position.y),
+			spline(a.position.z, b.position.z, c.position.z, d.position.z),
+		),
+		direction: AngularDirection::from_angles(
+			spline(a_horizontal, b_horizontal, c_horizontal, d_horizontal).rem_euclid(TAU),
+			spline(
+				a.direction.angle_vertical,
+				b.direction.angle_vertical,
+				c.direction.angle_vertical,
+				d.direction.angle_vertical,
+			),
+		),
+		roll: spline(a.roll, b.roll, c.roll, d.roll),
+		field_of_view_y: spline(
+			a.field_of_view_y,
+			b.field_of_view_y,
+			c.field_of_view_y,
+			d.field_of_view_y,
+		)
+		.clamp(MIN_FIELD_OF_VIEW_Y, MAX_FIELD_OF_VIEW_Y),
+	}
+}
+
+/// Uniform Catmull-Rom spline, it goes from `b` (at `t` = 0) to `c` (at `t` = 1),
+/// with `a` and `d` giving the tangents.
+fn catmull_rom(a: f32, b: f32, c: f32, d: f32, t: f32) -> f32 {
+	let t2 = t * t;
+	let t3 = t2 * t;
+	0.5 * (2.0 * b
+		+ (c - a) * t
+		+ (2.0 * a - 5.0 * b + 4.0 * c - d) * t2
+		+ (3.0 * b - a - 3.0 * c + d) * t3)
+}
+
+/// Where to save a screenshot, with a number that is not already taken.
+pub(crate) fn unused_screenshot_path() -> PathBuf {
+	unused_photo_path("photo", ".png")
+}
+
+/// `photos/<prefix>_<number><suffix>` with the first number that is not already taken.
+fn unused_photo_path(prefix: &str, suffix: &str) -> PathBuf {
+	(1..)
+		.map(|number| Path::new(PHOTOS_DIRECTORY).join(format!("{prefix}_{number}{suffix}")))
+		.find(|path| !path.exists())
+		.unwrap()
+}
+
+/// Can the window surface be read back to take screenshots?
+pub(crate) fn screenshots_are_supported(
+	window_surface_config: &wgpu::SurfaceConfiguration,
+) -> bool {
+	window_surface_config.usage.contains(wgpu::TextureUsages::COPY_SRC)
+		&& matches!(
+			window_surface_config.format,
+			wgpu::TextureFormat::Rgba8Unorm
+				| wgpu::TextureFormat::Rgba8UnormSrgb
+				| wgpu::TextureFormat::Bgra8Unorm
+				| wgpu::TextureFormat::Bgra8UnormSrgb
+		)
+}
+
+/// Buffer in which a frame is copied to be read back from the GPU and saved as an image.
+pub(crate) struct ScreenshotReadback {
+	buffer: wgpu::Buffer,
+	width: u32,
+	height: u32,
+	bytes_per_row: u32,
+	bgra: bool,
+}
+
+impl ScreenshotReadback {
+	pub(crate) fn new(
+		device: &wgpu::Device,
+		window_surface_config: &wgpu::SurfaceConfiguration,
+	) -> ScreenshotReadback {
+		let width = window_surface_config.width;
+		let height = window_surface_config.height;
+		let bytes_per_row = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+		let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("Screenshot Readback Buffer"),
+			size: bytes_per_row as u64 * height as u64,
+			usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+			mapped_at_creation: false,
+		});
+		let bgra = matches!(
+			window_surface_config.format,
+			wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+		);
+		ScreenshotReadback { buffer, width, height, bytes_per_row, bgra }
+	}
+
+	pub(crate) fn copy_from(&self, encoder: &mut wgpu::CommandEncoder, texture: &wgpu::Texture) {
+		encoder.copy_texture_to_buffer(
+			texture.as_image_copy(),
+			wgpu::ImageCopyBuffer {
+				buffer: &self.buffer,
+				layout: wgpu::ImageDataLayout {
+					offset: 0,
+					bytes_per_row: Some(self.bytes_per_row),
+					rows_per_image: Some(self.height),
+				},
+			},
+			wgpu::Extent3d { width: self.width, height: self.height, depth_or_array_layers: 1 },
+		);
+	}
+
+	/// Must be called after the copy was submitted, waits for it to be done.
+	pub(crate) fn save(&self, device: &wgpu::Device, path: &Path) -> Result<(), String> {
+		let buffer_slice = self.buffer.slice(..);
+		let (sender, receiver) = std::sync::mpsc::channel();
+		buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+			let _ = sender.send(result);
+		});
+		device.poll(wgpu::Maintain::Wait);
+		receiver
+			.recv()
+			.map_err(|error| error.to_string())?
+			.map_err(|error| format!("Could not read the screenshot back from the GPU: {error}"))?;
+		let mut image = image::RgbaImage::new(self.width, self.height);
+		{
+			let data = buffer_slice.get_mapped_range();
+			let row_size = self.width as usize * 4;
+			for (y, row) in data.chunks_exact(self.bytes_per_row as usize).enumerate() {
+				let row_start = y * row_size;
+				image.as_mut()[row_start..(row_start + row_size)].copy_from_slice(&row[..row_size]);
+			}
+		}
+		self.buffer.unmap();
+		if self.bgra {
+			for pixel in image.pixels_mut() {
+				pixel.0.swap(0, 2);
+			}
+		}
+		// The window is not meant to be transparent, whatever ends up in its alpha channel.
+		for pixel in image.pixels_mut() {
+			pixel.0[3] = 255;
+		}
+
+		if let Some(directory) = path.parent() {
+			std::fs::create_dir_all(directory)
+				.map_err(|error| format!("Could not create \"{}\": {error}", directory.display()))?;
+		}
+		image
+			.save_with_format(path, image::ImageFormat::Png)
+			.map_err(|error| format!("Could not write \"{}\": {error}", path.display()))
+	}
+}
@@ -2,7 +2,7 @@ use cgmath::{InnerSpace, Zero};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-	block_types::BlockTypeTable,
+	block_types::{BlockType, BlockTypeTable, GroundResponse, DOOR_PANEL_THICKNESS},
 	chunks::ChunkGrid,
 	coords::{
 		AlignedBox, AxisOrientation, BlockCoords, NonOrientedAxis, OrientedAxis, OrientedFaceCoords,
@@ -18,6 +18,95 @@ pub(crate) struct AlignedPhysBox {
 	motion: cgmath::Vector3<f32>,
 	on_faces: Vec<OrientedFaceCoords>,
 	is_overlapping_blocks: bool,
+	/// If standing on a moving platform, this is the velocity of the platform
+	/// (in blocks per second), the box is carried along.
+	#[serde(skip)]
+	carried_velocity: Option<cgmath::Vector3<f32>>,
+	/// The velocity that the walking actually gives to the box, it follows the walking vector
+	/// more or less quickly depending on the friction of the ground (see `GroundResponse`).
+	#[serde(skip)]
+	walking_velocity: Option<cgmath::Vector3<f32>>,
+}
+
+/// What `AlignedPhysBox::unstuck` did.
+pub(crate) enum UnstuckOutcome {
+	/// The box was not inside matter, it was left where it was.
+	NotStuck,
+	/// The box was moved to free space.
+	Moved,
+	/// There is no free space close enough, the box was left where it was.
+	NoFreeSpaceFound,
+	/// Some blocks the box overlaps are in chunks that are not loaded,
+	/// it cannot be known yet if the box is stuck or not.
+	NotLoaded,
+}
+
+/// How far (in blocks) from a stuck box is free space looked for.
+const UNSTUCK_MAX_HORIZONTAL_DISTANCE: i32 = 6;
+const UNSTUCK_MAX_UPWARD_DISTANCE: i32 = 48;
+const UNSTUCK_MAX_DOWNWARD_DISTANCE: i32 = 6;
+
+/// The box of a moving body that other boxes can stand on (see `stand_on_platforms`).
+/// Only its top matters, it is not solid from the sides or from below.
+#[derive(Clone)]
+pub(crate) struct PlatformSurface {
+	pub(crate) aligned_box: AlignedBox,
+	/// In blocks per second.
+	pub(crate) velocity: cgmath::Vector3<f32>,
+}
+
+impl PlatformSurface {
+	fn top_z(&self) -> f32 {
+		self.aligned_box.pos.z + self.aligned_box.dims.z / 2.0
+	}
+}
+
+/// A box bottom that is at most that much below the top of a platform (after having been above
+/// it) is put back on top of it, this allows to land on platforms that move upwards.
+const PLATFORM_LANDING_TOLERANCE: f32 = 0.3;
+
+/// A box that walks into layer blocks (like snow) steps up on them if their top is at most that
+/// much above its bottom, higher layers block the way.
+const LAYER_MAX_STEP_UP_HEIGHT: f32 = 0.5;
+
+/// A physics step cuts the displacement of a box into sub steps that are at most that long
+/// (in blocks), less than half a block so that a box never moves past a block in one sub step.
+const SUB_STEP_MAX_LENGTH: f32 = 0.49;
+/// Sub steps are also at most that fraction of the smallest dimension of the box.
+const SUB_STEP_MAX_LENGTH_TO_BOX_RATIO: f32 = 0.5;
+
+/// How fast (per second, multiplied by the friction of the ground) does the velocity of walking
+/// get to the walking vector, high enough for normal ground that it is about immediate.
+const WALKING_GRIP_RATE: f32 = 150.0;
+
+/// The z coordinate of the top of the layers of a layer block (like snow), if it is one.
+fn layer_top_z_at(
+	coords: BlockCoords,
+	chunk_grid: &ChunkGrid,
+	block_type_table: &BlockTypeTable,
+) -> Option<f32> {
+	let block = chunk_grid.get_block(coords)?;
+	let is_layer = matches!(
+		block_type_table.get(block.type_id).unwrap(),
+		BlockType::Layer { .. }
+	);
+	is_layer.then(|| coords.z as f32 - 0.5 + block.layer_height())
+}
+
+/// The thin box of the panel of a door or trapdoor, if there is one at the given coords.
+fn door_panel_box_at(
+	coords: BlockCoords,
+	chunk_grid: &ChunkGrid,
+	block_type_table: &BlockTypeTable,
+) -> Option<AlignedBox> {
+	let block = chunk_grid.get_block(coords)?;
+	let side = block.door_panel_side(block_type_table.get(block.type_id).unwrap())?;
+	let axis_i = side.axis.index();
+	let mut pos = coords.map(|x| x as f32);
+	pos[axis_i] += (0.5 - DOOR_PANEL_THICKNESS / 2.0) * side.orientation.sign() as f32;
+	let mut dims = cgmath::vec3(1.0, 1.0, 1.0);
+	dims[axis_i] = DOOR_PANEL_THICKNESS;
+	Some(AlignedBox { pos, dims })
 }
 
 impl AlignedPhysBox {
@@ -27,6 +116,8 @@ impl AlignedPhysBox {
 			motion,
 			on_faces: vec![],
 			is_overlapping_blocks: false,
+			carried_velocity: None,
+			walking_velocity: None,
 		}
 	}
 
@@ -34,10 +125,19 @@ impl AlignedPhysBox {
 		&self.aligned_box
 	}
 	pub(crate) fn on_ground_and_not_overlapping(&self) -> bool {
-		self.on_faces.iter().any(|face| face.direction_to_exterior == OrientedAxis::Z_PLUS)
+		(self.on_faces.iter().any(|face| face.direction_to_exterior == OrientedAxis::Z_PLUS)
+			|| self.carried_velocity.is_some())
 			&& !self.is_overlapping_blocks
 	}
 
+	/// Is there a block face (that we are pressed on) that blocks the way in the given direction?
+	pub(crate) fn is_blocked_horizontally_towards(&self, direction: cgmath::Vector3<f32>) -> bool {
+		self.on_faces.iter().any(|face| {
+			face.direction_to_exterior.axis != NonOrientedAxis::Z
+				&& face.direction_to_exterior.delta().map(|x| x as f32).dot(direction) < 0.0
+		})
+	}
+
 	pub(crate) fn impose_position(&mut self, position: cgmath::Point3<f32>) {
 		self.aligned_box.pos = position;
 		self.on_faces.clear();
@@ -54,6 +154,181 @@ impl AlignedPhysBox {
 		self.motion.y = 0.0;
 	}
 
+	/// Distance constraint, if the box is further than `max_distance` from `anchor` then it is
+	/// brought back at that distance and loses the component of its motion that goes away from it.
+	pub(crate) fn constrain_max_distance_to(
+		&mut self,
+		anchor: cgmath::Point3<f32>,
+		max_distance: f32,
+	) {
+		let offset = self.aligned_box.pos - anchor;
+		let distance = offset.magnitude();
+		if distance <= max_distance {
+			return;
+		}
+		let direction = offset / distance;
+		self.aligned_box.pos = anchor + direction * max_distance;
+		let motion_away = self.motion.dot(direction);
+		if motion_away > 0.0 {
+			self.motion -= direction * motion_away;
+		}
+	}
+
+	/// Must be called after each physics step (`previous_pos` being the position before the step).
+	/// If the box landed on (or is still on) one of the given platforms then it is put on top of it
+	/// and will be carried by it during the next steps. If it just left a platform (jumped off
+	/// or walked off) then it keeps the velocity of that platform as its own motion, so that
+	/// jumping off of a moving platform does not feel like hitting a wall.
+	pub(crate) fn stand_on_platforms(
+		&mut self,
+		platforms: &[PlatformSurface],
+		previous_pos: cgmath::Point3<f32>,
+	) {
+		let half_height = self.aligned_box.dims.z / 2.0;
+		let bottom_z = self.aligned_box.pos.z - half_height;
+		let previous_bottom_z = previous_pos.z - half_height;
+		let moving_up_by_itself = self.motion.z > 0.0;
+		let platform_below = platforms
+			.iter()
+			.filter(|platform| {
+				let overlaps_horizontally = [0, 1].into_iter().all(|i| {
+					(self.aligned_box.pos[i] - platform.aligned_box.pos[i]).abs()
+						< (self.aligned_box.dims[i] + platform.aligned_box.dims[i]) / 2.0
+				});
+				let top_z = platform.top_z();
+				overlaps_horizontally
+					&& bottom_z <= top_z + 0.01
+					&& previous_bottom_z >= top_z - PLATFORM_LANDING_TOLERANCE
+			})
+			.max_by(|a, b| a.top_z().total_cmp(&b.top_z()));
+
+		match platform_below {
+			Some(platform) if !moving_up_by_itself => {
+				self.aligned_box.pos.z = platform.top_z() + half_height;
+				self.motion.z = self.motion.z.max(0.0);
+				self.carried_velocity = Some(platform.velocity);
+			},
+			_ => {
+				if let Some(velocity) = self.carried_velocity.take() {
+					self.motion += velocity / 144.0;
+				}
+			},
+		}
+	}
+
+	/// If the box is inside matter (which can happen when loading a save of a world that is now
+	/// generated differently, for example), then it is moved to the closest free space nearby.
+	/// Moving up is preferred to moving sideways or down, the box is more likely to end up on
+	/// the surface than in a cave that way.
+	pub(crate) fn unstuck(
+		&mut self,
+		chunk_grid: &ChunkGrid,
+		block_type_table: &Arc<BlockTypeTable>,
+	) -> UnstuckOutcome {
+		// `None` if not loaded.
+		let is_opaque = |coords: BlockCoords| -> Option<bool> {
+			chunk_grid
+				.get_block(coords)
+				.map(|block| block_type_table.get(block.type_id).unwrap().is_opaque())
+		};
+		let is_free = |aligned_box: &AlignedBox| -> Option<bool> {
+			let mut free = true;
+			for coords in aligned_box.strictly_overlapping_block_coords_span().iter() {
+				free &= !is_opaque(coords)?;
+			}
+			Some(free)
+		};
+
+		match is_free(&self.aligned_box) {
+			None => return UnstuckOutcome::NotLoaded,
+			Some(true) => return UnstuckOutcome::NotStuck,
+			Some(false) => {},
+		}
+
+		// Candidate positions have the bottom of the box just above the bottom of a block,
+		// so that the box overlaps as few blocks as possible.
+		let dims = self.aligned_box.dims;
+		let feet_block =
+			(self.aligned_box.pos - cgmath::vec3(0.0, 0.0, dims.z / 2.0)).map(|x| x.round() as i32);
+		let mut offsets = vec![];
+		let horizontal = UNSTUCK_MAX_HORIZONTAL_DISTANCE;
+		for dz in -UNSTUCK_MAX_DOWNWARD_DISTANCE..=UNSTUCK_MAX_UPWARD_DISTANCE {
+			for dy in -horizontal..=horizontal {
+				for dx in -horizontal..=horizontal {
+					offsets.push(cgmath::vec3(dx, dy, dz));
+				}
+			}
+		}
+		offsets.sort_by_key(|offset| {
+			// Going down costs more.
+			let vertical_cost = offset.z.max(-offset.z * 4);
+			offset.x * offset.x + offset.y * offset.y + vertical_cost * vertical_cost
+		});
+		for offset in offsets {
+			let block = (feet_block + offset).map(|x| x as f32);
+			let candidate = AlignedBox {
+				pos: block + cgmath::vec3(0.0, 0.0, -0.5 + dims.z / 2.0 + 0.001),
+				dims,
+			};
+			if is_free(&candidate) == Some(true) {
+				self.impose_position(candidate.pos);
+				self.motion = cgmath::vec3(0.0, 0.0, 0.0);
+				self.carried_velocity = None;
+				self.is_overlapping_blocks = false;
+				return UnstuckOutcome::Moved;
+			}
+		}
+		UnstuckOutcome::NoFreeSpaceFound
+	}
+
+	/// Is there something to stand on (an opaque block, layers or a door panel) right below the box?
+	fn has_support_below(&self, chunk_grid: &ChunkGrid, block_type_table: &BlockTypeTable) -> bool {
+		let bottom_z = self.aligned_box.pos.z - self.aligned_box.dims.z / 2.0;
+		let mut moved_aligned_box = self.aligned_box.clone();
+		moved_aligned_box.pos.z -= 0.005;
+		let block_span_below = moved_aligned_box.overlapping_block_coords_span();
+		chunk_grid.is_any_block_opaque_in(
+			block_span_below.side(OrientedAxis::Z_MINUS),
+			block_type_table,
+		) || (chunk_grid.may_have_layers_in(block_span_below)
+			&& block_span_below.iter().any(|coords| {
+				layer_top_z_at(coords, chunk_grid, block_type_table)
+					.is_some_and(|top_z| (bottom_z - top_z).abs() < 0.01)
+			})) || (chunk_grid.may_have_doors_in(block_span_below)
+			&& block_span_below.iter().any(|coords| {
+				door_panel_box_at(coords, chunk_grid, block_type_table)
+					.is_some_and(|panel_box| panel_box.overlaps(&moved_aligned_box))
+			}))
+	}
+
+	/// The mean of the ground responses of the blocks the box stands on, `None` if it is not
+	/// on the ground.
+	fn ground_response(
+		&self,
+		chunk_grid: &ChunkGrid,
+		block_type_table: &BlockTypeTable,
+	) -> Option<GroundResponse> {
+		let mut count = 0;
+		let mut friction_sum = 0.0;
+		let mut speed_factor_sum = 0.0;
+		for face in self.on_faces.iter() {
+			if face.direction_to_exterior != OrientedAxis::Z_PLUS {
+				continue;
+			}
+			let response = chunk_grid
+				.get_block(face.interior_coords)
+				.map(|block| block_type_table.ground_response(block.type_id))
+				.unwrap_or(GroundResponse::DEFAULT);
+			count += 1;
+			friction_sum += response.friction;
+			speed_factor_sum += response.speed_factor;
+		}
+		(count != 0).then(|| GroundResponse {
+			friction: friction_sum / count as f32,
+			speed_factor: speed_factor_sum / count as f32,
+		})
+	}
+
 	pub(crate) fn apply_one_physics_step(
 		&mut self,
 		walking_vector: cgmath::Vector3<f32>,
@@ -61,20 +336,27 @@ impl AlignedPhysBox {
 		block_type_table: &Arc<BlockTypeTable>,
 		dt: Duration,
 		bubble_up: bool,
+		edge_guard: bool,
 	) {
 		let is_opaque = |coords: BlockCoords| -> bool {
 			chunk_grid
 				.get_block(coords)
 				.is_some_and(|block| block_type_table.get(block.type_id).unwrap().is_opaque())
 		};
+		let layer_top_z = |coords: BlockCoords| layer_top_z_at(coords, chunk_grid, block_type_table);
+		let door_panel_box =
+			|coords: BlockCoords| door_panel_box_at(coords, chunk_grid, block_type_table);
 
 		// Is the hitbox inside matter?
-		let overlapping_blocks = self
-			.aligned_box
-			.overlapping_block_coords_span()
-			.iter()
-			.filter(|&coords| is_opaque(coords));
-		let top_z_overlapping_blocks = overlapping_blocks.map(|coords| coords.z).max();
+		let overlapping_block_span = self.aligned_box.strictly_overlapping_block_coords_span();
+		let top_z_overlapping_blocks = if chunk_grid
+			.is_any_block_opaque_in(overlapping_block_span, block_type_table)
+		{
+			let overlapping_blocks = overlapping_block_span.iter().filter(|&coords| is_opaque(coords));
+			overlapping_blocks.map(|coords| coords.z).max()
+		} else {
+			None
+		};
 		self.is_overlapping_blocks = top_z_overlapping_blocks.is_some();
 
 		// Bubble up through solid matter if the hitbox happens to already be inside matter.
@@ -87,14 +369,41 @@ impl AlignedPhysBox {
 		}
 
 		// The `displacement` is the vector that shall be added to the position for this iteration.
-		let displacement = (self.motion * 144.0 + walking_vector) * dt.as_secs_f32();
+		// Standing on a moving platform moves the box along with it, this displacement is subject
+		// to collisions with blocks just like any other displacement.
+		let carried_velocity = self.carried_velocity.unwrap_or(cgmath::vec3(0.0, 0.0, 0.0));
+		// The ground changes the walking, it can be slower (like on sand) or slippery (like on ice).
+		let walking_velocity = match self.ground_response(chunk_grid, block_type_table) {
+			Some(ground_response) => {
+				let target = walking_vector * ground_response.speed_factor;
+				let previous = self.walking_velocity.unwrap_or(target);
+				let grip = (ground_response.friction * WALKING_GRIP_RATE * dt.as_secs_f32()).min(1.0);
+				previous + (target - previous) * grip
+			},
+			None => walking_vector,
+		};
+		self.walking_velocity = Some(walking_velocity);
+		let displacement =
+			(self.motion * 144.0 + walking_velocity + carried_velocity) * dt.as_secs_f32();
+		// With the edge guard, a box that stands on blocks (and is not jumping) does not walk off
+		// the edges of the blocks it stands on, a horizontal move that would leave nothing below
+		// the box is cancelled (only along the axis of that move, so the box can walk along edges).
+		let edge_guarded = edge_guard
+			&& self.motion.z <= 0.0
+			&& self.on_faces.iter().any(|face| face.direction_to_exterior == OrientedAxis::Z_PLUS);
 		// Apply forces like gravity or friction.
 		self.motion.z -= 0.35 * dt.as_secs_f32();
 		self.motion /= 1.0 + 0.0015 * 144.0 * dt.as_secs_f32();
 
 		// Cut the displacement into sub steps, each having a length of at most `sub_step_max_length`.
 		// That ensures that the hitbox does not moves so fast that it passes through blocks.
-		let sub_step_max_length = 0.49;
+		// Thin things (like a single layer of snow) are not skipped either, even by small and fast
+		// hitboxes, as a sub step is also short enough for the hitbox to overlap with everything
+		// that it passes through at the end of some sub step.
+		let dims = self.aligned_box.dims;
+		let smallest_dim = dims.x.min(dims.y).min(dims.z);
+		let sub_step_max_length =
+			SUB_STEP_MAX_LENGTH.min(smallest_dim * SUB_STEP_MAX_LENGTH_TO_BOX_RATIO).max(0.01);
 		let displacement_total_length = displacement.magnitude();
 		let displacement_normalized = displacement.normalize();
 		let number_of_full_sub_steps =
@@ -113,6 +422,7 @@ impl AlignedPhysBox {
 				last_sub_step_length
 			};
 			let sub_step_displacement = displacement_normalized * sub_step_displacement_length;
+			let bottom_z_before_sub_step = self.aligned_box.pos.z - self.aligned_box.dims.z / 2.0;
 
 			// We handle the motion axis by axis.
 			// For each axis, we apply the motion then deal with collisions if any.
@@ -138,18 +448,36 @@ impl AlignedPhysBox {
 				let oriented_axis = OrientedAxis { axis, orientation };
 
 				// Apply the motion along the considered axis.
+				let pos_before_move = self.aligned_box.pos[axis_i];
 				self.aligned_box.pos[axis_i] += sub_step_displacement[axis_i];
 
+				if edge_guarded
+					&& axis != NonOrientedAxis::Z
+					&& !self.has_support_below(chunk_grid, block_type_table)
+				{
+					self.aligned_box.pos[axis_i] -= sub_step_displacement[axis_i];
+					self.motion[axis_i] = 0.0;
+					continue;
+				}
+
 				// The hitbox overlaps with some blocks (a rectangukar 3D span of blocks) (solid or not).
 				// We get that block span to have a list of block to check for collisions, as the hitbox
 				// can only collide with blocks that overlap with it.
-				let next_block_span = self.aligned_box.overlapping_block_coords_span();
+				let next_block_span = self.aligned_box.strictly_overlapping_block_coords_span();
 				// We only look at the blocks at one side of that span, the side the hitbox is moving
 				// towards.
 				let blocks_on_side = next_block_span.side(oriented_axis);
 				// If any of these blocks is solid, the it means that the hitbox is moving towards a
 				// solid block that overlaps with it, thus there is a collision.
-				let collision = blocks_on_side.iter().any(is_opaque);
+				// Layers that are too high to be stepped on are like walls when moving horizontally.
+				let bottom_z = self.aligned_box.pos.z - self.aligned_box.dims.z / 2.0;
+				let is_layer_too_high_to_step_on = |coords: BlockCoords| {
+					layer_top_z(coords).is_some_and(|top_z| top_z - bottom_z > LAYER_MAX_STEP_UP_HEIGHT)
+				};
+				let collision = chunk_grid.is_any_block_opaque_in(blocks_on_side, block_type_table)
+					|| (axis != NonOrientedAxis::Z
+						&& chunk_grid.may_have_layers_in(blocks_on_side)
+						&& blocks_on_side.iter().any(is_layer_too_high_to_step_on));
 				if collision {
 					// There is a collision to be solved.
 
@@ -178,6 +506,49 @@ impl AlignedPhysBox {
 						hitbox_side_coord_solved - (self.aligned_box.dims[axis_i] / 2.0) * sign;
 					self.aligned_box.pos[axis_i] = pos_coord_solved;
 				}
+
+				// Door panels are thin boxes in their blocks, the hitbox stops against the panels
+				// it moves into, but not against the ones it already overlapped before moving
+				// (so that it can get out of a door that was closed on it).
+				if chunk_grid.may_have_doors_in(next_block_span) {
+					let hitbox_side_before_move = pos_before_move + (dims[axis_i] / 2.0) * sign;
+					let panel_face_hit = next_block_span
+						.iter()
+						.filter_map(door_panel_box)
+						.filter(|panel_box| panel_box.overlaps(&self.aligned_box))
+						.map(|panel_box| panel_box.pos[axis_i] - (panel_box.dims[axis_i] / 2.0) * sign)
+						.filter(|&face_coord| (face_coord - hitbox_side_before_move) * sign >= -0.001)
+						.min_by(|a, b| (a * sign).total_cmp(&(b * sign)));
+					if let Some(face_coord) = panel_face_hit {
+						if self.motion[axis_i] * sign > 0.0 {
+							self.motion[axis_i] = 0.0;
+						}
+						// The small margin keeps the hitbox from overlapping the panel it stopped against.
+						self.aligned_box.pos[axis_i] = face_coord - (dims[axis_i] / 2.0 + 0.0001) * sign;
+					}
+				}
+			}
+
+			// Layers can be walked on, the hitbox is put back on top of the layers it sank into
+			// (by falling on them or by walking into layers low enough to be stepped on).
+			let bottom_z = self.aligned_box.pos.z - self.aligned_box.dims.z / 2.0;
+			let overlapping_block_span = self.aligned_box.overlapping_block_coords_span();
+			let top_z_of_layers_sank_into = if chunk_grid.may_have_layers_in(overlapping_block_span) {
+				overlapping_block_span
+					.iter()
+					.filter_map(layer_top_z)
+					.filter(|&top_z| {
+						bottom_z < top_z
+							&& (top_z - bottom_z <= LAYER_MAX_STEP_UP_HEIGHT
+								|| bottom_z_before_sub_step >= top_z - 0.01)
+					})
+					.max_by(f32::total_cmp)
+			} else {
+				None
+			};
+			if let Some(top_z) = top_z_of_layers_sank_into {
+				self.aligned_box.pos.z = top_z + self.aligned_box.dims.z / 2.0 + 0.001;
+				self.motion.z = self.motion.z.max(0.0);
 			}
 		}
 
@@ -187,6 +558,9 @@ impl AlignedPhysBox {
 			let mut moved_aligned_box = self.aligned_box.clone();
 			moved_aligned_box.pos += direction.delta().map(|x| x as f32) * 0.005;
 			let block_span_on_side = moved_aligned_box.overlapping_block_coords_span().side(direction);
+			if !chunk_grid.is_any_block_opaque_in(block_span_on_side, block_type_table) {
+				continue;
+			}
 			for interior_coords in block_span_on_side.iter().filter(|&coords| is_opaque(coords)) {
 				let direction_to_exterior = OrientedAxis::from_delta(direction.delta() * -1).unwrap();
 				let face = OrientedFaceCoords { interior_coords, direction_to_exterior };
@@ -196,15 +570,66 @@ impl AlignedPhysBox {
 				}
 			}
 		}
+		// Standing on layers is like standing on the top face of their block.
+		let bottom_z = self.aligned_box.pos.z - self.aligned_box.dims.z / 2.0;
+		let mut moved_aligned_box = self.aligned_box.clone();
+		moved_aligned_box.pos.z -= 0.005;
+		let block_span_below = moved_aligned_box.overlapping_block_coords_span();
+		if chunk_grid.may_have_layers_in(block_span_below) {
+			for interior_coords in block_span_below.iter() {
+				let is_on_layers =
+					layer_top_z(interior_coords).is_some_and(|top_z| (bottom_z - top_z).abs() < 0.01);
+				if is_on_layers {
+					let direction_to_exterior = OrientedAxis::Z_PLUS;
+					self.on_faces.push(OrientedFaceCoords { interior_coords, direction_to_exterior });
+				}
+			}
+		}
+		// Being pressed on a door panel is like being pressed on a face of its block.
+		let mut grown_aligned_box = self.aligned_box.clone();
+		grown_aligned_box.dims += cgmath::vec3(0.01, 0.01, 0.01);
+		let touched_block_span = grown_aligned_box.overlapping_block_coords_span();
+		if chunk_grid.may_have_doors_in(touched_block_span) {
+			for interior_coords in touched_block_span.iter() {
+				let Some(panel_box) = door_panel_box(interior_coords) else {
+					continue;
+				};
+				if panel_box.overlaps(&self.aligned_box) {
+					continue;
+				}
+				for direction in OrientedAxis::all_the_six_possible_directions() {
+					let mut moved_aligned_box = self.aligned_box.clone();
+					moved_aligned_box.pos += direction.delta().map(|x| x as f32) * 0.005;
+					if panel_box.overlaps(&moved_aligned_box) {
+						let direction_to_exterior =
+							OrientedAxis::from_delta(direction.delta() * -1).unwrap();
+						self.on_faces.push(OrientedFaceCoords { interior_coords, direction_to_exterior });
+					}
+				}
+			}
+		}
 
-		// If pressed on face, then apply more friction.
+		// If pressed on a face of the ground or of a ceiling, then apply more friction.
+		// Walls do not slow down the motion along them, only the part of the motion that goes
+		// into a wall is stopped, so that a box that hits a wall diagonally slides along it.
 		for face in self.on_faces.iter() {
-			if self.motion[face.direction_to_exterior.axis.index()]
-				* (face.direction_to_exterior.orientation.sign() as f32)
-				< 0.0
-			{
-				let friction = self.motion[face.direction_to_exterior.axis.index()].abs();
-				self.motion /= 1.0 + friction * 10.0 * 144.0 * dt.as_secs_f32();
+			let axis_i = face.direction_to_exterior.axis.index();
+			let sign = face.direction_to_exterior.orientation.sign() as f32;
+			if self.motion[axis_i] * sign < 0.0 {
+				if face.direction_to_exterior.axis == NonOrientedAxis::Z {
+					let ground_friction = if face.direction_to_exterior == OrientedAxis::Z_PLUS {
+						chunk_grid
+							.get_block(face.interior_coords)
+							.map(|block| block_type_table.ground_response(block.type_id).friction)
+							.unwrap_or(1.0)
+					} else {
+						1.0
+					};
+					let friction = self.motion[axis_i].abs() * ground_friction;
+					self.motion /= 1.0 + friction * 10.0 * 144.0 * dt.as_secs_f32();
+				} else {
+					self.motion[axis_i] = 0.0;
+				}
 			}
 		}
 	}
@@ -241,3 +666,325 @@ impl PlayerJumpManager {
 		}
 	}
 }
+
+/// By how much (in blocks) the player's box is shorter while sneaking.
+const SNEAKING_HEIGHT_REDUCTION: f32 = 0.3;
+
+/// Manages the player's sneaking.
+/// While sneaking, the player's box is shorter (its bottom does not move, so the camera that is
+/// placed relative to the box center gets lower), the player walks slower and is guarded from
+/// walking off edges (see the `edge_guard` parameter of `AlignedPhysBox::apply_one_physics_step`).
+/// Hostile creatures are not supposed to notice a sneaking player (see `is_sneaking`).
+pub(crate) struct PlayerSneakManager {
+	/// The height of the player's box when not sneaking, if sneaking.
+	standing_height: Option<f32>,
+}
+
+impl PlayerSneakManager {
+	pub(crate) fn new() -> PlayerSneakManager {
+		PlayerSneakManager { standing_height: None }
+	}
+
+	pub(crate) fn is_sneaking(&self) -> bool {
+		self.standing_height.is_some()
+	}
+
+	/// Must be called at every frame, `sneak_requested` being whether the sneak control is held.
+	/// Releasing the control does not stand the player back up if there is no room for that
+	/// (when sneaking under a low ceiling), the player stands up as soon as there is room.
+	pub(crate) fn manage(
+		&mut self,
+		sneak_requested: bool,
+		phys_box: &mut AlignedPhysBox,
+		chunk_grid: &ChunkGrid,
+		block_type_table: &BlockTypeTable,
+	) {
+		match (sneak_requested, self.standing_height) {
+			(true, None) => {
+				let standing_height = phys_box.aligned_box.dims.z;
+				set_height_keeping_bottom(
+					&mut phys_box.aligned_box,
+					standing_height - SNEAKING_HEIGHT_REDUCTION,
+				);
+				self.standing_height = Some(standing_height);
+			},
+			(false, Some(standing_height)) => {
+				let mut standing_box = phys_box.aligned_box.clone();
+				set_height_keeping_bottom(&mut standing_box, standing_height);
+				let room_to_stand_up = !chunk_grid.is_any_block_opaque_in(
+					standing_box.strictly_overlapping_block_coords_span(),
+					block_type_table,
+				);
+				if room_to_stand_up {
+					phys_box.aligned_box = standing_box;
+					self.standing_height = None;
+				}
+			},
+			_ => {},
+		}
+	}
+}
+
+fn set_height_keeping_bottom(aligned_box: &mut AlignedBox, height: f32) {
+	aligned_box.pos.z += (height - aligned_box.dims.z) / 2.0;
+	aligned_box.dims.z = height;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		block_types::BlockTypeId,
+		chunk_blocks::{Block, ChunkBlocksBeingGenerated, ChunkCullingInfo},
+		coords::{ChunkCoordsSpan, ChunkDimensions},
+	};
+
+	const DT: f32 = 1.0 / 60.0;
+	/// The z coordinate of a player box that stands on ground blocks at z = -1.
+	const STANDING_Z: f32 = -0.5 + 0.9 + 0.001;
+
+	/// A world made of the chunks around the origin, with opaque blocks where `is_solid` says so.
+	fn synthetic_world(is_solid: impl Fn(BlockCoords) -> bool) -> (ChunkGrid, Arc<BlockTypeTable>) {
+		synthetic_world_of(|coords, block_type_table| {
+			is_solid(coords).then(|| block_type_table.ground_id())
+		})
+	}
+
+	/// A world made of the chunks around the origin, with the blocks that `block_at` gives
+	/// (and air elsewhere).
+	fn synthetic_world_of(
+		block_at: impl Fn(BlockCoords, &BlockTypeTable) -> Option<BlockTypeId>,
+	) -> (ChunkGrid, Arc<BlockTypeTable>) {
+		let block_type_table = Arc::new(BlockTypeTable::new());
+		let cd = ChunkDimensions::from(16);
+		let mut chunk_grid = ChunkGrid::new(cd, None);
+		for chunk_coords in crate::coords::iter_3d_rect_inf_sup_included(
+			cgmath::point3(-1, -1, -1),
+			cgmath::point3(0, 0, 0),
+		) {
+			let coords_span = ChunkCoordsSpan { cd, chunk_coords };
+			let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
+			for coords in coords_span.iter_coords() {
+				if let Some(type_id) = block_at(coords, &block_type_table) {
+					chunk_blocks.set_id(coords, type_id);
+				}
+			}
+			let chunk_blocks = chunk_blocks.finish_generation();
+			let culling_info = ChunkCullingInfo::compute_from_blocks(&chunk_blocks, &block_type_table);
+			chunk_grid.add_chunk_loading_results(chunk_coords, chunk_blocks, culling_info, None);
+		}
+		chunk_grid.update_collision_shapes(&block_type_table);
+		(chunk_grid, block_type_table)
+	}
+
+	fn player_box_at(pos: cgmath::Point3<f32>) -> AlignedPhysBox {
+		AlignedPhysBox::new(
+			AlignedBox { pos, dims: cgmath::vec3(0.8, 0.8, 1.8) },
+			cgmath::vec3(0.0, 0.0, 0.0),
+		)
+	}
+
+	fn run_physics_steps(
+		phys_box: &mut AlignedPhysBox,
+		walking_vector: cgmath::Vector3<f32>,
+		chunk_grid: &ChunkGrid,
+		block_type_table: &Arc<BlockTypeTable>,
+		steps: usize,
+	) -> Vec<cgmath::Point3<f32>> {
+		(0..steps)
+			.map(|_| {
+				phys_box.apply_one_physics_step(
+					walking_vector,
+					chunk_grid,
+					block_type_table,
+					Duration::from_secs_f32(DT),
+					true,
+					false,
+				);
+				phys_box.aligned_box.pos
+			})
+			.collect()
+	}
+
+	#[test]
+	fn walking_diagonally_into_a_wall_slides_along_it() {
+		// Ground, and a wall that faces towards -x.
+		let (chunk_grid, table) =
+			synthetic_world(|coords| coords.z <= -1 || (coords.x >= 3 && coords.z <= 3));
+		let mut phys_box = player_box_at(cgmath::point3(0.0, 0.0, STANDING_Z));
+		let walking_vector = cgmath::vec3(1.0, 1.0, 0.0).normalize() * 12.0;
+		let positions = run_physics_steps(&mut phys_box, walking_vector, &chunk_grid, &table, 100);
+		let tangential_step = walking_vector.y * DT;
+		let sliding_positions: Vec<_> = positions.iter().skip(20).collect();
+		for pair in sliding_positions.windows(2) {
+			let (before, after) = (pair[0], pair[1]);
+			assert!((after.x - (2.5 - 0.4)).abs() < 0.01);
+			assert!((after.y - before.y - tangential_step).abs() < 0.001);
+			assert_eq!(after.z, before.z);
+		}
+	}
+
+	#[test]
+	fn hitting_a_wall_diagonally_keeps_the_motion_along_the_wall() {
+		// Just a wall, the box falls along it.
+		let (chunk_grid, table) = synthetic_world(|coords| coords.x >= 3);
+		let mut phys_box = player_box_at(cgmath::point3(0.0, 0.0, 0.0));
+		phys_box.motion = cgmath::vec3(0.05, 0.05, 0.0);
+		let mut previous_motion = phys_box.motion;
+		for _ in 0..60 {
+			run_physics_steps(
+				&mut phys_box,
+				cgmath::vec3(0.0, 0.0, 0.0),
+				&chunk_grid,
+				&table,
+				1,
+			);
+			// Only the air friction slows the box down.
+			assert!(phys_box.motion.y > previous_motion.y * 0.99);
+			assert!(phys_box.motion.z < previous_motion.z);
+			previous_motion = phys_box.motion;
+		}
+		assert_eq!(phys_box.motion.x, 0.0);
+		assert!(phys_box.aligned_box.pos.x < 2.5 - 0.4);
+		assert!(phys_box.aligned_box.pos.y > 2.0);
+	}
+
+	#[test]
+	fn touching_a_wall_is_not_being_inside_it() {
+		// The box starts with a side exactly on the face of the wall.
+		let (chunk_grid, table) =
+			synthetic_world(|coords| coords.z <= -1 || (coords.x >= 3 && coords.z <= 3));
+		let mut phys_box = player_box_at(cgmath::point3(2.1, 0.0, STANDING_Z));
+		let walking_vector = cgmath::vec3(0.0, 12.0, 0.0);
+		let positions = run_physics_steps(&mut phys_box, walking_vector, &chunk_grid, &table, 30);
+		for pair in positions.windows(2) {
+			let (before, after) = (pair[0], pair[1]);
+			assert_eq!(after.x, 2.1);
+			assert!((after.y - before.y - walking_vector.y * DT).abs() < 0.001);
+			assert!((after.z - STANDING_Z).abs() < 0.01);
+		}
+	}
+
+	#[test]
+	fn corners_do_not_cause_jitter() {
+		let walking_vector = cgmath::vec3(1.0, 1.0, 0.0).normalize() * 12.0;
+
+		// A pillar with a corner that points towards the box, the box starts right against that
+		// corner and then slides along one side of the pillar without being pushed back and forth.
+		let (chunk_grid, table) = synthetic_world(|coords| {
+			coords.z <= -1 || (coords.x >= 3 && coords.y >= 3 && coords.z <= 3)
+		});
+		let mut phys_box = player_box_at(cgmath::point3(2.1, 2.1, STANDING_Z));
+		let positions = run_physics_steps(&mut phys_box, walking_vector, &chunk_grid, &table, 60);
+		for pair in positions.windows(2) {
+			let (before, after) = (pair[0], pair[1]);
+			assert!(after.x > before.x);
+			assert_eq!(after.y, before.y);
+			assert!(after.y <= 2.1);
+			assert_eq!(after.z, before.z);
+		}
+
+		// Two walls that form a corner around the box, the box ends up stuck in the corner
+		// and does not move anymore.
+		let (chunk_grid, table) = synthetic_world(|coords| {
+			coords.z <= -1 || ((coords.x >= 3 || coords.y >= 3) && coords.z <= 3)
+		});
+		let mut phys_box = player_box_at(cgmath::point3(0.0, 0.0, STANDING_Z));
+		let positions = run_physics_steps(&mut phys_box, walking_vector, &chunk_grid, &table, 60);
+		let stuck_position = positions[30];
+		assert!((stuck_position.x - 2.1).abs() < 0.01);
+		assert!((stuck_position.y - 2.1).abs() < 0.01);
+		assert!(positions.iter().skip(30).all(|&position| position == stuck_position));
+	}
+
+	#[test]
+	fn ice_is_slippery_and_sand_is_slow() {
+		let ground_of_type = |ground_id: fn(&BlockTypeTable) -> BlockTypeId| {
+			synthetic_world_of(move |coords, block_type_table| {
+				(coords.z <= -1).then(|| ground_id(block_type_table))
+			})
+		};
+		let walking_vector = cgmath::vec3(12.0, 0.0, 0.0);
+		// Walks for a second, then stops walking for a second.
+		let walk_then_stop = |chunk_grid: &ChunkGrid, table: &Arc<BlockTypeTable>| {
+			let mut phys_box = player_box_at(cgmath::point3(-14.0, 0.0, STANDING_Z));
+			run_physics_steps(
+				&mut phys_box,
+				cgmath::vec3(0.0, 0.0, 0.0),
+				chunk_grid,
+				table,
+				5,
+			);
+			let start = phys_box.aligned_box.pos;
+			let walking = run_physics_steps(&mut phys_box, walking_vector, chunk_grid, table, 60);
+			let stopping = run_physics_steps(
+				&mut phys_box,
+				cgmath::vec3(0.0, 0.0, 0.0),
+				chunk_grid,
+				table,
+				60,
+			);
+			let walked_distance = walking.last().unwrap().x - start.x;
+			let slid_distance = stopping.last().unwrap().x - walking.last().unwrap().x;
+			(walked_distance, slid_distance)
+		};
+
+		let (chunk_grid, table) = ground_of_type(BlockTypeTable::ground_id);
+		let (walked_on_ground, slid_on_ground) = walk_then_stop(&chunk_grid, &table);
+		assert!((walked_on_ground - 12.0).abs() < 0.1);
+		assert!(slid_on_ground < 0.01);
+
+		let (chunk_grid, table) = ground_of_type(BlockTypeTable::kinda_sand_id);
+		let (walked_on_sand, slid_on_sand) = walk_then_stop(&chunk_grid, &table);
+		assert!((walked_on_sand - 12.0 * 0.6).abs() < 0.1);
+		assert!(slid_on_sand < 0.01);
+
+		let (chunk_grid, table) = ground_of_type(BlockTypeTable::kinda_ice_id);
+		let (walked_on_ice, slid_on_ice) = walk_then_stop(&chunk_grid, &table);
+		assert!(walked_on_ice < walked_on_ground);
+		assert!(slid_on_ice > 1.0);
+	}
+
+	#[test]
+	fn doors_block_the_way_only_when_closed() {
+		// Ground, and a closed door in the way (its panel is against the X+ side of its block).
+		let door_coords = cgmath::point3(3, 0, 0);
+		let (mut chunk_grid, table) = synthetic_world_of(|coords, block_type_table| {
+			if coords.z <= -1 {
+				Some(block_type_table.ground_id())
+			} else {
+				(coords == door_coords).then(|| block_type_table.kinda_door_id())
+			}
+		});
+		let walking_vector = cgmath::vec3(12.0, 0.0, 0.0);
+		let mut phys_box = player_box_at(cgmath::point3(0.0, -0.2, STANDING_Z));
+		run_physics_steps(&mut phys_box, walking_vector, &chunk_grid, &table, 60);
+		let panel_face_x = 3.5 - DOOR_PANEL_THICKNESS;
+		assert!((phys_box.aligned_box.pos.x - (panel_face_x - 0.4)).abs() < 0.01);
+
+		// Open, the panel is against the Y+ side of its block, out of the way.
+		let open_door = Block::new_door(table.kinda_door_id(), OrientedAxis::X_PLUS, true);
+		chunk_grid.set_block_and_request_updates_to_meshes(door_coords, open_door);
+		chunk_grid.update_collision_shapes(&table);
+		run_physics_steps(&mut phys_box, walking_vector, &chunk_grid, &table, 60);
+		assert!(phys_box.aligned_box.pos.x > 5.0);
+	}
+
+	#[test]
+	fn closed_trapdoors_can_be_stood_on() {
+		let (chunk_grid, table) = synthetic_world_of(|coords, block_type_table| {
+			(coords == cgmath::point3(0, 0, 0)).then(|| block_type_table.kinda_trapdoor_id())
+		});
+		let mut phys_box = player_box_at(cgmath::point3(0.0, 0.0, 3.0));
+		run_physics_steps(
+			&mut phys_box,
+			cgmath::vec3(0.0, 0.0, 0.0),
+			&chunk_grid,
+			&table,
+			120,
+		);
+		let panel_top_z = -0.5 + DOOR_PANEL_THICKNESS;
+		assert!((phys_box.aligned_box.pos.z - (panel_top_z + 0.9)).abs() < 0.01);
+		assert!(phys_box.on_ground_and_not_overlapping());
+	}
+}
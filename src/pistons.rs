@@ -0,0 +1,139 @@
+//! Pistons, that push the line of blocks in front of them by one block when they get powered by
+//! signals (see `signals`), and that retract when they are no longer powered (without pulling
+//! the blocks back).
+//!
+//! A piston pushes towards its facing side (see `BlockData::PistonState`), when placed it faces
+//! out of the face it was placed against. The line of pushed blocks ends at the first air block,
+//! liquid or decoration (that gets crushed). Extended pistons and piston heads cannot be pushed,
+//! and a piston does not extend if the line is longer than `MAX_PUSHED_BLOCKS` or reaches into
+//! chunks that are not loaded.
+
+use crate::{
+	block_types::{BlockType, BlockTypeTable, Targetability},
+	chunk_blocks::Block,
+	chunks::{ChunkGrid, EditBatch},
+	coords::{iter_3d_cube_center_radius, AlignedBox, BlockCoords, CubicCoordsSpan},
+};
+
+/// A piston cannot push more blocks than that.
+const MAX_PUSHED_BLOCKS: usize = 12;
+
+/// What a piston did when updated.
+pub(crate) enum PistonUpdate {
+	/// The piston did not move (it already was as it should be, or it cannot push).
+	Unchanged,
+	/// The piston extended or retracted, setting these blocks.
+	Moved(Vec<BlockCoords>),
+	/// The piston should extend but something (the player or an entity) is in the way,
+	/// it should be tried again later.
+	Blocked,
+}
+
+/// Extends or retracts the piston at the given coords so that it is extended if powered.
+pub(crate) fn update_piston(
+	chunk_grid: &ChunkGrid,
+	coords: BlockCoords,
+	is_powered: bool,
+	block_type_table: &BlockTypeTable,
+	player_box: &AlignedBox,
+	edit_batch: &mut EditBatch,
+) -> PistonUpdate {
+	let piston_id = block_type_table.kinda_piston_id();
+	let head_id = block_type_table.kinda_piston_head_id();
+	let Some(piston) = chunk_grid.get_block(coords).filter(|block| block.type_id == piston_id)
+	else {
+		return PistonUpdate::Unchanged;
+	};
+	let (facing, extended) = piston.piston_state();
+	let front_coords = coords + facing.delta();
+
+	if extended && !is_powered {
+		edit_batch.set(coords, Block::new_piston(piston_id, facing, false));
+		let head_is_there = chunk_grid
+			.get_block(front_coords)
+			.is_some_and(|block| block.type_id == head_id && block.piston_state().0 == facing);
+		if head_is_there {
+			edit_batch.set(front_coords, Block::from(BlockTypeTable::AIR_ID));
+			return PistonUpdate::Moved(vec![coords, front_coords]);
+		}
+		return PistonUpdate::Moved(vec![coords]);
+	} else if extended || !is_powered {
+		return PistonUpdate::Unchanged;
+	}
+
+	// The blocks to push, the last cell is where the last pushed block goes.
+	let mut line = vec![];
+	let mut cell = front_coords;
+	loop {
+		let Some(block) = chunk_grid.get_block(cell) else {
+			return PistonUpdate::Unchanged;
+		};
+		let block_type = block_type_table.get(block.type_id).unwrap();
+		let is_crushed = block_type.targetability() != Targetability::Targetable
+			|| matches!(block_type, BlockType::Liquid { .. });
+		if is_crushed {
+			break;
+		}
+		let is_immovable =
+			block.type_id == head_id || (block.type_id == piston_id && block.piston_state().1);
+		if is_immovable || line.len() >= MAX_PUSHED_BLOCKS {
+			return PistonUpdate::Unchanged;
+		}
+		line.push(cell);
+		cell += facing.delta();
+	}
+
+	// All the cells from the head to the last pushed block end up filled.
+	let filled_cells: Vec<BlockCoords> =
+		(0..=line.len() as i32).map(|i| front_coords + facing.delta() * i).collect();
+	if filled_cells.iter().any(|&cell| is_anything_in(chunk_grid, cell, player_box)) {
+		return PistonUpdate::Blocked;
+	}
+
+	for &cell in line.iter().rev() {
+		let block = chunk_grid.get_block(cell).unwrap().as_owned_block();
+		edit_batch.set(cell + facing.delta(), block);
+	}
+	edit_batch.set(front_coords, Block::new_piston(head_id, facing, true));
+	edit_batch.set(coords, Block::new_piston(piston_id, facing, true));
+	let mut moved_cells = filled_cells;
+	moved_cells.push(coords);
+	PistonUpdate::Moved(moved_cells)
+}
+
+/// Is the piston head at the given coords left without its extended piston behind it?
+/// This happens when an extended piston is broken.
+pub(crate) fn is_orphan_head(
+	chunk_grid: &ChunkGrid,
+	coords: BlockCoords,
+	block_type_table: &BlockTypeTable,
+) -> bool {
+	let Some(head) = chunk_grid
+		.get_block(coords)
+		.filter(|block| block.type_id == block_type_table.kinda_piston_head_id())
+	else {
+		return false;
+	};
+	let (facing, _extended) = head.piston_state();
+	let has_piston = chunk_grid.get_block(coords - facing.delta()).is_some_and(|block| {
+		block.type_id == block_type_table.kinda_piston_id() && block.piston_state() == (facing, true)
+	});
+	!has_piston
+}
+
+/// Does the player or an entity overlap the block at the given coords?
+fn is_anything_in(chunk_grid: &ChunkGrid, coords: BlockCoords, player_box: &AlignedBox) -> bool {
+	let block_box = CubicCoordsSpan::with_center_and_radius(coords, 1).to_aligned_box();
+	if block_box.overlaps(player_box) {
+		return true;
+	}
+	let chunk_coords = chunk_grid.cd().world_coords_to_containing_chunk_coords(coords);
+	iter_3d_cube_center_radius(chunk_coords, 2).any(|chunk_coords| {
+		chunk_grid.can_entity_in_chunk_maybe_collide_with_box(chunk_coords, &block_box)
+			&& chunk_grid.iter_entities_in_chunk(chunk_coords).is_some_and(|mut entities| {
+				entities.any(|entity| {
+					entity.aligned_box().is_some_and(|entity_box| entity_box.overlaps(&block_box))
+				})
+			})
+	})
+}
@@ -0,0 +1,297 @@
+//! The model of the player, a humanoid made of colored cube parts (like the creatures, see
+//! `models`) that is rendered when the player is seen from a third person view. Its colors are
+//! the appearance of the player, set in the config file (like `shirt = [40, 90, 200]` in
+//! the `[appearance]` table) or with the `player_color($shirt, 40, 90, 200)` command.
+//!
+//! The model can play emotes (like `emote($wave)`), simple animations that move its parts.
+
+use std::time::{Duration, Instant};
+
+use cgmath::{EuclideanSpace, SquareMatrix};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	entities::ForPartManipulation,
+	entity_parts::{colored_cube::ColoredCubePartKind, PartHandler, PartInstance, PartTables},
+	models::{delete_parts, Model, ModelPart},
+};
+
+/// Height of the joints of the legs to the body, as placed by `PlayerAppearance::model`.
+const HIP_Z: f32 = -0.2;
+/// Height of the joints of the arms to the body, as placed by `PlayerAppearance::model`.
+const SHOULDER_Z: f32 = 0.475;
+/// How long it takes to get in (and out of) the pose of an emote.
+const EMOTE_BLEND_TIME: f32 = 0.25;
+/// An emote stops when the player moves further than that from where it started.
+const EMOTE_CANCELING_DISTANCE: f32 = 0.1;
+
+/// The colors of the parts of the player model.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct PlayerAppearance {
+	pub(crate) skin: [u8; 3],
+	pub(crate) hair: [u8; 3],
+	pub(crate) shirt: [u8; 3],
+	pub(crate) pants: [u8; 3],
+}
+
+impl Default for PlayerAppearance {
+	fn default() -> PlayerAppearance {
+		PlayerAppearance {
+			skin: [230, 190, 150],
+			hair: [90, 60, 30],
+			shirt: [60, 120, 200],
+			pants: [50, 50, 80],
+		}
+	}
+}
+
+impl PlayerAppearance {
+	/// The names of the colors, as given to the `player_color` command.
+	pub(crate) const COLOR_NAMES: [&'static str; 4] = ["skin", "hair", "shirt", "pants"];
+
+	pub(crate) fn color_mut(&mut self, name: &str) -> Option<&mut [u8; 3]> {
+		match name {
+			"skin" => Some(&mut self.skin),
+			"hair" => Some(&mut self.hair),
+			"shirt" => Some(&mut self.shirt),
+			"pants" => Some(&mut self.pants),
+			_ => None,
+		}
+	}
+
+	/// The model fits in the box of the player (0.8 by 0.8 by 1.8) with its origin at the center
+	/// of the box, facing the x axis.
+	fn model(&self) -> Model {
+		let part = |name: &str, offset: [f32; 3], scale: [f32; 3], color| ModelPart {
+			name: name.to_string(),
+			offset: offset.into(),
+			scale: scale.into(),
+			color,
+		};
+		Model {
+			parts: vec![
+				part("left_leg", [0.0, 0.12, -0.55], [0.22, 0.2, 0.7], self.pants),
+				part(
+					"right_leg",
+					[0.0, -0.12, -0.55],
+					[0.22, 0.2, 0.7],
+					self.pants,
+				),
+				part("body", [0.0, 0.0, 0.15], [0.3, 0.5, 0.7], self.shirt),
+				part(
+					"left_arm",
+					[0.0, 0.35, 0.15],
+					[0.18, 0.18, 0.65],
+					self.shirt,
+				),
+				part(
+					"right_arm",
+					[0.0, -0.35, 0.15],
+					[0.18, 0.18, 0.65],
+					self.shirt,
+				),
+				part("head", [0.0, 0.0, 0.68], [0.36, 0.36, 0.36], self.skin),
+				part("hair", [-0.02, 0.0, 0.83], [0.4, 0.4, 0.12], self.hair),
+			],
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Emote {
+	Wave,
+	Sit,
+	Point,
+}
+
+impl Emote {
+	/// The names of the emotes, as given to the `emote` command.
+	pub(crate) const NAMES: [&'static str; 3] = ["wave", "sit", "point"];
+
+	pub(crate) fn from_name(name: &str) -> Option<Emote> {
+		match name {
+			"wave" => Some(Emote::Wave),
+			"sit" => Some(Emote::Sit),
+			"point" => Some(Emote::Point),
+			_ => None,
+		}
+	}
+
+	/// How long it plays, `None` for the ones that last until the player moves.
+	fn duration(self) -> Option<Duration> {
+		match self {
+			Emote::Wave => Some(Duration::from_secs_f32(2.5)),
+			Emote::Sit => None,
+			Emote::Point => Some(Duration::from_secs_f32(2.0)),
+		}
+	}
+
+	/// The transform of the part of the given name `time` seconds into the emote, `weight` goes
+	/// from 0.0 (not posed at all) to 1.0 (fully posed) to blend the pose in and out.
+	fn pose(self, part_name: &str, time: f32, weight: f32) -> cgmath::Matrix4<f32> {
+		let around = |pivot: cgmath::Vector3<f32>, rotation: cgmath::Matrix4<f32>| {
+			cgmath::Matrix4::from_translation(pivot)
+				* rotation
+				* cgmath::Matrix4::from_translation(-pivot)
+		};
+		let right_shoulder = cgmath::vec3(0.0, -0.35, SHOULDER_Z);
+		let left_shoulder = cgmath::vec3(0.0, 0.35, SHOULDER_Z);
+		match (self, part_name) {
+			(Emote::Wave, "right_arm") => {
+				// Raised up and to the side, swinging.
+				let angle = -150.0 + 20.0 * (time * 10.0).sin();
+				around(
+					right_shoulder,
+					cgmath::Matrix4::from_angle_x(cgmath::Deg(angle * weight)),
+				)
+			},
+			(Emote::Point, "right_arm") => {
+				// Straight forward.
+				around(
+					right_shoulder,
+					cgmath::Matrix4::from_angle_y(cgmath::Deg(-90.0 * weight)),
+				)
+			},
+			(Emote::Sit, _) => {
+				// On the ground with the legs forward and the hands on the knees.
+				let lowering =
+					cgmath::Matrix4::from_translation(cgmath::vec3(0.0, 0.0, -0.59 * weight));
+				let local = match part_name {
+					"left_leg" | "right_leg" => {
+						let hip = cgmath::vec3(0.0, 0.0, HIP_Z);
+						around(
+							hip,
+							cgmath::Matrix4::from_angle_y(cgmath::Deg(-90.0 * weight)),
+						)
+					},
+					"left_arm" => around(
+						left_shoulder,
+						cgmath::Matrix4::from_angle_y(cgmath::Deg(-30.0 * weight)),
+					),
+					"right_arm" => around(
+						right_shoulder,
+						cgmath::Matrix4::from_angle_y(cgmath::Deg(-30.0 * weight)),
+					),
+					_ => cgmath::Matrix4::identity(),
+				};
+				lowering * local
+			},
+			_ => cgmath::Matrix4::identity(),
+		}
+	}
+}
+
+struct PlayingEmote {
+	emote: Emote,
+	start_time: Instant,
+	/// Where the player was when the emote started.
+	start_pos: cgmath::Point3<f32>,
+}
+
+impl PlayingEmote {
+	fn is_over(&self, player_pos: cgmath::Point3<f32>) -> bool {
+		let duration_is_over =
+			self.emote.duration().is_some_and(|duration| self.start_time.elapsed() >= duration);
+		let player_moved =
+			cgmath::MetricSpace::distance(self.start_pos, player_pos) > EMOTE_CANCELING_DISTANCE;
+		duration_is_over || player_moved
+	}
+
+	fn pose(&self, part_name: &str) -> cgmath::Matrix4<f32> {
+		let time = self.start_time.elapsed().as_secs_f32();
+		let mut weight = (time / EMOTE_BLEND_TIME).min(1.0);
+		if let Some(duration) = self.emote.duration() {
+			let time_left = duration.as_secs_f32() - time;
+			weight = weight.min(time_left / EMOTE_BLEND_TIME).max(0.0);
+		}
+		self.emote.pose(part_name, time, weight)
+	}
+}
+
+pub(crate) struct PlayerModel {
+	appearance: PlayerAppearance,
+	model: Model,
+	parts: Vec<PartHandler<ColoredCubePartKind>>,
+	emote: Option<PlayingEmote>,
+}
+
+impl PlayerModel {
+	pub(crate) fn new(appearance: PlayerAppearance) -> PlayerModel {
+		let model = appearance.model();
+		PlayerModel { appearance, model, parts: vec![], emote: None }
+	}
+
+	pub(crate) fn appearance(&self) -> &PlayerAppearance {
+		&self.appearance
+	}
+
+	pub(crate) fn set_appearance(&mut self, appearance: PlayerAppearance, part_tables: &PartTables) {
+		self.model = appearance.model();
+		self.appearance = appearance;
+		// The colors of the parts are given when they are allocated.
+		self.hide(part_tables);
+	}
+
+	/// Starts playing the emote (interrupting the one that was playing, if any), the player
+	/// being at the given position.
+	pub(crate) fn play_emote(&mut self, emote: Emote, player_pos: cgmath::Point3<f32>) {
+		self.emote = Some(PlayingEmote { emote, start_time: Instant::now(), start_pos: player_pos });
+	}
+
+	/// Makes the parts render the player at the given position (the center of its box) facing
+	/// the given horizontal angle. It is meant to be called every frame the player is seen.
+	pub(crate) fn update_parts(
+		&mut self,
+		pos: cgmath::Point3<f32>,
+		angle_horizontal: f32,
+		part_manipulation: &ForPartManipulation,
+	) {
+		let transform = cgmath::Matrix4::<f32>::from_translation(pos.to_vec())
+			* cgmath::Matrix4::<f32>::from_angle_z(cgmath::Rad(angle_horizontal));
+		if self.emote.as_ref().is_some_and(|emote| emote.is_over(pos)) {
+			self.emote = None;
+		}
+		let pose = |part: &ModelPart| match &self.emote {
+			Some(emote) => emote.pose(&part.name),
+			None => cgmath::Matrix4::identity(),
+		};
+		self.model.update_parts_posed(&mut self.parts, transform, &pose, part_manipulation);
+		// The player moves every frame, its parts are not interpolated between entity physics
+		// steps (see `PartsInterpolation`) so that they do not lag behind.
+		let mut colored_cubes = part_manipulation.part_tables.colored_cubes.lock().unwrap();
+		for part in self.parts.iter_mut() {
+			part.modify_instance(&mut colored_cubes, |instance| {
+				instance.set_previous_model_matrix(instance.model_matrix());
+			});
+		}
+	}
+
+	/// Stops rendering the player (like in first person view).
+	pub(crate) fn hide(&mut self, part_tables: &PartTables) {
+		delete_parts(&self.parts, part_tables);
+		self.parts.clear();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use cgmath::Transform;
+
+	use super::*;
+
+	#[test]
+	fn pointing_arm_points_forward() {
+		let hand = cgmath::point3(0.0, -0.35, SHOULDER_Z - 0.65);
+		let pointing_hand = Emote::Point.pose("right_arm", 1.0, 1.0).transform_point(hand);
+		assert!(
+			cgmath::MetricSpace::distance(pointing_hand, cgmath::point3(0.65, -0.35, SHOULDER_Z))
+				< 0.001
+		);
+		let resting_hand = Emote::Point.pose("right_arm", 1.0, 0.0).transform_point(hand);
+		assert!(cgmath::MetricSpace::distance(resting_hand, hand) < 0.001);
+		for name in Emote::NAMES {
+			assert!(Emote::from_name(name).is_some());
+		}
+	}
+}
@@ -0,0 +1,177 @@
+//! World pre-generation, all the chunks in a sphere are generated and saved in the background
+//! so that exploring there later only has to read them from the save (which is much faster than
+//! generating them, so there are no hitches when moving fast).
+//!
+//! The pregeneration only uses the workers that the chunk loading leaves available, and at most
+//! half of the workers, so that the game stays responsive while it runs.
+
+use std::{
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+use cgmath::MetricSpace;
+
+use crate::{
+	block_types::BlockTypeTable,
+	chunk_cache::ChunkCache,
+	chunks::ChunkGrid,
+	coords::{iter_3d_cube_center_radius, ChunkCoords, ChunkCoordsSpan, ChunkDimensions},
+	entities::IdGenerator,
+	saves::Save,
+	tasks::WorkerTasksManager,
+	threadpool::ThreadPool,
+	world_gen::WorldGenerator,
+};
+
+/// How often the progress is reported in the log.
+const PROGRESS_REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+pub(crate) struct Pregen {
+	/// The chunks that are yet to be pregenerated, the closest to the center at the end.
+	pending: Vec<ChunkCoords>,
+	chunk_count: usize,
+	/// The chunks that were pregenerated or that did not need to be.
+	finished_count: usize,
+	last_report: Instant,
+}
+
+/// Data that is needed to pregenerate one chunk.
+pub(crate) struct DataForChunkPregen {
+	pub(crate) world_generator: Arc<dyn WorldGenerator + Sync + Send>,
+	pub(crate) block_type_table: Arc<BlockTypeTable>,
+	pub(crate) save: Arc<Save>,
+	/// The blocks are saved with the `--only-modified` setting off, and are put in the chunk
+	/// cache else.
+	pub(crate) chunk_cache: Option<Arc<ChunkCache>>,
+	pub(crate) cd: ChunkDimensions,
+}
+
+impl DataForChunkPregen {
+	/// Meant to be run on a worker thread.
+	pub(crate) fn pregenerate(self, chunk_coords: ChunkCoords, id_generator: &IdGenerator) {
+		let coords_span = ChunkCoordsSpan { cd: self.cd, chunk_coords };
+		let (chunk_blocks, chunk_entities) = self.world_generator.generate_chunk_blocks_and_entities(
+			coords_span,
+			&self.block_type_table,
+			id_generator,
+		);
+		match self.chunk_cache.as_ref() {
+			None => chunk_blocks.save(&self.save),
+			Some(chunk_cache) => chunk_cache.store(&chunk_blocks),
+		}
+		// The entities are generated only once per chunk, they must be kept.
+		if chunk_entities.count_entities() >= 1 {
+			chunk_entities.save(&self.save);
+		}
+	}
+}
+
+impl Pregen {
+	/// The chunks to pregenerate are the ones up to `radius` blocks from the center chunk.
+	pub(crate) fn new(center: ChunkCoords, radius: i32, cd: ChunkDimensions) -> Pregen {
+		let radius_in_chunks = radius as f32 / cd.edge as f32;
+		let centerf = center.map(|x| x as f32);
+		let mut pending: Vec<ChunkCoords> =
+			iter_3d_cube_center_radius(center, radius_in_chunks.ceil() as i32 + 1)
+				.filter(|chunk_coords| {
+					chunk_coords.map(|x| x as f32).distance(centerf) <= radius_in_chunks
+				})
+				.collect();
+		pending.sort_unstable_by_key(|chunk_coords| {
+			-(chunk_coords.map(|x| x as f32).distance2(centerf) * 10.0) as i64
+		});
+		Pregen {
+			chunk_count: pending.len(),
+			pending,
+			finished_count: 0,
+			last_report: Instant::now(),
+		}
+	}
+
+	/// Returns the localization key of the reason why the chunks could not be kept, if any.
+	pub(crate) fn check_possible(
+		save: Option<&Arc<Save>>,
+		only_save_modified_chunks: bool,
+		chunk_cache: Option<&Arc<ChunkCache>>,
+	) -> Result<(), &'static str> {
+		if save.is_none() {
+			Err("log.pregen_needs_save")
+		} else if only_save_modified_chunks && chunk_cache.is_none() {
+			Err("log.pregen_only_modified")
+		} else {
+			Ok(())
+		}
+	}
+
+	pub(crate) fn chunk_count(&self) -> usize {
+		self.chunk_count
+	}
+
+	/// Starts pregeneration tasks on the available workers (if any).
+	///
+	/// The chunks are marked as generated right away so that their entities are never generated
+	/// a second time, and they cannot be loaded while they are being pregenerated.
+	#[allow(clippy::too_many_arguments)]
+	pub(crate) fn handle_pregen(
+		&mut self,
+		chunk_grid: &mut ChunkGrid,
+		worker_tasks: &mut WorkerTasksManager,
+		pool: &mut ThreadPool,
+		world_generator: &Arc<dyn WorldGenerator + Sync + Send>,
+		block_type_table: &Arc<BlockTypeTable>,
+		save: &Arc<Save>,
+		only_save_modified_chunks: bool,
+		chunk_cache: Option<&Arc<ChunkCache>>,
+		id_generator: &Arc<IdGenerator>,
+	) {
+		let max_pregen_tasks = (pool.number_of_workers() / 2).max(1);
+		let mut slot_count = worker_tasks
+			.how_many_loading_compatible_workers_available(pool)
+			.min(max_pregen_tasks.saturating_sub(worker_tasks.how_many_chunks_being_pregenerated()));
+		while slot_count >= 1 {
+			let Some(chunk_coords) = self.pending.pop() else {
+				break;
+			};
+			let already_generated = chunk_grid.was_already_generated_before(chunk_coords)
+				|| chunk_grid.is_loaded(chunk_coords)
+				|| worker_tasks.is_being_loaded(chunk_coords);
+			if already_generated {
+				self.finished_count += 1;
+				continue;
+			}
+			slot_count -= 1;
+			chunk_grid.mark_as_generated(chunk_coords);
+			let data_for_chunk_pregen = DataForChunkPregen {
+				world_generator: Arc::clone(world_generator),
+				block_type_table: Arc::clone(block_type_table),
+				save: Arc::clone(save),
+				chunk_cache: only_save_modified_chunks.then(|| chunk_cache.cloned()).flatten(),
+				cd: chunk_grid.cd(),
+			};
+			worker_tasks.run_chunk_pregen_task(
+				pool,
+				chunk_coords,
+				data_for_chunk_pregen,
+				Arc::clone(id_generator),
+			);
+		}
+	}
+
+	pub(crate) fn handle_pregen_result(&mut self) {
+		self.finished_count += 1;
+	}
+
+	/// Is everything pregenerated (and saved)?
+	pub(crate) fn is_finished(&self, worker_tasks: &WorkerTasksManager) -> bool {
+		self.pending.is_empty() && worker_tasks.how_many_chunks_being_pregenerated() == 0
+	}
+
+	/// The number of finished chunks (out of `chunk_count`), once in a while.
+	pub(crate) fn progress_to_report(&mut self) -> Option<usize> {
+		(self.last_report.elapsed() >= PROGRESS_REPORT_INTERVAL).then(|| {
+			self.last_report = Instant::now();
+			self.finished_count
+		})
+	}
+}
@@ -0,0 +1,80 @@
+//! Debug rendering modes that change how the blocks are rendered to help see what is going on
+//! (like where the meshing is expensive).
+
+use crate::{chunk_meshing::ChunkMesh, shaders::block::HeatmapTintPod};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RenderDebugMode {
+	Normal,
+	/// Only the edges of the triangles of the blocks are drawn.
+	Wireframe,
+	/// Chunks are tinted depending on some measure, from blue (low) to red (high).
+	ChunkHeatmap(ChunkHeatmapMeasure),
+	/// The block light (see `block_light`) of the space in front of the targeted face is displayed,
+	/// and if `color_blocks` then the blocks are colored by the block light that they get.
+	LightLevel {
+		color_blocks: bool,
+	},
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ChunkHeatmapMeasure {
+	/// How long it took to generate the last mesh of the chunk.
+	MeshingDuration,
+	/// How much GPU memory the mesh of the chunk takes.
+	MeshSize,
+}
+
+impl RenderDebugMode {
+	pub(crate) fn from_name(name: &str) -> Option<RenderDebugMode> {
+		Some(match name {
+			"normal" => RenderDebugMode::Normal,
+			"wireframe" => RenderDebugMode::Wireframe,
+			"heatmap_meshing" => RenderDebugMode::ChunkHeatmap(ChunkHeatmapMeasure::MeshingDuration),
+			"heatmap_memory" => RenderDebugMode::ChunkHeatmap(ChunkHeatmapMeasure::MeshSize),
+			"light_level" => RenderDebugMode::LightLevel { color_blocks: false },
+			"light_level_blocks" => RenderDebugMode::LightLevel { color_blocks: true },
+			_ => return None,
+		})
+	}
+}
+
+impl ChunkHeatmapMeasure {
+	fn measure(self, mesh: &ChunkMesh) -> f32 {
+		match self {
+			ChunkHeatmapMeasure::MeshingDuration => mesh.meshing_duration.as_secs_f32(),
+			ChunkHeatmapMeasure::MeshSize => mesh.block_vertex_buffer.size() as f32,
+		}
+	}
+}
+
+/// Gradient from blue (0.0) to green (0.5) to red (1.0).
+fn heatmap_color(ratio: f32) -> HeatmapTintPod {
+	let ratio = ratio.clamp(0.0, 1.0);
+	if ratio < 0.5 {
+		let t = ratio * 2.0;
+		[0.0, t, 1.0 - t]
+	} else {
+		let t = (ratio - 0.5) * 2.0;
+		[t, 1.0 - t, 0.0]
+	}
+}
+
+/// One tint per given mesh (in the same order), the hottest mesh is red.
+pub(crate) fn heatmap_tints<'a>(
+	meshes: impl Iterator<Item = &'a ChunkMesh>,
+	measure: ChunkHeatmapMeasure,
+) -> Vec<HeatmapTintPod> {
+	let values: Vec<f32> = meshes.map(|mesh| measure.measure(mesh)).collect();
+	let max_value = values.iter().copied().fold(0.0, f32::max);
+	values
+		.into_iter()
+		.map(|value| {
+			heatmap_color(if max_value > 0.0 {
+				value / max_value
+			} else {
+				0.0
+			})
+		})
+		.collect()
+}
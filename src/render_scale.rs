@@ -0,0 +1,206 @@
+//! Render scale, the scene can be rendered at a resolution that differs from the window's.
+//!
+//! Below 100%, the scene is rendered to a smaller offscreen texture that is then upscaled to the
+//! window (with bilinear filtering and some sharpening), trading sharpness for framerate.
+//! Above 100%, the scene is rendered to a bigger texture that is then downscaled to the window,
+//! which is a (costly) form of anti-aliasing. The interface is always rendered at the resolution
+//! of the window so that text stays crisp. The post-processing that samples the scene (like the
+//! bloom, or the color grading that is done when scaling) also needs the scene to be rendered
+//! to an offscreen texture, even at 100%.
+
+use wgpu::util::DeviceExt;
+
+use crate::{
+	color_grading::ColorGrading, render_stats::RenderStats,
+	shaders::upscale::render_pipeline_and_bind_group_layout,
+};
+
+pub(crate) const MIN_RENDER_SCALE: f32 = 0.5;
+pub(crate) const MAX_RENDER_SCALE: f32 = 2.0;
+/// Sharpness of the upscaling (when the render scale is below 100%), from 0.0 to 1.0.
+const UPSCALE_SHARPNESS: f32 = 0.6;
+
+struct ScaledTarget {
+	view: wgpu::TextureView,
+	bind_group: wgpu::BindGroup,
+}
+
+pub(crate) struct RenderScaling {
+	scale: f32,
+	output_format: wgpu::TextureFormat,
+	render_pipeline: wgpu::RenderPipeline,
+	bind_group_layout: wgpu::BindGroupLayout,
+	sampler: wgpu::Sampler,
+	sharpness_buffer: wgpu::Buffer,
+	/// The scene is rendered to an offscreen target even at 100% if needed by the post-processing.
+	needs_offscreen_target: bool,
+	/// There is no need for an offscreen target when the render scale is 100%,
+	/// the scene is then rendered directly to the window.
+	target: Option<ScaledTarget>,
+}
+
+impl RenderScaling {
+	pub(crate) fn new(
+		device: &wgpu::Device,
+		output_format: wgpu::TextureFormat,
+		color_grading: &ColorGrading,
+		scale: f32,
+		needs_offscreen_target: bool,
+		window_width: u32,
+		window_height: u32,
+	) -> RenderScaling {
+		let (render_pipeline, bind_group_layout) = render_pipeline_and_bind_group_layout(
+			device,
+			output_format,
+			color_grading.bind_group_layout(),
+		);
+		let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+			label: Some("Scaled Scene Sampler"),
+			address_mode_u: wgpu::AddressMode::ClampToEdge,
+			address_mode_v: wgpu::AddressMode::ClampToEdge,
+			address_mode_w: wgpu::AddressMode::ClampToEdge,
+			mag_filter: wgpu::FilterMode::Linear,
+			min_filter: wgpu::FilterMode::Linear,
+			mipmap_filter: wgpu::FilterMode::Nearest,
+			..Default::default()
+		});
+		let sharpness_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("Upscale Sharpness Buffer"),
+			contents: bytemuck::cast_slice(&[0.0f32]),
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+		});
+		let mut render_scaling = RenderScaling {
+			scale: scale.clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE),
+			output_format,
+			render_pipeline,
+			bind_group_layout,
+			sampler,
+			sharpness_buffer,
+			needs_offscreen_target,
+			target: None,
+		};
+		render_scaling.resize(device, window_width, window_height);
+		render_scaling
+	}
+
+	pub(crate) fn scale(&self) -> f32 {
+		self.scale
+	}
+
+	/// The given scale is clamped to the supported range. The scaled target (and the other
+	/// textures that have the scaled size) must be recreated after that.
+	pub(crate) fn set_scale(&mut self, scale: f32) {
+		self.scale = scale.clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE);
+	}
+
+	/// The scaled target (and the other textures that have the scaled size) must be recreated
+	/// after that.
+	pub(crate) fn set_needs_offscreen_target(&mut self, needs_offscreen_target: bool) {
+		self.needs_offscreen_target = needs_offscreen_target;
+	}
+
+	/// The size of the textures that the scene is rendered to.
+	pub(crate) fn scaled_size(
+		&self,
+		device: &wgpu::Device,
+		window_width: u32,
+		window_height: u32,
+	) -> (u32, u32) {
+		let max_dimension = device.limits().max_texture_dimension_2d;
+		let scale_dimension =
+			|dimension: u32| ((dimension as f32 * self.scale).round() as u32).clamp(1, max_dimension);
+		(
+			scale_dimension(window_width),
+			scale_dimension(window_height),
+		)
+	}
+
+	/// The scaled target must have the scaled size of the window.
+	pub(crate) fn resize(&mut self, device: &wgpu::Device, window_width: u32, window_height: u32) {
+		if self.scale == 1.0 && !self.needs_offscreen_target {
+			self.target = None;
+			return;
+		}
+		let (width, height) = self.scaled_size(device, window_width, window_height);
+		let texture = device.create_texture(&wgpu::TextureDescriptor {
+			label: Some("Scaled Scene Texture"),
+			size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+			mip_level_count: 1,
+			sample_count: 1,
+			dimension: wgpu::TextureDimension::D2,
+			format: self.output_format,
+			view_formats: &[],
+			usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+		});
+		let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+		let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+			label: Some("Upscale Shader Bind Group"),
+			layout: &self.bind_group_layout,
+			entries: &[
+				wgpu::BindGroupEntry {
+					binding: 0,
+					resource: wgpu::BindingResource::TextureView(&view),
+				},
+				wgpu::BindGroupEntry {
+					binding: 1,
+					resource: wgpu::BindingResource::Sampler(&self.sampler),
+				},
+				wgpu::BindGroupEntry {
+					binding: 2,
+					resource: self.sharpness_buffer.as_entire_binding(),
+				},
+			],
+		});
+		self.target = Some(ScaledTarget { view, bind_group });
+	}
+
+	/// The texture that the scene should be rendered to instead of the window, if any.
+	pub(crate) fn scene_view(&self) -> Option<&wgpu::TextureView> {
+		self.target.as_ref().map(|target| &target.view)
+	}
+
+	/// Renders the scaled and color graded scene to the window (if the scene was rendered
+	/// to a scaled target).
+	pub(crate) fn render_to_window(
+		&self,
+		queue: &wgpu::Queue,
+		encoder: &mut wgpu::CommandEncoder,
+		window_view: &wgpu::TextureView,
+		color_grading: &ColorGrading,
+		stats: &mut RenderStats,
+	) {
+		let Some(target) = &self.target else {
+			return;
+		};
+		// Downscaling does not need sharpening, it is sharp already.
+		let sharpness = if self.scale < 1.0 {
+			UPSCALE_SHARPNESS
+		} else {
+			0.0
+		};
+		queue.write_buffer(
+			&self.sharpness_buffer,
+			0,
+			bytemuck::cast_slice(&[sharpness]),
+		);
+
+		let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+			label: Some("Render Pass to scale the scene to the window"),
+			color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+				view: window_view,
+				resolve_target: None,
+				ops: wgpu::Operations {
+					load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+					store: wgpu::StoreOp::Store,
+				},
+			})],
+			depth_stencil_attachment: None,
+			timestamp_writes: None,
+			occlusion_query_set: None,
+		});
+		render_pass.set_pipeline(&self.render_pipeline);
+		render_pass.set_bind_group(0, &target.bind_group, &[]);
+		render_pass.set_bind_group(1, color_grading.bind_group(), &[]);
+		stats.draw_triangles(&mut render_pass, 3, 1);
+	}
+}
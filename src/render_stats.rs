@@ -0,0 +1,87 @@
+//! Statistics about the rendering of a frame, to get an idea of what the GPU is being asked to do
+//! and of how much memory our buffers take on the GPU side.
+
+/// Sizes (in bytes) of the GPU buffers, sorted by what they are used for.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct GpuMemoryUsage {
+	pub(crate) chunk_meshes: u64,
+	pub(crate) entities: u64,
+	pub(crate) interface: u64,
+}
+
+impl GpuMemoryUsage {
+	pub(crate) fn total(&self) -> u64 {
+		self.chunk_meshes + self.entities + self.interface
+	}
+}
+
+/// Statistics gathered during the rendering of one frame (all the render passes included, so
+/// a chunk mesh is counted once for the shadow map of each cascade and once more for the screen).
+#[derive(Clone, Copy, Default)]
+pub(crate) struct RenderStats {
+	pub(crate) draw_calls: u32,
+	pub(crate) triangles: u64,
+	pub(crate) memory: GpuMemoryUsage,
+}
+
+impl RenderStats {
+	pub(crate) fn draw_triangles(
+		&mut self,
+		render_pass: &mut wgpu::RenderPass,
+		vertex_count: u32,
+		instance_count: u32,
+	) {
+		render_pass.draw(0..vertex_count, 0..instance_count);
+		self.draw_calls += 1;
+		self.triangles += (vertex_count / 3) as u64 * instance_count as u64;
+	}
+
+	/// Draws a single instance, of the given index (like the index of a chunk mesh, that tells
+	/// the block shaders which data to use, see `ChunkInstances`).
+	pub(crate) fn draw_triangles_of_instance(
+		&mut self,
+		render_pass: &mut wgpu::RenderPass,
+		vertex_count: u32,
+		instance_index: u32,
+	) {
+		render_pass.draw(0..vertex_count, instance_index..(instance_index + 1));
+		self.draw_calls += 1;
+		self.triangles += (vertex_count / 3) as u64;
+	}
+
+	/// The draw call arguments are decided on the GPU (see `chunk_mesh_culling`) so the triangles
+	/// are counted as if the draw call was not culled, with the given vertex count.
+	pub(crate) fn draw_triangles_indirect<'a>(
+		&mut self,
+		render_pass: &mut wgpu::RenderPass<'a>,
+		indirect_buffer: &'a wgpu::Buffer,
+		indirect_offset: u64,
+		max_vertex_count: u32,
+	) {
+		render_pass.draw_indirect(indirect_buffer, indirect_offset);
+		self.draw_calls += 1;
+		self.triangles += (max_vertex_count / 3) as u64;
+	}
+
+	/// Lines do not count as triangles, but the draw call still counts.
+	pub(crate) fn draw_lines(&mut self, render_pass: &mut wgpu::RenderPass, vertex_count: u32) {
+		render_pass.draw(0..vertex_count, 0..1);
+		self.draw_calls += 1;
+	}
+}
+
+/// Formats a number of bytes in a human readable way (like `12.3 MiB`).
+pub(crate) fn format_bytes(bytes: u64) -> String {
+	const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+	let mut value = bytes as f64;
+	let mut unit_index = 0;
+	while value >= 1024.0 && unit_index < UNITS.len() - 1 {
+		value /= 1024.0;
+		unit_index += 1;
+	}
+	if unit_index == 0 {
+		format!("{bytes} B")
+	} else {
+		format!("{value:.1} {}", UNITS[unit_index])
+	}
+}
@@ -1,11 +1,23 @@
 use std::{mem::size_of, sync::Arc};
 
 use crate::{
+	bloom::Bloom,
 	camera::{CameraOrthographicSettings, Matrix4x4Pod},
+	chunk_instances::ChunkInstances,
+	chunk_mesh_culling::ChunkMeshCulling,
+	chunk_meshing::ChunkMesh,
 	chunks::ChunkGrid,
+	color_grading::ColorGrading,
+	deferred_lighting::DeferredLighting,
 	entity_parts::{DataForPartTableRendering, PartTablesForRendering},
 	game_init::WhichCameraToUse,
-	rendering_init::{BindingThingy, RenderPipelinesAndBindGroups},
+	held_block::HeldBlockMesh,
+	lens_effects::LensEffects,
+	photo_mode::ScreenshotReadback,
+	render_debug::RenderDebugMode,
+	render_scale::RenderScaling,
+	render_stats::{GpuMemoryUsage, RenderStats},
+	rendering_init::{BindingThingy, RenderPipelinesAndBindGroups, WaterReflectionStuff},
 	simple_meshes::{SimpleLineMesh, SimpleTextureMesh},
 	skybox::SkyboxMesh,
 };
@@ -23,31 +35,128 @@ pub(crate) struct DataForRendering<'a> {
 	pub(crate) shadow_map_cascade_view_thingies: &'a [BindingThingy<wgpu::TextureView>],
 	pub(crate) chunk_grid: &'a ChunkGrid,
 	pub(crate) z_buffer_view: &'a wgpu::TextureView,
+	/// If multisampling, the screen is rendered to this instead of the window surface texture
+	/// and then resolved to the window surface texture at the end.
+	pub(crate) msaa_color_view: Option<&'a wgpu::TextureView>,
+	pub(crate) deferred_lighting: Option<&'a DeferredLighting>,
+	/// If present, the chunk meshes that are out of view are culled on the GPU
+	/// (in the order of `ChunkGrid::iter_chunk_meshes`).
+	pub(crate) chunk_mesh_culling: Option<&'a ChunkMeshCulling>,
+	/// The data of the chunk meshes (in the same order), for the block render pipelines.
+	pub(crate) chunk_instances: &'a ChunkInstances,
+	pub(crate) water_reflection_stuff: &'a WaterReflectionStuff,
+	/// The skybox as seen in the water reflection, the reflection is only rendered if present.
+	pub(crate) water_reflection_skybox_mesh: Option<&'a SkyboxMesh>,
+	pub(crate) render_scaling: &'a RenderScaling,
+	pub(crate) lens_effects: &'a LensEffects,
+	pub(crate) bloom: &'a Bloom,
+	pub(crate) color_grading: &'a ColorGrading,
+	pub(crate) interface_z_buffer_view: &'a wgpu::TextureView,
 	pub(crate) selected_camera: WhichCameraToUse,
+	pub(crate) render_debug_mode: RenderDebugMode,
 	pub(crate) enable_display_phys_box: bool,
 	pub(crate) player_box_mesh: &'a SimpleLineMesh,
 	pub(crate) player_blocks_box_mesh: &'a SimpleLineMesh,
 	pub(crate) entities_box_meshes: &'a [SimpleLineMesh],
 	pub(crate) chunk_with_entities_box_meshes: &'a [SimpleLineMesh],
+	pub(crate) entity_labels_mesh: &'a SimpleTextureMesh,
+	pub(crate) tether_lines_mesh: &'a SimpleLineMesh,
+	pub(crate) model_editor_gizmo_mesh: &'a SimpleLineMesh,
+	pub(crate) entity_labels_see_through: bool,
 	pub(crate) targeted_face_mesh_opt: &'a Option<SimpleLineMesh>,
+	pub(crate) selection_box_mesh_opt: &'a Option<SimpleLineMesh>,
+	pub(crate) structure_debug_mesh: Option<&'a SimpleLineMesh>,
 	pub(crate) enable_display_interface: bool,
 	pub(crate) chunk_box_meshes: &'a [SimpleLineMesh],
 	pub(crate) skybox_mesh: &'a SkyboxMesh,
 	pub(crate) typing_in_command_line: bool,
+	/// The held block (or hand) to render in the first-person view, if it is to be rendered.
+	pub(crate) held_block_mesh: Option<&'a HeldBlockMesh>,
 	pub(crate) cursor_mesh: &'a SimpleLineMesh,
 	pub(crate) interface_simple_texture_mesh: &'a SimpleTextureMesh,
 	pub(crate) interface_simple_line_mesh: &'a SimpleLineMesh,
 	pub(crate) part_tables: &'a PartTablesForRendering,
+	/// Where to save a screenshot of the frame, if one is to be taken (see `photo_mode`).
+	pub(crate) screenshot_path: Option<&'a std::path::Path>,
 }
 
 impl<'a> DataForRendering<'a> {
+	fn set_sun_camera_viewport_if_selected(&self, render_pass: &mut wgpu::RenderPass) {
+		if matches!(self.selected_camera, WhichCameraToUse::Sun) {
+			let (scene_width, scene_height) = self.render_scaling.scaled_size(
+				self.device,
+				self.window_surface_config.width,
+				self.window_surface_config.height,
+			);
+			let scale = scene_height as f32 / self.sun_cameras[0].height;
+			let w = self.sun_cameras[0].width * scale;
+			let h = self.sun_cameras[0].height * scale;
+			let x = scene_width as f32 / 2.0 - w / 2.0;
+			let y = scene_height as f32 / 2.0 - h / 2.0;
+			render_pass.set_viewport(x, y, w, h, 0.0, 1.0);
+		}
+	}
+
+	/// Draws the chunk mesh of the given index (in the order of `ChunkGrid::iter_chunk_meshes`),
+	/// its vertex buffer must already be set.
+	fn draw_chunk_mesh<'p>(
+		&self,
+		render_pass: &mut wgpu::RenderPass<'p>,
+		stats: &mut RenderStats,
+		mesh_index: usize,
+		mesh: &ChunkMesh,
+	) where
+		'a: 'p,
+	{
+		let draw_args = self.chunk_mesh_culling.and_then(|culling| culling.draw_args(mesh_index));
+		match draw_args {
+			Some((indirect_buffer, offset)) => stats.draw_triangles_indirect(
+				render_pass,
+				indirect_buffer,
+				offset,
+				mesh.block_vertex_count,
+			),
+			None => stats.draw_triangles_of_instance(
+				render_pass,
+				mesh.block_vertex_count,
+				mesh_index as u32,
+			),
+		}
+	}
+
+	/// Sizes of the buffers that the rendering uses, those that are not of a fixed small size.
+	fn gpu_memory_usage(&self) -> GpuMemoryUsage {
+		let chunk_meshes =
+			self.chunk_grid.iter_chunk_meshes().map(|mesh| mesh.block_vertex_buffer.size()).sum();
+		let entities = self
+			.part_tables
+			.textured
+			.iter()
+			.chain(self.part_tables.colored.iter())
+			.map(|table| table.mesh_vertex_buffer.size() + table.instance_buffer.size())
+			.sum::<u64>()
+			+ self.entity_labels_mesh.vertex_buffer.size();
+		let interface = self.interface_simple_texture_mesh.vertex_buffer.size()
+			+ self.interface_simple_line_mesh.vertex_buffer.size()
+			+ self.cursor_mesh.vertex_buffer.size();
+		GpuMemoryUsage { chunk_meshes, entities, interface }
+	}
+
 	/// Blocking if V-sync is enabled which will make the FPS match the screen refresh rate.
-	pub(crate) fn render(&self) {
+	pub(crate) fn render(&self) -> RenderStats {
+		let mut stats = RenderStats { memory: self.gpu_memory_usage(), ..RenderStats::default() };
+
 		let mut encoder = self
 			.device
 			.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Render Encoder") });
 
+		if let Some(chunk_mesh_culling) = self.chunk_mesh_culling {
+			chunk_mesh_culling.cull(&mut encoder);
+		}
+
 		// Render pass to generate the shadow map cascades.
+		// Note that all the 3D rendering uses reverse-Z
+		// (see `CameraSettings::view_projection_matrix`) so depth buffers are cleared to 0.
 		for cascade_index in 0..self.sun_cameras.len() {
 			encoder.copy_buffer_to_buffer(
 				&self.sun_camera_matrices_thingy.resource,
@@ -63,7 +172,7 @@ impl<'a> DataForRendering<'a> {
 				depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
 					view: &self.shadow_map_cascade_view_thingies[cascade_index].resource,
 					depth_ops: Some(wgpu::Operations {
-						load: wgpu::LoadOp::Clear(1.0),
+						load: wgpu::LoadOp::Clear(0.0),
 						store: wgpu::StoreOp::Store,
 					}),
 					stencil_ops: None,
@@ -75,9 +184,14 @@ impl<'a> DataForRendering<'a> {
 			// Blocks.
 			render_pass.set_pipeline(&self.rendering.block_shadow_render_pipeline);
 			render_pass.set_bind_group(0, &self.rendering.block_shadow_bind_group, &[]);
-			for mesh in self.chunk_grid.iter_chunk_meshes() {
+			render_pass.set_bind_group(1, self.chunk_instances.bind_group(), &[]);
+			for (mesh_index, mesh) in self.chunk_grid.iter_chunk_meshes().enumerate() {
 				render_pass.set_vertex_buffer(0, mesh.block_vertex_buffer.slice(..));
-				render_pass.draw(0..mesh.block_vertex_count, 0..1);
+				stats.draw_triangles_of_instance(
+					&mut render_pass,
+					mesh.block_vertex_count,
+					mesh_index as u32,
+				);
 			}
 
 			// Entity parts textured.
@@ -92,7 +206,7 @@ impl<'a> DataForRendering<'a> {
 				} = part_table_for_rendering;
 				render_pass.set_vertex_buffer(0, mesh_vertex_buffer.slice(..));
 				render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
-				render_pass.draw(0..*mesh_vertices_count, 0..*instances_count);
+				stats.draw_triangles(&mut render_pass, *mesh_vertices_count, *instances_count);
 			}
 
 			// Entity parts colored.
@@ -107,29 +221,180 @@ impl<'a> DataForRendering<'a> {
 				} = part_table_for_rendering;
 				render_pass.set_vertex_buffer(0, mesh_vertex_buffer.slice(..));
 				render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
-				render_pass.draw(0..*mesh_vertices_count, 0..*instances_count);
+				stats.draw_triangles(&mut render_pass, *mesh_vertices_count, *instances_count);
 			}
 		}
 
-		// Render pass to render the world to the screen.
 		let window_texture = self.window_surface.get_current_texture().unwrap();
+		let window_texture_view =
+			window_texture.texture.create_view(&wgpu::TextureViewDescriptor::default());
+		// The scene may be rendered to a scaled target instead of directly to the window,
+		// and if multisampling it is rendered to a multisampled target that is resolved at the end.
+		let scene_resolved_view = self.render_scaling.scene_view().unwrap_or(&window_texture_view);
+		let scene_view = self.msaa_color_view.unwrap_or(scene_resolved_view);
+		let clear_color = wgpu::Color { r: 0.0, g: 0.7, b: 1.0, a: 0.0 };
+
+		// Render passes to render the reflection of the world in the water plane,
+		// the blocks and then the skybox behind them (like for the screen).
+		// The chunk mesh culling was done for the camera, not for its reflection, so it is not used.
+		if let Some(water_reflection_skybox_mesh) = self.water_reflection_skybox_mesh {
+			let reflection_view = &self.water_reflection_stuff.reflection_view_thingy.resource;
+			{
+				let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+					label: Some("Render Pass to render the blocks to the water reflection"),
+					color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+						view: reflection_view,
+						resolve_target: None,
+						ops: wgpu::Operations {
+							load: wgpu::LoadOp::Clear(clear_color),
+							store: wgpu::StoreOp::Store,
+						},
+					})],
+					depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+						view: &self.water_reflection_stuff.reflection_z_buffer_view,
+						depth_ops: Some(wgpu::Operations {
+							load: wgpu::LoadOp::Clear(0.0),
+							store: wgpu::StoreOp::Store,
+						}),
+						stencil_ops: None,
+					}),
+					timestamp_writes: None,
+					occlusion_query_set: None,
+				});
+
+				render_pass.set_pipeline(&self.rendering.block_water_reflection_render_pipeline);
+				render_pass.set_bind_group(0, &self.rendering.block_water_reflection_bind_group, &[]);
+				render_pass.set_bind_group(1, self.chunk_instances.bind_group(), &[]);
+				for (mesh_index, mesh) in self.chunk_grid.iter_chunk_meshes().enumerate() {
+					render_pass.set_vertex_buffer(0, mesh.block_vertex_buffer.slice(..));
+					stats.draw_triangles_of_instance(
+						&mut render_pass,
+						mesh.block_vertex_count,
+						mesh_index as u32,
+					);
+				}
+			}
+
+			{
+				let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+					label: Some("Render Pass to render the skybox to the water reflection"),
+					color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+						view: reflection_view,
+						resolve_target: None,
+						ops: wgpu::Operations { load: wgpu::LoadOp::Load, store: wgpu::StoreOp::Store },
+					})],
+					depth_stencil_attachment: None,
+					timestamp_writes: None,
+					occlusion_query_set: None,
+				});
+
+				render_pass.set_pipeline(&self.rendering.skybox_water_reflection_render_pipeline);
+				render_pass.set_bind_group(0, &self.rendering.skybox_water_reflection_bind_group, &[]);
+				let mesh = water_reflection_skybox_mesh;
+				render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+				stats.draw_triangles(&mut render_pass, mesh.vertices.len() as u32, 1);
+			}
+		}
+
+		// In the deferred rendering path, blocks are rendered to the G-buffers and then lit
+		// to the screen, the world render pass that follows only renders the rest over that.
+		// The debug rendering modes only apply to the forward rendering of blocks.
+		let deferred_lighting =
+			self.deferred_lighting.filter(|_| self.render_debug_mode == RenderDebugMode::Normal);
+		if let Some(deferred_lighting) = deferred_lighting {
+			{
+				let color_attachments: Vec<_> = deferred_lighting
+					.gbuffer_views()
+					.iter()
+					.enumerate()
+					.map(|(index, view)| {
+						Some(wgpu::RenderPassColorAttachment {
+							view,
+							resolve_target: None,
+							ops: wgpu::Operations {
+								load: wgpu::LoadOp::Clear(if index == 0 {
+									clear_color
+								} else {
+									wgpu::Color::TRANSPARENT
+								}),
+								store: wgpu::StoreOp::Store,
+							},
+						})
+					})
+					.collect();
+				let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+					label: Some("Render Pass to render the blocks to the G-buffers"),
+					color_attachments: &color_attachments,
+					depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+						view: self.z_buffer_view,
+						depth_ops: Some(wgpu::Operations {
+							load: wgpu::LoadOp::Clear(0.0),
+							store: wgpu::StoreOp::Store,
+						}),
+						stencil_ops: None,
+					}),
+					timestamp_writes: None,
+					occlusion_query_set: None,
+				});
+
+				self.set_sun_camera_viewport_if_selected(&mut render_pass);
+
+				render_pass.set_pipeline(&self.rendering.block_gbuffer_render_pipeline);
+				render_pass.set_bind_group(0, &self.rendering.block_bind_group, &[]);
+				render_pass.set_bind_group(1, self.chunk_instances.bind_group(), &[]);
+				for (mesh_index, mesh) in self.chunk_grid.iter_chunk_meshes().enumerate() {
+					render_pass.set_vertex_buffer(0, mesh.block_vertex_buffer.slice(..));
+					self.draw_chunk_mesh(&mut render_pass, &mut stats, mesh_index, mesh);
+				}
+			}
+
+			{
+				let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+					label: Some("Render Pass to light the G-buffers to the screen"),
+					color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+						view: scene_view,
+						resolve_target: None,
+						ops: wgpu::Operations {
+							load: wgpu::LoadOp::Clear(clear_color),
+							store: wgpu::StoreOp::Store,
+						},
+					})],
+					depth_stencil_attachment: None,
+					timestamp_writes: None,
+					occlusion_query_set: None,
+				});
+
+				render_pass.set_pipeline(&deferred_lighting.render_pipeline);
+				render_pass.set_bind_group(0, &deferred_lighting.bind_group, &[]);
+				stats.draw_triangles(&mut render_pass, 3, 1);
+			}
+		}
+
+		// Render pass to render the world to the screen.
 		{
-			let window_texture_view =
-				window_texture.texture.create_view(&wgpu::TextureViewDescriptor::default());
+			let deferred = deferred_lighting.is_some();
 			let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
 				label: Some("Render Pass to render the world"),
 				color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-					view: &window_texture_view,
+					view: scene_view,
 					resolve_target: None,
 					ops: wgpu::Operations {
-						load: wgpu::LoadOp::Clear(wgpu::Color { r: 0.0, g: 0.7, b: 1.0, a: 0.0 }),
+						load: if deferred {
+							wgpu::LoadOp::Load
+						} else {
+							wgpu::LoadOp::Clear(clear_color)
+						},
 						store: wgpu::StoreOp::Store,
 					},
 				})],
 				depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
 					view: self.z_buffer_view,
 					depth_ops: Some(wgpu::Operations {
-						load: wgpu::LoadOp::Clear(1.0),
+						load: if deferred {
+							wgpu::LoadOp::Load
+						} else {
+							wgpu::LoadOp::Clear(0.0)
+						},
 						store: wgpu::StoreOp::Store,
 					}),
 					stencil_ops: None,
@@ -138,21 +403,33 @@ impl<'a> DataForRendering<'a> {
 				occlusion_query_set: None,
 			});
 
-			if matches!(self.selected_camera, WhichCameraToUse::Sun) {
-				let scale = self.window_surface_config.height as f32 / self.sun_cameras[0].height;
-				let w = self.sun_cameras[0].width * scale;
-				let h = self.sun_cameras[0].height * scale;
-				let x = self.window_surface_config.width as f32 / 2.0 - w / 2.0;
-				let y = self.window_surface_config.height as f32 / 2.0 - h / 2.0;
-				render_pass.set_viewport(x, y, w, h, 0.0, 1.0);
-			}
+			self.set_sun_camera_viewport_if_selected(&mut render_pass);
 
-			// Blocks.
-			render_pass.set_pipeline(&self.rendering.block_render_pipeline);
-			render_pass.set_bind_group(0, &self.rendering.block_bind_group, &[]);
-			for mesh in self.chunk_grid.iter_chunk_meshes() {
-				render_pass.set_vertex_buffer(0, mesh.block_vertex_buffer.slice(..));
-				render_pass.draw(0..mesh.block_vertex_count, 0..1);
+			// Blocks (unless already rendered by the deferred rendering path).
+			if !deferred {
+				let wireframe = matches!(self.render_debug_mode, RenderDebugMode::Wireframe);
+				let color_blocks_by_light = matches!(
+					self.render_debug_mode,
+					RenderDebugMode::LightLevel { color_blocks: true }
+				);
+				let heatmap = matches!(self.render_debug_mode, RenderDebugMode::ChunkHeatmap(_));
+				render_pass.set_pipeline(if heatmap {
+					&self.rendering.block_heatmap_render_pipeline
+				} else if color_blocks_by_light {
+					&self.rendering.block_light_render_pipeline
+				} else if let (true, Some(wireframe_render_pipeline)) =
+					(wireframe, &self.rendering.block_wireframe_render_pipeline)
+				{
+					wireframe_render_pipeline
+				} else {
+					&self.rendering.block_render_pipeline
+				});
+				render_pass.set_bind_group(0, &self.rendering.block_bind_group, &[]);
+				render_pass.set_bind_group(1, self.chunk_instances.bind_group(), &[]);
+				for (mesh_index, mesh) in self.chunk_grid.iter_chunk_meshes().enumerate() {
+					render_pass.set_vertex_buffer(0, mesh.block_vertex_buffer.slice(..));
+					self.draw_chunk_mesh(&mut render_pass, &mut stats, mesh_index, mesh);
+				}
 			}
 
 			// Entity parts textured.
@@ -167,7 +444,7 @@ impl<'a> DataForRendering<'a> {
 				} = part_table_for_rendering;
 				render_pass.set_vertex_buffer(0, mesh_vertex_buffer.slice(..));
 				render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
-				render_pass.draw(0..*mesh_vertices_count, 0..*instances_count);
+				stats.draw_triangles(&mut render_pass, *mesh_vertices_count, *instances_count);
 			}
 
 			// Entity parts colored.
@@ -182,16 +459,16 @@ impl<'a> DataForRendering<'a> {
 				} = part_table_for_rendering;
 				render_pass.set_vertex_buffer(0, mesh_vertex_buffer.slice(..));
 				render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
-				render_pass.draw(0..*mesh_vertices_count, 0..*instances_count);
+				stats.draw_triangles(&mut render_pass, *mesh_vertices_count, *instances_count);
 			}
 
 			if self.enable_display_phys_box {
 				render_pass.set_pipeline(&self.rendering.simple_line_render_pipeline);
 				render_pass.set_bind_group(0, &self.rendering.simple_line_bind_group, &[]);
 				render_pass.set_vertex_buffer(0, self.player_box_mesh.vertex_buffer.slice(..));
-				render_pass.draw(0..self.player_box_mesh.vertex_count, 0..1);
+				stats.draw_lines(&mut render_pass, self.player_box_mesh.vertex_count);
 				render_pass.set_vertex_buffer(0, self.player_blocks_box_mesh.vertex_buffer.slice(..));
-				render_pass.draw(0..self.player_blocks_box_mesh.vertex_count, 0..1);
+				stats.draw_lines(&mut render_pass, self.player_blocks_box_mesh.vertex_count);
 			}
 
 			if let Some(targeted_block_box_mesh) = &self.targeted_face_mesh_opt {
@@ -199,41 +476,84 @@ impl<'a> DataForRendering<'a> {
 					render_pass.set_pipeline(&self.rendering.simple_line_render_pipeline);
 					render_pass.set_bind_group(0, &self.rendering.simple_line_bind_group, &[]);
 					render_pass.set_vertex_buffer(0, targeted_block_box_mesh.vertex_buffer.slice(..));
-					render_pass.draw(0..targeted_block_box_mesh.vertex_count, 0..1);
+					stats.draw_lines(&mut render_pass, targeted_block_box_mesh.vertex_count);
+				}
+			}
+
+			if let Some(selection_box_mesh) = &self.selection_box_mesh_opt {
+				if self.enable_display_interface {
+					render_pass.set_pipeline(&self.rendering.simple_line_render_pipeline);
+					render_pass.set_bind_group(0, &self.rendering.simple_line_bind_group, &[]);
+					render_pass.set_vertex_buffer(0, selection_box_mesh.vertex_buffer.slice(..));
+					stats.draw_lines(&mut render_pass, selection_box_mesh.vertex_count);
 				}
 			}
 
+			if let Some(structure_debug_mesh) = self.structure_debug_mesh {
+				render_pass.set_pipeline(&self.rendering.simple_line_render_pipeline);
+				render_pass.set_bind_group(0, &self.rendering.simple_line_bind_group, &[]);
+				render_pass.set_vertex_buffer(0, structure_debug_mesh.vertex_buffer.slice(..));
+				stats.draw_lines(&mut render_pass, structure_debug_mesh.vertex_count);
+			}
+
 			for chunk_box_mesh in self.chunk_box_meshes.iter() {
 				render_pass.set_pipeline(&self.rendering.simple_line_render_pipeline);
 				render_pass.set_bind_group(0, &self.rendering.simple_line_bind_group, &[]);
 				render_pass.set_vertex_buffer(0, chunk_box_mesh.vertex_buffer.slice(..));
-				render_pass.draw(0..chunk_box_mesh.vertex_count, 0..1);
+				stats.draw_lines(&mut render_pass, chunk_box_mesh.vertex_count);
 			}
 
 			for entity_box_mesh in self.entities_box_meshes.iter() {
 				render_pass.set_pipeline(&self.rendering.simple_line_render_pipeline);
 				render_pass.set_bind_group(0, &self.rendering.simple_line_bind_group, &[]);
 				render_pass.set_vertex_buffer(0, entity_box_mesh.vertex_buffer.slice(..));
-				render_pass.draw(0..entity_box_mesh.vertex_count, 0..1);
+				stats.draw_lines(&mut render_pass, entity_box_mesh.vertex_count);
+			}
+
+			if self.tether_lines_mesh.vertex_count > 0 {
+				render_pass.set_pipeline(&self.rendering.simple_line_render_pipeline);
+				render_pass.set_bind_group(0, &self.rendering.simple_line_bind_group, &[]);
+				render_pass.set_vertex_buffer(0, self.tether_lines_mesh.vertex_buffer.slice(..));
+				stats.draw_lines(&mut render_pass, self.tether_lines_mesh.vertex_count);
+			}
+
+			if self.model_editor_gizmo_mesh.vertex_count > 0 {
+				render_pass.set_pipeline(&self.rendering.simple_line_render_pipeline);
+				render_pass.set_bind_group(0, &self.rendering.simple_line_bind_group, &[]);
+				render_pass.set_vertex_buffer(0, self.model_editor_gizmo_mesh.vertex_buffer.slice(..));
+				stats.draw_lines(&mut render_pass, self.model_editor_gizmo_mesh.vertex_count);
 			}
 
 			for chunk_box_mesh in self.chunk_with_entities_box_meshes.iter() {
 				render_pass.set_pipeline(&self.rendering.simple_line_render_pipeline);
 				render_pass.set_bind_group(0, &self.rendering.simple_line_bind_group, &[]);
 				render_pass.set_vertex_buffer(0, chunk_box_mesh.vertex_buffer.slice(..));
-				render_pass.draw(0..chunk_box_mesh.vertex_count, 0..1);
+				stats.draw_lines(&mut render_pass, chunk_box_mesh.vertex_count);
+			}
+
+			// Entity labels (names and all), last so that the see-through ones are drawn
+			// over everything.
+			if self.enable_display_interface && self.entity_labels_mesh.vertex_count > 0 {
+				render_pass.set_pipeline(if self.entity_labels_see_through {
+					&self.rendering.simple_texture_3d_see_through_render_pipeline
+				} else {
+					&self.rendering.simple_texture_3d_render_pipeline
+				});
+				render_pass.set_bind_group(0, &self.rendering.simple_texture_3d_bind_group, &[]);
+				let mesh = &self.entity_labels_mesh;
+				render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+				stats.draw_triangles(&mut render_pass, mesh.vertex_count, 1);
 			}
 		}
 
 		// Render pass to render the skybox to the screen.
+		// This is the last render pass of the scene, so multisampling is resolved here if enabled.
 		{
-			let window_texture_view =
-				window_texture.texture.create_view(&wgpu::TextureViewDescriptor::default());
 			let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
 				label: Some("Render Pass to render the skybox"),
 				color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-					view: &window_texture_view,
-					resolve_target: None,
+					view: scene_view,
+					resolve_target: self.msaa_color_view.map(|_| scene_resolved_view),
 					ops: wgpu::Operations { load: wgpu::LoadOp::Load, store: wgpu::StoreOp::Store },
 				})],
 				depth_stencil_attachment: None,
@@ -241,25 +561,60 @@ impl<'a> DataForRendering<'a> {
 				occlusion_query_set: None,
 			});
 
-			if matches!(self.selected_camera, WhichCameraToUse::Sun) {
-				let scale = self.window_surface_config.height as f32 / self.sun_cameras[0].height;
-				let w = self.sun_cameras[0].width * scale;
-				let h = self.sun_cameras[0].height * scale;
-				let x = self.window_surface_config.width as f32 / 2.0 - w / 2.0;
-				let y = self.window_surface_config.height as f32 / 2.0 - h / 2.0;
-				render_pass.set_viewport(x, y, w, h, 0.0, 1.0);
-			}
+			self.set_sun_camera_viewport_if_selected(&mut render_pass);
 
 			render_pass.set_pipeline(&self.rendering.skybox_render_pipeline);
 			render_pass.set_bind_group(0, &self.rendering.skybox_bind_group, &[]);
 			render_pass.set_vertex_buffer(0, self.skybox_mesh.vertex_buffer.slice(..));
-			render_pass.draw(0..(self.skybox_mesh.vertices.len() as u32), 0..1);
+			stats.draw_triangles(&mut render_pass, self.skybox_mesh.vertices.len() as u32, 1);
+		}
+
+		if let Some(scene_view) = self.render_scaling.scene_view() {
+			self.lens_effects.render(&mut encoder, scene_view, &mut stats);
+			self.bloom.render(self.queue, &mut encoder, scene_view, &mut stats);
+		}
+
+		self.render_scaling.render_to_window(
+			self.queue,
+			&mut encoder,
+			&window_texture_view,
+			self.color_grading,
+			&mut stats,
+		);
+
+		// Render pass to draw the held block (or hand).
+		// It is rendered over the scene (it is never hidden by the world, as it is not in the world)
+		// but under the interface. It borrows the interface's depth buffer.
+		if let Some(held_block_mesh) = self.held_block_mesh {
+			let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+				label: Some("Render Pass to render the held block"),
+				color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+					view: &window_texture_view,
+					resolve_target: None,
+					ops: wgpu::Operations { load: wgpu::LoadOp::Load, store: wgpu::StoreOp::Store },
+				})],
+				depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+					view: self.interface_z_buffer_view,
+					depth_ops: Some(wgpu::Operations {
+						load: wgpu::LoadOp::Clear(0.0),
+						store: wgpu::StoreOp::Discard,
+					}),
+					stencil_ops: None,
+				}),
+				timestamp_writes: None,
+				occlusion_query_set: None,
+			});
+
+			render_pass.set_pipeline(&self.rendering.held_block_render_pipeline);
+			render_pass.set_bind_group(0, &self.rendering.held_block_bind_group, &[]);
+			render_pass.set_vertex_buffer(0, held_block_mesh.vertex_buffer.slice(..));
+			stats.draw_triangles(&mut render_pass, held_block_mesh.vertex_count, 1);
 		}
 
 		// Render pass to draw the interface.
+		// The interface is not projected by a camera, it does not use reverse-Z.
+		// It is rendered directly to the window (no render scale, no multisampling).
 		{
-			let window_texture_view =
-				window_texture.texture.create_view(&wgpu::TextureViewDescriptor::default());
 			let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
 				label: Some("Render Pass to render the interface"),
 				color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -268,7 +623,7 @@ impl<'a> DataForRendering<'a> {
 					ops: wgpu::Operations { load: wgpu::LoadOp::Load, store: wgpu::StoreOp::Store },
 				})],
 				depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-					view: self.z_buffer_view,
+					view: self.interface_z_buffer_view,
 					depth_ops: Some(wgpu::Operations {
 						load: wgpu::LoadOp::Clear(1.0),
 						store: wgpu::StoreOp::Store,
@@ -286,7 +641,7 @@ impl<'a> DataForRendering<'a> {
 				render_pass.set_pipeline(&self.rendering.simple_line_2d_render_pipeline);
 				render_pass.set_bind_group(0, &self.rendering.simple_line_2d_bind_group, &[]);
 				render_pass.set_vertex_buffer(0, self.cursor_mesh.vertex_buffer.slice(..));
-				render_pass.draw(0..self.cursor_mesh.vertex_count, 0..1);
+				stats.draw_lines(&mut render_pass, self.cursor_mesh.vertex_count);
 			}
 
 			if self.enable_display_interface && !matches!(self.selected_camera, WhichCameraToUse::Sun)
@@ -295,18 +650,31 @@ impl<'a> DataForRendering<'a> {
 				render_pass.set_bind_group(0, &self.rendering.simple_texture_2d_bind_group, &[]);
 				let mesh = &self.interface_simple_texture_mesh;
 				render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
-				render_pass.draw(0..mesh.vertex_count, 0..1);
+				stats.draw_triangles(&mut render_pass, mesh.vertex_count, 1);
 
 				render_pass.set_pipeline(&self.rendering.simple_line_2d_render_pipeline);
 				render_pass.set_bind_group(0, &self.rendering.simple_line_2d_bind_group, &[]);
 				let mesh = &self.interface_simple_line_mesh;
 				render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
-				render_pass.draw(0..mesh.vertex_count, 0..1);
+				stats.draw_lines(&mut render_pass, mesh.vertex_count);
 			}
 		}
 
+		let screenshot_readback = self.screenshot_path.map(|_path| {
+			let readback = ScreenshotReadback::new(self.device, self.window_surface_config);
+			readback.copy_from(&mut encoder, &window_texture.texture);
+			readback
+		});
+
 		let submission = self.queue.submit(std::iter::once(encoder.finish()));
 
+		if let Some((readback, path)) = screenshot_readback.zip(self.screenshot_path) {
+			match readback.save(self.device, path) {
+				Ok(()) => log::debug!("Screenshot saved to \"{}\"", path.display()),
+				Err(error) => log::warn!("Could not take a screenshot: {error}"),
+			}
+		}
+
 		window_texture.present();
 
 		if self.force_block_on_the_presentation {
@@ -320,5 +688,7 @@ impl<'a> DataForRendering<'a> {
 			// Written when using wgpu 0.20.0, this may be fixed later.
 			self.device.poll(wgpu::Maintain::wait_for(submission));
 		}
+
+		stats
 	}
 }
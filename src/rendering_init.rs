@@ -6,7 +6,12 @@ use wgpu::util::DeviceExt;
 
 use crate::{
 	camera::Matrix4x4Pod,
-	shaders::{self, Vector2Pod, Vector3Pod},
+	shaders::{
+		self,
+		block::{NormalMappingSettingsPod, WaterReflectionSettingsPod},
+		held_block::HeldBlockSettingsPod,
+		Vector2Pod, Vector3Pod,
+	},
 };
 
 /// Type representation for the `ty` and `count` fields of a `wgpu::BindGroupLayoutEntry`.
@@ -72,12 +77,13 @@ pub(crate) fn make_z_buffer_texture_view(
 	format: wgpu::TextureFormat,
 	width: u32,
 	height: u32,
+	sample_count: u32,
 ) -> wgpu::TextureView {
 	let z_buffer_texture_description = wgpu::TextureDescriptor {
 		label: Some("Z Buffer"),
 		size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
 		mip_level_count: 1,
-		sample_count: 1,
+		sample_count,
 		dimension: wgpu::TextureDimension::D2,
 		format,
 		view_formats: &[],
@@ -87,11 +93,83 @@ pub(crate) fn make_z_buffer_texture_view(
 	z_buffer_texture.create_view(&wgpu::TextureViewDescriptor::default())
 }
 
+/// With multisample anti-aliasing, the screen is rendered to this multisampled texture
+/// that is then resolved to the window surface texture.
+/// Without multisampling (sample count of 1) there is no need for such texture.
+pub(crate) fn make_msaa_color_texture_view(
+	device: &wgpu::Device,
+	format: wgpu::TextureFormat,
+	width: u32,
+	height: u32,
+	sample_count: u32,
+) -> Option<wgpu::TextureView> {
+	(sample_count > 1).then(|| {
+		let msaa_color_texture = device.create_texture(&wgpu::TextureDescriptor {
+			label: Some("Multisampled Color Buffer"),
+			size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+			mip_level_count: 1,
+			sample_count,
+			dimension: wgpu::TextureDimension::D2,
+			format,
+			view_formats: &[],
+			usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+		});
+		msaa_color_texture.create_view(&wgpu::TextureViewDescriptor::default())
+	})
+}
+
+/// The highest sample count (among 1, 2, 4 and 8) that is not above the requested one
+/// and that is supported by the adapter for all the given formats.
+pub(crate) fn supported_msaa_sample_count(
+	adapter: &wgpu::Adapter,
+	requested_sample_count: u32,
+	formats: &[wgpu::TextureFormat],
+) -> u32 {
+	// Without this feature, only the sample counts required by the WebGPU spec are allowed.
+	let adapter_specific_sample_counts =
+		adapter.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+	[8, 4, 2]
+		.into_iter()
+		.filter(|&sample_count| sample_count <= requested_sample_count)
+		.find(|&sample_count| {
+			formats.iter().all(|&format| {
+				if adapter_specific_sample_counts {
+					adapter
+						.get_texture_format_features(format)
+						.flags
+						.sample_count_supported(sample_count)
+				} else {
+					format
+						.guaranteed_format_features(wgpu::Features::empty())
+						.flags
+						.sample_count_supported(sample_count)
+				}
+			})
+		})
+		.unwrap_or(1)
+}
+
 pub(crate) struct RenderPipelinesAndBindGroups {
 	pub(crate) block_shadow_render_pipeline: wgpu::RenderPipeline,
 	pub(crate) block_shadow_bind_group: wgpu::BindGroup,
 	pub(crate) block_render_pipeline: wgpu::RenderPipeline,
+	/// Renders blocks to the G-buffers in the deferred rendering path.
+	pub(crate) block_gbuffer_render_pipeline: wgpu::RenderPipeline,
+	/// Only available if the device supports it, see `RenderDebugMode::Wireframe`.
+	pub(crate) block_wireframe_render_pipeline: Option<wgpu::RenderPipeline>,
+	/// See `RenderDebugMode::ChunkHeatmap`.
+	pub(crate) block_heatmap_render_pipeline: wgpu::RenderPipeline,
+	/// See `RenderDebugMode::LightLevel`.
+	pub(crate) block_light_render_pipeline: wgpu::RenderPipeline,
 	pub(crate) block_bind_group: wgpu::BindGroup,
+	/// Render the blocks and the skybox to the water reflection, see `WaterReflectionStuff`.
+	pub(crate) block_water_reflection_render_pipeline: wgpu::RenderPipeline,
+	pub(crate) block_water_reflection_bind_group: wgpu::BindGroup,
+	pub(crate) skybox_water_reflection_render_pipeline: wgpu::RenderPipeline,
+	pub(crate) skybox_water_reflection_bind_group: wgpu::BindGroup,
+	/// Render the blocks to a map export texture, see `map_export`.
+	pub(crate) block_map_export_render_pipeline: wgpu::RenderPipeline,
+	pub(crate) block_map_export_bind_group: wgpu::BindGroup,
 	pub(crate) part_textured_shadow_render_pipeline: wgpu::RenderPipeline,
 	pub(crate) part_textured_shadow_bind_group: wgpu::BindGroup,
 	pub(crate) part_textured_render_pipeline: wgpu::RenderPipeline,
@@ -106,8 +184,13 @@ pub(crate) struct RenderPipelinesAndBindGroups {
 	pub(crate) simple_line_2d_bind_group: wgpu::BindGroup,
 	pub(crate) simple_texture_2d_render_pipeline: wgpu::RenderPipeline,
 	pub(crate) simple_texture_2d_bind_group: wgpu::BindGroup,
+	pub(crate) simple_texture_3d_render_pipeline: wgpu::RenderPipeline,
+	pub(crate) simple_texture_3d_see_through_render_pipeline: wgpu::RenderPipeline,
+	pub(crate) simple_texture_3d_bind_group: wgpu::BindGroup,
 	pub(crate) skybox_render_pipeline: wgpu::RenderPipeline,
 	pub(crate) skybox_bind_group: wgpu::BindGroup,
+	pub(crate) held_block_render_pipeline: wgpu::RenderPipeline,
+	pub(crate) held_block_bind_group: wgpu::BindGroup,
 }
 
 pub(crate) struct AllBindingThingies<'a> {
@@ -120,19 +203,28 @@ pub(crate) struct AllBindingThingies<'a> {
 	pub(crate) shadow_map_sampler_thingy: &'a BindingThingy<wgpu::Sampler>,
 	pub(crate) atlas_texture_view_thingy: &'a BindingThingy<wgpu::TextureView>,
 	pub(crate) atlas_texture_sampler_thingy: &'a BindingThingy<wgpu::Sampler>,
+	pub(crate) normal_atlas_texture_view_thingy: &'a BindingThingy<wgpu::TextureView>,
+	pub(crate) normal_mapping_settings_thingy: &'a BindingThingy<wgpu::Buffer>,
 	pub(crate) skybox_cubemap_texture_view_thingy: &'a BindingThingy<wgpu::TextureView>,
 	pub(crate) skybox_cubemap_texture_sampler_thingy: &'a BindingThingy<wgpu::Sampler>,
 	pub(crate) fog_center_position_thingy: &'a BindingThingy<wgpu::Buffer>,
 	pub(crate) fog_inf_sup_radiuses_thingy: &'a BindingThingy<wgpu::Buffer>,
 	pub(crate) texturing_and_coloring_array_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) time_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) entity_interpolation_factor_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) water_reflection_stuff: &'a WaterReflectionStuff,
+	pub(crate) held_block_settings_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) map_export_camera_matrix_thingy: &'a BindingThingy<wgpu::Buffer>,
 }
 
 pub(crate) fn init_rendering_stuff(
 	device: Arc<wgpu::Device>,
 	all_binding_thingies: AllBindingThingies,
+	chunk_instances_bind_group_layout: &wgpu::BindGroupLayout,
 	shadow_map_format: wgpu::TextureFormat,
 	window_surface_format: wgpu::TextureFormat,
 	z_buffer_format: wgpu::TextureFormat,
+	sample_count: u32,
 ) -> RenderPipelinesAndBindGroups {
 	let (block_shadow_render_pipeline, block_shadow_bind_group) =
 		shaders::block_shadow::render_pipeline_and_bind_group(
@@ -144,10 +236,17 @@ pub(crate) fn init_rendering_stuff(
 				fog_center_position_thingy: all_binding_thingies.fog_center_position_thingy,
 				fog_inf_sup_radiuses_thingy: all_binding_thingies.fog_inf_sup_radiuses_thingy,
 			},
+			chunk_instances_bind_group_layout,
 			shadow_map_format,
 		);
 
-	let (block_render_pipeline, block_bind_group) = shaders::block::render_pipeline_and_bind_group(
+	let water_reflection_stuff = all_binding_thingies.water_reflection_stuff;
+	let (
+		block_render_pipelines,
+		block_bind_group,
+		block_water_reflection_bind_group,
+		block_map_export_bind_group,
+	) = shaders::block::render_pipelines_and_bind_groups(
 		&device,
 		shaders::block::BindingThingies {
 			camera_matrix_thingy: all_binding_thingies.camera_matrix_thingy,
@@ -159,9 +258,21 @@ pub(crate) fn init_rendering_stuff(
 			atlas_texture_sampler_thingy: all_binding_thingies.atlas_texture_sampler_thingy,
 			fog_center_position_thingy: all_binding_thingies.fog_center_position_thingy,
 			fog_inf_sup_radiuses_thingy: all_binding_thingies.fog_inf_sup_radiuses_thingy,
+			time_thingy: all_binding_thingies.time_thingy,
+			water_reflection_view_thingy: &water_reflection_stuff.reflection_view_thingy,
+			water_reflection_sampler_thingy: &water_reflection_stuff.reflection_sampler_thingy,
+			water_reflection_settings_thingy: &water_reflection_stuff.settings_thingy,
+			normal_atlas_texture_view_thingy: all_binding_thingies.normal_atlas_texture_view_thingy,
+			normal_mapping_settings_thingy: all_binding_thingies.normal_mapping_settings_thingy,
+			water_reflection_camera_matrix_thingy: &water_reflection_stuff
+				.reflection_camera_matrix_thingy,
+			water_reflection_placeholder_view_thingy: &water_reflection_stuff.placeholder_view_thingy,
+			map_export_camera_matrix_thingy: all_binding_thingies.map_export_camera_matrix_thingy,
 		},
+		chunk_instances_bind_group_layout,
 		window_surface_format,
 		z_buffer_format,
+		sample_count,
 	);
 
 	let (part_textured_shadow_render_pipeline, part_textured_shadow_bind_group) =
@@ -175,6 +286,8 @@ pub(crate) fn init_rendering_stuff(
 				fog_inf_sup_radiuses_thingy: all_binding_thingies.fog_inf_sup_radiuses_thingy,
 				texturing_and_coloring_array_thingy: all_binding_thingies
 					.texturing_and_coloring_array_thingy,
+				entity_interpolation_factor_thingy: all_binding_thingies
+					.entity_interpolation_factor_thingy,
 			},
 			shadow_map_format,
 		);
@@ -194,9 +307,12 @@ pub(crate) fn init_rendering_stuff(
 				shadow_map_sampler_thingy: all_binding_thingies.shadow_map_sampler_thingy,
 				fog_center_position_thingy: all_binding_thingies.fog_center_position_thingy,
 				fog_inf_sup_radiuses_thingy: all_binding_thingies.fog_inf_sup_radiuses_thingy,
+				entity_interpolation_factor_thingy: all_binding_thingies
+					.entity_interpolation_factor_thingy,
 			},
 			window_surface_format,
 			z_buffer_format,
+			sample_count,
 		);
 
 	let (part_colored_shadow_render_pipeline, part_colored_shadow_bind_group) =
@@ -206,6 +322,8 @@ pub(crate) fn init_rendering_stuff(
 				sun_camera_single_matrix_thingy: all_binding_thingies.sun_camera_single_matrix_thingy,
 				fog_center_position_thingy: all_binding_thingies.fog_center_position_thingy,
 				fog_inf_sup_radiuses_thingy: all_binding_thingies.fog_inf_sup_radiuses_thingy,
+				entity_interpolation_factor_thingy: all_binding_thingies
+					.entity_interpolation_factor_thingy,
 			},
 			shadow_map_format,
 		);
@@ -223,9 +341,12 @@ pub(crate) fn init_rendering_stuff(
 				shadow_map_sampler_thingy: all_binding_thingies.shadow_map_sampler_thingy,
 				fog_center_position_thingy: all_binding_thingies.fog_center_position_thingy,
 				fog_inf_sup_radiuses_thingy: all_binding_thingies.fog_inf_sup_radiuses_thingy,
+				entity_interpolation_factor_thingy: all_binding_thingies
+					.entity_interpolation_factor_thingy,
 			},
 			window_surface_format,
 			z_buffer_format,
+			sample_count,
 		);
 
 	let (simple_line_render_pipeline, simple_line_bind_group) =
@@ -236,8 +357,10 @@ pub(crate) fn init_rendering_stuff(
 			},
 			window_surface_format,
 			z_buffer_format,
+			sample_count,
 		);
 
+	// The interface is rendered directly to the window, without multisampling.
 	let (simple_line_2d_render_pipeline, simple_line_2d_bind_group) =
 		shaders::simple_line_2d::render_pipeline(
 			&device,
@@ -260,6 +383,22 @@ pub(crate) fn init_rendering_stuff(
 			z_buffer_format,
 		);
 
+	let (
+		simple_texture_3d_render_pipeline,
+		simple_texture_3d_see_through_render_pipeline,
+		simple_texture_3d_bind_group,
+	) = shaders::simple_texture_3d::render_pipelines(
+		&device,
+		shaders::simple_texture_3d::BindingThingies {
+			camera_matrix_thingy: all_binding_thingies.camera_matrix_thingy,
+			atlas_texture_view_thingy: all_binding_thingies.atlas_texture_view_thingy,
+			atlas_texture_sampler_thingy: all_binding_thingies.atlas_texture_sampler_thingy,
+		},
+		window_surface_format,
+		z_buffer_format,
+		sample_count,
+	);
+
 	let (skybox_render_pipeline, skybox_bind_group) =
 		shaders::skybox::render_pipeline_and_bind_group(
 			&device,
@@ -271,13 +410,50 @@ pub(crate) fn init_rendering_stuff(
 					.skybox_cubemap_texture_sampler_thingy,
 			},
 			window_surface_format,
+			sample_count,
+		);
+	let (skybox_water_reflection_render_pipeline, skybox_water_reflection_bind_group) =
+		shaders::skybox::render_pipeline_and_bind_group(
+			&device,
+			shaders::skybox::BindingThingies {
+				camera_matrix_thingy: &water_reflection_stuff.reflection_camera_matrix_thingy,
+				skybox_cubemap_texture_view_thingy: all_binding_thingies
+					.skybox_cubemap_texture_view_thingy,
+				skybox_cubemap_texture_sampler_thingy: all_binding_thingies
+					.skybox_cubemap_texture_sampler_thingy,
+			},
+			window_surface_format,
+			1,
+		);
+
+	// The held block is rendered directly to the window, without multisampling.
+	let (held_block_render_pipeline, held_block_bind_group) =
+		shaders::held_block::render_pipeline_and_bind_group(
+			&device,
+			shaders::held_block::BindingThingies {
+				held_block_settings_thingy: all_binding_thingies.held_block_settings_thingy,
+				atlas_texture_view_thingy: all_binding_thingies.atlas_texture_view_thingy,
+				atlas_texture_sampler_thingy: all_binding_thingies.atlas_texture_sampler_thingy,
+			},
+			window_surface_format,
+			z_buffer_format,
 		);
 
 	RenderPipelinesAndBindGroups {
 		block_shadow_render_pipeline,
 		block_shadow_bind_group,
-		block_render_pipeline,
+		block_render_pipeline: block_render_pipelines.forward,
+		block_gbuffer_render_pipeline: block_render_pipelines.gbuffer,
+		block_wireframe_render_pipeline: block_render_pipelines.wireframe,
+		block_heatmap_render_pipeline: block_render_pipelines.heatmap,
+		block_light_render_pipeline: block_render_pipelines.block_light,
 		block_bind_group,
+		block_water_reflection_render_pipeline: block_render_pipelines.water_reflection,
+		block_water_reflection_bind_group,
+		skybox_water_reflection_render_pipeline,
+		skybox_water_reflection_bind_group,
+		block_map_export_render_pipeline: block_render_pipelines.map_export,
+		block_map_export_bind_group,
 		part_textured_shadow_render_pipeline,
 		part_textured_shadow_bind_group,
 		part_textured_render_pipeline,
@@ -292,8 +468,13 @@ pub(crate) fn init_rendering_stuff(
 		simple_line_2d_bind_group,
 		simple_texture_2d_render_pipeline,
 		simple_texture_2d_bind_group,
+		simple_texture_3d_render_pipeline,
+		simple_texture_3d_see_through_render_pipeline,
+		simple_texture_3d_bind_group,
 		skybox_render_pipeline,
 		skybox_bind_group,
+		held_block_render_pipeline,
+		held_block_bind_group,
 	}
 }
 
@@ -317,6 +498,93 @@ pub(crate) fn init_aspect_ratio_thingy(device: Arc<wgpu::Device>) -> BindingThin
 	}
 }
 
+/// Time (in seconds) used by shaders to animate stuff.
+pub(crate) fn init_time_thingy(device: Arc<wgpu::Device>) -> BindingThingy<wgpu::Buffer> {
+	let time_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+		label: Some("Time Buffer"),
+		contents: bytemuck::cast_slice(&[f32::zeroed()]),
+		usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+	});
+	let time_binding_type = BindingType {
+		ty: wgpu::BindingType::Buffer {
+			ty: wgpu::BufferBindingType::Uniform,
+			has_dynamic_offset: false,
+			min_binding_size: None,
+		},
+		count: None,
+	};
+	BindingThingy { binding_type: time_binding_type, resource: time_buffer }
+}
+
+/// Where the entity parts are rendered between their state before and after the last entity
+/// physics step, see `PartsInterpolation`.
+pub(crate) fn init_entity_interpolation_factor_thingy(
+	device: Arc<wgpu::Device>,
+) -> BindingThingy<wgpu::Buffer> {
+	let factor_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+		label: Some("Entity Interpolation Factor Buffer"),
+		contents: bytemuck::cast_slice(&[1.0f32]),
+		usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+	});
+	let factor_binding_type = BindingType {
+		ty: wgpu::BindingType::Buffer {
+			ty: wgpu::BufferBindingType::Uniform,
+			has_dynamic_offset: false,
+			min_binding_size: None,
+		},
+		count: None,
+	};
+	BindingThingy { binding_type: factor_binding_type, resource: factor_buffer }
+}
+
+/// See `HeldBlockView`.
+pub(crate) fn init_held_block_settings_thingy(
+	device: Arc<wgpu::Device>,
+) -> BindingThingy<wgpu::Buffer> {
+	let held_block_settings_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+		label: Some("Held Block Settings Buffer"),
+		contents: bytemuck::cast_slice(&[HeldBlockSettingsPod::zeroed()]),
+		usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+	});
+	let held_block_settings_binding_type = BindingType {
+		ty: wgpu::BindingType::Buffer {
+			ty: wgpu::BufferBindingType::Uniform,
+			has_dynamic_offset: false,
+			min_binding_size: None,
+		},
+		count: None,
+	};
+	BindingThingy {
+		binding_type: held_block_settings_binding_type,
+		resource: held_block_settings_buffer,
+	}
+}
+
+/// See `NormalMappingSettingsPod`.
+pub(crate) fn init_normal_mapping_settings_thingy(
+	device: Arc<wgpu::Device>,
+	settings: NormalMappingSettingsPod,
+) -> BindingThingy<wgpu::Buffer> {
+	let normal_mapping_settings_buffer =
+		device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("Normal Mapping Settings Buffer"),
+			contents: bytemuck::cast_slice(&[settings]),
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+		});
+	let normal_mapping_settings_binding_type = BindingType {
+		ty: wgpu::BindingType::Buffer {
+			ty: wgpu::BufferBindingType::Uniform,
+			has_dynamic_offset: false,
+			min_binding_size: None,
+		},
+		count: None,
+	};
+	BindingThingy {
+		binding_type: normal_mapping_settings_binding_type,
+		resource: normal_mapping_settings_buffer,
+	}
+}
+
 pub(crate) struct ShadowMapStuff {
 	pub(crate) shadow_map_format: wgpu::TextureFormat,
 	/// View on the whole texture array, all cascades included.
@@ -365,7 +633,8 @@ pub(crate) fn init_shadow_map_stuff(
 		mag_filter: wgpu::FilterMode::Linear,
 		min_filter: wgpu::FilterMode::Linear,
 		mipmap_filter: wgpu::FilterMode::Nearest,
-		compare: Some(wgpu::CompareFunction::LessEqual),
+		// Reverse-Z (see `CameraSettings::view_projection_matrix`), greater depth is closer.
+		compare: Some(wgpu::CompareFunction::GreaterEqual),
 		..Default::default()
 	});
 	let shadow_map_sampler_binding_type = BindingType {
@@ -505,45 +774,49 @@ pub(crate) struct AtlasStuff {
 	pub(crate) atlas_texture_view_thingy: BindingThingy<wgpu::TextureView>,
 	pub(crate) atlas_texture_sampler_thingy: BindingThingy<wgpu::Sampler>,
 	pub(crate) atlas_texture: wgpu::Texture,
+	/// See `Atlas::normal_atlas_image`, sampled with the atlas sampler.
+	pub(crate) normal_atlas_texture_view_thingy: BindingThingy<wgpu::TextureView>,
+	pub(crate) normal_atlas_texture: wgpu::Texture,
 }
 pub(crate) fn init_atlas_stuff(
 	device: Arc<wgpu::Device>,
 	queue: &wgpu::Queue,
 	atlas_data: &[u8],
+	normal_atlas_data: &[u8],
 ) -> AtlasStuff {
 	assert_eq!(atlas_data.len(), 4 * ATLAS_DIMS.0 * ATLAS_DIMS.1);
+	assert_eq!(normal_atlas_data.len(), 4 * ATLAS_DIMS.0 * ATLAS_DIMS.1);
 
 	let atlas_texture_size = wgpu::Extent3d {
 		width: ATLAS_DIMS.0 as u32,
 		height: ATLAS_DIMS.1 as u32,
 		depth_or_array_layers: 1,
 	};
-	let atlas_texture = device.create_texture(&wgpu::TextureDescriptor {
-		label: Some("Atlas Texture"),
-		size: atlas_texture_size,
-		mip_level_count: 1,
-		sample_count: 1,
-		dimension: wgpu::TextureDimension::D2,
-		format: wgpu::TextureFormat::Rgba8UnormSrgb,
-		usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-		view_formats: &[],
-	});
-	queue.write_texture(
-		wgpu::ImageCopyTexture {
-			texture: &atlas_texture,
-			mip_level: 0,
-			origin: wgpu::Origin3d::ZERO,
-			aspect: wgpu::TextureAspect::All,
-		},
+	let make_texture = |label, format, data| {
+		let texture = device.create_texture(&wgpu::TextureDescriptor {
+			label: Some(label),
+			size: atlas_texture_size,
+			mip_level_count: 1,
+			sample_count: 1,
+			dimension: wgpu::TextureDimension::D2,
+			format,
+			usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+			view_formats: &[],
+		});
+		update_atlas_texture(queue, &texture, &data);
+		texture
+	};
+	let atlas_texture = make_texture(
+		"Atlas Texture",
+		wgpu::TextureFormat::Rgba8UnormSrgb,
 		atlas_data,
-		wgpu::ImageDataLayout {
-			offset: 0,
-			bytes_per_row: Some(4 * ATLAS_DIMS.0 as u32),
-			rows_per_image: Some(ATLAS_DIMS.1 as u32),
-		},
-		atlas_texture_size,
 	);
-	let atlas_texture_view = atlas_texture.create_view(&wgpu::TextureViewDescriptor::default());
+	// The normals and heights are not colors, they are not in sRGB.
+	let normal_atlas_texture = make_texture(
+		"Normal Atlas Texture",
+		wgpu::TextureFormat::Rgba8Unorm,
+		normal_atlas_data,
+	);
 	let atlas_texture_view_binding_type = BindingType {
 		ty: wgpu::BindingType::Texture {
 			multisampled: false,
@@ -553,8 +826,12 @@ pub(crate) fn init_atlas_stuff(
 		count: None,
 	};
 	let atlas_texture_view_thingy = BindingThingy {
+		binding_type: atlas_texture_view_binding_type.clone(),
+		resource: atlas_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+	};
+	let normal_atlas_texture_view_thingy = BindingThingy {
 		binding_type: atlas_texture_view_binding_type,
-		resource: atlas_texture_view,
+		resource: normal_atlas_texture.create_view(&wgpu::TextureViewDescriptor::default()),
 	};
 	let atlas_texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
 		address_mode_u: wgpu::AddressMode::ClampToEdge,
@@ -578,6 +855,8 @@ pub(crate) fn init_atlas_stuff(
 		atlas_texture_view_thingy,
 		atlas_texture_sampler_thingy,
 		atlas_texture,
+		normal_atlas_texture_view_thingy,
+		normal_atlas_texture,
 	}
 }
 
@@ -608,6 +887,34 @@ pub(crate) fn update_atlas_texture(
 	);
 }
 
+/// Replaces a rectangle of the atlas texture (at `coords` in pixels) by the given image.
+pub(crate) fn update_atlas_texture_rect(
+	queue: &wgpu::Queue,
+	atlas_texture: &wgpu::Texture,
+	coords: (u32, u32),
+	image: &image::RgbaImage,
+) {
+	queue.write_texture(
+		wgpu::ImageCopyTexture {
+			texture: atlas_texture,
+			mip_level: 0,
+			origin: wgpu::Origin3d { x: coords.0, y: coords.1, z: 0 },
+			aspect: wgpu::TextureAspect::All,
+		},
+		image.as_ref(),
+		wgpu::ImageDataLayout {
+			offset: 0,
+			bytes_per_row: Some(4 * image.width()),
+			rows_per_image: Some(image.height()),
+		},
+		wgpu::Extent3d {
+			width: image.width(),
+			height: image.height(),
+			depth_or_array_layers: 1,
+		},
+	);
+}
+
 use crate::skybox::SKYBOX_SIDE_DIMS;
 
 pub(crate) struct SkyboxStuff {
@@ -757,6 +1064,123 @@ pub(crate) fn init_fog_stuff(device: Arc<wgpu::Device>) -> FogStuff {
 	FogStuff { fog_center_position_thingy, fog_inf_sup_radiuses_thingy }
 }
 
+/// Water reflects the world in a planar mirror: the world is rendered a second time to
+/// the reflection texture, seen from the reflection of the camera in the water plane
+/// (see `ChunkGrid::dominant_water_plane_z`), then the water surfaces sample that texture
+/// where they are on the screen (as this is where their reflection is for a planar mirror)
+/// and blend it in with a Fresnel factor. Can be disabled as it costs a second rendering
+/// of the chunk meshes.
+pub(crate) struct WaterReflectionStuff {
+	pub(crate) reflection_view_thingy: BindingThingy<wgpu::TextureView>,
+	pub(crate) reflection_sampler_thingy: BindingThingy<wgpu::Sampler>,
+	/// Bound instead of the reflection texture when rendering the reflection (a texture cannot be
+	/// sampled while being rendered to), the water surfaces are not rendered in the reflection.
+	pub(crate) placeholder_view_thingy: BindingThingy<wgpu::TextureView>,
+	pub(crate) reflection_z_buffer_view: wgpu::TextureView,
+	/// Replaces the camera matrix when rendering the reflection.
+	pub(crate) reflection_camera_matrix_thingy: BindingThingy<wgpu::Buffer>,
+	/// See `WaterReflectionSettingsPod`.
+	pub(crate) settings_thingy: BindingThingy<wgpu::Buffer>,
+}
+/// The reflection texture does not depend on the window size, the waves distort the reflection
+/// anyway so it does not need to be very sharp.
+pub(crate) const WATER_REFLECTION_TEXTURE_SIZE: u32 = 1024;
+pub(crate) fn init_water_reflection_stuff(
+	device: Arc<wgpu::Device>,
+	color_format: wgpu::TextureFormat,
+	z_buffer_format: wgpu::TextureFormat,
+) -> WaterReflectionStuff {
+	let make_color_texture_view = |label, size| {
+		let texture = device.create_texture(&wgpu::TextureDescriptor {
+			label: Some(label),
+			size: wgpu::Extent3d { width: size, height: size, depth_or_array_layers: 1 },
+			mip_level_count: 1,
+			sample_count: 1,
+			dimension: wgpu::TextureDimension::D2,
+			format: color_format,
+			usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+			view_formats: &[],
+		});
+		texture.create_view(&wgpu::TextureViewDescriptor::default())
+	};
+	let reflection_view_binding_type = BindingType {
+		ty: wgpu::BindingType::Texture {
+			sample_type: wgpu::TextureSampleType::Float { filterable: true },
+			view_dimension: wgpu::TextureViewDimension::D2,
+			multisampled: false,
+		},
+		count: None,
+	};
+	let reflection_view_thingy = BindingThingy {
+		binding_type: reflection_view_binding_type.clone(),
+		resource: make_color_texture_view("Water Reflection Texture", WATER_REFLECTION_TEXTURE_SIZE),
+	};
+	let placeholder_view_thingy = BindingThingy {
+		binding_type: reflection_view_binding_type,
+		resource: make_color_texture_view("Water Reflection Placeholder Texture", 1),
+	};
+	let reflection_z_buffer_view = make_z_buffer_texture_view(
+		&device,
+		z_buffer_format,
+		WATER_REFLECTION_TEXTURE_SIZE,
+		WATER_REFLECTION_TEXTURE_SIZE,
+		1,
+	);
+
+	let reflection_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+		label: Some("Water Reflection Sampler"),
+		address_mode_u: wgpu::AddressMode::ClampToEdge,
+		address_mode_v: wgpu::AddressMode::ClampToEdge,
+		address_mode_w: wgpu::AddressMode::ClampToEdge,
+		mag_filter: wgpu::FilterMode::Linear,
+		min_filter: wgpu::FilterMode::Linear,
+		mipmap_filter: wgpu::FilterMode::Nearest,
+		..Default::default()
+	});
+	let reflection_sampler_thingy = BindingThingy {
+		binding_type: BindingType {
+			ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+			count: None,
+		},
+		resource: reflection_sampler,
+	};
+
+	let uniform_binding_type = BindingType {
+		ty: wgpu::BindingType::Buffer {
+			ty: wgpu::BufferBindingType::Uniform,
+			has_dynamic_offset: false,
+			min_binding_size: None,
+		},
+		count: None,
+	};
+	let reflection_camera_matrix_buffer =
+		device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("Water Reflection Camera Buffer"),
+			contents: bytemuck::cast_slice(&[Matrix4x4Pod::zeroed()]),
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+		});
+	let reflection_camera_matrix_thingy = BindingThingy {
+		binding_type: uniform_binding_type.clone(),
+		resource: reflection_camera_matrix_buffer,
+	};
+	let settings_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+		label: Some("Water Reflection Settings Buffer"),
+		contents: bytemuck::cast_slice(&[WaterReflectionSettingsPod::zeroed()]),
+		usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+	});
+	let settings_thingy =
+		BindingThingy { binding_type: uniform_binding_type, resource: settings_buffer };
+
+	WaterReflectionStuff {
+		reflection_view_thingy,
+		reflection_sampler_thingy,
+		placeholder_view_thingy,
+		reflection_z_buffer_view,
+		reflection_camera_matrix_thingy,
+		settings_thingy,
+	}
+}
+
 const TEXTURING_AND_COLORING_ARRAY_LENGTH: usize = 10000;
 
 pub(crate) fn init_texturing_and_coloring_array_thingy(
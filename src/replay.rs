@@ -0,0 +1,282 @@
+//! Replays, the sessions of the player can be recorded to a file (with `--record-replay`)
+//! and played back later (with `--play-replay`), to debug the physics or to make timelapses.
+//!
+//! At every frame the time step, the inputs of the player (walking, jumping and sneaking) and
+//! the position of the player are recorded, along with the blocks placed and broken by the player.
+//! When played back, one recorded frame is played per frame (with the recorded time step, so that
+//! the simulation gets the same time steps as when it was recorded) and the recorded blocks are
+//! placed again, while the camera can move freely with the walking controls and the mouse.
+//! A replay can be played back in two ways (see `PlaybackMode`).
+//!
+//! The world is generated again from the seed, the world generator and the chunk dimensions of
+//! the recording. Playing a replay with a save that was modified since the recording started
+//! does not give the same world.
+//!
+//! A replay file is a deflate-compressed stream of MessagePack values, a `ReplayHeader` followed
+//! by one `ReplayFrame` per frame. If the game does not exit cleanly then the last frames are lost.
+
+use std::{
+	fs::File,
+	io::{BufReader, BufWriter, Write},
+	path::Path,
+	time::Duration,
+};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{chunk_blocks::Block, coords::BlockCoords, world_gen::WhichWorldGenerator};
+
+/// Incremented when the format of the replay files changes.
+const REPLAY_FORMAT_VERSION: u32 = 0;
+
+/// When the resimulated position of the player gets this far (in blocks) from the recorded
+/// position, the divergence is reported.
+const DIVERGENCE_THRESHOLD: f32 = 0.01;
+
+/// What is needed to generate the same world as when the replay was recorded.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ReplayHeader {
+	format_version: u32,
+	pub(crate) world_gen_seed: i32,
+	pub(crate) which_world_generator: WhichWorldGenerator,
+	pub(crate) chunk_edge: i32,
+}
+
+impl ReplayHeader {
+	pub(crate) fn new(
+		world_gen_seed: i32,
+		which_world_generator: WhichWorldGenerator,
+		chunk_edge: i32,
+	) -> ReplayHeader {
+		ReplayHeader {
+			format_version: REPLAY_FORMAT_VERSION,
+			world_gen_seed,
+			which_world_generator,
+			chunk_edge,
+		}
+	}
+}
+
+/// What the player asked the player physics to do during a frame.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct PlayerInputs {
+	/// In world space (it already takes into account the direction of the camera).
+	pub(crate) walking_vector: cgmath::Vector3<f32>,
+	pub(crate) jump: bool,
+	pub(crate) sneak: bool,
+}
+
+impl PlayerInputs {
+	pub(crate) fn none() -> PlayerInputs {
+		PlayerInputs {
+			walking_vector: cgmath::vec3(0.0, 0.0, 0.0),
+			jump: false,
+			sneak: false,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ReplayFrame {
+	pub(crate) dt: Duration,
+	pub(crate) inputs: PlayerInputs,
+	/// Where the player was at the end of the frame.
+	pub(crate) player_position: cgmath::Point3<f32>,
+	/// The blocks placed by the player during the frame (breaking a block places air).
+	pub(crate) edits: Vec<(BlockCoords, Block)>,
+}
+
+/// Writes the frames to a replay file as they are played.
+pub(crate) struct ReplayRecorder {
+	encoder: flate2::write::DeflateEncoder<BufWriter<File>>,
+	/// The edits and the jump of the frame being played, they are recorded with it.
+	edits: Vec<(BlockCoords, Block)>,
+	jump: bool,
+	frame_count: u64,
+}
+
+impl ReplayRecorder {
+	pub(crate) fn create(path: &Path, header: &ReplayHeader) -> Result<ReplayRecorder, String> {
+		let file = File::create(path).map_err(|error| error.to_string())?;
+		let mut encoder =
+			flate2::write::DeflateEncoder::new(BufWriter::new(file), flate2::Compression::default());
+		rmp_serde::encode::write(&mut encoder, header).map_err(|error| error.to_string())?;
+		Ok(ReplayRecorder { encoder, edits: vec![], jump: false, frame_count: 0 })
+	}
+
+	/// The player placed the given block during the frame being played.
+	pub(crate) fn record_edit(&mut self, coords: BlockCoords, block: &Block) {
+		self.edits.push((coords, block.clone()));
+	}
+
+	/// The player jumped during the frame being played.
+	pub(crate) fn record_jump(&mut self) {
+		self.jump = true;
+	}
+
+	/// Records the frame that was just played.
+	pub(crate) fn record_frame(
+		&mut self,
+		dt: Duration,
+		walking_vector: cgmath::Vector3<f32>,
+		sneak: bool,
+		player_position: cgmath::Point3<f32>,
+	) -> Result<(), String> {
+		let frame = ReplayFrame {
+			dt,
+			inputs: PlayerInputs { walking_vector, jump: self.jump, sneak },
+			player_position,
+			edits: std::mem::take(&mut self.edits),
+		};
+		self.jump = false;
+		self.frame_count += 1;
+		rmp_serde::encode::write(&mut self.encoder, &frame).map_err(|error| error.to_string())
+	}
+
+	/// Must be called at the end of the recording, else the end of the file is missing.
+	pub(crate) fn finish(self) -> Result<u64, String> {
+		let mut writer = self.encoder.finish().map_err(|error| error.to_string())?;
+		writer.flush().map_err(|error| error.to_string())?;
+		Ok(self.frame_count)
+	}
+}
+
+/// How the player is moved when a replay is played.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum PlaybackMode {
+	/// The player is moved to the recorded positions.
+	Replay,
+	/// The recorded inputs are given to the player physics again (starting from the first recorded
+	/// position) and the differences with the recorded positions are reported.
+	/// The physics that depends on the real time (like how late a jump can be after leaving
+	/// the ground) or on what was loaded at the time may diverge.
+	Resimulate,
+}
+
+/// A replay being played back.
+pub(crate) struct ReplayPlayback {
+	mode: PlaybackMode,
+	header: ReplayHeader,
+	/// The frames that were not played yet.
+	frames: std::collections::VecDeque<ReplayFrame>,
+	frame_count: usize,
+	/// The camera moves freely while the player is replayed.
+	free_camera_position: Option<cgmath::Point3<f32>>,
+	/// The greatest distance between the resimulated and the recorded positions of the player.
+	max_divergence: f32,
+	divergence_reported: bool,
+	end_reported: bool,
+}
+
+impl ReplayPlayback {
+	pub(crate) fn open(path: &Path, mode: PlaybackMode) -> Result<ReplayPlayback, String> {
+		let file = File::open(path).map_err(|error| error.to_string())?;
+		let mut decoder = flate2::bufread::DeflateDecoder::new(BufReader::new(file));
+		let header: ReplayHeader =
+			rmp_serde::decode::from_read(&mut decoder).map_err(|error| error.to_string())?;
+		if header.format_version != REPLAY_FORMAT_VERSION {
+			return Err(format!(
+				"Unsupported replay format version {} (expected {REPLAY_FORMAT_VERSION})",
+				header.format_version
+			));
+		}
+		let mut frames = std::collections::VecDeque::new();
+		loop {
+			match rmp_serde::decode::from_read::<_, ReplayFrame>(&mut decoder) {
+				Ok(frame) => frames.push_back(frame),
+				Err(rmp_serde::decode::Error::InvalidMarkerRead(error))
+					if error.kind() == std::io::ErrorKind::UnexpectedEof =>
+				{
+					break;
+				},
+				Err(error) => {
+					log::warn!(
+						"The replay ends with a frame that could not be read \
+						(the recording was not finished properly?): {error}"
+					);
+					break;
+				},
+			}
+		}
+		let frame_count = frames.len();
+		Ok(ReplayPlayback {
+			mode,
+			header,
+			frames,
+			frame_count,
+			free_camera_position: None,
+			max_divergence: 0.0,
+			divergence_reported: false,
+			end_reported: false,
+		})
+	}
+
+	pub(crate) fn header(&self) -> &ReplayHeader {
+		&self.header
+	}
+
+	pub(crate) fn frame_count(&self) -> usize {
+		self.frame_count
+	}
+
+	/// The next frame to play, if the replay is not over.
+	pub(crate) fn next_frame(&mut self) -> Option<ReplayFrame> {
+		let frame = self.frames.pop_front();
+		if frame.is_none() && !self.end_reported {
+			self.end_reported = true;
+			log::info!("End of the replay ({} frames)", self.frame_count);
+			if self.mode == PlaybackMode::Resimulate {
+				log::info!(
+					"Greatest distance between the resimulated and the recorded positions \
+					of the player: {} blocks",
+					self.max_divergence
+				);
+			}
+		}
+		frame
+	}
+
+	/// The position that the player is moved to for the given frame (that was just taken from
+	/// `next_frame`), if any. Else the player physics is run with the recorded inputs.
+	pub(crate) fn imposed_player_position(
+		&self,
+		frame: &ReplayFrame,
+	) -> Option<cgmath::Point3<f32>> {
+		let is_first_frame = self.frames.len() + 1 == self.frame_count;
+		(self.mode == PlaybackMode::Replay || is_first_frame).then_some(frame.player_position)
+	}
+
+	/// Reports (once) when the resimulated position of the player diverges from the recorded one.
+	pub(crate) fn compare_player_position(
+		&mut self,
+		frame: &ReplayFrame,
+		player_position: cgmath::Point3<f32>,
+	) {
+		use cgmath::MetricSpace;
+		let divergence = player_position.distance(frame.player_position);
+		self.max_divergence = self.max_divergence.max(divergence);
+		if divergence > DIVERGENCE_THRESHOLD && !self.divergence_reported {
+			self.divergence_reported = true;
+			let frame_index = self.frame_count - self.frames.len() - 1;
+			log::warn!(
+				"The resimulation diverges at frame {frame_index}: \
+				the player is at {player_position:?} instead of {:?}",
+				frame.player_position
+			);
+		}
+	}
+
+	/// Moves the free camera by the given walking (of the user, not of the replayed player),
+	/// returns its position. It starts where the player is.
+	pub(crate) fn move_free_camera(
+		&mut self,
+		walking_vector: cgmath::Vector3<f32>,
+		real_dt: Duration,
+		player_eye_position: cgmath::Point3<f32>,
+	) -> cgmath::Point3<f32> {
+		let position = self.free_camera_position.get_or_insert(player_eye_position);
+		*position += walking_vector * real_dt.as_secs_f32();
+		*position
+	}
+}
@@ -0,0 +1,122 @@
+//! Quitting the game without losing anything, all the state that is to be persistent is written
+//! to the save before the game exits.
+//!
+//! Quitting goes through steps that can take several frames (with a progress bar displayed):
+//! first no new work is started and the tasks which results are to be saved are waited for,
+//! then the chunks are saved a few at a time (so that the progress can be displayed), and then
+//! the game exits (the rest of the state is saved when exiting, see `exiting`).
+
+use std::{
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+use crate::{chunks::ChunkGrid, coords::ChunkCoords, saves::Save, tasks::WorkerTasksManager};
+
+/// Time spent saving chunks in one frame, the progress is displayed between the frames.
+const SAVING_TIME_BUDGET_PER_FRAME: Duration = Duration::from_millis(20);
+
+/// How far quitting went, to be displayed.
+pub(crate) struct SavingProgress {
+	/// The tasks that are still being waited for before saving.
+	pub(crate) tasks_to_wait_for: usize,
+	pub(crate) chunk_count: usize,
+	pub(crate) saved_chunk_count: usize,
+}
+
+impl SavingProgress {
+	/// From 0.0 to 1.0, waiting for the tasks does not count.
+	pub(crate) fn ratio(&self) -> f32 {
+		if self.chunk_count == 0 {
+			0.0
+		} else {
+			self.saved_chunk_count as f32 / self.chunk_count as f32
+		}
+	}
+}
+
+enum Step {
+	WaitingForTasks,
+	/// The chunks that are yet to be saved.
+	SavingChunks(Vec<ChunkCoords>),
+	Done,
+}
+
+pub(crate) struct SaveAndQuit {
+	step: Step,
+	tasks_to_wait_for: usize,
+	chunk_count: usize,
+	started_at: Instant,
+}
+
+impl SaveAndQuit {
+	pub(crate) fn new() -> SaveAndQuit {
+		SaveAndQuit {
+			step: Step::WaitingForTasks,
+			tasks_to_wait_for: 0,
+			chunk_count: 0,
+			started_at: Instant::now(),
+		}
+	}
+
+	/// Makes some progress, it is meant to be called once per frame while no new work is started
+	/// and while the chunk grid is exclusively owned.
+	pub(crate) fn advance(
+		&mut self,
+		chunk_grid: &ChunkGrid,
+		worker_tasks: &WorkerTasksManager,
+		save: Option<&Arc<Save>>,
+		only_save_modified_chunks: bool,
+	) {
+		match &mut self.step {
+			Step::WaitingForTasks if save.is_none() => {
+				// Nothing is to be saved, no need to wait.
+				self.step = Step::Done;
+			},
+			Step::WaitingForTasks => {
+				self.tasks_to_wait_for = worker_tasks.how_many_tasks_with_results_to_save();
+				if self.tasks_to_wait_for == 0 {
+					let chunks_to_save = chunk_grid.chunks_to_save();
+					self.chunk_count = chunks_to_save.len();
+					self.step = Step::SavingChunks(chunks_to_save);
+				}
+			},
+			Step::SavingChunks(chunks_to_save) => {
+				let frame_start = Instant::now();
+				while frame_start.elapsed() < SAVING_TIME_BUDGET_PER_FRAME {
+					let Some(chunk_coords) = chunks_to_save.pop() else {
+						break;
+					};
+					chunk_grid.save_chunk(chunk_coords, save, only_save_modified_chunks);
+				}
+				if chunks_to_save.is_empty() {
+					log::info!(
+						"Saved {} chunks in {:.2} seconds before quitting",
+						self.chunk_count,
+						self.started_at.elapsed().as_secs_f32()
+					);
+					self.step = Step::Done;
+				}
+			},
+			Step::Done => {},
+		}
+	}
+
+	/// Are all the chunks saved? The game can exit then.
+	pub(crate) fn is_done(&self) -> bool {
+		matches!(self.step, Step::Done)
+	}
+
+	pub(crate) fn progress(&self) -> SavingProgress {
+		let saved_chunk_count = match &self.step {
+			Step::WaitingForTasks => 0,
+			Step::SavingChunks(chunks_to_save) => self.chunk_count - chunks_to_save.len(),
+			Step::Done => self.chunk_count,
+		};
+		SavingProgress {
+			tasks_to_wait_for: self.tasks_to_wait_for,
+			chunk_count: self.chunk_count,
+			saved_chunk_count,
+		}
+	}
+}
@@ -4,7 +4,7 @@ use std::{
 	collections::HashMap,
 	io::{Read, Write},
 	path::PathBuf,
-	sync::{Arc, RwLock},
+	sync::{Arc, RwLock, RwLockWriteGuard},
 };
 
 use fxhash::FxHashMap;
@@ -17,9 +17,13 @@ pub(crate) struct Save {
 	pub(crate) name: String,
 	pub(crate) main_directory: std::path::PathBuf,
 	pub(crate) state_file_path: std::path::PathBuf,
+	/// The names of the block types, one per line, in the order of their ids.
+	pub(crate) block_type_names_file_path: std::path::PathBuf,
 	chunks_directory: std::path::PathBuf,
 	pub(crate) textures_directory: std::path::PathBuf,
 	pub(crate) atlas_texture_file_path: std::path::PathBuf,
+	/// See `Atlas::emissive_mask`.
+	pub(crate) atlas_emissive_mask_file_path: std::path::PathBuf,
 
 	/// Super mega thread safe file i/o manager that enforces rust's borrow cheking rules on files.
 	file_io_table: RwLock<FxHashMap<PathBuf, Arc<RwLock<FileIoToken>>>>,
@@ -33,18 +37,26 @@ pub(crate) enum WhichChunkFile {
 impl Save {
 	pub(crate) fn create(name: String) -> Save {
 		assert!(name.chars().all(|c| c.is_ascii_alphanumeric()));
-		let main_directory = {
-			let mut main_directory = std::path::PathBuf::new();
-			main_directory.push("saves");
-			main_directory.push(&name);
-			std::fs::create_dir_all(&main_directory).unwrap();
-			main_directory
-		};
+		let mut main_directory = std::path::PathBuf::new();
+		main_directory.push("saves");
+		main_directory.push(&name);
+		Save::create_in_directory(name, main_directory)
+	}
+
+	/// Creates or opens the save of the given name that is in the given directory
+	/// (instead of in the `saves` directory).
+	pub(crate) fn create_in_directory(name: String, main_directory: PathBuf) -> Save {
+		std::fs::create_dir_all(&main_directory).unwrap();
 		let state_file_path = {
 			let mut chunks_directory = main_directory.clone();
 			chunks_directory.push("state");
 			chunks_directory
 		};
+		let block_type_names_file_path = {
+			let mut chunks_directory = main_directory.clone();
+			chunks_directory.push("block_type_names");
+			chunks_directory
+		};
 		let chunks_directory = {
 			let mut chunks_directory = main_directory.clone();
 			chunks_directory.push("chunks");
@@ -62,6 +74,7 @@ impl Save {
 			chunks_directory.push("atlas.png");
 			chunks_directory
 		};
+		let atlas_emissive_mask_file_path = textures_directory.join("atlas_emissive_mask.png");
 
 		let file_io_table = RwLock::new(HashMap::default());
 
@@ -69,9 +82,11 @@ impl Save {
 			name,
 			main_directory,
 			state_file_path,
+			block_type_names_file_path,
 			chunks_directory,
 			textures_directory,
 			atlas_texture_file_path,
+			atlas_emissive_mask_file_path,
 			file_io_table,
 		}
 	}
@@ -91,6 +106,40 @@ impl Save {
 		path
 	}
 
+	/// The coords of the chunks that have a file of the given kind in the save.
+	pub(crate) fn chunks_with_a_file(&self, which_file: WhichChunkFile) -> Vec<ChunkCoords> {
+		let which_file_char = match which_file {
+			WhichChunkFile::Blocks => 'b',
+			WhichChunkFile::Entities => 'e',
+		};
+		let Ok(directory) = std::fs::read_dir(&self.chunks_directory) else {
+			return vec![];
+		};
+		directory
+			.filter_map(|entry| {
+				let file_name = entry.ok()?.file_name().into_string().ok()?;
+				let mut parts = file_name.split(',');
+				let (Some(x), Some(y), Some(z), Some(c), None) = (
+					parts.next(),
+					parts.next(),
+					parts.next(),
+					parts.next(),
+					parts.next(),
+				) else {
+					return None;
+				};
+				if c.parse::<char>().ok()? != which_file_char {
+					return None;
+				}
+				Some(cgmath::point3(
+					x.parse().ok()?,
+					y.parse().ok()?,
+					z.parse().ok()?,
+				))
+			})
+			.collect()
+	}
+
 	pub(crate) fn skybox_face_texture_file_path(
 		&self,
 		face_direction: OrientedAxis,
@@ -140,13 +189,63 @@ impl SyncFileIo {
 			file.read_to_end(&mut data).unwrap();
 		}
 		if delete_file_after_read {
-			let _guard = self.token.write().unwrap();
-			std::fs::remove_file(&self.path).ok();
-			// Note: `remove_file` doc says that the file may not be immediately removed, which would
-			// be a problem if it could happen after the write guard is dropped. However, the doc says
-			// that this can happen because of "other open file descriptors", which should not exist
-			// due to our write guard, so we should be safe...
+			self.delete();
 		}
 		Some(data)
 	}
+
+	pub(crate) fn delete(&self) {
+		let _guard = self.token.write().unwrap();
+		std::fs::remove_file(&self.path).ok();
+		// Note: `remove_file` doc says that the file may not be immediately removed, which would
+		// be a problem if it could happen after the write guard is dropped. However, the doc says
+		// that this can happen because of "other open file descriptors", which should not exist
+		// due to our write guard, so we should be safe...
+	}
+
+	/// Gives exclusive access to the file until the returned guard is dropped, so that a sequence
+	/// of operations (like reading the file and then deleting it) cannot be interleaved with
+	/// the operations of other threads on the same file.
+	pub(crate) fn lock(&self) -> LockedFileIo<'_> {
+		LockedFileIo { path: &self.path, _guard: self.token.write().unwrap() }
+	}
+}
+
+/// See `SyncFileIo::lock`.
+pub(crate) struct LockedFileIo<'a> {
+	path: &'a PathBuf,
+	_guard: RwLockWriteGuard<'a, FileIoToken>,
+}
+
+impl LockedFileIo<'_> {
+	pub(crate) fn write(&self, data: &[u8]) {
+		let mut file = std::fs::File::create(self.path).unwrap();
+		file.write_all(data).unwrap();
+	}
+
+	pub(crate) fn read(&self) -> Option<Vec<u8>> {
+		std::fs::read(self.path).ok()
+	}
+
+	pub(crate) fn delete(&self) {
+		std::fs::remove_file(self.path).ok();
+	}
+
+	/// Renames the file to `<file>.corrupt` (or `<file>.corrupt_2`, etc. if that is taken),
+	/// to keep the data of a file that cannot be read out of the way without losing it.
+	/// Returns the new path.
+	pub(crate) fn move_aside_as_corrupt(&self) -> std::io::Result<PathBuf> {
+		let mut aside_path = self.path.as_os_str().to_owned();
+		aside_path.push(".corrupt");
+		let mut aside_path = PathBuf::from(aside_path);
+		let mut number = 2;
+		while aside_path.exists() {
+			let mut numbered_path = self.path.as_os_str().to_owned();
+			numbered_path.push(format!(".corrupt_{number}"));
+			aside_path = PathBuf::from(numbered_path);
+			number += 1;
+		}
+		std::fs::rename(self.path, &aside_path)?;
+		Ok(aside_path)
+	}
 }
@@ -0,0 +1,91 @@
+//! The selection tool (a world edit wand): when enabled, clicking on blocks sets the two corners
+//! of a cuboid selection that some commands (fill, copy, move, hollow) then operate on.
+//! The selection only lasts for the session, it is not saved.
+
+use crate::{
+	chunk_blocks::Block,
+	chunks::ChunkGrid,
+	coords::{BlockCoords, CubicCoordsSpan},
+};
+
+#[derive(Default)]
+pub(crate) struct Selection {
+	pub(crate) first_corner: Option<BlockCoords>,
+	pub(crate) second_corner: Option<BlockCoords>,
+}
+
+impl Selection {
+	/// The selected blocks, if both corners are set (they are both included in the span).
+	pub(crate) fn span(&self) -> Option<CubicCoordsSpan> {
+		let (a, b) = (self.first_corner?, self.second_corner?);
+		Some(CubicCoordsSpan::with_inf_sup_but_sup_is_included(
+			cgmath::point3(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+			cgmath::point3(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+		))
+	}
+
+	/// What is to be highlighted: the selection, or the corner that is set if only one is.
+	pub(crate) fn displayed_span(&self) -> Option<CubicCoordsSpan> {
+		self.span().or_else(|| {
+			let corner = self.first_corner.or(self.second_corner)?;
+			Some(CubicCoordsSpan::with_inf_sup_but_sup_is_included(
+				corner, corner,
+			))
+		})
+	}
+
+	pub(crate) fn translate(&mut self, delta: cgmath::Vector3<i32>) {
+		self.first_corner = self.first_corner.map(|corner| corner + delta);
+		self.second_corner = self.second_corner.map(|corner| corner + delta);
+	}
+
+	pub(crate) fn clear(&mut self) {
+		self.first_corner = None;
+		self.second_corner = None;
+	}
+}
+
+/// Copied blocks of a cuboid area, that can be pasted elsewhere.
+pub(crate) struct BlockClipboard {
+	dims: cgmath::Vector3<i32>,
+	/// In the iteration order of `CubicCoordsSpan::iter`, `None` for blocks that were in
+	/// chunks that were not loaded (these are left untouched when pasting).
+	blocks: Vec<Option<Block>>,
+}
+
+impl BlockClipboard {
+	pub(crate) fn copy(chunk_grid: &ChunkGrid, span: CubicCoordsSpan) -> BlockClipboard {
+		let blocks = span
+			.iter()
+			.map(|coords| chunk_grid.get_block(coords).map(|block| block.as_owned_block()))
+			.collect();
+		BlockClipboard { dims: span.sup_excluded - span.inf, blocks }
+	}
+
+	pub(crate) fn block_count(&self) -> usize {
+		self.blocks.len()
+	}
+
+	/// The blocks to place to paste the copied area with its inf corner at `inf`.
+	pub(crate) fn pasted_at(
+		&self,
+		inf: BlockCoords,
+	) -> impl Iterator<Item = (BlockCoords, Block)> + '_ {
+		let span = CubicCoordsSpan::with_inf_sup_but_sup_is_excluded(inf, inf + self.dims);
+		span
+			.iter()
+			.zip(self.blocks.iter())
+			.filter_map(|(coords, block)| Some((coords, block.clone()?)))
+	}
+}
+
+/// The coords of the blocks of the span that are not on its faces.
+pub(crate) fn hollow_interior(span: CubicCoordsSpan) -> impl Iterator<Item = BlockCoords> {
+	let inf = span.inf;
+	let sup = span.sup_included();
+	span.iter().filter(move |coords| {
+		(inf.x < coords.x && coords.x < sup.x)
+			&& (inf.y < coords.y && coords.y < sup.y)
+			&& (inf.z < coords.z && coords.z < sup.z)
+	})
+}
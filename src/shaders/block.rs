@@ -1,6 +1,9 @@
 use wgpu::vertex_attr_array;
 
-use crate::rendering_init::BindingThingy;
+use crate::{
+	map_export::MAP_EXPORT_FORMAT, rendering_init::BindingThingy,
+	shaders::deferred_lighting::GBUFFER_FORMATS,
+};
 
 /// Vertex type used in chunk block meshes.
 #[derive(Copy, Clone, Debug)]
@@ -12,18 +15,79 @@ pub(crate) struct BlockVertexPod {
 	pub(crate) coords_in_atlas: [f32; 2],
 	pub(crate) normal: [f32; 3],
 	pub(crate) ambiant_occlusion: f32,
+	/// Tells the shader how to animate the vertex, see the `MATERIAL_FLAG_*` constants.
+	pub(crate) material_flags: u32,
+	/// The color of the block light (see `block_light`) that reaches the vertex's face.
+	pub(crate) block_light: [f32; 3],
 }
 impl BlockVertexPod {
-	pub(crate) fn vertex_attributes() -> [wgpu::VertexAttribute; 4] {
+	pub(crate) fn vertex_attributes() -> [wgpu::VertexAttribute; 6] {
 		vertex_attr_array![
 			0 => Float32x3,
 			1 => Float32x2,
 			2 => Float32x3,
 			3 => Float32,
+			4 => Uint32,
+			5 => Float32x3,
 		]
 	}
 }
 
+/// The vertex sways gently (like leaves in the wind).
+/// Must match the constant of the same name in the shader.
+pub(crate) const MATERIAL_FLAG_FOLIAGE: u32 = 1 << 0;
+/// The vertex is on the surface of some water and goes up and down with the waves.
+/// Must match the constant of the same name in the shader.
+pub(crate) const MATERIAL_FLAG_WATER_SURFACE: u32 = 1 << 1;
+/// The vertex is part of a billboard that turns around its vertical axis to face the player.
+/// Its position is on that axis and its `normal` does not hold a normal but tells the shader where
+/// to move it: `normal[0]` is how far on the side it goes (to the right when seen from the front)
+/// and `normal[1]` is 1 for the front side and -1 for the back side.
+/// Must match the constant of the same name in the shaders (`block.wgsl` and `block_shadow.wgsl`).
+pub(crate) const MATERIAL_FLAG_BILLBOARD: u32 = 1 << 2;
+
+/// Parameters of the water reflection (see `WaterReflectionStuff`).
+#[derive(Copy, Clone, Debug)]
+/// Certified Plain Old Data (so it can be sent to the GPU as a uniform).
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct WaterReflectionSettingsPod {
+	/// Position of the camera, the Fresnel factor depends on the angle at which water is seen.
+	pub(crate) camera_position: [f32; 3],
+	/// Height of the water plane in which the reflection was rendered.
+	pub(crate) plane_z: f32,
+	/// How much of the reflection is blended in (times the Fresnel factor), 0.0 disables it.
+	pub(crate) strength: f32,
+	pub(crate) _padding: [f32; 3],
+}
+
+/// How much the blocks are normal mapped (see `Atlas::normal_atlas_image`).
+pub(crate) const NORMAL_MAPPING_STRENGTH: f32 = 1.0;
+
+/// Parameters of the normal mapping of the blocks (see `Atlas::normal_atlas_image`).
+#[derive(Copy, Clone, Debug)]
+/// Certified Plain Old Data (so it can be sent to the GPU as a uniform).
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct NormalMappingSettingsPod {
+	/// How much the normals are bent by the bumps of the textures, 0.0 disables normal mapping.
+	pub(crate) strength: f32,
+	pub(crate) _padding: [f32; 3],
+}
+
+impl NormalMappingSettingsPod {
+	pub(crate) fn new(enabled: bool) -> NormalMappingSettingsPod {
+		NormalMappingSettingsPod {
+			strength: if enabled {
+				NORMAL_MAPPING_STRENGTH
+			} else {
+				0.0
+			},
+			_padding: [0.0; 3],
+		}
+	}
+}
+
 pub(crate) struct BindingThingies<'a> {
 	pub(crate) camera_matrix_thingy: &'a BindingThingy<wgpu::Buffer>,
 	pub(crate) sun_light_direction_thingy: &'a BindingThingy<wgpu::Buffer>,
@@ -34,14 +98,75 @@ pub(crate) struct BindingThingies<'a> {
 	pub(crate) atlas_texture_sampler_thingy: &'a BindingThingy<wgpu::Sampler>,
 	pub(crate) fog_center_position_thingy: &'a BindingThingy<wgpu::Buffer>,
 	pub(crate) fog_inf_sup_radiuses_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) time_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) water_reflection_view_thingy: &'a BindingThingy<wgpu::TextureView>,
+	pub(crate) water_reflection_sampler_thingy: &'a BindingThingy<wgpu::Sampler>,
+	pub(crate) water_reflection_settings_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) normal_atlas_texture_view_thingy: &'a BindingThingy<wgpu::TextureView>,
+	pub(crate) normal_mapping_settings_thingy: &'a BindingThingy<wgpu::Buffer>,
+	/// Replace the camera matrix and the reflection texture in the bind group used to render
+	/// the reflection itself.
+	pub(crate) water_reflection_camera_matrix_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) water_reflection_placeholder_view_thingy: &'a BindingThingy<wgpu::TextureView>,
+	/// Replaces the camera matrix in the bind group used to export maps (see `map_export`).
+	pub(crate) map_export_camera_matrix_thingy: &'a BindingThingy<wgpu::Buffer>,
+}
+
+pub(crate) struct BlockRenderPipelines {
+	/// Renders to the output.
+	pub(crate) forward: wgpu::RenderPipeline,
+	/// Renders to the G-buffers of the deferred rendering path.
+	pub(crate) gbuffer: wgpu::RenderPipeline,
+	/// Same as `forward` but only draws the edges of the triangles.
+	/// Requires the `POLYGON_MODE_LINE` feature, which is not always available.
+	pub(crate) wireframe: Option<wgpu::RenderPipeline>,
+	/// Same as `forward` but tints each chunk with its heatmap tint (see `ChunkInstancePod`),
+	/// see `render_debug::heatmap_tints`.
+	pub(crate) heatmap: wgpu::RenderPipeline,
+	/// Same as `forward` but the blocks only show the block light that they get.
+	pub(crate) block_light: wgpu::RenderPipeline,
+	/// Renders the reflection of the blocks in the water plane, to the water reflection texture.
+	/// Must be used with the reflection bind group.
+	pub(crate) water_reflection: wgpu::RenderPipeline,
+	/// Same as `forward` but renders to a map export texture (see `map_export`).
+	/// Must be used with the map export bind group.
+	pub(crate) map_export: wgpu::RenderPipeline,
+}
+
+/// The tint of a chunk in the chunk heatmap debug rendering mode.
+pub(crate) type HeatmapTintPod = [f32; 3];
+
+/// What the block shaders need to know about a chunk mesh, see `ChunkInstances`.
+#[derive(Copy, Clone, Debug)]
+/// Certified Plain Old Data (so it can be sent to the GPU as a uniform).
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct ChunkInstancePod {
+	/// The positions of the vertices of the chunk mesh are relative to that (see `ChunkMesh::offset`).
+	pub(crate) offset: [f32; 3],
+	/// How much the chunk has faded in, see `ChunkMesh::fade_in_progress`.
+	pub(crate) fade_in: f32,
+	/// Only used by the chunk heatmap debug rendering mode.
+	pub(crate) heatmap_tint: HeatmapTintPod,
+	pub(crate) _padding: f32,
 }
 
-pub(crate) fn render_pipeline_and_bind_group(
+/// Returns the render pipelines that render the blocks, the bind group that they all use,
+/// the bind group to use when rendering the water reflection and the one to use when exporting
+/// a map. The second bind group of all the pipelines is the one of `ChunkInstances`.
+pub(crate) fn render_pipelines_and_bind_groups(
 	device: &wgpu::Device,
 	binding_thingies: BindingThingies,
+	chunk_instances_bind_group_layout: &wgpu::BindGroupLayout,
 	output_format: wgpu::TextureFormat,
 	z_buffer_format: wgpu::TextureFormat,
-) -> (wgpu::RenderPipeline, wgpu::BindGroup) {
+	sample_count: u32,
+) -> (
+	BlockRenderPipelines,
+	wgpu::BindGroup,
+	wgpu::BindGroup,
+	wgpu::BindGroup,
+) {
 	let vertex_buffer_layout = wgpu::VertexBufferLayout {
 		array_stride: std::mem::size_of::<BlockVertexPod>() as wgpu::BufferAddress,
 		step_mode: wgpu::VertexStepMode::Vertex,
@@ -53,14 +178,20 @@ pub(crate) fn render_pipeline_and_bind_group(
 		label: Some("Block Shader Bind Group Layout"),
 		entries: &[
 			binding_thingies.camera_matrix_thingy.layout_entry(0, S::VERTEX),
-			binding_thingies.sun_light_direction_thingy.layout_entry(1, S::VERTEX),
+			binding_thingies.sun_light_direction_thingy.layout_entry(1, S::VERTEX | S::FRAGMENT),
 			binding_thingies.sun_camera_matrices_thingy.layout_entry(2, S::FRAGMENT),
 			binding_thingies.shadow_map_view_thingy.layout_entry(3, S::FRAGMENT),
 			binding_thingies.shadow_map_sampler_thingy.layout_entry(4, S::FRAGMENT),
 			binding_thingies.atlas_texture_view_thingy.layout_entry(5, S::FRAGMENT),
 			binding_thingies.atlas_texture_sampler_thingy.layout_entry(6, S::FRAGMENT),
-			binding_thingies.fog_center_position_thingy.layout_entry(7, S::FRAGMENT),
+			binding_thingies.fog_center_position_thingy.layout_entry(7, S::VERTEX | S::FRAGMENT),
 			binding_thingies.fog_inf_sup_radiuses_thingy.layout_entry(8, S::FRAGMENT),
+			binding_thingies.time_thingy.layout_entry(9, S::VERTEX | S::FRAGMENT),
+			binding_thingies.water_reflection_view_thingy.layout_entry(10, S::FRAGMENT),
+			binding_thingies.water_reflection_sampler_thingy.layout_entry(11, S::FRAGMENT),
+			binding_thingies.water_reflection_settings_thingy.layout_entry(12, S::FRAGMENT),
+			binding_thingies.normal_atlas_texture_view_thingy.layout_entry(13, S::FRAGMENT),
+			binding_thingies.normal_mapping_settings_thingy.layout_entry(14, S::FRAGMENT),
 		],
 	});
 	let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -76,6 +207,56 @@ pub(crate) fn render_pipeline_and_bind_group(
 			binding_thingies.atlas_texture_sampler_thingy.bind_group_entry(6),
 			binding_thingies.fog_center_position_thingy.bind_group_entry(7),
 			binding_thingies.fog_inf_sup_radiuses_thingy.bind_group_entry(8),
+			binding_thingies.time_thingy.bind_group_entry(9),
+			binding_thingies.water_reflection_view_thingy.bind_group_entry(10),
+			binding_thingies.water_reflection_sampler_thingy.bind_group_entry(11),
+			binding_thingies.water_reflection_settings_thingy.bind_group_entry(12),
+			binding_thingies.normal_atlas_texture_view_thingy.bind_group_entry(13),
+			binding_thingies.normal_mapping_settings_thingy.bind_group_entry(14),
+		],
+	});
+	let reflection_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+		label: Some("Block Shader Water Reflection Bind Group"),
+		layout: &bind_group_layout,
+		entries: &[
+			binding_thingies.water_reflection_camera_matrix_thingy.bind_group_entry(0),
+			binding_thingies.sun_light_direction_thingy.bind_group_entry(1),
+			binding_thingies.sun_camera_matrices_thingy.bind_group_entry(2),
+			binding_thingies.shadow_map_view_thingy.bind_group_entry(3),
+			binding_thingies.shadow_map_sampler_thingy.bind_group_entry(4),
+			binding_thingies.atlas_texture_view_thingy.bind_group_entry(5),
+			binding_thingies.atlas_texture_sampler_thingy.bind_group_entry(6),
+			binding_thingies.fog_center_position_thingy.bind_group_entry(7),
+			binding_thingies.fog_inf_sup_radiuses_thingy.bind_group_entry(8),
+			binding_thingies.time_thingy.bind_group_entry(9),
+			binding_thingies.water_reflection_placeholder_view_thingy.bind_group_entry(10),
+			binding_thingies.water_reflection_sampler_thingy.bind_group_entry(11),
+			binding_thingies.water_reflection_settings_thingy.bind_group_entry(12),
+			binding_thingies.normal_atlas_texture_view_thingy.bind_group_entry(13),
+			binding_thingies.normal_mapping_settings_thingy.bind_group_entry(14),
+		],
+	});
+	// The water reflection was rendered for the camera of the player, not for the map camera,
+	// so the map export disables it (and does not sample the reflection texture).
+	let map_export_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+		label: Some("Block Shader Map Export Bind Group"),
+		layout: &bind_group_layout,
+		entries: &[
+			binding_thingies.map_export_camera_matrix_thingy.bind_group_entry(0),
+			binding_thingies.sun_light_direction_thingy.bind_group_entry(1),
+			binding_thingies.sun_camera_matrices_thingy.bind_group_entry(2),
+			binding_thingies.shadow_map_view_thingy.bind_group_entry(3),
+			binding_thingies.shadow_map_sampler_thingy.bind_group_entry(4),
+			binding_thingies.atlas_texture_view_thingy.bind_group_entry(5),
+			binding_thingies.atlas_texture_sampler_thingy.bind_group_entry(6),
+			binding_thingies.fog_center_position_thingy.bind_group_entry(7),
+			binding_thingies.fog_inf_sup_radiuses_thingy.bind_group_entry(8),
+			binding_thingies.time_thingy.bind_group_entry(9),
+			binding_thingies.water_reflection_placeholder_view_thingy.bind_group_entry(10),
+			binding_thingies.water_reflection_sampler_thingy.bind_group_entry(11),
+			binding_thingies.water_reflection_settings_thingy.bind_group_entry(12),
+			binding_thingies.normal_atlas_texture_view_thingy.bind_group_entry(13),
+			binding_thingies.normal_mapping_settings_thingy.bind_group_entry(14),
 		],
 	});
 
@@ -85,33 +266,119 @@ pub(crate) fn render_pipeline_and_bind_group(
 	});
 	let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
 		label: Some("Block Render Pipeline Layout"),
-		bind_group_layouts: &[&bind_group_layout],
+		bind_group_layouts: &[&bind_group_layout, chunk_instances_bind_group_layout],
 		push_constant_ranges: &[],
 	});
 
-	let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-		label: Some("Block Render Pipeline"),
+	let vertex_buffer_layouts = [vertex_buffer_layout];
+
+	let make_forward_render_pipeline = |label, polygon_mode, fragment_entry_point, format, count| {
+		device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+			label: Some(label),
+			layout: Some(&render_pipeline_layout),
+			vertex: wgpu::VertexState {
+				module: &shader,
+				entry_point: "vertex_shader_main",
+				compilation_options: wgpu::PipelineCompilationOptions::default(),
+				buffers: &vertex_buffer_layouts,
+			},
+			fragment: Some(wgpu::FragmentState {
+				module: &shader,
+				entry_point: fragment_entry_point,
+				compilation_options: wgpu::PipelineCompilationOptions::default(),
+				targets: &[Some(wgpu::ColorTargetState {
+					format,
+					// The blocks can get trasparent when far away to create a fog transparency
+					// effect that blends in the skybox. It sould only blend in the skybox though,
+					// not with blocks behind them, so here we do not do any alpha blending so that
+					// blocks do not blend with other blocks, and then the skybox will do the blending
+					// in reverse to draw itself behind the blocks.
+					blend: Some(wgpu::BlendState::REPLACE),
+					write_mask: wgpu::ColorWrites::ALL,
+				})],
+			}),
+			primitive: wgpu::PrimitiveState {
+				topology: wgpu::PrimitiveTopology::TriangleList,
+				strip_index_format: None,
+				front_face: wgpu::FrontFace::Ccw,
+				cull_mode: Some(wgpu::Face::Back),
+				polygon_mode,
+				unclipped_depth: false,
+				conservative: false,
+			},
+			depth_stencil: Some(wgpu::DepthStencilState {
+				format: z_buffer_format,
+				depth_write_enabled: true,
+				depth_compare: wgpu::CompareFunction::GreaterEqual,
+				stencil: wgpu::StencilState::default(),
+				bias: wgpu::DepthBiasState::default(),
+			}),
+			multisample: wgpu::MultisampleState { count, mask: !0, alpha_to_coverage_enabled: false },
+			multiview: None,
+		})
+	};
+
+	let render_pipeline = make_forward_render_pipeline(
+		"Block Render Pipeline",
+		wgpu::PolygonMode::Fill,
+		"fragment_shader_main",
+		output_format,
+		sample_count,
+	);
+	let wireframe_render_pipeline =
+		device.features().contains(wgpu::Features::POLYGON_MODE_LINE).then(|| {
+			make_forward_render_pipeline(
+				"Block Wireframe Render Pipeline",
+				wgpu::PolygonMode::Line,
+				"fragment_shader_main",
+				output_format,
+				sample_count,
+			)
+		});
+	let heatmap_render_pipeline = make_forward_render_pipeline(
+		"Block Heatmap Render Pipeline",
+		wgpu::PolygonMode::Fill,
+		"fragment_shader_heatmap_main",
+		output_format,
+		sample_count,
+	);
+	let block_light_render_pipeline = make_forward_render_pipeline(
+		"Block Light Render Pipeline",
+		wgpu::PolygonMode::Fill,
+		"fragment_shader_block_light_main",
+		output_format,
+		sample_count,
+	);
+	// The map export texture is not multisampled.
+	let map_export_render_pipeline = make_forward_render_pipeline(
+		"Block Map Export Render Pipeline",
+		wgpu::PolygonMode::Fill,
+		"fragment_shader_main",
+		MAP_EXPORT_FORMAT,
+		1,
+	);
+
+	let gbuffer_render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+		label: Some("Block G-Buffer Render Pipeline"),
 		layout: Some(&render_pipeline_layout),
 		vertex: wgpu::VertexState {
 			module: &shader,
 			entry_point: "vertex_shader_main",
 			compilation_options: wgpu::PipelineCompilationOptions::default(),
-			buffers: &[vertex_buffer_layout],
+			buffers: &vertex_buffer_layouts,
 		},
 		fragment: Some(wgpu::FragmentState {
 			module: &shader,
-			entry_point: "fragment_shader_main",
+			entry_point: "fragment_shader_gbuffer_main",
 			compilation_options: wgpu::PipelineCompilationOptions::default(),
-			targets: &[Some(wgpu::ColorTargetState {
-				format: output_format,
-				// The blocks can get trasparent when far away to create a fog transparency effect
-				// that blends in the skybox. It sould only blend in the skybox though, not with blocks
-				// behind them, so here we do not do any alpha blending so that blocks do not blend
-				// with other blocks, and then the skybox will do the blending in reverse to draw
-				// itself behind the blocks.
-				blend: Some(wgpu::BlendState::REPLACE),
-				write_mask: wgpu::ColorWrites::ALL,
-			})],
+			targets: &GBUFFER_FORMATS.map(|format| {
+				Some(wgpu::ColorTargetState {
+					format,
+					// Not all the G-buffer formats support blending (and there is nothing to blend).
+					blend: None,
+					write_mask: wgpu::ColorWrites::ALL,
+				})
+			}),
 		}),
 		primitive: wgpu::PrimitiveState {
 			topology: wgpu::PrimitiveTopology::TriangleList,
@@ -125,13 +392,75 @@ pub(crate) fn render_pipeline_and_bind_group(
 		depth_stencil: Some(wgpu::DepthStencilState {
 			format: z_buffer_format,
 			depth_write_enabled: true,
-			depth_compare: wgpu::CompareFunction::LessEqual,
+			depth_compare: wgpu::CompareFunction::GreaterEqual,
 			stencil: wgpu::StencilState::default(),
 			bias: wgpu::DepthBiasState::default(),
 		}),
+		// The deferred rendering path does not support multisampling.
 		multisample: wgpu::MultisampleState { count: 1, mask: !0, alpha_to_coverage_enabled: false },
 		multiview: None,
 	});
 
-	(render_pipeline, bind_group)
+	let water_reflection_render_pipeline =
+		device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+			label: Some("Block Water Reflection Render Pipeline"),
+			layout: Some(&render_pipeline_layout),
+			vertex: wgpu::VertexState {
+				module: &shader,
+				entry_point: "vertex_shader_main",
+				compilation_options: wgpu::PipelineCompilationOptions::default(),
+				buffers: &vertex_buffer_layouts,
+			},
+			fragment: Some(wgpu::FragmentState {
+				module: &shader,
+				entry_point: "fragment_shader_water_reflection_main",
+				compilation_options: wgpu::PipelineCompilationOptions::default(),
+				targets: &[Some(wgpu::ColorTargetState {
+					format: output_format,
+					// Same as the forward pipelines, the skybox is drawn behind afterwards.
+					blend: Some(wgpu::BlendState::REPLACE),
+					write_mask: wgpu::ColorWrites::ALL,
+				})],
+			}),
+			primitive: wgpu::PrimitiveState {
+				topology: wgpu::PrimitiveTopology::TriangleList,
+				strip_index_format: None,
+				// The mirror reverses the winding of the triangles.
+				front_face: wgpu::FrontFace::Cw,
+				cull_mode: Some(wgpu::Face::Back),
+				polygon_mode: wgpu::PolygonMode::Fill,
+				unclipped_depth: false,
+				conservative: false,
+			},
+			depth_stencil: Some(wgpu::DepthStencilState {
+				format: z_buffer_format,
+				depth_write_enabled: true,
+				depth_compare: wgpu::CompareFunction::GreaterEqual,
+				stencil: wgpu::StencilState::default(),
+				bias: wgpu::DepthBiasState::default(),
+			}),
+			// The reflection texture is not multisampled.
+			multisample: wgpu::MultisampleState {
+				count: 1,
+				mask: !0,
+				alpha_to_coverage_enabled: false,
+			},
+			multiview: None,
+		});
+
+	let render_pipelines = BlockRenderPipelines {
+		forward: render_pipeline,
+		gbuffer: gbuffer_render_pipeline,
+		wireframe: wireframe_render_pipeline,
+		heatmap: heatmap_render_pipeline,
+		block_light: block_light_render_pipeline,
+		water_reflection: water_reflection_render_pipeline,
+		map_export: map_export_render_pipeline,
+	};
+	(
+		render_pipelines,
+		bind_group,
+		reflection_bind_group,
+		map_export_bind_group,
+	)
 }
@@ -3,6 +3,8 @@ struct VertexInput {
 	@location(1) coords_in_atlas: vec2<f32>,
 	@location(2) normal: vec3<f32>,
 	@location(3) ambiant_occlusion: f32,
+	@location(4) material_flags: u32,
+	@location(5) block_light: vec3<f32>,
 };
 
 struct VertexOutput {
@@ -11,6 +13,22 @@ struct VertexOutput {
 	@location(1) shade: f32,
 	@location(2) ambiant_occlusion: f32,
 	@location(3) world_position: vec3<f32>,
+	@location(4) normal: vec3<f32>,
+	// Only used by the chunk heatmap debug rendering mode.
+	@location(5) heatmap_tint: vec3<f32>,
+	@location(7) block_light: vec3<f32>,
+	@location(8) @interpolate(flat) material_flags: u32,
+	// Same as `screen_position` but not transformed for the fragment shader.
+	@location(9) clip_position: vec4<f32>,
+	@location(10) fade_in: f32,
+};
+
+// Must match `ChunkInstancePod` in `block.rs`.
+struct ChunkInstance {
+	offset: vec3<f32>,
+	// From 0 (the chunk just appeared) to 1 (it is fully there).
+	fade_in: f32,
+	heatmap_tint: vec3<f32>,
 };
 
 @group(0) @binding(0) var<uniform> uniform_camera: mat4x4<f32>;
@@ -22,22 +40,197 @@ struct VertexOutput {
 @group(0) @binding(6) var uniform_atlas_sampler: sampler;
 @group(0) @binding(7) var<uniform> uniform_fog_center_position: vec3<f32>;
 @group(0) @binding(8) var<uniform> uniform_fog_inf_sup_radiuses: vec2<f32>;
+@group(0) @binding(9) var<uniform> uniform_time: f32;
+@group(0) @binding(10) var uniform_water_reflection_texture: texture_2d<f32>;
+@group(0) @binding(11) var uniform_water_reflection_sampler: sampler;
+@group(0) @binding(12) var<uniform> uniform_water_reflection: WaterReflectionSettings;
+// Has the same layout as the atlas, see `Atlas::normal_atlas_image`.
+@group(0) @binding(13) var uniform_normal_atlas_texture: texture_2d<f32>;
+@group(0) @binding(14) var<uniform> uniform_normal_mapping: NormalMappingSettings;
+// Indexed by the instance index, each chunk mesh draw call has one instance (see `ChunkInstances`).
+@group(1) @binding(0) var<storage, read> chunk_instances: array<ChunkInstance>;
+
+// Must match `WaterReflectionSettingsPod` in `block.rs`.
+struct WaterReflectionSettings {
+	camera_position: vec3<f32>,
+	plane_z: f32,
+	strength: f32,
+};
+
+// Must match `NormalMappingSettingsPod` in `block.rs`.
+struct NormalMappingSettings {
+	strength: f32,
+};
+
+// Must match the constants of the same name in `block.rs`.
+const MATERIAL_FLAG_FOLIAGE: u32 = 1u;
+const MATERIAL_FLAG_WATER_SURFACE: u32 = 2u;
+const MATERIAL_FLAG_BILLBOARD: u32 = 4u;
+
+struct PlacedVertex {
+	position: vec3<f32>,
+	normal: vec3<f32>,
+};
+
+// Billboard vertices are given on the vertical axis of the billboard, their normal tells where
+// they go on the sides (see `MATERIAL_FLAG_BILLBOARD`). The billboard turns to face the player
+// (that is at the fog center), and not the camera, so that it is the same in all the passes.
+// Must match `billboard_vertex_position` in `block_shadow.wgsl`.
+fn billboard_vertex(position: vec3<f32>, normal: vec3<f32>) -> PlacedVertex {
+	var towards_player = uniform_fog_center_position.xy - position.xy;
+	if length(towards_player) < 0.0001 {
+		towards_player = vec2<f32>(1.0, 0.0);
+	}
+	towards_player = normalize(towards_player);
+	let rightward = vec2<f32>(-towards_player.y, towards_player.x);
+	var placed: PlacedVertex;
+	placed.position = vec3<f32>(position.xy + rightward * normal.x, position.z);
+	placed.normal = vec3<f32>(towards_player * normal.y, 0.0);
+	return placed;
+}
+
+// Animates the vertices according to their material flags.
+// The displacement only depends on the position (and time) so that vertices shared by neighboring
+// faces stay together and do not open cracks between faces.
+fn displaced_position(position: vec3<f32>, material_flags: u32) -> vec3<f32> {
+	var displaced = position;
+	if (material_flags & MATERIAL_FLAG_FOLIAGE) != 0u {
+		// Gentle swaying, with a phase that depends on the position so that it does not look
+		// like the whole tree is moving in one piece.
+		let phase = dot(position, vec3<f32>(0.7, 1.3, 0.5));
+		displaced.x += sin(uniform_time * 1.7 + phase) * 0.04;
+		displaced.y += sin(uniform_time * 1.3 + phase * 1.3 + 1.0) * 0.04;
+	}
+	if (material_flags & MATERIAL_FLAG_WATER_SURFACE) != 0u {
+		// A few waves going in different directions, the surface is lowered a bit so that the
+		// waves do not go above the blocks around.
+		let wave_a = sin(uniform_time * 1.5 + position.x * 0.9 + position.y * 0.3);
+		let wave_b = sin(uniform_time * 1.1 - position.y * 0.7 + position.x * 0.2);
+		let wave_c = sin(uniform_time * 2.3 + (position.x + position.y) * 1.7) * 0.3;
+		displaced.z += (wave_a + wave_b + wave_c) * 0.03 - 0.12;
+	}
+	return displaced;
+}
+
+// The light that a face gets from the sky (when it is not lit by the sun directly), that depends
+// on the direction the face is facing. Faces that face up get more of it than faces that face down,
+// and side faces that face towards the sun get more of it than the ones that face away, so that
+// the sides of the blocks do not all look the same in the shadows and change as the sun goes around.
+// It never goes above 1.0, so that shadowed faces are never brighter than lit ones.
+fn sky_light(normal: vec3<f32>) -> f32 {
+	let towards_sun = -uniform_sun_light_direction.xy;
+	let towards_sun_horizontal = towards_sun / max(length(towards_sun), 0.0001);
+	return 0.65 + 0.2 * normal.z + 0.15 * dot(normal.xy, towards_sun_horizontal);
+}
 
 @vertex
-fn vertex_shader_main(vertex_input: VertexInput) -> VertexOutput {
+fn vertex_shader_main(
+	vertex_input: VertexInput, @builtin(instance_index) instance_index: u32
+) -> VertexOutput {
+	let chunk = chunk_instances[instance_index];
 	var vertex_output: VertexOutput;
-	vertex_output.screen_position = uniform_camera * vec4<f32>(vertex_input.position, 1.0);
-	var shade = dot(vertex_input.normal, -uniform_sun_light_direction);
+	let world_position = vertex_input.position + chunk.offset;
+	var placed = PlacedVertex(world_position, vertex_input.normal);
+	if (vertex_input.material_flags & MATERIAL_FLAG_BILLBOARD) != 0u {
+		placed = billboard_vertex(world_position, vertex_input.normal);
+	}
+	var position = displaced_position(placed.position, vertex_input.material_flags);
+	// Chunks that are fading in also rise into place (decelerating as they arrive).
+	let fade_in = chunk.fade_in;
+	position.z -= (1.0 - fade_in) * (1.0 - fade_in) * 1.5;
+	let normal = placed.normal;
+	vertex_output.screen_position = uniform_camera * vec4<f32>(position, 1.0);
+	var shade = dot(normal, -uniform_sun_light_direction);
 	shade = clamp(shade, 0.0, 1.0);
 	vertex_output.coords_in_atlas = vertex_input.coords_in_atlas;
 	vertex_output.shade = shade;
 	vertex_output.ambiant_occlusion = vertex_input.ambiant_occlusion;
-	vertex_output.world_position = vertex_input.position;
+	vertex_output.world_position = position;
+	vertex_output.normal = normal;
+	vertex_output.heatmap_tint = chunk.heatmap_tint;
+	vertex_output.block_light = vertex_input.block_light;
+	vertex_output.material_flags = vertex_input.material_flags;
+	vertex_output.clip_position = vertex_output.screen_position;
+	vertex_output.fade_in = fade_in;
 	return vertex_output;
 }
 
-@fragment
-fn fragment_shader_main(the: VertexOutput) -> @location(0) vec4<f32> {
+// The reflection of the world on a water surface (see `WaterReflectionStuff`), with how much of it
+// is to be blended in (as the alpha).
+fn water_reflection(the: VertexOutput) -> vec4<f32> {
+	// The reflection in a planar mirror was rendered at the position on the screen of the mirror.
+	let position_on_screen = the.clip_position.xy / the.clip_position.w;
+	var coords = position_on_screen * vec2<f32>(0.5, -0.5) + vec2<f32>(0.5, 0.5);
+	// The waves distort the reflection a bit.
+	let phase = uniform_time * 1.3 + dot(the.world_position.xy, vec2<f32>(0.8, 0.6));
+	coords += vec2<f32>(sin(phase), cos(phase * 1.1)) * 0.006;
+	// Sampled without mipmaps as it is not always in uniform control flow.
+	let reflected = textureSampleLevel(
+		uniform_water_reflection_texture, uniform_water_reflection_sampler, coords, 0.0);
+
+	// Fresnel effect (with Schlick's approximation), water seen from a grazing angle reflects
+	// almost everything, water seen from above reflects almost nothing.
+	let towards_camera = normalize(uniform_water_reflection.camera_position - the.world_position);
+	let cos_angle = clamp(towards_camera.z, 0.0, 1.0);
+	let fresnel = 0.02 + 0.98 * pow(1.0 - cos_angle, 5.0);
+	return vec4<f32>(reflected.rgb, fresnel * uniform_water_reflection.strength);
+}
+
+// What the normal atlas tells about a fragment (see `Atlas::normal_atlas_image`).
+struct Bump {
+	// Bent by the bumps of the texture.
+	normal: vec3<f32>,
+	// From 0 to 1, how much the fragment glows.
+	emission: f32,
+};
+
+// The normal map is in the frame of the texture, the directions in the world along which
+// the texture coordinates go are found from how they change between neighboring fragments,
+// so that the vertices do not need to carry tangents.
+// Must be called in uniform control flow (because of the derivatives).
+fn bump_of_fragment(the: VertexOutput) -> Bump {
+	let normal = normalize(the.normal);
+	let sampled = textureSample(uniform_normal_atlas_texture, uniform_atlas_sampler, the.coords_in_atlas);
+	let bent_normal_in_texture_xy = sampled.xy * 2.0 - vec2<f32>(1.0, 1.0);
+	let bent_normal_in_texture = vec3<f32>(
+		bent_normal_in_texture_xy,
+		sqrt(max(1.0 - dot(bent_normal_in_texture_xy, bent_normal_in_texture_xy), 0.0)),
+	);
+
+	let position_dx = dpdx(the.world_position);
+	let position_dy = dpdy(the.world_position);
+	let coords_dx = dpdx(the.coords_in_atlas);
+	let coords_dy = dpdy(the.coords_in_atlas);
+	let position_dy_perp = cross(position_dy, normal);
+	let position_dx_perp = cross(normal, position_dx);
+	let tangent = position_dy_perp * coords_dx.x + position_dx_perp * coords_dy.x;
+	let bitangent = position_dy_perp * coords_dx.y + position_dx_perp * coords_dy.y;
+	let scale = inverseSqrt(max(max(dot(tangent, tangent), dot(bitangent, bitangent)), 1e-20));
+
+	let strength = uniform_normal_mapping.strength;
+	let bent_normal =
+		(tangent * bent_normal_in_texture.x + bitangent * bent_normal_in_texture.y) * scale * strength
+		+ normal * bent_normal_in_texture.z;
+	var bump: Bump;
+	bump.normal = normalize(bent_normal);
+	bump.emission = sampled.b;
+	return bump;
+}
+
+// What the shading of a fragment gives, the lit color and what is needed to light it more
+// (the deferred rendering path adds more lights later).
+struct ShadedFragment {
+	color: vec4<f32>,
+	// Color of the matter, with the ambiant occlusion, but without any lighting.
+	albedo: vec3<f32>,
+	// Bent by the normal mapping.
+	normal: vec3<f32>,
+};
+
+fn shade_fragment(the: VertexOutput) -> ShadedFragment {
+	let bump = bump_of_fragment(the);
+	let normal = bump.normal;
+
 	var not_in_shadow = 1.0;
 
 	// Each cascade is a shadow map, from smallest (so more precise) to largest.
@@ -82,19 +275,29 @@ fn fragment_shader_main(the: VertexOutput) -> @location(0) vec4<f32> {
 	fog_transparency = clamp(fog_transparency, 0.0, 1.0);
 	var fog_opacity = 1.0 - fog_transparency;
 	out_color.a *= fog_opacity;
+	// Chunks that are fading in blend in the skybox the same way.
+	out_color.a *= the.fade_in;
 
 	// Full transparency.
 	if out_color.a == 0.0 {
 		discard;
 	}
 
+	// The bumps catch the sun light depending on how they face it, but a face that does not face
+	// the sun does not get any of it even where its bumps would.
+	let bumped_shade = clamp(dot(normal, -uniform_sun_light_direction), 0.0, 1.0);
+	let face_shade = select(0.0, bumped_shade, the.shade > 0.0);
+
 	// Apply the darkenning due to the shadows and ambiant occlusion.
-	var shade = the.shade * not_in_shadow;
+	var shade = face_shade * not_in_shadow;
 	var out_color_rgb = out_color.rgb;
-	let shade_ratio = 0.7; // How dark can in get in the shadows.
-	out_color_rgb *= shade * shade_ratio + (1.0 - shade_ratio);
 	let ambiant_occlusion_ratio = 0.7; // How dark can it get in the corners.
 	out_color_rgb *= the.ambiant_occlusion * ambiant_occlusion_ratio + (1.0 - ambiant_occlusion_ratio);
+	let albedo = out_color_rgb;
+	let shade_ratio = 0.7; // How dark can in get in the shadows.
+	let sun_and_sky_light = shade * shade_ratio + (1.0 - shade_ratio) * sky_light(normal);
+	// The block light (from light emitting blocks) adds its color to the surfaces it reaches.
+	out_color_rgb *= sun_and_sky_light + the.block_light;
 
 	// Apply a touch of the sun light color over exposed surfaces.
 	let sun_light_color = vec3<f32>(0.5, 0.35, 0.0) * 0.8;
@@ -102,5 +305,81 @@ fn fragment_shader_main(the: VertexOutput) -> @location(0) vec4<f32> {
 		out_color_rgb * (vec3<f32>(1.0, 1.0, 1.0) + sun_light_color), out_color_rgb,
 		1.0 - shade);
 
-	return vec4<f32>(out_color_rgb, out_color.a);
+	// The glowing parts of the texture are seen whatever light reaches them,
+	// at the full brightness of the texture.
+	out_color_rgb = mix(out_color_rgb, out_color.rgb, bump.emission);
+
+	let is_water_surface =
+		(the.material_flags & MATERIAL_FLAG_WATER_SURFACE) != 0u && the.normal.z > 0.5;
+	if is_water_surface && uniform_water_reflection.strength > 0.0 {
+		let reflection = water_reflection(the);
+		out_color_rgb = mix(out_color_rgb, reflection.rgb, reflection.a);
+	}
+
+	var shaded: ShadedFragment;
+	shaded.color = vec4<f32>(out_color_rgb, out_color.a);
+	// The lights do not make the glowing parts brighter.
+	shaded.albedo = albedo * (1.0 - bump.emission);
+	shaded.normal = normal;
+	return shaded;
+}
+
+@fragment
+fn fragment_shader_main(the: VertexOutput) -> @location(0) vec4<f32> {
+	return shade_fragment(the).color;
+}
+
+@fragment
+fn fragment_shader_heatmap_main(the: VertexOutput) -> @location(0) vec4<f32> {
+	let color = shade_fragment(the).color;
+	// Keep a bit of the shading so that the shapes remain readable.
+	let brightness = dot(color.rgb, vec3<f32>(0.3, 0.6, 0.1));
+	return vec4<f32>(the.heatmap_tint * (brightness * 0.6 + 0.4), color.a);
+}
+
+// Used by the light level debug rendering mode, the blocks only show the block light they get.
+@fragment
+fn fragment_shader_block_light_main(the: VertexOutput) -> @location(0) vec4<f32> {
+	let color = shade_fragment(the).color;
+	// Keep a bit of the shading so that the shapes remain readable.
+	let brightness = dot(color.rgb, vec3<f32>(0.3, 0.6, 0.1));
+	return vec4<f32>(the.block_light * 0.85 + vec3<f32>(brightness * 0.15), color.a);
+}
+
+// Renders the blocks to the water reflection (with the reflected camera), only what is above
+// the water plane is reflected, and the water surfaces do not reflect themselves.
+@fragment
+fn fragment_shader_water_reflection_main(the: VertexOutput) -> @location(0) vec4<f32> {
+	let color = shade_fragment(the).color;
+	let is_below_water_plane = the.world_position.z < uniform_water_reflection.plane_z;
+	if is_below_water_plane || (the.material_flags & MATERIAL_FLAG_WATER_SURFACE) != 0u {
+		discard;
+	}
+	return color;
+}
+
+// The deferred rendering path renders the blocks in a G-buffer, the formats of the targets
+// are given by `GBUFFER_FORMATS` in `deferred_lighting.rs`.
+struct GBufferOutput {
+	// Lit by the sun only, with the fog transparency.
+	@location(0) color: vec4<f32>,
+	@location(1) albedo: vec4<f32>,
+	@location(2) normal: vec4<f32>,
+	@location(3) world_position: vec4<f32>,
+};
+
+@fragment
+fn fragment_shader_gbuffer_main(the: VertexOutput) -> GBufferOutput {
+	// The light emitting blocks are point lights in the deferred rendering path,
+	// they must not light the blocks twice.
+	var the_without_block_light = the;
+	the_without_block_light.block_light = vec3<f32>(0.0, 0.0, 0.0);
+	let shaded = shade_fragment(the_without_block_light);
+	var output: GBufferOutput;
+	output.color = shaded.color;
+	output.albedo = vec4<f32>(shaded.albedo, 1.0);
+	// The point lights light the bumps too.
+	output.normal = vec4<f32>(shaded.normal, 0.0);
+	output.world_position = vec4<f32>(the.world_position, 1.0);
+	return output;
 }
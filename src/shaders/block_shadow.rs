@@ -9,9 +9,11 @@ pub(crate) struct BindingThingies<'a> {
 	pub(crate) fog_inf_sup_radiuses_thingy: &'a BindingThingy<wgpu::Buffer>,
 }
 
+/// The second bind group of the pipeline is the one of `ChunkInstances`.
 pub(crate) fn render_pipeline_and_bind_group(
 	device: &wgpu::Device,
 	binding_thingies: BindingThingies,
+	chunk_instances_bind_group_layout: &wgpu::BindGroupLayout,
 	z_buffer_format: wgpu::TextureFormat,
 ) -> (wgpu::RenderPipeline, wgpu::BindGroup) {
 	let vertex_buffer_layout = wgpu::VertexBufferLayout {
@@ -27,7 +29,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 			binding_thingies.sun_camera_single_matrix_thingy.layout_entry(0, S::VERTEX),
 			binding_thingies.atlas_texture_view_thingy.layout_entry(1, S::FRAGMENT),
 			binding_thingies.atlas_texture_sampler_thingy.layout_entry(2, S::FRAGMENT),
-			binding_thingies.fog_center_position_thingy.layout_entry(3, S::FRAGMENT),
+			binding_thingies.fog_center_position_thingy.layout_entry(3, S::VERTEX | S::FRAGMENT),
 			binding_thingies.fog_inf_sup_radiuses_thingy.layout_entry(4, S::FRAGMENT),
 		],
 	});
@@ -49,7 +51,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 	});
 	let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
 		label: Some("Block Shadow Render Pipeline Layout"),
-		bind_group_layouts: &[&bind_group_layout],
+		bind_group_layouts: &[&bind_group_layout, chunk_instances_bind_group_layout],
 		push_constant_ranges: &[],
 	});
 
@@ -80,7 +82,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 		depth_stencil: Some(wgpu::DepthStencilState {
 			format: z_buffer_format,
 			depth_write_enabled: true,
-			depth_compare: wgpu::CompareFunction::Less,
+			depth_compare: wgpu::CompareFunction::Greater,
 			stencil: wgpu::StencilState::default(),
 			bias: wgpu::DepthBiasState::default(),
 		}),
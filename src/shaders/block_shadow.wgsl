@@ -3,6 +3,7 @@ struct VertexInput {
 	@location(1) coords_in_atlas: vec2<f32>,
 	@location(2) normal: vec3<f32>,
 	@location(3) ambiant_occlusion: f32,
+	@location(4) material_flags: u32,
 };
 
 struct VertexOutput {
@@ -17,12 +18,43 @@ struct VertexOutput {
 @group(0) @binding(3) var<uniform> uniform_fog_center_position: vec3<f32>;
 @group(0) @binding(4) var<uniform> uniform_fog_inf_sup_radiuses: vec2<f32>;
 
+// Must match `ChunkInstancePod` in `block.rs`, only the offset is used here.
+struct ChunkInstance {
+	offset: vec3<f32>,
+	fade_in: f32,
+	heatmap_tint: vec3<f32>,
+};
+
+// Indexed by the instance index, each chunk mesh draw call has one instance (see `ChunkInstances`).
+@group(1) @binding(0) var<storage, read> chunk_instances: array<ChunkInstance>;
+
+// Must match the constant of the same name in `block.rs`.
+const MATERIAL_FLAG_BILLBOARD: u32 = 4u;
+
+// Same as `billboard_vertex` in `block.wgsl` (without the normal, unused here),
+// so that billboards cast the shadow of what is seen.
+fn billboard_vertex_position(position: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
+	var towards_player = uniform_fog_center_position.xy - position.xy;
+	if length(towards_player) < 0.0001 {
+		towards_player = vec2<f32>(1.0, 0.0);
+	}
+	towards_player = normalize(towards_player);
+	let rightward = vec2<f32>(-towards_player.y, towards_player.x);
+	return vec3<f32>(position.xy + rightward * normal.x, position.z);
+}
+
 @vertex
-fn vertex_shader_main(vertex_input: VertexInput) -> VertexOutput {
+fn vertex_shader_main(
+	vertex_input: VertexInput, @builtin(instance_index) instance_index: u32
+) -> VertexOutput {
 	var vertex_output: VertexOutput;
-	vertex_output.screen_position = uniform_sun_camera * vec4<f32>(vertex_input.position, 1.0);
+	var position = vertex_input.position + chunk_instances[instance_index].offset;
+	if (vertex_input.material_flags & MATERIAL_FLAG_BILLBOARD) != 0u {
+		position = billboard_vertex_position(position, vertex_input.normal);
+	}
+	vertex_output.screen_position = uniform_sun_camera * vec4<f32>(position, 1.0);
 	vertex_output.coords_in_atlas = vertex_input.coords_in_atlas;
-	vertex_output.world_position = vertex_input.position;
+	vertex_output.world_position = position;
 	return vertex_output;
 }
 
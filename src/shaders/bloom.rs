@@ -0,0 +1,156 @@
+/// Parameters of the bloom (see `Bloom`).
+#[derive(Copy, Clone, Debug)]
+/// Certified Plain Old Data (so it can be sent to the GPU as a uniform).
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct BloomSettingsPod {
+	/// From 0.0 to 1.0, the brightness above which the scene glows.
+	pub(crate) threshold: f32,
+	/// How much of the glow is added to the scene.
+	pub(crate) intensity: f32,
+	pub(crate) _padding: [f32; 2],
+}
+
+/// The format of the textures in which the glow is extracted and blurred.
+pub(crate) const BLOOM_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+pub(crate) struct BloomRenderPipelines {
+	/// Extracts what is bright enough to glow from the scene.
+	pub(crate) bright_pass: wgpu::RenderPipeline,
+	pub(crate) blur_horizontal: wgpu::RenderPipeline,
+	pub(crate) blur_vertical: wgpu::RenderPipeline,
+	/// Adds the glow to the scene.
+	pub(crate) composite: wgpu::RenderPipeline,
+}
+
+/// The bind groups depend on the textures that are sampled (that are recreated when the window is
+/// resized or when the render scale changes), so here we only give their layout, see `Bloom`.
+/// All the pipelines use the same layout, with the texture they sample.
+pub(crate) fn render_pipelines_and_bind_group_layout(
+	device: &wgpu::Device,
+	output_format: wgpu::TextureFormat,
+) -> (BloomRenderPipelines, wgpu::BindGroupLayout) {
+	use wgpu::ShaderStages as S;
+	let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+		label: Some("Bloom Shader Bind Group Layout"),
+		entries: &[
+			wgpu::BindGroupLayoutEntry {
+				binding: 0,
+				visibility: S::FRAGMENT,
+				ty: wgpu::BindingType::Texture {
+					sample_type: wgpu::TextureSampleType::Float { filterable: true },
+					view_dimension: wgpu::TextureViewDimension::D2,
+					multisampled: false,
+				},
+				count: None,
+			},
+			wgpu::BindGroupLayoutEntry {
+				binding: 1,
+				visibility: S::FRAGMENT,
+				ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+				count: None,
+			},
+			wgpu::BindGroupLayoutEntry {
+				binding: 2,
+				visibility: S::FRAGMENT,
+				ty: wgpu::BindingType::Buffer {
+					ty: wgpu::BufferBindingType::Uniform,
+					has_dynamic_offset: false,
+					min_binding_size: None,
+				},
+				count: None,
+			},
+		],
+	});
+
+	let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+		label: Some("Bloom Shader"),
+		source: wgpu::ShaderSource::Wgsl(include_str!("bloom.wgsl").into()),
+	});
+	let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+		label: Some("Bloom Render Pipeline Layout"),
+		bind_group_layouts: &[&bind_group_layout],
+		push_constant_ranges: &[],
+	});
+
+	let make_render_pipeline = |label, fragment_entry_point, format, blend| {
+		device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+			label: Some(label),
+			layout: Some(&render_pipeline_layout),
+			vertex: wgpu::VertexState {
+				module: &shader,
+				entry_point: "vertex_shader_main",
+				compilation_options: wgpu::PipelineCompilationOptions::default(),
+				buffers: &[],
+			},
+			fragment: Some(wgpu::FragmentState {
+				module: &shader,
+				entry_point: fragment_entry_point,
+				compilation_options: wgpu::PipelineCompilationOptions::default(),
+				targets: &[Some(wgpu::ColorTargetState {
+					format,
+					blend: Some(blend),
+					write_mask: wgpu::ColorWrites::ALL,
+				})],
+			}),
+			primitive: wgpu::PrimitiveState {
+				topology: wgpu::PrimitiveTopology::TriangleList,
+				strip_index_format: None,
+				front_face: wgpu::FrontFace::Ccw,
+				cull_mode: None,
+				polygon_mode: wgpu::PolygonMode::Fill,
+				unclipped_depth: false,
+				conservative: false,
+			},
+			depth_stencil: None,
+			multisample: wgpu::MultisampleState {
+				count: 1,
+				mask: !0,
+				alpha_to_coverage_enabled: false,
+			},
+			multiview: None,
+		})
+	};
+
+	// The glow is added to the colors of the scene, its alpha is left as it is.
+	let additive_blend = wgpu::BlendState {
+		color: wgpu::BlendComponent {
+			src_factor: wgpu::BlendFactor::One,
+			dst_factor: wgpu::BlendFactor::One,
+			operation: wgpu::BlendOperation::Add,
+		},
+		alpha: wgpu::BlendComponent {
+			src_factor: wgpu::BlendFactor::Zero,
+			dst_factor: wgpu::BlendFactor::One,
+			operation: wgpu::BlendOperation::Add,
+		},
+	};
+	let render_pipelines = BloomRenderPipelines {
+		bright_pass: make_render_pipeline(
+			"Bloom Bright Pass Render Pipeline",
+			"fragment_shader_bright_pass_main",
+			BLOOM_FORMAT,
+			wgpu::BlendState::REPLACE,
+		),
+		blur_horizontal: make_render_pipeline(
+			"Bloom Horizontal Blur Render Pipeline",
+			"fragment_shader_blur_horizontal_main",
+			BLOOM_FORMAT,
+			wgpu::BlendState::REPLACE,
+		),
+		blur_vertical: make_render_pipeline(
+			"Bloom Vertical Blur Render Pipeline",
+			"fragment_shader_blur_vertical_main",
+			BLOOM_FORMAT,
+			wgpu::BlendState::REPLACE,
+		),
+		composite: make_render_pipeline(
+			"Bloom Composite Render Pipeline",
+			"fragment_shader_composite_main",
+			output_format,
+			additive_blend,
+		),
+	};
+
+	(render_pipelines, bind_group_layout)
+}
@@ -0,0 +1,70 @@
+struct VertexOutput {
+	@builtin(position) screen_position: vec4<f32>,
+	@location(0) coords: vec2<f32>,
+};
+
+// Must match `BloomSettingsPod` in `bloom.rs`.
+struct BloomSettings {
+	threshold: f32,
+	intensity: f32,
+	_padding: vec2<f32>,
+};
+
+@group(0) @binding(0) var source_texture: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+@group(0) @binding(2) var<uniform> uniform_settings: BloomSettings;
+
+@vertex
+fn vertex_shader_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+	// One triangle that covers the whole screen.
+	let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+	var vertex_output: VertexOutput;
+	vertex_output.screen_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+	vertex_output.coords = vec2<f32>(uv.x, 1.0 - uv.y);
+	return vertex_output;
+}
+
+// Keeps only what is brighter than the threshold (the glowing parts of the blocks, the sun),
+// with a smooth transition so that the bloom does not pop in when something gets brighter.
+@fragment
+fn fragment_shader_bright_pass_main(the: VertexOutput) -> @location(0) vec4<f32> {
+	// The source is the scene at twice the resolution, the bilinear filtering averages 4 pixels.
+	let color = textureSample(source_texture, source_sampler, the.coords).rgb;
+	let brightness = max(color.r, max(color.g, color.b));
+	let threshold = uniform_settings.threshold;
+	let knee = max(1.0 - threshold, 0.0001);
+	let contribution = clamp((brightness - threshold) / knee, 0.0, 1.0);
+	return vec4<f32>(color * contribution * contribution, 1.0);
+}
+
+// A 9 tap gaussian blur along the given direction, that only needs 5 samples thanks to the bilinear
+// filtering sampling between two pixels with the right weights.
+fn blur(coords: vec2<f32>, direction: vec2<f32>) -> vec4<f32> {
+	let texel = direction / vec2<f32>(textureDimensions(source_texture));
+	var offsets = array<f32, 3>(0.0, 1.3846153846, 3.2307692308);
+	var weights = array<f32, 3>(0.2270270270, 0.3162162162, 0.0702702703);
+	var sum = textureSample(source_texture, source_sampler, coords).rgb * weights[0];
+	for (var i = 1; i < 3; i++) {
+		let offset = texel * offsets[i];
+		sum += textureSample(source_texture, source_sampler, coords + offset).rgb * weights[i];
+		sum += textureSample(source_texture, source_sampler, coords - offset).rgb * weights[i];
+	}
+	return vec4<f32>(sum, 1.0);
+}
+
+@fragment
+fn fragment_shader_blur_horizontal_main(the: VertexOutput) -> @location(0) vec4<f32> {
+	return blur(the.coords, vec2<f32>(1.0, 0.0));
+}
+
+@fragment
+fn fragment_shader_blur_vertical_main(the: VertexOutput) -> @location(0) vec4<f32> {
+	return blur(the.coords, vec2<f32>(0.0, 1.0));
+}
+
+// Added to the scene (see the blending of the composite render pipeline).
+@fragment
+fn fragment_shader_composite_main(the: VertexOutput) -> @location(0) vec4<f32> {
+	let bloom = textureSample(source_texture, source_sampler, the.coords).rgb;
+	return vec4<f32>(bloom * uniform_settings.intensity, 0.0);
+}
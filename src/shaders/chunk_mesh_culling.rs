@@ -0,0 +1,53 @@
+/// Metadata of a chunk mesh for the culling compute shader.
+/// Certified Plain Old Data (so it can be sent to the GPU as a storage buffer element).
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct ChunkMeshMetadataPod {
+	pub(crate) bounds_inf: [f32; 3],
+	pub(crate) vertex_count: u32,
+	pub(crate) bounds_sup: [f32; 3],
+	pub(crate) _padding: u32,
+}
+
+/// The buffers depend on the number of chunk meshes (they are recreated when they get too small),
+/// so here we only give the bind group layout, see `ChunkMeshCulling` for the bind group.
+pub(crate) fn compute_pipeline_and_bind_group_layout(
+	device: &wgpu::Device,
+) -> (wgpu::ComputePipeline, wgpu::BindGroupLayout) {
+	let buffer_entry = |binding: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry {
+		binding,
+		visibility: wgpu::ShaderStages::COMPUTE,
+		ty: wgpu::BindingType::Buffer { ty, has_dynamic_offset: false, min_binding_size: None },
+		count: None,
+	};
+	let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+		label: Some("Chunk Mesh Culling Shader Bind Group Layout"),
+		entries: &[
+			buffer_entry(0, wgpu::BufferBindingType::Uniform),
+			buffer_entry(1, wgpu::BufferBindingType::Uniform),
+			buffer_entry(2, wgpu::BufferBindingType::Storage { read_only: true }),
+			buffer_entry(3, wgpu::BufferBindingType::Storage { read_only: false }),
+		],
+	});
+
+	let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+		label: Some("Chunk Mesh Culling Shader"),
+		source: wgpu::ShaderSource::Wgsl(include_str!("chunk_mesh_culling.wgsl").into()),
+	});
+	let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+		label: Some("Chunk Mesh Culling Compute Pipeline Layout"),
+		bind_group_layouts: &[&bind_group_layout],
+		push_constant_ranges: &[],
+	});
+
+	let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+		label: Some("Chunk Mesh Culling Compute Pipeline"),
+		layout: Some(&pipeline_layout),
+		module: &shader,
+		entry_point: "compute_shader_main",
+		compilation_options: wgpu::PipelineCompilationOptions::default(),
+	});
+
+	(compute_pipeline, bind_group_layout)
+}
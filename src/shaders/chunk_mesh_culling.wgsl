@@ -0,0 +1,62 @@
+// Must match `ChunkMeshMetadataPod` in `chunk_mesh_culling.rs`.
+struct ChunkMeshMetadata {
+	bounds_inf: vec3<f32>,
+	vertex_count: u32,
+	bounds_sup: vec3<f32>,
+	_padding: u32,
+};
+
+// The layout expected by `draw_indirect`.
+struct DrawIndirectArgs {
+	vertex_count: u32,
+	instance_count: u32,
+	first_vertex: u32,
+	first_instance: u32,
+};
+
+@group(0) @binding(0) var<uniform> uniform_camera: mat4x4<f32>;
+@group(0) @binding(1) var<uniform> uniform_chunk_mesh_count: u32;
+@group(0) @binding(2) var<storage, read> chunk_meshes: array<ChunkMeshMetadata>;
+@group(0) @binding(3) var<storage, read_write> draw_args: array<DrawIndirectArgs>;
+
+// Is the box (given by its corners) completely out of the view frustum?
+// It is if all its corners are on the outer side of one of the planes of the frustum,
+// the planes are tested in clip space (before the division by w) where they are simple.
+// Only the side planes and the plane of the camera are tested (the far plane may be at infinity).
+fn is_out_of_view(bounds_inf: vec3<f32>, bounds_sup: vec3<f32>) -> bool {
+	var out_left = true;
+	var out_right = true;
+	var out_bottom = true;
+	var out_top = true;
+	var out_behind = true;
+	for (var corner_index = 0u; corner_index < 8u; corner_index++) {
+		let corner = vec3<f32>(
+			select(bounds_inf.x, bounds_sup.x, (corner_index & 1u) != 0u),
+			select(bounds_inf.y, bounds_sup.y, (corner_index & 2u) != 0u),
+			select(bounds_inf.z, bounds_sup.z, (corner_index & 4u) != 0u),
+		);
+		let clip = uniform_camera * vec4<f32>(corner, 1.0);
+		out_left = out_left && clip.x < -clip.w;
+		out_right = out_right && clip.x > clip.w;
+		out_bottom = out_bottom && clip.y < -clip.w;
+		out_top = out_top && clip.y > clip.w;
+		out_behind = out_behind && clip.w <= 0.0;
+	}
+	return out_left || out_right || out_bottom || out_top || out_behind;
+}
+
+@compute @workgroup_size(64)
+fn compute_shader_main(@builtin(global_invocation_id) id: vec3<u32>) {
+	let index = id.x;
+	if index >= uniform_chunk_mesh_count {
+		return;
+	}
+	let mesh = chunk_meshes[index];
+	var args: DrawIndirectArgs;
+	args.vertex_count = mesh.vertex_count;
+	args.instance_count = select(1u, 0u, is_out_of_view(mesh.bounds_inf, mesh.bounds_sup));
+	args.first_vertex = 0u;
+	// The instance index tells the block shaders which chunk mesh is drawn (see `ChunkInstances`).
+	args.first_instance = index;
+	draw_args[index] = args;
+}
@@ -0,0 +1,95 @@
+/// Formats of the G-buffer textures that the blocks are rendered to in the deferred rendering path,
+/// in the order of the outputs of `fragment_shader_gbuffer_main` in the block shader:
+/// color lit by the sun, albedo, normal and world position.
+pub(crate) const GBUFFER_FORMATS: [wgpu::TextureFormat; 4] = [
+	wgpu::TextureFormat::Rgba8UnormSrgb,
+	wgpu::TextureFormat::Rgba8UnormSrgb,
+	wgpu::TextureFormat::Rgba16Float,
+	wgpu::TextureFormat::Rgba32Float,
+];
+
+/// The bind group depends on the G-buffer textures (that are recreated when the window is resized),
+/// so here we only give its layout, see `DeferredLighting` for the bind group.
+pub(crate) fn render_pipeline_and_bind_group_layout(
+	device: &wgpu::Device,
+	output_format: wgpu::TextureFormat,
+) -> (wgpu::RenderPipeline, wgpu::BindGroupLayout) {
+	use wgpu::ShaderStages as S;
+	let gbuffer_texture_entry = |binding: u32| wgpu::BindGroupLayoutEntry {
+		binding,
+		visibility: S::FRAGMENT,
+		ty: wgpu::BindingType::Texture {
+			sample_type: wgpu::TextureSampleType::Float { filterable: false },
+			view_dimension: wgpu::TextureViewDimension::D2,
+			multisampled: false,
+		},
+		count: None,
+	};
+	let buffer_entry = |binding: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry {
+		binding,
+		visibility: S::FRAGMENT,
+		ty: wgpu::BindingType::Buffer { ty, has_dynamic_offset: false, min_binding_size: None },
+		count: None,
+	};
+	let storage = wgpu::BufferBindingType::Storage { read_only: true };
+	let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+		label: Some("Deferred Lighting Shader Bind Group Layout"),
+		entries: &[
+			gbuffer_texture_entry(0),
+			gbuffer_texture_entry(1),
+			gbuffer_texture_entry(2),
+			gbuffer_texture_entry(3),
+			buffer_entry(4, wgpu::BufferBindingType::Uniform),
+			buffer_entry(5, storage),
+			buffer_entry(6, storage),
+			buffer_entry(7, storage),
+		],
+	});
+
+	let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+		label: Some("Deferred Lighting Shader"),
+		source: wgpu::ShaderSource::Wgsl(include_str!("deferred_lighting.wgsl").into()),
+	});
+	let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+		label: Some("Deferred Lighting Render Pipeline Layout"),
+		bind_group_layouts: &[&bind_group_layout],
+		push_constant_ranges: &[],
+	});
+
+	let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+		label: Some("Deferred Lighting Render Pipeline"),
+		layout: Some(&render_pipeline_layout),
+		vertex: wgpu::VertexState {
+			module: &shader,
+			entry_point: "vertex_shader_main",
+			compilation_options: wgpu::PipelineCompilationOptions::default(),
+			buffers: &[],
+		},
+		fragment: Some(wgpu::FragmentState {
+			module: &shader,
+			entry_point: "fragment_shader_main",
+			compilation_options: wgpu::PipelineCompilationOptions::default(),
+			targets: &[Some(wgpu::ColorTargetState {
+				format: output_format,
+				// The alpha is the fog transparency, the skybox is blent behind later
+				// (just like in the forward rendering path).
+				blend: Some(wgpu::BlendState::REPLACE),
+				write_mask: wgpu::ColorWrites::ALL,
+			})],
+		}),
+		primitive: wgpu::PrimitiveState {
+			topology: wgpu::PrimitiveTopology::TriangleList,
+			strip_index_format: None,
+			front_face: wgpu::FrontFace::Ccw,
+			cull_mode: None,
+			polygon_mode: wgpu::PolygonMode::Fill,
+			unclipped_depth: false,
+			conservative: false,
+		},
+		depth_stencil: None,
+		multisample: wgpu::MultisampleState { count: 1, mask: !0, alpha_to_coverage_enabled: false },
+		multiview: None,
+	});
+
+	(render_pipeline, bind_group_layout)
+}
@@ -0,0 +1,81 @@
+struct VertexOutput {
+	@builtin(position) screen_position: vec4<f32>,
+};
+
+struct PointLight {
+	// The w component is the radius (the light does not reach further than that).
+	position_and_radius: vec4<f32>,
+	color: vec4<f32>,
+};
+
+struct ClusterSettings {
+	camera_position: vec4<f32>,
+	camera_forward: vec4<f32>,
+	// Size of a screen tile in pixels, then the near and far distances of the depth slicing.
+	tile_size_and_depth_range: vec4<f32>,
+	// Number of clusters along the screen x axis, the screen y axis and the depth axis.
+	cluster_counts: vec4<u32>,
+};
+
+@group(0) @binding(0) var gbuffer_color: texture_2d<f32>;
+@group(0) @binding(1) var gbuffer_albedo: texture_2d<f32>;
+@group(0) @binding(2) var gbuffer_normal: texture_2d<f32>;
+@group(0) @binding(3) var gbuffer_world_position: texture_2d<f32>;
+@group(0) @binding(4) var<uniform> uniform_cluster_settings: ClusterSettings;
+@group(0) @binding(5) var<storage, read> lights: array<PointLight>;
+// For each cluster, the offset and the number of its lights in `light_indices`.
+@group(0) @binding(6) var<storage, read> cluster_light_ranges: array<vec2<u32> >;
+@group(0) @binding(7) var<storage, read> light_indices: array<u32>;
+
+@vertex
+fn vertex_shader_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+	// One triangle that covers the whole screen.
+	let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+	var vertex_output: VertexOutput;
+	vertex_output.screen_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+	return vertex_output;
+}
+
+@fragment
+fn fragment_shader_main(the: VertexOutput) -> @location(0) vec4<f32> {
+	let pixel = vec2<i32>(the.screen_position.xy);
+	let color = textureLoad(gbuffer_color, pixel, 0);
+	let world_position = textureLoad(gbuffer_world_position, pixel, 0);
+	if world_position.w == 0.0 {
+		// No block there, the skybox will be drawn behind.
+		return color;
+	}
+	let albedo = textureLoad(gbuffer_albedo, pixel, 0).rgb;
+	let normal = textureLoad(gbuffer_normal, pixel, 0).xyz;
+
+	// Find the cluster that contains the fragment.
+	// The depth slices are exponentially thicker with the distance, like the perspective.
+	let settings = uniform_cluster_settings;
+	let counts = settings.cluster_counts.xyz;
+	let tile = min(vec2<u32>(the.screen_position.xy / settings.tile_size_and_depth_range.xy),
+		counts.xy - vec2<u32>(1u, 1u));
+	let near = settings.tile_size_and_depth_range.z;
+	let far = settings.tile_size_and_depth_range.w;
+	let depth = dot(world_position.xyz - settings.camera_position.xyz, settings.camera_forward.xyz);
+	let slice_float = log(max(depth, near) / near) / log(far / near) * f32(counts.z);
+	let slice = min(u32(slice_float), counts.z - 1u);
+	let cluster_index = (slice * counts.y + tile.y) * counts.x + tile.x;
+
+	// Only the lights that may reach the cluster are considered.
+	let range = cluster_light_ranges[cluster_index];
+	var light_sum = vec3<f32>(0.0, 0.0, 0.0);
+	for (var i = 0u; i < range.y; i++) {
+		let light = lights[light_indices[range.x + i]];
+		let to_light = light.position_and_radius.xyz - world_position.xyz;
+		let distance_to_light = length(to_light);
+		let radius = light.position_and_radius.w;
+		if distance_to_light < radius {
+			let attenuation = pow(1.0 - distance_to_light / radius, 2.0);
+			// Faces that do not face the light still get a bit of it, it looks less harsh.
+			let facing = max(dot(normal, to_light / max(distance_to_light, 0.0001)), 0.0);
+			light_sum += light.color.rgb * attenuation * (facing * 0.8 + 0.2);
+		}
+	}
+
+	return vec4<f32>(color.rgb + albedo * light_sum, color.a);
+}
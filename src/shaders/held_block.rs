@@ -0,0 +1,111 @@
+use crate::{rendering_init::BindingThingy, shaders::block::BlockVertexPod};
+
+/// How to render the held block (or the hand), see `HeldBlockView`.
+#[derive(Copy, Clone, Debug)]
+/// Certified Plain Old Data (so it can be sent to the GPU as a uniform).
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct HeldBlockSettingsPod {
+	/// Places the mesh in the bottom-right corner of the screen, and projects it.
+	pub(crate) view_projection_model_matrix: [[f32; 4]; 4],
+	/// The rotation part of the model matrix, to rotate the normals (for the lighting).
+	pub(crate) rotation_matrix: [[f32; 4]; 4],
+	/// Color of the hand, used instead of the atlas if `textured` is 0.
+	pub(crate) hand_color: [f32; 3],
+	pub(crate) textured: u32,
+}
+
+pub(crate) struct BindingThingies<'a> {
+	pub(crate) held_block_settings_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) atlas_texture_view_thingy: &'a BindingThingy<wgpu::TextureView>,
+	pub(crate) atlas_texture_sampler_thingy: &'a BindingThingy<wgpu::Sampler>,
+}
+
+/// The held block is rendered directly to the window after the scene (like the interface),
+/// with its own depth buffer so that it is never hidden by the blocks that are in front of the
+/// player (as it is not really in the world).
+pub(crate) fn render_pipeline_and_bind_group(
+	device: &wgpu::Device,
+	binding_thingies: BindingThingies,
+	output_format: wgpu::TextureFormat,
+	z_buffer_format: wgpu::TextureFormat,
+) -> (wgpu::RenderPipeline, wgpu::BindGroup) {
+	// Same vertices as the blocks of the chunk meshes, see `generate_lone_block_mesh`.
+	let vertex_buffer_layout = wgpu::VertexBufferLayout {
+		array_stride: std::mem::size_of::<BlockVertexPod>() as wgpu::BufferAddress,
+		step_mode: wgpu::VertexStepMode::Vertex,
+		attributes: &BlockVertexPod::vertex_attributes(),
+	};
+
+	use wgpu::ShaderStages as S;
+	let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+		label: Some("Held Block Shader Bind Group Layout"),
+		entries: &[
+			binding_thingies.held_block_settings_thingy.layout_entry(0, S::VERTEX | S::FRAGMENT),
+			binding_thingies.atlas_texture_view_thingy.layout_entry(1, S::FRAGMENT),
+			binding_thingies.atlas_texture_sampler_thingy.layout_entry(2, S::FRAGMENT),
+		],
+	});
+	let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+		label: Some("Held Block Shader Bind Group"),
+		layout: &bind_group_layout,
+		entries: &[
+			binding_thingies.held_block_settings_thingy.bind_group_entry(0),
+			binding_thingies.atlas_texture_view_thingy.bind_group_entry(1),
+			binding_thingies.atlas_texture_sampler_thingy.bind_group_entry(2),
+		],
+	});
+
+	let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+		label: Some("Held Block Shader"),
+		source: wgpu::ShaderSource::Wgsl(include_str!("held_block.wgsl").into()),
+	});
+	let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+		label: Some("Held Block Render Pipeline Layout"),
+		bind_group_layouts: &[&bind_group_layout],
+		push_constant_ranges: &[],
+	});
+
+	let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+		label: Some("Held Block Render Pipeline"),
+		layout: Some(&render_pipeline_layout),
+		vertex: wgpu::VertexState {
+			module: &shader,
+			entry_point: "vertex_shader_main",
+			compilation_options: wgpu::PipelineCompilationOptions::default(),
+			buffers: &[vertex_buffer_layout],
+		},
+		fragment: Some(wgpu::FragmentState {
+			module: &shader,
+			entry_point: "fragment_shader_main",
+			compilation_options: wgpu::PipelineCompilationOptions::default(),
+			targets: &[Some(wgpu::ColorTargetState {
+				format: output_format,
+				blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+				write_mask: wgpu::ColorWrites::ALL,
+			})],
+		}),
+		primitive: wgpu::PrimitiveState {
+			topology: wgpu::PrimitiveTopology::TriangleList,
+			strip_index_format: None,
+			front_face: wgpu::FrontFace::Ccw,
+			// X-shaped blocks have faces on both sides, so back faces can be culled.
+			cull_mode: Some(wgpu::Face::Back),
+			polygon_mode: wgpu::PolygonMode::Fill,
+			unclipped_depth: false,
+			conservative: false,
+		},
+		// Projected like the scene, with reverse-Z (see `OPENGL_TO_WGPU_REVERSE_Z_MATRIX`).
+		depth_stencil: Some(wgpu::DepthStencilState {
+			format: z_buffer_format,
+			depth_write_enabled: true,
+			depth_compare: wgpu::CompareFunction::GreaterEqual,
+			stencil: wgpu::StencilState::default(),
+			bias: wgpu::DepthBiasState::default(),
+		}),
+		multisample: wgpu::MultisampleState { count: 1, mask: !0, alpha_to_coverage_enabled: false },
+		multiview: None,
+	});
+
+	(render_pipeline, bind_group)
+}
@@ -0,0 +1,53 @@
+struct VertexInput {
+	@location(0) position: vec3<f32>,
+	@location(1) coords_in_atlas: vec2<f32>,
+	@location(2) normal: vec3<f32>,
+	@location(3) ambiant_occlusion: f32,
+	@location(4) material_flags: u32,
+	@location(5) block_light: vec3<f32>,
+};
+
+struct VertexOutput {
+	@builtin(position) screen_position: vec4<f32>,
+	@location(0) coords_in_atlas: vec2<f32>,
+	@location(1) shade: f32,
+};
+
+// Must match `HeldBlockSettingsPod` in `held_block.rs`.
+struct HeldBlockSettings {
+	view_projection_model_matrix: mat4x4<f32>,
+	rotation_matrix: mat4x4<f32>,
+	hand_color: vec3<f32>,
+	textured: u32,
+};
+
+@group(0) @binding(0) var<uniform> uniform_held_block: HeldBlockSettings;
+@group(0) @binding(1) var uniform_atlas_texture: texture_2d<f32>;
+@group(0) @binding(2) var uniform_atlas_sampler: sampler;
+
+@vertex
+fn vertex_shader_main(vertex_input: VertexInput) -> VertexOutput {
+	var vertex_output: VertexOutput;
+	vertex_output.screen_position =
+		uniform_held_block.view_projection_model_matrix * vec4<f32>(vertex_input.position, 1.0);
+	vertex_output.coords_in_atlas = vertex_input.coords_in_atlas;
+	// The held block is not in the world, it is lit by a light that does not move with the sun
+	// (from above and a bit from the left of the screen) so that it always looks the same.
+	let normal = (uniform_held_block.rotation_matrix * vec4<f32>(vertex_input.normal, 0.0)).xyz;
+	let towards_light = normalize(vec3<f32>(-0.3, 0.4, 1.0));
+	vertex_output.shade = 0.55 + 0.45 * clamp(dot(normalize(normal), towards_light), 0.0, 1.0);
+	return vertex_output;
+}
+
+@fragment
+fn fragment_shader_main(the: VertexOutput) -> @location(0) vec4<f32> {
+	var out_color = vec4<f32>(uniform_held_block.hand_color, 1.0);
+	if uniform_held_block.textured != 0u {
+		out_color = textureSample(uniform_atlas_texture, uniform_atlas_sampler, the.coords_in_atlas);
+	}
+	// Full transparency.
+	if out_color.a == 0.0 {
+		discard;
+	}
+	return vec4<f32>(out_color.rgb * the.shade, out_color.a);
+}
@@ -0,0 +1,144 @@
+/// Parameters of the depth of field and of the motion blur (see `LensEffects`).
+#[derive(Copy, Clone, Debug)]
+/// Certified Plain Old Data (so it can be sent to the GPU as a uniform).
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct LensEffectsSettingsPod {
+	pub(crate) inverse_view_projection: [[f32; 4]; 4],
+	/// The view projection matrix of the last frame.
+	pub(crate) previous_view_projection: [[f32; 4]; 4],
+	/// The w component is not used.
+	pub(crate) camera_position: [f32; 4],
+	pub(crate) depth_of_field: f32,
+	pub(crate) motion_blur: f32,
+	pub(crate) _padding: [f32; 2],
+}
+
+pub(crate) struct LensEffectsRenderPipelines {
+	/// Renders the scene with the effects to another texture.
+	pub(crate) effects: wgpu::RenderPipeline,
+	/// Copies that texture back to the scene texture.
+	pub(crate) copy: wgpu::RenderPipeline,
+}
+
+/// The bind groups depend on the textures that are sampled (that are recreated when the window is
+/// resized or when the render scale changes), so here we only give their layout, see `LensEffects`.
+/// The depth texture is multisampled if the scene is (`sample_count` is greater than 1).
+pub(crate) fn render_pipelines_and_bind_group_layout(
+	device: &wgpu::Device,
+	output_format: wgpu::TextureFormat,
+	sample_count: u32,
+) -> (LensEffectsRenderPipelines, wgpu::BindGroupLayout) {
+	use wgpu::ShaderStages as S;
+	let multisampled = sample_count > 1;
+	let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+		label: Some("Lens Effects Shader Bind Group Layout"),
+		entries: &[
+			wgpu::BindGroupLayoutEntry {
+				binding: 0,
+				visibility: S::FRAGMENT,
+				ty: wgpu::BindingType::Texture {
+					sample_type: wgpu::TextureSampleType::Float { filterable: true },
+					view_dimension: wgpu::TextureViewDimension::D2,
+					multisampled: false,
+				},
+				count: None,
+			},
+			wgpu::BindGroupLayoutEntry {
+				binding: 1,
+				visibility: S::FRAGMENT,
+				ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+				count: None,
+			},
+			wgpu::BindGroupLayoutEntry {
+				binding: 2,
+				visibility: S::FRAGMENT,
+				ty: wgpu::BindingType::Texture {
+					sample_type: wgpu::TextureSampleType::Depth,
+					view_dimension: wgpu::TextureViewDimension::D2,
+					multisampled,
+				},
+				count: None,
+			},
+			wgpu::BindGroupLayoutEntry {
+				binding: 3,
+				visibility: S::FRAGMENT,
+				ty: wgpu::BindingType::Buffer {
+					ty: wgpu::BufferBindingType::Uniform,
+					has_dynamic_offset: false,
+					min_binding_size: None,
+				},
+				count: None,
+			},
+		],
+	});
+
+	let mut shader_source = include_str!("lens_effects.wgsl").to_string();
+	if multisampled {
+		shader_source = shader_source.replace(
+			"alias DepthTexture = texture_depth_2d;",
+			"alias DepthTexture = texture_depth_multisampled_2d;",
+		);
+	}
+	let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+		label: Some("Lens Effects Shader"),
+		source: wgpu::ShaderSource::Wgsl(shader_source.into()),
+	});
+	let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+		label: Some("Lens Effects Render Pipeline Layout"),
+		bind_group_layouts: &[&bind_group_layout],
+		push_constant_ranges: &[],
+	});
+
+	let make_render_pipeline = |label, fragment_entry_point| {
+		device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+			label: Some(label),
+			layout: Some(&render_pipeline_layout),
+			vertex: wgpu::VertexState {
+				module: &shader,
+				entry_point: "vertex_shader_main",
+				compilation_options: wgpu::PipelineCompilationOptions::default(),
+				buffers: &[],
+			},
+			fragment: Some(wgpu::FragmentState {
+				module: &shader,
+				entry_point: fragment_entry_point,
+				compilation_options: wgpu::PipelineCompilationOptions::default(),
+				targets: &[Some(wgpu::ColorTargetState {
+					format: output_format,
+					blend: Some(wgpu::BlendState::REPLACE),
+					write_mask: wgpu::ColorWrites::ALL,
+				})],
+			}),
+			primitive: wgpu::PrimitiveState {
+				topology: wgpu::PrimitiveTopology::TriangleList,
+				strip_index_format: None,
+				front_face: wgpu::FrontFace::Ccw,
+				cull_mode: None,
+				polygon_mode: wgpu::PolygonMode::Fill,
+				unclipped_depth: false,
+				conservative: false,
+			},
+			depth_stencil: None,
+			multisample: wgpu::MultisampleState {
+				count: 1,
+				mask: !0,
+				alpha_to_coverage_enabled: false,
+			},
+			multiview: None,
+		})
+	};
+
+	let render_pipelines = LensEffectsRenderPipelines {
+		effects: make_render_pipeline(
+			"Lens Effects Render Pipeline",
+			"fragment_shader_effects_main",
+		),
+		copy: make_render_pipeline(
+			"Lens Effects Copy Render Pipeline",
+			"fragment_shader_copy_main",
+		),
+	};
+
+	(render_pipelines, bind_group_layout)
+}
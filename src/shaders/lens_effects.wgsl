@@ -0,0 +1,134 @@
+struct VertexOutput {
+	@builtin(position) screen_position: vec4<f32>,
+	@location(0) coords: vec2<f32>,
+};
+
+// Must match `LensEffectsSettingsPod` in `lens_effects.rs`.
+struct LensEffectsSettings {
+	inverse_view_projection: mat4x4<f32>,
+	// The view projection matrix of the last frame, to know where the pixels were on the screen.
+	previous_view_projection: mat4x4<f32>,
+	camera_position: vec4<f32>,
+	// How much what is out of focus is blurred, 0.0 disables the depth of field.
+	depth_of_field: f32,
+	// How much of the motion of the camera during the last frame blurs, 0.0 disables it.
+	motion_blur: f32,
+	_padding_a: f32,
+	_padding_b: f32,
+};
+
+// This type is replaced by `texture_depth_multisampled_2d` when the scene is multisampled.
+alias DepthTexture = texture_depth_2d;
+
+@group(0) @binding(0) var source_texture: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+@group(0) @binding(2) var depth_texture: DepthTexture;
+@group(0) @binding(3) var<uniform> uniform_settings: LensEffectsSettings;
+
+// Radius (in pixels) of the blur of what is the most out of focus.
+const MAX_BLUR_RADIUS: f32 = 10.0;
+const DEPTH_OF_FIELD_SAMPLE_COUNT: i32 = 32;
+// Length (in screen widths or heights) of the longest motion blur streaks.
+const MAX_MOTION_LENGTH: f32 = 0.06;
+const MOTION_BLUR_SAMPLE_COUNT: i32 = 12;
+const GOLDEN_ANGLE: f32 = 2.39996323;
+
+@vertex
+fn vertex_shader_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+	// One triangle that covers the whole screen.
+	let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+	var vertex_output: VertexOutput;
+	vertex_output.screen_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+	vertex_output.coords = vec2<f32>(uv.x, 1.0 - uv.y);
+	return vertex_output;
+}
+
+// Position in the world of what is seen at the given screen coords.
+fn world_position_at(coords: vec2<f32>) -> vec3<f32> {
+	let dimensions = vec2<i32>(textureDimensions(depth_texture));
+	let texel = clamp(vec2<i32>(coords * vec2<f32>(dimensions)), vec2<i32>(0), dimensions - 1);
+	// With multisampling, the third argument is the sample index instead of the mip level.
+	let depth = textureLoad(depth_texture, texel, 0);
+	let position = uniform_settings.inverse_view_projection
+		* vec4<f32>(coords.x * 2.0 - 1.0, 1.0 - coords.y * 2.0, depth, 1.0);
+	return position.xyz / position.w;
+}
+
+fn distance_at(coords: vec2<f32>) -> f32 {
+	return distance(world_position_at(coords), uniform_settings.camera_position.xyz);
+}
+
+// Radius (in pixels) of the disk that a point at the given distance is blurred into,
+// growing like for a thin lens that is focused at the given focus distance.
+fn circle_of_confusion(distance: f32, focus: f32) -> f32 {
+	let blur = abs(distance - focus) / max(distance, 0.0001) * uniform_settings.depth_of_field;
+	return min(blur * MAX_BLUR_RADIUS, MAX_BLUR_RADIUS);
+}
+
+@fragment
+fn fragment_shader_effects_main(the: VertexOutput) -> @location(0) vec4<f32> {
+	let texel_size = 1.0 / vec2<f32>(textureDimensions(source_texture));
+	let center = textureSampleLevel(source_texture, source_sampler, the.coords, 0.0);
+	var color = center.rgb;
+
+	if uniform_settings.depth_of_field > 0.0 {
+		// The focus is on what is under the crosshair.
+		let focus = distance_at(vec2<f32>(0.5, 0.5));
+		let radius = circle_of_confusion(distance_at(the.coords), focus);
+		if radius > 0.5 {
+			// The samples are spread on the disk of confusion along a spiral (Vogel's disk).
+			// A sample only counts if its own disk of confusion reaches this pixel, so that
+			// what is in focus does not bleed on the blurry background.
+			var sum = color;
+			var total_weight = 1.0;
+			for (var i = 1; i < DEPTH_OF_FIELD_SAMPLE_COUNT; i++) {
+				let sample_distance = sqrt(f32(i) / f32(DEPTH_OF_FIELD_SAMPLE_COUNT)) * radius;
+				let angle = f32(i) * GOLDEN_ANGLE;
+				let sample_coords = the.coords
+					+ vec2<f32>(cos(angle), sin(angle)) * sample_distance * texel_size;
+				let sample_radius = circle_of_confusion(distance_at(sample_coords), focus);
+				let weight = clamp(sample_radius - sample_distance + 1.0, 0.0, 1.0);
+				sum += textureSampleLevel(source_texture, source_sampler, sample_coords, 0.0).rgb
+					* weight;
+				total_weight += weight;
+			}
+			color = sum / total_weight;
+		}
+	}
+
+	if uniform_settings.motion_blur > 0.0 {
+		// Where the pixel was on the screen in the last frame gives its motion (due to the motion
+		// of the camera only, moving entities are not taken into account).
+		let previous_position = uniform_settings.previous_view_projection
+			* vec4<f32>(world_position_at(the.coords), 1.0);
+		if previous_position.w > 0.0 {
+			let previous_ndc = previous_position.xy / previous_position.w;
+			let previous_coords = vec2<f32>(previous_ndc.x * 0.5 + 0.5, 0.5 - previous_ndc.y * 0.5);
+			var motion = (the.coords - previous_coords) * uniform_settings.motion_blur;
+			let motion_length = length(motion);
+			if motion_length > MAX_MOTION_LENGTH {
+				motion *= MAX_MOTION_LENGTH / motion_length;
+			}
+			if motion_length * f32(textureDimensions(source_texture).x) > 1.0 {
+				// The samples along the motion are not blurred by the depth of field, which is
+				// hardly noticeable as they are blurred by the motion anyway.
+				var sum = color;
+				for (var i = 1; i < MOTION_BLUR_SAMPLE_COUNT; i++) {
+					let t = f32(i) / f32(MOTION_BLUR_SAMPLE_COUNT - 1) - 0.5;
+					sum += textureSampleLevel(source_texture, source_sampler, the.coords + motion * t, 0.0)
+						.rgb;
+				}
+				color = sum / f32(MOTION_BLUR_SAMPLE_COUNT);
+			}
+		}
+	}
+
+	return vec4<f32>(color, center.a);
+}
+
+// The effects can't be rendered to the scene texture that they sample,
+// so they are rendered to another texture that is then copied back to the scene texture.
+@fragment
+fn fragment_shader_copy_main(the: VertexOutput) -> @location(0) vec4<f32> {
+	return textureLoad(source_texture, vec2<i32>(the.screen_position.xy), 0);
+}
@@ -1,5 +1,10 @@
 pub(crate) mod block;
 pub(crate) mod block_shadow;
+pub(crate) mod bloom;
+pub(crate) mod chunk_mesh_culling;
+pub(crate) mod deferred_lighting;
+pub(crate) mod held_block;
+pub(crate) mod lens_effects;
 pub(crate) mod part_colored;
 pub(crate) mod part_colored_shadow;
 pub(crate) mod part_textured;
@@ -7,7 +12,9 @@ pub(crate) mod part_textured_shadow;
 pub(crate) mod simple_line;
 pub(crate) mod simple_line_2d;
 pub(crate) mod simple_texture_2d;
+pub(crate) mod simple_texture_3d;
 pub(crate) mod skybox;
+pub(crate) mod upscale;
 
 /// Vector in 3D.
 #[derive(Copy, Clone, Debug)]
@@ -17,9 +17,17 @@ pub(crate) struct PartColoredInstancePod {
 	pub(crate) inv_trans_model_matrix_3_of_4: [f32; 4],
 	pub(crate) inv_trans_model_matrix_4_of_4: [f32; 4],
 	pub(crate) coloring_offset: u32,
+	/// The model matrix as it was rendered before the last entity physics step, the rendered
+	/// model matrix goes from it to `model_matrix` (see `PartsInterpolation`).
+	pub(crate) previous_model_matrix_1_of_4: [f32; 4],
+	pub(crate) previous_model_matrix_2_of_4: [f32; 4],
+	pub(crate) previous_model_matrix_3_of_4: [f32; 4],
+	pub(crate) previous_model_matrix_4_of_4: [f32; 4],
+	/// From 0.0 (normal colors) to 1.0 (all red), to make the entity flash red when it is hit.
+	pub(crate) hit_tint: f32,
 }
 impl PartColoredInstancePod {
-	pub(crate) fn vertex_attributes() -> [wgpu::VertexAttribute; 9] {
+	pub(crate) fn vertex_attributes() -> [wgpu::VertexAttribute; 14] {
 		vertex_attr_array![
 			2 => Float32x4,
 			3 => Float32x4,
@@ -30,6 +38,11 @@ impl PartColoredInstancePod {
 			8 => Float32x4,
 			9 => Float32x4,
 			10 => Uint32,
+			11 => Float32x4,
+			12 => Float32x4,
+			13 => Float32x4,
+			14 => Float32x4,
+			15 => Float32,
 		]
 	}
 }
@@ -43,6 +56,7 @@ pub(crate) struct BindingThingies<'a> {
 	pub(crate) shadow_map_sampler_thingy: &'a BindingThingy<wgpu::Sampler>,
 	pub(crate) fog_center_position_thingy: &'a BindingThingy<wgpu::Buffer>,
 	pub(crate) fog_inf_sup_radiuses_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) entity_interpolation_factor_thingy: &'a BindingThingy<wgpu::Buffer>,
 }
 
 pub(crate) fn render_pipeline_and_bind_group(
@@ -50,6 +64,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 	binding_thingies: BindingThingies,
 	output_format: wgpu::TextureFormat,
 	z_buffer_format: wgpu::TextureFormat,
+	sample_count: u32,
 ) -> (wgpu::RenderPipeline, wgpu::BindGroup) {
 	let vertex_buffer_layout = wgpu::VertexBufferLayout {
 		array_stride: std::mem::size_of::<PartVertexPod>() as wgpu::BufferAddress,
@@ -74,6 +89,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 			binding_thingies.shadow_map_sampler_thingy.layout_entry(5, S::FRAGMENT),
 			binding_thingies.fog_center_position_thingy.layout_entry(6, S::FRAGMENT),
 			binding_thingies.fog_inf_sup_radiuses_thingy.layout_entry(7, S::FRAGMENT),
+			binding_thingies.entity_interpolation_factor_thingy.layout_entry(8, S::VERTEX),
 		],
 	});
 	let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -88,6 +104,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 			binding_thingies.shadow_map_sampler_thingy.bind_group_entry(5),
 			binding_thingies.fog_center_position_thingy.bind_group_entry(6),
 			binding_thingies.fog_inf_sup_radiuses_thingy.bind_group_entry(7),
+			binding_thingies.entity_interpolation_factor_thingy.bind_group_entry(8),
 		],
 	});
 
@@ -133,11 +150,15 @@ pub(crate) fn render_pipeline_and_bind_group(
 		depth_stencil: Some(wgpu::DepthStencilState {
 			format: z_buffer_format,
 			depth_write_enabled: true,
-			depth_compare: wgpu::CompareFunction::LessEqual,
+			depth_compare: wgpu::CompareFunction::GreaterEqual,
 			stencil: wgpu::StencilState::default(),
 			bias: wgpu::DepthBiasState::default(),
 		}),
-		multisample: wgpu::MultisampleState { count: 1, mask: !0, alpha_to_coverage_enabled: false },
+		multisample: wgpu::MultisampleState {
+			count: sample_count,
+			mask: !0,
+			alpha_to_coverage_enabled: false,
+		},
 		multiview: None,
 	});
 
@@ -13,6 +13,11 @@ struct InstanceInput {
 	@location(8) inv_trans_model_matrix_3_of_4: vec4<f32>,
 	@location(9) inv_trans_model_matrix_4_of_4: vec4<f32>,
 	@location(10) coloring_offset: u32,
+	@location(11) previous_model_matrix_1_of_4: vec4<f32>,
+	@location(12) previous_model_matrix_2_of_4: vec4<f32>,
+	@location(13) previous_model_matrix_3_of_4: vec4<f32>,
+	@location(14) previous_model_matrix_4_of_4: vec4<f32>,
+	@location(15) hit_tint: f32,
 };
 
 struct VertexOutput {
@@ -30,6 +35,7 @@ struct VertexOutput {
 @group(0) @binding(5) var uniform_shadow_map_sampler: sampler_comparison;
 @group(0) @binding(6) var<uniform> uniform_fog_center_position: vec3<f32>;
 @group(0) @binding(7) var<uniform> uniform_fog_inf_sup_radiuses: vec2<f32>;
+@group(0) @binding(8) var<uniform> uniform_entity_interpolation_factor: f32;
 
 @vertex
 fn vertex_shader_main(
@@ -43,6 +49,16 @@ fn vertex_shader_main(
 		instance_input.model_matrix_3_of_4,
 		instance_input.model_matrix_4_of_4,
 	);
+	// Entity parts only move at each entity physics step, so they are rendered somewhere between
+	// where they were rendered when the last step was applied and where it put them.
+	var previous_model_matrix = mat4x4(
+		instance_input.previous_model_matrix_1_of_4,
+		instance_input.previous_model_matrix_2_of_4,
+		instance_input.previous_model_matrix_3_of_4,
+		instance_input.previous_model_matrix_4_of_4,
+	);
+	model_matrix = previous_model_matrix
+		+ (model_matrix - previous_model_matrix) * uniform_entity_interpolation_factor;
 	var inv_trans_model_matrix = mat4x4(
 		instance_input.inv_trans_model_matrix_1_of_4,
 		instance_input.inv_trans_model_matrix_2_of_4,
@@ -55,6 +71,8 @@ fn vertex_shader_main(
 	var g = uniform_texturing_and_coloring_array[coloring_vertex_offset + 1];
 	var b = uniform_texturing_and_coloring_array[coloring_vertex_offset + 2];
 	var color = vec3(r, g, b);
+	// Entities flash red when hit.
+	color = mix(color, vec3(0.9, 0.1, 0.1), instance_input.hit_tint);
 
 	var world_normal = inv_trans_model_matrix * vec4<f32>(vertex_input.normal, 1.0);
 	var shade = dot(world_normal.xyz, -uniform_sun_light_direction);
@@ -7,6 +7,7 @@ pub(crate) struct BindingThingies<'a> {
 	pub(crate) sun_camera_single_matrix_thingy: &'a BindingThingy<wgpu::Buffer>,
 	pub(crate) fog_center_position_thingy: &'a BindingThingy<wgpu::Buffer>,
 	pub(crate) fog_inf_sup_radiuses_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) entity_interpolation_factor_thingy: &'a BindingThingy<wgpu::Buffer>,
 }
 
 pub(crate) fn render_pipeline_and_bind_group(
@@ -32,6 +33,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 			binding_thingies.sun_camera_single_matrix_thingy.layout_entry(0, S::VERTEX),
 			binding_thingies.fog_center_position_thingy.layout_entry(1, S::FRAGMENT),
 			binding_thingies.fog_inf_sup_radiuses_thingy.layout_entry(2, S::FRAGMENT),
+			binding_thingies.entity_interpolation_factor_thingy.layout_entry(3, S::VERTEX),
 		],
 	});
 	let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -41,6 +43,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 			binding_thingies.sun_camera_single_matrix_thingy.bind_group_entry(0),
 			binding_thingies.fog_center_position_thingy.bind_group_entry(1),
 			binding_thingies.fog_inf_sup_radiuses_thingy.bind_group_entry(2),
+			binding_thingies.entity_interpolation_factor_thingy.bind_group_entry(3),
 		],
 	});
 
@@ -81,7 +84,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 		depth_stencil: Some(wgpu::DepthStencilState {
 			format: z_buffer_format,
 			depth_write_enabled: true,
-			depth_compare: wgpu::CompareFunction::Less,
+			depth_compare: wgpu::CompareFunction::Greater,
 			stencil: wgpu::StencilState::default(),
 			bias: wgpu::DepthBiasState::default(),
 		}),
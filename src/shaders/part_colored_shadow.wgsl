@@ -13,6 +13,10 @@ struct InstanceInput {
 	@location(8) inv_trans_model_matrix_3_of_4: vec4<f32>,
 	@location(9) inv_trans_model_matrix_4_of_4: vec4<f32>,
 	@location(10) coloring_point_offset: u32,
+	@location(11) previous_model_matrix_1_of_4: vec4<f32>,
+	@location(12) previous_model_matrix_2_of_4: vec4<f32>,
+	@location(13) previous_model_matrix_3_of_4: vec4<f32>,
+	@location(14) previous_model_matrix_4_of_4: vec4<f32>,
 };
 
 struct VertexOutput {
@@ -23,6 +27,7 @@ struct VertexOutput {
 @group(0) @binding(0) var<uniform> uniform_sun_camera: mat4x4<f32>;
 @group(0) @binding(1) var<uniform> uniform_fog_center_position: vec3<f32>;
 @group(0) @binding(2) var<uniform> uniform_fog_inf_sup_radiuses: vec2<f32>;
+@group(0) @binding(3) var<uniform> uniform_entity_interpolation_factor: f32;
 
 // TODO: There is a lot of code duplication between here, `block_shadow.wgsl` and
 // `part_textured_shadow.wgsl`, we have to factorize!
@@ -39,6 +44,16 @@ fn vertex_shader_main(
 		instance_input.model_matrix_3_of_4,
 		instance_input.model_matrix_4_of_4,
 	);
+	// Entity parts only move at each entity physics step, so they are rendered somewhere between
+	// where they were rendered when the last step was applied and where it put them.
+	var previous_model_matrix = mat4x4(
+		instance_input.previous_model_matrix_1_of_4,
+		instance_input.previous_model_matrix_2_of_4,
+		instance_input.previous_model_matrix_3_of_4,
+		instance_input.previous_model_matrix_4_of_4,
+	);
+	model_matrix = previous_model_matrix
+		+ (model_matrix - previous_model_matrix) * uniform_entity_interpolation_factor;
 
 	var world_position = model_matrix * vec4<f32>(vertex_input.position, 1.0);
 
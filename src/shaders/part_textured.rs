@@ -35,9 +35,15 @@ pub(crate) struct PartTexturedInstancePod {
 	pub(crate) inv_trans_model_matrix_3_of_4: [f32; 4],
 	pub(crate) inv_trans_model_matrix_4_of_4: [f32; 4],
 	pub(crate) texture_mapping_offset: u32,
+	/// The model matrix as it was rendered before the last entity physics step, the rendered
+	/// model matrix goes from it to `model_matrix` (see `PartsInterpolation`).
+	pub(crate) previous_model_matrix_1_of_4: [f32; 4],
+	pub(crate) previous_model_matrix_2_of_4: [f32; 4],
+	pub(crate) previous_model_matrix_3_of_4: [f32; 4],
+	pub(crate) previous_model_matrix_4_of_4: [f32; 4],
 }
 impl PartTexturedInstancePod {
-	pub(crate) fn vertex_attributes() -> [wgpu::VertexAttribute; 9] {
+	pub(crate) fn vertex_attributes() -> [wgpu::VertexAttribute; 13] {
 		vertex_attr_array![
 			2 => Float32x4,
 			3 => Float32x4,
@@ -48,6 +54,10 @@ impl PartTexturedInstancePod {
 			8 => Float32x4,
 			9 => Float32x4,
 			10 => Uint32,
+			11 => Float32x4,
+			12 => Float32x4,
+			13 => Float32x4,
+			14 => Float32x4,
 		]
 	}
 }
@@ -63,6 +73,7 @@ pub(crate) struct BindingThingies<'a> {
 	pub(crate) shadow_map_sampler_thingy: &'a BindingThingy<wgpu::Sampler>,
 	pub(crate) fog_center_position_thingy: &'a BindingThingy<wgpu::Buffer>,
 	pub(crate) fog_inf_sup_radiuses_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) entity_interpolation_factor_thingy: &'a BindingThingy<wgpu::Buffer>,
 }
 
 pub(crate) fn render_pipeline_and_bind_group(
@@ -70,6 +81,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 	binding_thingies: BindingThingies,
 	output_format: wgpu::TextureFormat,
 	z_buffer_format: wgpu::TextureFormat,
+	sample_count: u32,
 ) -> (wgpu::RenderPipeline, wgpu::BindGroup) {
 	let vertex_buffer_layout = wgpu::VertexBufferLayout {
 		array_stride: std::mem::size_of::<PartVertexPod>() as wgpu::BufferAddress,
@@ -96,6 +108,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 			binding_thingies.shadow_map_sampler_thingy.layout_entry(7, S::FRAGMENT),
 			binding_thingies.fog_center_position_thingy.layout_entry(8, S::FRAGMENT),
 			binding_thingies.fog_inf_sup_radiuses_thingy.layout_entry(9, S::FRAGMENT),
+			binding_thingies.entity_interpolation_factor_thingy.layout_entry(10, S::VERTEX),
 		],
 	});
 	let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -112,6 +125,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 			binding_thingies.shadow_map_sampler_thingy.bind_group_entry(7),
 			binding_thingies.fog_center_position_thingy.bind_group_entry(8),
 			binding_thingies.fog_inf_sup_radiuses_thingy.bind_group_entry(9),
+			binding_thingies.entity_interpolation_factor_thingy.bind_group_entry(10),
 		],
 	});
 
@@ -157,11 +171,15 @@ pub(crate) fn render_pipeline_and_bind_group(
 		depth_stencil: Some(wgpu::DepthStencilState {
 			format: z_buffer_format,
 			depth_write_enabled: true,
-			depth_compare: wgpu::CompareFunction::LessEqual,
+			depth_compare: wgpu::CompareFunction::GreaterEqual,
 			stencil: wgpu::StencilState::default(),
 			bias: wgpu::DepthBiasState::default(),
 		}),
-		multisample: wgpu::MultisampleState { count: 1, mask: !0, alpha_to_coverage_enabled: false },
+		multisample: wgpu::MultisampleState {
+			count: sample_count,
+			mask: !0,
+			alpha_to_coverage_enabled: false,
+		},
 		multiview: None,
 	});
 
@@ -13,6 +13,10 @@ struct InstanceInput {
 	@location(8) inv_trans_model_matrix_3_of_4: vec4<f32>,
 	@location(9) inv_trans_model_matrix_4_of_4: vec4<f32>,
 	@location(10) texture_mapping_offset: u32,
+	@location(11) previous_model_matrix_1_of_4: vec4<f32>,
+	@location(12) previous_model_matrix_2_of_4: vec4<f32>,
+	@location(13) previous_model_matrix_3_of_4: vec4<f32>,
+	@location(14) previous_model_matrix_4_of_4: vec4<f32>,
 };
 
 struct VertexOutput {
@@ -32,6 +36,7 @@ struct VertexOutput {
 @group(0) @binding(7) var uniform_shadow_map_sampler: sampler_comparison;
 @group(0) @binding(8) var<uniform> uniform_fog_center_position: vec3<f32>;
 @group(0) @binding(9) var<uniform> uniform_fog_inf_sup_radiuses: vec2<f32>;
+@group(0) @binding(10) var<uniform> uniform_entity_interpolation_factor: f32;
 
 @vertex
 fn vertex_shader_main(
@@ -45,6 +50,16 @@ fn vertex_shader_main(
 		instance_input.model_matrix_3_of_4,
 		instance_input.model_matrix_4_of_4,
 	);
+	// Entity parts only move at each entity physics step, so they are rendered somewhere between
+	// where they were rendered when the last step was applied and where it put them.
+	var previous_model_matrix = mat4x4(
+		instance_input.previous_model_matrix_1_of_4,
+		instance_input.previous_model_matrix_2_of_4,
+		instance_input.previous_model_matrix_3_of_4,
+		instance_input.previous_model_matrix_4_of_4,
+	);
+	model_matrix = previous_model_matrix
+		+ (model_matrix - previous_model_matrix) * uniform_entity_interpolation_factor;
 	var inv_trans_model_matrix = mat4x4(
 		instance_input.inv_trans_model_matrix_1_of_4,
 		instance_input.inv_trans_model_matrix_2_of_4,
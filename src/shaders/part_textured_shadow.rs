@@ -10,6 +10,7 @@ pub(crate) struct BindingThingies<'a> {
 	pub(crate) fog_center_position_thingy: &'a BindingThingy<wgpu::Buffer>,
 	pub(crate) fog_inf_sup_radiuses_thingy: &'a BindingThingy<wgpu::Buffer>,
 	pub(crate) texturing_and_coloring_array_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) entity_interpolation_factor_thingy: &'a BindingThingy<wgpu::Buffer>,
 }
 
 pub(crate) fn render_pipeline_and_bind_group(
@@ -38,6 +39,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 			binding_thingies.fog_center_position_thingy.layout_entry(3, S::FRAGMENT),
 			binding_thingies.fog_inf_sup_radiuses_thingy.layout_entry(4, S::FRAGMENT),
 			binding_thingies.texturing_and_coloring_array_thingy.layout_entry(5, S::VERTEX),
+			binding_thingies.entity_interpolation_factor_thingy.layout_entry(6, S::VERTEX),
 		],
 	});
 	let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -50,6 +52,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 			binding_thingies.fog_center_position_thingy.bind_group_entry(3),
 			binding_thingies.fog_inf_sup_radiuses_thingy.bind_group_entry(4),
 			binding_thingies.texturing_and_coloring_array_thingy.bind_group_entry(5),
+			binding_thingies.entity_interpolation_factor_thingy.bind_group_entry(6),
 		],
 	});
 
@@ -90,7 +93,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 		depth_stencil: Some(wgpu::DepthStencilState {
 			format: z_buffer_format,
 			depth_write_enabled: true,
-			depth_compare: wgpu::CompareFunction::Less,
+			depth_compare: wgpu::CompareFunction::Greater,
 			stencil: wgpu::StencilState::default(),
 			bias: wgpu::DepthBiasState::default(),
 		}),
@@ -13,6 +13,10 @@ struct InstanceInput {
 	@location(8) inv_trans_model_matrix_3_of_4: vec4<f32>,
 	@location(9) inv_trans_model_matrix_4_of_4: vec4<f32>,
 	@location(10) texture_mapping_offset: u32,
+	@location(11) previous_model_matrix_1_of_4: vec4<f32>,
+	@location(12) previous_model_matrix_2_of_4: vec4<f32>,
+	@location(13) previous_model_matrix_3_of_4: vec4<f32>,
+	@location(14) previous_model_matrix_4_of_4: vec4<f32>,
 };
 
 struct VertexOutput {
@@ -27,6 +31,7 @@ struct VertexOutput {
 @group(0) @binding(3) var<uniform> uniform_fog_center_position: vec3<f32>;
 @group(0) @binding(4) var<uniform> uniform_fog_inf_sup_radiuses: vec2<f32>;
 @group(0) @binding(5) var<storage, read> uniform_texturing_and_coloring_array: array<f32>;
+@group(0) @binding(6) var<uniform> uniform_entity_interpolation_factor: f32;
 
 // TODO: There is a lot of code duplication between here and `block_shadow.wgsl`,
 // we have to factorize!
@@ -43,6 +48,16 @@ fn vertex_shader_main(
 		instance_input.model_matrix_3_of_4,
 		instance_input.model_matrix_4_of_4,
 	);
+	// Entity parts only move at each entity physics step, so they are rendered somewhere between
+	// where they were rendered when the last step was applied and where it put them.
+	var previous_model_matrix = mat4x4(
+		instance_input.previous_model_matrix_1_of_4,
+		instance_input.previous_model_matrix_2_of_4,
+		instance_input.previous_model_matrix_3_of_4,
+		instance_input.previous_model_matrix_4_of_4,
+	);
+	model_matrix = previous_model_matrix
+		+ (model_matrix - previous_model_matrix) * uniform_entity_interpolation_factor;
 
 	var texture_mapping_vertex_offset = instance_input.texture_mapping_offset + vertex_index * 2;
 	var x_in_atlas = uniform_texturing_and_coloring_array[texture_mapping_vertex_offset + 0];
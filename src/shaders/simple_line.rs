@@ -29,6 +29,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 	binding_thingies: BindingThingies,
 	output_format: wgpu::TextureFormat,
 	z_buffer_format: wgpu::TextureFormat,
+	sample_count: u32,
 ) -> (wgpu::RenderPipeline, wgpu::BindGroup) {
 	let vertex_buffer_layout = wgpu::VertexBufferLayout {
 		array_stride: std::mem::size_of::<SimpleLineVertexPod>() as wgpu::BufferAddress,
@@ -88,11 +89,15 @@ pub(crate) fn render_pipeline_and_bind_group(
 		depth_stencil: Some(wgpu::DepthStencilState {
 			format: z_buffer_format,
 			depth_write_enabled: true,
-			depth_compare: wgpu::CompareFunction::LessEqual,
+			depth_compare: wgpu::CompareFunction::GreaterEqual,
 			stencil: wgpu::StencilState::default(),
 			bias: wgpu::DepthBiasState::default(),
 		}),
-		multisample: wgpu::MultisampleState { count: 1, mask: !0, alpha_to_coverage_enabled: false },
+		multisample: wgpu::MultisampleState {
+			count: sample_count,
+			mask: !0,
+			alpha_to_coverage_enabled: false,
+		},
 		multiview: None,
 	});
 
@@ -0,0 +1,113 @@
+use crate::{rendering_init::BindingThingy, shaders::simple_texture_2d::SimpleTextureVertexPod};
+
+pub(crate) struct BindingThingies<'a> {
+	pub(crate) camera_matrix_thingy: &'a BindingThingy<wgpu::Buffer>,
+	pub(crate) atlas_texture_view_thingy: &'a BindingThingy<wgpu::TextureView>,
+	pub(crate) atlas_texture_sampler_thingy: &'a BindingThingy<wgpu::Sampler>,
+}
+
+/// Same vertex type as the 2D version, but the positions are in world space.
+///
+/// Returns two pipelines, the first one is depth-tested, the second one renders over everything
+/// (it sees through the world).
+pub(crate) fn render_pipelines(
+	device: &wgpu::Device,
+	binding_thingies: BindingThingies,
+	output_format: wgpu::TextureFormat,
+	z_buffer_format: wgpu::TextureFormat,
+	sample_count: u32,
+) -> (wgpu::RenderPipeline, wgpu::RenderPipeline, wgpu::BindGroup) {
+	let vertex_buffer_layout = wgpu::VertexBufferLayout {
+		array_stride: std::mem::size_of::<SimpleTextureVertexPod>() as wgpu::BufferAddress,
+		step_mode: wgpu::VertexStepMode::Vertex,
+		attributes: &SimpleTextureVertexPod::vertex_attributes(),
+	};
+
+	use wgpu::ShaderStages as S;
+	let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+		label: Some("Simple Texture 3D Shader Bind Group Layout"),
+		entries: &[
+			binding_thingies.camera_matrix_thingy.layout_entry(0, S::VERTEX),
+			binding_thingies.atlas_texture_view_thingy.layout_entry(1, S::FRAGMENT),
+			binding_thingies.atlas_texture_sampler_thingy.layout_entry(2, S::FRAGMENT),
+		],
+	});
+	let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+		label: Some("Simple Texture 3D Shader Bind Group"),
+		layout: &bind_group_layout,
+		entries: &[
+			binding_thingies.camera_matrix_thingy.bind_group_entry(0),
+			binding_thingies.atlas_texture_view_thingy.bind_group_entry(1),
+			binding_thingies.atlas_texture_sampler_thingy.bind_group_entry(2),
+		],
+	});
+
+	let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+		label: Some("Simple Texture 3D Shader"),
+		source: wgpu::ShaderSource::Wgsl(include_str!("simple_texture_3d.wgsl").into()),
+	});
+	let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+		label: Some("Simple Texture 3D Render Pipeline Layout"),
+		bind_group_layouts: &[&bind_group_layout],
+		push_constant_ranges: &[],
+	});
+
+	let make_render_pipeline = |see_through: bool| {
+		device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+			label: Some(if see_through {
+				"Simple Texture 3D See-Through Render Pipeline"
+			} else {
+				"Simple Texture 3D Render Pipeline"
+			}),
+			layout: Some(&render_pipeline_layout),
+			vertex: wgpu::VertexState {
+				module: &shader,
+				entry_point: "vertex_shader_main",
+				compilation_options: wgpu::PipelineCompilationOptions::default(),
+				buffers: std::slice::from_ref(&vertex_buffer_layout),
+			},
+			fragment: Some(wgpu::FragmentState {
+				module: &shader,
+				entry_point: "fragment_shader_main",
+				compilation_options: wgpu::PipelineCompilationOptions::default(),
+				targets: &[Some(wgpu::ColorTargetState {
+					format: output_format,
+					blend: Some(wgpu::BlendState::REPLACE),
+					write_mask: wgpu::ColorWrites::ALL,
+				})],
+			}),
+			primitive: wgpu::PrimitiveState {
+				topology: wgpu::PrimitiveTopology::TriangleList,
+				strip_index_format: None,
+				front_face: wgpu::FrontFace::Ccw,
+				cull_mode: None,
+				polygon_mode: wgpu::PolygonMode::Fill,
+				unclipped_depth: false,
+				conservative: false,
+			},
+			depth_stencil: Some(wgpu::DepthStencilState {
+				format: z_buffer_format,
+				depth_write_enabled: !see_through,
+				depth_compare: if see_through {
+					wgpu::CompareFunction::Always
+				} else {
+					wgpu::CompareFunction::GreaterEqual
+				},
+				stencil: wgpu::StencilState::default(),
+				bias: wgpu::DepthBiasState::default(),
+			}),
+			multisample: wgpu::MultisampleState {
+				count: sample_count,
+				mask: !0,
+				alpha_to_coverage_enabled: false,
+			},
+			multiview: None,
+		})
+	};
+
+	(
+		make_render_pipeline(false),
+		make_render_pipeline(true),
+		bind_group,
+	)
+}
@@ -33,6 +33,7 @@ pub(crate) fn render_pipeline_and_bind_group(
 	device: &wgpu::Device,
 	binding_thingies: BindingThingies,
 	output_format: wgpu::TextureFormat,
+	sample_count: u32,
 ) -> (wgpu::RenderPipeline, wgpu::BindGroup) {
 	let vertex_buffer_layout = wgpu::VertexBufferLayout {
 		array_stride: std::mem::size_of::<SkyboxVertexPod>() as wgpu::BufferAddress,
@@ -114,7 +115,11 @@ pub(crate) fn render_pipeline_and_bind_group(
 			conservative: false,
 		},
 		depth_stencil: None,
-		multisample: wgpu::MultisampleState { count: 1, mask: !0, alpha_to_coverage_enabled: false },
+		multisample: wgpu::MultisampleState {
+			count: sample_count,
+			mask: !0,
+			alpha_to_coverage_enabled: false,
+		},
 		multiview: None,
 	});
 
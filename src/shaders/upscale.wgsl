@@ -0,0 +1,110 @@
+struct VertexOutput {
+	@builtin(position) screen_position: vec4<f32>,
+	@location(0) coords: vec2<f32>,
+};
+
+@group(0) @binding(0) var scene_texture: texture_2d<f32>;
+@group(0) @binding(1) var scene_sampler: sampler;
+// How much the result is sharpened, from 0.0 (just bilinear filtering) to 1.0.
+@group(0) @binding(2) var<uniform> uniform_sharpness: f32;
+
+// Must match `ColorGradingPod` in `upscale.rs`.
+struct ColorGrading {
+	exposure: f32,
+	contrast: f32,
+	saturation: f32,
+	// 0u for none, 1u for Reinhard and 2u for ACES.
+	tonemapper: u32,
+	// Edge of the color lookup table, 0.0 if there is none.
+	lut_size: f32,
+	_padding_a: f32,
+	_padding_b: f32,
+	_padding_c: f32,
+};
+
+@group(1) @binding(0) var<uniform> uniform_grading: ColorGrading;
+@group(1) @binding(1) var lut_texture: texture_3d<f32>;
+@group(1) @binding(2) var lut_sampler: sampler;
+
+@vertex
+fn vertex_shader_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+	// One triangle that covers the whole screen.
+	let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+	var vertex_output: VertexOutput;
+	vertex_output.screen_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+	vertex_output.coords = vec2<f32>(uv.x, 1.0 - uv.y);
+	return vertex_output;
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+	let low = color * 12.92;
+	let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+	return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+	let low = color / 12.92;
+	let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+	return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+// Reinhard extended so that `white` (and above) is mapped to white.
+fn tonemap_reinhard(color: vec3<f32>, white: f32) -> vec3<f32> {
+	return color * (1.0 + color / (white * white)) / (1.0 + color);
+}
+
+// Krzysztof Narkowicz's fit of the ACES filmic curve.
+fn tonemap_aces(color: vec3<f32>) -> vec3<f32> {
+	let a = 2.51;
+	let b = 0.03;
+	let c = 2.43;
+	let d = 0.59;
+	let e = 0.14;
+	return clamp((color * (a * color + b)) / (color * (c * color + d) + e), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn grade(color: vec3<f32>) -> vec3<f32> {
+	var linear = color * uniform_grading.exposure;
+	if uniform_grading.tonemapper == 1u {
+		linear = tonemap_reinhard(linear, max(uniform_grading.exposure, 1.0));
+	} else if uniform_grading.tonemapper == 2u {
+		linear = tonemap_aces(linear);
+	}
+
+	// Contrast and saturation are adjusted in sRGB space, that is closer to what we perceive.
+	var srgb = linear_to_srgb(clamp(linear, vec3<f32>(0.0), vec3<f32>(1.0)));
+	srgb = (srgb - 0.5) * uniform_grading.contrast + 0.5;
+	let luma = dot(srgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+	srgb = clamp(mix(vec3<f32>(luma), srgb, uniform_grading.saturation), vec3<f32>(0.0), vec3<f32>(1.0));
+
+	if uniform_grading.lut_size > 0.0 {
+		// The lookup table is made for sRGB colors, and its texels are sampled at their centers.
+		let size = uniform_grading.lut_size;
+		let lut_coords = srgb * (size - 1.0) / size + 0.5 / size;
+		srgb = textureSampleLevel(lut_texture, lut_sampler, lut_coords, 0.0).rgb;
+	}
+	return srgb_to_linear(srgb);
+}
+
+@fragment
+fn fragment_shader_main(the: VertexOutput) -> @location(0) vec4<f32> {
+	let center = textureSample(scene_texture, scene_sampler, the.coords);
+	if uniform_sharpness <= 0.0 {
+		return vec4<f32>(grade(center.rgb), center.a);
+	}
+
+	// Sharpening a bit like AMD's contrast adaptive sharpening (that FSR also uses):
+	// the center is pushed away from its neighbors, less so where the contrast is already high,
+	// and the result is kept in the range of the neighborhood to avoid halos.
+	let texel = 1.0 / vec2<f32>(textureDimensions(scene_texture));
+	let up = textureSample(scene_texture, scene_sampler, the.coords + vec2<f32>(0.0, -texel.y)).rgb;
+	let down = textureSample(scene_texture, scene_sampler, the.coords + vec2<f32>(0.0, texel.y)).rgb;
+	let left = textureSample(scene_texture, scene_sampler, the.coords + vec2<f32>(-texel.x, 0.0)).rgb;
+	let right = textureSample(scene_texture, scene_sampler, the.coords + vec2<f32>(texel.x, 0.0)).rgb;
+	let min_rgb = min(center.rgb, min(min(up, down), min(left, right)));
+	let max_rgb = max(center.rgb, max(max(up, down), max(left, right)));
+	let contrast = max_rgb - min_rgb;
+	let amount = uniform_sharpness * (vec3<f32>(1.0, 1.0, 1.0) - contrast) * 0.25;
+	let sharpened = center.rgb + (center.rgb * 4.0 - up - down - left - right) * amount;
+	return vec4<f32>(grade(clamp(sharpened, min_rgb, max_rgb)), center.a);
+}
@@ -0,0 +1,212 @@
+//! Signals, a minimal circuit layer: signal sources power the signal wires next to them,
+//! the power goes along connected wires (losing one level per wire), and signal lamps light up
+//! when next to a source or a powered wire. Pistons (see `pistons`) extend when powered the same
+//! way.
+//!
+//! Signals only change when blocks are set (see `ChunkGrid::take_block_updates`): the wire
+//! networks and lamps around the set blocks are updated, which sets other blocks that are in turn
+//! looked at the next frame. Only the blocks that actually change are set, so it settles.
+
+use std::collections::VecDeque;
+
+use fxhash::{FxHashMap, FxHashSet};
+
+use crate::{
+	block_types::BlockTypeTable,
+	chunk_blocks::Block,
+	chunks::{ChunkGrid, EditBatch},
+	coords::{AlignedBox, BlockCoords, OrientedAxis},
+	pistons::{is_orphan_head, update_piston, PistonUpdate},
+};
+
+/// The power of a wire next to a signal source, each wire further away has one less.
+const MAX_SIGNAL_POWER: u8 = 15;
+/// Wire networks larger than that are left as they are, so that a huge network cannot
+/// take too much time in a frame.
+const MAX_NETWORK_SIZE: usize = 4096;
+
+pub(crate) struct Signals {
+	/// Blocks to look at again in the next update, as if they were set again. These are around
+	/// the pistons that could not move yet (see `PistonUpdate::Blocked`).
+	retried_updates: Vec<BlockCoords>,
+}
+
+impl Signals {
+	pub(crate) fn new() -> Signals {
+		Signals { retried_updates: vec![] }
+	}
+
+	/// Must be called every frame with the blocks that were set since the last call,
+	/// returns the wires, lamps and pistons that change (if any).
+	pub(crate) fn update(
+		&mut self,
+		chunk_grid: &ChunkGrid,
+		mut block_updates: Vec<BlockCoords>,
+		block_type_table: &BlockTypeTable,
+		player_box: &AlignedBox,
+	) -> Option<EditBatch> {
+		block_updates.append(&mut self.retried_updates);
+		if block_updates.is_empty() {
+			return None;
+		}
+		let (edit_batch, retried_updates) =
+			update_signals(chunk_grid, &block_updates, block_type_table, player_box);
+		self.retried_updates = retried_updates;
+		(edit_batch.block_count() > 0).then_some(edit_batch)
+	}
+}
+
+/// Returns the blocks to set, and the block updates to retry in the next update.
+fn update_signals(
+	chunk_grid: &ChunkGrid,
+	block_updates: &[BlockCoords],
+	block_type_table: &BlockTypeTable,
+	player_box: &AlignedBox,
+) -> (EditBatch, Vec<BlockCoords>) {
+	let mut edit_batch = chunk_grid.edit_batch();
+	let wire_id = block_type_table.kinda_signal_wire_id();
+	let source_id = block_type_table.kinda_signal_source_id();
+	let lamp_id = block_type_table.kinda_signal_lamp_id();
+	let lamp_lit_id = block_type_table.kinda_signal_lamp_lit_id();
+	let piston_id = block_type_table.kinda_piston_id();
+	let type_id_at = |coords: BlockCoords| chunk_grid.get_block(coords).map(|block| block.type_id);
+	let is_wire = |coords: BlockCoords| type_id_at(coords) == Some(wire_id);
+	let is_source = |coords: BlockCoords| type_id_at(coords) == Some(source_id);
+
+	// The set blocks and their neighbors are the ones that may react.
+	let mut touched: FxHashSet<BlockCoords> = FxHashSet::default();
+	for &coords in block_updates {
+		touched.insert(coords);
+		touched.extend(face_neighbors(coords));
+	}
+
+	// The wire networks that contain the touched wires.
+	let mut network: FxHashSet<BlockCoords> = FxHashSet::default();
+	let mut to_visit: Vec<BlockCoords> =
+		touched.iter().copied().filter(|&coords| is_wire(coords)).collect();
+	while let Some(coords) = to_visit.pop() {
+		if network.len() >= MAX_NETWORK_SIZE {
+			return (edit_batch, vec![]);
+		}
+		if network.insert(coords) {
+			to_visit.extend(face_neighbors(coords).filter(|&neighbor| is_wire(neighbor)));
+		}
+	}
+
+	// The wires next to sources have the maximal power, that goes down by one from wire to wire.
+	let mut powers: FxHashMap<BlockCoords, u8> = FxHashMap::default();
+	let mut queue: VecDeque<BlockCoords> = VecDeque::new();
+	for &coords in network.iter() {
+		if face_neighbors(coords).any(is_source) {
+			powers.insert(coords, MAX_SIGNAL_POWER);
+			queue.push_back(coords);
+		}
+	}
+	while let Some(coords) = queue.pop_front() {
+		let power = powers[&coords];
+		if power <= 1 {
+			continue;
+		}
+		for neighbor in face_neighbors(coords) {
+			if network.contains(&neighbor) && !powers.contains_key(&neighbor) {
+				powers.insert(neighbor, power - 1);
+				queue.push_back(neighbor);
+			}
+		}
+	}
+
+	// The lamps and pistons around the touched blocks and around the networks.
+	let power_at = |coords: BlockCoords| match powers.get(&coords) {
+		Some(&power) => power,
+		None if network.contains(&coords) => 0,
+		None => chunk_grid
+			.get_block(coords)
+			.filter(|block| block.type_id == wire_id)
+			.map_or(0, |block| block.signal_power()),
+	};
+	let is_powered = |coords: BlockCoords| {
+		face_neighbors(coords).any(|neighbor| is_source(neighbor) || power_at(neighbor) > 0)
+	};
+	let mut lamps: FxHashSet<BlockCoords> = FxHashSet::default();
+	let mut pistons: FxHashSet<BlockCoords> = FxHashSet::default();
+	for &coords in touched.iter().chain(network.iter()) {
+		for coords in std::iter::once(coords).chain(face_neighbors(coords)) {
+			match type_id_at(coords) {
+				Some(id) if id == lamp_id || id == lamp_lit_id => {
+					lamps.insert(coords);
+				},
+				Some(id) if id == piston_id => {
+					pistons.insert(coords);
+				},
+				_ => {},
+			}
+		}
+	}
+
+	// Moving pistons set blocks that may be wires, lamps or other pistons, so when pistons move
+	// the rest waits for the next update (where the moved blocks are taken into account).
+	let mut retried_updates = vec![];
+	let mut moved_cells: FxHashSet<BlockCoords> = FxHashSet::default();
+	for &coords in touched.iter() {
+		if is_orphan_head(chunk_grid, coords, block_type_table) {
+			edit_batch.set(coords, Block::from(BlockTypeTable::AIR_ID));
+			moved_cells.insert(coords);
+		}
+	}
+	for coords in pistons {
+		if moved_cells.contains(&coords) {
+			// An other piston moved it, it will be looked at again since it was set.
+			continue;
+		}
+		// The blocks set by the pistons that moved are written to an other batch first so that
+		// they are only kept if they do not conflict with the blocks set by other pistons.
+		let mut piston_edit_batch = chunk_grid.edit_batch();
+		match update_piston(
+			chunk_grid,
+			coords,
+			is_powered(coords),
+			block_type_table,
+			player_box,
+			&mut piston_edit_batch,
+		) {
+			PistonUpdate::Unchanged => {},
+			PistonUpdate::Moved(cells) => {
+				if cells.iter().any(|cell| moved_cells.contains(cell)) {
+					retried_updates.push(coords);
+				} else {
+					moved_cells.extend(cells);
+					edit_batch.append(piston_edit_batch);
+				}
+			},
+			PistonUpdate::Blocked => retried_updates.push(coords),
+		}
+	}
+	if !moved_cells.is_empty() {
+		retried_updates.extend(block_updates.iter().copied());
+		return (edit_batch, retried_updates);
+	}
+
+	for &coords in network.iter() {
+		let power = powers.get(&coords).copied().unwrap_or(0);
+		if chunk_grid.get_block(coords).unwrap().signal_power() != power {
+			edit_batch.set(coords, Block::new_signal_wire(wire_id, power));
+		}
+	}
+
+	for coords in lamps {
+		let new_type_id = if is_powered(coords) {
+			lamp_lit_id
+		} else {
+			lamp_id
+		};
+		if type_id_at(coords) != Some(new_type_id) {
+			edit_batch.set(coords, Block::from(new_type_id));
+		}
+	}
+
+	(edit_batch, retried_updates)
+}
+
+fn face_neighbors(coords: BlockCoords) -> impl Iterator<Item = BlockCoords> {
+	OrientedAxis::all_the_six_possible_directions().map(move |direction| coords + direction.delta())
+}
@@ -1,4 +1,4 @@
-use cgmath::ElementWise;
+use cgmath::{ElementWise, MetricSpace};
 use wgpu::util::DeviceExt;
 
 use crate::{
@@ -27,14 +27,57 @@ impl SimpleLineMesh {
 		SimpleLineMesh { vertex_count: vertices.len() as u32, vertex_buffer }
 	}
 
+	/// Vertices of a rope of the given `length` hanging between `a` and `b`, it sags if it is
+	/// longer than the distance between its ends. The sagging is approximated by a parabola.
+	pub(crate) fn vertices_for_sagging_rope(
+		a: cgmath::Point3<f32>,
+		b: cgmath::Point3<f32>,
+		length: f32,
+		color: [f32; 3],
+	) -> Vec<SimpleLineVertexPod> {
+		const SEGMENT_COUNT: usize = 12;
+		let distance = a.distance(b);
+		// For a parabola of span `d` and sag `s`, the length is about `d + 8s²/(3d)`.
+		let sag = if length > distance {
+			(3.0 * distance * (length - distance) / 8.0).sqrt().min(length / 2.0)
+		} else {
+			0.0
+		};
+		let point_at = |t: f32| a + (b - a) * t - cgmath::vec3(0.0, 0.0, 4.0 * sag * t * (1.0 - t));
+		let mut vertices = Vec::with_capacity(SEGMENT_COUNT * 2);
+		for i in 0..SEGMENT_COUNT {
+			let start = point_at(i as f32 / SEGMENT_COUNT as f32);
+			let end = point_at((i + 1) as f32 / SEGMENT_COUNT as f32);
+			vertices.push(SimpleLineVertexPod { position: start.into(), color });
+			vertices.push(SimpleLineVertexPod { position: end.into(), color });
+		}
+		vertices
+	}
+
 	pub(crate) fn from_aligned_box(
 		device: &wgpu::Device,
 		aligned_box: &AlignedBox,
 	) -> SimpleLineMesh {
+		SimpleLineMesh::from_aligned_box_with_color(device, aligned_box, [1.0, 1.0, 1.0])
+	}
+
+	pub(crate) fn from_aligned_box_with_color(
+		device: &wgpu::Device,
+		aligned_box: &AlignedBox,
+		color: [f32; 3],
+	) -> SimpleLineMesh {
+		let vertices = SimpleLineMesh::vertices_for_aligned_box(aligned_box, color);
+		SimpleLineMesh::from_vertices(device, vertices)
+	}
+
+	/// Vertices of the edges of the given box.
+	pub(crate) fn vertices_for_aligned_box(
+		aligned_box: &AlignedBox,
+		color: [f32; 3],
+	) -> Vec<SimpleLineVertexPod> {
 		// NO EARLY OPTIMIZATION
 		// This shall remain in an unoptimized, unfactorized and flexible state for now!
 
-		let color = [1.0, 1.0, 1.0];
 		let mut vertices = Vec::new();
 		// A---B  +--->   The L square and the H square are horizontal.
 		// |   |  |   X+  L has lower value of Z coord.
@@ -74,7 +117,7 @@ impl SimpleLineMesh {
 		vertices.push(SimpleLineVertexPod { position: ch.into(), color });
 		vertices.push(SimpleLineVertexPod { position: dl.into(), color });
 		vertices.push(SimpleLineVertexPod { position: dh.into(), color });
-		SimpleLineMesh::from_vertices(device, vertices)
+		vertices
 	}
 
 	/// The `side_offset` parameter moves the generated rectangle along its normal.
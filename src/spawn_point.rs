@@ -0,0 +1,79 @@
+//! Finding where the player appears in a new world, so that it is on some ground
+//! with free space above instead of in the middle of rock or above the void.
+
+use std::sync::Arc;
+
+use fxhash::FxHashMap;
+
+use crate::{
+	block_types::{BlockType, BlockTypeTable},
+	chunk_blocks::ChunkBlocks,
+	coords::{BlockCoords, ChunkCoords, ChunkCoordsSpan, ChunkDimensions},
+	entities::IdGenerator,
+	world_gen::WorldGenerator,
+};
+
+/// How far from the origin (horizontally, in chunks) is the search allowed to go.
+const MAX_CHUNK_DISTANCE: i32 = 2;
+/// Generating chunks takes time, the search gives up after generating that many chunks
+/// (which can happen in worlds that are mostly empty).
+const MAX_GENERATED_CHUNKS: usize = 120;
+/// The vertical range (in blocks) where the ground is looked for, from top to bottom
+/// (so that the player appears on top of things, and not in some cave).
+const SEARCH_Z_TOP: i32 = 100;
+const SEARCH_Z_BOTTOM: i32 = -40;
+/// Number of non-solid blocks needed above the ground, a bit more than the player height.
+const FREE_SPACE_HEIGHT: i32 = 3;
+
+/// Generates the chunks near the origin (with the given generator, so the terrain and the
+/// structures are what the world will have there) and looks for the closest column where
+/// there is ground to stand on with free space above it (not in water either).
+/// Returns the coords of the block to stand on.
+pub(crate) fn find_safe_spawn_ground(
+	world_generator: &Arc<dyn WorldGenerator + Sync + Send>,
+	block_type_table: &Arc<BlockTypeTable>,
+	id_generator: &IdGenerator,
+	cd: ChunkDimensions,
+) -> Option<BlockCoords> {
+	let mut generated_chunks: FxHashMap<ChunkCoords, ChunkBlocks> = FxHashMap::default();
+	let mut is_free_and_is_opaque = |coords: BlockCoords| -> Option<(bool, bool)> {
+		let chunk_coords = cd.world_coords_to_containing_chunk_coords(coords);
+		if !generated_chunks.contains_key(&chunk_coords)
+			&& generated_chunks.len() >= MAX_GENERATED_CHUNKS
+		{
+			return None;
+		}
+		let chunk_blocks = generated_chunks.entry(chunk_coords).or_insert_with(|| {
+			let coords_span = ChunkCoordsSpan { cd, chunk_coords };
+			world_generator
+				.generate_chunk_blocks_and_entities(coords_span, block_type_table, id_generator)
+				.0
+		});
+		let block_type = block_type_table.get(chunk_blocks.get(coords).unwrap().type_id).unwrap();
+		let is_liquid = matches!(block_type, BlockType::Liquid { .. });
+		Some((
+			!block_type.is_opaque() && !is_liquid,
+			block_type.is_opaque(),
+		))
+	};
+
+	// Columns ordered by distance to the origin.
+	let max_distance = MAX_CHUNK_DISTANCE * cd.edge;
+	let mut columns: Vec<cgmath::Point2<i32>> = (-max_distance..=max_distance)
+		.flat_map(|x| (-max_distance..=max_distance).map(move |y| cgmath::point2(x, y)))
+		.collect();
+	columns.sort_by_key(|column| column.x * column.x + column.y * column.y);
+
+	for column in columns {
+		let mut free_above = 0;
+		for z in (SEARCH_Z_BOTTOM..=SEARCH_Z_TOP).rev() {
+			let coords = cgmath::point3(column.x, column.y, z);
+			let (free, opaque) = is_free_and_is_opaque(coords)?;
+			if opaque && free_above >= FREE_SPACE_HEIGHT {
+				return Some(coords);
+			}
+			free_above = if free { free_above + 1 } else { 0 };
+		}
+	}
+	None
+}
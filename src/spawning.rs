@@ -0,0 +1,330 @@
+//! Creature spawning and despawning.
+//!
+//! Every now and then, the spawner tries to spawn creatures in the loaded world around the player,
+//! following spawn rules (on which surface, at which light level, at which altitude, in which
+//! biome) and population caps. Naturally spawned creatures that end up far from the player
+//! (or in bright light for the hostile ones) are despawned, the other entities (placed by
+//! the player, generated with the world, etc.) are persistent.
+
+use std::{f32::consts::TAU, ops::RangeInclusive, sync::Arc};
+
+use cgmath::MetricSpace;
+use fxhash::FxHashSet;
+use rand::{thread_rng, Rng};
+
+use crate::{
+	block_types::{BlockTypeId, BlockTypeTable},
+	chunks::ChunkGrid,
+	coords::{iter_3d_cube_center_radius, BlockCoords, ChunkCoords},
+	entities::{Entity, Id as EntityId, IdGenerator},
+	entity_parts::PartTables,
+	saves::Save,
+	world_gen::{Biome, WorldGenerator},
+};
+
+/// Time (in seconds) between two rounds of spawning attempts (and despawning checks).
+const SPAWN_ROUND_INTERVAL: f32 = 1.0;
+/// Number of random positions tried at each spawning round.
+const SPAWN_ATTEMPTS_PER_ROUND: usize = 4;
+/// Creatures spawn at a horizontal distance from the player in that range (in blocks),
+/// not too close so that they do not pop into existence right in front of the player.
+const SPAWN_DISTANCE_RANGE: std::ops::Range<f32> = 24.0..64.0;
+/// Maximum number of naturally spawned entities in the chunks around (and including)
+/// the chunk in which a creature would spawn.
+const POPULATION_CAP_PER_AREA: usize = 6;
+/// Maximum number of naturally spawned entities in the whole loaded world.
+const POPULATION_CAP_TOTAL: usize = 60;
+/// Naturally spawned entities further than that from the player (in blocks) may despawn
+/// (with some probability at each round).
+const DESPAWN_SOFT_DISTANCE: f32 = 80.0;
+/// Naturally spawned entities further than that from the player (in blocks) always despawn.
+const DESPAWN_HARD_DISTANCE: f32 = 128.0;
+/// Probability for a naturally spawned entity beyond the soft distance to despawn at each round.
+const DESPAWN_SOFT_PROBABILITY: f64 = 0.025;
+/// Naturally spawned entities that do not stand bright light (see `Entity::despawns_in_light`)
+/// despawn where the light level is at least that.
+const DESPAWN_LIGHT_LEVEL: u8 = 12;
+
+pub(crate) const MAX_LIGHT_LEVEL: u8 = 15;
+
+#[derive(Clone, Copy)]
+enum Creature {
+	Ball,
+	Mount,
+	Hostile,
+}
+
+impl Creature {
+	fn spawn(self, id_generator: &IdGenerator, ground: BlockCoords) -> Entity {
+		match self {
+			Creature::Ball => Entity::new_test_ball(
+				id_generator,
+				ground.map(|x| x as f32) + cgmath::vec3(0.0, 0.0, 1.0),
+				cgmath::vec3(0.0, 0.0, 0.0),
+			),
+			Creature::Mount => Entity::new_mount(
+				id_generator,
+				ground.map(|x| x as f32) + cgmath::vec3(0.0, 0.0, 1.2),
+			),
+			Creature::Hostile => Entity::new_hostile(
+				id_generator,
+				ground.map(|x| x as f32) + cgmath::vec3(0.0, 0.0, 1.5),
+			),
+		}
+		.spawned_naturally()
+	}
+}
+
+#[derive(Clone, Copy)]
+enum SpawnSurface {
+	Grass,
+	Ground,
+}
+
+impl SpawnSurface {
+	fn allows(self, block_type_table: &BlockTypeTable, type_id: BlockTypeId) -> bool {
+		match self {
+			SpawnSurface::Grass => type_id == block_type_table.kinda_grass_id(),
+			SpawnSurface::Ground => type_id == block_type_table.ground_id(),
+		}
+	}
+}
+
+/// Where and when a creature can spawn. A position must satisfy every condition.
+struct SpawnRule {
+	creature: Creature,
+	/// The block on which the creature stands.
+	surfaces: &'static [SpawnSurface],
+	/// The light level in the space where the creature appears.
+	light_levels: RangeInclusive<u8>,
+	/// The altitude (z coordinate) of the surface block, it tells apart the lowlands
+	/// from the heights.
+	altitudes: RangeInclusive<i32>,
+	/// The biomes in which the creature can spawn, `None` for anywhere. Rules with biomes
+	/// never apply in worlds whose generator has no biomes.
+	biomes: Option<&'static [Biome]>,
+	/// Relative likeliness of this rule being picked among the rules that allow a position.
+	weight: u32,
+}
+
+const SPAWN_RULES: &[SpawnRule] = &[
+	SpawnRule {
+		creature: Creature::Ball,
+		surfaces: &[SpawnSurface::Grass, SpawnSurface::Ground],
+		light_levels: 10..=MAX_LIGHT_LEVEL,
+		altitudes: -32..=120,
+		biomes: None,
+		weight: 3,
+	},
+	SpawnRule {
+		creature: Creature::Mount,
+		surfaces: &[SpawnSurface::Grass],
+		light_levels: 12..=MAX_LIGHT_LEVEL,
+		altitudes: 0..=80,
+		biomes: None,
+		weight: 1,
+	},
+	// In caves and under overhangs.
+	SpawnRule {
+		creature: Creature::Hostile,
+		surfaces: &[SpawnSurface::Grass, SpawnSurface::Ground],
+		light_levels: 0..=4,
+		altitudes: -128..=120,
+		biomes: None,
+		weight: 2,
+	},
+	// Forests are dark enough under the trees for them to lurk there too.
+	SpawnRule {
+		creature: Creature::Hostile,
+		surfaces: &[SpawnSurface::Grass],
+		light_levels: 0..=7,
+		altitudes: 0..=80,
+		biomes: Some(&[Biome::Forest]),
+		weight: 2,
+	},
+];
+
+/// There is no light propagation in the world, so the light level is estimated from how exposed
+/// to the sky the given position is. It is at maximum if there is no opaque block above it,
+/// and else depends on how many horizontal neighbors see the sky (light leaking from the sides).
+pub(crate) fn sky_light_level(chunk_grid: &ChunkGrid, coords: BlockCoords) -> u8 {
+	let sees_the_sky =
+		|coords: BlockCoords| chunk_grid.top_solid_z(coords.x, coords.y).is_none_or(|z| z < coords.z);
+	if sees_the_sky(coords) {
+		MAX_LIGHT_LEVEL
+	} else {
+		let open_neighbors = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+			.into_iter()
+			.filter(|&(x, y)| sees_the_sky(coords + cgmath::vec3(x, y, 0)))
+			.count();
+		open_neighbors as u8 * 3
+	}
+}
+
+pub(crate) struct Spawner {
+	pub(crate) enabled: bool,
+	last_round: Option<std::time::Instant>,
+}
+
+impl Spawner {
+	pub(crate) fn new(enabled: bool) -> Spawner {
+		Spawner { enabled, last_round: None }
+	}
+
+	/// Must be called regularly with write access to the world,
+	/// a round of spawning and despawning happens from time to time.
+	#[allow(clippy::too_many_arguments)]
+	pub(crate) fn update(
+		&mut self,
+		chunk_grid: &mut ChunkGrid,
+		player_pos: cgmath::Point3<f32>,
+		block_type_table: &Arc<BlockTypeTable>,
+		world_generator: &dyn WorldGenerator,
+		id_generator: &IdGenerator,
+		save: Option<&Arc<Save>>,
+		part_tables: &PartTables,
+	) {
+		let round_is_due = self
+			.last_round
+			.is_none_or(|last_round| last_round.elapsed().as_secs_f32() >= SPAWN_ROUND_INTERVAL);
+		if !self.enabled || !round_is_due {
+			return;
+		}
+		self.last_round = Some(std::time::Instant::now());
+
+		despawn_far_or_lit_creatures(chunk_grid, player_pos, part_tables);
+
+		let mut population_total = count_naturally_spawned(chunk_grid.iter_entities());
+		for _ in 0..SPAWN_ATTEMPTS_PER_ROUND {
+			if population_total >= POPULATION_CAP_TOTAL {
+				break;
+			}
+			let spawned = try_to_spawn_somewhere(
+				chunk_grid,
+				player_pos,
+				block_type_table,
+				world_generator,
+				id_generator,
+			);
+			if let Some(entity) = spawned {
+				chunk_grid.add_entity(entity, save);
+				population_total += 1;
+			}
+		}
+	}
+}
+
+fn count_naturally_spawned<'a>(entities: impl Iterator<Item = &'a Entity>) -> usize {
+	entities.filter(|entity| !entity.is_persistent()).count()
+}
+
+fn despawn_far_or_lit_creatures(
+	chunk_grid: &mut ChunkGrid,
+	player_pos: cgmath::Point3<f32>,
+	part_tables: &PartTables,
+) {
+	let lit_ids: FxHashSet<EntityId> = chunk_grid
+		.iter_entities()
+		.filter(|entity| !entity.is_persistent() && entity.despawns_in_light())
+		.filter(|entity| {
+			let coords = entity.pos().map(|x| x.round() as i32);
+			sky_light_level(chunk_grid, coords) >= DESPAWN_LIGHT_LEVEL
+		})
+		.map(|entity| entity.id())
+		.collect();
+	let mut rng = thread_rng();
+	let chunk_coords_list: Vec<_> = chunk_grid.iter_chunk_with_entities_coords().collect();
+	for chunk_coords in chunk_coords_list {
+		chunk_grid.remove_entities_in_chunk_that(
+			chunk_coords,
+			|entity| {
+				let distance = entity.pos().distance(player_pos);
+				lit_ids.contains(&entity.id())
+					|| (!entity.is_persistent()
+						&& (distance > DESPAWN_HARD_DISTANCE
+							|| (distance > DESPAWN_SOFT_DISTANCE
+								&& rng.gen_bool(DESPAWN_SOFT_PROBABILITY))))
+			},
+			part_tables,
+		);
+	}
+}
+
+/// Picks a random position around the player and tries to spawn a creature there.
+fn try_to_spawn_somewhere(
+	chunk_grid: &ChunkGrid,
+	player_pos: cgmath::Point3<f32>,
+	block_type_table: &BlockTypeTable,
+	world_generator: &dyn WorldGenerator,
+	id_generator: &IdGenerator,
+) -> Option<Entity> {
+	let mut rng = thread_rng();
+	let angle = rng.gen_range(0.0..TAU);
+	let distance = rng.gen_range(SPAWN_DISTANCE_RANGE);
+	let column = player_pos + cgmath::vec3(angle.cos(), angle.sin(), 0.0) * distance;
+	let top = column.map(|x| x.round() as i32) + cgmath::vec3(0, 0, 24);
+	let ground = find_ground_with_room_above(chunk_grid, block_type_table, top, 48)?;
+
+	let chunk_coords = chunk_grid.cd().world_coords_to_containing_chunk_coords(ground);
+	if !chunk_grid.is_loaded(chunk_coords)
+		|| area_population(chunk_grid, chunk_coords) >= POPULATION_CAP_PER_AREA
+	{
+		return None;
+	}
+
+	let surface_type_id = chunk_grid.get_block(ground)?.type_id;
+	let light_level = sky_light_level(chunk_grid, ground + cgmath::vec3(0, 0, 1));
+	let biome = world_generator.biome_at(ground);
+	let allowed_rules: Vec<_> = SPAWN_RULES
+		.iter()
+		.filter(|rule| {
+			rule.surfaces.iter().any(|surface| surface.allows(block_type_table, surface_type_id))
+				&& rule.light_levels.contains(&light_level)
+				&& rule.altitudes.contains(&ground.z)
+				&& rule.biomes.is_none_or(|biomes| biome.is_some_and(|biome| biomes.contains(&biome)))
+		})
+		.collect();
+	let total_weight: u32 = allowed_rules.iter().map(|rule| rule.weight).sum();
+	if total_weight == 0 {
+		return None;
+	}
+	let mut picked_weight = rng.gen_range(0..total_weight);
+	for rule in allowed_rules {
+		if picked_weight < rule.weight {
+			return Some(rule.creature.spawn(id_generator, ground));
+		}
+		picked_weight -= rule.weight;
+	}
+	unreachable!()
+}
+
+fn area_population(chunk_grid: &ChunkGrid, chunk_coords: ChunkCoords) -> usize {
+	iter_3d_cube_center_radius(chunk_coords, 2)
+		.filter_map(|neighbor_coords| chunk_grid.iter_entities_in_chunk(neighbor_coords))
+		.map(count_naturally_spawned)
+		.sum()
+}
+
+/// Looks down from `start` for an opaque block with two non-opaque blocks above it
+/// (room for a creature to stand). Gives up when reaching a part of the world that is not loaded.
+fn find_ground_with_room_above(
+	chunk_grid: &ChunkGrid,
+	block_type_table: &BlockTypeTable,
+	start: BlockCoords,
+	max_depth: i32,
+) -> Option<BlockCoords> {
+	let mut non_opaque_streak = 0;
+	for depth in 0..max_depth {
+		let coords = start - cgmath::vec3(0, 0, depth);
+		let block = chunk_grid.get_block(coords)?;
+		if block_type_table.get(block.type_id).unwrap().is_opaque() {
+			if non_opaque_streak >= 2 {
+				return Some(coords);
+			}
+			non_opaque_streak = 0;
+		} else {
+			non_opaque_streak += 1;
+		}
+	}
+	None
+}
@@ -0,0 +1,150 @@
+//! Snow and fallen leaves piling up on the exposed surfaces of the world around the player.
+//!
+//! Every frame, some random columns of blocks around the player are looked at. If it is snowing,
+//! a layer of snow is added on top of the highest opaque block of the column (as given by the
+//! heightmap, so only the surfaces under the open sky get snow). Fallen leaves pile up (slower,
+//! and not as high) on the ground under the leaves of trees. See `BlockType::Layer`.
+
+use std::time::Duration;
+
+use rand::{thread_rng, Rng};
+
+use crate::{
+	block_types::{BlockTypeId, BlockTypeTable, MAX_LAYER_COUNT},
+	chunk_blocks::Block,
+	chunks::{ChunkGrid, EditBatch},
+	coords::BlockCoords,
+};
+
+/// Layers are only added in columns at most that far from the player (horizontally, in blocks).
+const SURFACE_LAYERS_RADIUS: i32 = 48;
+/// Number of columns that get a layer of snow per second while it is snowing.
+const SNOW_ATTEMPTS_PER_SECOND: f32 = 60.0;
+/// Number of columns that are looked at per second to maybe get some fallen leaves.
+const FALLEN_LEAVES_ATTEMPTS_PER_SECOND: f32 = 2.0;
+/// Fallen leaves do not pile up higher than that (in layers).
+const MAX_FALLEN_LEAVES_COUNT: u8 = 2;
+/// Fallen leaves can fall from leaves at most that far above the ground (in blocks).
+const MAX_FALLEN_LEAVES_FALL_HEIGHT: i32 = 16;
+
+pub(crate) struct SurfaceLayers {
+	pub(crate) snowing: bool,
+	/// The fractions of attempts that were not made yet, they add up over the frames.
+	pending_snow_attempts: f32,
+	pending_fallen_leaves_attempts: f32,
+}
+
+impl SurfaceLayers {
+	pub(crate) fn new(snowing: bool) -> SurfaceLayers {
+		SurfaceLayers {
+			snowing,
+			pending_snow_attempts: 0.0,
+			pending_fallen_leaves_attempts: 0.0,
+		}
+	}
+
+	/// Must be called every frame (with an up-to-date heightmap, see `ChunkGrid::update_heightmap`),
+	/// returns the layers to add to the world (if any).
+	pub(crate) fn update(
+		&mut self,
+		chunk_grid: &ChunkGrid,
+		player_pos: cgmath::Point3<f32>,
+		block_type_table: &BlockTypeTable,
+		dt: Duration,
+		simulation_distance: Option<f32>,
+	) -> Option<EditBatch> {
+		let mut edit_batch = chunk_grid.edit_batch();
+		let player_column = player_pos.map(|x| x.round() as i32);
+		// The simulation distance may come from the config file, where it is not validated.
+		let radius = simulation_distance.map_or(SURFACE_LAYERS_RADIUS, |distance| {
+			SURFACE_LAYERS_RADIUS.min(distance as i32).max(0)
+		});
+
+		if self.snowing {
+			self.pending_snow_attempts += SNOW_ATTEMPTS_PER_SECOND * dt.as_secs_f32();
+		}
+		while self.pending_snow_attempts >= 1.0 {
+			self.pending_snow_attempts -= 1.0;
+			let (x, y) = random_column_around(player_column, radius);
+			let Some(top_z) = chunk_grid.top_solid_z(x, y) else {
+				continue;
+			};
+			let coords = cgmath::point3(x, y, top_z + 1);
+			let snow_id = block_type_table.kinda_snow_layer_id();
+			if let Some(block) = add_layer(chunk_grid, coords, snow_id, MAX_LAYER_COUNT) {
+				edit_batch.set(coords, block);
+			}
+		}
+
+		self.pending_fallen_leaves_attempts += FALLEN_LEAVES_ATTEMPTS_PER_SECOND * dt.as_secs_f32();
+		while self.pending_fallen_leaves_attempts >= 1.0 {
+			self.pending_fallen_leaves_attempts -= 1.0;
+			let (x, y) = random_column_around(player_column, radius);
+			let Some(top_z) = chunk_grid.top_solid_z(x, y) else {
+				continue;
+			};
+			let top_coords = cgmath::point3(x, y, top_z);
+			let Some(ground_coords) = ground_under_leaves(chunk_grid, top_coords, block_type_table)
+			else {
+				continue;
+			};
+			let coords = ground_coords + cgmath::vec3(0, 0, 1);
+			let leaves_id = block_type_table.kinda_fallen_leaves_id();
+			if let Some(block) = add_layer(chunk_grid, coords, leaves_id, MAX_FALLEN_LEAVES_COUNT) {
+				edit_batch.set(coords, block);
+			}
+		}
+
+		(edit_batch.block_count() > 0).then_some(edit_batch)
+	}
+}
+
+fn random_column_around(center: cgmath::Point3<i32>, radius: i32) -> (i32, i32) {
+	let mut rng = thread_rng();
+	(
+		center.x + rng.gen_range(-radius..=radius),
+		center.y + rng.gen_range(-radius..=radius),
+	)
+}
+
+/// The block to put at the given coords to add one layer of the given layer block type there,
+/// if there is air or some layers of that type (but not too many already).
+fn add_layer(
+	chunk_grid: &ChunkGrid,
+	coords: BlockCoords,
+	layer_type_id: BlockTypeId,
+	max_layer_count: u8,
+) -> Option<Block> {
+	let block = chunk_grid.get_block(coords)?;
+	if block.type_id == BlockTypeTable::AIR_ID {
+		Some(Block::new_layer(layer_type_id, 1))
+	} else if block.type_id == layer_type_id && block.layer_count() < max_layer_count {
+		Some(Block::new_layer(layer_type_id, block.layer_count() + 1))
+	} else {
+		None
+	}
+}
+
+/// If the given block at the top of a column is a leaf block, then finds the ground below the
+/// leaves (the first opaque block below that is not part of a tree), if it is not too far below.
+fn ground_under_leaves(
+	chunk_grid: &ChunkGrid,
+	top_coords: BlockCoords,
+	block_type_table: &BlockTypeTable,
+) -> Option<BlockCoords> {
+	let leaf_id = block_type_table.kinda_leaf_id();
+	if chunk_grid.get_block(top_coords)?.type_id != leaf_id {
+		return None;
+	}
+	for depth in 1..=MAX_FALLEN_LEAVES_FALL_HEIGHT {
+		let coords = top_coords - cgmath::vec3(0, 0, depth);
+		let block = chunk_grid.get_block(coords)?;
+		if !block_type_table.get(block.type_id).unwrap().is_opaque() || block.type_id == leaf_id {
+			continue;
+		}
+		// Leaves fall around the trunk, not on it.
+		let is_ground = block.type_id != block_type_table.kinda_wood_id();
+		return is_ground.then_some(coords);
+	}
+	None
+}
@@ -11,11 +11,14 @@ use crate::{
 	chunk_blocks::{ChunkBlocks, ChunkCullingInfo},
 	chunk_loading::DataForChunkLoading,
 	chunk_meshing::{ChunkMesh, DataForChunkMeshing},
-	chunks::ChunkGrid,
-	coords::{ChunkCoords, ChunkCoordsSpan, ChunkDimensions},
+	chunks::{ChunkGrid, ChunkGridSnapshot},
+	coords::{AlignedBox, ChunkCoords, ChunkCoordsSpan, ChunkDimensions},
 	entities::{ChunkEntities, EntitiesPhysicsStepResult, ForPartManipulation, IdGenerator},
+	pregen::DataForChunkPregen,
 	skybox::SkyboxFaces,
 	threadpool::ThreadPool,
+	trace,
+	world_stats::{DataForWorldStats, WorldStats},
 };
 
 /// The main-thread reciever for the results of a task that was given to a worker thread.
@@ -24,11 +27,16 @@ pub(crate) enum WorkerTask {
 		ChunkCoords,
 		std::sync::mpsc::Receiver<(ChunkBlocks, ChunkCullingInfo, Option<ChunkEntities>)>,
 	),
-	MeshChunk(ChunkCoords, std::sync::mpsc::Receiver<Option<ChunkMesh>>),
+	MeshChunk(
+		ChunkCoords,
+		std::sync::mpsc::Receiver<(Option<ChunkMesh>, ChunkGridSnapshot)>,
+	),
 	PhysicsStepOnSomeEntities(std::sync::mpsc::Receiver<EntitiesPhysicsStepResult>),
 	/// The counter at the end is the number of faces already finished.
 	PaintNewSkybox(std::sync::mpsc::Receiver<SkyboxFaces>, Arc<AtomicI32>),
 	GenerateAtlas(std::sync::mpsc::Receiver<Atlas>),
+	ComputeWorldStats(std::sync::mpsc::Receiver<WorldStats>),
+	PregenerateChunk(ChunkCoords, std::sync::mpsc::Receiver<()>),
 }
 
 pub(crate) struct WorkerTasksManager {
@@ -62,10 +70,18 @@ impl WorkerTasksManager {
 		let (sender, receiver) = std::sync::mpsc::channel();
 		self.current_tasks.push(WorkerTask::MeshChunk(chunk_coords, receiver));
 		pool.enqueue_task(Box::new(move || {
-			let vertices = data_for_chunk_meshing.generate_mesh_vertices();
+			let _span = trace::span("chunk_meshing");
+			let meshing_start = std::time::Instant::now();
+			let snapshot = data_for_chunk_meshing.snapshot().clone();
+			let offset = data_for_chunk_meshing.mesh_offset();
+			let (vertices, lights) = data_for_chunk_meshing.generate_mesh_vertices_and_lights();
+			// A light emitting block with no visible face does not light much anyway.
 			let non_empty_mesh = !vertices.is_empty();
-			let mesh = non_empty_mesh.then(|| ChunkMesh::from_vertices(&device, vertices));
-			let _ = sender.send(mesh);
+			let mesh = non_empty_mesh.then(|| {
+				let meshing_duration = meshing_start.elapsed();
+				ChunkMesh::from_vertices_and_lights(&device, vertices, offset, lights, meshing_duration)
+			});
+			let _ = sender.send((mesh, snapshot));
 		}));
 	}
 
@@ -96,11 +112,13 @@ impl WorkerTasksManager {
 			receiver,
 		));
 		pool.enqueue_task(Box::new(move || {
+			let _span = trace::span("chunk_loading");
 			let DataForChunkLoading {
 				was_already_generated_before,
 				world_generator,
 				block_type_table,
 				save,
+				chunk_cache,
 				cd,
 			} = data_for_chunk_loading;
 			let coords_span = ChunkCoordsSpan { cd, chunk_coords };
@@ -130,11 +148,18 @@ impl WorkerTasksManager {
 			// If the entities were already generated, then they have been saved, and we must not
 			// generate then an other time to avoid duplicating them.
 			let keep_generated_entities = !was_already_generated_before;
-			// If the blocks were not saved, then we have to generate to get the blocks.
-			let generation_needed = blocks_from_save.is_none() || keep_generated_entities;
+			// If the blocks were not saved, then they may still be in the cache (which is only
+			// worth checking if there is no need to generate the entities anyway).
+			let blocks_from_save_or_cache = blocks_from_save.or_else(|| {
+				let chunk_cache = chunk_cache.as_ref().filter(|_| !keep_generated_entities)?;
+				chunk_cache.load(coords_span)
+			});
+			// If the blocks were not saved (nor cached), then we have to generate to get the blocks.
+			let generation_needed = blocks_from_save_or_cache.is_none() || keep_generated_entities;
 
 			// Now the generation happens if needed.
 			let blocks_and_entities_from_gen = generation_needed.then(|| {
+				let _span = trace::span("chunk_generation");
 				world_generator.generate_chunk_blocks_and_entities(
 					coords_span,
 					&block_type_table,
@@ -149,7 +174,7 @@ impl WorkerTasksManager {
 
 			// Sorting what we got. At the end, we must have one `ChunkBlocks`
 			// and one `Option<ChunkEntities>` (which should be `None` if empty).
-			let blocks = blocks_from_save.or(blocks_from_gen).unwrap();
+			let blocks = blocks_from_save_or_cache.or(blocks_from_gen).unwrap();
 			let entities = match (entities_from_save, entities_from_gen) {
 				(Some(entities_save), Some(entities_gen)) => Some(entities_save.merged(entities_gen)),
 				(Some(entities), _) | (_, Some(entities)) => Some(entities),
@@ -163,15 +188,22 @@ impl WorkerTasksManager {
 		}));
 	}
 
+	/// Also `true` for chunks being pregenerated, they cannot be loaded before that is done.
 	pub(crate) fn is_being_loaded(&self, chunk_coords: ChunkCoords) -> bool {
 		self.current_tasks.iter().any(|worker_task| match worker_task {
-			WorkerTask::LoadChunkBlocksAndEntities(chunk_coords_uwu, ..) => {
-				*chunk_coords_uwu == chunk_coords
-			},
+			WorkerTask::LoadChunkBlocksAndEntities(chunk_coords_uwu, ..)
+			| WorkerTask::PregenerateChunk(chunk_coords_uwu, ..) => *chunk_coords_uwu == chunk_coords,
 			_ => false,
 		})
 	}
 
+	pub(crate) fn is_loading_any_chunk(&self) -> bool {
+		self
+			.current_tasks
+			.iter()
+			.any(|worker_task| matches!(worker_task, WorkerTask::LoadChunkBlocksAndEntities(..)))
+	}
+
 	pub(crate) fn how_many_loading_compatible_workers_available(
 		&self,
 		pool: &mut ThreadPool,
@@ -181,6 +213,62 @@ impl WorkerTasksManager {
 			.saturating_sub(self.number_of_workers_that_cannot_do_loading)
 	}
 
+	pub(crate) fn run_chunk_pregen_task(
+		&mut self,
+		pool: &mut ThreadPool,
+		chunk_coords: ChunkCoords,
+		data_for_chunk_pregen: DataForChunkPregen,
+		id_generator: Arc<IdGenerator>,
+	) {
+		let (sender, receiver) = std::sync::mpsc::channel();
+		self.current_tasks.push(WorkerTask::PregenerateChunk(chunk_coords, receiver));
+		pool.enqueue_task(Box::new(move || {
+			let _span = trace::span("chunk_pregen");
+			data_for_chunk_pregen.pregenerate(chunk_coords, &id_generator);
+			let _ = sender.send(());
+		}));
+	}
+
+	pub(crate) fn how_many_chunks_being_pregenerated(&self) -> usize {
+		self
+			.current_tasks
+			.iter()
+			.filter(|worker_task| matches!(worker_task, WorkerTask::PregenerateChunk(..)))
+			.count()
+	}
+
+	/// The tasks which results end up in the save (directly or via the world),
+	/// quitting waits for them before saving (see `save_and_quit`).
+	pub(crate) fn how_many_tasks_with_results_to_save(&self) -> usize {
+		self
+			.current_tasks
+			.iter()
+			.filter(|worker_task| {
+				matches!(
+					worker_task,
+					WorkerTask::LoadChunkBlocksAndEntities(..)
+						| WorkerTask::PhysicsStepOnSomeEntities(..)
+						| WorkerTask::PaintNewSkybox(..)
+						| WorkerTask::GenerateAtlas(..)
+						| WorkerTask::PregenerateChunk(..)
+				)
+			})
+			.count()
+	}
+
+	pub(crate) fn run_world_stats_task(
+		&mut self,
+		pool: &mut ThreadPool,
+		data_for_world_stats: DataForWorldStats,
+	) {
+		let (sender, receiver) = std::sync::mpsc::channel();
+		self.current_tasks.push(WorkerTask::ComputeWorldStats(receiver));
+		pool.enqueue_task(Box::new(move || {
+			let _span = trace::span("world_stats");
+			let _ = sender.send(data_for_world_stats.compute());
+		}));
+	}
+
 	#[allow(clippy::too_many_arguments)]
 	pub(crate) fn run_physics_step_on_some_entities(
 		&mut self,
@@ -192,6 +280,7 @@ impl WorkerTasksManager {
 		entity_physics_dt: std::time::Duration,
 		part_manipulation: ForPartManipulation,
 		id_generator: &Arc<IdGenerator>,
+		player_box: Option<AlignedBox>,
 	) {
 		let (sender, receiver) = std::sync::mpsc::channel();
 		self.current_tasks.push(WorkerTask::PhysicsStepOnSomeEntities(receiver));
@@ -199,8 +288,10 @@ impl WorkerTasksManager {
 		let block_type_table = Arc::clone(block_type_table);
 		let id_generator = Arc::clone(id_generator);
 		pool.enqueue_task(Box::new(move || {
+			let _span = trace::span("entities_physics");
 			let mut next_entities_map: FxHashMap<ChunkCoords, ChunkEntities> = HashMap::default();
 			let mut actions_on_world = vec![];
+			let mut player_hits = vec![];
 			for chunk_coords in chunk_coords_list.into_iter() {
 				ChunkEntities::apply_one_physics_step(
 					chunk_coords,
@@ -212,10 +303,12 @@ impl WorkerTasksManager {
 					entity_physics_dt,
 					&part_manipulation,
 					&id_generator,
+					player_box.as_ref(),
+					&mut player_hits,
 				);
 			}
 			let entities_physics_step_result =
-				EntitiesPhysicsStepResult { next_entities_map, actions_on_world };
+				EntitiesPhysicsStepResult { next_entities_map, actions_on_world, player_hits };
 			let _ = sender.send(entities_physics_step_result);
 		}));
 	}
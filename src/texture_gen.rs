@@ -1,8 +1,12 @@
-use image::{GenericImage, GenericImageView, ImageBuffer, Rgba, SubImage};
+use image::{GenericImage, GenericImageView, ImageBuffer, Luma, Rgba, SubImage};
 
-use crate::noise::OctavedNoise;
+use cgmath::{InnerSpace, MetricSpace};
+
+use crate::{block_types::ToolKind, noise::OctavedNoise};
 
 pub(crate) type View<'a> = SubImage<&'a mut ImageBuffer<Rgba<u8>, Vec<u8>>>;
+/// View on the emissive mask of a texture (see `Atlas::emissive_mask`).
+pub(crate) type EmissiveView<'a> = SubImage<&'a mut image::GrayImage>;
 
 pub(crate) struct TextureViewWrapping<'a> {
 	view: View<'a>,
@@ -279,6 +283,554 @@ pub(crate) fn default_ground(view: View, world_seed: i32, texture_seed: i32) {
 	texture.apply_initializer(&initializer, world_seed, texture_seed);
 }
 
+/// A flower (stem, leaves and petals of the given color) on a transparent background,
+/// for decoration blocks. The row 0 is the bottom (where it touches the ground).
+pub(crate) fn decoration_flower(
+	mut view: View,
+	world_seed: i32,
+	texture_seed: i32,
+	petal_color: Color,
+) {
+	let noise = OctavedNoise::new(1, vec![world_seed, texture_seed]);
+	let shaded = |color: Color, coords: cgmath::Point2<i32>, how_much: f32| -> Color {
+		let noise_value = noise.sample_i2d_1d(coords, &[1]) * 2.0 - 1.0;
+		let mut color = color;
+		for i in 0..3 {
+			color.0[i] = (color.0[i] as f32 + noise_value * how_much).clamp(0.0, 255.0) as u8;
+		}
+		color
+	};
+	let stem_color = Color::from([60, 150, 40, 255]);
+	let heart_color = Color::from([230, 190, 60, 255]);
+	let heart = cgmath::point2(7.5, 11.5);
+	let (w, h) = view.dimensions();
+	for y in 0..h {
+		for x in 0..w {
+			let coords = cgmath::point2(x as i32, y as i32);
+			let distance_to_heart = cgmath::point2(x as f32, y as f32).distance(heart);
+			let is_stem = (x == 7 || x == 8) && y < 11;
+			let is_leaf = (y == 4 && (5..7).contains(&x)) || (y == 6 && (9..11).contains(&x));
+			let color = if distance_to_heart < 1.3 {
+				shaded(heart_color, coords, 20.0)
+			} else if distance_to_heart < 3.6 {
+				shaded(petal_color, coords, 35.0)
+			} else if is_stem || is_leaf {
+				shaded(stem_color, coords, 25.0)
+			} else {
+				Color::from([0, 0, 0, 0])
+			};
+			view.put_pixel(x, y, color);
+		}
+	}
+}
+
+/// A mushroom (a stem with a spotted cap) on a transparent background, for decoration blocks.
+/// The row 0 is the bottom (where it touches the ground).
+pub(crate) fn decoration_mushroom(mut view: View, world_seed: i32, texture_seed: i32) {
+	let noise = OctavedNoise::new(1, vec![world_seed, texture_seed]);
+	let (w, h) = view.dimensions();
+	for y in 0..h {
+		for x in 0..w {
+			let coords = cgmath::point2(x as i32, y as i32);
+			let noise_value = noise.sample_i2d_1d(coords, &[1]);
+			let cap_x = (x as f32 - 7.5) / 6.5;
+			let cap_y = (y as f32 - 5.5) / 4.5;
+			let is_cap = y >= 6 && cap_x * cap_x + cap_y * cap_y < 1.0;
+			let is_stem = (6..10).contains(&x) && y < 7;
+			let color = if is_cap {
+				if noise.sample_i2d_1d(coords / 2, &[2]) < 0.2 {
+					[240, 235, 225, 255]
+				} else {
+					[(180.0 + noise_value * 50.0) as u8, 30, 25, 255]
+				}
+			} else if is_stem {
+				let shade = (215.0 + noise_value * 25.0) as u8;
+				[shade, shade - 15, shade - 40, 255]
+			} else {
+				[0, 0, 0, 0]
+			};
+			view.put_pixel(x, y, Color::from(color));
+		}
+	}
+}
+
+/// Snow, mostly white with a bit of blue in the hollows, for layer blocks.
+pub(crate) fn layer_snow(mut view: View, world_seed: i32, texture_seed: i32) {
+	let noise = OctavedNoise::new(2, vec![world_seed, texture_seed]);
+	let (w, h) = view.dimensions();
+	for y in 0..h {
+		for x in 0..w {
+			let coords = cgmath::point2(x as i32, y as i32);
+			let noise_value = noise.sample_i2d_1d(coords, &[1]);
+			let shade = (230.0 + noise_value * 25.0) as u8;
+			let sparkle = noise.sample_i2d_1d(coords, &[2]) > 0.93;
+			let color = if sparkle {
+				[255, 255, 255, 255]
+			} else {
+				[shade - 10, shade - 5, shade, 255]
+			};
+			view.put_pixel(x, y, Color::from(color));
+		}
+	}
+}
+
+/// Fallen leaves, a mess of green, yellow and brown leaves, for layer blocks.
+pub(crate) fn layer_fallen_leaves(mut view: View, world_seed: i32, texture_seed: i32) {
+	let noise = OctavedNoise::new(1, vec![world_seed, texture_seed]);
+	let leaf_colors = [[70, 130, 30], [170, 150, 40], [140, 80, 30], [100, 60, 25]];
+	let (w, h) = view.dimensions();
+	for y in 0..h {
+		for x in 0..w {
+			let coords = cgmath::point2(x as i32, y as i32);
+			// Pixels are grouped in small clumps so that it looks like leaves and not like noise.
+			let leaf_value = noise.sample_i2d_1d(coords / 2, &[1]);
+			let [r, g, b] = leaf_colors[((leaf_value * 4.0) as usize).min(3)];
+			let shade = 0.75 + noise.sample_i2d_1d(coords, &[2]) * 0.35;
+			let color = [
+				(r as f32 * shade) as u8,
+				(g as f32 * shade) as u8,
+				(b as f32 * shade) as u8,
+				255,
+			];
+			view.put_pixel(x, y, Color::from(color));
+		}
+	}
+}
+
+/// Ice, smooth light blue with a few white diagonal streaks.
+pub(crate) fn block_ice(mut view: View, world_seed: i32, texture_seed: i32) {
+	let noise = OctavedNoise::new(2, vec![world_seed, texture_seed]);
+	let (w, h) = view.dimensions();
+	for y in 0..h {
+		for x in 0..w {
+			let coords = cgmath::point2(x as i32, y as i32);
+			let shade = (noise.sample_i2d_1d(coords / 4, &[1]) * 25.0) as u8;
+			let streak = (x + y) % 7 == 0 && noise.sample_i2d_1d(coords / 3, &[2]) > 0.4;
+			let color = if streak {
+				[230, 242, 255, 255]
+			} else {
+				[150 + shade, 195 + shade, 235, 255]
+			};
+			view.put_pixel(x, y, Color::from(color));
+		}
+	}
+}
+
+/// Sand, pale yellow grains.
+pub(crate) fn block_sand(mut view: View, world_seed: i32, texture_seed: i32) {
+	let noise = OctavedNoise::new(1, vec![world_seed, texture_seed]);
+	let (w, h) = view.dimensions();
+	for y in 0..h {
+		for x in 0..w {
+			let coords = cgmath::point2(x as i32, y as i32);
+			let shade = 0.85 + noise.sample_i2d_1d(coords, &[1]) * 0.2;
+			let dark_grain = noise.sample_i2d_1d(coords, &[2]) > 0.9;
+			let shade = if dark_grain { shade * 0.8 } else { shade };
+			let color = [
+				(230.0 * shade) as u8,
+				(205.0 * shade) as u8,
+				(140.0 * shade) as u8,
+				255,
+			];
+			view.put_pixel(x, y, Color::from(color));
+		}
+	}
+}
+
+/// Dirt, dark brown with a few lighter pebbles.
+pub(crate) fn block_dirt(mut view: View, world_seed: i32, texture_seed: i32) {
+	let noise = OctavedNoise::new(2, vec![world_seed, texture_seed]);
+	let (w, h) = view.dimensions();
+	for y in 0..h {
+		for x in 0..w {
+			let coords = cgmath::point2(x as i32, y as i32);
+			let shade = 0.8 + noise.sample_i2d_1d(coords / 2, &[1]) * 0.3;
+			let pebble = noise.sample_i2d_1d(coords, &[2]) > 0.92;
+			let color = if pebble {
+				[150, 130, 110, 255]
+			} else {
+				[
+					(120.0 * shade) as u8,
+					(80.0 * shade) as u8,
+					(50.0 * shade) as u8,
+					255,
+				]
+			};
+			view.put_pixel(x, y, Color::from(color));
+		}
+	}
+}
+
+/// Pressure plate, a grey stone slab with a raised square in the middle.
+pub(crate) fn block_pressure_plate(mut view: View, world_seed: i32, texture_seed: i32) {
+	let noise = OctavedNoise::new(1, vec![world_seed, texture_seed]);
+	let (w, h) = view.dimensions();
+	for y in 0..h {
+		for x in 0..w {
+			let coords = cgmath::point2(x as i32, y as i32);
+			let shade = noise.sample_i2d_1d(coords, &[1]) * 15.0;
+			let distance_to_border = x.min(y).min(w - 1 - x).min(h - 1 - y);
+			let base = match distance_to_border {
+				0 | 1 => 95.0,
+				2 => 60.0,
+				3 => 165.0,
+				_ => 135.0,
+			};
+			let value = (base + shade) as u8;
+			view.put_pixel(x, y, Color::from([value, value, value + 5, 255]));
+		}
+	}
+}
+
+/// Wooden planks in a darker frame, the color of the door and trapdoor textures.
+fn planks_in_frame_color(
+	noise: &OctavedNoise,
+	x: u32,
+	y: u32,
+	w: u32,
+	h: u32,
+	vertical_planks: bool,
+) -> [u8; 3] {
+	let coords = cgmath::point2(x as i32, y as i32);
+	let along = if vertical_planks { x } else { y };
+	let is_frame = x == 0 || y == 0 || x == w - 1 || y == h - 1;
+	let is_gap_between_planks = along % 5 == 0;
+	let shade = 0.85 + noise.sample_i2d_1d(coords, &[1]) * 0.15;
+	let shade = if is_frame {
+		shade * 0.55
+	} else if is_gap_between_planks {
+		shade * 0.75
+	} else {
+		shade
+	};
+	[
+		(150.0 * shade) as u8,
+		(100.0 * shade) as u8,
+		(55.0 * shade) as u8,
+	]
+}
+
+/// Door, vertical wooden planks in a frame with a metal knob.
+pub(crate) fn block_door(mut view: View, world_seed: i32, texture_seed: i32) {
+	let noise = OctavedNoise::new(1, vec![world_seed, texture_seed]);
+	let (w, h) = view.dimensions();
+	for y in 0..h {
+		for x in 0..w {
+			let is_knob = (x == 11 || x == 12) && (y == 7 || y == 8);
+			let [r, g, b] = if is_knob {
+				[70, 70, 75]
+			} else {
+				planks_in_frame_color(&noise, x, y, w, h, true)
+			};
+			view.put_pixel(x, y, Color::from([r, g, b, 255]));
+		}
+	}
+}
+
+/// Trapdoor, horizontal wooden planks in a frame with a cross bar.
+pub(crate) fn block_trapdoor(mut view: View, world_seed: i32, texture_seed: i32) {
+	let noise = OctavedNoise::new(1, vec![world_seed, texture_seed]);
+	let (w, h) = view.dimensions();
+	for y in 0..h {
+		for x in 0..w {
+			let is_cross_bar = x == y || x == w - 1 - y;
+			let [r, g, b] = planks_in_frame_color(&noise, x, y, w, h, false);
+			let color = if is_cross_bar {
+				[r / 4 * 3, g / 4 * 3, b / 4 * 3, 255]
+			} else {
+				[r, g, b, 255]
+			};
+			view.put_pixel(x, y, Color::from(color));
+		}
+	}
+}
+
+/// Chest, wooden planks with dark metal bands and a lock in the middle.
+pub(crate) fn block_chest(mut view: View, world_seed: i32, texture_seed: i32) {
+	let noise = OctavedNoise::new(1, vec![world_seed, texture_seed]);
+	let (w, h) = view.dimensions();
+	for y in 0..h {
+		for x in 0..w {
+			let is_band = y == 5 || y == 6;
+			let is_lock = (7..=8).contains(&x) && (4..=8).contains(&y);
+			let [r, g, b] = if is_lock {
+				[200, 180, 90]
+			} else if is_band {
+				[60, 55, 55]
+			} else {
+				planks_in_frame_color(&noise, x, y, w, h, false)
+			};
+			view.put_pixel(x, y, Color::from([r, g, b, 255]));
+		}
+	}
+}
+
+/// A tool of the given kind (a wooden handle going up diagonally and a head of the given color)
+/// on a transparent background. The row 0 is the bottom.
+pub(crate) fn tool(
+	mut view: View,
+	world_seed: i32,
+	texture_seed: i32,
+	kind: ToolKind,
+	head_color: [u8; 3],
+) {
+	let noise = OctavedNoise::new(1, vec![world_seed, texture_seed]);
+	let (w, h) = view.dimensions();
+	for y in 0..h {
+		for x in 0..w {
+			let coords = cgmath::point2(x as i32, y as i32);
+			let shade = 0.8 + noise.sample_i2d_1d(coords, &[1]) * 0.25;
+			let (xf, yf) = (x as f32, y as f32);
+			let is_handle = (2..13).contains(&x) && (x == y || x == y + 1);
+			let is_head = match kind {
+				// A bar across the top of the handle, curved a bit towards the bottom.
+				ToolKind::Pickaxe => {
+					let across = (xf - yf).abs();
+					(20..=22).contains(&(x + y)) && across < 9.0
+						|| (x + y == 19 || x + y == 18) && (6.0..9.0).contains(&across)
+				},
+				// A rounded blade at the end of the handle.
+				ToolKind::Shovel => cgmath::point2(xf, yf).distance(cgmath::point2(12.0, 12.0)) < 3.0,
+				// A blade on one side of the top of the handle.
+				ToolKind::Axe => {
+					x > y && cgmath::point2(xf, yf).distance(cgmath::point2(12.5, 9.0)) < 3.5
+				},
+			};
+			let color = if is_head {
+				let [r, g, b] = head_color;
+				[
+					(r as f32 * shade) as u8,
+					(g as f32 * shade) as u8,
+					(b as f32 * shade) as u8,
+					255,
+				]
+			} else if is_handle {
+				[
+					(120.0 * shade) as u8,
+					(80.0 * shade) as u8,
+					(45.0 * shade) as u8,
+					255,
+				]
+			} else {
+				[0, 0, 0, 0]
+			};
+			view.put_pixel(x, y, Color::from(color));
+		}
+	}
+}
+
+/// Named texture generators with parameters, meant to make the textures of new block types
+/// (see `block_definitions`) and to be iterated on live (see the `texgen_preview` command).
+#[derive(Clone, Copy)]
+pub(crate) enum TextureRecipe {
+	/// Patches of the palette colors with a few dark cracks.
+	Stone,
+	/// Growth rings (going through the palette colors) warped a bit, with grain along them.
+	Wood,
+	/// Brushed streaks with a light gradient and rivets in the corners.
+	Metal,
+	/// Patches of the darker palette colors with veins of the lightest palette color that glow.
+	Ore,
+	/// Bricks of the darker palette colors carved with runes of the lightest palette color
+	/// that glow.
+	Runes,
+}
+
+/// The parameters of a `TextureRecipe`.
+#[derive(Clone)]
+pub(crate) struct RecipeParams {
+	/// The colors the texture is made of, ideally from the darkest to the lightest.
+	pub(crate) palette: Vec<[u8; 3]>,
+	/// Size (in pixels) of the features of the texture (stone patches, wood rings, metal streaks).
+	pub(crate) scale: f32,
+	/// From 0 to 1, how noisy the texture is at the pixel level.
+	pub(crate) roughness: f32,
+}
+
+impl TextureRecipe {
+	pub(crate) fn from_name(name: &str) -> Option<TextureRecipe> {
+		match name {
+			"stone" => Some(TextureRecipe::Stone),
+			"wood" => Some(TextureRecipe::Wood),
+			"metal" => Some(TextureRecipe::Metal),
+			"ore" => Some(TextureRecipe::Ore),
+			"runes" => Some(TextureRecipe::Runes),
+			_ => None,
+		}
+	}
+
+	/// Paints the texture on `view`, and where it glows on `emissive_view`.
+	pub(crate) fn paint(
+		self,
+		mut view: View,
+		mut emissive_view: EmissiveView,
+		world_seed: i32,
+		texture_seed: i32,
+		params: &RecipeParams,
+	) {
+		let noise = OctavedNoise::new(2, vec![world_seed, texture_seed]);
+		let scale = params.scale.max(1.0);
+		let (w, h) = view.dimensions();
+		for y in 0..h {
+			for x in 0..w {
+				let coords = cgmath::point2(x as i32, y as i32);
+				let pos = cgmath::point2(x as f32, y as f32) / scale;
+				let grain = noise.sample_i2d_1d(coords, &[1]) * 2.0 - 1.0;
+				let mut emission = 0;
+				let (color, shade) = match self {
+					TextureRecipe::Stone => {
+						let patch = noise.sample_2d_1d(pos, &[2]);
+						let is_crack = (noise.sample_2d_1d(pos * 0.7, &[3]) - 0.5).abs() < 0.025;
+						let shade = if is_crack { 0.6 } else { 1.0 };
+						(palette_color(&params.palette, patch), shade)
+					},
+					TextureRecipe::Ore => {
+						let patch = noise.sample_2d_1d(pos, &[2]);
+						let is_vein = (noise.sample_2d_1d(pos * 0.8, &[3]) - 0.5).abs() < 0.06;
+						if is_vein {
+							emission = 255;
+							(palette_color(&params.palette, 1.0), 1.0)
+						} else {
+							(palette_color(&params.palette, patch * 0.6), 1.0)
+						}
+					},
+					TextureRecipe::Runes => {
+						// Each brick has a rune made of some of the strokes of a little grid.
+						let brick = cgmath::point2(x as i32 / 8, y as i32 / 8);
+						let (bx, by) = (x % 8, y % 8);
+						let strokes = [
+							bx == 2 && (2..=5).contains(&by),
+							bx == 5 && (2..=5).contains(&by),
+							by == 2 && (2..=5).contains(&bx),
+							by == 5 && (2..=5).contains(&bx),
+							bx == by && (2..=5).contains(&bx),
+						];
+						let is_rune = strokes.iter().enumerate().any(|(stroke_index, &is_on_stroke)| {
+							is_on_stroke && noise.sample_i2d_1d(brick, &[4, stroke_index as i32]) > 0.5
+						});
+						let is_mortar = bx == 0 || by == 0;
+						let patch = noise.sample_2d_1d(pos, &[2]);
+						if is_rune {
+							emission = 255;
+							(palette_color(&params.palette, 1.0), 1.0)
+						} else {
+							let shade = if is_mortar { 0.7 } else { 1.0 };
+							(palette_color(&params.palette, patch * 0.5), shade)
+						}
+					},
+					TextureRecipe::Wood => {
+						let warp = noise.sample_2d_1d(pos * 0.5, &[2]) * 2.0;
+						// Goes from the dark to the light side of the ring and back.
+						let ring = 1.0 - ((pos.x + warp).rem_euclid(1.0) * 2.0 - 1.0).abs();
+						let along_grain = noise.sample_i2d_1d(cgmath::point2(x as i32, 0), &[3]);
+						(
+							palette_color(&params.palette, ring),
+							0.9 + along_grain * 0.15,
+						)
+					},
+					TextureRecipe::Metal => {
+						let streak = noise.sample_2d_1d(cgmath::point2(pos.x, y as f32 / 64.0), &[2]);
+						let gradient = (x + y) as f32 / (w + h) as f32;
+						let is_rivet = x.min(w - 1 - x) == 1 && y.min(h - 1 - y) == 1;
+						let shade = if is_rivet { 0.55 } else { 1.0 };
+						(
+							palette_color(&params.palette, streak * 0.5 + gradient * 0.5),
+							shade,
+						)
+					},
+				};
+				let shade = shade * (1.0 + grain * params.roughness.clamp(0.0, 1.0) * 0.4);
+				let [r, g, b] = color.map(|channel| (channel as f32 * shade).clamp(0.0, 255.0) as u8);
+				view.put_pixel(x, y, Color::from([r, g, b, 255]));
+				emissive_view.put_pixel(x, y, Luma([emission]));
+			}
+		}
+	}
+}
+
+/// The color at `t` (from 0 to 1) along the palette, interpolated between its colors.
+fn palette_color(palette: &[[u8; 3]], t: f32) -> [u8; 3] {
+	match palette {
+		[] => [255, 100, 100],
+		[color] => *color,
+		_ => {
+			let position = t.clamp(0.0, 1.0) * (palette.len() - 1) as f32;
+			let index = (position.floor() as usize).min(palette.len() - 2);
+			let ratio = position - index as f32;
+			let (a, b) = (palette[index], palette[index + 1]);
+			[0, 1, 2].map(|i| (a[i] as f32 * (1.0 - ratio) + b[i] as f32 * ratio) as u8)
+		},
+	}
+}
+
+/// A palette given by the name of one of the predefined palettes (like `granite` or `oak`),
+/// or by its colors in hexadecimal like `rgb_5a4a3a_8a7a6a_b0a090`.
+pub(crate) fn palette_from_name(name: &str) -> Option<Vec<[u8; 3]>> {
+	let palette: &[[u8; 3]] = match name {
+		"grey" => &[[85, 85, 90], [125, 125, 130], [165, 165, 170]],
+		"granite" => &[[110, 75, 70], [160, 120, 110], [200, 170, 160]],
+		"sandstone" => &[[170, 140, 90], [210, 185, 130], [235, 215, 165]],
+		"oak" => &[[110, 75, 40], [150, 105, 60], [180, 135, 80]],
+		"birch" => &[[190, 170, 130], [220, 205, 170], [240, 230, 200]],
+		"iron" => &[[110, 110, 115], [165, 165, 170], [215, 215, 220]],
+		"gold" => &[[170, 120, 30], [225, 185, 60], [255, 235, 130]],
+		"copper" => &[[130, 65, 40], [185, 105, 65], [225, 150, 110]],
+		_ => {
+			let hex_colors = name.strip_prefix("rgb_")?;
+			return hex_colors
+				.split('_')
+				.map(|hex_color| {
+					let value = u32::from_str_radix(hex_color, 16).ok()?;
+					(hex_color.len() == 6).then_some([
+						(value >> 16) as u8,
+						(value >> 8) as u8,
+						value as u8,
+					])
+				})
+				.collect();
+		},
+	};
+	Some(palette.to_vec())
+}
+
+/// How much the bumps of the normal maps stick out, see `paint_normal_map`.
+const BUMPINESS: f32 = 2.5;
+
+/// Paints the height and normal map of a block texture on `normal_view`. The height is given by
+/// the brightness of the texture, so that the dark pixels are dents (like the gaps between planks
+/// or the cracks in stone). The normal is in the frame of the texture (x goes rightward and y goes
+/// downward along the texture, z goes out of it), its x and y components are encoded as
+/// `component * 0.5 + 0.5` in the red and green channels (z is deduced from them). The emissive
+/// mask of the texture is copied in the blue channel, and the height is in the alpha channel.
+/// The texture is seen as tiling so that the bumps go on seamlessly from a block to the next.
+pub(crate) fn paint_normal_map(
+	texture: &impl GenericImageView<Pixel = Color>,
+	emissive_mask: &impl GenericImageView<Pixel = Luma<u8>>,
+	mut normal_view: View,
+) {
+	let (w, h) = texture.dimensions();
+	let height = |x: i32, y: i32| {
+		let pixel = texture.get_pixel(x.rem_euclid(w as i32) as u32, y.rem_euclid(h as i32) as u32);
+		let [r, g, b, a] = pixel.0.map(|channel| channel as f32 / 255.0);
+		(r * 0.3 + g * 0.6 + b * 0.1) * a
+	};
+	let encode = |component: f32| ((component * 0.5 + 0.5) * 255.0).round() as u8;
+	for y in 0..h as i32 {
+		for x in 0..w as i32 {
+			let slope_x = (height(x + 1, y) - height(x - 1, y)) / 2.0;
+			let slope_y = (height(x, y + 1) - height(x, y - 1)) / 2.0;
+			let normal = cgmath::vec3(-slope_x * BUMPINESS, -slope_y * BUMPINESS, 1.0).normalize();
+			let height = (height(x, y) * 255.0).round() as u8;
+			let emission = emissive_mask.get_pixel(x as u32, y as u32).0[0];
+			normal_view.put_pixel(
+				x as u32,
+				y as u32,
+				Color::from([encode(normal.x), encode(normal.y), emission, height]),
+			);
+		}
+	}
+}
+
 pub(crate) fn generate_texture(view: View, world_seed: i32, texture_seed: i32) {
 	let mut texture = TextureViewWrapping::from_view(view);
 	texture.apply_texture_generator(
@@ -0,0 +1,140 @@
+//! Recording of the time spent in the major parts of the game (chunk generation, meshing,
+//! physics, rendering, etc.) to a trace file, when asked for with `--trace`.
+//!
+//! The trace file is in the Chrome trace event format, it can be opened with Perfetto
+//! (https://ui.perfetto.dev) or `chrome://tracing` to see what each thread did and when.
+
+use std::{
+	cell::Cell,
+	fs::File,
+	io::{BufWriter, Write},
+	path::Path,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Mutex, OnceLock,
+	},
+	time::Instant,
+};
+
+static TRACER: OnceLock<Tracer> = OnceLock::new();
+
+struct Tracer {
+	/// The time stamps in the trace are relative to that.
+	start: Instant,
+	writer: Mutex<TraceWriter>,
+	next_thread_id: AtomicU64,
+}
+
+struct TraceWriter {
+	file: BufWriter<File>,
+	event_count: usize,
+	/// After the file is finished, nothing more is written to it.
+	finished: bool,
+}
+
+thread_local! {
+	/// The id of the current thread in the trace, given when the thread records its first event.
+	static THREAD_ID: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+impl Tracer {
+	fn write_event(&self, event: &str) {
+		let mut writer = self.writer.lock().unwrap();
+		if writer.finished {
+			return;
+		}
+		let separator = if writer.event_count == 0 { "" } else { ",\n" };
+		// If the disk is full or something then the trace is missing some events,
+		// the game does not have to stop for that.
+		let _ = write!(writer.file, "{separator}{event}");
+		writer.event_count += 1;
+	}
+
+	fn thread_id(&self) -> u64 {
+		THREAD_ID.with(|thread_id| match thread_id.get() {
+			Some(id) => id,
+			None => {
+				let id = self.next_thread_id.fetch_add(1, Ordering::Relaxed);
+				thread_id.set(Some(id));
+				// Metadata event that gives a name to the thread in the trace viewers.
+				let thread = std::thread::current();
+				let name = thread.name().unwrap_or("unnamed thread");
+				self.write_event(&format!(
+					"{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{id},\
+					\"args\":{{\"name\":\"{}\"}}}}",
+					escape(name)
+				));
+				id
+			},
+		})
+	}
+
+	fn record_span(&self, name: &str, start: Instant, end: Instant) {
+		let thread_id = self.thread_id();
+		let timestamp = (start - self.start).as_secs_f64() * 1_000_000.0;
+		let duration = (end - start).as_secs_f64() * 1_000_000.0;
+		self.write_event(&format!(
+			"{{\"name\":\"{name}\",\"ph\":\"X\",\"ts\":{timestamp:.3},\"dur\":{duration:.3},\
+			\"pid\":1,\"tid\":{thread_id}}}"
+		));
+	}
+}
+
+/// Makes a string fit in a JSON string literal.
+fn escape(string: &str) -> String {
+	string
+		.chars()
+		.filter(|c| !c.is_control())
+		.flat_map(|c| {
+			let escaped = matches!(c, '"' | '\\');
+			escaped.then_some('\\').into_iter().chain(std::iter::once(c))
+		})
+		.collect()
+}
+
+/// Starts recording the spans to the given trace file.
+pub(crate) fn start_tracing(path: &Path) -> std::io::Result<()> {
+	let mut file = BufWriter::new(File::create(path)?);
+	file.write_all(b"[\n")?;
+	let writer = TraceWriter { file, event_count: 0, finished: false };
+	let tracer = Tracer {
+		start: Instant::now(),
+		writer: Mutex::new(writer),
+		next_thread_id: AtomicU64::new(0),
+	};
+	let _ = TRACER.set(tracer);
+	Ok(())
+}
+
+/// Completes the trace file (if tracing), spans that end after that are not recorded.
+pub(crate) fn finish_tracing() {
+	if let Some(tracer) = TRACER.get() {
+		let mut writer = tracer.writer.lock().unwrap();
+		if !writer.finished {
+			writer.finished = true;
+			let _ = writer.file.write_all(b"\n]\n");
+			let _ = writer.file.flush();
+		}
+	}
+}
+
+/// Measures the time from its creation to its drop (when tracing).
+/// The name should be some plain identifier (it is not escaped).
+pub(crate) struct Span {
+	name: &'static str,
+	start: Option<Instant>,
+}
+
+impl Drop for Span {
+	fn drop(&mut self) {
+		if let (Some(tracer), Some(start)) = (TRACER.get(), self.start) {
+			tracer.record_span(self.name, start, Instant::now());
+		}
+	}
+}
+
+/// To be kept alive during the part of the code to measure,
+/// like `let _span = trace::span("chunk_meshing");`.
+pub(crate) fn span(name: &'static str) -> Span {
+	Span { name, start: TRACER.get().map(|_| Instant::now()) }
+}
@@ -0,0 +1,114 @@
+//! Triggers are places that fire events (see `GameEvent`) when the player or an entity starts
+//! or stops standing in or on them. There are invisible trigger volumes (named spans of blocks)
+//! and pressure plates (a block type).
+
+use fxhash::FxHashSet;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	block_types::BlockTypeTable,
+	chunks::ChunkGrid,
+	coords::{AlignedBox, BlockCoords, CubicCoordsSpan},
+	entities::Id,
+	events::GameEvent,
+};
+
+/// An invisible span of blocks that fires events when something stands in it or on it.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct TriggerVolume {
+	pub(crate) name: String,
+	pub(crate) span: CubicCoordsSpan,
+}
+
+/// Something that can be stood in or on.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub(crate) enum Trigger {
+	Volume(String),
+	PressurePlate(BlockCoords),
+}
+
+impl Trigger {
+	/// The name that event hooks can refer to, all the pressure plates share the same name.
+	pub(crate) fn name(&self) -> &str {
+		match self {
+			Trigger::Volume(name) => name,
+			Trigger::PressurePlate(_) => "pressure_plate",
+		}
+	}
+}
+
+/// Something that can stand in or on a trigger.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) enum Occupant {
+	Player,
+	Entity(Id),
+}
+
+pub(crate) struct Triggers {
+	volumes: Vec<TriggerVolume>,
+	/// What stood in or on what at the last `update`.
+	occupations: FxHashSet<(Trigger, Occupant)>,
+}
+
+impl Triggers {
+	pub(crate) fn new(volumes: Vec<TriggerVolume>) -> Triggers {
+		Triggers { volumes, occupations: FxHashSet::default() }
+	}
+
+	pub(crate) fn volumes(&self) -> &[TriggerVolume] {
+		&self.volumes
+	}
+
+	/// Adds a volume, replacing the volume of the same name if there is one.
+	pub(crate) fn add_volume(&mut self, volume: TriggerVolume) {
+		self.remove_volume(&volume.name);
+		self.volumes.push(volume);
+	}
+
+	/// Returns `false` if there is no volume of that name.
+	pub(crate) fn remove_volume(&mut self, name: &str) -> bool {
+		let count_before = self.volumes.len();
+		self.volumes.retain(|volume| volume.name != name);
+		self.volumes.len() != count_before
+	}
+
+	/// Finds out what the given occupants (and their boxes) stand in or on, and returns
+	/// the events for the occupations that started or ended since the last call.
+	pub(crate) fn update(
+		&mut self,
+		occupants: impl Iterator<Item = (Occupant, AlignedBox)>,
+		chunk_grid: &ChunkGrid,
+		block_type_table: &BlockTypeTable,
+	) -> Vec<GameEvent> {
+		let pressure_plate_id = block_type_table.kinda_pressure_plate_id();
+		let mut occupations = FxHashSet::default();
+		for (occupant, aligned_box) in occupants {
+			// The blocks the box is in, and the blocks it stands on.
+			let mut lowered_box = aligned_box;
+			lowered_box.pos.z -= 0.005;
+			let occupied_span = lowered_box.strictly_overlapping_block_coords_span();
+			for volume in self.volumes.iter() {
+				if volume.span.overlaps(&occupied_span) {
+					occupations.insert((Trigger::Volume(volume.name.clone()), occupant));
+				}
+			}
+			for coords in occupied_span.iter() {
+				let is_pressure_plate =
+					chunk_grid.get_block(coords).is_some_and(|block| block.type_id == pressure_plate_id);
+				if is_pressure_plate {
+					occupations.insert((Trigger::PressurePlate(coords), occupant));
+				}
+			}
+		}
+
+		let mut events = vec![];
+		for (trigger, occupant) in occupations.difference(&self.occupations) {
+			events.push(GameEvent::TriggerEntered { trigger: trigger.clone(), occupant: *occupant });
+		}
+		for (trigger, occupant) in self.occupations.difference(&occupations) {
+			events.push(GameEvent::TriggerLeft { trigger: trigger.clone(), occupant: *occupant });
+		}
+		self.occupations = occupations;
+		events
+	}
+}
@@ -0,0 +1,281 @@
+//! Export of blocks to the MagicaVoxel `.vox` format, so that builds and terrain can be opened
+//! in external voxel tools. The exported files are written in the `exports` directory.
+//!
+//! A `.vox` model is at most 256 voxels on a side, so larger areas are split into several models
+//! that the scene graph of the file places next to each other. There is one color per block type
+//! (the average color of its texture), and decorations (flowers, grass blades, etc.) are left out
+//! since they would be full voxels.
+
+use fxhash::FxHashMap;
+
+use crate::{
+	block_types::{BlockTypeId, BlockTypeTable, Targetability},
+	chunks::ChunkGrid,
+	coords::{BlockCoords, CubicCoordsSpan},
+};
+
+/// The directory in which the exported files are written.
+const EXPORTS_DIRECTORY: &str = "exports";
+/// The maximum size of a model (on each axis) allowed by the format.
+const MODEL_SIDE: i32 = 256;
+/// The palette has 256 entries but the color index 0 means "no voxel".
+const PALETTE_SIZE: usize = 255;
+
+/// The result of a successful export.
+pub(crate) struct VoxExportSummary {
+	pub(crate) path: std::path::PathBuf,
+	pub(crate) voxel_count: usize,
+	pub(crate) model_count: usize,
+}
+
+/// Exports the blocks of the given span, or of all the loaded chunks if `None`.
+/// Returns `Ok(None)` if there is nothing to export.
+pub(crate) fn export_vox(
+	chunk_grid: &ChunkGrid,
+	span: Option<CubicCoordsSpan>,
+	block_type_table: &BlockTypeTable,
+	block_type_colors: &[[u8; 3]],
+) -> Result<Option<VoxExportSummary>, String> {
+	let is_exported = |type_id: BlockTypeId| {
+		block_type_table.get(type_id).unwrap().targetability() == Targetability::Targetable
+	};
+	let mut blocks: Vec<(BlockCoords, BlockTypeId)> = vec![];
+	match span {
+		Some(span) => {
+			for coords in span.iter() {
+				if let Some(block) = chunk_grid.get_block(coords) {
+					if is_exported(block.type_id) {
+						blocks.push((coords, block.type_id));
+					}
+				}
+			}
+		},
+		None => {
+			for chunk_coords in chunk_grid.iter_loaded_chunk_coords() {
+				let chunk_blocks = chunk_grid.get_chunk_blocks(chunk_coords).unwrap();
+				if chunk_blocks.contains_only_air() {
+					continue;
+				}
+				for coords in chunk_blocks.coords_span.iter_coords() {
+					let type_id = chunk_blocks.get(coords).unwrap().type_id;
+					if is_exported(type_id) {
+						blocks.push((coords, type_id));
+					}
+				}
+			}
+		},
+	}
+	if blocks.is_empty() {
+		return Ok(None);
+	}
+
+	// The exported area starts at the inf corner of the blocks so that no space is wasted.
+	let inf = blocks.iter().fold(blocks[0].0, |inf, &(coords, _)| {
+		cgmath::point3(
+			inf.x.min(coords.x),
+			inf.y.min(coords.y),
+			inf.z.min(coords.z),
+		)
+	});
+	let sup_included = blocks.iter().fold(blocks[0].0, |sup, &(coords, _)| {
+		cgmath::point3(
+			sup.x.max(coords.x),
+			sup.y.max(coords.y),
+			sup.z.max(coords.z),
+		)
+	});
+	let dims = sup_included - inf + cgmath::vec3(1, 1, 1);
+
+	let palette = Palette::new(
+		blocks.iter().map(|&(_, type_id)| type_id),
+		block_type_colors,
+	);
+
+	let mut models: FxHashMap<(i32, i32, i32), Vec<[u8; 4]>> = FxHashMap::default();
+	for &(coords, type_id) in blocks.iter() {
+		let relative = coords - inf;
+		let model = relative.map(|x| x.div_euclid(MODEL_SIDE));
+		let in_model = relative.map(|x| x.rem_euclid(MODEL_SIDE) as u8);
+		models.entry(model.into()).or_default().push([
+			in_model.x,
+			in_model.y,
+			in_model.z,
+			palette.color_index(type_id),
+		]);
+	}
+	let mut models: Vec<_> = models.into_iter().collect();
+	models.sort_unstable_by_key(|&(model, _)| model);
+
+	let mut writer = VoxWriter::default();
+	for (model, voxels) in models.iter() {
+		let model = cgmath::vec3(model.0, model.1, model.2);
+		let size = (dims - model * MODEL_SIDE).map(|x| x.min(MODEL_SIDE));
+		writer.model(size, voxels);
+	}
+	if models.len() >= 2 {
+		// The models are placed by their centers.
+		let translations = models.iter().map(|(model, _)| {
+			let model = cgmath::vec3(model.0, model.1, model.2);
+			let size = (dims - model * MODEL_SIDE).map(|x| x.min(MODEL_SIDE));
+			model * MODEL_SIDE + size / 2
+		});
+		writer.scene_graph(translations);
+	}
+	writer.palette(&palette.colors);
+
+	let path = unused_export_path();
+	std::fs::create_dir_all(EXPORTS_DIRECTORY)
+		.map_err(|error| format!("Could not create \"{EXPORTS_DIRECTORY}\": {error}"))?;
+	std::fs::write(&path, writer.finish())
+		.map_err(|error| format!("Could not write \"{}\": {error}", path.display()))?;
+	Ok(Some(VoxExportSummary {
+		path,
+		voxel_count: blocks.len(),
+		model_count: models.len(),
+	}))
+}
+
+/// The colors of the exported block types.
+struct Palette {
+	colors: Vec<[u8; 3]>,
+	/// The color index (starting at 1) of each exported block type.
+	color_indices: FxHashMap<BlockTypeId, u8>,
+}
+
+impl Palette {
+	fn new(type_ids: impl Iterator<Item = BlockTypeId>, block_type_colors: &[[u8; 3]]) -> Palette {
+		let mut counts: FxHashMap<BlockTypeId, usize> = FxHashMap::default();
+		for type_id in type_ids {
+			*counts.entry(type_id).or_default() += 1;
+		}
+		// If there are too many block types then the rarest ones get the closest color.
+		let mut type_ids: Vec<_> = counts.into_iter().collect();
+		type_ids.sort_unstable_by_key(|&(type_id, count)| (std::cmp::Reverse(count), type_id));
+		let mut colors: Vec<[u8; 3]> = vec![];
+		let mut color_indices = FxHashMap::default();
+		for (type_id, _count) in type_ids {
+			let color = block_type_colors.get(type_id as usize).copied().unwrap_or([255, 255, 255]);
+			let color_index = if colors.len() < PALETTE_SIZE {
+				colors.push(color);
+				colors.len() - 1
+			} else {
+				let distance2 = |other: &[u8; 3]| {
+					(0..3).map(|i| (color[i] as i32 - other[i] as i32).pow(2)).sum::<i32>()
+				};
+				(0..colors.len()).min_by_key(|&i| distance2(&colors[i])).unwrap()
+			};
+			color_indices.insert(type_id, color_index as u8 + 1);
+		}
+		Palette { colors, color_indices }
+	}
+
+	fn color_index(&self, type_id: BlockTypeId) -> u8 {
+		self.color_indices[&type_id]
+	}
+}
+
+/// Writes the children chunks of the `MAIN` chunk of a `.vox` file.
+#[derive(Default)]
+struct VoxWriter {
+	children: Vec<u8>,
+	model_count: i32,
+}
+
+impl VoxWriter {
+	fn chunk(&mut self, id: &[u8; 4], content: &[u8]) {
+		self.children.extend_from_slice(id);
+		self.children.extend_from_slice(&(content.len() as i32).to_le_bytes());
+		self.children.extend_from_slice(&0i32.to_le_bytes());
+		self.children.extend_from_slice(content);
+	}
+
+	fn model(&mut self, size: cgmath::Vector3<i32>, voxels: &[[u8; 4]]) {
+		let mut content = vec![];
+		for x in [size.x, size.y, size.z] {
+			content.extend_from_slice(&x.to_le_bytes());
+		}
+		self.chunk(b"SIZE", &content);
+		let mut content = vec![];
+		content.extend_from_slice(&(voxels.len() as i32).to_le_bytes());
+		content.extend(voxels.iter().flatten());
+		self.chunk(b"XYZI", &content);
+		self.model_count += 1;
+	}
+
+	/// A transform node at the root, over a group node, over one transform node and one shape node
+	/// for each model.
+	fn scene_graph(&mut self, translations: impl Iterator<Item = cgmath::Vector3<i32>>) {
+		let child_ids: Vec<i32> = (0..self.model_count).map(|i| 2 + i * 2).collect();
+		self.transform_node(0, 1, cgmath::vec3(0, 0, 0));
+		let mut content = vec![];
+		content.extend_from_slice(&1i32.to_le_bytes());
+		write_dict(&mut content, &[]);
+		content.extend_from_slice(&(child_ids.len() as i32).to_le_bytes());
+		for child_id in child_ids.iter() {
+			content.extend_from_slice(&child_id.to_le_bytes());
+		}
+		self.chunk(b"nGRP", &content);
+		for (model_id, (node_id, translation)) in child_ids.into_iter().zip(translations).enumerate()
+		{
+			self.transform_node(node_id, node_id + 1, translation);
+			let mut content = vec![];
+			content.extend_from_slice(&(node_id + 1).to_le_bytes());
+			write_dict(&mut content, &[]);
+			content.extend_from_slice(&1i32.to_le_bytes());
+			content.extend_from_slice(&(model_id as i32).to_le_bytes());
+			write_dict(&mut content, &[]);
+			self.chunk(b"nSHP", &content);
+		}
+	}
+
+	fn transform_node(&mut self, node_id: i32, child_id: i32, translation: cgmath::Vector3<i32>) {
+		let mut content = vec![];
+		content.extend_from_slice(&node_id.to_le_bytes());
+		write_dict(&mut content, &[]);
+		content.extend_from_slice(&child_id.to_le_bytes());
+		// Reserved id and layer id.
+		content.extend_from_slice(&(-1i32).to_le_bytes());
+		content.extend_from_slice(&(-1i32).to_le_bytes());
+		// One frame.
+		content.extend_from_slice(&1i32.to_le_bytes());
+		let (x, y, z) = translation.into();
+		write_dict(&mut content, &[("_t", &format!("{x} {y} {z}"))]);
+		self.chunk(b"nTRN", &content);
+	}
+
+	fn palette(&mut self, colors: &[[u8; 3]]) {
+		let mut content = vec![0; 256 * 4];
+		for (i, color) in colors.iter().enumerate() {
+			content[i * 4..i * 4 + 3].copy_from_slice(color);
+			content[i * 4 + 3] = 255;
+		}
+		self.chunk(b"RGBA", &content);
+	}
+
+	fn finish(self) -> Vec<u8> {
+		let mut file = vec![];
+		file.extend_from_slice(b"VOX ");
+		file.extend_from_slice(&150i32.to_le_bytes());
+		file.extend_from_slice(b"MAIN");
+		file.extend_from_slice(&0i32.to_le_bytes());
+		file.extend_from_slice(&(self.children.len() as i32).to_le_bytes());
+		file.extend(self.children);
+		file
+	}
+}
+
+fn write_dict(content: &mut Vec<u8>, entries: &[(&str, &str)]) {
+	content.extend_from_slice(&(entries.len() as i32).to_le_bytes());
+	for string in entries.iter().flat_map(|&(key, value)| [key, value]) {
+		content.extend_from_slice(&(string.len() as i32).to_le_bytes());
+		content.extend_from_slice(string.as_bytes());
+	}
+}
+
+/// `exports/export_<number>.vox` with the first number that is not already taken.
+fn unused_export_path() -> std::path::PathBuf {
+	(1..)
+		.map(|number| std::path::Path::new(EXPORTS_DIRECTORY).join(format!("export_{number}.vox")))
+		.find(|path| !path.exists())
+		.unwrap()
+}
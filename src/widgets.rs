@@ -23,7 +23,15 @@ pub(crate) enum WidgetLabel {
 	GeneralDebugInfo,
 	LogLineList,
 	ItemHeld,
+	ToolInfo,
 	HealthBar,
+	SelectionInfo,
+	ModelEditorInfo,
+	TexturePreview,
+	LoadingScreen,
+	SavingScreen,
+	StatsScreen,
+	Toasts,
 }
 
 /// A node in the tree that makes the interface.
@@ -48,9 +56,19 @@ pub(crate) enum Widget {
 	},
 	/// Loading bar for the face counter of some skybox generation.
 	FaceCounter {
+		/// Displayed before the loading bar.
+		label: String,
 		settings: font::TextRenderingSettings,
 		counter: Arc<AtomicI32>,
 	},
+	/// A bar that fills from left to right as something progresses.
+	ProgressBar {
+		/// From 0.0 (empty) to 1.0 (full).
+		progress: f32,
+		/// In pixels.
+		dimensions: cgmath::Vector2<f32>,
+		color: [f32; 3],
+	},
 	/// A wrapper around a widget that tags it with a label.
 	/// It allows for some code to find the contained widget via the label easily.
 	Label {
@@ -149,8 +167,10 @@ pub(crate) enum BoxDimensions {
 #[derive(PartialEq, Eq, Hash)]
 pub(crate) enum BoxContentPlacement {
 	TopLeft,
+	TopRight,
+	Center,
 	BottomRight,
-	// TODO: Add the other 7 (out of 9) obvious points.
+	// TODO: Add the other 5 (out of 9) obvious points.
 }
 
 impl Widget {
@@ -167,10 +187,19 @@ impl Widget {
 	}
 
 	pub(crate) fn new_face_counter(
+		label: String,
 		settings: font::TextRenderingSettings,
 		counter: Arc<AtomicI32>,
 	) -> Widget {
-		Widget::FaceCounter { settings, counter }
+		Widget::FaceCounter { label, settings, counter }
+	}
+
+	pub(crate) fn new_progress_bar(
+		progress: f32,
+		dimensions: cgmath::Vector2<f32>,
+		color: [f32; 3],
+	) -> Widget {
+		Widget::ProgressBar { progress: progress.clamp(0.0, 1.0), dimensions, color }
 	}
 
 	pub(crate) fn new_labeled_nothing(label: WidgetLabel) -> Widget {
@@ -209,6 +238,18 @@ impl Widget {
 		Widget::DisappearWhenComplete { sub_widget, completed_time: None, delay_before_disappearing }
 	}
 
+	/// Disappears after the delay, as if the sub widget was completed right away.
+	pub(crate) fn new_disappear_after(
+		delay_before_disappearing: std::time::Duration,
+		sub_widget: Box<Widget>,
+	) -> Widget {
+		Widget::DisappearWhenComplete {
+			sub_widget,
+			completed_time: Some(std::time::Instant::now()),
+			delay_before_disappearing,
+		}
+	}
+
 	pub(crate) fn new_list(
 		sub_widgets: Vec<Widget>,
 		interspace: f32,
@@ -254,10 +295,10 @@ impl Widget {
 	}
 
 	pub(crate) fn is_completed(&self) -> bool {
-		if let Widget::FaceCounter { counter, .. } = self {
-			counter.load(atomic::Ordering::Relaxed) >= 6
-		} else {
-			false
+		match self {
+			Widget::FaceCounter { counter, .. } => counter.load(atomic::Ordering::Relaxed) >= 6,
+			Widget::ProgressBar { progress, .. } => *progress >= 1.0,
+			_ => false,
 		}
 	}
 
@@ -268,6 +309,7 @@ impl Widget {
 			Widget::SimpleText { .. } => {},
 			Widget::SimpleTexture { .. } => {},
 			Widget::FaceCounter { .. } => {},
+			Widget::ProgressBar { .. } => {},
 			Widget::Label { sub_widget, .. } => sub_widget.for_each_rec(f),
 			Widget::Margins { sub_widget, .. } => sub_widget.for_each_rec(f),
 			Widget::SmoothlyIncoming { sub_widget, .. } => sub_widget.for_each_rec(f),
@@ -289,6 +331,7 @@ impl Widget {
 			Widget::SimpleText { .. } => None,
 			Widget::SimpleTexture { .. } => None,
 			Widget::FaceCounter { .. } => None,
+			Widget::ProgressBar { .. } => None,
 			Widget::Label { label, .. } if *label == label_to_find => Some(self),
 			Widget::Label { sub_widget, .. } => sub_widget.find_label(label_to_find),
 			Widget::Margins { sub_widget, .. } => sub_widget.find_label(label_to_find),
@@ -358,11 +401,12 @@ impl Widget {
 			Widget::SimpleTexture { rect_in_atlas, scale } => {
 				rect_in_atlas.texture_rect_in_atlas_wh * *scale
 			},
-			Widget::FaceCounter { settings, .. } => font.dimensions_of_text(
+			Widget::FaceCounter { label, settings, .. } => font.dimensions_of_text(
 				window_dimensions.x,
 				settings.clone(),
-				"skybox generation: [██████] 6/6",
+				&format!("{label}: [██████] 6/6"),
 			),
+			Widget::ProgressBar { dimensions, .. } => dimensions * (2.0 / window_dimensions.x),
 			Widget::Label { sub_widget, .. } => sub_widget.dimensions(font, window_dimensions),
 			Widget::Margins { sub_widget, margin_left, margin_top, margin_right, margin_bottom } => {
 				let sub_dimensions = sub_widget.dimensions(font, window_dimensions);
@@ -470,12 +514,13 @@ impl Widget {
 				);
 				meshes.add_simple_texture_vertices(simple_texture_vertices);
 			},
-			Widget::FaceCounter { settings, counter } => {
+			Widget::FaceCounter { label, settings, counter } => {
 				let counter_value = counter.load(atomic::Ordering::Relaxed);
 				// TODO: Make something cooler!
 				// For now it is just some text that changes to represent a loading bar >_<.
 				let mut text = String::new();
-				text += "skybox generation: ";
+				text += label;
+				text += ": ";
 				text.push('[');
 				for _ in 0..counter_value {
 					text.push('█');
@@ -494,6 +539,25 @@ impl Widget {
 				);
 				meshes.add_simple_texture_vertices(simple_texture_vertices);
 			},
+			Widget::ProgressBar { progress, dimensions, color } => {
+				let pixel_size = 2.0 / window_dimensions.x;
+				meshes.add_simple_line_vertices(simple_line_vertices_for_rect(
+					top_left,
+					dimensions * pixel_size,
+					*color,
+				));
+				// The interface can only draw lines and textured rects, so the filled part
+				// is made of one horizontal line per row of pixels.
+				let fill_width = (dimensions.x - 4.0) * progress * pixel_size;
+				let mut vertices = vec![];
+				for row in 2..(dimensions.y as i32 - 1) {
+					let left = top_left + cgmath::vec3(2.0, -(row as f32), 0.0) * pixel_size;
+					let right = left + cgmath::vec3(fill_width, 0.0, 0.0);
+					vertices.push(SimpleLineVertexPod { position: left.into(), color: *color });
+					vertices.push(SimpleLineVertexPod { position: right.into(), color: *color });
+				}
+				meshes.add_simple_line_vertices(vertices);
+			},
 			Widget::Label { sub_widget, .. } => {
 				sub_widget.generate_mesh_vertices(
 					top_left,
@@ -659,6 +723,10 @@ impl Widget {
 					let sub_dimensions = sub_widget.dimensions(font, window_dimensions);
 					let sub_offset = match position {
 						BoxContentPlacement::TopLeft => cgmath::vec2(0.0, 0.0),
+						BoxContentPlacement::TopRight => {
+							cgmath::vec2(dimensions.x - sub_dimensions.x, 0.0)
+						},
+						BoxContentPlacement::Center => (dimensions - sub_dimensions) / 2.0,
 						BoxContentPlacement::BottomRight => dimensions - sub_dimensions,
 					};
 					let sub_top_left = top_left + cgmath::vec3(sub_offset.x, -sub_offset.y, 0.0);
@@ -712,12 +780,12 @@ impl InterfaceMeshesVertices {
 		self.simple_texture_vertices.append(&mut vertices);
 	}
 
-	fn add_simple_line_vertices(&mut self, mut vertices: Vec<SimpleLineVertexPod>) {
+	pub(crate) fn add_simple_line_vertices(&mut self, mut vertices: Vec<SimpleLineVertexPod>) {
 		self.simple_line_vertices.append(&mut vertices);
 	}
 }
 
-fn simple_line_vertices_for_rect(
+pub(crate) fn simple_line_vertices_for_rect(
 	top_left: cgmath::Point3<f32>,
 	dimensions: cgmath::Vector2<f32>,
 	color: [f32; 3],
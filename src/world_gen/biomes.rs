@@ -0,0 +1,91 @@
+//! The biomes of the default world generator, and how likely each structure type is
+//! to be found in each of them.
+
+use super::{
+	l_system::LSystem,
+	structure_engine::{StructureTypeId, StructureTypeWeights},
+};
+
+/// Structure types of the default world generator, their index is where their generator
+/// is in the list of structure types.
+pub(crate) const TREE: StructureTypeId = StructureTypeId { index: 0 };
+pub(crate) const BOULDER: StructureTypeId = StructureTypeId { index: 1 };
+pub(crate) const CACTUS: StructureTypeId = StructureTypeId { index: 2 };
+pub(crate) const BUILDING: StructureTypeId = StructureTypeId { index: 3 };
+pub(crate) const STRUCTURE_TYPE_COUNT: usize = 4;
+pub(crate) const STRUCTURE_TYPE_NAMES: [&str; STRUCTURE_TYPE_COUNT] =
+	["tree", "boulder", "cactus", "building"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Biome {
+	Plains,
+	Forest,
+	/// Where there is no grass.
+	Desert,
+}
+
+impl Biome {
+	/// In the order of their index (`Biome as usize`).
+	pub(crate) const ALL: [Biome; 3] = [Biome::Plains, Biome::Forest, Biome::Desert];
+
+	pub(crate) fn structure_type_weights(self) -> StructureTypeWeights {
+		let weights: &[(StructureTypeId, f32)] = match self {
+			Biome::Plains => &[(TREE, 1.0), (BOULDER, 1.0), (BUILDING, 0.1)],
+			Biome::Forest => &[(TREE, 8.0), (BOULDER, 1.0)],
+			Biome::Desert => &[(CACTUS, 3.0), (BOULDER, 1.0)],
+		};
+		let nothing_weight = match self {
+			Biome::Plains => 4.0,
+			Biome::Forest => 1.0,
+			Biome::Desert => 4.0,
+		};
+		StructureTypeWeights::new(STRUCTURE_TYPE_COUNT, weights, nothing_weight)
+	}
+
+	/// How the trees that grow here look like.
+	pub(crate) fn tree_l_system(self) -> LSystem {
+		match self {
+			// Short trunk that splits into a few branches, with a wide canopy.
+			Biome::Plains => LSystem {
+				axiom: "FFAFL",
+				rules: &[
+					(
+						'A',
+						&[
+							"F[&BL]+++[&BL]+++[&BL]",
+							"F[&BL]++[&BL]++[&BL]++[&BL]",
+							"FF[&&BL]+++++[&BL]",
+						],
+					),
+					('B', &["FB", "F[+&FL]B", "F[-&FL]B"]),
+				],
+				iterations: 3,
+				segment_length: 1.5,
+				angle: 35.0,
+				leaf_radius: 2.2,
+			},
+			// Tall trunk with layers of small drooping branches.
+			Biome::Forest => LSystem {
+				axiom: "FFFAFL",
+				rules: &[(
+					'A',
+					&["F[&&FL]++[&&FL]++[&&FL]A", "F[&&FL]+++[&&FL]A", "FFA"],
+				)],
+				iterations: 5,
+				segment_length: 1.0,
+				angle: 40.0,
+				leaf_radius: 1.6,
+			},
+			// Trees do not really grow here, but one can end up just across the border
+			// of a desert, so here is a small dead shrub.
+			Biome::Desert => LSystem {
+				axiom: "A",
+				rules: &[('A', &["F[&F]+++[&F]A", "FA"])],
+				iterations: 2,
+				segment_length: 1.0,
+				angle: 40.0,
+				leaf_radius: 0.0,
+			},
+		}
+	}
+}
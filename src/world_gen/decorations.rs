@@ -0,0 +1,49 @@
+//! Small decorations (flowers, mushrooms) that the world generators scatter on the grass.
+
+use crate::{
+	block_types::{BlockTypeId, BlockTypeTable},
+	coords::BlockCoords,
+	noise,
+};
+
+/// Decides where decorations go, they come in patches (of flowers of the same color, or of
+/// mushrooms) with a density that varies smoothly, and are absent from most of the grass.
+pub(crate) struct DecorationPlacer {
+	noise_density: noise::OctavedNoise,
+	noise_kind: noise::OctavedNoise,
+	noise_scatter: noise::OctavedNoise,
+}
+
+impl DecorationPlacer {
+	pub(crate) fn new(seed: i32) -> DecorationPlacer {
+		DecorationPlacer {
+			noise_density: noise::OctavedNoise::new(2, vec![seed, 7, 1]),
+			noise_kind: noise::OctavedNoise::new(1, vec![seed, 7, 2]),
+			noise_scatter: noise::OctavedNoise::new(1, vec![seed, 7, 3]),
+		}
+	}
+
+	/// The decoration to place at the given coords (that are just above some grass), if any.
+	pub(crate) fn decoration_on_grass(
+		&self,
+		coords: BlockCoords,
+		block_type_table: &BlockTypeTable,
+	) -> Option<BlockTypeId> {
+		let coordsf = coords.map(|x| x as f32);
+		let density_scale = 40.0;
+		let d = self.noise_density.sample_3d_1d(coordsf / density_scale, &[]);
+		let density = ((d - 0.6) * 0.6).max(0.0);
+		if self.noise_scatter.sample_3d_1d(coordsf, &[]) >= density {
+			return None;
+		}
+		let kind_scale = 25.0;
+		let kind = self.noise_kind.sample_3d_1d(coordsf / kind_scale, &[]);
+		Some(if kind < 0.4 {
+			block_type_table.kinda_red_flower_id()
+		} else if kind < 0.8 {
+			block_type_table.kinda_yellow_flower_id()
+		} else {
+			block_type_table.kinda_mushroom_id()
+		})
+	}
+}
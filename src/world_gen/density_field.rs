@@ -0,0 +1,94 @@
+//! Density fields sampled on a coarse lattice and trilinearly interpolated for each block,
+//! for generators that evaluate expensive but smooth functions (like big scale noise) per block.
+
+use crate::coords::{BlockCoords, ChunkCoordsSpan, CubicCoordsSpan};
+
+/// Samples of a density function at the points of a lattice that covers some span.
+/// The lattice points are at coords that are multiples of the step, so that the lattices
+/// of neighboring chunks share their points on their common faces (and there are no seams).
+pub(crate) struct DensityField {
+	step: i32,
+	lattice_inf: BlockCoords,
+	lattice_dims: cgmath::Vector3<i32>,
+	values: Vec<f32>,
+}
+
+impl DensityField {
+	/// Samples `density` on a lattice (with the given distance between lattice points)
+	/// that covers `span`.
+	pub(crate) fn sample(
+		span: CubicCoordsSpan,
+		step: i32,
+		density: impl Fn(BlockCoords) -> f32,
+	) -> DensityField {
+		let lattice_inf = span.inf.map(|x| x.div_euclid(step) * step);
+		// Past the sup of the span, so that all the blocks of the span are in a lattice cell.
+		let lattice_sup = span.sup_included().map(|x| (x.div_euclid(step) + 1) * step);
+		let lattice_dims = (lattice_sup - lattice_inf) / step + cgmath::vec3(1, 1, 1);
+		let mut values =
+			Vec::with_capacity((lattice_dims.x * lattice_dims.y * lattice_dims.z) as usize);
+		for z in 0..lattice_dims.z {
+			for y in 0..lattice_dims.y {
+				for x in 0..lattice_dims.x {
+					values.push(density(lattice_inf + cgmath::vec3(x, y, z) * step));
+				}
+			}
+		}
+		DensityField { step, lattice_inf, lattice_dims, values }
+	}
+
+	/// Samples `density` so that it can be queried in the chunk and in the layer of blocks
+	/// just above the chunk (to know if a block has ground above it).
+	pub(crate) fn sample_for_chunk(
+		coords_span: ChunkCoordsSpan,
+		step: i32,
+		density: impl Fn(BlockCoords) -> f32,
+	) -> DensityField {
+		let mut span = CubicCoordsSpan::from_chunk_span(coords_span);
+		span.sup_excluded.z += 1;
+		DensityField::sample(span, step, density)
+	}
+
+	fn lattice_value(&self, lattice_coords: cgmath::Vector3<i32>) -> f32 {
+		let dims = self.lattice_dims;
+		let index = lattice_coords.x + dims.x * (lattice_coords.y + dims.y * lattice_coords.z);
+		self.values[index as usize]
+	}
+
+	/// The density at the given coords, interpolated from the samples around it.
+	/// The coords must be in the span given to `sample`.
+	pub(crate) fn get(&self, coords: BlockCoords) -> f32 {
+		let relative = coords - self.lattice_inf;
+		let cell = relative.map(|x| x.div_euclid(self.step));
+		let ratio = (relative - cell * self.step).map(|x| x as f32 / self.step as f32);
+		let mut value = 0.0;
+		for dz in 0..2 {
+			for dy in 0..2 {
+				for dx in 0..2 {
+					let weight = (if dx == 0 { 1.0 - ratio.x } else { ratio.x })
+						* (if dy == 0 { 1.0 - ratio.y } else { ratio.y })
+						* (if dz == 0 { 1.0 - ratio.z } else { ratio.z });
+					value += weight * self.lattice_value(cell + cgmath::vec3(dx, dy, dz));
+				}
+			}
+		}
+		value
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	#[test]
+	fn linear_density_is_interpolated_exactly() {
+		let density = |coords: BlockCoords| (coords.x * 3 - coords.y + coords.z * 2) as f32;
+		let span = CubicCoordsSpan::with_inf_sup_but_sup_is_excluded(
+			cgmath::point3(-7, -3, 2),
+			cgmath::point3(5, 9, 11),
+		);
+		let field = DensityField::sample(span, 4, density);
+		for coords in span.iter() {
+			assert!((field.get(coords) - density(coords)).abs() < 0.001);
+		}
+	}
+}
@@ -0,0 +1,266 @@
+//! Erosion of the heightmaps of some world generators, by simulating droplets of water that
+//! carry sediment downhill (carving valleys and depositing sediment where they slow down),
+//! followed by some crumbling of the slopes that are too steep.
+//!
+//! A simulation needs a whole area of the heightmap, so it is done per region and the results
+//! are cached. Each column is covered by the four regions around it, and their results are
+//! blended so that there are no seams between regions.
+
+use std::sync::{Arc, Mutex};
+
+use cgmath::InnerSpace;
+use fxhash::FxHashMap;
+
+use crate::noise::OctavedNoise;
+
+/// Distance between the centers of two neighboring regions, in blocks.
+const REGION_SPACING: i32 = 48;
+/// A region is simulated from the center of its neighbors on one side to the center of its
+/// neighbors on the other side.
+const REGION_EDGE: usize = 2 * REGION_SPACING as usize + 1;
+/// When there are more regions than that in the cache, the cache is emptied.
+const MAX_CACHED_REGIONS: usize = 512;
+
+const DROPLET_MAX_STEPS: u32 = 48;
+/// How much of its direction does a droplet keep instead of following the slope.
+const DROPLET_INERTIA: f32 = 0.05;
+const DROPLET_CAPACITY: f32 = 4.0;
+/// Flat ground still allows to carry a bit of sediment.
+const DROPLET_MIN_SLOPE: f32 = 0.01;
+const DROPLET_EROSION: f32 = 0.3;
+const DROPLET_DEPOSITION: f32 = 0.3;
+const DROPLET_EVAPORATION: f32 = 0.02;
+const DROPLET_GRAVITY: f32 = 4.0;
+const THERMAL_ITERATIONS: u32 = 10;
+/// The height difference between neighboring columns above which the higher one crumbles.
+const THERMAL_TALUS: f32 = 1.2;
+
+/// Change of height of the columns of a region, in the order of the rows along X.
+struct RegionHeightChanges {
+	changes: Vec<f32>,
+}
+
+/// Erosion of a heightmap, the heightmap itself is always given by the world generator
+/// (it must always be the same function for the same `Erosion`).
+pub(crate) struct Erosion {
+	noise: OctavedNoise,
+	regions: Mutex<FxHashMap<cgmath::Point2<i32>, Arc<RegionHeightChanges>>>,
+}
+
+impl Erosion {
+	pub(crate) fn new(seed: i32) -> Erosion {
+		Erosion {
+			noise: OctavedNoise::new(1, vec![seed, 1000]),
+			regions: Mutex::new(FxHashMap::default()),
+		}
+	}
+
+	/// How much the column at the given coords was raised (by sediment deposits) or
+	/// lowered (by being eroded away) by the erosion.
+	pub(crate) fn height_change(
+		&self,
+		coords: cgmath::Point2<i32>,
+		raw_height: &dyn Fn(cgmath::Point2<i32>) -> f32,
+	) -> f32 {
+		let cell = coords.map(|x| x.div_euclid(REGION_SPACING));
+		let ratio = (coords - cell * REGION_SPACING).map(|x| x as f32 / REGION_SPACING as f32);
+		let mut change = 0.0;
+		for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+			// Bilinear weights, that get to zero at the edges of the simulated areas.
+			let weight_x = if dx == 0 { 1.0 - ratio.x } else { ratio.x };
+			let weight_y = if dy == 0 { 1.0 - ratio.y } else { ratio.y };
+			let weight = weight_x * weight_y;
+			if weight == 0.0 {
+				continue;
+			}
+			let region_cell = cell + cgmath::vec2(dx, dy);
+			let region = self.region(region_cell, raw_height);
+			let region_inf = region_inf(region_cell);
+			let (x, y) = (
+				(coords.x - region_inf.x) as usize,
+				(coords.y - region_inf.y) as usize,
+			);
+			change += weight * region.changes[y * REGION_EDGE + x];
+		}
+		change
+	}
+
+	fn region(
+		&self,
+		region_cell: cgmath::Point2<i32>,
+		raw_height: &dyn Fn(cgmath::Point2<i32>) -> f32,
+	) -> Arc<RegionHeightChanges> {
+		if let Some(region) = self.regions.lock().unwrap().get(&region_cell) {
+			return Arc::clone(region);
+		}
+		// The simulation is done without holding the lock. Two threads may simulate
+		// the same region at the same time, but they get the same result anyway.
+		let region = Arc::new(self.simulate_region(region_cell, raw_height));
+		let mut regions = self.regions.lock().unwrap();
+		if regions.len() >= MAX_CACHED_REGIONS {
+			regions.clear();
+		}
+		regions.insert(region_cell, Arc::clone(&region));
+		region
+	}
+
+	fn simulate_region(
+		&self,
+		region_cell: cgmath::Point2<i32>,
+		raw_height: &dyn Fn(cgmath::Point2<i32>) -> f32,
+	) -> RegionHeightChanges {
+		let inf = region_inf(region_cell);
+		let raw_heights: Vec<f32> = (0..(REGION_EDGE * REGION_EDGE))
+			.map(|index| {
+				let (x, y) = (index % REGION_EDGE, index / REGION_EDGE);
+				raw_height(inf + cgmath::vec2(x as i32, y as i32))
+			})
+			.collect();
+		let mut heights = HeightGrid { heights: raw_heights.clone() };
+
+		let droplet_count = REGION_EDGE * REGION_EDGE / 2;
+		for droplet_index in 0..droplet_count {
+			let start = self.noise.sample_i3d_3d(
+				cgmath::point3(region_cell.x, region_cell.y, droplet_index as i32),
+				&[1],
+			);
+			let start = cgmath::vec2(start.x, start.y) * (REGION_EDGE - 1) as f32;
+			heights.simulate_droplet(start);
+		}
+		for _i in 0..THERMAL_ITERATIONS {
+			heights.crumble_steep_slopes();
+		}
+
+		let changes =
+			heights.heights.iter().zip(raw_heights.iter()).map(|(eroded, raw)| eroded - raw).collect();
+		RegionHeightChanges { changes }
+	}
+}
+
+/// The coords of the column with the lowest coords of the simulated area of the region.
+fn region_inf(region_cell: cgmath::Point2<i32>) -> cgmath::Point2<i32> {
+	region_cell * REGION_SPACING - cgmath::vec2(REGION_SPACING, REGION_SPACING)
+}
+
+/// The heights of the columns of the simulated area of a region.
+struct HeightGrid {
+	heights: Vec<f32>,
+}
+
+impl HeightGrid {
+	/// Indices and bilinear weights of the four columns around the given position.
+	fn corners(position: cgmath::Vector2<f32>) -> [(usize, f32); 4] {
+		let (x, y) = (position.x.floor() as usize, position.y.floor() as usize);
+		let (u, v) = (position.x - x as f32, position.y - y as f32);
+		let index = y * REGION_EDGE + x;
+		[
+			(index, (1.0 - u) * (1.0 - v)),
+			(index + 1, u * (1.0 - v)),
+			(index + REGION_EDGE, (1.0 - u) * v),
+			(index + REGION_EDGE + 1, u * v),
+		]
+	}
+
+	fn height_and_gradient(&self, position: cgmath::Vector2<f32>) -> (f32, cgmath::Vector2<f32>) {
+		let [(i00, w00), (i10, w10), (i01, w01), (i11, w11)] = HeightGrid::corners(position);
+		let (h00, h10, h01, h11) = (
+			self.heights[i00],
+			self.heights[i10],
+			self.heights[i01],
+			self.heights[i11],
+		);
+		let (u, v) = (position.x.fract(), position.y.fract());
+		let height = h00 * w00 + h10 * w10 + h01 * w01 + h11 * w11;
+		let gradient = cgmath::vec2(
+			(h10 - h00) * (1.0 - v) + (h11 - h01) * v,
+			(h01 - h00) * (1.0 - u) + (h11 - h10) * u,
+		);
+		(height, gradient)
+	}
+
+	fn add_at(&mut self, position: cgmath::Vector2<f32>, amount: f32) {
+		for (index, weight) in HeightGrid::corners(position) {
+			self.heights[index] += amount * weight;
+		}
+	}
+
+	fn is_inside(position: cgmath::Vector2<f32>) -> bool {
+		let max = (REGION_EDGE - 1) as f32;
+		0.0 <= position.x && position.x < max && 0.0 <= position.y && position.y < max
+	}
+
+	/// A droplet goes downhill, picking up sediment when going fast on a slope
+	/// and depositing it when slowing down.
+	fn simulate_droplet(&mut self, start: cgmath::Vector2<f32>) {
+		let mut position = start;
+		let mut direction = cgmath::vec2(0.0, 0.0);
+		let mut speed = 1.0;
+		let mut water = 1.0;
+		let mut sediment = 0.0;
+		for _i in 0..DROPLET_MAX_STEPS {
+			if !HeightGrid::is_inside(position) {
+				break;
+			}
+			let (height, gradient) = self.height_and_gradient(position);
+			direction = direction * DROPLET_INERTIA - gradient * (1.0 - DROPLET_INERTIA);
+			if direction.magnitude2() < 0.000001 {
+				// Stuck in a perfectly flat place.
+				self.add_at(position, sediment);
+				break;
+			}
+			direction = direction.normalize();
+			let new_position = position + direction;
+			if !HeightGrid::is_inside(new_position) {
+				break;
+			}
+			let height_difference = self.height_and_gradient(new_position).0 - height;
+
+			let capacity =
+				(-height_difference).max(DROPLET_MIN_SLOPE) * speed * water * DROPLET_CAPACITY;
+			if sediment > capacity || height_difference > 0.0 {
+				// Going uphill fills the hole behind, or the droplet carries too much.
+				let amount = if height_difference > 0.0 {
+					height_difference.min(sediment)
+				} else {
+					(sediment - capacity) * DROPLET_DEPOSITION
+				};
+				sediment -= amount;
+				self.add_at(position, amount);
+			} else {
+				// Never dig below the next position, that would make holes.
+				let amount = ((capacity - sediment) * DROPLET_EROSION).min(-height_difference);
+				sediment += amount;
+				self.add_at(position, -amount);
+			}
+
+			speed = (speed * speed - height_difference * DROPLET_GRAVITY).max(0.0).sqrt();
+			water *= 1.0 - DROPLET_EVAPORATION;
+			position = new_position;
+		}
+	}
+
+	/// Moves material from columns that are too high compared to a neighbor to that neighbor.
+	fn crumble_steep_slopes(&mut self) {
+		let mut changes = vec![0.0; self.heights.len()];
+		for y in 0..REGION_EDGE {
+			for x in 0..REGION_EDGE {
+				let index = y * REGION_EDGE + x;
+				let neighbors = [
+					(x + 1 < REGION_EDGE).then_some(index + 1),
+					(y + 1 < REGION_EDGE).then_some(index + REGION_EDGE),
+				];
+				for neighbor_index in neighbors.into_iter().flatten() {
+					let difference = self.heights[index] - self.heights[neighbor_index];
+					if difference.abs() > THERMAL_TALUS {
+						let amount = (difference.abs() - THERMAL_TALUS) * 0.25 * difference.signum();
+						changes[index] -= amount;
+						changes[neighbor_index] += amount;
+					}
+				}
+			}
+		}
+		for (height, change) in self.heights.iter_mut().zip(changes) {
+			*height += change;
+		}
+	}
+}
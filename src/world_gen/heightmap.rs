@@ -0,0 +1,102 @@
+//! World generator that reads the terrain heights from a grayscale image (a heightmap),
+//! which allows importing real-world terrain (from elevation data exported as a PNG).
+//!
+//! One pixel of the image is one column of blocks, the image is centered on the origin
+//! and its top is towards positive Y.
+
+use std::{path::PathBuf, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	block_types::BlockTypeTable,
+	chunk_blocks::{ChunkBlocks, ChunkBlocksBeingGenerated},
+	coords::ChunkCoordsSpan,
+	entities::{ChunkEntities, IdGenerator},
+};
+
+use super::WorldGenerator;
+
+/// How many blocks of dirt are between the grass and the ground.
+const DIRT_THICKNESS: i32 = 3;
+
+/// What is needed to recreate the heightmap world generator, saved with the world.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct HeightmapSettings {
+	pub(crate) path: PathBuf,
+	/// Height (in blocks) of the white pixels, black pixels are at height zero.
+	pub(crate) height_scale: f32,
+	/// Beyond the image, repeat its border pixels instead of repeating the image.
+	pub(crate) clamp: bool,
+}
+
+pub(crate) struct WorldGeneratorHeightmap {
+	/// The terrain height of each pixel, row after row.
+	heights: Vec<i32>,
+	width: i32,
+	height: i32,
+	clamp: bool,
+}
+
+impl WorldGeneratorHeightmap {
+	pub(crate) fn load(settings: &HeightmapSettings) -> Result<WorldGeneratorHeightmap, String> {
+		let image = image::open(&settings.path).map_err(|error| error.to_string())?;
+		// 16 bits per pixel so that the precision of 16-bit elevation data is not lost.
+		let image = image.to_luma16();
+		if image.width() == 0 || image.height() == 0 {
+			return Err("the image is empty".to_string());
+		}
+		let heights = image
+			.pixels()
+			.map(|pixel| (pixel.0[0] as f32 / u16::MAX as f32 * settings.height_scale).round() as i32)
+			.collect();
+		Ok(WorldGeneratorHeightmap {
+			heights,
+			width: image.width() as i32,
+			height: image.height() as i32,
+			clamp: settings.clamp,
+		})
+	}
+
+	/// The Z coordinate of the grass block at the top of the given column.
+	fn terrain_height(&self, coords_xy: cgmath::Point2<i32>) -> i32 {
+		// The image is centered on the origin, and its rows go down (towards negative Y).
+		let x = coords_xy.x + self.width / 2;
+		let y = self.height / 2 - 1 - coords_xy.y;
+		let (x, y) = if self.clamp {
+			(x.clamp(0, self.width - 1), y.clamp(0, self.height - 1))
+		} else {
+			(x.rem_euclid(self.width), y.rem_euclid(self.height))
+		};
+		self.heights[(y * self.width + x) as usize]
+	}
+}
+
+impl WorldGenerator for WorldGeneratorHeightmap {
+	fn generate_chunk_blocks_and_entities(
+		&self,
+		coords_span: ChunkCoordsSpan,
+		block_type_table: &Arc<BlockTypeTable>,
+		_id_generator: &IdGenerator,
+	) -> (ChunkBlocks, ChunkEntities) {
+		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
+		for coords in chunk_blocks.coords_span().iter_coords() {
+			let terrain_height = self.terrain_height(cgmath::point2(coords.x, coords.y));
+			let depth = terrain_height - coords.z;
+			let block = if depth < 0 {
+				block_type_table.air_id()
+			} else if depth == 0 {
+				block_type_table.kinda_grass_id()
+			} else if depth <= DIRT_THICKNESS {
+				block_type_table.kinda_dirt_id()
+			} else {
+				block_type_table.ground_id()
+			};
+			chunk_blocks.set_id(coords, block);
+		}
+		(
+			chunk_blocks.finish_generation(),
+			ChunkEntities::new_empty(coords_span),
+		)
+	}
+}
@@ -0,0 +1,211 @@
+//! The hybrid world generator partitions the world into large zones that each use one of the
+//! other world generators, and blends the terrains of neighboring zones across their borders
+//! (so that all the experimental generators can be explored in one world).
+
+use std::sync::Arc;
+
+use cgmath::MetricSpace;
+use clap::ValueEnum;
+
+use crate::{
+	block_types::BlockTypeTable,
+	chunk_blocks::{ChunkBlocks, ChunkBlocksBeingGenerated},
+	coords::{BlockCoords, ChunkCoordsSpan},
+	entities::{ChunkEntities, IdGenerator},
+	noise,
+};
+
+use super::{Biome, WhichWorldGenerator, WorldGenerator};
+
+/// The zones are the Voronoi cells of points that are scattered with one point per square cell
+/// of that size.
+const ZONE_CELL_SIZE: i32 = 240;
+/// Width (in blocks) of the band along the border between two zones where the two terrains
+/// are blended.
+const BLEND_WIDTH: f32 = 40.0;
+
+pub(crate) struct WorldGeneratorHybrid {
+	noise_zones: noise::OctavedNoise,
+	generators: Vec<Arc<dyn WorldGenerator + Sync + Send>>,
+}
+
+impl WorldGeneratorHybrid {
+	pub(crate) fn new(seed: i32, block_type_table: &Arc<BlockTypeTable>) -> WorldGeneratorHybrid {
+		let generators = WhichWorldGenerator::value_variants()
+			.iter()
+			.filter(|which| {
+				!matches!(
+					which,
+					WhichWorldGenerator::Hybrid | WhichWorldGenerator::Empty
+				)
+			})
+			.map(|which| which.get_the_actual_generator(seed, block_type_table))
+			.collect();
+		WorldGeneratorHybrid {
+			noise_zones: noise::OctavedNoise::new(1, vec![seed, 1000]),
+			generators,
+		}
+	}
+
+	/// For each generator that has a say in the given column, its weight (they sum to 1).
+	fn generator_weights(&self, coords_xy: cgmath::Point2<i32>) -> Vec<(usize, f32)> {
+		let coordsf_xy = coords_xy.map(|x| x as f32);
+		let cell = coords_xy.map(|x| x.div_euclid(ZONE_CELL_SIZE));
+		let mut zone_points = vec![];
+		for dx in -1..=1 {
+			for dy in -1..=1 {
+				let neighbor_cell = cell + cgmath::vec2(dx, dy);
+				let cell_3d = cgmath::point3(neighbor_cell.x, neighbor_cell.y, 0);
+				let position_in_cell = self.noise_zones.sample_i3d_3d(cell_3d, &[1]);
+				let point = cgmath::point2(
+					(neighbor_cell.x as f32 + position_in_cell.x) * ZONE_CELL_SIZE as f32,
+					(neighbor_cell.y as f32 + position_in_cell.y) * ZONE_CELL_SIZE as f32,
+				);
+				let generator_index = ((self.noise_zones.sample_i3d_1d(cell_3d, &[2])
+					* self.generators.len() as f32) as usize)
+					.min(self.generators.len() - 1);
+				zone_points.push((generator_index, coordsf_xy.distance(point)));
+			}
+		}
+
+		// The closest zone point has full weight, the others have some weight if they are
+		// almost as close (which happens near the borders of the zone).
+		let closest_distance =
+			zone_points.iter().map(|&(_, distance)| distance).fold(f32::INFINITY, f32::min);
+		let mut weights: Vec<(usize, f32)> = vec![];
+		for (generator_index, distance) in zone_points {
+			let weight = (1.0 - (distance - closest_distance) / BLEND_WIDTH).max(0.0);
+			if weight > 0.0 {
+				match weights.iter_mut().find(|(index, _)| *index == generator_index) {
+					Some((_, total_weight)) => *total_weight += weight,
+					None => weights.push((generator_index, weight)),
+				}
+			}
+		}
+		let weight_sum: f32 = weights.iter().map(|&(_, weight)| weight).sum();
+		for (_, weight) in weights.iter_mut() {
+			*weight /= weight_sum;
+		}
+		weights
+	}
+}
+
+impl WorldGenerator for WorldGeneratorHybrid {
+	/// The biome of the generator that has the most say there.
+	fn biome_at(&self, coords: BlockCoords) -> Option<Biome> {
+		let (index, _weight) = self
+			.generator_weights(cgmath::point2(coords.x, coords.y))
+			.into_iter()
+			.max_by(|(_, weight_a), (_, weight_b)| weight_a.total_cmp(weight_b))?;
+		self.generators[index].biome_at(coords)
+	}
+
+	fn settings_fingerprint(&self) -> Option<u64> {
+		let fingerprints: Vec<Option<u64>> =
+			self.generators.iter().map(|generator| generator.settings_fingerprint()).collect();
+		fingerprints.iter().any(Option::is_some).then(|| fxhash::hash64(&fingerprints))
+	}
+
+	fn generate_chunk_blocks_and_entities(
+		&self,
+		coords_span: ChunkCoordsSpan,
+		block_type_table: &Arc<BlockTypeTable>,
+		id_generator: &IdGenerator,
+	) -> (ChunkBlocks, ChunkEntities) {
+		let inf = coords_span.block_coords_inf();
+		let edge = coords_span.cd.edge;
+		let column_index = |coords_xy: cgmath::Point2<i32>| -> usize {
+			((coords_xy.y - inf.y) * edge + (coords_xy.x - inf.x)) as usize
+		};
+		let column_weights: Vec<Vec<(usize, f32)>> = (0..(edge * edge))
+			.map(|index| {
+				let coords_xy = cgmath::point2(inf.x + index % edge, inf.y + index / edge);
+				self.generator_weights(coords_xy)
+			})
+			.collect();
+
+		// Only the generators that have a say somewhere in the chunk are run.
+		let mut involved_generators: Vec<usize> = column_weights
+			.iter()
+			.flat_map(|weights| weights.iter().map(|&(index, _)| index))
+			.collect();
+		involved_generators.sort_unstable();
+		involved_generators.dedup();
+		if let [only_generator] = involved_generators[..] {
+			return self.generators[only_generator].generate_chunk_blocks_and_entities(
+				coords_span,
+				block_type_table,
+				id_generator,
+			);
+		}
+		let generated: Vec<(usize, ChunkBlocks, ChunkEntities)> = involved_generators
+			.iter()
+			.map(|&index| {
+				let (blocks, entities) = self.generators[index].generate_chunk_blocks_and_entities(
+					coords_span,
+					block_type_table,
+					id_generator,
+				);
+				(index, blocks, entities)
+			})
+			.collect();
+		let generated_by = |generator_index: usize| {
+			generated.iter().find(|(index, _, _)| *index == generator_index).unwrap()
+		};
+		let is_opaque = |type_id| block_type_table.get(type_id).unwrap().is_opaque();
+
+		// Each terrain is seen as a density (1 where there are opaque blocks, 0 elsewhere)
+		// and the blended terrain is where the weighted sum of the densities is high enough.
+		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
+		for coords in coords_span.iter_coords() {
+			let weights = &column_weights[column_index(cgmath::point2(coords.x, coords.y))];
+			let mut density = 0.0;
+			let mut heaviest_opaque = None;
+			let mut heaviest = None;
+			for &(generator_index, weight) in weights.iter() {
+				let block = generated_by(generator_index).1.get(coords).unwrap();
+				let opaque = is_opaque(block.type_id);
+				if opaque {
+					density += weight;
+					if heaviest_opaque
+						.as_ref()
+						.is_none_or(|(_, heaviest_weight)| *heaviest_weight < weight)
+					{
+						heaviest_opaque = Some((block.as_owned_block(), weight));
+					}
+				}
+				if heaviest.as_ref().is_none_or(|(_, _, heaviest_weight)| *heaviest_weight < weight) {
+					heaviest = Some((block.as_owned_block(), opaque, weight));
+				}
+			}
+			let block = if density > 0.5 {
+				heaviest_opaque.map(|(block, _)| block)
+			} else {
+				// What is not opaque (like grass blades or water) is kept from the main
+				// terrain of the column, except for the opaque blocks that got blended away.
+				heaviest.and_then(|(block, opaque, _)| (!opaque).then_some(block))
+			};
+			if let Some(block) = block {
+				chunk_blocks.set(coords, block);
+			}
+		}
+
+		// Entities are kept where their generator has the most weight.
+		let mut chunk_entities = ChunkEntities::new_empty(coords_span);
+		for (generator_index, _, entities) in generated.iter() {
+			for entity in entities.iter_entities() {
+				let pos = entity.pos().map(|x| x.round() as i32);
+				let weights = self.generator_weights(cgmath::point2(pos.x, pos.y));
+				let heaviest_generator = weights
+					.iter()
+					.max_by(|(_, weight_a), (_, weight_b)| weight_a.total_cmp(weight_b))
+					.map(|&(index, _)| index);
+				if heaviest_generator == Some(*generator_index) {
+					chunk_entities.add_entity(entity.clone());
+				}
+			}
+		}
+
+		(chunk_blocks.finish_generation(), chunk_entities)
+	}
+}
@@ -0,0 +1,250 @@
+//! Jigsaw-style assembly of buildings from reusable pieces (rooms and corridors).
+//! Pieces have sockets (doorways in the middle of some of their walls), and pieces are attached
+//! to the free sockets of the already placed pieces, as long as they fit.
+
+use crate::{
+	coords::{BlockCoords, CubicCoordsSpan},
+	noise::OctavedNoise,
+};
+
+use super::structure_engine::{BlockPlacing, StructureInstanceGenerationContext};
+
+/// Sockets only connect to sockets of an other kind (rooms are linked by corridors).
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SocketKind {
+	Room,
+	Corridor,
+}
+
+/// A box of walls with a floor and a roof, and doorways that can be opened in the middle
+/// of some of its walls.
+struct Piece {
+	/// Walls, floor and roof included. The horizontal dimensions are odd so that walls
+	/// have a middle.
+	dims: cgmath::Vector3<i32>,
+	/// Horizontal directions from the center to the walls that have a socket.
+	sockets: &'static [(i32, i32)],
+	socket_kind: SocketKind,
+	/// How likely this piece is to be picked among the compatible ones.
+	weight: f32,
+}
+
+const PIECES: &[Piece] = &[
+	Piece {
+		dims: cgmath::vec3(7, 7, 5),
+		sockets: &[(1, 0), (-1, 0), (0, 1), (0, -1)],
+		socket_kind: SocketKind::Room,
+		weight: 2.0,
+	},
+	Piece {
+		dims: cgmath::vec3(11, 9, 6),
+		sockets: &[(1, 0), (-1, 0), (0, 1)],
+		socket_kind: SocketKind::Room,
+		weight: 1.0,
+	},
+	// A dead end room.
+	Piece {
+		dims: cgmath::vec3(5, 5, 5),
+		sockets: &[(1, 0)],
+		socket_kind: SocketKind::Room,
+		weight: 1.0,
+	},
+	// Straight corridor.
+	Piece {
+		dims: cgmath::vec3(7, 3, 4),
+		sockets: &[(1, 0), (-1, 0)],
+		socket_kind: SocketKind::Corridor,
+		weight: 2.0,
+	},
+	// Corridor that turns.
+	Piece {
+		dims: cgmath::vec3(5, 5, 4),
+		sockets: &[(-1, 0), (0, 1)],
+		socket_kind: SocketKind::Corridor,
+		weight: 1.0,
+	},
+];
+
+/// Horizontal quarter turn counterclockwise (seen from above).
+fn rotate(direction: cgmath::Vector3<i32>, quarter_turns: i32) -> cgmath::Vector3<i32> {
+	let mut direction = direction;
+	for _i in 0..quarter_turns.rem_euclid(4) {
+		direction = cgmath::vec3(-direction.y, direction.x, direction.z);
+	}
+	direction
+}
+
+/// A piece once it was given a rotation and a position.
+struct PlacedPiece {
+	span: CubicCoordsSpan,
+	socket_directions: Vec<cgmath::Vector3<i32>>,
+	socket_kind: SocketKind,
+}
+
+impl PlacedPiece {
+	fn new(piece: &Piece, quarter_turns: i32, inf: BlockCoords) -> PlacedPiece {
+		let mut dims = piece.dims;
+		if quarter_turns % 2 == 1 {
+			(dims.x, dims.y) = (dims.y, dims.x);
+		}
+		PlacedPiece {
+			span: CubicCoordsSpan::with_inf_sup_but_sup_is_excluded(inf, inf + dims),
+			socket_directions: piece
+				.sockets
+				.iter()
+				.map(|&(x, y)| rotate(cgmath::vec3(x, y, 0), quarter_turns))
+				.collect(),
+			socket_kind: piece.socket_kind,
+		}
+	}
+
+	/// The block of the wall (at the height of the feet) in which the doorway of the socket
+	/// in the given direction is.
+	fn doorway(&self, direction: cgmath::Vector3<i32>) -> BlockCoords {
+		let dims = self.span.sup_excluded - self.span.inf;
+		let center = self.span.inf + cgmath::vec3(dims.x / 2, dims.y / 2, 1);
+		center + cgmath::vec3(direction.x * (dims.x / 2), direction.y * (dims.y / 2), 0)
+	}
+}
+
+/// Limits to how big an assembled building can get.
+struct AssemblyLimits {
+	/// How many pieces away from the first piece can a piece be.
+	max_depth: u32,
+	max_piece_count: usize,
+	/// All the pieces must fit in there.
+	bounding_span: CubicCoordsSpan,
+}
+
+/// Deterministically decides which pieces go where (from a first room whose floor is centered
+/// on `floor_center`), and which doorways connect the pieces.
+fn assemble(
+	noise: &OctavedNoise,
+	floor_center: BlockCoords,
+	limits: &AssemblyLimits,
+) -> (Vec<PlacedPiece>, Vec<BlockCoords>) {
+	let first_piece = &PIECES[0];
+	let first_inf = floor_center - cgmath::vec3(first_piece.dims.x / 2, first_piece.dims.y / 2, 0);
+	let mut placed_pieces = vec![PlacedPiece::new(first_piece, 0, first_inf)];
+	let mut doorways = vec![];
+
+	// Free sockets (index of their piece, direction, depth of their piece), in the order
+	// they are to be attached to (breadth first so that the building grows evenly).
+	let mut free_sockets = std::collections::VecDeque::new();
+	for &direction in placed_pieces[0].socket_directions.iter() {
+		free_sockets.push_back((0, direction, 0));
+	}
+	while let Some((piece_index, direction, depth)) = free_sockets.pop_front() {
+		if depth >= limits.max_depth || placed_pieces.len() >= limits.max_piece_count {
+			continue;
+		}
+		let doorway = placed_pieces[piece_index].doorway(direction);
+		let socket_kind = placed_pieces[piece_index].socket_kind;
+
+		// Pick a compatible piece, with a weighted random choice.
+		let candidates: Vec<&Piece> =
+			PIECES.iter().filter(|piece| piece.socket_kind != socket_kind).collect();
+		let total_weight: f32 = candidates.iter().map(|piece| piece.weight).sum();
+		let mut remaining = noise.sample_i3d_1d(doorway, &[1]) * total_weight;
+		let piece = candidates
+			.iter()
+			.find(|piece| {
+				remaining -= piece.weight;
+				remaining < 0.0
+			})
+			.unwrap_or(candidates.last().unwrap());
+
+		// Pick which of its sockets connects, and turn the piece so that this socket
+		// faces the socket it connects to.
+		let socket_index = (noise.sample_i3d_1d(doorway, &[2]) * piece.sockets.len() as f32)
+			.min(piece.sockets.len() as f32 - 1.0) as usize;
+		let (x, y) = piece.sockets[socket_index];
+		let quarter_turns = (0..4)
+			.find(|&quarter_turns| rotate(cgmath::vec3(x, y, 0), quarter_turns) == -direction)
+			.unwrap();
+		// The doorways of the two sockets are next to each other.
+		let new_doorway = doorway + direction;
+		let at_zero = PlacedPiece::new(piece, quarter_turns, cgmath::point3(0, 0, 0));
+		let inf = cgmath::point3(0, 0, 0) + (new_doorway - at_zero.doorway(-direction));
+		let new_piece = PlacedPiece::new(piece, quarter_turns, inf);
+
+		// It has to fit.
+		let in_bounds = limits.bounding_span.contains(new_piece.span.inf)
+			&& limits.bounding_span.contains(new_piece.span.sup_included());
+		let overlaps_other_pieces =
+			placed_pieces.iter().any(|placed_piece| placed_piece.span.overlaps(&new_piece.span));
+		if !in_bounds || overlaps_other_pieces {
+			continue;
+		}
+
+		doorways.push(doorway);
+		doorways.push(new_doorway);
+		let new_piece_index = placed_pieces.len();
+		for &new_direction in new_piece.socket_directions.iter() {
+			if new_direction != -direction {
+				free_sockets.push_back((new_piece_index, new_direction, depth + 1));
+			}
+		}
+		placed_pieces.push(new_piece);
+	}
+
+	(placed_pieces, doorways)
+}
+
+/// Generates a building made of rooms and corridors on the ground below the origin.
+pub(crate) fn generate_building(
+	context: &mut StructureInstanceGenerationContext,
+	noise: &OctavedNoise,
+	max_radius: i32,
+) {
+	let Some(ground) = context.find_terrain_ground_below(context.origin.coords, max_radius) else {
+		return;
+	};
+	let mut bounding_span = CubicCoordsSpan::with_center_and_radius(ground, max_radius);
+	bounding_span.add_margins(-2);
+	let limits = AssemblyLimits { max_depth: 5, max_piece_count: 14, bounding_span };
+	let (pieces, doorways) = assemble(noise, ground, &limits);
+
+	let table = context.block_type_table;
+	let foundation =
+		BlockPlacing { block_type_to_place: table.ground_id(), only_place_on_air: false };
+	let wall = BlockPlacing {
+		block_type_to_place: table.kinda_wood_id(),
+		only_place_on_air: false,
+	};
+	let roof = BlockPlacing {
+		block_type_to_place: table.kinda_leaf_id(),
+		only_place_on_air: false,
+	};
+	let air = BlockPlacing { block_type_to_place: table.air_id(), only_place_on_air: false };
+	for piece in pieces.iter() {
+		let inf = piece.span.inf;
+		let sup = piece.span.sup_included();
+		for coords in piece.span.iter() {
+			let is_wall =
+				coords.x == inf.x || coords.x == sup.x || coords.y == inf.y || coords.y == sup.y;
+			let placing = if coords.z == inf.z {
+				&foundation
+			} else if coords.z == sup.z {
+				&roof
+			} else if is_wall {
+				&wall
+			} else {
+				&air
+			};
+			context.place_block(placing, coords);
+		}
+		// Fill the terrain below the floor in case it is not perfectly flat.
+		for x in inf.x..=sup.x {
+			for y in inf.y..=sup.y {
+				for z in 1..=2 {
+					context.place_block(&foundation, cgmath::point3(x, y, inf.z - z));
+				}
+			}
+		}
+	}
+	for doorway in doorways {
+		context.place_block(&air, doorway);
+		context.place_block(&air, doorway + cgmath::vec3(0, 0, 1));
+	}
+}
@@ -0,0 +1,140 @@
+//! L-system trees: a word is rewritten a few times by rules (that have alternatives picked
+//! by noise), and the resulting word is read by a turtle that draws the branches and
+//! places the leaves.
+//!
+//! The symbols read by the turtle are:
+//! - `F` moves forward by a segment, placing wood along the way,
+//! - `+` and `-` turn around the vertical axis,
+//! - `&` and `^` tilt down (away from the vertical) and up,
+//! - `[` and `]` save and restore the turtle state (to draw a branch and come back),
+//! - `L` places a ball of leaves.
+//!
+//! Other symbols are only there to be rewritten.
+
+use cgmath::InnerSpace;
+
+use crate::{coords::BlockCoords, noise::OctavedNoise};
+
+use super::structure_engine::{BlockPlacing, StructureInstanceGenerationContext};
+
+/// The rules of a kind of tree.
+pub(crate) struct LSystem {
+	pub(crate) axiom: &'static str,
+	/// For some symbols, the alternatives that can replace it (one is picked by noise
+	/// for each occurrence).
+	pub(crate) rules: &'static [(char, &'static [&'static str])],
+	pub(crate) iterations: u32,
+	/// In blocks.
+	pub(crate) segment_length: f32,
+	/// In degrees, varies a bit from a turn to an other.
+	pub(crate) angle: f32,
+	/// Radius of the balls of leaves, no leaves at all if zero.
+	pub(crate) leaf_radius: f32,
+}
+
+/// Safety net against rules that grow the word too much.
+const MAX_WORD_LENGTH: usize = 5000;
+
+impl LSystem {
+	fn expand(&self, noise: &OctavedNoise, seed_coords: BlockCoords) -> Vec<char> {
+		let mut word: Vec<char> = self.axiom.chars().collect();
+		for iteration in 0..self.iterations {
+			let mut next_word = Vec::with_capacity(word.len() * 2);
+			for (index, &symbol) in word.iter().enumerate() {
+				let alternatives = self.rules.iter().find_map(|&(rule_symbol, alternatives)| {
+					(rule_symbol == symbol).then_some(alternatives)
+				});
+				match alternatives {
+					Some(alternatives) => {
+						let value = noise.sample_i3d_1d(seed_coords, &[iteration as i32, index as i32]);
+						let alternative_index =
+							((value * alternatives.len() as f32) as usize).min(alternatives.len() - 1);
+						next_word.extend(alternatives[alternative_index].chars());
+					},
+					None => next_word.push(symbol),
+				}
+			}
+			if next_word.len() > MAX_WORD_LENGTH {
+				break;
+			}
+			word = next_word;
+		}
+		word
+	}
+}
+
+#[derive(Clone, Copy)]
+struct Turtle {
+	position: cgmath::Point3<f32>,
+	/// Normalized.
+	heading: cgmath::Vector3<f32>,
+	/// Normalized horizontal vector orthogonal to the heading, tilting is a rotation around it.
+	/// It is needed to know in which direction to tilt a vertical heading.
+	side: cgmath::Vector3<f32>,
+}
+
+/// Generates a tree that grows from the top of the ground block `ground`.
+pub(crate) fn generate_tree(
+	context: &mut StructureInstanceGenerationContext,
+	noise: &OctavedNoise,
+	l_system: &LSystem,
+	ground: BlockCoords,
+) {
+	let wood = BlockPlacing {
+		block_type_to_place: context.block_type_table.kinda_wood_id(),
+		only_place_on_air: false,
+	};
+	let leaves = BlockPlacing {
+		block_type_to_place: context.block_type_table.kinda_leaf_id(),
+		only_place_on_air: true,
+	};
+	let to_block_coords = |position: cgmath::Point3<f32>| position.map(|x| x.round() as i32);
+
+	let word = l_system.expand(noise, ground);
+	let mut turtle = Turtle {
+		position: (ground + cgmath::vec3(0, 0, 1)).map(|x| x as f32),
+		heading: cgmath::vec3(0.0, 0.0, 1.0),
+		side: cgmath::vec3(0.0, 1.0, 0.0),
+	};
+	let mut stack = vec![];
+	for (index, &symbol) in word.iter().enumerate() {
+		let angle = || {
+			let variation = noise.sample_i3d_1d(ground, &[-1, index as i32]) * 0.5 + 0.75;
+			cgmath::Deg(l_system.angle * variation)
+		};
+		match symbol {
+			'F' => {
+				// Small steps so that there is no hole in slanted branches.
+				let step_count = (l_system.segment_length * 3.0).ceil() as i32;
+				let step = turtle.heading * (l_system.segment_length / step_count as f32);
+				for _i in 0..step_count {
+					context.place_block(&wood, to_block_coords(turtle.position));
+					turtle.position += step;
+				}
+			},
+			'+' | '-' => {
+				let sign = if symbol == '+' { 1.0 } else { -1.0 };
+				let rotation =
+					cgmath::Matrix3::from_axis_angle(cgmath::vec3(0.0, 0.0, 1.0), angle() * sign);
+				turtle.heading = (rotation * turtle.heading).normalize();
+				turtle.side = (rotation * turtle.side).normalize();
+			},
+			'&' | '^' => {
+				let sign = if symbol == '&' { 1.0 } else { -1.0 };
+				let rotation = cgmath::Matrix3::from_axis_angle(turtle.side, angle() * sign);
+				turtle.heading = (rotation * turtle.heading).normalize();
+			},
+			'[' => stack.push(turtle),
+			']' => {
+				if let Some(saved_turtle) = stack.pop() {
+					turtle = saved_turtle;
+				}
+			},
+			'L' if l_system.leaf_radius > 0.0 => {
+				let center = to_block_coords(turtle.position).map(|x| x as f32);
+				context.place_ball(&leaves, center, l_system.leaf_radius);
+			},
+			_ => {},
+		}
+	}
+}
@@ -1,4 +1,14 @@
+mod biomes;
+mod decorations;
+mod density_field;
+mod erosion;
+mod heightmap;
+mod hybrid;
+mod jigsaw;
+mod l_system;
+mod spline;
 mod structure_engine;
+mod village;
 
 use std::{cmp::Ordering, f32::consts::TAU, sync::Arc};
 
@@ -9,7 +19,7 @@ use smallvec::SmallVec;
 
 use crate::{
 	block_types::{BlockTypeId, BlockTypeTable},
-	chunk_blocks::{ChunkBlocks, ChunkBlocksBeingGenerated},
+	chunk_blocks::{Block, ChunkBlocks, ChunkBlocksBeingGenerated},
 	coords::{
 		iter_3d_rect_inf_sup_excluded, BlockCoords, ChunkCoordsSpan, CubicCoordsSpan, NonOrientedAxis,
 	},
@@ -17,9 +27,22 @@ use crate::{
 	noise,
 };
 
-use self::structure_engine::{
-	BlockPlacing, StructureInstanceGenerationContext, StructureOriginGenerator,
-	StructureTypeInstanceGenerator, TestStructureOriginGenerator,
+pub(crate) use self::biomes::Biome;
+pub(crate) use self::heightmap::{HeightmapSettings, WorldGeneratorHeightmap};
+pub(crate) use self::structure_engine::StructureOriginsDebugView;
+
+use self::{
+	biomes::{STRUCTURE_TYPE_COUNT, STRUCTURE_TYPE_NAMES},
+	decorations::DecorationPlacer,
+	density_field::DensityField,
+	erosion::Erosion,
+	spline::TerrainCurves,
+	structure_engine::{
+		agree_to_link, BlockPlacing, PoissonDiskStructureOriginGenerator,
+		StructureInstanceGenerationContext, StructureOriginGenerator, StructureTransform,
+		StructureTypeId, StructureTypeInstanceGenerator, StructureTypeTable,
+		TestStructureOriginGenerator,
+	},
 };
 
 pub(crate) trait WorldGenerator {
@@ -29,8 +52,43 @@ pub(crate) trait WorldGenerator {
 		block_type_table: &Arc<BlockTypeTable>,
 		id_generator: &IdGenerator,
 	) -> (ChunkBlocks, ChunkEntities);
+
+	/// The structure origins in the given span, with the names of their structure types,
+	/// if the world generator can tell where its structures are without generating the chunks.
+	fn structure_origins_in_span(
+		&self,
+		_span: CubicCoordsSpan,
+	) -> Option<Vec<(&'static str, BlockCoords)>> {
+		None
+	}
+
+	/// What the structure origin generators have in the given span, to be displayed for
+	/// debugging, if the world generator can tell where its structures are without generating
+	/// the chunks.
+	fn structure_origins_debug_views(
+		&self,
+		_span: CubicCoordsSpan,
+	) -> Option<Vec<StructureOriginsDebugView>> {
+		None
+	}
+
+	/// The biome at the given coords, if the world generator has biomes.
+	fn biome_at(&self, _coords: BlockCoords) -> Option<Biome> {
+		None
+	}
+
+	/// A hash of what the world generator loaded from editable files (like the terrain curves),
+	/// if it depends on such files, so that the chunk cache can tell when the files changed.
+	fn settings_fingerprint(&self) -> Option<u64> {
+		None
+	}
 }
 
+/// Version of the code of the world generators, it should be bumped by changes that make
+/// a world generator generate different blocks for the same seed (so that the chunks cached
+/// by the previous versions are not used anymore, see `ChunkCache`).
+pub(crate) const WORLD_GEN_VERSION: u32 = 1;
+
 #[derive(Clone, Copy, ValueEnum, Serialize, Deserialize)]
 pub(crate) enum WhichWorldGenerator {
 	Default,
@@ -59,6 +117,10 @@ pub(crate) enum WhichWorldGenerator {
 	HeightBiomes,
 	HeightBiomesVolume,
 	Height03,
+	/// `Height03` with erosion.
+	Height03Eroded,
+	/// `HeightBiomes` with erosion.
+	HeightBiomesEroded,
 	StructuresPoc,
 	StructuresLinksPoc,
 	StructuresTrees,
@@ -71,9 +133,24 @@ pub(crate) enum WhichWorldGenerator {
 	WierdTerrain03,
 	StructuresProceduralPoc,
 	StructuresArcs,
+	FloatingIslands,
+	/// Warped ridged mountains over billowy hills.
+	Ridges,
+	/// Oceans, plains, hills and mountains shaped by editable curves (see `TerrainCurves`).
+	Splines,
+	/// Zones of all the other generators, blended at their borders.
+	Hybrid,
 }
 
 impl WhichWorldGenerator {
+	/// Is the ground of the generated worlds made of islands floating in the sky?
+	pub(crate) fn makes_sky_islands(self) -> bool {
+		matches!(
+			self,
+			WhichWorldGenerator::SkyIslands | WhichWorldGenerator::FloatingIslands
+		)
+	}
+
 	pub(crate) fn get_the_actual_generator(
 		self,
 		seed: i32,
@@ -105,11 +182,19 @@ impl WhichWorldGenerator {
 			WhichWorldGenerator::Plane02 => Arc::new(WorldGeneratorPlane02 { seed }),
 			WhichWorldGenerator::WierdTerrain02 => Arc::new(WorldGeneratorWierdTerrain02 { seed }),
 			WhichWorldGenerator::Height02 => Arc::new(WorldGeneratorHeight02 { seed }),
-			WhichWorldGenerator::HeightBiomes => Arc::new(WorldGeneratorHeightBiomes { seed }),
+			WhichWorldGenerator::HeightBiomes => {
+				Arc::new(WorldGeneratorHeightBiomes { seed, erosion: None })
+			},
 			WhichWorldGenerator::HeightBiomesVolume => {
 				Arc::new(WorldGeneratorHeightBiomesVolume { seed })
 			},
-			WhichWorldGenerator::Height03 => Arc::new(WorldGeneratorHeight03 { seed }),
+			WhichWorldGenerator::Height03 => Arc::new(WorldGeneratorHeight03 { seed, erosion: None }),
+			WhichWorldGenerator::Height03Eroded => {
+				Arc::new(WorldGeneratorHeight03 { seed, erosion: Some(Erosion::new(seed)) })
+			},
+			WhichWorldGenerator::HeightBiomesEroded => {
+				Arc::new(WorldGeneratorHeightBiomes { seed, erosion: Some(Erosion::new(seed)) })
+			},
 			WhichWorldGenerator::StructuresPoc => Arc::new(WorldGeneratorStructuresPoc { seed }),
 			WhichWorldGenerator::StructuresLinksPoc => {
 				Arc::new(WorldGeneratorStructuresLinksPoc { seed })
@@ -135,6 +220,14 @@ impl WhichWorldGenerator {
 				),
 			),
 			WhichWorldGenerator::StructuresArcs => Arc::new(WorldGeneratorStructuresArcs { seed }),
+			WhichWorldGenerator::FloatingIslands => Arc::new(WorldGeneratorFloatingIslands { seed }),
+			WhichWorldGenerator::Ridges => Arc::new(WorldGeneratorRidges { seed }),
+			WhichWorldGenerator::Splines => {
+				Arc::new(WorldGeneratorSplines { seed, curves: TerrainCurves::load() })
+			},
+			WhichWorldGenerator::Hybrid => {
+				Arc::new(hybrid::WorldGeneratorHybrid::new(seed, block_type_table))
+			},
 		}
 	}
 }
@@ -143,6 +236,63 @@ pub(crate) struct DefaultWorldGenerator {
 	pub(crate) seed: i32,
 }
 
+/// Where the biomes of the default world generator are.
+struct DefaultBiomeMap {
+	noise_no_grass: noise::OctavedNoise,
+	noise_forest: noise::OctavedNoise,
+}
+
+impl DefaultBiomeMap {
+	fn new(seed: i32) -> DefaultBiomeMap {
+		DefaultBiomeMap {
+			noise_no_grass: noise::OctavedNoise::new(5, vec![seed, 3]),
+			noise_forest: noise::OctavedNoise::new(2, vec![seed, 5]),
+		}
+	}
+
+	fn is_no_grass(&self, coords: BlockCoords) -> bool {
+		let coordsf = coords.map(|x| x as f32);
+		let scale = 75.0;
+		self.noise_no_grass.sample_3d_1d(coordsf / scale, &[]) < 0.25
+	}
+
+	fn is_forest(&self, coords: BlockCoords) -> bool {
+		let coordsf = coords.map(|x| x as f32);
+		let scale = 75.0;
+		self.noise_forest.sample_3d_1d(coordsf / scale, &[]) < 0.35
+	}
+
+	fn biome_at(&self, coords: BlockCoords) -> Biome {
+		if self.is_no_grass(coords) {
+			Biome::Desert
+		} else if self.is_forest(coords) {
+			Biome::Forest
+		} else {
+			Biome::Plains
+		}
+	}
+}
+
+impl DefaultWorldGenerator {
+	/// Which structure types are found where depends on the biomes.
+	fn structure_origin_generator<'a>(
+		&self,
+		biome_index_at: &'a dyn Fn(BlockCoords) -> usize,
+	) -> TestStructureOriginGenerator<'a> {
+		let structure_type_table = StructureTypeTable::new(
+			Biome::ALL.map(Biome::structure_type_weights).to_vec(),
+			biome_index_at,
+		);
+		TestStructureOriginGenerator::with_type_table(self.seed, 31, (-3, 10), structure_type_table)
+	}
+
+	/// Villages are much rarer than other structures so they have their own origins,
+	/// that are kept far enough from each other for two villages to never overlap.
+	fn village_origin_generator(&self) -> PoissonDiskStructureOriginGenerator<'static> {
+		PoissonDiskStructureOriginGenerator::new(self.seed.wrapping_add(1), 120, 2, 1)
+	}
+}
+
 impl WorldGenerator for DefaultWorldGenerator {
 	fn generate_chunk_blocks_and_entities(
 		&self,
@@ -153,9 +303,11 @@ impl WorldGenerator for DefaultWorldGenerator {
 		// Define the terrain generation as a deterministic coords->block function.
 		let noise_a = noise::OctavedNoise::new(5, vec![self.seed, 1]);
 		let noise_b = noise::OctavedNoise::new(5, vec![self.seed, 2]);
-		let noise_no_grass = noise::OctavedNoise::new(5, vec![self.seed, 3]);
+		let biome_map = DefaultBiomeMap::new(self.seed);
 		let noise_grass_a = noise::OctavedNoise::new(2, vec![self.seed, 1, 1]);
+		let decorations = DecorationPlacer::new(self.seed);
 		let noise_grass_b = noise::OctavedNoise::new(2, vec![self.seed, 1, 2]);
+		let noise_frozen = noise::OctavedNoise::new(3, vec![self.seed, 6]);
 		let coords_to_ground = |coords: BlockCoords| -> bool {
 			let coordsf = coords.map(|x| x as f32);
 			let scale = 100.0;
@@ -176,10 +328,12 @@ impl WorldGenerator for DefaultWorldGenerator {
 			};
 			noise_grass_b.sample_3d_1d(coordsf, &[]) < density
 		};
-		let coords_to_no_grass = |coords: BlockCoords| -> bool {
+		let coords_to_no_grass = |coords: BlockCoords| -> bool { biome_map.is_no_grass(coords) };
+		// Patches of the grassy surface are frozen into ice.
+		let coords_to_frozen = |coords: BlockCoords| -> bool {
 			let coordsf = coords.map(|x| x as f32);
-			let scale = 75.0;
-			noise_no_grass.sample_3d_1d(coordsf / scale, &[]) < 0.25
+			let scale = 40.0;
+			noise_frozen.sample_3d_1d(coordsf / scale, &[]) < 0.2
 		};
 		let coords_to_generated_block = |coords: BlockCoords| -> Option<BlockTypeId> {
 			let coordsf = coords.map(|x| x as f32);
@@ -193,15 +347,17 @@ impl WorldGenerator for DefaultWorldGenerator {
 		let coords_to_terrain = |coords: BlockCoords| -> BlockTypeId {
 			let ground = coords_to_ground(coords);
 			if ground {
-				let ground_maybe_generated =
-					coords_to_generated_block(coords).unwrap_or(block_type_table.ground_id());
+				let generated_block = coords_to_generated_block(coords);
+				let ground_maybe_generated = generated_block.unwrap_or(block_type_table.ground_id());
 				let ground_above = coords_to_ground(coords + cgmath::vec3(0, 0, 1));
 				if ground_above {
 					ground_maybe_generated
 				} else {
 					let no_grass = coords_to_no_grass(coords);
 					if no_grass {
-						ground_maybe_generated
+						generated_block.unwrap_or(block_type_table.kinda_sand_id())
+					} else if coords_to_frozen(coords) {
+						block_type_table.kinda_ice_id()
 					} else {
 						block_type_table.kinda_grass_id()
 					}
@@ -209,9 +365,14 @@ impl WorldGenerator for DefaultWorldGenerator {
 			} else {
 				let ground_below = coords_to_ground(coords + cgmath::vec3(0, 0, -1));
 				if ground_below {
-					let no_grass_below = coords_to_no_grass(coords + cgmath::vec3(0, 0, -1));
+					let no_grass_below = coords_to_no_grass(coords + cgmath::vec3(0, 0, -1))
+						|| coords_to_frozen(coords + cgmath::vec3(0, 0, -1));
 					if no_grass_below {
 						block_type_table.air_id()
+					} else if let Some(decoration) =
+						decorations.decoration_on_grass(coords, block_type_table)
+					{
+						decoration
 					} else if coords_to_grass(coords) {
 						block_type_table.kinda_grass_blades_id()
 					} else {
@@ -225,83 +386,20 @@ impl WorldGenerator for DefaultWorldGenerator {
 
 		// Define structure generation.
 		let structure_max_blocky_radius = 42;
-		let noise_structure = noise::OctavedNoise::new(1, vec![self.seed, 4]);
-		let noise_tree_spawning = noise::OctavedNoise::new(2, vec![self.seed, 5]);
-		let noise_boulder_spawning = noise::OctavedNoise::new(2, vec![self.seed, 6]);
-		let spawn_tree = |coords: BlockCoords| -> bool {
-			let coordsf = coords.map(|x| x as f32);
-			let scale = 75.0;
-			noise_tree_spawning.sample_3d_1d(coordsf / scale, &[]) < 0.35
-		};
-		let spawn_boulder = |coords: BlockCoords| -> bool {
-			let coordsf = coords.map(|x| x as f32);
-			let scale = 75.0;
-			noise_boulder_spawning.sample_3d_1d(coordsf / scale, &[]) < 0.35
-		};
+		let biome_at = |coords: BlockCoords| -> Biome { biome_map.biome_at(coords) };
+		let biome_index_at = |coords: BlockCoords| -> usize { biome_at(coords) as usize };
+		let noise_tree = noise::OctavedNoise::new(1, vec![self.seed, 10]);
 		let generate_structure_tree = |mut context: StructureInstanceGenerationContext| {
-			if !spawn_tree(context.origin.coords) {
+			let Some(ground) =
+				context.find_terrain_ground_below(context.origin.coords, structure_max_blocky_radius)
+			else {
 				return;
-			}
-			// Let's generate a tree.
-			let mut placing_head = context.origin.coords;
-			// We try to find the ground (we don't want to generate a tree floating in the air).
-			// We go down and stop on ground, or abort (and fail to generate) if no ground is found.
-			let mut found_ground = false;
-			for _i in 0..structure_max_blocky_radius {
-				let no_ground_above = context
-					.block_type_table
-					.get((context.terrain_generator)(
-						placing_head + cgmath::vec3(0, 0, 1),
-					))
-					.unwrap()
-					.is_air();
-				let ground_here = !context
-					.block_type_table
-					.get((context.terrain_generator)(placing_head))
-					.unwrap()
-					.is_air();
-				if no_ground_above && ground_here {
-					found_ground = true;
-					break;
-				}
-				placing_head.z -= 1;
-			}
-			if !found_ground {
-				return;
-			}
-			// We are on the ground now, we can generate a tree.
-			// We pick a height of the trunk and generate it.
-			let noise_value_a = noise_structure.sample_i3d_1d(placing_head, &[1]);
-			let height =
-				((noise_value_a * 0.5 + 0.5) * structure_max_blocky_radius.min(15) as f32) as i32;
-			for _i in 0..height {
-				context.place_block(
-					&BlockPlacing {
-						block_type_to_place: context.block_type_table.kinda_wood_id(),
-						only_place_on_air: false,
-					},
-					placing_head,
-				);
-				placing_head.z += 1;
-			}
-			// We pick a radius for the ball of leaves and generate it.
-			let noise_value_b = noise_structure.sample_i3d_1d(placing_head, &[2]);
-			let ball_radius = (noise_value_b * 0.2 + 0.8) * 3.5;
-			context.place_ball(
-				&BlockPlacing {
-					block_type_to_place: context.block_type_table.kinda_leaf_id(),
-					only_place_on_air: true,
-				},
-				placing_head.map(|x| x as f32),
-				ball_radius,
-			);
-			// The tree is done now ^^.
+			};
+			let l_system = biome_at(ground).tree_l_system();
+			l_system::generate_tree(&mut context, &noise_tree, &l_system, ground);
 		};
 		let noise_structure = noise::OctavedNoise::new(1, vec![self.seed, 4]);
 		let generate_structure_boulder = |mut context: StructureInstanceGenerationContext| {
-			if !spawn_boulder(context.origin.coords) {
-				return;
-			}
 			let mut placing_head = context.origin.coords;
 			let mut found_ground = false;
 			for _i in 0..structure_max_blocky_radius {
@@ -344,12 +442,44 @@ impl WorldGenerator for DefaultWorldGenerator {
 			));
 		};
 
-		let structure_types: [&StructureTypeInstanceGenerator; 2] =
-			[&generate_structure_tree, &generate_structure_boulder];
+		let generate_structure_cactus = |mut context: StructureInstanceGenerationContext| {
+			let Some(ground) =
+				context.find_terrain_ground_below(context.origin.coords, structure_max_blocky_radius)
+			else {
+				return;
+			};
+			let cactus = BlockPlacing {
+				block_type_to_place: context.block_type_table.kinda_leaf_id(),
+				only_place_on_air: true,
+			};
+			let noise_value = noise_structure.sample_i3d_1d(ground, &[3]);
+			let height = 3 + (noise_value * 4.0) as i32;
+			for z in 1..=height {
+				context.place_block(&cactus, ground + cgmath::vec3(0, 0, z));
+			}
+			// An arm on one side, the structure transform decides which side.
+			if height >= 4 {
+				for z in (height / 2)..=(height / 2 + 2) {
+					context.place_block(&cactus, ground + cgmath::vec3(1, 0, z));
+				}
+			}
+		};
+
+		let noise_building = noise::OctavedNoise::new(1, vec![self.seed, 9]);
+		let generate_structure_building = |mut context: StructureInstanceGenerationContext| {
+			jigsaw::generate_building(&mut context, &noise_building, structure_max_blocky_radius);
+		};
+
+		// The order matches the structure type ids in `biomes`.
+		let structure_types: [&StructureTypeInstanceGenerator; STRUCTURE_TYPE_COUNT] = [
+			&generate_structure_tree,
+			&generate_structure_boulder,
+			&generate_structure_cactus,
+			&generate_structure_building,
+		];
 
 		// Setup structure origins generation stuff.
-		let structure_origin_generator =
-			TestStructureOriginGenerator::new(self.seed, 31, (-3, 10), structure_types.len() as i32);
+		let structure_origin_generator = self.structure_origin_generator(&biome_index_at);
 
 		// Now we generate the block data in the chunk.
 		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
@@ -360,15 +490,19 @@ impl WorldGenerator for DefaultWorldGenerator {
 			chunk_blocks.set_id(coords, coords_to_terrain(coords));
 		}
 
+		let noise_structure_transform = noise::OctavedNoise::new(1, vec![self.seed, 8]);
+
 		// Generate the structures that can overlap with the chunk.
 		let mut span_to_check = CubicCoordsSpan::from_chunk_span(coords_span);
 		span_to_check.add_margins(structure_max_blocky_radius);
 		let origins = structure_origin_generator.get_origins_in_span(span_to_check);
 		for origin in origins.into_iter() {
+			origin.annotate(&STRUCTURE_TYPE_NAMES);
 			let allowed_span =
 				CubicCoordsSpan::with_center_and_radius(origin.coords, structure_max_blocky_radius);
 			let context = StructureInstanceGenerationContext {
 				origin,
+				transform: StructureTransform::from_noise(&noise_structure_transform, origin.coords),
 				allowed_span,
 				chunk_blocks: &mut chunk_blocks,
 				chunk_entities: &mut chunk_entities,
@@ -379,8 +513,83 @@ impl WorldGenerator for DefaultWorldGenerator {
 			structure_types[origin.type_id.index](context);
 		}
 
+		// Generate the villages that can overlap with the chunk.
+		// Villages are much rarer than other structures so they have their own origins.
+		let noise_village = noise::OctavedNoise::new(1, vec![self.seed, 7]);
+		let village_origin_generator = self.village_origin_generator();
+		let origins = village_origin_generator.get_origins_in_span(span_to_check);
+		for origin in origins.into_iter() {
+			origin.annotate(&["village"]);
+			let allowed_span =
+				CubicCoordsSpan::with_center_and_radius(origin.coords, structure_max_blocky_radius);
+			let mut context = StructureInstanceGenerationContext {
+				origin,
+				// Villages already lay their houses out in all directions, and villagers remember
+				// the coords of the houses, which the transform would not follow.
+				transform: StructureTransform::IDENTITY,
+				allowed_span,
+				chunk_blocks: &mut chunk_blocks,
+				chunk_entities: &mut chunk_entities,
+				_origin_generator: &village_origin_generator,
+				block_type_table,
+				terrain_generator: &coords_to_terrain,
+			};
+			village::generate_village(
+				&mut context,
+				&noise_village,
+				id_generator,
+				structure_max_blocky_radius,
+			);
+		}
+
 		(chunk_blocks.finish_generation(), chunk_entities)
 	}
+
+	fn biome_at(&self, coords: BlockCoords) -> Option<Biome> {
+		Some(DefaultBiomeMap::new(self.seed).biome_at(coords))
+	}
+
+	fn structure_origins_in_span(
+		&self,
+		span: CubicCoordsSpan,
+	) -> Option<Vec<(&'static str, BlockCoords)>> {
+		let biome_map = DefaultBiomeMap::new(self.seed);
+		let biome_index_at = |coords: BlockCoords| -> usize { biome_map.biome_at(coords) as usize };
+		let structure_origins = self
+			.structure_origin_generator(&biome_index_at)
+			.get_origins_in_span(span)
+			.into_iter()
+			.map(|origin| (STRUCTURE_TYPE_NAMES[origin.type_id.index], origin.coords));
+		let village_origins = self
+			.village_origin_generator()
+			.get_origins_in_span(span)
+			.into_iter()
+			.map(|origin| ("village", origin.coords));
+		Some(structure_origins.chain(village_origins).collect())
+	}
+
+	fn structure_origins_debug_views(
+		&self,
+		span: CubicCoordsSpan,
+	) -> Option<Vec<StructureOriginsDebugView>> {
+		let biome_map = DefaultBiomeMap::new(self.seed);
+		let biome_index_at = |coords: BlockCoords| -> usize { biome_map.biome_at(coords) as usize };
+		let structures = StructureOriginsDebugView::new(
+			&self.structure_origin_generator(&biome_index_at),
+			span,
+			[0.4, 0.4, 0.4],
+			|type_id| (STRUCTURE_TYPE_NAMES[type_id.index], type_id.debug_color()),
+		);
+		// Villages get the color that would be the one of the structure type after the others.
+		let village_color = StructureTypeId { index: STRUCTURE_TYPE_COUNT }.debug_color();
+		let villages = StructureOriginsDebugView::new(
+			&self.village_origin_generator(),
+			span,
+			[0.8, 0.8, 0.8],
+			|_type_id| ("village", village_color),
+		);
+		Some(vec![structures, villages])
+	}
 }
 
 struct FlatWorldGenerator {}
@@ -449,6 +658,7 @@ impl WorldGenerator for WorldGeneratorLines01 {
 		let noise_b = noise::OctavedNoise::new(5, vec![self.seed, 2]);
 		let noise_no_grass = noise::OctavedNoise::new(5, vec![self.seed, 3]);
 		let noise_grass_a = noise::OctavedNoise::new(2, vec![self.seed, 1, 1]);
+		let decorations = DecorationPlacer::new(self.seed);
 		let noise_grass_b = noise::OctavedNoise::new(2, vec![self.seed, 1, 2]);
 		let coords_to_ground = |coords: BlockCoords| -> bool {
 			let coordsf = coords.map(|x| x as f32);
@@ -496,6 +706,10 @@ impl WorldGenerator for WorldGeneratorLines01 {
 					let no_grass_below = coords_to_no_grass(coords + cgmath::vec3(0, 0, -1));
 					if no_grass_below {
 						block_type_table.air_id()
+					} else if let Some(decoration) =
+						decorations.decoration_on_grass(coords, block_type_table)
+					{
+						decoration
 					} else if coords_to_grass(coords) {
 						block_type_table.kinda_grass_blades_id()
 					} else {
@@ -514,6 +728,9 @@ impl WorldGenerator for WorldGeneratorLines01 {
 	}
 }
 
+/// Distance between the points where the volume generators actually evaluate their noises.
+const DENSITY_FIELD_STEP: i32 = 4;
+
 struct WorldGeneratorVolumes01 {
 	pub(crate) seed: i32,
 }
@@ -526,12 +743,15 @@ impl WorldGenerator for WorldGeneratorVolumes01 {
 		_id_generator: &IdGenerator,
 	) -> (ChunkBlocks, ChunkEntities) {
 		let noise_a = noise::OctavedNoise::new(5, vec![self.seed, 1]);
-		let coords_to_ground = |coords: BlockCoords| -> bool {
+		let coords_to_density = |coords: BlockCoords| -> f32 {
 			let coordsf = coords.map(|x| x as f32);
 			let scale = 100.0;
 			let a = noise_a.sample_3d_1d(coordsf / scale, &[]);
-			a < 0.35
+			0.35 - a
 		};
+		let density_field =
+			DensityField::sample_for_chunk(coords_span, DENSITY_FIELD_STEP, coords_to_density);
+		let coords_to_ground = |coords: BlockCoords| -> bool { density_field.get(coords) > 0.0 };
 		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
 		for coords in chunk_blocks.coords_span().iter_coords() {
 			let ground = coords_to_ground(coords);
@@ -747,9 +967,13 @@ impl WorldGenerator for WorldGeneratorLinksX {
 		let noise_a = noise::OctavedNoise::new(1, vec![self.seed, 1]);
 		let noise_b = noise::OctavedNoise::new(1, vec![self.seed, 2]);
 		let noise_c = noise::OctavedNoise::new(1, vec![self.seed, 3]);
-		let noise_d = noise::OctavedNoise::new(4, vec![self.seed, 4]);
-		let noise_e = noise::OctavedNoise::new(4, vec![self.seed, 5]);
-		let noise_f = noise::OctavedNoise::new(4, vec![self.seed, 6]);
+		let warp = noise::DomainWarp::new(
+			noise::OctavedNoise::new(4, vec![self.seed, 4]),
+			noise::OctavedNoise::new(4, vec![self.seed, 5]),
+			noise::OctavedNoise::new(4, vec![self.seed, 6]),
+			30.0,
+			20.0,
+		);
 		let coords_to_ground_uwu = |coordsf: cgmath::Point3<f32>| -> bool {
 			let scale = 85.0;
 			let radius = 10.0;
@@ -772,16 +996,7 @@ impl WorldGenerator for WorldGeneratorLinksX {
 		};
 		let coords_to_ground = |coords: BlockCoords| -> bool {
 			let coordsf = coords.map(|x| x as f32);
-			let scale = 30.0;
-			let deformation_max_length = 20.0;
-			let d = noise_d.sample_3d_1d(coordsf / scale, &[]);
-			let e = noise_e.sample_3d_1d(coordsf / scale, &[]);
-			let f = noise_f.sample_3d_1d(coordsf / scale, &[]);
-			use crate::coords::AngularDirection;
-			let deformation = AngularDirection::from_angles(d * TAU, e * (TAU / 2.0)).to_vec3()
-				* f * deformation_max_length;
-			let deformed_coordsf = coordsf + deformation;
-			coords_to_ground_uwu(deformed_coordsf)
+			coords_to_ground_uwu(warp.warp_3d(coordsf))
 		};
 		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
 		for coords in chunk_blocks.coords_span().iter_coords() {
@@ -819,9 +1034,13 @@ impl WorldGenerator for WorldGeneratorLinks {
 		let noise_a = noise::OctavedNoise::new(1, vec![self.seed, 1]);
 		let noise_b = noise::OctavedNoise::new(1, vec![self.seed, 2]);
 		let noise_c = noise::OctavedNoise::new(1, vec![self.seed, 3]);
-		let noise_d = noise::OctavedNoise::new(4, vec![self.seed, 4]);
-		let noise_e = noise::OctavedNoise::new(4, vec![self.seed, 5]);
-		let noise_f = noise::OctavedNoise::new(4, vec![self.seed, 6]);
+		let warp = noise::DomainWarp::new(
+			noise::OctavedNoise::new(4, vec![self.seed, 4]),
+			noise::OctavedNoise::new(4, vec![self.seed, 5]),
+			noise::OctavedNoise::new(4, vec![self.seed, 6]),
+			30.0,
+			13.0,
+		);
 		let coords_to_ground_uwu = |coordsf: cgmath::Point3<f32>| -> bool {
 			let scale = 65.0;
 			let radius = 7.0;
@@ -854,16 +1073,7 @@ impl WorldGenerator for WorldGeneratorLinks {
 		};
 		let coords_to_ground = |coords: BlockCoords| -> bool {
 			let coordsf = coords.map(|x| x as f32);
-			let scale = 30.0;
-			let deformation_max_length = 13.0;
-			let d = noise_d.sample_3d_1d(coordsf / scale, &[]);
-			let e = noise_e.sample_3d_1d(coordsf / scale, &[]);
-			let f = noise_f.sample_3d_1d(coordsf / scale, &[]);
-			use crate::coords::AngularDirection;
-			let deformation = AngularDirection::from_angles(d * TAU, e * (TAU / 2.0)).to_vec3()
-				* f * deformation_max_length;
-			let deformed_coordsf = coordsf + deformation;
-			coords_to_ground_uwu(deformed_coordsf)
+			coords_to_ground_uwu(warp.warp_3d(coordsf))
 		};
 		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
 		for coords in chunk_blocks.coords_span().iter_coords() {
@@ -901,9 +1111,13 @@ impl WorldGenerator for WorldGeneratorLinksGround {
 		let noise_a = noise::OctavedNoise::new(1, vec![self.seed, 1]);
 		let noise_b = noise::OctavedNoise::new(1, vec![self.seed, 2]);
 		let noise_c = noise::OctavedNoise::new(1, vec![self.seed, 3]);
-		let noise_d = noise::OctavedNoise::new(4, vec![self.seed, 4]);
-		let noise_e = noise::OctavedNoise::new(4, vec![self.seed, 5]);
-		let noise_f = noise::OctavedNoise::new(4, vec![self.seed, 6]);
+		let warp = noise::DomainWarp::new(
+			noise::OctavedNoise::new(4, vec![self.seed, 4]),
+			noise::OctavedNoise::new(4, vec![self.seed, 5]),
+			noise::OctavedNoise::new(4, vec![self.seed, 6]),
+			30.0,
+			13.0,
+		);
 		let coords_to_ground_uwu = |coordsf: cgmath::Point3<f32>| -> bool {
 			if coordsf.z < 0.0 {
 				return true;
@@ -942,16 +1156,7 @@ impl WorldGenerator for WorldGeneratorLinksGround {
 		};
 		let coords_to_ground = |coords: BlockCoords| -> bool {
 			let coordsf = coords.map(|x| x as f32);
-			let scale = 30.0;
-			let deformation_max_length = 13.0;
-			let d = noise_d.sample_3d_1d(coordsf / scale, &[]);
-			let e = noise_e.sample_3d_1d(coordsf / scale, &[]);
-			let f = noise_f.sample_3d_1d(coordsf / scale, &[]);
-			use crate::coords::AngularDirection;
-			let deformation = AngularDirection::from_angles(d * TAU, e * (TAU / 2.0)).to_vec3()
-				* f * deformation_max_length;
-			let deformed_coordsf = coordsf + deformation;
-			coords_to_ground_uwu(deformed_coordsf)
+			coords_to_ground_uwu(warp.warp_3d(coordsf))
 		};
 		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
 		for coords in chunk_blocks.coords_span().iter_coords() {
@@ -989,9 +1194,13 @@ impl WorldGenerator for WorldGeneratorLinksCaves {
 		let noise_a = noise::OctavedNoise::new(1, vec![self.seed, 1]);
 		let noise_b = noise::OctavedNoise::new(1, vec![self.seed, 2]);
 		let noise_c = noise::OctavedNoise::new(1, vec![self.seed, 3]);
-		let noise_d = noise::OctavedNoise::new(4, vec![self.seed, 4]);
-		let noise_e = noise::OctavedNoise::new(4, vec![self.seed, 5]);
-		let noise_f = noise::OctavedNoise::new(4, vec![self.seed, 6]);
+		let warp = noise::DomainWarp::new(
+			noise::OctavedNoise::new(4, vec![self.seed, 4]),
+			noise::OctavedNoise::new(4, vec![self.seed, 5]),
+			noise::OctavedNoise::new(4, vec![self.seed, 6]),
+			30.0,
+			13.0,
+		);
 		let coords_to_ground_uwu = |coordsf: cgmath::Point3<f32>| -> bool {
 			if coordsf.z > 0.0 {
 				return false;
@@ -1027,16 +1236,7 @@ impl WorldGenerator for WorldGeneratorLinksCaves {
 		};
 		let coords_to_ground = |coords: BlockCoords| -> bool {
 			let coordsf = coords.map(|x| x as f32);
-			let scale = 30.0;
-			let deformation_max_length = 13.0;
-			let d = noise_d.sample_3d_1d(coordsf / scale, &[]);
-			let e = noise_e.sample_3d_1d(coordsf / scale, &[]);
-			let f = noise_f.sample_3d_1d(coordsf / scale, &[]);
-			use crate::coords::AngularDirection;
-			let deformation = AngularDirection::from_angles(d * TAU, e * (TAU / 2.0)).to_vec3()
-				* f * deformation_max_length;
-			let deformed_coordsf = coordsf + deformation;
-			coords_to_ground_uwu(deformed_coordsf)
+			coords_to_ground_uwu(warp.warp_3d(coordsf))
 		};
 		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
 		for coords in chunk_blocks.coords_span().iter_coords() {
@@ -1074,10 +1274,14 @@ impl WorldGenerator for WorldGeneratorLinks02 {
 		let noise_a = noise::OctavedNoise::new(1, vec![self.seed, 1]);
 		let noise_b = noise::OctavedNoise::new(1, vec![self.seed, 2]);
 		let noise_c = noise::OctavedNoise::new(1, vec![self.seed, 3]);
-		let noise_d = noise::OctavedNoise::new(4, vec![self.seed, 4]);
-		let noise_e = noise::OctavedNoise::new(4, vec![self.seed, 5]);
-		let noise_f = noise::OctavedNoise::new(4, vec![self.seed, 6]);
 		let noise_g = noise::OctavedNoise::new(1, vec![self.seed, 7]);
+		let warp = noise::DomainWarp::new(
+			noise::OctavedNoise::new(4, vec![self.seed, 4]),
+			noise::OctavedNoise::new(4, vec![self.seed, 5]),
+			noise::OctavedNoise::new(4, vec![self.seed, 6]),
+			30.0,
+			25.0,
+		);
 		let coords_to_ground_uwu = |coordsf: cgmath::Point3<f32>| -> bool {
 			let scale = 65.0;
 			let radius = 7.0;
@@ -1135,16 +1339,7 @@ impl WorldGenerator for WorldGeneratorLinks02 {
 		};
 		let coords_to_ground = |coords: BlockCoords| -> bool {
 			let coordsf = coords.map(|x| x as f32);
-			let scale = 30.0;
-			let deformation_max_length = 25.0;
-			let d = noise_d.sample_3d_1d(coordsf / scale, &[]);
-			let e = noise_e.sample_3d_1d(coordsf / scale, &[]);
-			let f = noise_f.sample_3d_1d(coordsf / scale, &[]);
-			use crate::coords::AngularDirection;
-			let deformation = AngularDirection::from_angles(d * TAU, e * (TAU / 2.0)).to_vec3()
-				* f * deformation_max_length;
-			let deformed_coordsf = coordsf + deformation;
-			coords_to_ground_uwu(deformed_coordsf)
+			coords_to_ground_uwu(warp.warp_3d(coordsf))
 		};
 		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
 		for coords in chunk_blocks.coords_span().iter_coords() {
@@ -1182,93 +1377,86 @@ impl WorldGenerator for WorldGeneratorLinksFlat {
 		let noise_a = noise::OctavedNoise::new(1, vec![self.seed, 1]);
 		let noise_b = noise::OctavedNoise::new(1, vec![self.seed, 2]);
 		let noise_c = noise::OctavedNoise::new(1, vec![self.seed, 3]);
-		let noise_d = noise::OctavedNoise::new(4, vec![self.seed, 4]);
-		let noise_e = noise::OctavedNoise::new(4, vec![self.seed, 5]);
-		let noise_f = noise::OctavedNoise::new(4, vec![self.seed, 6]);
 		let noise_g = noise::OctavedNoise::new(1, vec![self.seed, 7]);
-		let coords_to_ground_uwu =
-			|coordsf: cgmath::Point3<f32>| -> bool {
-				let scale = 55.0;
-				let radius = 7.0;
-				let coordsf_to_the = |coordsf: cgmath::Point3<f32>| -> cgmath::Point3<f32> {
+		let warp = noise::DomainWarp::new(
+			noise::OctavedNoise::new(4, vec![self.seed, 4]),
+			noise::OctavedNoise::new(4, vec![self.seed, 5]),
+			noise::OctavedNoise::new(4, vec![self.seed, 6]),
+			30.0,
+			14.0,
+		)
+		.with_vertical_strength(4.0);
+		let coords_to_ground_uwu = |coordsf: cgmath::Point3<f32>| -> bool {
+			let scale = 55.0;
+			let radius = 7.0;
+			let coordsf_to_the = |coordsf: cgmath::Point3<f32>| -> cgmath::Point3<f32> {
+				let coordsf_i_scaled = coordsf.map(|x| (x / scale).floor());
+				let a = noise_a.sample_3d_1d(coordsf_i_scaled, &[]);
+				let b = noise_b.sample_3d_1d(coordsf_i_scaled, &[]);
+				let c = noise_c.sample_3d_1d(coordsf_i_scaled, &[]);
+				let coordsf_min = coordsf.map(|x| (x / scale).floor() * scale);
+				let _coordsf_max = coordsf.map(|x| (x / scale).ceil() * scale);
+				let the = cgmath::vec3(a, b, c).map(|x| radius + x * (scale - 2.0 * radius));
+				coordsf_min + the
+			};
+			let coordsf_to_link_negativewards =
+				|coordsf: cgmath::Point3<f32>, axis: NonOrientedAxis| -> bool {
 					let coordsf_i_scaled = coordsf.map(|x| (x / scale).floor());
-					let a = noise_a.sample_3d_1d(coordsf_i_scaled, &[]);
-					let b = noise_b.sample_3d_1d(coordsf_i_scaled, &[]);
-					let c = noise_c.sample_3d_1d(coordsf_i_scaled, &[]);
-					let coordsf_min = coordsf.map(|x| (x / scale).floor() * scale);
-					let _coordsf_max = coordsf.map(|x| (x / scale).ceil() * scale);
-					let the = cgmath::vec3(a, b, c).map(|x| radius + x * (scale - 2.0 * radius));
-					coordsf_min + the
-				};
-				let coordsf_to_link_negativewards =
-					|coordsf: cgmath::Point3<f32>, axis: NonOrientedAxis| -> bool {
-						let coordsf_i_scaled = coordsf.map(|x| (x / scale).floor());
-						let axis_channel = axis.index() as i32;
-						let g = noise_g.sample_3d_1d(coordsf_i_scaled, &[axis_channel]);
-						g < 0.5
-					};
-				let in_link = |a: cgmath::Point3<f32>,
-				               b: cgmath::Point3<f32>,
-				               coordsf: cgmath::Point3<f32>,
-				               radius: f32|
-				 -> bool {
-					let dist = distance_to_segment(a, b, coordsf);
-					if dist < radius {
-						let dist_above = distance_to_segment(a, b, coordsf + cgmath::vec3(0.0, 0.0, 1.0));
-						dist_above < dist
-					} else {
-						false
-					}
+					let axis_channel = axis.index() as i32;
+					let g = noise_g.sample_3d_1d(coordsf_i_scaled, &[axis_channel]);
+					g < 0.5
 				};
-				let the = coordsf_to_the(coordsf);
-				let xp = coordsf_to_the(coordsf + cgmath::vec3(1.0, 0.0, 0.0) * scale);
-				let xm = coordsf_to_the(coordsf - cgmath::vec3(1.0, 0.0, 0.0) * scale);
-				let yp = coordsf_to_the(coordsf + cgmath::vec3(0.0, 1.0, 0.0) * scale);
-				let ym = coordsf_to_the(coordsf - cgmath::vec3(0.0, 1.0, 0.0) * scale);
-				let zp = coordsf_to_the(coordsf + cgmath::vec3(0.0, 0.0, 1.0) * scale);
-				let zm = coordsf_to_the(coordsf - cgmath::vec3(0.0, 0.0, 1.0) * scale);
-				let vxp = in_link(the, xp, coordsf, radius);
-				let vxm = in_link(the, xm, coordsf, radius);
-				let vyp = in_link(the, yp, coordsf, radius);
-				let vym = in_link(the, ym, coordsf, radius);
-				let vzp = in_link(the, zp, coordsf, radius);
-				let vzm = in_link(the, zm, coordsf, radius);
-				let lxp = coordsf_to_link_negativewards(
-					coordsf + cgmath::vec3(1.0, 0.0, 0.0) * scale,
-					NonOrientedAxis::X,
-				);
-				let lxm = coordsf_to_link_negativewards(coordsf, NonOrientedAxis::X);
-				let lyp = coordsf_to_link_negativewards(
-					coordsf + cgmath::vec3(0.0, 1.0, 0.0) * scale,
-					NonOrientedAxis::Y,
-				);
-				let lym = coordsf_to_link_negativewards(coordsf, NonOrientedAxis::Y);
-				let lzp = coordsf_to_link_negativewards(
-					coordsf + cgmath::vec3(0.0, 0.0, 1.0) * scale,
-					NonOrientedAxis::Z,
-				);
-				let lzm = coordsf_to_link_negativewards(coordsf, NonOrientedAxis::Z);
-				(lxp && vxp)
-					|| (lxm && vxm) || (lyp && vyp)
-					|| (lym && vym) || (lzp && vzp)
-					|| (lzm && vzm)
+			let in_link = |a: cgmath::Point3<f32>,
+			               b: cgmath::Point3<f32>,
+			               coordsf: cgmath::Point3<f32>,
+			               radius: f32|
+			 -> bool {
+				let dist = distance_to_segment(a, b, coordsf);
+				if dist < radius {
+					let dist_above = distance_to_segment(a, b, coordsf + cgmath::vec3(0.0, 0.0, 1.0));
+					dist_above < dist
+				} else {
+					false
+				}
 			};
+			let the = coordsf_to_the(coordsf);
+			let xp = coordsf_to_the(coordsf + cgmath::vec3(1.0, 0.0, 0.0) * scale);
+			let xm = coordsf_to_the(coordsf - cgmath::vec3(1.0, 0.0, 0.0) * scale);
+			let yp = coordsf_to_the(coordsf + cgmath::vec3(0.0, 1.0, 0.0) * scale);
+			let ym = coordsf_to_the(coordsf - cgmath::vec3(0.0, 1.0, 0.0) * scale);
+			let zp = coordsf_to_the(coordsf + cgmath::vec3(0.0, 0.0, 1.0) * scale);
+			let zm = coordsf_to_the(coordsf - cgmath::vec3(0.0, 0.0, 1.0) * scale);
+			let vxp = in_link(the, xp, coordsf, radius);
+			let vxm = in_link(the, xm, coordsf, radius);
+			let vyp = in_link(the, yp, coordsf, radius);
+			let vym = in_link(the, ym, coordsf, radius);
+			let vzp = in_link(the, zp, coordsf, radius);
+			let vzm = in_link(the, zm, coordsf, radius);
+			let lxp = coordsf_to_link_negativewards(
+				coordsf + cgmath::vec3(1.0, 0.0, 0.0) * scale,
+				NonOrientedAxis::X,
+			);
+			let lxm = coordsf_to_link_negativewards(coordsf, NonOrientedAxis::X);
+			let lyp = coordsf_to_link_negativewards(
+				coordsf + cgmath::vec3(0.0, 1.0, 0.0) * scale,
+				NonOrientedAxis::Y,
+			);
+			let lym = coordsf_to_link_negativewards(coordsf, NonOrientedAxis::Y);
+			let lzp = coordsf_to_link_negativewards(
+				coordsf + cgmath::vec3(0.0, 0.0, 1.0) * scale,
+				NonOrientedAxis::Z,
+			);
+			let lzm = coordsf_to_link_negativewards(coordsf, NonOrientedAxis::Z);
+			(lxp && vxp)
+				|| (lxm && vxm)
+				|| (lyp && vyp)
+				|| (lym && vym)
+				|| (lzp && vzp)
+				|| (lzm && vzm)
+		};
 		let coords_to_ground = |coords: BlockCoords| -> bool {
 			let coordsf = coords.map(|x| x as f32);
-			let scale = 30.0;
-			let horizontal_deformation_max_length = 14.0;
-			let vertical_deformation_max_length = 4.0;
-			let d = noise_d.sample_3d_1d(coordsf / scale, &[]);
-			let e = noise_e.sample_3d_1d(coordsf / scale, &[]);
-			let f = noise_f.sample_3d_1d(coordsf / scale, &[]);
-			use crate::coords::AngularDirection;
-			let mut deformation =
-				AngularDirection::from_angles(d * TAU, e * (TAU / 2.0)).to_vec3() * f;
-			deformation.x *= horizontal_deformation_max_length;
-			deformation.y *= horizontal_deformation_max_length;
-			deformation.z *= vertical_deformation_max_length;
-			let deformed_coordsf = coordsf + deformation;
-			coords_to_ground_uwu(deformed_coordsf)
+			coords_to_ground_uwu(warp.warp_3d(coordsf))
 		};
 		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
 		for coords in chunk_blocks.coords_span().iter_coords() {
@@ -1308,11 +1496,17 @@ impl WorldGenerator for WorldGeneratorSkyIslands {
 		let noise_c = noise::OctavedNoise::new(1, vec![self.seed, 3]);
 		let noise_d = noise::OctavedNoise::new(1, vec![self.seed, 4]);
 		let noise_e = noise::OctavedNoise::new(1, vec![self.seed, 5]);
-		let noise_f = noise::OctavedNoise::new(4, vec![self.seed, 6]);
-		let noise_g = noise::OctavedNoise::new(4, vec![self.seed, 7]);
-		let noise_h = noise::OctavedNoise::new(4, vec![self.seed, 8]);
 		let noise_grass_a = noise::OctavedNoise::new(2, vec![self.seed, 1, 1]);
+		let decorations = DecorationPlacer::new(self.seed);
 		let noise_grass_b = noise::OctavedNoise::new(2, vec![self.seed, 1, 2]);
+		let warp = noise::DomainWarp::new(
+			noise::OctavedNoise::new(4, vec![self.seed, 6]),
+			noise::OctavedNoise::new(4, vec![self.seed, 7]),
+			noise::OctavedNoise::new(4, vec![self.seed, 8]),
+			30.0,
+			14.0,
+		)
+		.with_vertical_strength(4.0);
 		let coords_to_ground_uwu = |coordsf: cgmath::Point3<f32>| -> bool {
 			let scale = 100.0;
 			let min_radius = 4.0;
@@ -1336,20 +1530,7 @@ impl WorldGenerator for WorldGeneratorSkyIslands {
 		};
 		let coords_to_ground = |coords: BlockCoords| -> bool {
 			let coordsf = coords.map(|x| x as f32);
-			let scale = 30.0;
-			let horizontal_deformation_max_length = 14.0;
-			let vertical_deformation_max_length = 4.0;
-			let f = noise_f.sample_3d_1d(coordsf / scale, &[]);
-			let g = noise_g.sample_3d_1d(coordsf / scale, &[]);
-			let h = noise_h.sample_3d_1d(coordsf / scale, &[]);
-			use crate::coords::AngularDirection;
-			let mut deformation =
-				AngularDirection::from_angles(f * TAU, g * (TAU / 2.0)).to_vec3() * h;
-			deformation.x *= horizontal_deformation_max_length;
-			deformation.y *= horizontal_deformation_max_length;
-			deformation.z *= vertical_deformation_max_length;
-			let deformed_coordsf = coordsf + deformation;
-			coords_to_ground_uwu(deformed_coordsf)
+			coords_to_ground_uwu(warp.warp_3d(coordsf))
 		};
 		let coords_to_grass = |coords: BlockCoords| -> bool {
 			let coordsf = coords.map(|x| x as f32);
@@ -1376,7 +1557,12 @@ impl WorldGenerator for WorldGeneratorSkyIslands {
 				}
 			} else {
 				let ground_below = coords_to_ground(coords + cgmath::vec3(0, 0, -1));
-				if ground_below && coords_to_grass(coords) {
+				let decoration = ground_below
+					.then(|| decorations.decoration_on_grass(coords, block_type_table))
+					.flatten();
+				if let Some(decoration) = decoration {
+					decoration
+				} else if ground_below && coords_to_grass(coords) {
 					block_type_table.kinda_grass_blades_id()
 				} else {
 					block_type_table.air_id()
@@ -1408,7 +1594,7 @@ impl WorldGenerator for WorldGeneratorVolumes02 {
 		let noise_d = noise::OctavedNoise::new(5, vec![self.seed, 4]);
 		let noise_e = noise::OctavedNoise::new(5, vec![self.seed, 5]);
 		let noise_f = noise::OctavedNoise::new(5, vec![self.seed, 6]);
-		let coords_to_ground = |coords: BlockCoords| -> bool {
+		let coords_to_density = |coords: BlockCoords| -> f32 {
 			let coordsf = coords.map(|x| x as f32);
 			let scale = 100.0;
 			let a = noise_a.sample_3d_1d(coordsf / scale, &[]);
@@ -1421,8 +1607,12 @@ impl WorldGenerator for WorldGeneratorVolumes02 {
 			let f = noise_f.sample_3d_1d(coordsf / detail_scale, &[]);
 			let def = cgmath::vec3(d - 0.5, e - 0.5, f - 0.5).normalize();
 			let uwu = abc.dot(def);
-			uwu < -0.4 && def.z < 0.0
+			// Positive where `uwu < -0.4 && def.z < 0.0`.
+			-(uwu + 0.4).max(def.z)
 		};
+		let density_field =
+			DensityField::sample_for_chunk(coords_span, DENSITY_FIELD_STEP, coords_to_density);
+		let coords_to_ground = |coords: BlockCoords| -> bool { density_field.get(coords) > 0.0 };
 		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
 		for coords in chunk_blocks.coords_span().iter_coords() {
 			let ground = coords_to_ground(coords);
@@ -1467,9 +1657,12 @@ impl WorldGenerator for WorldGeneratorVolumes03 {
 			let c = noise_c.sample_3d_1d(coordsf / scale, &[]);
 			a.max(b).max(c) + a - c
 		};
-		let coords_to_ground = |coords: BlockCoords| -> bool {
-			coords_to_ground_uwu(coords) < coords_to_ground_uwu(coords + cgmath::vec3(0, 0, 1))
+		let coords_to_density = |coords: BlockCoords| -> f32 {
+			coords_to_ground_uwu(coords + cgmath::vec3(0, 0, 1)) - coords_to_ground_uwu(coords)
 		};
+		let density_field =
+			DensityField::sample_for_chunk(coords_span, DENSITY_FIELD_STEP, coords_to_density);
+		let coords_to_ground = |coords: BlockCoords| -> bool { density_field.get(coords) > 0.0 };
 		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
 		for coords in chunk_blocks.coords_span().iter_coords() {
 			let ground = coords_to_ground(coords);
@@ -1801,8 +1994,12 @@ impl WorldGenerator for WorldGeneratorHeight02 {
 	}
 }
 
+/// Where the erosion deposited at least that much sediment, there is no grass on top.
+const ERODED_TERRAIN_SEDIMENT_THRESHOLD: f32 = 0.5;
+
 struct WorldGeneratorHeightBiomes {
 	pub(crate) seed: i32,
+	pub(crate) erosion: Option<Erosion>,
 }
 
 impl WorldGenerator for WorldGeneratorHeightBiomes {
@@ -1874,18 +2071,24 @@ impl WorldGenerator for WorldGeneratorHeightBiomes {
 			}
 			get_height(0, &values, &biome_heights).0
 		};
-
-		let coords_to_ground = |coords: BlockCoords| -> bool {
-			let height = coords_to_biome_height(coords);
-			let coordsf = coords.map(|x| x as f32);
-			coordsf.z < height
+		let coords_xy_to_raw_height = |coords_xy: cgmath::Point2<i32>| -> f32 {
+			coords_to_biome_height(cgmath::point3(coords_xy.x, coords_xy.y, 0))
 		};
+
 		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
 		for coords in chunk_blocks.coords_span().iter_coords() {
-			let ground = coords_to_ground(coords);
+			let coords_xy = cgmath::point2(coords.x, coords.y);
+			let raw_height = coords_xy_to_raw_height(coords_xy);
+			let height_change = self.erosion.as_ref().map_or(0.0, |erosion| {
+				erosion.height_change(coords_xy, &coords_xy_to_raw_height)
+			});
+			let height = raw_height + height_change;
+			let coordsf = coords.map(|x| x as f32);
+			let ground = coordsf.z < height;
 			let block = if ground {
-				let ground_above = coords_to_ground(coords + cgmath::vec3(0, 0, 1));
-				if ground_above {
+				let ground_above = coordsf.z + 1.0 < height;
+				let sediment_deposit = height_change > ERODED_TERRAIN_SEDIMENT_THRESHOLD;
+				if ground_above || sediment_deposit {
 					block_type_table.ground_id()
 				} else {
 					block_type_table.kinda_grass_id()
@@ -1999,6 +2202,7 @@ impl WorldGenerator for WorldGeneratorHeightBiomesVolume {
 
 struct WorldGeneratorHeight03 {
 	pub(crate) seed: i32,
+	pub(crate) erosion: Option<Erosion>,
 }
 
 impl WorldGenerator for WorldGeneratorHeight03 {
@@ -2009,19 +2213,25 @@ impl WorldGenerator for WorldGeneratorHeight03 {
 		_id_generator: &IdGenerator,
 	) -> (ChunkBlocks, ChunkEntities) {
 		let noise_a = noise::OctavedNoise::new(3, vec![self.seed, 1]);
-		let coords_to_ground = |coords: BlockCoords| -> bool {
-			let coordsf = coords.map(|x| x as f32);
-			let coordsf_xy = cgmath::point2(coordsf.x, coordsf.y);
+		let coords_xy_to_raw_height = |coords_xy: cgmath::Point2<i32>| -> f32 {
+			let coordsf_xy = coords_xy.map(|x| x as f32);
 			let scale = 60.0;
-			let height = 20.0 * noise_a.sample_2d_1d(coordsf_xy / scale, &[]);
-			coordsf.z < height
+			20.0 * noise_a.sample_2d_1d(coordsf_xy / scale, &[])
 		};
 		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
 		for coords in chunk_blocks.coords_span().iter_coords() {
-			let ground = coords_to_ground(coords);
+			let coords_xy = cgmath::point2(coords.x, coords.y);
+			let raw_height = coords_xy_to_raw_height(coords_xy);
+			let height_change = self.erosion.as_ref().map_or(0.0, |erosion| {
+				erosion.height_change(coords_xy, &coords_xy_to_raw_height)
+			});
+			let height = raw_height + height_change;
+			let coordsf = coords.map(|x| x as f32);
+			let ground = coordsf.z < height;
 			let block = if ground {
-				let ground_above = coords_to_ground(coords + cgmath::vec3(0, 0, 1));
-				if ground_above {
+				let ground_above = coordsf.z + 1.0 < height;
+				let sediment_deposit = height_change > ERODED_TERRAIN_SEDIMENT_THRESHOLD;
+				if ground_above || sediment_deposit {
 					block_type_table.ground_id()
 				} else {
 					block_type_table.kinda_grass_id()
@@ -2073,13 +2283,13 @@ impl WorldGenerator for WorldGeneratorStructuresPoc {
 		};
 
 		// Setup structure origins generation stuff.
-		let noise_cell_data = noise::OctavedNoise::new(1, vec![self.seed, 2]);
+		let hash_cell_data = noise::hash::Hash::new(&[self.seed, 2]);
 		let cell_size = 37;
 		let block_coords_to_cell_coords = |block_coords: BlockCoords| -> cgmath::Point3<i32> {
 			block_coords.map(|x| x.div_euclid(cell_size))
 		};
 		let cell_coords_to_number_of_structure_origins = |cell_coords: cgmath::Point3<i32>| -> usize {
-			let v = noise_cell_data.sample(&[], &[&[cell_coords.x, cell_coords.y, cell_coords.z, 1]]);
+			let v = hash_cell_data.with_coords_3d(cell_coords).with_channel(1).unit();
 			(v * 6.0 - 2.0).max(0.0).floor() as usize
 		};
 		let cell_coords_and_structure_origin_index_to_origin_coords_in_world =
@@ -2087,16 +2297,10 @@ impl WorldGenerator for WorldGeneratorStructuresPoc {
 				let xyz: SmallVec<[f32; 3]> = [0, 1, 2]
 					.into_iter()
 					.map(|axis| {
-						noise_cell_data.sample(
-							&[],
-							&[&[
-								cell_coords.x,
-								cell_coords.y,
-								cell_coords.z,
-								1 + axis,
-								origin_index as i32,
-							]],
-						)
+						hash_cell_data
+							.with_coords_3d(cell_coords)
+							.with(&[1 + axis, origin_index as i32])
+							.unit()
 					})
 					.collect();
 				let coords_in_unit_cube = cgmath::point3(xyz[0], xyz[1], xyz[2]);
@@ -2242,13 +2446,13 @@ impl WorldGenerator for WorldGeneratorStructuresLinksPoc {
 		};
 
 		// Setup structure origins generation stuff.
-		let noise_cell_data = noise::OctavedNoise::new(1, vec![self.seed, 2]);
+		let hash_cell_data = noise::hash::Hash::new(&[self.seed, 2]);
 		let cell_size = 37;
 		let block_coords_to_cell_coords = |block_coords: BlockCoords| -> cgmath::Point3<i32> {
 			block_coords.map(|x| x.div_euclid(cell_size))
 		};
 		let cell_coords_to_number_of_structure_origins = |cell_coords: cgmath::Point3<i32>| -> usize {
-			let v = noise_cell_data.sample(&[], &[&[cell_coords.x, cell_coords.y, cell_coords.z, 1]]);
+			let v = hash_cell_data.with_coords_3d(cell_coords).with_channel(1).unit();
 			(v * 6.0 - 2.0).max(0.0).floor() as usize
 		};
 		let cell_coords_and_structure_origin_index_to_origin_coords_in_world =
@@ -2256,16 +2460,10 @@ impl WorldGenerator for WorldGeneratorStructuresLinksPoc {
 				let xyz: SmallVec<[f32; 3]> = [0, 1, 2]
 					.into_iter()
 					.map(|axis| {
-						noise_cell_data.sample(
-							&[],
-							&[&[
-								cell_coords.x,
-								cell_coords.y,
-								cell_coords.z,
-								1 + axis,
-								origin_index as i32,
-							]],
-						)
+						hash_cell_data
+							.with_coords_3d(cell_coords)
+							.with(&[1 + axis, origin_index as i32])
+							.unit()
 					})
 					.collect();
 				let coords_in_unit_cube = cgmath::point3(xyz[0], xyz[1], xyz[2]);
@@ -2351,35 +2549,15 @@ impl WorldGenerator for WorldGeneratorStructuresLinksPoc {
 						}
 						if structure_origin_can_link(other_origin_coords) {
 							// Hehe found one, let's decide if we link.
-							// We get two noise values that we will also get (in the other order)
-							// in the other structure, and when we add them we get the same value
-							// that the other would get, so we can agree on something that way ^^.
-							let value_us_to_other = noise_a.sample(
-								&[],
-								&[&[
-									origin_block_coords.x,
-									origin_block_coords.y,
-									origin_block_coords.z,
-									other_origin_coords.x,
-									other_origin_coords.y,
-									other_origin_coords.z,
-								]],
-							);
-							let value_other_to_us = noise_a.sample(
-								&[],
-								&[&[
-									other_origin_coords.x,
-									other_origin_coords.y,
-									other_origin_coords.z,
-									origin_block_coords.x,
-									origin_block_coords.y,
-									origin_block_coords.z,
-								]],
-							);
 							// We only link to a few other structures because if we linked
 							// to everyone we could then it fills the world with links
 							// and it becomes difficult to see and appreciate the generation.
-							let link = (value_us_to_other + value_other_to_us) * 0.5 < 0.08;
+							let link = agree_to_link(
+								noise_a.hash(),
+								origin_block_coords,
+								other_origin_coords,
+								0.08,
+							);
 
 							if link {
 								// Let's link!
@@ -2515,13 +2693,13 @@ impl WorldGenerator for WorldGeneratorStructuresTrees {
 		};
 
 		// Setup structure origins generation stuff.
-		let noise_cell_data = noise::OctavedNoise::new(1, vec![self.seed, 2]);
+		let hash_cell_data = noise::hash::Hash::new(&[self.seed, 2]);
 		let cell_size = 37;
 		let block_coords_to_cell_coords = |block_coords: BlockCoords| -> cgmath::Point3<i32> {
 			block_coords.map(|x| x.div_euclid(cell_size))
 		};
 		let cell_coords_to_number_of_structure_origins = |cell_coords: cgmath::Point3<i32>| -> usize {
-			let v = noise_cell_data.sample(&[], &[&[cell_coords.x, cell_coords.y, cell_coords.z, 1]]);
+			let v = hash_cell_data.with_coords_3d(cell_coords).with_channel(1).unit();
 			((v * 6.0 - 2.0) * 3.0).max(0.0).floor() as usize
 		};
 		let cell_coords_and_structure_origin_index_to_origin_coords_in_world =
@@ -2529,16 +2707,10 @@ impl WorldGenerator for WorldGeneratorStructuresTrees {
 				let xyz: SmallVec<[f32; 3]> = [0, 1, 2]
 					.into_iter()
 					.map(|axis| {
-						noise_cell_data.sample(
-							&[],
-							&[&[
-								cell_coords.x,
-								cell_coords.y,
-								cell_coords.z,
-								1 + axis,
-								origin_index as i32,
-							]],
-						)
+						hash_cell_data
+							.with_coords_3d(cell_coords)
+							.with(&[1 + axis, origin_index as i32])
+							.unit()
 					})
 					.collect();
 				let coords_in_unit_cube = cgmath::point3(xyz[0], xyz[1], xyz[2]);
@@ -2564,7 +2736,7 @@ impl WorldGenerator for WorldGeneratorStructuresTrees {
 		// generation can place blocks. A radius of 1 means just the origin block, a
 		// radius of 2 means a 3x3x3 blocks sized cube around the origin block, etc.
 		let structure_max_blocky_radius = 42;
-		let noise_structure = noise::OctavedNoise::new(1, vec![self.seed, 3]);
+		let hash_structure = noise::hash::Hash::new(&[self.seed, 3]);
 		let generate_structure =
 			|origin_block_coords: BlockCoords, chunk_blocks: &mut ChunkBlocksBeingGenerated| {
 				let mut placing_head = origin_block_coords;
@@ -2589,13 +2761,11 @@ impl WorldGenerator for WorldGeneratorStructuresTrees {
 				if !found_ground {
 					return;
 				}
-				let noise_value_a =
-					noise_structure.sample(&[], &[&[placing_head.x, placing_head.y, placing_head.z, 1]]);
+				let noise_value_a = hash_structure.with_coords_3d(placing_head).with_channel(1).unit();
 				let height =
 					((noise_value_a * 0.5 + 0.5) * structure_max_blocky_radius.min(11) as f32) as i32;
 				placing_head.z += height;
-				let noise_value_b =
-					noise_structure.sample(&[], &[&[placing_head.x, placing_head.y, placing_head.z, 2]]);
+				let noise_value_b = hash_structure.with_coords_3d(placing_head).with_channel(2).unit();
 				let ball_radius = (noise_value_b * 0.2 + 0.8) * 3.5;
 				for coords in
 					crate::coords::iter_3d_cube_center_radius(placing_head, ball_radius.ceil() as i32)
@@ -2706,13 +2876,13 @@ impl WorldGenerator for WorldGeneratorStructuresSpikes {
 		};
 
 		// Setup structure origins generation stuff.
-		let noise_cell_data = noise::OctavedNoise::new(1, vec![self.seed, 2]);
+		let hash_cell_data = noise::hash::Hash::new(&[self.seed, 2]);
 		let cell_size = 37;
 		let block_coords_to_cell_coords = |block_coords: BlockCoords| -> cgmath::Point3<i32> {
 			block_coords.map(|x| x.div_euclid(cell_size))
 		};
 		let cell_coords_to_number_of_structure_origins = |cell_coords: cgmath::Point3<i32>| -> usize {
-			let v = noise_cell_data.sample(&[], &[&[cell_coords.x, cell_coords.y, cell_coords.z, 1]]);
+			let v = hash_cell_data.with_coords_3d(cell_coords).with_channel(1).unit();
 			(v * 3.5 - 2.0).max(0.0).floor() as usize
 		};
 		let cell_coords_and_structure_origin_index_to_origin_coords_in_world =
@@ -2720,16 +2890,10 @@ impl WorldGenerator for WorldGeneratorStructuresSpikes {
 				let xyz: SmallVec<[f32; 3]> = [0, 1, 2]
 					.into_iter()
 					.map(|axis| {
-						noise_cell_data.sample(
-							&[],
-							&[&[
-								cell_coords.x,
-								cell_coords.y,
-								cell_coords.z,
-								1 + axis,
-								origin_index as i32,
-							]],
-						)
+						hash_cell_data
+							.with_coords_3d(cell_coords)
+							.with(&[1 + axis, origin_index as i32])
+							.unit()
 					})
 					.collect();
 				let coords_in_unit_cube = cgmath::point3(xyz[0], xyz[1], xyz[2]);
@@ -2755,7 +2919,7 @@ impl WorldGenerator for WorldGeneratorStructuresSpikes {
 		// generation can place blocks. A radius of 1 means just the origin block, a
 		// radius of 2 means a 3x3x3 blocks sized cube around the origin block, etc.
 		let structure_max_blocky_radius = 61;
-		let noise_structure = noise::OctavedNoise::new(1, vec![self.seed, 3]);
+		let hash_structure = noise::hash::Hash::new(&[self.seed, 3]);
 		let generate_structure =
 			|origin_block_coords: BlockCoords, chunk_blocks: &mut ChunkBlocksBeingGenerated| {
 				let mut placing_head = origin_block_coords;
@@ -2785,10 +2949,8 @@ impl WorldGenerator for WorldGeneratorStructuresSpikes {
 				if !found_ground {
 					return;
 				}
-				let noise_value_a =
-					noise_structure.sample(&[], &[&[placing_head.x, placing_head.y, placing_head.z, 1]]);
-				let noise_value_b =
-					noise_structure.sample(&[], &[&[placing_head.x, placing_head.y, placing_head.z, 2]]);
+				let noise_value_a = hash_structure.with_coords_3d(placing_head).with_channel(1).unit();
+				let noise_value_b = hash_structure.with_coords_3d(placing_head).with_channel(2).unit();
 				let us = placing_head.map(|x| x as f32);
 				let spike_end = us
 					+ cgmath::vec3(
@@ -2902,6 +3064,7 @@ impl WorldGenerator for WorldGeneratorLines02 {
 		let noise_c = noise::OctavedNoise::new(5, vec![self.seed, 3]);
 		let noise_no_grass = noise::OctavedNoise::new(5, vec![self.seed, 3]);
 		let noise_grass_a = noise::OctavedNoise::new(2, vec![self.seed, 1, 1]);
+		let decorations = DecorationPlacer::new(self.seed);
 		let noise_grass_b = noise::OctavedNoise::new(2, vec![self.seed, 1, 2]);
 		let coords_to_ground = |coords: BlockCoords| -> bool {
 			let coordsf = coords.map(|x| x as f32);
@@ -2951,6 +3114,10 @@ impl WorldGenerator for WorldGeneratorLines02 {
 					let no_grass_below = coords_to_no_grass(coords + cgmath::vec3(0, 0, -1));
 					if no_grass_below {
 						block_type_table.air_id()
+					} else if let Some(decoration) =
+						decorations.decoration_on_grass(coords, block_type_table)
+					{
+						decoration
 					} else if coords_to_grass(coords) {
 						block_type_table.kinda_grass_blades_id()
 					} else {
@@ -2985,6 +3152,7 @@ impl WorldGenerator for WorldGeneratorLines03 {
 		let noise_c = noise::OctavedNoise::new(4, vec![self.seed, 3]);
 		let noise_no_grass = noise::OctavedNoise::new(5, vec![self.seed, 3]);
 		let noise_grass_a = noise::OctavedNoise::new(2, vec![self.seed, 1, 1]);
+		let decorations = DecorationPlacer::new(self.seed);
 		let noise_grass_b = noise::OctavedNoise::new(2, vec![self.seed, 1, 2]);
 		let sorted_noises = |coordsf: cgmath::Point3<f32>| -> [(usize, f32); 3] {
 			let mut array = [
@@ -3040,6 +3208,10 @@ impl WorldGenerator for WorldGeneratorLines03 {
 					let no_grass_below = coords_to_no_grass(coords + cgmath::vec3(0, 0, -1));
 					if no_grass_below {
 						block_type_table.air_id()
+					} else if let Some(decoration) =
+						decorations.decoration_on_grass(coords, block_type_table)
+					{
+						decoration
 					} else if coords_to_grass(coords) {
 						block_type_table.kinda_grass_blades_id()
 					} else {
@@ -3093,13 +3265,13 @@ impl WorldGenerator for WorldGeneratorStructuresLinksSmooth {
 		};
 
 		// Setup structure origins generation stuff.
-		let noise_cell_data = noise::OctavedNoise::new(1, vec![self.seed, 2]);
+		let hash_cell_data = noise::hash::Hash::new(&[self.seed, 2]);
 		let cell_size = 51;
 		let block_coords_to_cell_coords = |block_coords: BlockCoords| -> cgmath::Point3<i32> {
 			block_coords.map(|x| x.div_euclid(cell_size))
 		};
 		let cell_coords_to_number_of_structure_origins = |cell_coords: cgmath::Point3<i32>| -> usize {
-			let v = noise_cell_data.sample(&[], &[&[cell_coords.x, cell_coords.y, cell_coords.z, 1]]);
+			let v = hash_cell_data.with_coords_3d(cell_coords).with_channel(1).unit();
 			(v * 20.0 - 17.5).max(0.0).floor() as usize
 		};
 		let cell_coords_and_structure_origin_index_to_origin_coords_in_world =
@@ -3107,16 +3279,10 @@ impl WorldGenerator for WorldGeneratorStructuresLinksSmooth {
 				let xyz: SmallVec<[f32; 3]> = [0, 1, 2]
 					.into_iter()
 					.map(|axis| {
-						noise_cell_data.sample(
-							&[],
-							&[&[
-								cell_coords.x,
-								cell_coords.y,
-								cell_coords.z,
-								1 + axis,
-								origin_index as i32,
-							]],
-						)
+						hash_cell_data
+							.with_coords_3d(cell_coords)
+							.with(&[1 + axis, origin_index as i32])
+							.unit()
 					})
 					.collect();
 				let coords_in_unit_cube = cgmath::point3(xyz[0], xyz[1], xyz[2]);
@@ -3229,35 +3395,15 @@ impl WorldGenerator for WorldGeneratorStructuresLinksSmooth {
 						}
 						if structure_origin_can_link(other_origin_coords) {
 							// Hehe found one, let's decide if we link.
-							// We get two noise values that we will also get (in the other order)
-							// in the other structure, and when we add them we get the same value
-							// that the other would get, so we can agree on something that way ^^.
-							let value_us_to_other = noise_a.sample(
-								&[],
-								&[&[
-									origin_block_coords.x,
-									origin_block_coords.y,
-									origin_block_coords.z,
-									other_origin_coords.x,
-									other_origin_coords.y,
-									other_origin_coords.z,
-								]],
-							);
-							let value_other_to_us = noise_a.sample(
-								&[],
-								&[&[
-									other_origin_coords.x,
-									other_origin_coords.y,
-									other_origin_coords.z,
-									origin_block_coords.x,
-									origin_block_coords.y,
-									origin_block_coords.z,
-								]],
-							);
 							// We only link to a few other structures because if we linked
 							// to everyone we could then it fills the world with links
 							// and it becomes difficult to see and appreciate the generation.
-							let link = (value_us_to_other + value_other_to_us) * 0.5 < 0.25;
+							let link = agree_to_link(
+								noise_a.hash(),
+								origin_block_coords,
+								other_origin_coords,
+								0.25,
+							);
 
 							if link {
 								// Let's link!
@@ -3498,15 +3644,19 @@ impl WorldGenerator for WorldGeneratorStructuresEnginePoc {
 			chunk_blocks.set_id(coords, coords_to_terrain(coords));
 		}
 
+		let noise_structure_transform = noise::OctavedNoise::new(1, vec![self.seed, 5]);
+
 		// Generate the structures that can overlap with the chunk.
 		let mut span_to_check = CubicCoordsSpan::from_chunk_span(coords_span);
 		span_to_check.add_margins(structure_max_blocky_radius);
 		let origins = structure_origin_generator.get_origins_in_span(span_to_check);
 		for origin in origins.into_iter() {
+			origin.annotate(&[]);
 			let allowed_span =
 				CubicCoordsSpan::with_center_and_radius(origin.coords, structure_max_blocky_radius);
 			let context = StructureInstanceGenerationContext {
 				origin,
+				transform: StructureTransform::from_noise(&noise_structure_transform, origin.coords),
 				allowed_span,
 				chunk_blocks: &mut chunk_blocks,
 				chunk_entities: &mut chunk_entities,
@@ -3618,15 +3768,19 @@ impl WorldGenerator for WorldGeneratorStructuresGeneratedBlocks {
 			chunk_blocks.set_id(coords, coords_to_terrain(coords));
 		}
 
+		let noise_structure_transform = noise::OctavedNoise::new(1, vec![self.seed, 2]);
+
 		// Generate the structures that can overlap with the chunk.
 		let mut span_to_check = CubicCoordsSpan::from_chunk_span(coords_span);
 		span_to_check.add_margins(structure_max_blocky_radius);
 		let origins = structure_origin_generator.get_origins_in_span(span_to_check);
 		for origin in origins.into_iter() {
+			origin.annotate(&[]);
 			let allowed_span =
 				CubicCoordsSpan::with_center_and_radius(origin.coords, structure_max_blocky_radius);
 			let context = StructureInstanceGenerationContext {
 				origin,
+				transform: StructureTransform::from_noise(&noise_structure_transform, origin.coords),
 				allowed_span,
 				chunk_blocks: &mut chunk_blocks,
 				chunk_entities: &mut chunk_entities,
@@ -3690,6 +3844,132 @@ impl WorldGenerator for WorldGeneratorWierdTerrain03 {
 	}
 }
 
+struct WorldGeneratorRidges {
+	pub(crate) seed: i32,
+}
+
+impl WorldGenerator for WorldGeneratorRidges {
+	fn generate_chunk_blocks_and_entities(
+		&self,
+		coords_span: ChunkCoordsSpan,
+		block_type_table: &Arc<BlockTypeTable>,
+		_id_generator: &IdGenerator,
+	) -> (ChunkBlocks, ChunkEntities) {
+		let noise_ridges = noise::OctavedNoise::new_ridged(5, vec![self.seed, 1]);
+		let noise_hills = noise::OctavedNoise::new_billow(3, vec![self.seed, 2]);
+		let warp = noise::DomainWarp::new(
+			noise::OctavedNoise::new(3, vec![self.seed, 3]),
+			noise::OctavedNoise::new(3, vec![self.seed, 4]),
+			noise::OctavedNoise::new(3, vec![self.seed, 5]),
+			50.0,
+			18.0,
+		)
+		.with_vertical_strength(6.0);
+		let coords_to_ground = |coords: BlockCoords| -> bool {
+			let coordsf = warp.warp_3d(coords.map(|x| x as f32));
+			let coordsf_xy = cgmath::point2(coordsf.x, coordsf.y);
+			let scale_ridges = 250.0;
+			let scale_hills = 60.0;
+			let ridges = noise_ridges.sample_2d_1d(coordsf_xy / scale_ridges, &[]);
+			let hills = noise_hills.sample_2d_1d(coordsf_xy / scale_hills, &[]);
+			let height = ridges.powi(2) * 90.0 + hills * 12.0 - 20.0;
+			coordsf.z < height
+		};
+		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
+		for coords in chunk_blocks.coords_span().iter_coords() {
+			let ground = coords_to_ground(coords);
+			let block = if ground {
+				let ground_above = coords_to_ground(coords + cgmath::vec3(0, 0, 1));
+				if ground_above {
+					block_type_table.ground_id()
+				} else {
+					block_type_table.kinda_grass_id()
+				}
+			} else {
+				block_type_table.air_id()
+			};
+			chunk_blocks.set_id(coords, block);
+		}
+		(
+			chunk_blocks.finish_generation(),
+			ChunkEntities::new_empty(coords_span),
+		)
+	}
+}
+
+/// The water fills everything up to this height.
+const SPLINES_SEA_LEVEL: i32 = 0;
+/// The surface is bare ground from this height.
+const SPLINES_ROCK_HEIGHT: i32 = 60;
+/// The surface is covered with snow from this height.
+const SPLINES_SNOW_HEIGHT: i32 = 75;
+
+struct WorldGeneratorSplines {
+	seed: i32,
+	curves: TerrainCurves,
+}
+
+impl WorldGenerator for WorldGeneratorSplines {
+	fn generate_chunk_blocks_and_entities(
+		&self,
+		coords_span: ChunkCoordsSpan,
+		block_type_table: &Arc<BlockTypeTable>,
+		_id_generator: &IdGenerator,
+	) -> (ChunkBlocks, ChunkEntities) {
+		let noise_continentalness = noise::OctavedNoise::new(5, vec![self.seed, 1]);
+		let noise_peaks = noise::OctavedNoise::new_ridged(4, vec![self.seed, 2]);
+		let noise_erosion = noise::OctavedNoise::new(4, vec![self.seed, 3]);
+		let column_to_height = |coords_xy: cgmath::Point2<i32>| -> i32 {
+			let coordsf_xy = coords_xy.map(|x| x as f32);
+			let continentalness = noise_continentalness.sample_2d_1d(coordsf_xy / 800.0, &[]);
+			let peaks = noise_peaks.sample_2d_1d(coordsf_xy / 200.0, &[]);
+			let erosion = noise_erosion.sample_2d_1d(coordsf_xy / 400.0, &[]);
+			self.curves.height(continentalness, peaks, erosion).round() as i32
+		};
+
+		// The height only depends on the column, it is computed once per column.
+		let inf = coords_span.block_coords_inf();
+		let edge = coords_span.cd.edge;
+		let heights: Vec<i32> = (0..edge)
+			.flat_map(|dx| (0..edge).map(move |dy| cgmath::point2(inf.x + dx, inf.y + dy)))
+			.map(column_to_height)
+			.collect();
+
+		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
+		for coords in chunk_blocks.coords_span().iter_coords() {
+			let height = heights[((coords.x - inf.x) * edge + (coords.y - inf.y)) as usize];
+			let is_beach = height <= SPLINES_SEA_LEVEL + 1;
+			let depth = height - coords.z;
+			let block = if depth < 0 {
+				if coords.z <= SPLINES_SEA_LEVEL {
+					Block::from(block_type_table.water_id())
+				} else if depth == -1 && height >= SPLINES_SNOW_HEIGHT {
+					Block::new_layer(block_type_table.kinda_snow_layer_id(), 1)
+				} else {
+					Block::from(block_type_table.air_id())
+				}
+			} else if depth < 4 && is_beach {
+				Block::from(block_type_table.kinda_sand_id())
+			} else if height >= SPLINES_ROCK_HEIGHT || depth >= 4 {
+				Block::from(block_type_table.ground_id())
+			} else if depth == 0 {
+				Block::from(block_type_table.kinda_grass_id())
+			} else {
+				Block::from(block_type_table.kinda_dirt_id())
+			};
+			chunk_blocks.set(coords, block);
+		}
+		(
+			chunk_blocks.finish_generation(),
+			ChunkEntities::new_empty(coords_span),
+		)
+	}
+
+	fn settings_fingerprint(&self) -> Option<u64> {
+		Some(self.curves.fingerprint())
+	}
+}
+
 mod procedural_structures_poc {
 	use crate::coords::OrientedAxis;
 
@@ -4094,15 +4374,19 @@ mod procedural_structures_poc {
 				chunk_blocks.set_id(coords, coords_to_terrain(coords));
 			}
 
+			let noise_structure_transform = OctavedNoise::new(1, vec![self.seed, 4]);
+
 			// Generate the structures that can overlap with the chunk.
 			let mut span_to_check = CubicCoordsSpan::from_chunk_span(coords_span);
 			span_to_check.add_margins(structure_max_blocky_radius);
 			let origins = structure_origin_generator.get_origins_in_span(span_to_check);
 			for origin in origins.into_iter() {
+				origin.annotate(&[]);
 				let allowed_span =
 					CubicCoordsSpan::with_center_and_radius(origin.coords, structure_max_blocky_radius);
 				let context = StructureInstanceGenerationContext {
 					origin,
+					transform: StructureTransform::from_noise(&noise_structure_transform, origin.coords),
 					allowed_span,
 					chunk_blocks: &mut chunk_blocks,
 					chunk_entities: &mut chunk_entities,
@@ -4302,10 +4586,267 @@ impl WorldGenerator for WorldGeneratorStructuresArcs {
 		span_to_check.add_margins(structure_max_blocky_radius);
 		let origins = structure_origin_generator.get_origins_in_span(span_to_check);
 		for origin in origins.into_iter() {
+			origin.annotate(&[]);
+			let allowed_span =
+				CubicCoordsSpan::with_center_and_radius(origin.coords, structure_max_blocky_radius);
+			let context = StructureInstanceGenerationContext {
+				origin,
+				// Arcs look at the terrain all along their path by themselves,
+				// which the transform would not follow.
+				transform: StructureTransform::IDENTITY,
+				allowed_span,
+				chunk_blocks: &mut chunk_blocks,
+				chunk_entities: &mut chunk_entities,
+				_origin_generator: &structure_origin_generator,
+				block_type_table,
+				terrain_generator: &coords_to_terrain,
+			};
+			structure_types[origin.type_id.index](context);
+		}
+
+		(chunk_blocks.finish_generation(), chunk_entities)
+	}
+}
+
+/// Sky islands with rounded undersides that have spikes hanging from them, trees on top,
+/// and streams of water that fall off their edges.
+struct WorldGeneratorFloatingIslands {
+	pub(crate) seed: i32,
+}
+
+impl WorldGenerator for WorldGeneratorFloatingIslands {
+	fn generate_chunk_blocks_and_entities(
+		&self,
+		coords_span: ChunkCoordsSpan,
+		block_type_table: &Arc<BlockTypeTable>,
+		_id_generator: &IdGenerator,
+	) -> (ChunkBlocks, ChunkEntities) {
+		// There is at most one island per horizontal cell, and it fits in its cell
+		// (deformations included) so that only one cell has to be looked at per column.
+		let noise_islands = noise::OctavedNoise::new(1, vec![self.seed, 1]);
+		let noise_edge = noise::OctavedNoise::new(3, vec![self.seed, 2]);
+		let noise_top = noise::OctavedNoise::new(3, vec![self.seed, 3]);
+		let noise_bottom = noise::OctavedNoise::new(3, vec![self.seed, 4]);
+		let noise_spikes = noise::OctavedNoise::new(1, vec![self.seed, 5]);
+		let noise_grass_a = noise::OctavedNoise::new(2, vec![self.seed, 1, 1]);
+		let decorations = DecorationPlacer::new(self.seed);
+		let noise_grass_b = noise::OctavedNoise::new(2, vec![self.seed, 1, 2]);
+		let island_cell_size = 120;
+		let island_max_radius = 36.0;
+		let island_in_cell =
+			|cell_coords: cgmath::Point2<i32>| -> Option<(cgmath::Point3<f32>, f32)> {
+				let cell_coords = cgmath::point3(cell_coords.x, cell_coords.y, 0);
+				if noise_islands.sample_i3d_1d(cell_coords, &[1]) < 0.35 {
+					return None;
+				}
+				let radius = 14.0 + noise_islands.sample_i3d_1d(cell_coords, &[2]) * 22.0;
+				let margin = island_max_radius + 4.0;
+				let position_in_cell = noise_islands
+					.sample_i3d_3d(cell_coords, &[3])
+					.map(|x| margin + x * (island_cell_size as f32 - 2.0 * margin));
+				let center = cgmath::point3(
+					(cell_coords.x * island_cell_size) as f32 + position_in_cell.x,
+					(cell_coords.y * island_cell_size) as f32 + position_in_cell.y,
+					40.0 + position_in_cell.z / island_cell_size as f32 * 50.0,
+				);
+				Some((center, radius))
+			};
+		let coords_to_ground = |coords: BlockCoords| -> bool {
+			let cell_coords = cgmath::point2(
+				coords.x.div_euclid(island_cell_size),
+				coords.y.div_euclid(island_cell_size),
+			);
+			let Some((center, radius)) = island_in_cell(cell_coords) else {
+				return false;
+			};
+			let coordsf = coords.map(|x| x as f32);
+			let depth = radius * 0.8;
+			let max_spike_length = 12.0;
+			// Quick rejection before sampling the noises.
+			let horizontal_distance =
+				cgmath::vec2(coordsf.x - center.x, coordsf.y - center.y).magnitude();
+			if horizontal_distance > radius * 1.15
+				|| coordsf.z > center.z + 7.0
+				|| coordsf.z < center.z - depth - 3.0 - max_spike_length
+			{
+				return false;
+			}
+
+			let coordsf_xy = cgmath::point2(coordsf.x, coordsf.y);
+			let radius_here = radius * (0.85 + 0.3 * noise_edge.sample_2d_1d(coordsf_xy / 15.0, &[]));
+			let t = horizontal_distance / radius_here;
+			if t >= 1.0 {
+				return false;
+			}
+			let top =
+				center.z + 4.0 * (1.0 - t * t) + 2.0 * noise_top.sample_2d_1d(coordsf_xy / 20.0, &[]);
+			let mut bottom = center.z
+				- depth * (1.0 - t * t).sqrt()
+				- 3.0 * noise_bottom.sample_2d_1d(coordsf_xy / 8.0, &[]);
+
+			// Spikes hang from the underside, at most one per small cell, and they are longer
+			// away from the edge.
+			let spike_cell_size = 6;
+			let spike_cell_coords = cgmath::point2(
+				coords.x.div_euclid(spike_cell_size),
+				coords.y.div_euclid(spike_cell_size),
+			);
+			if noise_spikes.sample_i2d_1d(spike_cell_coords, &[1]) < 0.4 {
+				let spike_radius = 2.0;
+				let spike_center = cgmath::point2(
+					(spike_cell_coords.x * spike_cell_size) as f32
+						+ 2.0 + 2.0 * noise_spikes.sample_i2d_1d(spike_cell_coords, &[2]),
+					(spike_cell_coords.y * spike_cell_size) as f32
+						+ 2.0 + 2.0 * noise_spikes.sample_i2d_1d(spike_cell_coords, &[3]),
+				);
+				let distance_to_spike = coordsf_xy.distance(spike_center);
+				if distance_to_spike < spike_radius {
+					let length =
+						(2.0 + noise_spikes.sample_i2d_1d(spike_cell_coords, &[4]) * 10.0) * (1.0 - t);
+					bottom -= length * (1.0 - distance_to_spike / spike_radius);
+				}
+			}
+
+			bottom <= coordsf.z && coordsf.z <= top
+		};
+		let coords_to_grass = |coords: BlockCoords| -> bool {
+			let coordsf = coords.map(|x| x as f32);
+			let scale = 30.0;
+			let d = noise_grass_a.sample_3d_1d(coordsf / scale, &[]);
+			let density = if d < 0.1 {
+				d * 0.9 + 0.1
+			} else if d < 0.3 {
+				0.1
+			} else {
+				0.01
+			};
+			noise_grass_b.sample_3d_1d(coordsf, &[]) < density
+		};
+		let coords_to_terrain = |coords: BlockCoords| -> BlockTypeId {
+			let ground = coords_to_ground(coords);
+			if ground {
+				let ground_above = coords_to_ground(coords + cgmath::vec3(0, 0, 1));
+				if ground_above {
+					block_type_table.ground_id()
+				} else {
+					block_type_table.kinda_grass_id()
+				}
+			} else {
+				let ground_below = coords_to_ground(coords + cgmath::vec3(0, 0, -1));
+				let decoration = ground_below
+					.then(|| decorations.decoration_on_grass(coords, block_type_table))
+					.flatten();
+				if let Some(decoration) = decoration {
+					decoration
+				} else if ground_below && coords_to_grass(coords) {
+					block_type_table.kinda_grass_blades_id()
+				} else {
+					block_type_table.air_id()
+				}
+			}
+		};
+
+		// Define structure generation.
+		let structure_max_blocky_radius = 64;
+		let noise_tree = noise::OctavedNoise::new(1, vec![self.seed, 6]);
+		let generate_structure_tree = |mut context: StructureInstanceGenerationContext| {
+			let Some(ground) = context.find_terrain_ground_below(context.origin.coords, 32) else {
+				return;
+			};
+			let l_system = Biome::Forest.tree_l_system();
+			l_system::generate_tree(&mut context, &noise_tree, &l_system, ground);
+		};
+		let noise_waterfall = noise::OctavedNoise::new(1, vec![self.seed, 7]);
+		let generate_structure_waterfall = |mut context: StructureInstanceGenerationContext| {
+			let Some(ground) = context.find_terrain_ground_below(context.origin.coords, 32) else {
+				return;
+			};
+			let is_ground = |coords: BlockCoords| -> bool {
+				context.block_type_table.get((context.terrain_generator)(coords)).unwrap().is_opaque()
+			};
+
+			// The stream flows in a straight line on the surface of the island
+			// until it reaches the edge, following the ups and downs of the surface.
+			let angle = noise_waterfall.sample_i3d_1d(ground, &[1]) * TAU;
+			let direction = cgmath::vec2(angle.cos(), angle.sin());
+			let mut stream = vec![ground];
+			let mut edge = None;
+			for step in 1..40 {
+				let previous = *stream.last().unwrap();
+				let offset = (direction * step as f32).map(|x| x.round() as i32);
+				let column = cgmath::point3(ground.x + offset.x, ground.y + offset.y, previous.z);
+				if column.x == previous.x && column.y == previous.y {
+					continue;
+				}
+				let surface = [1, 0, -1]
+					.into_iter()
+					.map(|dz| column + cgmath::vec3(0, 0, dz))
+					.find(|&coords| is_ground(coords) && !is_ground(coords + cgmath::vec3(0, 0, 1)));
+				if let Some(surface) = surface {
+					stream.push(surface);
+				} else if (0..3).all(|dz| !is_ground(column - cgmath::vec3(0, 0, dz))) {
+					edge = Some(column);
+					break;
+				} else {
+					// The surface goes up too steeply, water does not flow there.
+					return;
+				}
+			}
+			let Some(edge) = edge else {
+				return;
+			};
+
+			let water = BlockPlacing {
+				block_type_to_place: context.block_type_table.water_id(),
+				only_place_on_air: false,
+			};
+			let air = BlockPlacing {
+				block_type_to_place: context.block_type_table.air_id(),
+				only_place_on_air: false,
+			};
+			for &coords in stream.iter() {
+				context.place_block(&water, coords);
+				// Grass blades do not grow in water.
+				context.place_block(&air, coords + cgmath::vec3(0, 0, 1));
+			}
+			// Then it falls until it lands on something, or until it gets out of the area
+			// that the structure is allowed to modify (where it gets lost in the air).
+			let mut falling_head = edge;
+			while context.allowed_span.contains(falling_head) && !is_ground(falling_head) {
+				context.place_block(&water, falling_head);
+				falling_head.z -= 1;
+			}
+		};
+
+		let structure_types: [&StructureTypeInstanceGenerator; 2] =
+			[&generate_structure_tree, &generate_structure_waterfall];
+
+		// Setup structure origins generation stuff.
+		let structure_origin_generator =
+			TestStructureOriginGenerator::new(self.seed, 31, (0, 6), structure_types.len() as i32);
+
+		// Now we generate the block data in the chunk.
+		let mut chunk_blocks = ChunkBlocksBeingGenerated::new_empty(coords_span);
+		let mut chunk_entities = ChunkEntities::new_empty(coords_span);
+
+		// Generate terrain in the chunk.
+		for coords in chunk_blocks.coords_span().iter_coords() {
+			chunk_blocks.set_id(coords, coords_to_terrain(coords));
+		}
+
+		// Generate the structures that can overlap with the chunk.
+		let mut span_to_check = CubicCoordsSpan::from_chunk_span(coords_span);
+		span_to_check.add_margins(structure_max_blocky_radius);
+		let origins = structure_origin_generator.get_origins_in_span(span_to_check);
+		for origin in origins.into_iter() {
+			origin.annotate(&[]);
 			let allowed_span =
 				CubicCoordsSpan::with_center_and_radius(origin.coords, structure_max_blocky_radius);
 			let context = StructureInstanceGenerationContext {
 				origin,
+				// Waterfalls look at the terrain all along their stream by themselves,
+				// which the transform would not follow.
+				transform: StructureTransform::IDENTITY,
 				allowed_span,
 				chunk_blocks: &mut chunk_blocks,
 				chunk_entities: &mut chunk_entities,
@@ -0,0 +1,233 @@
+//! Splines that map noise values to terrain heights through curves defined by control points,
+//! so that the shape of the terrain can be tuned without touching the noise.
+//!
+//! The curves of the spline terrain (see `TerrainCurves`) can be edited without recompiling,
+//! in the `terrain_curves.qwy3_curves` file (if there is one) that has lines like
+//! `continentalness 0:-60 0.4:-4 0.5:6 1:40`, meaning "the continentalness curve goes through
+//! these (noise value, height) points". The curves that are not in the file keep their default.
+
+/// A smooth curve that goes through its control points, and that never overshoots between two
+/// of them (monotone cubic interpolation), it is constant beyond the first and last points.
+#[derive(Clone)]
+pub(crate) struct Spline {
+	/// Sorted by x.
+	points: Vec<(f32, f32)>,
+	/// The slope of the curve at each control point.
+	slopes: Vec<f32>,
+}
+
+impl Spline {
+	pub(crate) fn new(mut points: Vec<(f32, f32)>) -> Spline {
+		assert!(!points.is_empty());
+		points.sort_by(|a, b| a.0.total_cmp(&b.0));
+		points.dedup_by(|a, b| a.0 == b.0);
+
+		// Fritsch-Carlson: the slopes are averages of the secants around the points, that are
+		// then limited so that the curve stays monotone between the points.
+		let secants: Vec<f32> =
+			points.windows(2).map(|pair| (pair[1].1 - pair[0].1) / (pair[1].0 - pair[0].0)).collect();
+		let mut slopes: Vec<f32> = (0..points.len())
+			.map(|i| {
+				let before = i.checked_sub(1).map(|i| secants[i]);
+				let after = secants.get(i).copied();
+				match (before, after) {
+					(Some(before), Some(after)) if before * after > 0.0 => (before + after) / 2.0,
+					(Some(_), Some(_)) => 0.0,
+					(Some(secant), None) | (None, Some(secant)) => secant,
+					(None, None) => 0.0,
+				}
+			})
+			.collect();
+		for (i, &secant) in secants.iter().enumerate() {
+			if secant == 0.0 {
+				slopes[i] = 0.0;
+				slopes[i + 1] = 0.0;
+			} else {
+				let alpha = slopes[i] / secant;
+				let beta = slopes[i + 1] / secant;
+				let length = (alpha * alpha + beta * beta).sqrt();
+				if length > 3.0 {
+					slopes[i] = 3.0 * alpha / length * secant;
+					slopes[i + 1] = 3.0 * beta / length * secant;
+				}
+			}
+		}
+
+		Spline { points, slopes }
+	}
+
+	fn fingerprint(&self) -> u64 {
+		let point_bits: Vec<(u32, u32)> =
+			self.points.iter().map(|&(x, y)| (x.to_bits(), y.to_bits())).collect();
+		fxhash::hash64(&point_bits)
+	}
+
+	pub(crate) fn sample(&self, x: f32) -> f32 {
+		let first = self.points[0];
+		let last = self.points[self.points.len() - 1];
+		if x <= first.0 {
+			return first.1;
+		} else if x >= last.0 {
+			return last.1;
+		}
+		// The index of the first point that is after `x`, it has a point before it.
+		let i = self.points.partition_point(|point| point.0 <= x);
+		let (x0, y0) = self.points[i - 1];
+		let (x1, y1) = self.points[i];
+		let width = x1 - x0;
+		let t = (x - x0) / width;
+		let (t2, t3) = (t * t, t * t * t);
+		// Cubic Hermite basis.
+		let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+		let h10 = t3 - 2.0 * t2 + t;
+		let h01 = -2.0 * t3 + 3.0 * t2;
+		let h11 = t3 - t2;
+		h00 * y0 + h10 * width * self.slopes[i - 1] + h01 * y1 + h11 * width * self.slopes[i]
+	}
+}
+
+/// The curves that shape the spline terrain, the height of a column of blocks is
+/// `continentalness(c) + peaks(p) * erosion(e)` with the values of the three noises there.
+pub(crate) struct TerrainCurves {
+	/// From oceans to inland, gives the base height.
+	pub(crate) continentalness: Spline,
+	/// From valleys to peaks, gives the relief (that is scaled by the erosion).
+	pub(crate) peaks: Spline,
+	/// From rough to eroded, gives how much of the relief is there (plains are very eroded).
+	pub(crate) erosion: Spline,
+}
+
+/// The file in which the curves can be edited (see the module documentation).
+const TERRAIN_CURVES_FILE_PATH: &str = "terrain_curves.qwy3_curves";
+
+impl TerrainCurves {
+	/// Reads the terrain curves file (if any), the lines that are not valid are skipped
+	/// (and reported as errors).
+	pub(crate) fn load() -> TerrainCurves {
+		let mut curves = TerrainCurves::default();
+		let Ok(curves_string) = std::fs::read_to_string(TERRAIN_CURVES_FILE_PATH) else {
+			// No terrain curves file is fine, the default curves are used.
+			return curves;
+		};
+		for (line_index, line) in curves_string.lines().enumerate() {
+			let line_number = line_index + 1;
+			let mut words = line.split_whitespace();
+			let Some(curve_name) = words.next() else {
+				continue;
+			};
+			let points: Option<Vec<(f32, f32)>> = words
+				.map(|word| {
+					let (x, y) = word.split_once(':')?;
+					Some((x.parse().ok()?, y.parse().ok()?))
+				})
+				.collect();
+			let Some(points) = points.filter(|points| !points.is_empty()) else {
+				log::error!(
+					"In file \"{TERRAIN_CURVES_FILE_PATH}\" at line {line_number}: \
+					Expected control points like \"0.5:10\" after the curve name"
+				);
+				continue;
+			};
+			let curve = match curve_name {
+				"continentalness" => &mut curves.continentalness,
+				"peaks" => &mut curves.peaks,
+				"erosion" => &mut curves.erosion,
+				_ => {
+					log::error!(
+						"In file \"{TERRAIN_CURVES_FILE_PATH}\" at line {line_number}: \
+						Unknown curve \"{curve_name}\" (expected continentalness, peaks or erosion)"
+					);
+					continue;
+				},
+			};
+			*curve = Spline::new(points);
+		}
+		curves
+	}
+
+	/// A hash of the control points of the curves, curves with the same fingerprint
+	/// give the same terrain.
+	pub(crate) fn fingerprint(&self) -> u64 {
+		fxhash::hash64(&[
+			self.continentalness.fingerprint(),
+			self.peaks.fingerprint(),
+			self.erosion.fingerprint(),
+		])
+	}
+
+	pub(crate) fn height(&self, continentalness: f32, peaks: f32, erosion: f32) -> f32 {
+		self.continentalness.sample(continentalness)
+			+ self.peaks.sample(peaks) * self.erosion.sample(erosion)
+	}
+}
+
+impl Default for TerrainCurves {
+	fn default() -> TerrainCurves {
+		TerrainCurves {
+			continentalness: Spline::new(vec![
+				(0.0, -60.0),
+				(0.25, -40.0),
+				(0.38, -8.0),
+				(0.43, 1.0),
+				(0.55, 6.0),
+				(0.7, 25.0),
+				(1.0, 40.0),
+			]),
+			peaks: Spline::new(vec![
+				(0.0, -8.0),
+				(0.15, 0.0),
+				(0.35, 6.0),
+				(0.55, 30.0),
+				(0.8, 70.0),
+				(1.0, 90.0),
+			]),
+			erosion: Spline::new(vec![
+				(0.0, 1.6),
+				(0.3, 1.2),
+				(0.45, 0.6),
+				(0.6, 0.2),
+				(1.0, 0.1),
+			]),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn goes_through_control_points() {
+		let spline = Spline::new(vec![(0.0, 1.0), (0.5, 3.0), (0.2, -2.0), (1.0, 0.0)]);
+		for (x, y) in [(0.0, 1.0), (0.2, -2.0), (0.5, 3.0), (1.0, 0.0)] {
+			assert!((spline.sample(x) - y).abs() < 1e-5);
+		}
+		assert_eq!(spline.sample(-10.0), 1.0);
+		assert_eq!(spline.sample(10.0), 0.0);
+	}
+
+	#[test]
+	fn does_not_overshoot() {
+		let spline = Spline::new(vec![(0.0, 0.0), (0.1, 10.0), (0.9, 10.5), (1.0, 50.0)]);
+		let mut previous = spline.sample(0.0);
+		for i in 1..=1000 {
+			let y = spline.sample(i as f32 / 1000.0);
+			assert!(
+				y >= previous - 1e-4,
+				"not monotone at {}",
+				i as f32 / 1000.0
+			);
+			previous = y;
+		}
+	}
+	#[test]
+	fn edited_curves_change_the_fingerprint() {
+		let curves = TerrainCurves::default();
+		assert_eq!(curves.fingerprint(), TerrainCurves::default().fingerprint());
+		let edited_curves = TerrainCurves {
+			peaks: Spline::new(vec![(0.0, 0.0), (1.0, 100.0)]),
+			..TerrainCurves::default()
+		};
+		assert_ne!(curves.fingerprint(), edited_curves.fingerprint());
+	}
+}
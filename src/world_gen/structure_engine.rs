@@ -6,8 +6,9 @@ use crate::{
 	block_types::{BlockTypeId, BlockTypeTable},
 	chunk_blocks::ChunkBlocksBeingGenerated,
 	coords::{BlockCoords, CubicCoordsSpan},
+	debug_annotations,
 	entities::{ChunkEntities, Entity},
-	noise::OctavedNoise,
+	noise::{hash::Hash, OctavedNoise},
 };
 
 #[derive(Clone, Copy)]
@@ -31,26 +32,172 @@ pub(crate) struct StructureOrigin {
 	pub(crate) type_id: StructureTypeId,
 }
 
+/// Colors that tell the structure types apart in debug displays, indexed by
+/// `StructureTypeId::index` (looping around if there are more types than colors).
+pub(crate) const STRUCTURE_TYPE_DEBUG_COLORS: [[f32; 3]; 6] = [
+	[1.0, 0.3, 0.3],
+	[0.3, 1.0, 0.3],
+	[0.4, 0.5, 1.0],
+	[1.0, 1.0, 0.3],
+	[1.0, 0.3, 1.0],
+	[0.3, 1.0, 1.0],
+];
+
+impl StructureTypeId {
+	pub(crate) fn debug_color(self) -> [f32; 3] {
+		STRUCTURE_TYPE_DEBUG_COLORS[self.index % STRUCTURE_TYPE_DEBUG_COLORS.len()]
+	}
+}
+
+impl StructureOrigin {
+	/// Labels the origin with its type (see `debug_annotations`), `type_names` is indexed by
+	/// `StructureTypeId::index` and the index is displayed for the types that are not named.
+	pub(crate) fn annotate(&self, type_names: &[&str]) {
+		let text = match type_names.get(self.type_id.index) {
+			Some(name) => format!("{name} ({})", self.type_id.index),
+			None => format!("type {}", self.type_id.index),
+		};
+		debug_annotations::annotate(self.coords, text, self.type_id.debug_color());
+	}
+}
+
 /// Handles generation of structure origins.
 pub(crate) trait StructureOriginGenerator {
 	/// Returns the list of all the structure origins that are in the given `span`.
 	fn get_origins_in_span(&self, span: CubicCoordsSpan) -> Vec<StructureOrigin>;
+
+	/// The origins are generated cell by cell, the cells being cubes of that edge (in blocks)
+	/// aligned on the origin of the world.
+	fn cell_size(&self) -> i32;
+}
+
+/// What a structure origin generator has in some span, to be displayed for debugging
+/// (see the `debug_structures` command).
+pub(crate) struct StructureOriginsDebugView {
+	/// The cells (see `StructureOriginGenerator::cell_size`) that overlap with the span.
+	pub(crate) cells: Vec<CubicCoordsSpan>,
+	pub(crate) cell_color: [f32; 3],
+	/// The origins in the span, with the name and the color of their structure type.
+	pub(crate) origins: Vec<(BlockCoords, &'static str, [f32; 3])>,
+}
+
+impl StructureOriginsDebugView {
+	pub(crate) fn new(
+		origin_generator: &dyn StructureOriginGenerator,
+		span: CubicCoordsSpan,
+		cell_color: [f32; 3],
+		type_name_and_color: impl Fn(StructureTypeId) -> (&'static str, [f32; 3]),
+	) -> StructureOriginsDebugView {
+		let cell_size = origin_generator.cell_size();
+		let cell_span = CubicCoordsSpan::with_inf_sup_but_sup_is_included(
+			span.inf.map(|x| x.div_euclid(cell_size)),
+			span.sup_included().map(|x| x.div_euclid(cell_size)),
+		);
+		let cells = cell_span
+			.iter()
+			.map(|cell_coords| {
+				let inf = cell_coords * cell_size;
+				CubicCoordsSpan::with_inf_sup_but_sup_is_excluded(
+					inf,
+					inf + cgmath::vec3(1, 1, 1) * cell_size,
+				)
+			})
+			.collect();
+		let origins = origin_generator
+			.get_origins_in_span(span)
+			.into_iter()
+			.map(|origin| {
+				let (name, color) = type_name_and_color(origin.type_id);
+				(origin.coords, name, color)
+			})
+			.collect();
+		StructureOriginsDebugView { cells, cell_color, origins }
+	}
+}
+
+/// How likely each structure type is to be picked for a structure origin.
+#[derive(Clone)]
+pub(crate) struct StructureTypeWeights {
+	/// Indexed by `StructureTypeId::index`.
+	weights: Vec<f32>,
+	/// How likely it is to have no structure at all (the origin is then discarded).
+	nothing_weight: f32,
+}
+
+impl StructureTypeWeights {
+	pub(crate) fn uniform(how_many_types: usize) -> StructureTypeWeights {
+		StructureTypeWeights { weights: vec![1.0; how_many_types], nothing_weight: 0.0 }
+	}
+
+	/// Types that are not given a weight have a weight of zero.
+	pub(crate) fn new(
+		how_many_types: usize,
+		weights: &[(StructureTypeId, f32)],
+		nothing_weight: f32,
+	) -> StructureTypeWeights {
+		let mut weights_by_index = vec![0.0; how_many_types];
+		for &(type_id, weight) in weights {
+			weights_by_index[type_id.index] = weight;
+		}
+		StructureTypeWeights { weights: weights_by_index, nothing_weight }
+	}
+
+	/// The `value` (from 0.0 to 1.0) is where we land on the weights all put end to end.
+	fn pick(&self, value: f32) -> Option<StructureTypeId> {
+		let total = self.weights.iter().sum::<f32>() + self.nothing_weight;
+		let mut remaining = value * total;
+		for (index, &weight) in self.weights.iter().enumerate() {
+			if remaining < weight {
+				return Some(StructureTypeId { index });
+			}
+			remaining -= weight;
+		}
+		None
+	}
+}
+
+/// The structure type weights can vary from place to place, places are told apart
+/// by some kind of biome given by `biome_at` (an index in `weights_by_biome`).
+pub(crate) struct StructureTypeTable<'a> {
+	weights_by_biome: Vec<StructureTypeWeights>,
+	biome_at: &'a dyn Fn(BlockCoords) -> usize,
+}
+
+impl<'a> StructureTypeTable<'a> {
+	pub(crate) fn new(
+		weights_by_biome: Vec<StructureTypeWeights>,
+		biome_at: &'a dyn Fn(BlockCoords) -> usize,
+	) -> StructureTypeTable<'a> {
+		StructureTypeTable { weights_by_biome, biome_at }
+	}
+
+	/// All the types have the same weight everywhere.
+	pub(crate) fn uniform(how_many_types: usize) -> StructureTypeTable<'a> {
+		StructureTypeTable {
+			weights_by_biome: vec![StructureTypeWeights::uniform(how_many_types)],
+			biome_at: &|_coords| 0,
+		}
+	}
+
+	fn pick(&self, coords: BlockCoords, value: f32) -> Option<StructureTypeId> {
+		self.weights_by_biome[(self.biome_at)(coords)].pick(value)
+	}
 }
 
 /// The idea of this structure origin generator is that it considers a grid of big cubic cells
 /// and uses noise to deterministically place, in each cell, a noise-obtained number of origins
 /// at noise-obtained coords in the cell.
-pub(crate) struct TestStructureOriginGenerator {
+pub(crate) struct TestStructureOriginGenerator<'a> {
 	cell_size: i32,
 	/// How many structure origins to generate per cell (min, max_included).
 	/// The range can overlap with the negatives, getting a negative number of origins
 	/// to generate in a cell will mean zero.
 	how_many_min_max: (i32, i32),
-	how_many_types: i32,
+	type_table: StructureTypeTable<'a>,
 	noise: OctavedNoise,
 }
 
-impl StructureOriginGenerator for TestStructureOriginGenerator {
+impl StructureOriginGenerator for TestStructureOriginGenerator<'_> {
 	fn get_origins_in_span(&self, span: CubicCoordsSpan) -> Vec<StructureOrigin> {
 		let block_inf = span.inf;
 		let block_sup_included = span.sup_excluded - cgmath::vec3(1, 1, 1);
@@ -64,19 +211,34 @@ impl StructureOriginGenerator for TestStructureOriginGenerator {
 		}
 		origins
 	}
+
+	fn cell_size(&self) -> i32 {
+		self.cell_size
+	}
 }
 
-impl TestStructureOriginGenerator {
+impl<'a> TestStructureOriginGenerator<'a> {
+	/// The structure types are all equally likely everywhere.
 	pub(crate) fn new(
 		seed: i32,
 		cell_size: i32,
 		how_many_min_max: (i32, i32),
 		how_many_types: i32,
-	) -> TestStructureOriginGenerator {
+	) -> TestStructureOriginGenerator<'a> {
+		let type_table = StructureTypeTable::uniform(how_many_types as usize);
+		TestStructureOriginGenerator::with_type_table(seed, cell_size, how_many_min_max, type_table)
+	}
+
+	pub(crate) fn with_type_table(
+		seed: i32,
+		cell_size: i32,
+		how_many_min_max: (i32, i32),
+		type_table: StructureTypeTable<'a>,
+	) -> TestStructureOriginGenerator<'a> {
 		TestStructureOriginGenerator {
 			cell_size,
 			how_many_min_max,
-			how_many_types,
+			type_table,
 			noise: OctavedNoise::new(1, vec![seed]),
 		}
 	}
@@ -103,14 +265,15 @@ impl TestStructureOriginGenerator {
 		cell_coords_in_world + coords_in_cell.to_vec()
 	}
 
+	/// `None` if no structure type was picked for that origin (so there is no origin there).
 	fn get_origin_type_id(
 		&self,
 		cell_coords: cgmath::Point3<i32>,
 		origin_index: usize,
-	) -> StructureTypeId {
+		origin_coords: BlockCoords,
+	) -> Option<StructureTypeId> {
 		let value = self.noise.sample_i3d_1d(cell_coords, &[origin_index as i32]);
-		let type_id_index = ((self.how_many_types as f32 - 0.0001) * value).floor() as usize;
-		StructureTypeId { index: type_id_index }
+		self.type_table.pick(origin_coords, value.min(0.9999))
 	}
 
 	/// Given a cell and a block span,
@@ -124,10 +287,11 @@ impl TestStructureOriginGenerator {
 		let origin_number = self.get_cell_origin_number(cell_coords);
 		for origin_index in 0..origin_number {
 			let origin_coords = self.get_origin_coords(cell_coords, origin_index);
-			let origin_type_id = self.get_origin_type_id(cell_coords, origin_index);
 			if span.contains(origin_coords) {
-				add_origins_in_there
-					.push(StructureOrigin { coords: origin_coords, type_id: origin_type_id })
+				let origin_type_id = self.get_origin_type_id(cell_coords, origin_index, origin_coords);
+				if let Some(type_id) = origin_type_id {
+					add_origins_in_there.push(StructureOrigin { coords: origin_coords, type_id })
+				}
 			}
 		}
 	}
@@ -137,6 +301,183 @@ impl TestStructureOriginGenerator {
 	}
 }
 
+/// Structure origins with a guaranteed minimum distance between any two of them (blue noise),
+/// so that structures of the same kind never overlap or crowd each other.
+///
+/// The world is cut in cubic cells whose edge is the minimum distance, and each cell has a few
+/// candidate origins at hash-obtained coords, each with a hash-obtained priority. A candidate
+/// is kept if no other candidate closer than the minimum distance has a higher priority.
+/// That only depends on the candidates of the neighboring cells, so it is deterministic and
+/// any two chunks agree on it. It leaves more empty space than a proper Poisson-disk sampling
+/// (some candidates are removed by candidates that are themselves removed) but that is fine.
+pub(crate) struct PoissonDiskStructureOriginGenerator<'a> {
+	min_distance: i32,
+	candidates_per_cell: u32,
+	type_table: StructureTypeTable<'a>,
+	hash: Hash,
+}
+
+/// A candidate origin of a `PoissonDiskStructureOriginGenerator`.
+struct PoissonDiskCandidate {
+	coords: BlockCoords,
+	type_id: StructureTypeId,
+	/// Two candidates that are too close settle it with that, the highest priority is kept.
+	/// The coords break the (unlikely) ties.
+	priority: (u64, i32, i32, i32),
+}
+
+impl StructureOriginGenerator for PoissonDiskStructureOriginGenerator<'_> {
+	fn get_origins_in_span(&self, span: CubicCoordsSpan) -> Vec<StructureOrigin> {
+		let block_sup_included = span.sup_excluded - cgmath::vec3(1, 1, 1);
+		let cell_span = CubicCoordsSpan::with_inf_sup_but_sup_is_included(
+			self.block_coords_to_cell_coords(span.inf),
+			self.block_coords_to_cell_coords(block_sup_included),
+		);
+		let mut origins = vec![];
+		for cell_coords in cell_span.iter() {
+			for candidate in self.candidates_in_cell(cell_coords) {
+				if span.contains(candidate.coords) && self.is_kept(&candidate, cell_coords) {
+					origins
+						.push(StructureOrigin { coords: candidate.coords, type_id: candidate.type_id });
+				}
+			}
+		}
+		origins
+	}
+
+	fn cell_size(&self) -> i32 {
+		self.min_distance
+	}
+}
+
+impl<'a> PoissonDiskStructureOriginGenerator<'a> {
+	/// The structure types are all equally likely everywhere.
+	pub(crate) fn new(
+		seed: i32,
+		min_distance: i32,
+		candidates_per_cell: u32,
+		how_many_types: i32,
+	) -> PoissonDiskStructureOriginGenerator<'a> {
+		let type_table = StructureTypeTable::uniform(how_many_types as usize);
+		PoissonDiskStructureOriginGenerator::with_type_table(
+			seed,
+			min_distance,
+			candidates_per_cell,
+			type_table,
+		)
+	}
+
+	pub(crate) fn with_type_table(
+		seed: i32,
+		min_distance: i32,
+		candidates_per_cell: u32,
+		type_table: StructureTypeTable<'a>,
+	) -> PoissonDiskStructureOriginGenerator<'a> {
+		PoissonDiskStructureOriginGenerator {
+			min_distance,
+			candidates_per_cell,
+			type_table,
+			hash: Hash::new(&[seed]),
+		}
+	}
+
+	/// The candidates that got a structure type (the others do not even compete).
+	fn candidates_in_cell(&self, cell_coords: cgmath::Point3<i32>) -> Vec<PoissonDiskCandidate> {
+		let cell_hash = self.hash.with_coords_3d(cell_coords);
+		(0..self.candidates_per_cell as i32)
+			.filter_map(|candidate_index| {
+				let candidate_hash = cell_hash.with_channel(candidate_index);
+				let coords_in_cell = cgmath::vec3(1, 2, 3).map(|axis| {
+					let value = candidate_hash.with_channel(axis).unit();
+					(value * self.min_distance as f32) as i32
+				});
+				let coords = cell_coords * self.min_distance + coords_in_cell;
+				let type_value = candidate_hash.with_channel(4).unit();
+				let type_id = self.type_table.pick(coords, type_value)?;
+				let priority = (
+					candidate_hash.with_channel(5).bits(),
+					coords.x,
+					coords.y,
+					coords.z,
+				);
+				Some(PoissonDiskCandidate { coords, type_id, priority })
+			})
+			.collect()
+	}
+
+	/// The candidates that could be too close are in the cells around (the cells are as big as
+	/// the minimum distance).
+	fn is_kept(&self, candidate: &PoissonDiskCandidate, cell_coords: cgmath::Point3<i32>) -> bool {
+		let min_distance_squared = self.min_distance as i64 * self.min_distance as i64;
+		let neighbor_cell_span = CubicCoordsSpan::with_center_and_radius(cell_coords, 2);
+		neighbor_cell_span.iter().all(|neighbor_cell_coords| {
+			self.candidates_in_cell(neighbor_cell_coords).into_iter().all(|other| {
+				let difference = (other.coords - candidate.coords).map(|x| x as i64);
+				let distance_squared = difference.x.pow(2) + difference.y.pow(2) + difference.z.pow(2);
+				distance_squared >= min_distance_squared || other.priority <= candidate.priority
+			})
+		})
+	}
+
+	fn block_coords_to_cell_coords(&self, block_coords: BlockCoords) -> cgmath::Point3<i32> {
+		block_coords.map(|x| x.div_euclid(self.min_distance))
+	}
+}
+
+/// A mirroring (or not) followed by a rotation by some quarter turns around the vertical axis,
+/// both around the origin of a structure instance. It makes structures of the same type
+/// not all face the same direction.
+#[derive(Clone, Copy)]
+pub(crate) struct StructureTransform {
+	/// Mirroring along the X axis, before the rotation.
+	mirrored: bool,
+	/// From 0 to 3, counterclockwise (seen from above).
+	quarter_turns: u8,
+}
+
+impl StructureTransform {
+	pub(crate) const IDENTITY: StructureTransform =
+		StructureTransform { mirrored: false, quarter_turns: 0 };
+
+	/// One of the 8 possible transforms, picked deterministically from the origin coords.
+	pub(crate) fn from_noise(
+		noise: &OctavedNoise,
+		origin_coords: BlockCoords,
+	) -> StructureTransform {
+		let value = noise.sample_i3d_1d(origin_coords, &[]);
+		let index = ((value * 8.0).floor() as i32).clamp(0, 7);
+		StructureTransform { mirrored: index >= 4, quarter_turns: (index % 4) as u8 }
+	}
+
+	fn apply_to_xy<T>(self, (mut x, mut y): (T, T)) -> (T, T)
+	where
+		T: Copy + std::ops::Neg<Output = T>,
+	{
+		if self.mirrored {
+			x = -x;
+		}
+		for _i in 0..self.quarter_turns {
+			(x, y) = (-y, x);
+		}
+		(x, y)
+	}
+
+	pub(crate) fn apply(self, origin: BlockCoords, coords: BlockCoords) -> BlockCoords {
+		let (x, y) = self.apply_to_xy((coords.x - origin.x, coords.y - origin.y));
+		cgmath::point3(origin.x + x, origin.y + y, coords.z)
+	}
+
+	pub(crate) fn apply_to_pos(
+		self,
+		origin: BlockCoords,
+		pos: cgmath::Point3<f32>,
+	) -> cgmath::Point3<f32> {
+		let origin = origin.map(|x| x as f32);
+		let (x, y) = self.apply_to_xy((pos.x - origin.x, pos.y - origin.y));
+		cgmath::point3(origin.x + x, origin.y + y, pos.z)
+	}
+}
+
 type TerrainGenerator<'a> = dyn Fn(BlockCoords) -> BlockTypeId + 'a;
 
 /// All that is needed for the generation of a structure instance.
@@ -145,6 +486,10 @@ type TerrainGenerator<'a> = dyn Fn(BlockCoords) -> BlockTypeId + 'a;
 pub(crate) struct StructureInstanceGenerationContext<'a> {
 	/// The origin of the structure that we generate now.
 	pub(crate) origin: StructureOrigin,
+	/// Applied (around the origin) to everything that is placed through this context, and to
+	/// the coords given to `find_terrain_ground_below`, so that the structure generation
+	/// does not have to care about it.
+	pub(crate) transform: StructureTransform,
 	/// The span in which the structure is allowed to place blocks and entities.
 	pub(crate) allowed_span: CubicCoordsSpan,
 	/// The chunk that is being generated and for which we generate a structure.
@@ -160,6 +505,7 @@ pub(crate) struct StructureInstanceGenerationContext<'a> {
 	pub(crate) _origin_generator: &'a dyn StructureOriginGenerator,
 	pub(crate) block_type_table: &'a Arc<BlockTypeTable>,
 	/// Structures are allowed to see the terrain (the world if there was no structures).
+	/// Calling it directly bypasses the `transform`.
 	pub(crate) terrain_generator: &'a TerrainGenerator<'a>,
 }
 
@@ -174,6 +520,11 @@ pub(crate) struct BlockPlacing {
 
 impl<'a> StructureInstanceGenerationContext<'a> {
 	pub(crate) fn place_block(&mut self, block_placing: &BlockPlacing, coords: BlockCoords) {
+		let coords = self.transform.apply(self.origin.coords, coords);
+		self.place_block_without_transform(block_placing, coords);
+	}
+
+	fn place_block_without_transform(&mut self, block_placing: &BlockPlacing, coords: BlockCoords) {
 		if self.allowed_span.contains(coords) {
 			let shall_place_block = !block_placing.only_place_on_air
 				|| self
@@ -192,6 +543,7 @@ impl<'a> StructureInstanceGenerationContext<'a> {
 		center: cgmath::Point3<f32>,
 		radius: f32,
 	) {
+		let center = self.transform.apply_to_pos(self.origin.coords, center);
 		let ball_inf = (center - cgmath::vec3(1.0, 1.0, 1.0) * radius).map(|x| x.floor() as i32);
 		let ball_sup = (center + cgmath::vec3(1.0, 1.0, 1.0) * radius).map(|x| x.ceil() as i32);
 		let ball_span = CubicCoordsSpan::with_inf_sup_but_sup_is_included(ball_inf, ball_sup);
@@ -201,13 +553,41 @@ impl<'a> StructureInstanceGenerationContext<'a> {
 		if let Some(span) = span {
 			for coords in span.iter() {
 				if coords.map(|x| x as f32).distance(center) < radius {
-					self.place_block(block_placing, coords);
+					self.place_block_without_transform(block_placing, coords);
 				}
 			}
 		}
 	}
 
-	pub(crate) fn place_entity(&mut self, entity: Entity) {
+	/// Going down from `start` (at most `max_depth` blocks), looks for the first opaque terrain
+	/// block that has a non-opaque block (like air or grass blades) above it, and returns its coords.
+	/// The terrain is the world without structures.
+	pub(crate) fn find_terrain_ground_below(
+		&self,
+		start: BlockCoords,
+		max_depth: i32,
+	) -> Option<BlockCoords> {
+		let is_opaque = |coords: BlockCoords| -> bool {
+			self.block_type_table.get((self.terrain_generator)(coords)).unwrap().is_opaque()
+		};
+		// The transform does not change the vertical axis, so only the start needs to be
+		// transformed and the returned coords are the same column as `start`.
+		let mut placing_head = self.transform.apply(self.origin.coords, start);
+		let mut opaque_above = is_opaque(placing_head + cgmath::vec3(0, 0, 1));
+		for _i in 0..max_depth {
+			let opaque_here = is_opaque(placing_head);
+			if !opaque_above && opaque_here {
+				return Some(cgmath::point3(start.x, start.y, placing_head.z));
+			}
+			opaque_above = opaque_here;
+			placing_head.z -= 1;
+		}
+		None
+	}
+
+	/// Only the position of the entity is transformed, not other positions it may remember.
+	pub(crate) fn place_entity(&mut self, mut entity: Entity) {
+		entity.impose_position(self.transform.apply_to_pos(self.origin.coords, entity.pos()));
 		let chunk_span = self.chunk_entities.coords_span;
 		let in_the_chunk = entity.chunk_coords(chunk_span.cd) == chunk_span.chunk_coords;
 		if in_the_chunk {
@@ -219,6 +599,21 @@ impl<'a> StructureInstanceGenerationContext<'a> {
 	}
 }
 
+/// Decides if two structure origins (or anything that has coords) are linked together.
+/// Both get a hash value for the ordered pair (us, other), and the sum of the two values
+/// does not depend on the order, so both sides agree on the answer without having to talk.
+/// The higher the `threshold` (from 0.0 to 1.0), the more links.
+pub(crate) fn agree_to_link(
+	hash: Hash,
+	us: BlockCoords,
+	other: BlockCoords,
+	threshold: f32,
+) -> bool {
+	let value_us_to_other = hash.with_coords_3d(us).with_coords_3d(other).unit();
+	let value_other_to_us = hash.with_coords_3d(other).with_coords_3d(us).unit();
+	(value_us_to_other + value_other_to_us) * 0.5 < threshold
+}
+
 /// Generates a structure instance of one specific type.
 pub(crate) type StructureTypeInstanceGenerator<'a> =
 	dyn Fn(StructureInstanceGenerationContext) + 'a;
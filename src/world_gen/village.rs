@@ -0,0 +1,234 @@
+//! Villages are clusters of small houses around a village square, linked by paths,
+//! with villagers wandering from house to house.
+
+use std::f32::consts::TAU;
+
+use cgmath::{InnerSpace, MetricSpace};
+
+use crate::{
+	block_types::BlockTypeId,
+	coords::{BlockCoords, CubicCoordsSpan},
+	entities::{Entity, IdGenerator},
+	noise::OctavedNoise,
+};
+
+use super::{
+	distance_to_segment,
+	structure_engine::{agree_to_link, BlockPlacing, StructureInstanceGenerationContext},
+};
+
+/// Houses are 5x5 blocks (walls included), this is the distance from their center to their walls.
+const HOUSE_HALF_WIDTH: i32 = 2;
+/// Vertical distance around the village square ground in which house grounds are looked for.
+/// It also prevents villages from generating on terrain that is not flat enough.
+const HOUSE_GROUND_SEARCH_RANGE: i32 = 6;
+
+struct House {
+	/// The ground block at the center of the house (the floor will be at that height).
+	center: BlockCoords,
+	/// Horizontal direction from the center towards the door (the door faces the village square).
+	door_direction: cgmath::Vector3<i32>,
+}
+
+impl House {
+	/// The block just outside of the house in front of its door, at floor height.
+	fn door_front(&self) -> BlockCoords {
+		self.center + self.door_direction * (HOUSE_HALF_WIDTH + 1)
+	}
+
+	fn footprint_contains(&self, coords: BlockCoords) -> bool {
+		(coords.x - self.center.x).abs() <= HOUSE_HALF_WIDTH
+			&& (coords.y - self.center.y).abs() <= HOUSE_HALF_WIDTH
+	}
+}
+
+/// Generates a village around the structure origin (if the terrain there is flat enough).
+/// The village must fit in the allowed span, `max_radius` is the radius of that span.
+pub(crate) fn generate_village(
+	context: &mut StructureInstanceGenerationContext,
+	noise: &OctavedNoise,
+	id_generator: &IdGenerator,
+	max_radius: i32,
+) {
+	let Some(square) = context.find_terrain_ground_below(context.origin.coords, max_radius - 12)
+	else {
+		return;
+	};
+
+	// Decide where the houses go, around the village square.
+	let house_count = 3 + (noise.sample_i3d_1d(square, &[1]) * 5.0) as usize;
+	let angle_offset = noise.sample_i3d_1d(square, &[2]) * TAU;
+	let mut houses = vec![];
+	for house_index in 0..house_count {
+		let angle = angle_offset + TAU * house_index as f32 / house_count as f32;
+		let distance = 11.0 + noise.sample_i3d_1d(square, &[3, house_index as i32]) * 10.0;
+		let offset = cgmath::vec2(angle.cos(), angle.sin()) * distance;
+		let column = square + cgmath::vec3(offset.x.round() as i32, offset.y.round() as i32, 0);
+		let Some(center) = context.find_terrain_ground_below(
+			column + cgmath::vec3(0, 0, HOUSE_GROUND_SEARCH_RANGE),
+			HOUSE_GROUND_SEARCH_RANGE * 2,
+		) else {
+			continue;
+		};
+		// The ground under the house must be flat enough, we check the corners.
+		let flat_enough = [(-1, -1), (-1, 1), (1, -1), (1, 1)].into_iter().all(|(x, y)| {
+			let corner = center + cgmath::vec3(x, y, 0) * HOUSE_HALF_WIDTH;
+			context.find_terrain_ground_below(corner + cgmath::vec3(0, 0, 2), 4).is_some()
+		});
+		if !flat_enough {
+			continue;
+		}
+		let door_direction = if offset.x.abs() > offset.y.abs() {
+			cgmath::vec3(-offset.x.signum() as i32, 0, 0)
+		} else {
+			cgmath::vec3(0, -offset.y.signum() as i32, 0)
+		};
+		houses.push(House { center, door_direction });
+	}
+	if houses.len() < 3 {
+		// Not much of a village, the terrain is probably too rough here.
+		return;
+	}
+
+	// Paths from every house to the square, and between some pairs of houses.
+	let path_block = context.block_type_table.ground_id();
+	for house in houses.iter() {
+		place_path(context, &houses, square, house.door_front(), path_block);
+	}
+	for (index_a, house_a) in houses.iter().enumerate() {
+		for house_b in houses.iter().skip(index_a + 1) {
+			if agree_to_link(noise.hash(), house_a.center, house_b.center, 0.3) {
+				place_path(
+					context,
+					&houses,
+					house_a.door_front(),
+					house_b.door_front(),
+					path_block,
+				);
+			}
+		}
+	}
+
+	// A little monument in the village square.
+	for z in 1..=3 {
+		context.place_block(
+			&BlockPlacing { block_type_to_place: path_block, only_place_on_air: false },
+			square + cgmath::vec3(0, 0, z),
+		);
+	}
+
+	for house in houses.iter() {
+		place_house(context, house);
+	}
+
+	// One villager per house, they can go visit all the houses.
+	let homes: Vec<_> = houses
+		.iter()
+		.map(|house| house.door_front().map(|x| x as f32) + cgmath::vec3(0.0, 0.0, 1.5))
+		.collect();
+	for house in houses.iter() {
+		let pos = house.center.map(|x| x as f32) + cgmath::vec3(0.0, 0.0, 1.5);
+		context.place_entity(Entity::new_villager(id_generator, pos, homes.clone()));
+	}
+}
+
+fn place_house(context: &mut StructureInstanceGenerationContext, house: &House) {
+	let table = context.block_type_table;
+	let floor = BlockPlacing { block_type_to_place: table.ground_id(), only_place_on_air: false };
+	let wall = BlockPlacing {
+		block_type_to_place: table.kinda_wood_id(),
+		only_place_on_air: false,
+	};
+	let air = BlockPlacing { block_type_to_place: table.air_id(), only_place_on_air: false };
+	let roof = BlockPlacing {
+		block_type_to_place: table.kinda_leaf_id(),
+		only_place_on_air: false,
+	};
+
+	let door_blocks = [
+		house.center + house.door_direction * HOUSE_HALF_WIDTH + cgmath::vec3(0, 0, 1),
+		house.center + house.door_direction * HOUSE_HALF_WIDTH + cgmath::vec3(0, 0, 2),
+	];
+	let r = HOUSE_HALF_WIDTH;
+	for x in -r..=r {
+		for y in -r..=r {
+			let column = house.center + cgmath::vec3(x, y, 0);
+			let is_wall = x.abs() == r || y.abs() == r;
+			// Fill the terrain below the floor in case it is not perfectly flat.
+			for z in -2..=0 {
+				context.place_block(&floor, column + cgmath::vec3(0, 0, z));
+			}
+			for z in 1..=3 {
+				let coords = column + cgmath::vec3(0, 0, z);
+				let is_door = door_blocks.contains(&coords);
+				context.place_block(if is_wall && !is_door { &wall } else { &air }, coords);
+			}
+			context.place_block(&roof, column + cgmath::vec3(0, 0, 4));
+			if x.abs() < r && y.abs() < r {
+				context.place_block(&roof, column + cgmath::vec3(0, 0, 5));
+			}
+		}
+	}
+}
+
+/// Places a path on the ground from `a` to `b`, replacing the top block of the terrain,
+/// except under houses.
+fn place_path(
+	context: &mut StructureInstanceGenerationContext,
+	houses: &[House],
+	a: BlockCoords,
+	b: BlockCoords,
+	path_block: BlockTypeId,
+) {
+	let path_half_width = 1.0;
+	let inf = cgmath::point3(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z))
+		- cgmath::vec3(1, 1, HOUSE_GROUND_SEARCH_RANGE);
+	let sup = cgmath::point3(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z))
+		+ cgmath::vec3(1, 1, HOUSE_GROUND_SEARCH_RANGE);
+	let path_span = CubicCoordsSpan::with_inf_sup_but_sup_is_included(inf, sup);
+	// Only the part of the path that is in the chunk being generated matters.
+	let chunk_span = CubicCoordsSpan::from_chunk_span(context.chunk_blocks.coords_span());
+	let Some(span) = chunk_span.intersection(&path_span) else {
+		return;
+	};
+
+	let a_xy = cgmath::point3(a.x as f32, a.y as f32, 0.0);
+	let b_xy = cgmath::point3(b.x as f32, b.y as f32, 0.0);
+	let path_length = a_xy.distance(b_xy);
+	for x in span.inf.x..span.sup_excluded.x {
+		for y in span.inf.y..span.sup_excluded.y {
+			let column_xy = cgmath::point3(x as f32, y as f32, 0.0);
+			if distance_to_segment(a_xy, b_xy, column_xy) > path_half_width {
+				continue;
+			}
+			if houses.iter().any(|house| house.footprint_contains(cgmath::point3(x, y, 0))) {
+				continue;
+			}
+			// The path goes from the height of `a` to the height of `b`,
+			// the ground is looked for around the expected height.
+			let progression = if path_length == 0.0 {
+				0.0
+			} else {
+				((column_xy - a_xy).magnitude() / path_length).clamp(0.0, 1.0)
+			};
+			let expected_z = a.z as f32 + (b.z - a.z) as f32 * progression;
+			let start = cgmath::point3(x, y, expected_z.round() as i32 + HOUSE_GROUND_SEARCH_RANGE);
+			if let Some(ground) =
+				context.find_terrain_ground_below(start, HOUSE_GROUND_SEARCH_RANGE * 2)
+			{
+				context.place_block(
+					&BlockPlacing { block_type_to_place: path_block, only_place_on_air: false },
+					ground,
+				);
+				// Remove the grass blades or anything that could be on the path.
+				context.place_block(
+					&BlockPlacing {
+						block_type_to_place: context.block_type_table.air_id(),
+						only_place_on_air: false,
+					},
+					ground + cgmath::vec3(0, 0, 1),
+				);
+			}
+		}
+	}
+}
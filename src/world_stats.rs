@@ -0,0 +1,251 @@
+//! World stats, the `stats` command walks the loaded chunks (on the thread pool) to count
+//! the blocks of each type, and also counts the entities of each kind and the structure origins
+//! around the player. The result is logged and can also be written to a file in `stats`.
+//!
+//! The counts are about what is loaded when the command is run, the chunks are taken as they are
+//! at that moment (they are shared, so this does not copy them) and the computation does not see
+//! what changes afterwards.
+
+use std::{path::PathBuf, sync::Arc};
+
+use fxhash::FxHashMap;
+
+use crate::{
+	block_types::{BlockTypeId, BlockTypeTable},
+	chunk_blocks::ChunkBlocks,
+	chunks::ChunkGrid,
+	coords::{BlockCoords, CubicCoordsSpan},
+	localization::Localization,
+	world_gen::WorldGenerator,
+};
+
+/// The directory in which the stats are exported.
+const STATS_DIRECTORY: &str = "stats";
+/// How many entries of each histogram are logged (the exported file has all of them).
+const LOGGED_ENTRY_COUNT: usize = 8;
+
+/// What the computation of the world stats needs, taken from the game on the main thread.
+pub(crate) struct DataForWorldStats {
+	chunk_blocks: Vec<Arc<ChunkBlocks>>,
+	entity_counts: Vec<(&'static str, u64)>,
+	world_generator: Arc<dyn WorldGenerator + Sync + Send>,
+	/// Where the structure origins are counted.
+	structure_span: CubicCoordsSpan,
+	structure_radius: i32,
+	block_type_table: Arc<BlockTypeTable>,
+	export: bool,
+}
+
+impl DataForWorldStats {
+	/// Takes what is loaded around the player, the structure origins are looked for up to
+	/// `structure_radius` blocks around `center`.
+	pub(crate) fn new(
+		chunk_grid: &ChunkGrid,
+		world_generator: Arc<dyn WorldGenerator + Sync + Send>,
+		center: BlockCoords,
+		structure_radius: i32,
+		block_type_table: Arc<BlockTypeTable>,
+		export: bool,
+	) -> DataForWorldStats {
+		let chunk_blocks = chunk_grid
+			.iter_loaded_chunk_coords()
+			.filter_map(|chunk_coords| chunk_grid.get_chunk_blocks(chunk_coords).cloned())
+			.collect();
+		let entity_counts =
+			sorted_histogram(chunk_grid.iter_entities().map(|entity| (entity.kind_name(), 1)));
+		DataForWorldStats {
+			chunk_blocks,
+			entity_counts,
+			world_generator,
+			structure_span: CubicCoordsSpan::with_center_and_radius(center, structure_radius),
+			structure_radius,
+			block_type_table,
+			export,
+		}
+	}
+
+	/// Meant to be run on a worker thread, it can take a while with a lot of loaded chunks.
+	pub(crate) fn compute(self) -> WorldStats {
+		let block_counts = sorted_histogram(
+			self
+				.chunk_blocks
+				.iter()
+				.flat_map(|chunk_blocks| chunk_blocks.block_type_counts())
+				.map(|(type_id, count)| (type_id, count as u64)),
+		);
+		let block_counts = block_counts
+			.into_iter()
+			.map(|(type_id, count)| (block_type_name(&self.block_type_table, type_id), count))
+			.collect();
+		let structure_origins =
+			self.world_generator.structure_origins_in_span(self.structure_span).map(|mut origins| {
+				origins.sort_by_key(|(name, coords)| (*name, coords.x, coords.y, coords.z));
+				origins
+			});
+		let mut stats = WorldStats {
+			chunk_count: self.chunk_blocks.len(),
+			block_counts,
+			entity_counts: self.entity_counts,
+			structure_radius: self.structure_radius,
+			structure_origins,
+			exported_to: None,
+		};
+		if self.export {
+			stats.exported_to = Some(stats.export());
+		}
+		stats
+	}
+}
+
+/// The result of the computation of the world stats.
+pub(crate) struct WorldStats {
+	chunk_count: usize,
+	/// Names of the block types with how many blocks of that type there are, most common first.
+	block_counts: Vec<(String, u64)>,
+	/// Kinds of entities with how many entities of that kind there are, most common first.
+	entity_counts: Vec<(&'static str, u64)>,
+	structure_radius: i32,
+	/// `None` if the world generator cannot tell where its structures are.
+	structure_origins: Option<Vec<(&'static str, BlockCoords)>>,
+	/// Where the stats were exported, if they were asked to be.
+	exported_to: Option<Result<PathBuf, String>>,
+}
+
+impl WorldStats {
+	/// The lines to display in the log.
+	pub(crate) fn log_lines(&self, localization: &Localization) -> Vec<String> {
+		let block_count: u64 = self.block_counts.iter().map(|(_name, count)| count).sum();
+		let entity_count: u64 = self.entity_counts.iter().map(|(_kind, count)| count).sum();
+		let mut lines = vec![
+			localization.get_with_args(
+				"log.stats_blocks",
+				&[
+					("chunks", &self.chunk_count),
+					("blocks", &block_count),
+					(
+						"histogram",
+						&format_histogram(&self.block_counts, block_count),
+					),
+				],
+			),
+			localization.get_with_args(
+				"log.stats_entities",
+				&[
+					("count", &entity_count),
+					(
+						"histogram",
+						&format_histogram(&self.entity_counts, entity_count),
+					),
+				],
+			),
+		];
+		lines.push(match self.structure_counts() {
+			Some(structure_counts) => {
+				let structure_count: u64 = structure_counts.iter().map(|(_name, count)| count).sum();
+				localization.get_with_args(
+					"log.stats_structures",
+					&[
+						("count", &structure_count),
+						("radius", &self.structure_radius),
+						(
+							"histogram",
+							&format_histogram(&structure_counts, structure_count),
+						),
+					],
+				)
+			},
+			None => localization.get("log.stats_structures_unsupported").to_string(),
+		});
+		match &self.exported_to {
+			Some(Ok(path)) => lines
+				.push(localization.get_with_args("log.stats_exported", &[("path", &path.display())])),
+			Some(Err(error)) => {
+				lines.push(localization.get_with_args("log.stats_export_error", &[("error", error)]))
+			},
+			None => {},
+		}
+		lines
+	}
+
+	fn structure_counts(&self) -> Option<Vec<(&'static str, u64)>> {
+		self
+			.structure_origins
+			.as_ref()
+			.map(|origins| sorted_histogram(origins.iter().map(|&(name, _coords)| (name, 1))))
+	}
+
+	/// Writes all the stats (with the coords of every structure origin) to a new text file.
+	fn export(&self) -> Result<PathBuf, String> {
+		let mut text = format!("Loaded chunks: {}\n", self.chunk_count);
+		text.push_str("\nBlocks:\n");
+		for (name, count) in self.block_counts.iter() {
+			text.push_str(&format!("{name} {count}\n"));
+		}
+		text.push_str("\nEntities:\n");
+		for (kind, count) in self.entity_counts.iter() {
+			text.push_str(&format!("{kind} {count}\n"));
+		}
+		text.push_str(&format!(
+			"\nStructure origins within {} blocks:\n",
+			self.structure_radius
+		));
+		match self.structure_origins.as_ref() {
+			Some(origins) => {
+				for (name, coords) in origins.iter() {
+					text.push_str(&format!("{name} {} {} {}\n", coords.x, coords.y, coords.z));
+				}
+			},
+			None => text.push_str("unknown\n"),
+		}
+
+		let path = unused_stats_path();
+		std::fs::create_dir_all(STATS_DIRECTORY)
+			.map_err(|error| format!("Could not create \"{STATS_DIRECTORY}\": {error}"))?;
+		std::fs::write(&path, text)
+			.map_err(|error| format!("Could not write \"{}\": {error}", path.display()))?;
+		Ok(path)
+	}
+}
+
+fn block_type_name(block_type_table: &BlockTypeTable, type_id: BlockTypeId) -> String {
+	block_type_table.name(type_id).map_or_else(|| format!("#{type_id}"), str::to_string)
+}
+
+/// Sums the counts by key, the most common keys first (and ties sorted by key).
+fn sorted_histogram<K: std::hash::Hash + Eq + Ord>(
+	counts: impl Iterator<Item = (K, u64)>,
+) -> Vec<(K, u64)> {
+	let mut histogram: FxHashMap<K, u64> = FxHashMap::default();
+	for (key, count) in counts {
+		*histogram.entry(key).or_insert(0) += count;
+	}
+	let mut histogram: Vec<_> = histogram.into_iter().collect();
+	histogram.sort_by(|(key_a, count_a), (key_b, count_b)| {
+		count_b.cmp(count_a).then_with(|| key_a.cmp(key_b))
+	});
+	histogram
+}
+
+/// Like `grass 1234 (12.3%), stone 567 (5.6%), ...` with only the first entries.
+fn format_histogram(histogram: &[(impl std::fmt::Display, u64)], total: u64) -> String {
+	let mut entries: Vec<_> = histogram
+		.iter()
+		.take(LOGGED_ENTRY_COUNT)
+		.map(|(key, count)| {
+			let percent = *count as f64 / total.max(1) as f64 * 100.0;
+			format!("{key} {count} ({percent:.1}%)")
+		})
+		.collect();
+	if histogram.len() > LOGGED_ENTRY_COUNT {
+		entries.push("...".to_string());
+	}
+	entries.join(", ")
+}
+
+/// `stats/stats_<number>.txt` with the first number that is not already taken.
+fn unused_stats_path() -> PathBuf {
+	(1..)
+		.map(|number| std::path::Path::new(STATS_DIRECTORY).join(format!("stats_{number}.txt")))
+		.find(|path| !path.exists())
+		.unwrap()
+}